@@ -173,6 +173,8 @@ impl ConfigBuilder<WantsClientCert> {
             enable_tickets: true,
             versions: self.state.versions,
             enable_sni: true,
+            sni_override: None,
+            custom_extensions: Vec::new(),
             verifier: self.state.verifier,
             key_log: Arc::new(NoKeyLog {}),
             enable_early_data: false,
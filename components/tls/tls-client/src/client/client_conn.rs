@@ -135,6 +135,20 @@ pub struct ClientConfig {
     /// The default is true.
     pub enable_sni: bool,
 
+    /// The name to send in the Server Name Indication (SNI) extension
+    /// instead of the name passed to [`ClientConnection::new`], which is
+    /// still used to verify the server certificate.
+    ///
+    /// The default is `None`.
+    pub sni_override: Option<ServerName>,
+
+    /// Additional extensions to include in the client hello, as pairs of
+    /// extension type and encoded extension data.  Extensions of a type
+    /// which is already sent are ignored.
+    ///
+    /// The default is empty.
+    pub custom_extensions: Vec<(u16, Vec<u8>)>,
+
     /// How to verify the server certificate chain.
     pub(super) verifier: Arc<dyn verify::ServerCertVerifier>,
 
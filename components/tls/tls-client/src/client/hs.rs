@@ -23,7 +23,7 @@ use tls_core::{
             CertificateStatusRequest, ClientExtension, ClientHelloPayload, ClientSessionTicket,
             ConvertProtocolNameList, ECPointFormatList, HandshakeMessagePayload, HandshakePayload,
             HasServerExtensions, HelloRetryRequest, ProtocolNameList, Random, SCTList, SessionID,
-            SupportedPointFormats,
+            SupportedPointFormats, UnknownExtension,
         },
         message::{Message, MessagePayload},
     },
@@ -219,7 +219,8 @@ async fn emit_client_hello_for_retry(
         ClientExtension::CertificateStatusRequest(CertificateStatusRequest::build_ocsp()),
     ];
 
-    if let (Some(sni_name), true) = (server_name.for_sni(), config.enable_sni) {
+    let sni_name = config.sni_override.as_ref().unwrap_or(&server_name);
+    if let (Some(sni_name), true) = (sni_name.for_sni(), config.enable_sni) {
         exts.push(ClientExtension::make_sni(sni_name));
     }
 
@@ -253,6 +254,16 @@ async fn emit_client_hello_for_retry(
         )));
     }
 
+    for (typ, data) in &config.custom_extensions {
+        let typ = ExtensionType::from(*typ);
+        if exts.iter().all(|ext| ext.get_type() != typ) {
+            exts.push(ClientExtension::Unknown(UnknownExtension {
+                typ,
+                payload: Payload::new(data.clone()),
+            }));
+        }
+    }
+
     // Extra extensions must be placed before the PSK extension
     exts.extend(extra_exts.iter().cloned());
 
@@ -303,6 +314,7 @@ async fn emit_client_hello_for_retry(
 
     let session_id = session_id.unwrap_or_else(SessionID::empty);
     let mut cipher_suites: Vec<_> = config.cipher_suites.iter().map(|cs| cs.suite()).collect();
+    cx.common.offered_suites = cipher_suites.clone();
     // We don't do renegotiation at all, in fact.
     cipher_suites.push(CipherSuite::TLS_EMPTY_RENEGOTIATION_INFO_SCSV);
 
@@ -470,7 +482,12 @@ impl State<ClientConnectionData> for ExpectServerHello {
             ));
         }
 
-        let allowed_unsolicited = [ExtensionType::RenegotiationInfo];
+        // Some servers acknowledge the session ticket extension without it being offered. The
+        // ticket they then send is ignored, as resumption is not supported.
+        let allowed_unsolicited = [
+            ExtensionType::RenegotiationInfo,
+            ExtensionType::SessionTicket,
+        ];
         if self
             .hello
             .server_sent_unsolicited_extensions(&server_hello.extensions, &allowed_unsolicited)
@@ -510,9 +527,10 @@ impl State<ClientConnectionData> for ExpectServerHello {
                 cx.common
                     .send_fatal_alert(AlertDescription::HandshakeFailure)
                     .await?;
-                return Err(Error::PeerMisbehavedError(
-                    "server chose non-offered ciphersuite".to_string(),
-                ));
+                return Err(Error::NoCommonCipherSuite {
+                    offered: cx.common.offered_suites.clone(),
+                    chosen: Some(server_hello.cipher_suite),
+                });
             }
         };
 
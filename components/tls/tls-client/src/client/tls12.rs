@@ -975,12 +975,23 @@ struct ExpectCcs {
 #[async_trait]
 impl State<ClientConnectionData> for ExpectCcs {
     async fn handle(
-        self: Box<Self>,
+        mut self: Box<Self>,
         cx: &mut ClientContext<'_>,
         m: Message,
     ) -> hs::NextStateOrError {
         match m.payload {
             MessagePayload::ChangeCipherSpec(..) => {}
+            // Some servers send a ticket even though we did not offer to accept one. Resumption
+            // is not supported, so the ticket is ignored, but it is still part of the handshake
+            // transcript covered by the server's Finished message.
+            MessagePayload::Handshake(HandshakeMessagePayload {
+                typ: HandshakeType::NewSessionTicket,
+                ..
+            }) => {
+                debug!("Ignoring unsolicited NewSessionTicket");
+                self.transcript.add_message(&m);
+                return Ok(self);
+            }
             payload => {
                 return Err(inappropriate_message(
                     &payload,
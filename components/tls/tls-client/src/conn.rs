@@ -21,7 +21,9 @@ use tls_core::{
         alert::AlertMessagePayload,
         base::Payload,
         deframer::MessageDeframer,
-        enums::{AlertDescription, AlertLevel, ContentType, HandshakeType, ProtocolVersion},
+        enums::{
+            AlertDescription, AlertLevel, CipherSuite, ContentType, HandshakeType, ProtocolVersion,
+        },
         fragmenter::MessageFragmenter,
         handshake::Random,
         hsjoiner::HandshakeJoiner,
@@ -640,6 +642,8 @@ pub struct CommonState {
     pub(crate) record_layer: record_layer::RecordLayer,
     pub(crate) backend: Box<dyn Backend>,
     pub(crate) suite: Option<SupportedCipherSuite>,
+    /// The cipher suites offered in the client hello.
+    pub(crate) offered_suites: Vec<CipherSuite>,
     pub(crate) alpn_protocol: Option<Vec<u8>>,
     aligned_handshake: bool,
     pub(crate) may_send_application_data: bool,
@@ -673,6 +677,7 @@ impl CommonState {
             record_layer: record_layer::RecordLayer::new(),
             backend,
             suite: None,
+            offered_suites: Vec::new(),
             alpn_protocol: None,
             aligned_handshake: true,
             may_send_application_data: false,
@@ -1095,6 +1100,19 @@ impl CommonState {
         }
 
         error!("TLS alert received: {:#?}", alert);
+
+        // A handshake failure before a cipher suite was chosen means that the
+        // peer did not accept any of the offered suites.
+        if alert.description == AlertDescription::HandshakeFailure
+            && self.suite.is_none()
+            && !self.offered_suites.is_empty()
+        {
+            return Err(Error::NoCommonCipherSuite {
+                offered: self.offered_suites.clone(),
+                chosen: None,
+            });
+        }
+
         Err(Error::AlertReceived(alert.description))
     }
 
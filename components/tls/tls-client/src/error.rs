@@ -1,7 +1,7 @@
 use crate::{backend::BackendError, rand};
 use std::{error::Error as StdError, fmt, time::SystemTimeError};
 use tls_core::{
-    msgs::enums::{AlertDescription, ContentType, HandshakeType},
+    msgs::enums::{AlertDescription, CipherSuite, ContentType, HandshakeType},
     Error as CoreError,
 };
 
@@ -66,6 +66,16 @@ pub enum Error {
     /// We received a fatal alert.  This means the peer is unhappy.
     AlertReceived(AlertDescription),
 
+    /// The peer supports none of the offered cipher suites: it either
+    /// rejected the handshake before choosing one, or chose one which was
+    /// not offered.
+    NoCommonCipherSuite {
+        /// The cipher suites offered to the peer.
+        offered: Vec<CipherSuite>,
+        /// The cipher suite chosen by the peer, if any.
+        chosen: Option<CipherSuite>,
+    },
+
     /// We received an invalidly encoded certificate from the peer.
     InvalidCertificateEncoding,
 
@@ -146,6 +156,20 @@ impl fmt::Display for Error {
             Self::PeerIncompatibleError(ref why) => write!(f, "peer is incompatible: {}", why),
             Self::PeerMisbehavedError(ref why) => write!(f, "peer misbehaved: {}", why),
             Self::AlertReceived(ref alert) => write!(f, "received fatal alert: {:?}", alert),
+            Self::NoCommonCipherSuite {
+                ref offered,
+                ref chosen,
+            } => {
+                write!(
+                    f,
+                    "no cipher suite in common with the peer: offered {:?}",
+                    offered
+                )?;
+                match chosen {
+                    Some(chosen) => write!(f, ", peer chose {:?}", chosen),
+                    None => write!(f, ", peer rejected the handshake"),
+                }
+            }
             Self::InvalidCertificateEncoding => {
                 write!(f, "invalid peer certificate encoding")
             }
@@ -213,7 +237,7 @@ mod tests {
     #[test]
     fn smoke() {
         use sct;
-        use tls_core::msgs::enums::{AlertDescription, ContentType, HandshakeType};
+        use tls_core::msgs::enums::{AlertDescription, CipherSuite, ContentType, HandshakeType};
 
         let all = vec![
             Error::InappropriateMessage {
@@ -230,6 +254,10 @@ mod tests {
             Error::PeerIncompatibleError("no tls1.2".to_string()),
             Error::PeerMisbehavedError("inconsistent something".to_string()),
             Error::AlertReceived(AlertDescription::ExportRestriction),
+            Error::NoCommonCipherSuite {
+                offered: vec![CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256],
+                chosen: Some(CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384),
+            },
             Error::InvalidCertificateEncoding,
             Error::InvalidCertificateSignatureType,
             Error::InvalidCertificateSignature,
@@ -242,6 +242,35 @@ fn config_builder_for_client_rejects_incompatible_cipher_suites() {
     );
 }
 
+#[cfg(feature = "tls12")]
+#[tokio::test]
+async fn client_reports_no_common_cipher_suite() {
+    let client_config = finish_client_config(
+        KeyType::Ecdsa,
+        ClientConfig::builder()
+            .with_cipher_suites(&[find_suite(
+                CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            )])
+            .with_safe_default_kx_groups()
+            .with_safe_default_protocol_versions()
+            .unwrap(),
+    );
+    let server_config = make_server_config(KeyType::Ecdsa);
+
+    let (mut client, mut server) = make_pair_for_configs(client_config, server_config).await;
+
+    let errors = do_handshake_until_both_error(&mut client, &mut server)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        errors.last(),
+        Some(&ErrorFromPeer::Client(Error::NoCommonCipherSuite {
+            offered: vec![CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256],
+            chosen: None,
+        }))
+    );
+}
+
 #[tokio::test]
 async fn servered_client_data_sent() {
     let server_config = Arc::new(make_server_config(KeyType::Rsa));
@@ -3343,6 +3372,145 @@ async fn test_client_tls12_no_resume_after_server_downgrade() {
     assert_eq!(client_storage.puts(), 2);
 }
 
+/// A TLS 1.2 server may acknowledge the session ticket extension and send a NewSessionTicket
+/// before its ChangeCipherSpec. The ticket is ignored, and renegotiation is still rejected once
+/// the handshake is complete.
+#[cfg(feature = "tls12")]
+#[tokio::test]
+async fn test_client_tls12_ignores_session_ticket() {
+    use aes_gcm::{
+        aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+        Aes128Gcm,
+    };
+    use tls_client::internal::msgs::enums::{ContentType, ExtensionType, HandshakeType};
+
+    fn take_tls(server: &mut ServerConnection) -> Vec<u8> {
+        let mut flight = Vec::new();
+        while server.wants_write() {
+            server.write_tls(&mut flight).unwrap();
+        }
+        flight
+    }
+
+    fn give_tls(flight: &[u8], client: &mut ClientConnection) {
+        let mut offs = 0;
+        while offs < flight.len() {
+            offs += client.read_tls(&mut &flight[offs..]).unwrap();
+        }
+    }
+
+    let kt = KeyType::Rsa;
+    let mut client_config = finish_client_config(
+        kt,
+        ClientConfig::builder()
+            .with_cipher_suites(&[find_suite(
+                CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            )])
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(&[&tls_client::version::TLS12])
+            .unwrap(),
+    );
+    // The client does not ask for tickets on its own, and rustls only issues them on request.
+    client_config.custom_extensions = vec![(ExtensionType::SessionTicket.get_u16(), Vec::new())];
+
+    let server_key_log = Arc::new(KeyLogToVec::new("server"));
+    let mut server_config = make_server_config(kt);
+    server_config.ticketer = rustls::Ticketer::new().unwrap();
+    server_config.key_log = server_key_log.clone();
+
+    let (mut client, mut server) = make_pair_for_configs(client_config, server_config).await;
+
+    // ClientHello
+    send(&mut client, &mut server);
+    server.process_new_packets().unwrap();
+
+    // ServerHello .. ServerHelloDone
+    let flight = take_tls(&mut server);
+    assert_eq!(flight[5], HandshakeType::ServerHello.get_u8());
+    let server_random = flight[11..43].to_vec();
+    give_tls(&flight, &mut client);
+    client.process_new_packets().await.unwrap();
+
+    // ClientKeyExchange, ChangeCipherSpec, Finished
+    send(&mut client, &mut server);
+    server.process_new_packets().unwrap();
+
+    // NewSessionTicket, ChangeCipherSpec, Finished
+    let flight = take_tls(&mut server);
+    assert_eq!(flight[5], HandshakeType::NewSessionTicket.get_u8());
+    give_tls(&flight, &mut client);
+    client.process_new_packets().await.unwrap();
+
+    assert!(!client.is_handshaking());
+    assert!(!server.is_handshaking());
+
+    // rustls never requests renegotiation, so encrypt a HelloRequest with the server's keys.
+    let item = server_key_log
+        .take()
+        .into_iter()
+        .find(|item| item.label == "CLIENT_RANDOM")
+        .unwrap();
+    let mut key_block = [0u8; 40];
+    tls_core::prf::prf(
+        &mut key_block,
+        &item.secret,
+        b"key expansion",
+        &[server_random, item.client_random].concat(),
+    )
+    .unwrap();
+    let (server_write_key, server_write_iv) = (&key_block[16..32], &key_block[36..40]);
+
+    // The server's Finished was sent with sequence number 0.
+    let seq = 1u64.to_be_bytes();
+    let hello_request = [HandshakeType::HelloRequest.get_u8(), 0, 0, 0];
+    let header = [ContentType::Handshake.get_u8(), 0x03, 0x03];
+    let aad = [
+        &seq[..],
+        &header[..],
+        &(hello_request.len() as u16).to_be_bytes()[..],
+    ]
+    .concat();
+    let nonce = [server_write_iv, &seq[..]].concat();
+    let ciphertext = Aes128Gcm::new_from_slice(server_write_key)
+        .unwrap()
+        .encrypt(
+            GenericArray::from_slice(&nonce),
+            Payload {
+                msg: &hello_request,
+                aad: &aad,
+            },
+        )
+        .unwrap();
+    let record = [
+        &header[..],
+        &((seq.len() + ciphertext.len()) as u16).to_be_bytes()[..],
+        &seq[..],
+        &ciphertext[..],
+    ]
+    .concat();
+
+    give_tls(&record, &mut client);
+    client.process_new_packets().await.unwrap();
+    assert!(!client.is_handshaking());
+
+    // The client answers with a no_renegotiation warning, and the connection stays usable.
+    let mut alert = Vec::new();
+    while client.wants_write() {
+        client.write_tls(&mut alert).unwrap();
+    }
+    assert_eq!(alert[0], ContentType::Alert.get_u8());
+    let mut offs = 0;
+    while offs < alert.len() {
+        offs += server.read_tls(&mut &alert[offs..]).unwrap();
+    }
+    server.process_new_packets().unwrap();
+
+    assert_eq!(5, server.writer().write(b"hello").unwrap());
+    receive(&mut server, &mut client);
+    client.process_new_packets().await.unwrap();
+    check_read(&mut client.reader(), b"hello");
+}
+
 #[derive(Default, Debug)]
 struct LogCounts {
     trace: usize,
@@ -9,6 +9,7 @@ pub mod cipher;
 pub mod dns;
 pub mod handshake;
 pub mod ke;
+pub mod ocsp;
 #[cfg(feature = "prf")]
 pub mod prf;
 pub mod rand;
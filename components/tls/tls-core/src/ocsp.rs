@@ -0,0 +1,307 @@
+//! Verification of OCSP responses (RFC 6960) stapled by a server during the handshake.
+
+use der::{asn1::ObjectIdentifier, Decode, Encode};
+use ring::digest;
+use web_time::SystemTime;
+use x509_cert::{ext::pkix::ExtendedKeyUsage, Certificate as X509Certificate};
+use x509_ocsp::{BasicOcspResponse, CertId, OcspResponse, OcspResponseStatus};
+
+use crate::{
+    key::Certificate,
+    verify::{
+        pki_error, verify_sig_using_any_alg, SignatureAlgorithms, ECDSA_SHA256, ECDSA_SHA384,
+        ED25519, RSA_SHA256, RSA_SHA384, RSA_SHA512,
+    },
+    Error,
+};
+
+const ID_PKIX_OCSP_BASIC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.48.1.1");
+const ID_KP_OCSP_SIGNING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.9");
+const ID_CE_EXT_KEY_USAGE: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.37");
+const ID_SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const ID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// How long a response without a `nextUpdate` time is considered fresh, in seconds.
+const MAX_AGE_WITHOUT_NEXT_UPDATE: u64 = 7 * 24 * 60 * 60;
+
+/// The revocation status of a certificate asserted by an OCSP responder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CertStatus {
+    /// The certificate is not revoked.
+    Good,
+    /// The certificate was revoked.
+    Revoked {
+        /// The time of the revocation, in seconds since the UNIX epoch.
+        time: u64,
+    },
+    /// The responder does not know the certificate.
+    Unknown,
+}
+
+/// Verifies an OCSP response for `end_entity` and returns the status it asserts.
+///
+/// The response must be signed by `issuer`, the certificate which issued `end_entity`, or by a
+/// responder certificate included in the response which `issuer` delegated OCSP signing to. It
+/// must be fresh at `now`, that is `now` must lie between its `thisUpdate` and `nextUpdate`
+/// times.
+///
+/// This does not verify that `issuer` is trusted, which is left to the verification of the
+/// certificate chain.
+pub fn verify_ocsp_response(
+    response: &[u8],
+    end_entity: &Certificate,
+    issuer: &Certificate,
+    now: SystemTime,
+) -> Result<CertStatus, Error> {
+    let now = now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|_| Error::FailedToGetCurrentTime)?
+        .as_secs();
+
+    let end_entity = X509Certificate::from_der(&end_entity.0).map_err(malformed)?;
+    let issuer_cert = X509Certificate::from_der(&issuer.0).map_err(malformed)?;
+    let issuer = webpki::EndEntityCert::try_from(issuer.0.as_ref()).map_err(pki_error)?;
+
+    if !is_issued_by(&end_entity, &issuer)? {
+        return Err(invalid(
+            "the certificate was not issued by the provided issuer",
+        ));
+    }
+
+    let response = OcspResponse::from_der(response).map_err(malformed)?;
+    if response.response_status != OcspResponseStatus::Successful {
+        return Err(invalid("the responder returned an error"));
+    }
+    let response_bytes = response
+        .response_bytes
+        .ok_or_else(|| invalid("the response is empty"))?;
+    if response_bytes.response_type != ID_PKIX_OCSP_BASIC {
+        return Err(invalid("unsupported response type"));
+    }
+    let basic =
+        BasicOcspResponse::from_der(response_bytes.response.as_bytes()).map_err(malformed)?;
+
+    verify_responder(&basic, &issuer, now)?;
+
+    for single in &basic.tbs_response_data.responses {
+        if !is_cert_id_of(&single.cert_id, &end_entity, &issuer_cert) {
+            continue;
+        }
+
+        let this_update = single.this_update.0.to_unix_duration().as_secs();
+        let next_update = single
+            .next_update
+            .as_ref()
+            .map(|time| time.0.to_unix_duration().as_secs())
+            .unwrap_or_else(|| this_update.saturating_add(MAX_AGE_WITHOUT_NEXT_UPDATE));
+        if now < this_update || now > next_update {
+            return Err(invalid("the response is not fresh"));
+        }
+
+        return Ok(match &single.cert_status {
+            x509_ocsp::CertStatus::Good(_) => CertStatus::Good,
+            x509_ocsp::CertStatus::Revoked(info) => CertStatus::Revoked {
+                time: info.revocation_time.0.to_unix_duration().as_secs(),
+            },
+            x509_ocsp::CertStatus::Unknown(_) => CertStatus::Unknown,
+        });
+    }
+
+    Err(invalid("the response does not cover the certificate"))
+}
+
+/// Verifies that the response data is signed by the issuer or a delegated responder.
+fn verify_responder(
+    basic: &BasicOcspResponse,
+    issuer: &webpki::EndEntityCert,
+    now: u64,
+) -> Result<(), Error> {
+    let tbs = basic.tbs_response_data.to_der().map_err(malformed)?;
+    let algs = signature_algs(&basic.signature_algorithm.oid)?;
+    let signature = basic
+        .signature
+        .as_bytes()
+        .ok_or_else(|| invalid("malformed signature"))?;
+
+    if verify_sig_using_any_alg(issuer, algs, &tbs, signature).is_ok() {
+        return Ok(());
+    }
+
+    for cert in basic.certs.iter().flatten() {
+        if !is_delegated_responder(cert, issuer, now)? {
+            continue;
+        }
+
+        let der = cert.to_der().map_err(malformed)?;
+        let responder = webpki::EndEntityCert::try_from(der.as_slice()).map_err(pki_error)?;
+        if verify_sig_using_any_alg(&responder, algs, &tbs, signature).is_ok() {
+            return Ok(());
+        }
+    }
+
+    Err(invalid(
+        "the response is not signed by the issuer or a delegated responder",
+    ))
+}
+
+/// Returns whether the signature of `cert` verifies with the key of `issuer`.
+fn is_issued_by(cert: &X509Certificate, issuer: &webpki::EndEntityCert) -> Result<bool, Error> {
+    let Ok(algs) = signature_algs(&cert.signature_algorithm.oid) else {
+        return Ok(false);
+    };
+    let tbs = cert.tbs_certificate.to_der().map_err(malformed)?;
+    let Some(signature) = cert.signature.as_bytes() else {
+        return Ok(false);
+    };
+
+    Ok(verify_sig_using_any_alg(issuer, algs, &tbs, signature).is_ok())
+}
+
+/// Returns whether `cert` is a valid responder certificate issued by `issuer`.
+fn is_delegated_responder(
+    cert: &X509Certificate,
+    issuer: &webpki::EndEntityCert,
+    now: u64,
+) -> Result<bool, Error> {
+    let validity = &cert.tbs_certificate.validity;
+
+    Ok(is_issued_by(cert, issuer)?
+        && validity.not_before.to_unix_duration().as_secs() <= now
+        && now <= validity.not_after.to_unix_duration().as_secs()
+        && has_ocsp_signing_usage(cert)?)
+}
+
+/// Returns whether the extended key usage of the certificate includes OCSP signing.
+fn has_ocsp_signing_usage(cert: &X509Certificate) -> Result<bool, Error> {
+    for extension in cert.tbs_certificate.extensions.iter().flatten() {
+        if extension.extn_id != ID_CE_EXT_KEY_USAGE {
+            continue;
+        }
+
+        let usage =
+            ExtendedKeyUsage::from_der(extension.extn_value.as_bytes()).map_err(malformed)?;
+        if usage.0.contains(&ID_KP_OCSP_SIGNING) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Returns whether a `CertID` identifies `end_entity` issued by `issuer`.
+fn is_cert_id_of(cert_id: &CertId, end_entity: &X509Certificate, issuer: &X509Certificate) -> bool {
+    let hash_alg = match cert_id.hash_algorithm.oid {
+        ID_SHA1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+        ID_SHA256 => &digest::SHA256,
+        _ => return false,
+    };
+    let issuer_key = issuer
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .raw_bytes();
+
+    cert_id.serial_number == end_entity.tbs_certificate.serial_number
+        && digest::digest(hash_alg, issuer_key).as_ref() == cert_id.issuer_key_hash.as_bytes()
+}
+
+/// Returns the algorithms which may have produced a signature with the given algorithm
+/// identifier.
+fn signature_algs(oid: &ObjectIdentifier) -> Result<SignatureAlgorithms, Error> {
+    match *oid {
+        ECDSA_WITH_SHA256 => Ok(ECDSA_SHA256),
+        ECDSA_WITH_SHA384 => Ok(ECDSA_SHA384),
+        ID_ED25519 => Ok(ED25519),
+        SHA256_WITH_RSA => Ok(RSA_SHA256),
+        SHA384_WITH_RSA => Ok(RSA_SHA384),
+        SHA512_WITH_RSA => Ok(RSA_SHA512),
+        _ => Err(Error::InvalidCertificateSignatureType),
+    }
+}
+
+fn malformed(err: der::Error) -> Error {
+    invalid(&format!("malformed response: {err}"))
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidCertificateData(format!("invalid OCSP response: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const CA: &[u8] = include_bytes!("../testdata/ocsp/ca.der");
+    const OTHER: &[u8] = include_bytes!("../testdata/ocsp/other.der");
+    const EE: &[u8] = include_bytes!("../testdata/ocsp/ee.der");
+    const GOOD: &[u8] = include_bytes!("../testdata/ocsp/good.der");
+    const REVOKED: &[u8] = include_bytes!("../testdata/ocsp/revoked.der");
+    const DELEGATED: &[u8] = include_bytes!("../testdata/ocsp/delegated.der");
+
+    /// 2024-01-02, between the `thisUpdate` and `nextUpdate` of the responses.
+    fn fresh() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1704153600)
+    }
+
+    fn verify(response: &[u8], issuer: &[u8], now: SystemTime) -> Result<CertStatus, Error> {
+        verify_ocsp_response(
+            response,
+            &Certificate(EE.to_vec()),
+            &Certificate(issuer.to_vec()),
+            now,
+        )
+    }
+
+    #[test]
+    fn test_good_response() {
+        assert_eq!(verify(GOOD, CA, fresh()).unwrap(), CertStatus::Good);
+    }
+
+    #[test]
+    fn test_revoked_response() {
+        assert_eq!(
+            verify(REVOKED, CA, fresh()).unwrap(),
+            CertStatus::Revoked { time: 1704067200 }
+        );
+    }
+
+    #[test]
+    fn test_delegated_responder() {
+        assert_eq!(verify(DELEGATED, CA, fresh()).unwrap(), CertStatus::Good);
+    }
+
+    #[test]
+    fn test_rejects_tampered_signature() {
+        let mut response = GOOD.to_vec();
+        // The signature is at the end of the response, as it has no certificates.
+        *response.last_mut().unwrap() ^= 1;
+
+        assert!(verify(&response, CA, fresh()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_issuer() {
+        assert!(verify(GOOD, OTHER, fresh()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_stale_response() {
+        // 2024-02-01, after the `nextUpdate` of the response.
+        let stale = SystemTime::UNIX_EPOCH + Duration::from_secs(1706745600);
+
+        assert!(verify(GOOD, CA, stale).is_err());
+    }
+
+    #[test]
+    fn test_rejects_malformed_response() {
+        assert!(verify(&[0x30, 0x03, 0x0a, 0x01, 0x00], CA, fresh()).is_err());
+    }
+}
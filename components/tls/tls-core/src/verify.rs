@@ -12,7 +12,7 @@ use ring::digest::Digest;
 use std::convert::TryFrom;
 use web_time::SystemTime;
 
-type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
+pub(crate) type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
 
 /// Which signature verification mechanisms we support.  No particular
 /// order.
@@ -456,17 +456,17 @@ pub(crate) fn pki_error(error: webpki::Error) -> Error {
     }
 }
 
-static ECDSA_SHA256: SignatureAlgorithms =
+pub(crate) static ECDSA_SHA256: SignatureAlgorithms =
     &[&webpki::ECDSA_P256_SHA256, &webpki::ECDSA_P384_SHA256];
 
-static ECDSA_SHA384: SignatureAlgorithms =
+pub(crate) static ECDSA_SHA384: SignatureAlgorithms =
     &[&webpki::ECDSA_P256_SHA384, &webpki::ECDSA_P384_SHA384];
 
-static ED25519: SignatureAlgorithms = &[&webpki::ED25519];
+pub(crate) static ED25519: SignatureAlgorithms = &[&webpki::ED25519];
 
-static RSA_SHA256: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA256];
-static RSA_SHA384: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA384];
-static RSA_SHA512: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA512];
+pub(crate) static RSA_SHA256: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA256];
+pub(crate) static RSA_SHA384: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA384];
+pub(crate) static RSA_SHA512: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA512];
 static RSA_PSS_SHA256: SignatureAlgorithms = &[&webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY];
 static RSA_PSS_SHA384: SignatureAlgorithms = &[&webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY];
 static RSA_PSS_SHA512: SignatureAlgorithms = &[&webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY];
@@ -494,7 +494,7 @@ fn convert_scheme(scheme: SignatureScheme) -> Result<SignatureAlgorithms, Error>
     }
 }
 
-fn verify_sig_using_any_alg(
+pub(crate) fn verify_sig_using_any_alg(
     cert: &webpki::EndEntityCert,
     algs: SignatureAlgorithms,
     message: &[u8],
@@ -5,6 +5,9 @@ static DEFAULT_OPAQUE_RX_TRANSCRIPT_ID: &str = "opaque_rx";
 static DEFAULT_TX_TRANSCRIPT_ID: &str = "tx";
 static DEFAULT_RX_TRANSCRIPT_ID: &str = "rx";
 const DEFAULT_TRANSCRIPT_MAX_SIZE: usize = 1 << 14;
+const DEFAULT_TRANSCRIPT_PREPROCESS_SIZE: usize = 1 << 10;
+/// The number of received bytes preprocessed during setup for decrypting the handshake.
+pub(crate) const HANDSHAKE_PREPROCESS_SIZE: usize = 256;
 
 /// Transcript configuration.
 #[derive(Debug, Clone, Builder)]
@@ -16,6 +19,18 @@ pub struct TranscriptConfig {
     opaque_id: String,
     /// The maximum length of the transcript in bytes.
     max_size: usize,
+    /// The number of bytes to preprocess at a time.
+    ///
+    /// Data is preprocessed in increments of this size as it flows, up to the maximum length of
+    /// the transcript. See `setup_preprocess_size` for the data preprocessed during setup.
+    #[builder(default = "DEFAULT_TRANSCRIPT_PREPROCESS_SIZE")]
+    preprocess_size: usize,
+    /// The number of bytes to preprocess during setup, before the connection to the server.
+    ///
+    /// If not set, `preprocess_size` bytes are preprocessed for the sent transcript, and none
+    /// beyond the handshake for the received transcript.
+    #[builder(default)]
+    setup_preprocess_size: Option<usize>,
 }
 
 impl TranscriptConfig {
@@ -62,6 +77,17 @@ impl TranscriptConfig {
     pub fn max_size(&self) -> usize {
         self.max_size
     }
+
+    /// Returns the number of bytes to preprocess at a time.
+    pub fn preprocess_size(&self) -> usize {
+        self.preprocess_size.min(self.max_size)
+    }
+
+    /// Returns the number of bytes to preprocess during setup, if set.
+    pub fn setup_preprocess_size(&self) -> Option<usize> {
+        self.setup_preprocess_size
+            .map(|size| size.min(self.max_size))
+    }
 }
 
 /// Configuration options which are common to both the leader and the follower
@@ -121,6 +147,9 @@ impl MpcTlsCommonConfig {
 #[derive(Debug, Clone, Builder)]
 pub struct MpcTlsLeaderConfig {
     common: MpcTlsCommonConfig,
+    /// Whether to defer decryption of incoming messages until the connection is closed.
+    #[builder(default)]
+    defer_decryption: bool,
 }
 
 impl MpcTlsLeaderConfig {
@@ -133,6 +162,11 @@ impl MpcTlsLeaderConfig {
     pub fn common(&self) -> &MpcTlsCommonConfig {
         &self.common
     }
+
+    /// Whether decryption of incoming messages is deferred until the connection is closed.
+    pub fn defer_decryption(&self) -> bool {
+        self.defer_decryption
+    }
 }
 
 /// Configuration for the follower
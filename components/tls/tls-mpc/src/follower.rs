@@ -26,6 +26,7 @@ use tls_core::{
 };
 
 use crate::{
+    config::HANDSHAKE_PREPROCESS_SIZE,
     error::Kind,
     msg::{CloseConnection, Commit, MpcTlsFollowerMsg, MpcTlsMessage},
     record_layer::{Decrypter, Encrypter},
@@ -66,6 +67,9 @@ pub struct MpcTlsFollowerData {
     pub bytes_sent: usize,
     /// The total number of bytes received
     pub bytes_recv: usize,
+    /// Whether the server closed the connection with a CloseNotify alert, ie. the received data
+    /// was not truncated
+    pub close_notify: bool,
 }
 
 impl ludi::Actor for MpcTlsFollower {
@@ -89,6 +93,7 @@ impl ludi::Actor for MpcTlsFollower {
             server_key,
             bytes_sent,
             bytes_recv,
+            close_notify: self.close_notify,
         })
     }
 }
@@ -146,11 +151,17 @@ impl MpcTlsFollower {
                 .set_key(session_keys.server_write_key, session_keys.server_iv)
         )?;
 
+        let tx_config = self.config.common().tx_config();
+        let rx_config = self.config.common().rx_config();
+        let sent_len = tx_config
+            .setup_preprocess_size()
+            .unwrap_or(tx_config.preprocess_size());
+        // Enough for the handshake, and the received data to preprocess on top
+        let recv_len = HANDSHAKE_PREPROCESS_SIZE + rx_config.setup_preprocess_size().unwrap_or(0);
+
         futures::try_join!(
-            self.encrypter
-                .preprocess(self.config.common().tx_config().max_size()),
-            // For now we just preprocess enough for the handshake
-            self.decrypter.preprocess(256)
+            self.encrypter.preprocess(sent_len),
+            self.decrypter.preprocess(recv_len)
         )?;
 
         Ok(())
@@ -425,6 +436,11 @@ impl MpcTlsFollower {
         self.check_transcript_length(Direction::Sent, len)?;
         self.state.try_as_active()?;
 
+        let tx_config = self.config.common().tx_config();
+        self.encrypter
+            .preprocess_next(len, tx_config.preprocess_size(), tx_config.max_size())
+            .await?;
+
         self.encrypter
             .encrypt_blind(ContentType::ApplicationData, ProtocolVersion::TLSv1_2, len)
             .await?;
@@ -521,6 +537,11 @@ impl MpcTlsFollower {
             // to the ciphertext of this TLS message.
             self.decrypter.verify_plaintext(msg).await?;
         } else {
+            let rx_config = self.config.common().rx_config();
+            self.decrypter
+                .preprocess_next(&msg, rx_config.preprocess_size(), rx_config.max_size())
+                .await?;
+
             self.decrypter.decrypt_blind(msg).await?;
         }
 
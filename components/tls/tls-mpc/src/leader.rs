@@ -30,6 +30,7 @@ use tls_core::{
 };
 
 use crate::{
+    config::HANDSHAKE_PREPROCESS_SIZE,
     error::Kind,
     follower::{
         ClientFinishedVd, CommitMessage, ComputeClientKey, ComputeKeyExchange, DecryptAlert,
@@ -103,6 +104,8 @@ impl MpcTlsLeader {
             config.common().rx_config().opaque_id().to_string(),
         );
 
+        let is_decrypting = !config.defer_decryption();
+
         Self {
             config,
             channel,
@@ -112,7 +115,7 @@ impl MpcTlsLeader {
             encrypter,
             decrypter,
             notifier: BackendNotifier::new(),
-            is_decrypting: true,
+            is_decrypting,
             buffer: VecDeque::new(),
             committed: false,
         }
@@ -134,11 +137,17 @@ impl MpcTlsLeader {
                 .set_key(session_keys.server_write_key, session_keys.server_iv)
         )?;
 
+        let tx_config = self.config.common().tx_config();
+        let rx_config = self.config.common().rx_config();
+        let sent_len = tx_config
+            .setup_preprocess_size()
+            .unwrap_or(tx_config.preprocess_size());
+        // Enough for the handshake, and the received data to preprocess on top
+        let recv_len = HANDSHAKE_PREPROCESS_SIZE + rx_config.setup_preprocess_size().unwrap_or(0);
+
         futures::try_join!(
-            self.encrypter
-                .preprocess(self.config.common().tx_config().max_size()),
-            // For now we just preprocess enough for the handshake
-            self.decrypter.preprocess(256)
+            self.encrypter.preprocess(sent_len),
+            self.decrypter.preprocess(recv_len)
         )?;
 
         Ok(())
@@ -270,6 +279,15 @@ impl MpcTlsLeader {
             }))
             .await?;
 
+        let tx_config = self.config.common().tx_config();
+        self.encrypter
+            .preprocess_next(
+                msg.payload.0.len(),
+                tx_config.preprocess_size(),
+                tx_config.max_size(),
+            )
+            .await?;
+
         let msg = self.encrypter.encrypt_private(msg).await?;
 
         Ok(msg)
@@ -338,6 +356,11 @@ impl MpcTlsLeader {
             // and will prove the knowledge of the plaintext to the follower.
             self.decrypter.prove_plaintext(msg).await?
         } else {
+            let rx_config = self.config.common().rx_config();
+            self.decrypter
+                .preprocess_next(&msg, rx_config.preprocess_size(), rx_config.max_size())
+                .await?;
+
             self.decrypter.decrypt_private(msg).await?
         };
 
@@ -25,8 +25,15 @@ pub use error::MpcTlsError;
 pub use follower::{FollowerCtrl, MpcTlsFollower, MpcTlsFollowerData};
 pub use leader::{LeaderCtrl, MpcTlsData, MpcTlsLeader};
 pub use setup::setup_components;
+use tls_core::msgs::enums::CipherSuite;
 use utils_aio::duplex::Duplex;
 
+/// The cipher suites which can be used with the server under MPC.
+pub const SUPPORTED_CIPHER_SUITES: &[CipherSuite] = &[
+    CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+    CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+];
+
 /// A channel for sending and receiving messages between leader and follower
 pub type MpcTlsChannel = Box<dyn Duplex<msg::MpcTlsMessage>>;
 
@@ -9,12 +9,13 @@ use tls_core::{
     },
 };
 
-use crate::{error::Kind, MpcTlsError};
+use crate::{config::HANDSHAKE_PREPROCESS_SIZE, error::Kind, MpcTlsError};
 
 pub(crate) struct Encrypter {
     aead: Box<dyn aead::Aead>,
     seq: u64,
     sent_bytes: usize,
+    preprocessed: usize,
     transcript_id: String,
     opaque_transcript_id: String,
 }
@@ -29,6 +30,7 @@ impl Encrypter {
             aead,
             seq: 0,
             sent_bytes: 0,
+            preprocessed: 0,
             transcript_id,
             opaque_transcript_id,
         }
@@ -53,6 +55,26 @@ impl Encrypter {
             .await
             .map_err(|e| MpcTlsError::new_with_source(Kind::Encrypt, "preprocess error", e))?;
 
+        self.preprocessed += len;
+
+        Ok(())
+    }
+
+    /// Preprocesses another `step` bytes, or more if needed, if fewer than `len` bytes are
+    /// preprocessed beyond the data sent so far. At most `max` bytes are preprocessed in total.
+    ///
+    /// Both parties must call this with the same arguments before encrypting application data.
+    pub(crate) async fn preprocess_next(
+        &mut self,
+        len: usize,
+        step: usize,
+        max: usize,
+    ) -> Result<(), MpcTlsError> {
+        let next = next_preprocess_len(self.preprocessed, self.sent_bytes + len, step, max);
+        if next > 0 {
+            self.preprocess(next).await?;
+        }
+
         Ok(())
     }
 
@@ -179,6 +201,8 @@ pub(crate) struct Decrypter {
     aead: Box<dyn aead::Aead>,
     seq: u64,
     recv_bytes: usize,
+    /// Bytes preprocessed, including those for the handshake.
+    preprocessed: usize,
     transcript_id: String,
     opaque_transcript_id: String,
 }
@@ -193,6 +217,7 @@ impl Decrypter {
             aead,
             seq: 0,
             recv_bytes: 0,
+            preprocessed: 0,
             transcript_id,
             opaque_transcript_id,
         }
@@ -217,6 +242,35 @@ impl Decrypter {
             .await
             .map_err(|e| MpcTlsError::new_with_source(Kind::Decrypt, "preprocess error", e))?;
 
+        self.preprocessed += len;
+
+        Ok(())
+    }
+
+    /// Preprocesses another `step` bytes, or more if needed, if the preprocessed data does not
+    /// cover `msg` beyond the data received so far. At most `max` bytes are preprocessed in total
+    /// for application data.
+    ///
+    /// Both parties must call this with the same arguments before decrypting application data
+    /// with MPC.
+    pub(crate) async fn preprocess_next(
+        &mut self,
+        msg: &OpaqueMessage,
+        step: usize,
+        max: usize,
+    ) -> Result<(), MpcTlsError> {
+        // The payload contains the explicit nonce and the tag.
+        let len = msg.payload.0.len().saturating_sub(8 + 16);
+        let next = next_preprocess_len(
+            self.preprocessed,
+            HANDSHAKE_PREPROCESS_SIZE + self.recv_bytes + len,
+            step,
+            HANDSHAKE_PREPROCESS_SIZE + max,
+        );
+        if next > 0 {
+            self.preprocess(next).await?;
+        }
+
         Ok(())
     }
 
@@ -414,3 +468,37 @@ impl Decrypter {
         }
     }
 }
+
+/// Returns the number of bytes to preprocess so that `required` bytes are covered, given that
+/// `preprocessed` bytes already are.
+///
+/// Preprocesses at least `step` bytes at a time, and at most `max` bytes in total.
+fn next_preprocess_len(preprocessed: usize, required: usize, step: usize, max: usize) -> usize {
+    if required <= preprocessed {
+        return 0;
+    }
+
+    (preprocessed + step)
+        .max(required)
+        .min(max)
+        .saturating_sub(preprocessed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_preprocess_len() {
+        // Covered by what was preprocessed.
+        assert_eq!(next_preprocess_len(1024, 1000, 1024, 4096), 0);
+        assert_eq!(next_preprocess_len(1024, 1024, 1024, 4096), 0);
+        // Preprocesses another step.
+        assert_eq!(next_preprocess_len(1024, 1025, 1024, 4096), 1024);
+        // Preprocesses more than a step for a large record.
+        assert_eq!(next_preprocess_len(1024, 3000, 1024, 4096), 1976);
+        // Never beyond the maximum.
+        assert_eq!(next_preprocess_len(3584, 3600, 1024, 4096), 512);
+        assert_eq!(next_preprocess_len(4096, 5000, 1024, 4096), 0);
+    }
+}
@@ -18,4 +18,9 @@ fn main() {
     // Pass these 2 values as env var to the program
     println!("cargo:rustc-env=GIT_COMMIT_HASH={}", commit_hash);
     println!("cargo:rustc-env=GIT_COMMIT_TIMESTAMP={}", commit_timestamp);
+
+    // Generate the gRPC service from its protobuf schema
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/notary.proto")
+        .expect("Protobuf schema of the gRPC API should compile");
 }
@@ -0,0 +1,110 @@
+use axum::{extract::State, Json};
+use eyre::eyre;
+use std::sync::atomic::Ordering;
+use tlsn_verifier::tls::AsyncSigner;
+use tracing::info;
+
+use crate::{
+    config::{PolicyProperties, RotatedKeyProperties},
+    domain::{notary::NotaryGlobals, AdminStatusResponse},
+    error::NotaryServerError,
+    keys::{parse_public_key, CurrentKey},
+    server::{load_additional_key, load_notary_key},
+};
+
+/// Message signed with a new key before it is rotated in, to check that it matches its public key. It is
+/// prefixed with its own context, like usage receipts, so that it can't be mistaken for any other message
+/// signed with the key
+const KEY_CHECK_MESSAGE: &[u8] = b"tlsn-notary-server/key-check/v1\n";
+
+/// Handler to report the draining state, the notarizations in progress and the published keys
+pub async fn get_status(State(notary_globals): State<NotaryGlobals>) -> Json<AdminStatusResponse> {
+    Json(status(&notary_globals))
+}
+
+/// Handler to rotate a notary signing key to the one set in the request, which has the same format as the
+/// `notary-key` field of the config or one of its `additional-keys`. Ongoing sessions finish with the previous
+/// key
+pub async fn rotate_key(
+    State(notary_globals): State<NotaryGlobals>,
+    Json(config): Json<RotatedKeyProperties>,
+) -> Result<Json<AdminStatusResponse>, NotaryServerError> {
+    let tee_platform = notary_globals.notary_keys.tee_platform();
+    let key = match &config {
+        RotatedKeyProperties::Main(config) => load_notary_key(config, tee_platform).await,
+        RotatedKeyProperties::Additional(config) => load_additional_key(config, tee_platform),
+    }
+    .map_err(|err| NotaryServerError::BadProverRequest(err.to_string()))?;
+    check_key_pair(&key).await?;
+
+    let algorithm = key.algorithm;
+    notary_globals.notary_keys.rotate(key)?;
+    info!("Rotated {algorithm:?} notary signing key");
+
+    Ok(Json(status(&notary_globals)))
+}
+
+/// Handler to replace the notarization and rate limit policies with those set in the request
+pub async fn reload_policies(
+    State(notary_globals): State<NotaryGlobals>,
+    Json(policies): Json<PolicyProperties>,
+) -> Json<AdminStatusResponse> {
+    if let Some(notarization) = policies.notarization {
+        info!(?notarization, "Reloaded notarization policy");
+        *notary_globals.notarization_config.write().unwrap() = notarization;
+    }
+    if let Some(rate_limit) = policies.rate_limit {
+        info!(?rate_limit, "Reloaded rate limit policy");
+        notary_globals.rate_limiter.set_config(rate_limit);
+    }
+
+    Json(status(&notary_globals))
+}
+
+/// Handler to refuse new sessions, so that the server can be stopped once the active sessions reported
+/// in the response drop to zero
+pub async fn start_draining(
+    State(notary_globals): State<NotaryGlobals>,
+) -> Json<AdminStatusResponse> {
+    notary_globals.draining.store(true, Ordering::Relaxed);
+    info!("Started draining sessions");
+
+    Json(status(&notary_globals))
+}
+
+/// Handler to accept new sessions again
+pub async fn stop_draining(
+    State(notary_globals): State<NotaryGlobals>,
+) -> Json<AdminStatusResponse> {
+    notary_globals.draining.store(false, Ordering::Relaxed);
+    info!("Stopped draining sessions");
+
+    Json(status(&notary_globals))
+}
+
+fn status(notary_globals: &NotaryGlobals) -> AdminStatusResponse {
+    AdminStatusResponse {
+        draining: notary_globals.draining.load(Ordering::Relaxed),
+        active_sessions: notary_globals.metrics.active_sessions(),
+        keys: notary_globals.notary_keys.published(),
+    }
+}
+
+/// Check that the signer of a new key matches its public key, so that no attestations are signed with a key
+/// which is not the one published
+async fn check_key_pair(key: &CurrentKey) -> Result<(), NotaryServerError> {
+    let public_key = parse_public_key(key.algorithm, &key.public_key)
+        .map_err(|err| NotaryServerError::BadProverRequest(format!("Invalid public key: {err}")))?;
+    let signature = key
+        .signer
+        .sign(KEY_CHECK_MESSAGE)
+        .await
+        .map_err(|err| eyre!("Failed to sign with the new key: {err}"))?;
+    signature
+        .verify(KEY_CHECK_MESSAGE, public_key)
+        .map_err(|_| {
+            NotaryServerError::BadProverRequest(
+                "Signing key does not match the public key".to_string(),
+            )
+        })
+}
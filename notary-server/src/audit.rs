@@ -0,0 +1,647 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eyre::{ensure, eyre, Result};
+use hyper::{client::HttpConnector, header, Body, Client, Method, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt, path::Path, sync::Arc, time::Duration};
+use tlsn_core::Signature;
+use tlsn_verifier::tls::AsyncSigner;
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::AsyncWriteExt,
+    net::UdpSocket,
+    sync::Mutex as AsyncMutex,
+};
+
+use tracing::error;
+
+use crate::{config::AuditSinkProperties, keys::NotaryKeys};
+
+/// Hash that the first record of an audit log is chained to
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Syslog priority of the records, i.e. the log audit facility (13) with the informational severity (6)
+const SYSLOG_PRIORITY: u8 = 13 * 8 + 6;
+
+/// Prefix of the message signed for an audit record. The notary key also signs attestations, so the prefix
+/// keeps an audit signature from being valid for any other message signed with it
+pub const AUDIT_SIGNING_CONTEXT: &[u8] = b"tlsn-notary-server/audit-record/v1\n";
+
+/// Timeout of requests to the OTLP collector
+const OTLP_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Audit record of a notarization session. Each record commits to the previous one through `prev_hash`, so
+/// that records can't be removed, reordered or modified without breaking the chain
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    /// Position of the record in the log, starting from 0
+    pub sequence: u64,
+    pub session_id: String,
+    /// Name of the API key or subject of the bearer token used to create the session, if authorization is enabled
+    pub client_name: Option<String>,
+    /// Time at which the session was requested at /session
+    pub created_at: DateTime<Utc>,
+    /// Time at which the prover connected to /notarize
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Maximum data that the prover could send in the session
+    pub max_sent_data: Option<usize>,
+    /// Maximum data that the prover could receive in the session
+    pub max_recv_data: Option<usize>,
+    #[serde(flatten)]
+    pub outcome: AuditOutcome,
+    /// Hash of the previous record
+    pub prev_hash: String,
+    /// SHA-256 hash of this record with an empty `hash` and no `signature`, in hex
+    pub hash: String,
+    /// Signature of `hash` by the current notary key, so that the chain can't be rewritten or extended without
+    /// the key. Only missing if signing failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+}
+
+/// Outcome of a notarization session
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "outcome")]
+pub enum AuditOutcome {
+    #[serde(rename_all = "camelCase")]
+    Success {
+        /// Length of the data sent to the server, as committed in the session header
+        sent_len: usize,
+        /// Length of the data received from the server, as committed in the session header
+        recv_len: usize,
+        /// Id of the stored attestation, if attestation storage is enabled
+        attestation_id: Option<String>,
+    },
+    Failure {
+        error: String,
+    },
+}
+
+/// Audit record of a session before it is added to the log
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub session_id: String,
+    pub client_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub max_sent_data: Option<usize>,
+    pub max_recv_data: Option<usize>,
+    pub outcome: AuditOutcome,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> Result<String> {
+        let unhashed = Self {
+            hash: String::new(),
+            signature: None,
+            ..self.clone()
+        };
+        Ok(format!(
+            "{:x}",
+            Sha256::digest(serde_json::to_vec(&unhashed)?)
+        ))
+    }
+}
+
+/// Check that audit records form an unbroken chain, returning the sequence of the first record which
+/// doesn't, e.g. to verify an audit log file
+pub fn verify_audit_chain(records: &[AuditRecord]) -> Result<(), u64> {
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for (sequence, record) in records.iter().enumerate() {
+        let is_valid = record.sequence == sequence as u64
+            && record.prev_hash == prev_hash
+            && record.compute_hash().is_ok_and(|hash| hash == record.hash);
+        if !is_valid {
+            return Err(sequence as u64);
+        }
+        prev_hash = record.hash.clone();
+    }
+    Ok(())
+}
+
+/// Message signed for an audit record, i.e. its hash prefixed with [AUDIT_SIGNING_CONTEXT]
+pub fn audit_message(hash: &str) -> Vec<u8> {
+    [AUDIT_SIGNING_CONTEXT, hash.as_bytes()].concat()
+}
+
+/// Destination of the audit records, e.g. a file, syslog or an OTLP collector
+#[async_trait]
+pub trait AuditSink: fmt::Debug + Send + Sync {
+    /// Append a record
+    async fn write(&self, record: &AuditRecord) -> Result<()>;
+
+    /// Returns the last record written, to continue its chain after a restart. Sinks which can't be read
+    /// back start a new chain
+    async fn last(&self) -> Result<Option<AuditRecord>> {
+        Ok(None)
+    }
+}
+
+/// Append-only log of the notarization sessions, chaining each record to the previous one
+#[derive(Debug)]
+pub struct AuditLog {
+    sink: Arc<dyn AuditSink>,
+    /// Keys signing the hash of each record
+    notary_keys: Arc<NotaryKeys>,
+    /// Sequence and hash of the last record, locked while a record is written so that the chain follows
+    /// the order of the sink
+    last: AsyncMutex<(u64, String)>,
+}
+
+impl AuditLog {
+    pub async fn new(sink: Arc<dyn AuditSink>, notary_keys: Arc<NotaryKeys>) -> Result<Self> {
+        let last = match sink.last().await? {
+            Some(record) => (record.sequence + 1, record.hash),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        Ok(Self {
+            sink,
+            notary_keys,
+            last: AsyncMutex::new(last),
+        })
+    }
+
+    /// Append the record of a finished session
+    pub async fn record(&self, entry: AuditEntry) -> Result<()> {
+        let mut last = self.last.lock().await;
+        let mut record = AuditRecord {
+            sequence: last.0,
+            session_id: entry.session_id,
+            client_name: entry.client_name,
+            created_at: entry.created_at,
+            started_at: entry.started_at,
+            finished_at: Utc::now(),
+            max_sent_data: entry.max_sent_data,
+            max_recv_data: entry.max_recv_data,
+            outcome: entry.outcome,
+            prev_hash: last.1.clone(),
+            hash: String::new(),
+            signature: None,
+        };
+        record.hash = record.compute_hash()?;
+        // A record which couldn't be signed is still written, as the chain keeps it from being modified
+        match self
+            .notary_keys
+            .current()
+            .signer
+            .sign(&audit_message(&record.hash))
+            .await
+        {
+            Ok(signature) => record.signature = Some(signature),
+            Err(err) => error!(
+                sequence = record.sequence,
+                "Failed to sign audit record: {err}"
+            ),
+        }
+        self.sink.write(&record).await?;
+        *last = (record.sequence + 1, record.hash);
+        Ok(())
+    }
+}
+
+/// Set up the configured audit sink
+pub async fn connect_audit_sink(config: &AuditSinkProperties) -> Result<Arc<dyn AuditSink>> {
+    Ok(match config {
+        AuditSinkProperties::File { path } => Arc::new(FileAuditSink::open(path).await?),
+        AuditSinkProperties::Syslog {
+            address,
+            socket_path,
+            head_path,
+        } => {
+            let mut sink = SyslogAuditSink::connect(address.as_deref(), socket_path).await?;
+            if let Some(head_path) = head_path {
+                sink = sink.with_head_path(head_path);
+            }
+            Arc::new(sink)
+        }
+        AuditSinkProperties::Otlp {
+            endpoint,
+            headers,
+            head_path,
+        } => {
+            let mut sink = OtlpAuditSink::new(endpoint, headers.clone());
+            if let Some(head_path) = head_path {
+                sink = sink.with_head_path(head_path);
+            }
+            Arc::new(sink)
+        }
+    })
+}
+
+/// File keeping the last record sent to a sink which can't be read back, to continue its chain after a restart
+#[derive(Debug)]
+struct AuditHead {
+    path: String,
+}
+
+impl AuditHead {
+    async fn read(&self) -> Result<Option<AuditRecord>> {
+        if !Path::new(&self.path).exists() {
+            return Ok(None);
+        }
+        let content = fs::read(&self.path).await?;
+        let record = serde_json::from_slice(&content)
+            .map_err(|err| eyre!("Failed to parse audit head at {}: {err}", self.path))?;
+        Ok(Some(record))
+    }
+
+    /// Replace the last record, through a temporary file so that a crash doesn't leave a partial record
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        let tmp_path = format!("{}.tmp", self.path);
+        let mut file = File::create(&tmp_path).await?;
+        file.write_all(&serde_json::to_vec(record)?).await?;
+        file.sync_data().await?;
+        fs::rename(&tmp_path, &self.path)
+            .await
+            .map_err(|err| eyre!("Failed to write audit head at {}: {err}", self.path))?;
+        Ok(())
+    }
+}
+
+/// Audit sink appending the records as JSON lines to a file
+#[derive(Debug)]
+pub struct FileAuditSink {
+    path: String,
+    file: AsyncMutex<File>,
+}
+
+impl FileAuditSink {
+    pub async fn open(path: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|err| eyre!("Failed to open audit log at {path}: {err}"))?;
+        Ok(Self {
+            path: path.to_string(),
+            file: AsyncMutex::new(file),
+        })
+    }
+}
+
+#[async_trait]
+impl AuditSink for FileAuditSink {
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        // Make sure the record is on disk before the session is reported as finished
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    async fn last(&self) -> Result<Option<AuditRecord>> {
+        if !Path::new(&self.path).exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.path).await?;
+        content
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|err| eyre!("Failed to parse last record of audit log: {err}"))
+            })
+            .transpose()
+    }
+}
+
+/// Audit sink sending the records to syslog in the RFC 5424 format, with the log audit facility
+#[derive(Debug)]
+pub struct SyslogAuditSink {
+    transport: SyslogTransport,
+    head: Option<AuditHead>,
+}
+
+#[derive(Debug)]
+enum SyslogTransport {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixDatagram, String),
+}
+
+impl SyslogAuditSink {
+    /// Send to the syslog server at `address` (host:port) over UDP if set, otherwise to the local syslog
+    /// daemon at `socket_path`
+    pub async fn connect(address: Option<&str>, socket_path: &str) -> Result<Self> {
+        let transport = match address {
+            Some(address) => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                socket
+                    .connect(address)
+                    .await
+                    .map_err(|err| eyre!("Failed to connect to syslog at {address}: {err}"))?;
+                SyslogTransport::Udp(socket)
+            }
+            #[cfg(unix)]
+            None => SyslogTransport::Unix(
+                tokio::net::UnixDatagram::unbound()?,
+                socket_path.to_string(),
+            ),
+            #[cfg(not(unix))]
+            None => return Err(eyre!("Syslog at {socket_path} is only supported on unix")),
+        };
+        Ok(Self {
+            transport,
+            head: None,
+        })
+    }
+
+    /// Keep the last record sent in the file at `path`, to continue the chain after a restart
+    pub fn with_head_path(mut self, path: &str) -> Self {
+        self.head = Some(AuditHead {
+            path: path.to_string(),
+        });
+        self
+    }
+}
+
+#[async_trait]
+impl AuditSink for SyslogAuditSink {
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        let message = format!(
+            "<{SYSLOG_PRIORITY}>1 {} - notary-server {} audit - {}",
+            record.finished_at.to_rfc3339(),
+            std::process::id(),
+            serde_json::to_string(record)?
+        );
+        match &self.transport {
+            SyslogTransport::Udp(socket) => socket.send(message.as_bytes()).await?,
+            #[cfg(unix)]
+            SyslogTransport::Unix(socket, path) => socket.send_to(message.as_bytes(), path).await?,
+        };
+        if let Some(head) = &self.head {
+            head.write(record).await?;
+        }
+        Ok(())
+    }
+
+    async fn last(&self) -> Result<Option<AuditRecord>> {
+        match &self.head {
+            Some(head) => head.read().await,
+            None => Ok(None),
+        }
+    }
+}
+
+/// Audit sink exporting the records as log records to an OpenTelemetry collector, using OTLP/HTTP with JSON
+/// encoding
+pub struct OtlpAuditSink {
+    /// URL of the logs endpoint, i.e. <endpoint>/v1/logs
+    url: String,
+    headers: HashMap<String, String>,
+    client: Client<HttpsConnector<HttpConnector>>,
+    head: Option<AuditHead>,
+}
+
+impl OtlpAuditSink {
+    pub fn new(endpoint: &str, headers: HashMap<String, String>) -> Self {
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Self {
+            url: format!("{}/v1/logs", endpoint.trim_end_matches('/')),
+            headers,
+            client: Client::builder().build(connector),
+            head: None,
+        }
+    }
+
+    /// Keep the last record exported in the file at `path`, to continue the chain after a restart
+    pub fn with_head_path(mut self, path: &str) -> Self {
+        self.head = Some(AuditHead {
+            path: path.to_string(),
+        });
+        self
+    }
+}
+
+#[async_trait]
+impl AuditSink for OtlpAuditSink {
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        let outcome = match record.outcome {
+            AuditOutcome::Success { .. } => "success",
+            AuditOutcome::Failure { .. } => "failure",
+        };
+        let body = json!({
+            "resourceLogs": [{
+                "resource": {
+                    "attributes": [
+                        { "key": "service.name", "value": { "stringValue": "notary-server" } }
+                    ]
+                },
+                "scopeLogs": [{
+                    "scope": { "name": "notary-server.audit" },
+                    "logRecords": [{
+                        "timeUnixNano": record.finished_at.timestamp_nanos_opt().unwrap_or_default().to_string(),
+                        "severityNumber": 9,
+                        "severityText": "INFO",
+                        "body": { "stringValue": serde_json::to_string(record)? },
+                        "attributes": [
+                            { "key": "session.id", "value": { "stringValue": record.session_id } },
+                            { "key": "audit.sequence", "value": { "intValue": record.sequence.to_string() } },
+                            { "key": "audit.outcome", "value": { "stringValue": outcome } }
+                        ]
+                    }]
+                }]
+            }]
+        });
+
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header(header::CONTENT_TYPE, "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let request = request.body(Body::from(body.to_string()))?;
+
+        let response = tokio::time::timeout(OTLP_REQUEST_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| eyre!("Request to OTLP collector timed out"))??;
+        ensure!(
+            response.status().is_success(),
+            "Request to OTLP collector failed with status {}",
+            response.status()
+        );
+        if let Some(head) = &self.head {
+            head.write(record).await?;
+        }
+        Ok(())
+    }
+
+    async fn last(&self) -> Result<Option<AuditRecord>> {
+        match &self.head {
+            Some(head) => head.read().await,
+            None => Ok(None),
+        }
+    }
+}
+
+impl fmt::Debug for OtlpAuditSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtlpAuditSink")
+            .field("url", &self.url)
+            .field("head", &self.head)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{keys::CurrentKey, signer::LocalSigner};
+    use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+    use tlsn_core::SignatureAlgorithm;
+
+    fn notary_keys() -> (Arc<NotaryKeys>, p256::PublicKey) {
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let public_key = p256::PublicKey::from(*signing_key.verifying_key());
+        let notary_keys = NotaryKeys::new(
+            CurrentKey {
+                signer: Arc::new(LocalSigner::new(signing_key)),
+                algorithm: SignatureAlgorithm::P256,
+                public_key: "notary-public-key".to_string(),
+                tee_quote: None,
+            },
+            None,
+            None,
+        )
+        .unwrap();
+        (Arc::new(notary_keys), public_key)
+    }
+
+    fn entry(session_id: &str, outcome: AuditOutcome) -> AuditEntry {
+        AuditEntry {
+            session_id: session_id.to_string(),
+            client_name: Some("test-name-0".to_string()),
+            created_at: Utc::now(),
+            started_at: Utc::now(),
+            max_sent_data: Some(1024),
+            max_recv_data: None,
+            outcome,
+        }
+    }
+
+    async fn read_records(path: &str) -> Vec<AuditRecord> {
+        fs::read_to_string(path)
+            .await
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_file_audit_log_is_chained() {
+        let path = std::env::temp_dir().join(format!("notary-audit-{}.log", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let (notary_keys, public_key) = notary_keys();
+
+        let log = AuditLog::new(
+            Arc::new(FileAuditSink::open(&path).await.unwrap()),
+            notary_keys.clone(),
+        )
+        .await
+        .unwrap();
+        log.record(entry(
+            "session-0",
+            AuditOutcome::Success {
+                sent_len: 100,
+                recv_len: 200,
+                attestation_id: Some("session-0".to_string()),
+            },
+        ))
+        .await
+        .unwrap();
+
+        // The chain continues after a restart
+        let log = AuditLog::new(
+            Arc::new(FileAuditSink::open(&path).await.unwrap()),
+            notary_keys,
+        )
+        .await
+        .unwrap();
+        log.record(entry(
+            "session-1",
+            AuditOutcome::Failure {
+                error: "Connection closed".to_string(),
+            },
+        ))
+        .await
+        .unwrap();
+
+        let mut records = read_records(&path).await;
+        assert_eq!(records.len(), 2);
+        assert_eq!(verify_audit_chain(&records), Ok(()));
+        // Every record is signed by the notary, which doesn't change its hash
+        for record in &records {
+            let signature = record.signature.as_ref().unwrap();
+            assert!(signature
+                .verify(&audit_message(&record.hash), public_key)
+                .is_ok());
+            assert!(signature
+                .verify(record.hash.as_bytes(), public_key)
+                .is_err());
+        }
+
+        // Tampering with a record breaks the chain
+        records[0].max_sent_data = Some(4096);
+        assert_eq!(verify_audit_chain(&records), Err(0));
+        // So does removing one
+        assert_eq!(verify_audit_chain(&read_records(&path).await[1..]), Err(0));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_syslog_audit_log_continues_from_head() {
+        let head_path =
+            std::env::temp_dir().join(format!("notary-audit-head-{}.json", std::process::id()));
+        let head_path = head_path.to_str().unwrap().to_string();
+        let (notary_keys, _) = notary_keys();
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap().to_string();
+
+        let mut records = Vec::new();
+        for session_id in ["session-0", "session-1"] {
+            // Each session is recorded by a newly started log, which only knows the chain from the head
+            let sink = SyslogAuditSink::connect(Some(&address), "/dev/log")
+                .await
+                .unwrap()
+                .with_head_path(&head_path);
+            let log = AuditLog::new(Arc::new(sink), notary_keys.clone())
+                .await
+                .unwrap();
+            log.record(entry(
+                session_id,
+                AuditOutcome::Failure {
+                    error: "Connection closed".to_string(),
+                },
+            ))
+            .await
+            .unwrap();
+
+            let mut buf = vec![0; 65536];
+            let len = server.recv(&mut buf).await.unwrap();
+            let message = String::from_utf8(buf[..len].to_vec()).unwrap();
+            let (_, record) = message.split_once(" audit - ").unwrap();
+            records.push(serde_json::from_str::<AuditRecord>(record).unwrap());
+        }
+
+        assert_eq!(records[1].sequence, 1);
+        assert_eq!(verify_audit_chain(&records), Ok(()));
+
+        std::fs::remove_file(head_path).unwrap();
+    }
+}
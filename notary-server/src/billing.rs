@@ -0,0 +1,311 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use eyre::{ensure, eyre, Result};
+use hyper::{client::HttpConnector, header, Body, Client, Method, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+use tlsn_core::{Signature, SignatureAlgorithm};
+use tlsn_verifier::tls::AsyncSigner;
+use tracing::{debug, error};
+
+use crate::{
+    domain::{
+        auth::AuthorizedClient,
+        notary::{NotaryGlobals, SessionStore},
+    },
+    keys::NotaryKeys,
+    NotaryServerError,
+};
+
+/// Timeout of requests to the billing webhook
+const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time for which the receipt of a session can be fetched after it completed
+const RECEIPT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Usage of a completed notarization session, which paid notary services bill their clients for
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUsage {
+    pub session_id: String,
+    /// Name of the API key or subject of the bearer token used to create the session, if authorization is enabled
+    pub client_name: Option<String>,
+    /// Bytes sent and received by the prover in the notarized TLS session
+    pub sent_len: usize,
+    pub recv_len: usize,
+    /// Time at which the prover connected to /notarize
+    pub started_at: DateTime<Utc>,
+    /// Time at which the session header was signed
+    pub finished_at: DateTime<Utc>,
+    /// Time spent in the MPC protocol with the prover in milliseconds, from its setup to the signature
+    pub mpc_duration_ms: u64,
+    /// Algorithm of the signature of the attestation
+    pub signature_algorithm: SignatureAlgorithm,
+}
+
+/// Prefix of the message signed for a usage receipt. The notary key also signs attestations, so the prefix
+/// keeps a receipt signature from being valid for any other message signed with it
+pub const RECEIPT_SIGNING_CONTEXT: &[u8] = b"tlsn-notary-server/usage-receipt/v1\n";
+
+/// Receipt of the usage of a session signed with the current notary key, which the prover can present to
+/// dispute a bill
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReceipt {
+    /// JSON encoding of the [SessionUsage], which is signed prefixed with [RECEIPT_SIGNING_CONTEXT]
+    pub usage: String,
+    pub signature: Signature,
+    /// Public key of the signature in PEM format
+    pub public_key: String,
+}
+
+/// Hook invoked with the usage of each completed session, e.g. to report it to a billing system
+#[async_trait]
+pub trait BillingHook: fmt::Debug + Send + Sync {
+    /// Failures are logged and don't affect the session, which is already completed
+    async fn on_session_completed(&self, usage: &SessionUsage) -> Result<()>;
+}
+
+/// Billing of the completed sessions, through the billing hook and the signed usage receipts
+#[derive(Debug)]
+pub struct Billing {
+    hook: Option<Arc<dyn BillingHook>>,
+    signed_receipts: bool,
+    /// Store of the receipts, which may be shared with other notary instances
+    store: Arc<dyn SessionStore>,
+}
+
+impl Billing {
+    pub fn new(
+        hook: Option<Arc<dyn BillingHook>>,
+        signed_receipts: bool,
+        store: Arc<dyn SessionStore>,
+    ) -> Self {
+        Self {
+            hook,
+            signed_receipts,
+            store,
+        }
+    }
+
+    /// Report the usage of a completed session to the hook, and sign its receipt if enabled
+    pub async fn record(&self, usage: SessionUsage, notary_keys: &NotaryKeys) {
+        let session_id = usage.session_id.clone();
+        if let Some(hook) = &self.hook {
+            if let Err(err) = hook.on_session_completed(&usage).await {
+                error!(
+                    ?session_id,
+                    "Failed to report session usage to billing hook: {err}"
+                );
+            }
+        }
+
+        if !self.signed_receipts {
+            return;
+        }
+        let receipt = match sign_receipt(&usage, notary_keys).await {
+            Ok(receipt) => receipt,
+            Err(err) => {
+                error!(?session_id, "Failed to sign usage receipt: {err}");
+                return;
+            }
+        };
+        match self
+            .store
+            .insert_receipt(&session_id, &receipt, RECEIPT_TTL)
+            .await
+        {
+            Ok(()) => debug!(?session_id, "Signed usage receipt"),
+            Err(err) => error!(?session_id, "Failed to store usage receipt: {err}"),
+        }
+    }
+
+    /// Returns the receipt of a session if signed receipts are enabled and it hasn't expired
+    pub async fn receipt(&self, session_id: &str) -> Result<Option<UsageReceipt>> {
+        if !self.signed_receipts {
+            return Ok(None);
+        }
+        self.store.receipt(session_id).await
+    }
+}
+
+async fn sign_receipt(usage: &SessionUsage, notary_keys: &NotaryKeys) -> Result<UsageReceipt> {
+    let key = notary_keys.current();
+    let usage = serde_json::to_string(usage)?;
+    let signature = key.signer.sign(&receipt_message(&usage)).await?;
+    Ok(UsageReceipt {
+        usage,
+        signature,
+        public_key: key.public_key,
+    })
+}
+
+/// Message signed for a usage receipt, i.e. the JSON encoding of the usage prefixed with
+/// [RECEIPT_SIGNING_CONTEXT]
+pub fn receipt_message(usage: &str) -> Vec<u8> {
+    [RECEIPT_SIGNING_CONTEXT, usage.as_bytes()].concat()
+}
+
+/// Handler to fetch the signed usage receipt of a completed session, which is only served to the client
+/// that created the session if authorization is enabled
+pub async fn get_receipt(
+    State(notary_globals): State<NotaryGlobals>,
+    client: Option<Extension<AuthorizedClient>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<UsageReceipt>, NotaryServerError> {
+    let Some(billing) = &notary_globals.billing else {
+        return Err(NotaryServerError::NotFound(
+            "Signed receipts are not enabled".to_string(),
+        ));
+    };
+    let receipt = billing
+        .receipt(&session_id)
+        .await?
+        .ok_or_else(|| NotaryServerError::NotFound("Receipt does not exist".to_string()))?;
+
+    if let Some(Extension(client)) = client {
+        let usage: SessionUsage = serde_json::from_str(&receipt.usage)
+            .map_err(|err| eyre!("Failed to parse usage receipt: {err}"))?;
+        if usage.client_name.as_ref() != Some(&client.name) {
+            return Err(NotaryServerError::NotFound(
+                "Receipt does not exist".to_string(),
+            ));
+        }
+    }
+    Ok(Json(receipt))
+}
+
+/// Billing hook posting the usage of each completed session as JSON to a webhook
+pub struct WebhookBillingHook {
+    url: String,
+    headers: HashMap<String, String>,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl WebhookBillingHook {
+    pub fn new(url: &str, headers: HashMap<String, String>) -> Self {
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Self {
+            url: url.to_string(),
+            headers,
+            client: Client::builder().build(connector),
+        }
+    }
+}
+
+#[async_trait]
+impl BillingHook for WebhookBillingHook {
+    async fn on_session_completed(&self, usage: &SessionUsage) -> Result<()> {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header(header::CONTENT_TYPE, "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let request = request.body(Body::from(serde_json::to_string(usage)?))?;
+
+        let response = tokio::time::timeout(WEBHOOK_REQUEST_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| eyre!("Request to billing webhook timed out"))??;
+        ensure!(
+            response.status().is_success(),
+            "Request to billing webhook failed with status {}",
+            response.status()
+        );
+        Ok(())
+    }
+}
+
+impl fmt::Debug for WebhookBillingHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookBillingHook")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{domain::notary::MemorySessionStore, keys::CurrentKey, signer::LocalSigner};
+    use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+    use std::sync::Mutex;
+
+    /// Billing hook keeping the usages it is called with
+    #[derive(Debug, Default)]
+    struct RecordingHook(Mutex<Vec<SessionUsage>>);
+
+    #[async_trait]
+    impl BillingHook for RecordingHook {
+        async fn on_session_completed(&self, usage: &SessionUsage) -> Result<()> {
+            self.0.lock().unwrap().push(usage.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_usage() {
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let public_key = p256::PublicKey::from(*signing_key.verifying_key());
+        let notary_keys = NotaryKeys::new(
+            CurrentKey {
+                signer: Arc::new(LocalSigner::new(signing_key)),
+                algorithm: SignatureAlgorithm::P256,
+                public_key: "notary-public-key".to_string(),
+                tee_quote: None,
+            },
+            None,
+            None,
+        )
+        .unwrap();
+        let hook = Arc::new(RecordingHook::default());
+        let billing = Billing::new(
+            Some(hook.clone()),
+            true,
+            Arc::new(MemorySessionStore::default()),
+        );
+        let usage = SessionUsage {
+            session_id: "test-session-id".to_string(),
+            client_name: Some("test-name-0".to_string()),
+            sent_len: 100,
+            recv_len: 1000,
+            started_at: Utc::now(),
+            finished_at: Utc::now(),
+            mpc_duration_ms: 5000,
+            signature_algorithm: SignatureAlgorithm::P256,
+        };
+
+        billing.record(usage.clone(), &notary_keys).await;
+        assert_eq!(*hook.0.lock().unwrap(), vec![usage.clone()]);
+
+        let receipt = billing.receipt("test-session-id").await.unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_str::<SessionUsage>(&receipt.usage).unwrap(),
+            usage
+        );
+        assert!(receipt
+            .signature
+            .verify(&receipt_message(&receipt.usage), public_key)
+            .is_ok());
+        // The signature is only valid for the domain separated message
+        assert!(receipt
+            .signature
+            .verify(receipt.usage.as_bytes(), public_key)
+            .is_err());
+        assert!(billing
+            .receipt("unknown-session-id")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
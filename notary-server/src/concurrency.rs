@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::ConcurrencyProperties;
+
+/// Seconds after which a prover refused as the server or its IP address is saturated can retry
+const SATURATED_RETRY_AFTER: u64 = 10;
+
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
+pub enum ConcurrencyError {
+    #[error("Limit of {limit} concurrent sessions per IP address reached")]
+    TooManySessionsFromAddress { limit: usize },
+    #[error("All {limit} notarization workers are busy and the queue is full")]
+    Saturated { limit: usize },
+    #[error("No notarization worker became free within {timeout} seconds")]
+    QueueTimeout { timeout: u64 },
+}
+
+impl ConcurrencyError {
+    /// Seconds after which the client can retry
+    pub fn retry_after(&self) -> u64 {
+        match self {
+            Self::TooManySessionsFromAddress { .. } | Self::Saturated { .. } => {
+                SATURATED_RETRY_AFTER
+            }
+            Self::QueueTimeout { timeout } => *timeout,
+        }
+    }
+}
+
+/// Bounded pool of workers for the MPC protocol of notarizations, which queues sessions while all workers
+/// are busy and sheds them once the queue is full, as well as capping the sessions from each IP address
+#[derive(Debug)]
+pub struct SessionLimiter {
+    config: ConcurrencyProperties,
+    /// Free workers, if the number of concurrent sessions is limited
+    workers: Option<Arc<Semaphore>>,
+    /// Sessions waiting for a free worker
+    queued: AtomicUsize,
+    /// Sessions running or queued from each IP address
+    addresses: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl SessionLimiter {
+    pub fn new(config: ConcurrencyProperties) -> Self {
+        Self {
+            workers: config
+                .max_concurrent_sessions
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            config,
+            queued: Default::default(),
+            addresses: Default::default(),
+        }
+    }
+
+    /// Wait for a free worker to run a session from the IP address, refusing it if the address has reached its
+    /// limit, the queue is full, or no worker frees up in time
+    pub async fn acquire(&self, address: IpAddr) -> Result<SessionPermit, ConcurrencyError> {
+        let address = self.reserve_address(address)?;
+        let Some(workers) = &self.workers else {
+            return Ok(SessionPermit {
+                _worker: None,
+                _address: address,
+            });
+        };
+
+        let worker = match workers.clone().try_acquire_owned() {
+            Ok(worker) => worker,
+            Err(_) => {
+                let _queued = self.enqueue()?;
+                let timeout = self.config.queue_timeout;
+                tokio::time::timeout(
+                    Duration::from_secs(timeout),
+                    workers.clone().acquire_owned(),
+                )
+                .await
+                .map_err(|_| ConcurrencyError::QueueTimeout { timeout })?
+                .expect("Worker semaphore is never closed")
+            }
+        };
+        Ok(SessionPermit {
+            _worker: Some(worker),
+            _address: address,
+        })
+    }
+
+    /// Number of sessions waiting for a free worker
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    fn reserve_address(&self, address: IpAddr) -> Result<AddressReservation, ConcurrencyError> {
+        let mut addresses = self.addresses.lock().unwrap();
+        let sessions = addresses.entry(address).or_default();
+        if let Some(limit) = self.config.max_sessions_per_ip {
+            if *sessions >= limit {
+                return Err(ConcurrencyError::TooManySessionsFromAddress { limit });
+            }
+        }
+        *sessions += 1;
+        Ok(AddressReservation {
+            address,
+            addresses: self.addresses.clone(),
+        })
+    }
+
+    fn enqueue(&self) -> Result<QueueSlot<'_>, ConcurrencyError> {
+        let queued = self.queued.fetch_add(1, Ordering::Relaxed);
+        let slot = QueueSlot(&self.queued);
+        if queued >= self.config.max_queued_sessions {
+            return Err(ConcurrencyError::Saturated {
+                limit: self.config.max_concurrent_sessions.unwrap_or_default(),
+            });
+        }
+        Ok(slot)
+    }
+}
+
+/// Permit to run the MPC protocol of a session, which frees the worker and the slot of its IP address when
+/// dropped
+#[derive(Debug)]
+pub struct SessionPermit {
+    _worker: Option<OwnedSemaphorePermit>,
+    _address: AddressReservation,
+}
+
+#[derive(Debug)]
+struct AddressReservation {
+    address: IpAddr,
+    addresses: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl Drop for AddressReservation {
+    fn drop(&mut self) {
+        let mut addresses = self.addresses.lock().unwrap();
+        if let Some(sessions) = addresses.get_mut(&self.address) {
+            *sessions -= 1;
+            if *sessions == 0 {
+                addresses.remove(&self.address);
+            }
+        }
+    }
+}
+
+/// Place in the queue, which is left when dropped, including if the prover disconnects while waiting
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn limiter(
+        max_concurrent_sessions: Option<usize>,
+        max_queued_sessions: usize,
+        max_sessions_per_ip: Option<usize>,
+    ) -> SessionLimiter {
+        SessionLimiter::new(ConcurrencyProperties {
+            max_concurrent_sessions,
+            max_queued_sessions,
+            queue_timeout: 1,
+            max_sessions_per_ip,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sessions_per_address() {
+        let limiter = limiter(None, 0, Some(2));
+        let address: IpAddr = "127.0.0.1".parse().unwrap();
+        let first = limiter.acquire(address).await.unwrap();
+        let _second = limiter.acquire(address).await.unwrap();
+        assert_eq!(
+            limiter.acquire(address).await.unwrap_err(),
+            ConcurrencyError::TooManySessionsFromAddress { limit: 2 }
+        );
+        // Other addresses are not affected
+        assert!(limiter.acquire("127.0.0.2".parse().unwrap()).await.is_ok());
+
+        drop(first);
+        assert!(limiter.acquire(address).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_queue_and_shed_sessions() {
+        let limiter = Arc::new(limiter(Some(1), 1, None));
+        let address: IpAddr = "127.0.0.1".parse().unwrap();
+        let running = limiter.acquire(address).await.unwrap();
+
+        // The second session waits for the worker, and the third is shed as the queue is full
+        let queued = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(address).await }
+        });
+        while limiter.queued() == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            limiter.acquire(address).await.unwrap_err(),
+            ConcurrencyError::Saturated { limit: 1 }
+        );
+
+        drop(running);
+        let queued = queued.await.unwrap().unwrap();
+        assert_eq!(limiter.queued(), 0);
+
+        // Sessions which wait for longer than the timeout are refused
+        assert_eq!(
+            limiter.acquire(address).await.unwrap_err(),
+            ConcurrencyError::QueueTimeout { timeout: 1 }
+        );
+        drop(queued);
+    }
+}
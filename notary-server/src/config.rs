@@ -1,4 +1,6 @@
 use serde::Deserialize;
+use std::collections::HashMap;
+use tlsn_core::{tee::TeePlatform, SignatureAlgorithm};
 
 #[derive(Clone, Debug, Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
@@ -15,6 +17,211 @@ pub struct NotaryServerProperties {
     pub logging: LoggingProperties,
     /// Setting for authorization
     pub authorization: AuthorizationProperties,
+    /// Setting for rate limiting of clients
+    #[serde(default)]
+    pub rate_limit: RateLimitProperties,
+    /// Setting for persisting the signed attestations
+    #[serde(default)]
+    pub storage: StorageProperties,
+    /// Setting for websocket connections with provers
+    #[serde(default)]
+    pub websocket: WebSocketProperties,
+    /// Setting for sharing the session data between multiple notary instances
+    #[serde(default)]
+    pub session_store: SessionStoreProperties,
+    /// Setting for attaching a hardware attestation quote to notarizations, if the notary runs in a TEE
+    #[serde(default)]
+    pub tee: Option<TeeProperties>,
+    /// Setting for the admin API, to rotate the signing key, reload policies and drain sessions at runtime
+    #[serde(default)]
+    pub admin: AdminProperties,
+    /// Setting for recording an audit log of the notarization sessions
+    #[serde(default)]
+    pub audit: Option<AuditSinkProperties>,
+    /// Setting for serving the gRPC API alongside the HTTP and TCP endpoints
+    #[serde(default)]
+    pub grpc: Option<GrpcProperties>,
+    /// Setting for reporting the usage of completed sessions to a billing system and signing receipts of it
+    #[serde(default)]
+    pub billing: BillingProperties,
+    /// Setting for limiting the sessions running the MPC protocol at once, and queueing or refusing the others
+    #[serde(default)]
+    pub concurrency: ConcurrencyProperties,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct ConcurrencyProperties {
+    /// Maximum number of sessions running the MPC protocol at once. If not set, the sessions are not limited
+    pub max_concurrent_sessions: Option<usize>,
+    /// Maximum number of sessions waiting for one of the running sessions to finish, beyond which new sessions
+    /// are refused with 503 Service Unavailable
+    pub max_queued_sessions: usize,
+    /// Time in seconds that a session waits for one of the running sessions to finish before it is refused
+    pub queue_timeout: u64,
+    /// Maximum number of sessions from the same IP address running or queued at once
+    pub max_sessions_per_ip: Option<usize>,
+}
+
+impl Default for ConcurrencyProperties {
+    fn default() -> Self {
+        Self {
+            max_concurrent_sessions: None,
+            max_queued_sessions: 16,
+            queue_timeout: 30,
+            max_sessions_per_ip: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct BillingProperties {
+    /// URL that the usage of each completed session is posted to as JSON
+    pub webhook_url: Option<String>,
+    /// Headers of the requests to the webhook, e.g. for authorization
+    #[serde(default)]
+    pub webhook_headers: HashMap<String, String>,
+    /// Whether provers can fetch a receipt of the usage of their sessions signed by the notary at /receipts
+    #[serde(default)]
+    pub signed_receipts: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum AuditSinkProperties {
+    /// File which the records are appended to as JSON lines
+    #[serde(rename_all = "kebab-case")]
+    File { path: String },
+    /// Syslog with the log audit facility, in the RFC 5424 format
+    #[serde(rename_all = "kebab-case")]
+    Syslog {
+        /// Address (host:port) of a syslog server to send the records to over UDP. If not set, the records are
+        /// sent to the local syslog daemon
+        address: Option<String>,
+        /// Socket of the local syslog daemon
+        #[serde(default = "default_syslog_socket_path")]
+        socket_path: String,
+        /// File keeping the last record sent, to continue the chain after a restart. If not set, a new chain
+        /// is started on each restart
+        head_path: Option<String>,
+    },
+    /// OpenTelemetry collector, which the records are exported to as log records with OTLP/HTTP
+    #[serde(rename_all = "kebab-case")]
+    Otlp {
+        /// Base URL of the collector, e.g. http://localhost:4318
+        endpoint: String,
+        /// Headers sent with each request, e.g. for authentication
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// File keeping the last record exported, to continue the chain after a restart. If not set, a new
+        /// chain is started on each restart
+        head_path: Option<String>,
+    },
+}
+
+fn default_syslog_socket_path() -> String {
+    "/dev/log".to_string()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct AdminProperties {
+    /// Switch to turn on or off the /admin endpoints
+    pub enabled: bool,
+    /// Environment variable holding the API key that requests to the /admin endpoints must be authorized with
+    pub api_key_env: String,
+    /// File path of the JSON file where the public keys of the notary and their validity windows are kept
+    /// across restarts. If not set, the keys rotated out are forgotten when the server restarts
+    pub key_history_path: Option<String>,
+}
+
+impl Default for AdminProperties {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key_env: "NOTARY_ADMIN_API_KEY".to_string(),
+            key_history_path: None,
+        }
+    }
+}
+
+/// Policies which can be reloaded through the admin API, leaving out any of them keeps it unchanged
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct PolicyProperties {
+    pub notarization: Option<NotarizationProperties>,
+    pub rate_limit: Option<RateLimitProperties>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GrpcProperties {
+    /// Port of the gRPC API, which is served on the host of the notary server with its TLS certificate
+    pub port: u16,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TeeProperties {
+    /// TEE that the notary runs in, i.e. sgx (with Gramine), sev-snp or nitro
+    pub platform: TeePlatform,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct SessionStoreProperties {
+    /// URL of the Redis server to store the session data, rate limit usage and receipts in, e.g. redis://127.0.0.1:6379.
+    /// If not set, they are kept in memory, so that a session can only be completed on the instance it was created on
+    pub redis_url: Option<String>,
+    /// Time in seconds after which sessions that haven't been connected to expire from the Redis store
+    pub session_ttl: u64,
+}
+
+impl Default for SessionStoreProperties {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            session_ttl: 600,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct WebSocketProperties {
+    /// Interval in seconds at which pings are sent to keep the connection alive
+    pub ping_interval: u64,
+    /// Time in seconds after which the connection is closed if nothing, not even a pong, has been received
+    pub idle_timeout: u64,
+}
+
+impl Default for WebSocketProperties {
+    fn default() -> Self {
+        Self {
+            ping_interval: 30,
+            idle_timeout: 90,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct StorageProperties {
+    /// Switch to turn on or off the storage of attestations
+    pub enabled: bool,
+    /// File path of the SQLite database storing the attestations, which is created if it doesn't exist
+    pub sqlite_path: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct RateLimitProperties {
+    /// Maximum number of sessions that each API key or IP address can start per minute
+    pub max_sessions_per_minute: Option<u32>,
+    /// Maximum transcript data in bytes that each API key or IP address can request per day (UTC),
+    /// i.e. the sum of the max sent and received data of its sessions
+    pub max_daily_data: Option<usize>,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -22,8 +229,29 @@ pub struct NotaryServerProperties {
 pub struct AuthorizationProperties {
     /// Switch to turn on or off auth middleware
     pub enabled: bool,
-    /// File path of the whitelist API key csv
+    /// File path of the whitelist API key csv, can be left empty if only OIDC is used
     pub whitelist_csv_path: String,
+    /// Setting for accepting OAuth2/OIDC bearer tokens in addition to whitelisted API keys
+    #[serde(default)]
+    pub oidc: Option<OidcProperties>,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct OidcProperties {
+    /// Issuer that tokens must be issued by, i.e. the `iss` claim
+    pub issuer: String,
+    /// Audience that tokens must be issued for, i.e. one of the `aud` claim
+    pub audience: String,
+    /// URL of the JWKS of the issuer. If not set, it is discovered from the OpenID configuration of the issuer
+    pub jwks_url: Option<String>,
+    /// Interval in seconds after which the JWKS is fetched again, to pick up rotated keys
+    #[serde(default = "default_jwks_refresh_interval")]
+    pub jwks_refresh_interval: u64,
+}
+
+fn default_jwks_refresh_interval() -> u64 {
+    3600
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -31,6 +259,13 @@ pub struct AuthorizationProperties {
 pub struct NotarizationProperties {
     /// Global limit for maximum transcript size in bytes
     pub max_transcript_size: usize,
+    /// Maximum number of threads each session garbles and evaluates the encryption and decryption circuits on,
+    /// which is negotiated down to the maximum of the prover. Defaults to that of the verifier if not set
+    #[serde(default)]
+    pub max_threads: Option<usize>,
+    /// Prefixes of the ids of the MPC channels whose data sent to the prover is compressed, e.g. "ot/"
+    #[serde(default)]
+    pub compressed_channels: Vec<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -43,6 +278,13 @@ pub struct ServerProperties {
     /// Static html response returned from API root endpoint "/". Default html response contains
     /// placeholder strings that will be replaced with actual values in server.rs, e.g. {version}, {public_key}
     pub html_info: String,
+    /// Time in seconds that ongoing notarizations are given to finish when the server is asked to shut down
+    #[serde(default = "default_shutdown_grace_period")]
+    pub shutdown_grace_period: u64,
+}
+
+fn default_shutdown_grace_period() -> u64 {
+    30
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -50,15 +292,102 @@ pub struct ServerProperties {
 pub struct TLSProperties {
     /// Flag to turn on/off TLS between prover and notary (should always be turned on unless TLS is handled by external setup e.g. reverse proxy, cloud)
     pub enabled: bool,
+    #[serde(default)]
     pub private_key_pem_path: String,
+    #[serde(default)]
     pub certificate_pem_path: String,
+    /// Setting for provisioning and renewing the certificate automatically via ACME, instead of loading it from the PEM files above
+    #[serde(default)]
+    pub acme: Option<AcmeProperties>,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct AcmeProperties {
+    /// Domain names of the notary server that the certificate is issued for
+    pub domains: Vec<String>,
+    /// Email addresses of the operator, which the ACME provider sends notices about the certificate to
+    #[serde(default)]
+    pub contacts: Vec<String>,
+    /// Directory where the ACME account key and the certificate are cached across restarts
+    pub cache_dir: String,
+    /// Directory url of the ACME provider
+    #[serde(default = "default_acme_directory_url")]
+    pub directory_url: String,
+}
+
+fn default_acme_directory_url() -> String {
+    // Let's Encrypt production directory
+    "https://acme-v02.api.letsencrypt.org/directory".to_string()
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct NotarySigningKeyProperties {
+    /// Not used if the key is held in a KMS or HSM
+    #[serde(default)]
     pub private_key_pem_path: String,
     pub public_key_pem_path: String,
+    /// KMS or HSM holding the signing key, instead of the private key PEM file above
+    #[serde(default)]
+    pub backend: Option<SignerBackendProperties>,
+    /// Keys of other signature algorithms than P-256, which provers can request instead of the key above
+    #[serde(default)]
+    pub additional_keys: Vec<AdditionalSigningKeyProperties>,
+}
+
+/// Key to rotate in through the admin API, in the format of either the `notary-key` field or one of its
+/// `additional-keys`, the latter replacing the key of its algorithm
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum RotatedKeyProperties {
+    Additional(AdditionalSigningKeyProperties),
+    Main(NotarySigningKeyProperties),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AdditionalSigningKeyProperties {
+    /// Either secp256k1 or ed25519
+    pub algorithm: SignatureAlgorithm,
+    pub private_key_pem_path: String,
+    pub public_key_pem_path: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum SignerBackendProperties {
+    /// ECC_NIST_P256 key in AWS KMS, requires the `aws-kms` feature
+    #[serde(rename_all = "kebab-case")]
+    AwsKms {
+        /// Id, ARN or alias of the key
+        key_id: String,
+        /// Region of the key, if not set it is loaded from the environment
+        region: Option<String>,
+    },
+    /// EC_SIGN_P256_SHA256 key in GCP Cloud KMS, requires the `gcp-kms` feature
+    #[serde(rename_all = "kebab-case")]
+    GcpKms {
+        /// Resource name of the key version, i.e. projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*
+        key_version: String,
+    },
+    /// P-256 key in an HSM accessed through its PKCS#11 module, e.g. a YubiHSM 2, requires the `pkcs11` feature
+    #[serde(rename_all = "kebab-case")]
+    Pkcs11 {
+        /// File path of the PKCS#11 module of the HSM, e.g. yubihsm_pkcs11.so
+        module_path: String,
+        /// Label of the token holding the key
+        token_label: String,
+        /// Label of the private key
+        key_label: String,
+        /// Environment variable holding the user PIN of the token
+        #[serde(default = "default_pkcs11_pin_env")]
+        pin_env: String,
+    },
+}
+
+fn default_pkcs11_pin_env() -> String {
+    "NOTARY_PKCS11_PIN".to_string()
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
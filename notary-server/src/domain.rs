@@ -1,8 +1,12 @@
+pub mod attestation;
 pub mod auth;
 pub mod cli;
 pub mod notary;
 
 use serde::{Deserialize, Serialize};
+use tlsn_core::tee::TeeQuote;
+
+use crate::keys::PublishedKey;
 
 /// Response object of the /info API
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,4 +20,19 @@ pub struct InfoResponse {
     pub git_commit_hash: String,
     /// Current git commit timestamp of notary-server
     pub git_commit_timestamp: String,
+    /// Hardware attestation quote binding the public key to the measurement of notary-server, if it runs in a TEE
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tee_quote: Option<TeeQuote>,
+}
+
+/// Response object of the /admin APIs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminStatusResponse {
+    /// Whether new sessions are refused
+    pub draining: bool,
+    /// Number of notarizations in progress
+    pub active_sessions: u64,
+    /// Current and previous public keys of the notary with their validity windows
+    pub keys: Vec<PublishedKey>,
 }
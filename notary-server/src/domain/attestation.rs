@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use tlsn_core::{SessionHeader, Signature};
+
+/// Record of a successful notarization, i.e. the session header signed by the notary
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationRecord {
+    /// Unique id of the attestation, which is the id of the notarization session
+    pub id: String,
+    /// Name of the API key or subject of the bearer token used to create the session, if authorization is enabled
+    pub client_name: Option<String>,
+    /// Time when the session was requested
+    pub created_at: DateTime<Utc>,
+    /// Time when the session header was signed
+    pub notarized_at: DateTime<Utc>,
+    /// Session header signed by the notary
+    pub session_header: SessionHeader,
+    /// Signature of the notary over the session header
+    pub signature: Signature,
+}
+
+/// Storage of the attestations signed by the notary, e.g. a SQLite or Postgres database
+pub trait AttestationStore: Debug + Send + Sync {
+    /// Persists an attestation
+    fn insert(&self, record: &AttestationRecord) -> Result<()>;
+    /// Returns the attestation with the given id, if it exists
+    fn get(&self, id: &str) -> Result<Option<AttestationRecord>>;
+    /// Checks that the storage is reachable, for the readiness check of the server
+    fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+}
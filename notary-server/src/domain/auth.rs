@@ -1,13 +1,60 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Debug, sync::Mutex};
 
 /// Structure of each whitelisted record of the API key whitelist for authorization purpose
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct AuthorizationWhitelistRecord {
+    /// Identifier of the key, recorded in the sessions created with it
     pub name: String,
     pub api_key: String,
     pub created_at: String,
+    /// Maximum data that can be sent by provers using this key, capped by the global limit
+    #[serde(default)]
+    pub max_sent_data: Option<usize>,
+    /// Maximum data that can be received by provers using this key, capped by the global limit
+    #[serde(default)]
+    pub max_recv_data: Option<usize>,
+    /// Maximum transcript data that can be requested in each calendar month (UTC) by provers using this key
+    #[serde(default)]
+    pub max_monthly_data: Option<usize>,
+}
+
+/// Client authorized by the auth middleware, passed to the handlers to be recorded in the session
+#[derive(Clone, Debug)]
+pub struct AuthorizedClient {
+    /// Name of the API key, or subject of the bearer token
+    pub name: String,
+    /// Maximum data that can be sent by the prover, if limited for the client
+    pub max_sent_data: Option<usize>,
+    /// Maximum data that can be received by the prover, if limited for the client
+    pub max_recv_data: Option<usize>,
+    /// Maximum transcript data that can be requested by the client per month, if limited for the client
+    pub max_monthly_data: Option<usize>,
+}
+
+impl From<AuthorizationWhitelistRecord> for AuthorizedClient {
+    fn from(record: AuthorizationWhitelistRecord) -> Self {
+        Self {
+            name: record.name,
+            max_sent_data: record.max_sent_data,
+            max_recv_data: record.max_recv_data,
+            max_monthly_data: record.max_monthly_data,
+        }
+    }
+}
+
+/// Source of the API keys which are allowed to request notarization, e.g. a whitelist file or a database
+pub trait ApiKeyStore: Debug + Send + Sync {
+    /// Returns the record of an API key, if it is allowed
+    fn get(&self, api_key: &str) -> Option<AuthorizationWhitelistRecord>;
+}
+
+/// Whitelist loaded from a csv file, which is reloaded when the file changes
+impl ApiKeyStore for Mutex<HashMap<String, AuthorizationWhitelistRecord>> {
+    fn get(&self, api_key: &str) -> Option<AuthorizationWhitelistRecord> {
+        self.lock().unwrap().get(api_key).cloned()
+    }
 }
 
 /// Convert whitelist data structure from vector to hashmap using api_key as the key to speed up lookup
@@ -1,12 +1,34 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{atomic::AtomicBool, Arc, RwLock},
+    time::Duration,
+};
 
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use p256::ecdsa::SigningKey;
+use eyre::Result;
+use futures::channel::mpsc;
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
-use tokio::sync::Mutex as AsyncMutex;
+use subtle::ConstantTimeEq;
 
-use crate::{config::NotarizationProperties, domain::auth::AuthorizationWhitelistRecord};
+use tlsn_core::SignatureAlgorithm;
+use tlsn_verifier::tls::Socket;
+
+use crate::{
+    audit::AuditLog,
+    billing::{Billing, UsageReceipt},
+    concurrency::SessionLimiter,
+    config::{
+        ConcurrencyProperties, NotarizationProperties, RateLimitProperties, WebSocketProperties,
+    },
+    domain::{attestation::AttestationStore, auth::ApiKeyStore},
+    keys::NotaryKeys,
+    metrics::Metrics,
+    oidc::OidcValidator,
+    rate_limit::RateLimiter,
+};
 
 /// Response object of the /session API
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +36,9 @@ use crate::{config::NotarizationProperties, domain::auth::AuthorizationWhitelist
 pub struct NotarizationSessionResponse {
     /// Unique session id that is generated by notary and shared to prover
     pub session_id: String,
+    /// Secret to reconnect to a resumable session with, which is only known to the prover
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumption_secret: Option<String>,
 }
 
 /// Request object of the /session API
@@ -25,6 +50,10 @@ pub struct NotarizationSessionRequest {
     pub max_sent_data: Option<usize>,
     /// Maximum data that can be received by the prover
     pub max_recv_data: Option<usize>,
+    /// Whether the connection is resumed if it drops, by connecting again with the same session id
+    pub resumable: Option<bool>,
+    /// Algorithm of the signature of the attestation, P-256 if not set
+    pub signature_algorithm: Option<SignatureAlgorithm>,
 }
 
 /// Request query of the /notarize API
@@ -33,6 +62,8 @@ pub struct NotarizationSessionRequest {
 pub struct NotarizationRequestQuery {
     /// Session id that is returned from /session API
     pub session_id: String,
+    /// Resumption secret that is returned from /session API, to reconnect to a resumable session
+    pub resumption_secret: Option<String>,
 }
 
 /// Types of client that the prover is using
@@ -45,35 +76,291 @@ pub enum ClientType {
 }
 
 /// Session configuration data to be stored in temporary storage
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SessionData {
     pub max_sent_data: Option<usize>,
     pub max_recv_data: Option<usize>,
+    pub resumable: bool,
+    /// Secret that the prover must present to reconnect to the session, if it is resumable
+    #[serde(default)]
+    pub resumption_secret: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Name of the API key or subject of the bearer token used to create the session, if authorization is enabled
+    pub client_name: Option<String>,
+    /// Algorithm of the signature requested by the prover
+    #[serde(default)]
+    pub signature_algorithm: SignatureAlgorithm,
+}
+
+/// Counter of the usage of a client in a window of time, e.g. its sessions in the current minute
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsageCounter {
+    /// Key of the counter, unique to the client and the window
+    pub key: String,
+    pub amount: u64,
+    /// Maximum value of the counter
+    pub limit: u64,
+    /// End of the window, after which the counter is dropped
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Temporary storage of the data of sessions which any notary instance may need: the configuration data
+/// between the /session and /notarize requests, the usage counted against the rate limits of the clients and
+/// the signed usage receipts. It can be shared by multiple notary instances so that each request can be
+/// served by a different one
+#[async_trait]
+pub trait SessionStore: fmt::Debug + Send + Sync {
+    /// Stores the configuration data of a new session
+    async fn insert(&self, session_id: &str, data: SessionData) -> Result<()>;
+    /// Removes and returns the configuration data of a session, as each session id can only be used once
+    async fn take(&self, session_id: &str) -> Result<Option<SessionData>>;
+    /// Adds the amount of every counter at once, unless one of them would exceed its limit, in which case
+    /// none is changed and the index of the first such counter is returned. Counters which expired as of
+    /// `now` start from 0
+    async fn add_usage(
+        &self,
+        counters: &[UsageCounter],
+        now: DateTime<Utc>,
+    ) -> Result<Option<usize>>;
+    /// Stores the signed usage receipt of a completed session, which can be fetched for `ttl`
+    async fn insert_receipt(
+        &self,
+        session_id: &str,
+        receipt: &UsageReceipt,
+        ttl: Duration,
+    ) -> Result<()>;
+    /// Returns the usage receipt of a session if it hasn't expired
+    async fn receipt(&self, session_id: &str) -> Result<Option<UsageReceipt>>;
+    /// Checks that the store is reachable, for the readiness check of the server
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Store kept in memory of a single notary instance
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<String, SessionData>>,
+    /// Usage counters with the time at which they expire
+    usage: Mutex<HashMap<String, (u64, DateTime<Utc>)>>,
+    /// Usage receipts with the time at which they expire
+    receipts: Mutex<HashMap<String, (DateTime<Utc>, UsageReceipt)>>,
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn insert(&self, session_id: &str, data: SessionData) -> Result<()> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), data);
+        Ok(())
+    }
+
+    async fn take(&self, session_id: &str) -> Result<Option<SessionData>> {
+        Ok(self.sessions.lock().unwrap().remove(session_id))
+    }
+
+    async fn add_usage(
+        &self,
+        counters: &[UsageCounter],
+        now: DateTime<Utc>,
+    ) -> Result<Option<usize>> {
+        let mut usage = self.usage.lock().unwrap();
+        // Forget the counters of past windows, so that clients which stopped sending requests don't accumulate
+        usage.retain(|_, (_, expires_at)| *expires_at > now);
+        let exceeded = counters.iter().position(|counter| {
+            let value = usage.get(&counter.key).map_or(0, |(value, _)| *value);
+            value.saturating_add(counter.amount) > counter.limit
+        });
+        if exceeded.is_none() {
+            for counter in counters {
+                let (value, _) = usage
+                    .entry(counter.key.clone())
+                    .or_insert((0, counter.expires_at));
+                *value = value.saturating_add(counter.amount);
+            }
+        }
+        Ok(exceeded)
+    }
+
+    async fn insert_receipt(
+        &self,
+        session_id: &str,
+        receipt: &UsageReceipt,
+        ttl: Duration,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::from_std(ttl)?;
+        let mut receipts = self.receipts.lock().unwrap();
+        receipts.retain(|_, (expires_at, _)| *expires_at > now);
+        receipts.insert(session_id.to_string(), (expires_at, receipt.clone()));
+        Ok(())
+    }
+
+    async fn receipt(&self, session_id: &str) -> Result<Option<UsageReceipt>> {
+        Ok(self
+            .receipts
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .filter(|(expires_at, _)| *expires_at > Utc::now())
+            .map(|(_, receipt)| receipt.clone()))
+    }
 }
 
 /// Global data that needs to be shared with the axum handlers
 #[derive(Clone, Debug)]
 pub struct NotaryGlobals {
-    pub notary_signing_key: SigningKey,
-    pub notarization_config: NotarizationProperties,
-    /// A temporary storage to store configuration data, mainly used for WebSocket client
-    pub store: Arc<AsyncMutex<HashMap<String, SessionData>>>,
+    /// Signing key of the session headers, with the TEE quote binding it and the keys it was rotated from
+    pub notary_keys: Arc<NotaryKeys>,
+    /// Notarization limits, which can be reloaded through the admin API
+    pub notarization_config: Arc<RwLock<NotarizationProperties>>,
+    /// Keepalive settings of websocket connections with provers
+    pub websocket_config: WebSocketProperties,
+    /// A temporary storage of the configuration data, usage and receipts of sessions, which may be shared with
+    /// other notary instances
+    pub store: Arc<dyn SessionStore>,
     /// Whitelist of API keys for authorization purpose
-    pub authorization_whitelist: Option<Arc<Mutex<HashMap<String, AuthorizationWhitelistRecord>>>>,
+    pub authorization_whitelist: Option<Arc<dyn ApiKeyStore>>,
+    /// Validator of OAuth2/OIDC bearer tokens for authorization purpose
+    pub oidc_validator: Option<Arc<OidcValidator>>,
+    /// Limits on the sessions and data of each client
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Limits on the sessions running the MPC protocol at once, in total and from each IP address
+    pub session_limiter: Arc<SessionLimiter>,
+    /// Storage of the attestations signed by the notary
+    pub attestation_store: Option<Arc<dyn AttestationStore>>,
+    /// Prometheus metrics of the notarizations
+    pub metrics: Arc<Metrics>,
+    /// Ongoing resumable sessions, to pass the connections of provers reconnecting to them
+    pub resumptions: Resumptions,
+    /// API key that requests to the admin API must be authorized with, if it is enabled
+    pub admin_api_key: Option<String>,
+    /// Whether new sessions are refused, so that the server can be stopped once the ongoing ones finish
+    pub draining: Arc<AtomicBool>,
+    /// Audit log of the notarization sessions, if it is enabled
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Billing hook and signed usage receipts of the completed sessions, if either is enabled
+    pub billing: Option<Arc<Billing>>,
 }
 
 impl NotaryGlobals {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        notary_signing_key: SigningKey,
+        notary_keys: Arc<NotaryKeys>,
         notarization_config: NotarizationProperties,
-        authorization_whitelist: Option<Arc<Mutex<HashMap<String, AuthorizationWhitelistRecord>>>>,
+        authorization_whitelist: Option<Arc<dyn ApiKeyStore>>,
+        oidc_validator: Option<Arc<OidcValidator>>,
+        rate_limit: RateLimitProperties,
+        concurrency: ConcurrencyProperties,
+        attestation_store: Option<Arc<dyn AttestationStore>>,
+        websocket_config: WebSocketProperties,
+        store: Arc<dyn SessionStore>,
+        admin_api_key: Option<String>,
+        audit_log: Option<Arc<AuditLog>>,
+        billing: Option<Arc<Billing>>,
     ) -> Self {
+        let rate_limiter = Arc::new(RateLimiter::new(rate_limit, store.clone()));
         Self {
-            notary_signing_key,
-            notarization_config,
-            store: Default::default(),
+            notary_keys,
+            notarization_config: Arc::new(RwLock::new(notarization_config)),
+            websocket_config,
+            store,
             authorization_whitelist,
+            oidc_validator,
+            rate_limiter,
+            session_limiter: Arc::new(SessionLimiter::new(concurrency)),
+            attestation_store,
+            metrics: Default::default(),
+            resumptions: Default::default(),
+            admin_api_key,
+            draining: Default::default(),
+            audit_log,
+            billing,
         }
     }
 }
+
+/// Channels to pass the connections of provers reconnecting to resumable sessions, keyed by session id. The
+/// session id is logged and passed in URLs, so reconnecting also requires the resumption secret of the session
+#[derive(Clone, Default)]
+#[allow(clippy::type_complexity)]
+pub struct Resumptions(Arc<Mutex<HashMap<String, (String, mpsc::Sender<Box<dyn Socket>>)>>>);
+
+impl Resumptions {
+    /// Registers an ongoing resumable session, returning the receiver of reconnections to it
+    pub fn register(&self, session_id: String, secret: String) -> mpsc::Receiver<Box<dyn Socket>> {
+        let (sender, receiver) = mpsc::channel(1);
+        self.0.lock().unwrap().insert(session_id, (secret, sender));
+        receiver
+    }
+
+    /// Returns the channel to pass a reconnection to the session, if it is ongoing and the secret is that of
+    /// the session
+    pub fn get(
+        &self,
+        session_id: &str,
+        secret: Option<&str>,
+    ) -> Option<mpsc::Sender<Box<dyn Socket>>> {
+        let secret = secret?;
+        let sessions = self.0.lock().unwrap();
+        let (expected, sender) = sessions.get(session_id)?;
+        bool::from(expected.as_bytes().ct_eq(secret.as_bytes())).then(|| sender.clone())
+    }
+
+    /// Removes a session once it has finished
+    pub fn remove(&self, session_id: &str) {
+        self.0.lock().unwrap().remove(session_id);
+    }
+}
+
+impl fmt::Debug for Resumptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resumptions")
+            .field("sessions", &self.0.lock().unwrap().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_can_only_be_taken_once() {
+        let store = MemorySessionStore::default();
+        let data = SessionData {
+            max_sent_data: Some(100),
+            max_recv_data: None,
+            resumable: false,
+            resumption_secret: None,
+            created_at: Utc::now(),
+            client_name: None,
+            signature_algorithm: SignatureAlgorithm::P256,
+        };
+
+        store.insert("test-session-id", data).await.unwrap();
+        let taken = store.take("test-session-id").await.unwrap().unwrap();
+        assert_eq!(taken.max_sent_data, Some(100));
+        assert!(store.take("test-session-id").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resumption_requires_secret() {
+        let resumptions = Resumptions::default();
+        let _receiver = resumptions.register("test-session-id".to_string(), "secret".to_string());
+
+        assert!(resumptions.get("test-session-id", None).is_none());
+        assert!(resumptions.get("test-session-id", Some("wrong")).is_none());
+        assert!(resumptions.get("test-session-id", Some("secre")).is_none());
+        assert!(resumptions
+            .get("other-session-id", Some("secret"))
+            .is_none());
+        assert!(resumptions.get("test-session-id", Some("secret")).is_some());
+
+        resumptions.remove("test-session-id");
+        assert!(resumptions.get("test-session-id", Some("secret")).is_none());
+    }
+}
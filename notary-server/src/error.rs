@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
 };
 use eyre::Report;
@@ -7,6 +7,8 @@ use std::error::Error;
 
 use tlsn_verifier::tls::{VerifierConfigBuilderError, VerifierError};
 
+use crate::{concurrency::ConcurrencyError, rate_limit::RateLimitError};
+
 #[derive(Debug, thiserror::Error)]
 pub enum NotaryServerError {
     #[error(transparent)]
@@ -19,6 +21,14 @@ pub enum NotaryServerError {
     BadProverRequest(String),
     #[error("Unauthorized request from prover: {0}")]
     UnauthorizedProverRequest(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Too many requests from prover: {0}")]
+    TooManyProverRequests(#[from] RateLimitError),
+    #[error("Notary server is unavailable: {0}")]
+    Unavailable(String),
+    #[error("Notary server is overloaded: {0}")]
+    Overloaded(#[from] ConcurrencyError),
 }
 
 impl From<VerifierError> for NotaryServerError {
@@ -45,6 +55,36 @@ impl IntoResponse for NotaryServerError {
                 unauthorized_request_error.to_string(),
             )
                 .into_response(),
+            not_found_error @ NotaryServerError::NotFound(_) => {
+                (StatusCode::NOT_FOUND, not_found_error.to_string()).into_response()
+            }
+            NotaryServerError::TooManyProverRequests(err) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                // Tell the prover when it can retry
+                [(header::RETRY_AFTER, err.retry_after().to_string())],
+                NotaryServerError::TooManyProverRequests(err).to_string(),
+            )
+                .into_response(),
+            unavailable_error @ NotaryServerError::Unavailable(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                unavailable_error.to_string(),
+            )
+                .into_response(),
+            NotaryServerError::Overloaded(err) => {
+                // Sessions from an IP address over its limit are throttled, the others are shed as the server is saturated
+                let status = match err {
+                    ConcurrencyError::TooManySessionsFromAddress { .. } => {
+                        StatusCode::TOO_MANY_REQUESTS
+                    }
+                    _ => StatusCode::SERVICE_UNAVAILABLE,
+                };
+                (
+                    status,
+                    [(header::RETRY_AFTER, err.retry_after().to_string())],
+                    NotaryServerError::Overloaded(err).to_string(),
+                )
+                    .into_response()
+            }
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Something wrong happened.",
@@ -53,3 +93,50 @@ impl IntoResponse for NotaryServerError {
         }
     }
 }
+
+/// Trait implementation to convert this error into the status of a gRPC call
+#[cfg(feature = "grpc")]
+impl From<NotaryServerError> for tonic::Status {
+    fn from(error: NotaryServerError) -> Self {
+        match error {
+            bad_request_error @ NotaryServerError::BadProverRequest(_) => {
+                tonic::Status::invalid_argument(bad_request_error.to_string())
+            }
+            unauthorized_request_error @ NotaryServerError::UnauthorizedProverRequest(_) => {
+                tonic::Status::unauthenticated(unauthorized_request_error.to_string())
+            }
+            not_found_error @ NotaryServerError::NotFound(_) => {
+                tonic::Status::not_found(not_found_error.to_string())
+            }
+            NotaryServerError::TooManyProverRequests(err) => {
+                let retry_after = err.retry_after().to_string();
+                let mut status = tonic::Status::resource_exhausted(
+                    NotaryServerError::TooManyProverRequests(err).to_string(),
+                );
+                // Tell the prover when it can retry
+                if let Ok(retry_after) = retry_after.parse() {
+                    status.metadata_mut().insert("retry-after", retry_after);
+                }
+                status
+            }
+            unavailable_error @ NotaryServerError::Unavailable(_) => {
+                tonic::Status::unavailable(unavailable_error.to_string())
+            }
+            NotaryServerError::Overloaded(err) => {
+                let retry_after = err.retry_after().to_string();
+                let message = NotaryServerError::Overloaded(err.clone()).to_string();
+                let mut status = match err {
+                    ConcurrencyError::TooManySessionsFromAddress { .. } => {
+                        tonic::Status::resource_exhausted(message)
+                    }
+                    _ => tonic::Status::unavailable(message),
+                };
+                if let Ok(retry_after) = retry_after.parse() {
+                    status.metadata_mut().insert("retry-after", retry_after);
+                }
+                status
+            }
+            _ => tonic::Status::internal("Something wrong happened."),
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use chrono::{SecondsFormat, Utc};
+use eyre::eyre;
+use futures::{channel::mpsc, SinkExt, Stream};
+use std::{
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+};
+use tlsn_core::SignatureAlgorithm;
+use tlsn_verifier::tls::Socket;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, DuplexStream},
+    sync::oneshot,
+    task::JoinHandle,
+};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tonic::{
+    transport::{server::TcpIncoming, Identity, Server, ServerTlsConfig},
+    Request, Response, Status, Streaming,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    concurrency::SessionPermit,
+    config::{GrpcProperties, NotaryServerProperties},
+    domain::{
+        auth::AuthorizedClient,
+        notary::{NotaryGlobals, SessionData},
+    },
+    keys::CurrentKey,
+    middleware::authorize_client,
+    service::{
+        configure_session, fetch_attestation, finish_session, notary_service, resume_session,
+        start_session, SessionStart,
+    },
+    NotaryServerError,
+};
+
+/// Types and services generated from the protobuf schema in proto/notary.proto
+pub mod proto {
+    tonic::include_proto!("tlsn.notary.v1");
+}
+
+use proto::{
+    notarize_request,
+    notary_server::{Notary, NotaryServer},
+    Attestation, ConfigureSessionRequest, ConfigureSessionResponse, GetAttestationRequest,
+    NotarizeRequest, NotarizeResponse,
+};
+
+/// Size of the buffers between the gRPC stream and the notarization, which is also the maximum size
+/// of the messages sent to the prover
+const BUFFER_SIZE: usize = 1 << 16;
+
+/// Number of messages to the prover which are buffered before the notarization waits for them to be sent
+const OUTBOUND_MESSAGES: usize = 16;
+
+/// gRPC API of the notary, which shares the sessions, limits and authorization of the HTTP endpoints
+pub struct GrpcNotaryService {
+    notary_globals: NotaryGlobals,
+}
+
+impl GrpcNotaryService {
+    /// Authorize the client by the `authorization` metadata of the call, same as the authorization header
+    async fn authorize<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<Option<AuthorizedClient>, NotaryServerError> {
+        let auth_header = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        authorize_client(&self.notary_globals, auth_header).await
+    }
+}
+
+#[tonic::async_trait]
+impl Notary for GrpcNotaryService {
+    async fn configure_session(
+        &self,
+        request: Request<ConfigureSessionRequest>,
+    ) -> Result<Response<ConfigureSessionResponse>, Status> {
+        let client = self.authorize(&request).await?;
+        let address = request
+            .remote_addr()
+            .map(|address| address.ip())
+            .ok_or_else(|| Status::internal("Address of the prover is unknown"))?;
+        let request = request.into_inner();
+        info!(
+            ?request,
+            client_name = ?client.as_ref().map(|client| &client.name),
+            "Received gRPC request for configuring a notarization session"
+        );
+
+        let (session_id, resumption_secret) = configure_session(
+            &self.notary_globals,
+            client.as_ref(),
+            address,
+            request.max_sent_data.map(|size| size as usize),
+            request.max_recv_data.map(|size| size as usize),
+            request.resumable,
+            signature_algorithm(request.signature_algorithm()),
+        )
+        .await?;
+        Ok(Response::new(ConfigureSessionResponse {
+            session_id,
+            resumption_secret,
+        }))
+    }
+
+    type NotarizeStream = Pin<Box<dyn Stream<Item = Result<NotarizeResponse, Status>> + Send>>;
+
+    async fn notarize(
+        &self,
+        request: Request<Streaming<NotarizeRequest>>,
+    ) -> Result<Response<Self::NotarizeStream>, Status> {
+        info!("Received gRPC notarization request");
+        let address = request
+            .remote_addr()
+            .map(|address| address.ip())
+            .ok_or_else(|| Status::internal("Address of the prover is unknown"))?;
+        // Same as the /notarize endpoint, the notarization is not authorized as the session id
+        // generated for the authorized client is short-lived
+        let mut inbound = request.into_inner();
+        let Some(NotarizeRequest {
+            message: Some(notarize_request::Message::SessionId(session_id)),
+            resumption_secret,
+        }) = inbound.message().await?
+        else {
+            return Err(Status::invalid_argument(
+                "First message of the notarization should carry the session id",
+            ));
+        };
+
+        let start = start_session(
+            &self.notary_globals,
+            &session_id,
+            resumption_secret.as_deref(),
+            address,
+        )
+        .await?;
+        let (stream, outbound) = grpc_stream(inbound);
+        let notary_globals = self.notary_globals.clone();
+        match start {
+            SessionStart::New {
+                session_data,
+                key,
+                resumption,
+                permit,
+            } => {
+                tokio::spawn(grpc_notarize(
+                    stream,
+                    notary_globals,
+                    session_id,
+                    session_data,
+                    key,
+                    resumption,
+                    permit,
+                ));
+            }
+            // Pass the stream to the ongoing session if the prover is reconnecting to it
+            SessionStart::Resume(sender) => {
+                tokio::spawn(resume_session(
+                    Box::new(stream.compat()),
+                    sender,
+                    session_id,
+                ));
+            }
+        }
+        Ok(Response::new(Box::pin(outbound)))
+    }
+
+    async fn get_attestation(
+        &self,
+        request: Request<GetAttestationRequest>,
+    ) -> Result<Response<Attestation>, Status> {
+        let client = self.authorize(&request).await?;
+        let record = fetch_attestation(
+            &self.notary_globals,
+            client.as_ref(),
+            request.into_inner().id,
+        )
+        .await?;
+        let encode = |value: serde_json::Result<String>| {
+            value.map_err(|err| NotaryServerError::Unexpected(err.into()))
+        };
+        Ok(Response::new(Attestation {
+            id: record.id,
+            client_name: record.client_name,
+            created_at: record
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            notarized_at: record
+                .notarized_at
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            session_header: encode(serde_json::to_string(&record.session_header))?,
+            signature: encode(serde_json::to_string(&record.signature))?,
+        }))
+    }
+}
+
+/// Algorithm of the signature requested in the protobuf message, P-256 being the default if unspecified
+fn signature_algorithm(algorithm: proto::SignatureAlgorithm) -> Option<SignatureAlgorithm> {
+    match algorithm {
+        proto::SignatureAlgorithm::Unspecified => None,
+        proto::SignatureAlgorithm::P256 => Some(SignatureAlgorithm::P256),
+        proto::SignatureAlgorithm::Secp256k1 => Some(SignatureAlgorithm::Secp256k1),
+        proto::SignatureAlgorithm::Ed25519 => Some(SignatureAlgorithm::Ed25519),
+    }
+}
+
+/// Perform notarization using the bridged gRPC stream
+async fn grpc_notarize(
+    stream: DuplexStream,
+    notary_globals: NotaryGlobals,
+    session_id: String,
+    session_data: SessionData,
+    key: CurrentKey,
+    resumption: Option<mpsc::Receiver<Box<dyn Socket>>>,
+    permit: SessionPermit,
+) {
+    debug!(?session_id, "Started gRPC notarization stream");
+    let started_at = Utc::now();
+    // The policy may be reloaded during the session, which keeps the one it started with
+    let notarization = notary_globals.notarization_config.read().unwrap().clone();
+    let result = notary_service(
+        stream,
+        key.signer.as_ref(),
+        &session_id,
+        session_data.max_sent_data,
+        session_data.max_recv_data,
+        resumption,
+        &notary_globals.metrics,
+        key.tee_quote.as_ref(),
+        &notarization,
+    )
+    .await;
+    // Free the worker for queued sessions, as the MPC protocol is over
+    drop(permit);
+    match &result {
+        Ok(_) => info!(?session_id, "Successful notarization using grpc!"),
+        Err(err) => error!(?session_id, "Failed notarization using grpc: {err}"),
+    }
+    finish_session(
+        &notary_globals,
+        &session_id,
+        &session_data,
+        started_at,
+        result,
+    )
+    .await;
+
+    notary_globals.resumptions.remove(&session_id);
+}
+
+/// Bridge the messages of a gRPC notarization to a byte stream for the notarization, returning the
+/// stream of messages to the prover
+fn grpc_stream(
+    inbound: Streaming<NotarizeRequest>,
+) -> (
+    DuplexStream,
+    mpsc::Receiver<Result<NotarizeResponse, Status>>,
+) {
+    let (stream, bridge) = tokio::io::duplex(BUFFER_SIZE);
+    let (sender, outbound) = mpsc::channel(OUTBOUND_MESSAGES);
+    tokio::spawn(bridge_grpc(inbound, sender, bridge));
+    (stream, outbound)
+}
+
+async fn bridge_grpc(
+    mut inbound: Streaming<NotarizeRequest>,
+    mut sender: mpsc::Sender<Result<NotarizeResponse, Status>>,
+    bridge: DuplexStream,
+) {
+    let (mut reader, mut writer) = tokio::io::split(bridge);
+
+    // Both directions are polled concurrently, so that neither blocks the other when buffers are full
+    let inbound = async {
+        loop {
+            match inbound.message().await {
+                Ok(Some(NotarizeRequest {
+                    message: Some(notarize_request::Message::Data(data)),
+                    ..
+                })) => {
+                    if writer.write_all(&data).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(Some(_)) => {
+                    warn!("Received gRPC message without data after the session id");
+                    break;
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    debug!("gRPC notarization stream failed: {err}");
+                    break;
+                }
+            }
+        }
+    };
+
+    let outbound = async {
+        let mut buf = vec![0u8; BUFFER_SIZE];
+        loop {
+            match reader.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let response = NotarizeResponse {
+                        data: buf[..n].to_vec(),
+                    };
+                    if sender.send(Ok(response)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    };
+
+    // Once either direction ends the bridge is dropped, which ends the notarization stream
+    tokio::select! {
+        _ = inbound => {}
+        _ = outbound => {}
+    }
+}
+
+/// gRPC server running alongside the TCP server
+pub struct GrpcServer {
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl GrpcServer {
+    /// Start serving the gRPC API on its own port, with the TLS certificate of the TCP server if TLS is enabled
+    pub async fn start(
+        config: &NotaryServerProperties,
+        grpc: &GrpcProperties,
+        notary_globals: NotaryGlobals,
+    ) -> Result<Self, NotaryServerError> {
+        let mut builder = Server::builder();
+        if config.tls.enabled {
+            if config.tls.acme.is_some() {
+                return Err(eyre!(
+                    "gRPC API does not support TLS certificates provisioned via ACME"
+                )
+                .into());
+            }
+            let certificate = tokio::fs::read(&config.tls.certificate_pem_path)
+                .await
+                .map_err(|err| eyre!("Failed to read tls certificate for gRPC API: {err}"))?;
+            let private_key = tokio::fs::read(&config.tls.private_key_pem_path)
+                .await
+                .map_err(|err| eyre!("Failed to read tls private key for gRPC API: {err}"))?;
+            builder = builder
+                .tls_config(
+                    ServerTlsConfig::new().identity(Identity::from_pem(certificate, private_key)),
+                )
+                .map_err(|err| eyre!("Failed to instantiate gRPC tls config: {err}"))?;
+        }
+
+        let grpc_address = SocketAddr::new(
+            IpAddr::V4(config.server.host.parse().map_err(|err| {
+                eyre!("Failed to parse notary host address from server config: {err}")
+            })?),
+            grpc.port,
+        );
+        let incoming = TcpIncoming::new(grpc_address, true, None)
+            .map_err(|err| eyre!("Failed to bind gRPC address to tcp listener: {err}"))?;
+        info!("Listening for gRPC traffic at {}", grpc_address);
+
+        let router = builder.add_service(NotaryServer::new(GrpcNotaryService { notary_globals }));
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let shutdown = async {
+                let _ = shutdown_rx.await;
+            };
+            if let Err(err) = router
+                .serve_with_incoming_shutdown(incoming, shutdown)
+                .await
+            {
+                error!("gRPC server failed: {err}");
+            }
+        });
+        Ok(Self { shutdown, handle })
+    }
+
+    /// Stop accepting gRPC calls and wait for the ongoing ones to finish
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.handle.await;
+    }
+}
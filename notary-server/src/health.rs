@@ -0,0 +1,167 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::{sync::atomic::Ordering, time::Duration};
+use tlsn_verifier::tls::AsyncSigner;
+
+use crate::domain::notary::NotaryGlobals;
+
+/// Time after which a dependency which hasn't responded is considered unavailable
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Message signed to check that the signer is available
+const SIGNER_CHECK_MESSAGE: &[u8] = b"tlsn notary readiness check";
+
+/// Response object of the /healthz and /readyz APIs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    /// Whether all the checks passed
+    pub healthy: bool,
+    pub checks: Vec<HealthCheck>,
+}
+
+/// Result of the check of a dependency of the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub name: String,
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    fn new(name: &str, result: Result<()>) -> Self {
+        Self {
+            name: name.to_string(),
+            healthy: result.is_ok(),
+            error: result.err().map(|err| err.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for HealthResponse {
+    fn into_response(self) -> axum::response::Response {
+        let status = if self.healthy {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+/// Handler of the liveness check, which only tells that the server is able to serve requests
+pub async fn get_liveness() -> HealthResponse {
+    HealthResponse {
+        healthy: true,
+        checks: Vec::new(),
+    }
+}
+
+/// Handler of the readiness check, which tells whether the server can take new sessions, i.e. it is not
+/// draining and the signer and the storages that it depends on are available
+pub async fn get_readiness(State(notary_globals): State<NotaryGlobals>) -> HealthResponse {
+    let (signer, session_store, attestation_store) = tokio::join!(
+        with_timeout(check_signer(&notary_globals)),
+        with_timeout(notary_globals.store.ping()),
+        with_timeout(check_attestation_store(&notary_globals)),
+    );
+    let draining = if notary_globals.draining.load(Ordering::Relaxed) {
+        Err(eyre!("Server is draining sessions"))
+    } else {
+        Ok(())
+    };
+
+    let checks = vec![
+        HealthCheck::new("signer", signer),
+        HealthCheck::new("sessionStore", session_store),
+        HealthCheck::new("attestationStore", attestation_store),
+        HealthCheck::new("draining", draining),
+    ];
+    HealthResponse {
+        healthy: checks.iter().all(|check| check.healthy),
+        checks,
+    }
+}
+
+/// Sign a message with the current key, e.g. to check that the KMS or HSM holding it is reachable
+async fn check_signer(notary_globals: &NotaryGlobals) -> Result<()> {
+    let key = notary_globals.notary_keys.current();
+    key.signer
+        .sign(SIGNER_CHECK_MESSAGE)
+        .await
+        .map_err(|err| eyre!("Failed to sign: {err}"))?;
+    Ok(())
+}
+
+async fn check_attestation_store(notary_globals: &NotaryGlobals) -> Result<()> {
+    let Some(store) = notary_globals.attestation_store.clone() else {
+        return Ok(());
+    };
+    // Storage backends may block, e.g. on disk io
+    tokio::task::spawn_blocking(move || store.ping()).await?
+}
+
+async fn with_timeout(check: impl std::future::Future<Output = Result<()>>) -> Result<()> {
+    tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .map_err(|_| eyre!("Timed out after {CHECK_TIMEOUT:?}"))?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        domain::notary::MemorySessionStore,
+        keys::{CurrentKey, NotaryKeys},
+        signer::LocalSigner,
+    };
+    use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+    use std::sync::Arc;
+    use tlsn_core::SignatureAlgorithm;
+
+    fn notary_globals() -> NotaryGlobals {
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let key = CurrentKey {
+            signer: Arc::new(LocalSigner::new(signing_key)),
+            algorithm: SignatureAlgorithm::P256,
+            public_key: std::fs::read_to_string("./fixture/notary/notary.pub").unwrap(),
+            tee_quote: None,
+        };
+        NotaryGlobals::new(
+            Arc::new(NotaryKeys::new(key, None, None).unwrap()),
+            Default::default(),
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            None,
+            Default::default(),
+            Arc::new(MemorySessionStore::default()),
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_readiness() {
+        let notary_globals = notary_globals();
+        let response = get_readiness(State(notary_globals.clone())).await;
+        assert!(response.healthy);
+        assert!(response.checks.iter().all(|check| check.healthy));
+
+        // Draining servers are not ready to take new sessions
+        notary_globals.draining.store(true, Ordering::Relaxed);
+        let response = get_readiness(State(notary_globals)).await;
+        assert!(!response.healthy);
+        let draining = response
+            .checks
+            .iter()
+            .find(|check| check.name == "draining")
+            .unwrap();
+        assert!(!draining.healthy);
+    }
+}
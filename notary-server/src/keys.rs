@@ -0,0 +1,324 @@
+use chrono::{DateTime, Utc};
+use eyre::{ensure, eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+};
+use tlsn_core::{
+    tee::{TeePlatform, TeeQuote},
+    NotaryPublicKey, SignatureAlgorithm,
+};
+
+use crate::signer::NotarySigner;
+
+/// Public key of the notary with the period in which it signed session headers, published at /keys so that
+/// verifiers can check attestations signed before a key rotation
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedKey {
+    /// Algorithm of the key, keys restored from a history written before it was recorded are P-256
+    #[serde(default)]
+    pub algorithm: SignatureAlgorithm,
+    /// Public key in PEM format
+    pub public_key: String,
+    pub valid_from: DateTime<Utc>,
+    /// Time at which the key was rotated out, not set for the current key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+/// Key currently used to sign session headers
+#[derive(Clone, Debug)]
+pub struct CurrentKey {
+    pub signer: Arc<dyn NotarySigner>,
+    /// Algorithm of the signatures produced by the signer
+    pub algorithm: SignatureAlgorithm,
+    /// Public key in PEM format
+    pub public_key: String,
+    /// Hardware attestation quote binding the public key, if the notary runs in a TEE
+    pub tee_quote: Option<TeeQuote>,
+}
+
+/// Signing keys of the notary, one per signature algorithm, which can be rotated without restarting the
+/// server, and the public keys that they were rotated from
+#[derive(Debug)]
+pub struct NotaryKeys {
+    /// Keys by algorithm, the P-256 key is always set
+    current: RwLock<HashMap<SignatureAlgorithm, CurrentKey>>,
+    /// Published keys ordered by validity, the last one of each algorithm being its current key
+    published: Mutex<Vec<PublishedKey>>,
+    /// File where the published keys are kept across restarts
+    history_path: Option<String>,
+    /// TEE that the notary runs in, to generate a quote for each new key
+    tee_platform: Option<TeePlatform>,
+}
+
+impl NotaryKeys {
+    /// Start with the given P-256 key, restoring the keys it was rotated from out of `history_path` if set
+    pub fn new(
+        current: CurrentKey,
+        history_path: Option<String>,
+        tee_platform: Option<TeePlatform>,
+    ) -> Result<Self> {
+        ensure!(
+            current.algorithm == SignatureAlgorithm::P256,
+            "The main notary key must be P-256"
+        );
+        let published = match &history_path {
+            Some(path) if Path::new(path).exists() => {
+                serde_json::from_str(&fs::read_to_string(path)?)
+                    .map_err(|err| eyre!("Failed to parse notary key history at {path}: {err}"))?
+            }
+            _ => Vec::new(),
+        };
+        let keys = Self {
+            current: RwLock::new(HashMap::new()),
+            published: Mutex::new(published),
+            history_path,
+            tee_platform,
+        };
+        keys.load(current)?;
+        Ok(keys)
+    }
+
+    /// Returns the P-256 key to sign new session headers with, sessions keep the key they started with
+    pub fn current(&self) -> CurrentKey {
+        self.for_algorithm(SignatureAlgorithm::P256)
+            .expect("P-256 key is always set")
+    }
+
+    /// Add keys of other signature algorithms than P-256, which provers can request instead of the current
+    /// key. They are published and rotated like the P-256 key
+    pub fn with_additional_keys(
+        self,
+        additional: HashMap<SignatureAlgorithm, CurrentKey>,
+    ) -> Result<Self> {
+        for key in additional.into_values() {
+            self.load(key)?;
+        }
+        Ok(self)
+    }
+
+    /// Returns the key to sign new session headers with in the given algorithm, if one is loaded
+    pub fn for_algorithm(&self, algorithm: SignatureAlgorithm) -> Option<CurrentKey> {
+        self.current.read().unwrap().get(&algorithm).cloned()
+    }
+
+    /// Returns the current and previous public keys of all algorithms with their validity windows
+    pub fn published(&self) -> Vec<PublishedKey> {
+        self.published.lock().unwrap().clone()
+    }
+
+    pub fn tee_platform(&self) -> Option<TeePlatform> {
+        self.tee_platform
+    }
+
+    /// Replace the current key of the algorithm of `key`, ending the validity window of the previous one
+    pub fn rotate(&self, key: CurrentKey) -> Result<()> {
+        self.publish(key.algorithm, &key.public_key, Utc::now())?;
+        self.current.write().unwrap().insert(key.algorithm, key);
+        Ok(())
+    }
+
+    /// Load a key at startup, restarting with the same key continues its validity window
+    fn load(&self, key: CurrentKey) -> Result<()> {
+        let is_same_key = self
+            .published
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|published| published.algorithm == key.algorithm)
+            .is_some_and(|last| {
+                last.valid_until.is_none() && last.public_key.trim() == key.public_key.trim()
+            });
+        if !is_same_key {
+            self.publish(key.algorithm, &key.public_key, Utc::now())?;
+        }
+        self.current.write().unwrap().insert(key.algorithm, key);
+        Ok(())
+    }
+
+    fn publish(
+        &self,
+        algorithm: SignatureAlgorithm,
+        public_key: &str,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut published = self.published.lock().unwrap();
+        if let Some(last) = published
+            .iter_mut()
+            .rev()
+            .find(|published| published.algorithm == algorithm)
+        {
+            last.valid_until.get_or_insert(now);
+        }
+        published.push(PublishedKey {
+            algorithm,
+            public_key: public_key.to_string(),
+            valid_from: now,
+            valid_until: None,
+        });
+        if let Some(path) = &self.history_path {
+            fs::write(path, serde_json::to_string_pretty(&*published)?)
+                .map_err(|err| eyre!("Failed to write notary key history to {path}: {err}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a public key in PEM format of the given signature algorithm
+pub fn parse_public_key(
+    algorithm: SignatureAlgorithm,
+    public_key_pem: &str,
+) -> Result<NotaryPublicKey> {
+    use p256::pkcs8::DecodePublicKey;
+
+    let public_key = match algorithm {
+        SignatureAlgorithm::P256 => {
+            p256::PublicKey::from_public_key_pem(public_key_pem).map(NotaryPublicKey::from)
+        }
+        SignatureAlgorithm::Secp256k1 => {
+            k256::PublicKey::from_public_key_pem(public_key_pem).map(NotaryPublicKey::from)
+        }
+        SignatureAlgorithm::Ed25519 => {
+            ed25519_dalek::VerifyingKey::from_public_key_pem(public_key_pem)
+                .map(NotaryPublicKey::from)
+        }
+    };
+    public_key.map_err(|err| eyre!("Failed to parse {algorithm:?} notary public key: {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signer::LocalSigner;
+    use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+
+    fn key(public_key: &str) -> CurrentKey {
+        key_of(SignatureAlgorithm::P256, public_key)
+    }
+
+    fn key_of(algorithm: SignatureAlgorithm, public_key: &str) -> CurrentKey {
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        CurrentKey {
+            signer: Arc::new(LocalSigner::new(signing_key)),
+            algorithm,
+            public_key: public_key.to_string(),
+            tee_quote: None,
+        }
+    }
+
+    #[test]
+    fn test_rotate_key() {
+        let keys = NotaryKeys::new(key("key-0"), None, None).unwrap();
+        keys.rotate(key("key-1")).unwrap();
+
+        assert_eq!(keys.current().public_key, "key-1");
+        let published = keys.published();
+        assert_eq!(published.len(), 2);
+        assert_eq!(published[0].public_key, "key-0");
+        assert_eq!(published[0].valid_until, Some(published[1].valid_from));
+        assert_eq!(published[1].valid_until, None);
+    }
+
+    #[test]
+    fn test_restore_key_history() {
+        let path = std::env::temp_dir().join(format!("notary-keys-{}.json", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+
+        let keys = NotaryKeys::new(key("key-0"), Some(path.clone()), None).unwrap();
+        keys.rotate(key("key-1")).unwrap();
+
+        // Restarting with the current key keeps its validity window
+        let restarted = NotaryKeys::new(key("key-1"), Some(path.clone()), None).unwrap();
+        assert_eq!(restarted.published(), keys.published());
+
+        // Restarting with another key rotates the current one out
+        let restarted = NotaryKeys::new(key("key-2"), Some(path.clone()), None).unwrap();
+        let published = restarted.published();
+        assert_eq!(published.len(), 3);
+        assert!(published[1].valid_until.is_some());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_additional_keys() {
+        let keys = NotaryKeys::new(key("key-0"), None, None)
+            .unwrap()
+            .with_additional_keys(HashMap::from([(
+                SignatureAlgorithm::Ed25519,
+                key_of(SignatureAlgorithm::Ed25519, "ed25519-key"),
+            )]))
+            .unwrap();
+
+        assert_eq!(
+            keys.for_algorithm(SignatureAlgorithm::P256)
+                .unwrap()
+                .public_key,
+            "key-0"
+        );
+        assert_eq!(
+            keys.for_algorithm(SignatureAlgorithm::Ed25519)
+                .unwrap()
+                .public_key,
+            "ed25519-key"
+        );
+        assert!(keys.for_algorithm(SignatureAlgorithm::Secp256k1).is_none());
+
+        // Additional keys are published after the current one
+        let published = keys.published();
+        assert_eq!(published.len(), 2);
+        assert_eq!(published[1].algorithm, SignatureAlgorithm::Ed25519);
+        assert_eq!(published[1].public_key, "ed25519-key");
+    }
+
+    #[test]
+    fn test_rotate_additional_key() {
+        let path = std::env::temp_dir().join(format!(
+            "notary-additional-keys-{}.json",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap().to_string();
+        let additional = |public_key: &str| {
+            HashMap::from([(
+                SignatureAlgorithm::Ed25519,
+                key_of(SignatureAlgorithm::Ed25519, public_key),
+            )])
+        };
+
+        let keys = NotaryKeys::new(key("key-0"), Some(path.clone()), None)
+            .unwrap()
+            .with_additional_keys(additional("ed25519-key-0"))
+            .unwrap();
+        keys.rotate(key_of(SignatureAlgorithm::Ed25519, "ed25519-key-1"))
+            .unwrap();
+
+        // Only the window of the key of the same algorithm ends
+        assert_eq!(keys.current().public_key, "key-0");
+        assert_eq!(
+            keys.for_algorithm(SignatureAlgorithm::Ed25519)
+                .unwrap()
+                .public_key,
+            "ed25519-key-1"
+        );
+        let published = keys.published();
+        assert_eq!(published.len(), 3);
+        assert_eq!(published[0].valid_until, None);
+        assert_eq!(published[1].valid_until, Some(published[2].valid_from));
+        assert_eq!(published[2].algorithm, SignatureAlgorithm::Ed25519);
+
+        // Restarting with the rotated keys keeps their validity windows
+        let restarted = NotaryKeys::new(key("key-0"), Some(path.clone()), None)
+            .unwrap()
+            .with_additional_keys(additional("ed25519-key-1"))
+            .unwrap();
+        assert_eq!(restarted.published(), published);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,21 +1,53 @@
+mod admin;
+mod audit;
+mod billing;
+mod concurrency;
 mod config;
 mod domain;
 mod error;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod health;
+mod keys;
+mod metrics;
 mod middleware;
+mod oidc;
+mod rate_limit;
 mod server;
 mod server_tracing;
 mod service;
+mod signer;
+mod storage;
+mod tee;
 mod util;
 
+pub use audit::{
+    audit_message, verify_audit_chain, AuditOutcome, AuditRecord, AuditSink, FileAuditSink,
+    OtlpAuditSink, SyslogAuditSink, AUDIT_SIGNING_CONTEXT,
+};
+pub use billing::{BillingHook, SessionUsage, UsageReceipt, WebhookBillingHook};
 pub use config::{
-    AuthorizationProperties, LoggingProperties, NotarizationProperties, NotaryServerProperties,
-    NotarySigningKeyProperties, ServerProperties, TLSProperties,
+    AcmeProperties, AdminProperties, AuditSinkProperties, AuthorizationProperties,
+    BillingProperties, ConcurrencyProperties, GrpcProperties, LoggingProperties,
+    NotarizationProperties, NotaryServerProperties, NotarySigningKeyProperties, OidcProperties,
+    PolicyProperties, RateLimitProperties, ServerProperties, SessionStoreProperties,
+    SignerBackendProperties, StorageProperties, TLSProperties, TeeProperties, WebSocketProperties,
 };
 pub use domain::{
+    attestation::{AttestationRecord, AttestationStore},
+    auth::{ApiKeyStore, AuthorizationWhitelistRecord},
     cli::CliFields,
-    notary::{ClientType, NotarizationSessionRequest, NotarizationSessionResponse},
+    notary::{
+        ClientType, MemorySessionStore, NotarizationSessionRequest, NotarizationSessionResponse,
+        SessionData, SessionStore, UsageCounter,
+    },
+    AdminStatusResponse,
 };
 pub use error::NotaryServerError;
-pub use server::{read_pem_file, run_server};
+#[cfg(feature = "grpc")]
+pub use grpc::proto;
+pub use keys::PublishedKey;
+pub use server::{read_pem_file, run_server, run_server_with_stores, Stores};
 pub use server_tracing::init_tracing;
+pub use storage::{RedisSessionStore, SqliteAttestationStore};
 pub use util::parse_config_file;
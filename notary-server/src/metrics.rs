@@ -0,0 +1,229 @@
+use eyre::Result;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Buckets of the phase duration histogram in seconds, from a fast setup to a notarization of a
+/// large transcript over a slow connection
+const PHASE_DURATION_BUCKETS: [f64; 12] = [
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0,
+];
+
+/// Prometheus metrics of the notary server, exposed at the /metrics endpoint
+pub struct Metrics {
+    registry: Registry,
+    /// Notarization sessions started after the protocol upgrade
+    pub sessions_started: IntCounter,
+    /// Notarization sessions which finished with a signed session header
+    pub sessions_completed: IntCounter,
+    /// Notarization sessions which failed
+    pub sessions_failed: IntCounter,
+    /// Duration of each phase of a notarization, i.e. MPC setup, TLS session and notarization
+    pub phase_duration: HistogramVec,
+    /// Bytes sent to and received from provers
+    pub bytes_transferred: IntCounterVec,
+    /// Session headers signed with the notary key
+    pub signatures: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("notary".to_string()), None)
+            .expect("Metrics prefix should be valid");
+        let sessions_started = IntCounter::new(
+            "sessions_started_total",
+            "Number of notarization sessions started",
+        )
+        .unwrap();
+        let sessions_completed = IntCounter::new(
+            "sessions_completed_total",
+            "Number of notarization sessions completed successfully",
+        )
+        .unwrap();
+        let sessions_failed = IntCounter::new(
+            "sessions_failed_total",
+            "Number of notarization sessions which failed",
+        )
+        .unwrap();
+        let phase_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "phase_duration_seconds",
+                "Duration of the phases of successful notarizations",
+            )
+            .buckets(PHASE_DURATION_BUCKETS.to_vec()),
+            &["phase"],
+        )
+        .unwrap();
+        let bytes_transferred = IntCounterVec::new(
+            Opts::new(
+                "bytes_transferred_total",
+                "Number of bytes sent to and received from provers",
+            ),
+            &["direction"],
+        )
+        .unwrap();
+        let signatures = IntCounter::new(
+            "signatures_total",
+            "Number of session headers signed with the notary key",
+        )
+        .unwrap();
+
+        registry
+            .register(Box::new(sessions_started.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(sessions_completed.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(sessions_failed.clone()))
+            .unwrap();
+        registry.register(Box::new(phase_duration.clone())).unwrap();
+        registry
+            .register(Box::new(bytes_transferred.clone()))
+            .unwrap();
+        registry.register(Box::new(signatures.clone())).unwrap();
+
+        Self {
+            registry,
+            sessions_started,
+            sessions_completed,
+            sessions_failed,
+            phase_duration,
+            bytes_transferred,
+            signatures,
+        }
+    }
+
+    /// Record the duration of a phase of a notarization which started at `start`
+    pub fn observe_phase(&self, phase: &str, start: Instant) {
+        self.phase_duration
+            .with_label_values(&[phase])
+            .observe(start.elapsed().as_secs_f64());
+    }
+
+    /// Number of notarization sessions which have started but not finished yet
+    pub fn active_sessions(&self) -> u64 {
+        self.sessions_started
+            .get()
+            .saturating_sub(self.sessions_completed.get() + self.sessions_failed.get())
+    }
+
+    /// Wrap a connection to a prover, to count the bytes transferred over it
+    pub fn metered<T>(&self, inner: T) -> MeteredStream<T> {
+        MeteredStream {
+            inner,
+            sent: self.bytes_transferred.with_label_values(&["sent"]),
+            received: self.bytes_transferred.with_label_values(&["received"]),
+        }
+    }
+
+    /// Encode the metrics in the Prometheus text format
+    pub fn encode(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("sessions_started", &self.sessions_started.get())
+            .field("sessions_completed", &self.sessions_completed.get())
+            .field("sessions_failed", &self.sessions_failed.get())
+            .finish()
+    }
+}
+
+/// Connection to a prover which counts the bytes transferred over it
+pub struct MeteredStream<T> {
+    inner: T,
+    sent: IntCounter,
+    received: IntCounter,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for MeteredStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.received.inc_by((buf.filled().len() - filled) as u64);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for MeteredStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.sent.inc_by(written as u64);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_metered_stream() {
+        let metrics = Metrics::new();
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut client = metrics.metered(client);
+
+        client.write_all(b"hello").await.unwrap();
+        server.write_all(b"hi").await.unwrap();
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await.unwrap();
+
+        let sent = metrics.bytes_transferred.with_label_values(&["sent"]).get();
+        let received = metrics
+            .bytes_transferred
+            .with_label_values(&["received"])
+            .get();
+        assert_eq!((sent, received), (5, 2));
+    }
+
+    #[test]
+    fn test_encode_metrics() {
+        let metrics = Metrics::new();
+        metrics.sessions_started.inc();
+        metrics.observe_phase("setup", Instant::now());
+
+        let encoded = metrics.encode().unwrap();
+        assert!(encoded.contains("notary_sessions_started_total 1"));
+        assert!(encoded.contains("notary_phase_duration_seconds_count{phase=\"setup\"} 1"));
+    }
+}
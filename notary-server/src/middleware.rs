@@ -1,11 +1,14 @@
 use async_trait::async_trait;
 use axum::http::{header, request::Parts};
 use axum_core::extract::{FromRef, FromRequestParts};
-use std::collections::HashMap;
 use tracing::{error, trace};
 
 use crate::{
-    domain::{auth::AuthorizationWhitelistRecord, notary::NotaryGlobals},
+    domain::{
+        auth::{ApiKeyStore, AuthorizationWhitelistRecord, AuthorizedClient},
+        notary::NotaryGlobals,
+    },
+    oidc::OidcError,
     NotaryServerError,
 };
 
@@ -22,29 +25,97 @@ where
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let notary_globals = NotaryGlobals::from_ref(state);
-        let Some(whitelist) = notary_globals.authorization_whitelist else {
-            trace!("Skipping authorization as whitelist is not set.");
-            return Ok(Self);
-        };
         let auth_header = parts
             .headers
             .get(header::AUTHORIZATION)
             .and_then(|value| std::str::from_utf8(value.as_bytes()).ok());
+        if let Some(client) = authorize_client(&notary_globals, auth_header).await? {
+            // Make the client available to the handler, to record it in the session
+            parts.extensions.insert(client);
+        }
+        Ok(Self)
+    }
+}
+
+/// Authorize a client by the value of its authorization header, which is either a whitelisted API key
+/// or a bearer token issued by the OIDC provider. Returns `None` if authorization is not enabled
+pub(crate) async fn authorize_client(
+    notary_globals: &NotaryGlobals,
+    auth_header: Option<&str>,
+) -> Result<Option<AuthorizedClient>, NotaryServerError> {
+    if notary_globals.authorization_whitelist.is_none() && notary_globals.oidc_validator.is_none() {
+        trace!("Skipping authorization as whitelist is not set.");
+        return Ok(None);
+    }
+    let Some(auth_header) = auth_header else {
+        let err_msg = "Missing API key.".to_string();
+        error!(err_msg);
+        return Err(NotaryServerError::UnauthorizedProverRequest(err_msg));
+    };
 
-        match auth_header {
-            Some(auth_header) => {
-                let whitelist = whitelist.lock().unwrap();
-                if api_key_is_valid(auth_header, &whitelist) {
-                    trace!("Request authorized.");
-                    Ok(Self)
-                } else {
-                    let err_msg = "Invalid API key.".to_string();
+    // Whitelisted API keys take precedence, so that they can be used alongside bearer tokens
+    let mut client = notary_globals
+        .authorization_whitelist
+        .as_ref()
+        .and_then(|whitelist| authorize(auth_header, whitelist.as_ref()))
+        .map(AuthorizedClient::from);
+    if client.is_none() {
+        if let (Some(validator), Some(token)) = (
+            notary_globals.oidc_validator.as_ref(),
+            auth_header.strip_prefix("Bearer "),
+        ) {
+            match validator.validate(token.trim()).await {
+                Ok(authorized) => client = Some(authorized),
+                Err(err @ OidcError::Jwks(_)) => {
+                    error!("{err}");
+                    return Err(NotaryServerError::Unexpected(err.into()));
+                }
+                Err(err) => {
+                    let err_msg = err.to_string();
                     error!(err_msg);
-                    Err(NotaryServerError::UnauthorizedProverRequest(err_msg))
+                    return Err(NotaryServerError::UnauthorizedProverRequest(err_msg));
                 }
             }
-            None => {
-                let err_msg = "Missing API key.".to_string();
+        }
+    }
+
+    match client {
+        Some(client) => {
+            trace!(client_name = client.name, "Request authorized.");
+            Ok(Some(client))
+        }
+        None => {
+            let err_msg = "Invalid API key.".to_string();
+            error!(err_msg);
+            Err(NotaryServerError::UnauthorizedProverRequest(err_msg))
+        }
+    }
+}
+
+/// Auth middleware of the admin API, which only accepts the admin API key
+pub struct AdminAuthorizationMiddleware;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminAuthorizationMiddleware
+where
+    NotaryGlobals: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = NotaryServerError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let notary_globals = NotaryGlobals::from_ref(state);
+        let api_key = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| std::str::from_utf8(value.as_bytes()).ok())
+            .map(|value| value.strip_prefix("Bearer ").unwrap_or(value).trim());
+        match (api_key, notary_globals.admin_api_key.as_deref()) {
+            (Some(api_key), Some(admin_api_key)) if constant_time_eq(api_key, admin_api_key) => {
+                Ok(Self)
+            }
+            _ => {
+                let err_msg = "Invalid admin API key.".to_string();
                 error!(err_msg);
                 Err(NotaryServerError::UnauthorizedProverRequest(err_msg))
             }
@@ -52,54 +123,90 @@ where
     }
 }
 
-/// Helper function to check if an API key is in whitelist
-fn api_key_is_valid(
-    api_key: &str,
-    whitelist: &HashMap<String, AuthorizationWhitelistRecord>,
-) -> bool {
-    whitelist.get(api_key).is_some()
+/// Compare API keys in constant time, so that the admin API key can't be guessed from response times
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Helper function to look up the API key of an authorization header, which is either the key
+/// itself or a bearer token
+fn authorize(
+    auth_header: &str,
+    whitelist: &dyn ApiKeyStore,
+) -> Option<AuthorizationWhitelistRecord> {
+    let api_key = auth_header
+        .strip_prefix("Bearer ")
+        .unwrap_or(auth_header)
+        .trim();
+    whitelist.get(api_key)
 }
 
 #[cfg(test)]
 mod test {
-    use super::{api_key_is_valid, HashMap};
+    use super::{authorize, constant_time_eq};
     use crate::domain::auth::{
         authorization_whitelist_vec_into_hashmap, AuthorizationWhitelistRecord,
     };
-    use std::sync::Arc;
+    use std::{collections::HashMap, sync::Mutex};
 
-    fn get_whitelist_fixture() -> HashMap<String, AuthorizationWhitelistRecord> {
-        authorization_whitelist_vec_into_hashmap(vec![
+    fn get_whitelist_fixture() -> Mutex<HashMap<String, AuthorizationWhitelistRecord>> {
+        Mutex::new(authorization_whitelist_vec_into_hashmap(vec![
             AuthorizationWhitelistRecord {
                 name: "test-name-0".to_string(),
                 api_key: "test-api-key-0".to_string(),
                 created_at: "2023-10-18T07:38:53Z".to_string(),
+                max_sent_data: None,
+                max_recv_data: None,
+                max_monthly_data: None,
             },
             AuthorizationWhitelistRecord {
                 name: "test-name-1".to_string(),
                 api_key: "test-api-key-1".to_string(),
                 created_at: "2023-10-11T07:38:53Z".to_string(),
+                max_sent_data: None,
+                max_recv_data: None,
+                max_monthly_data: None,
             },
             AuthorizationWhitelistRecord {
                 name: "test-name-2".to_string(),
                 api_key: "test-api-key-2".to_string(),
                 created_at: "2022-10-11T07:38:53Z".to_string(),
+                max_sent_data: Some(1024),
+                max_recv_data: Some(4096),
+                max_monthly_data: Some(1 << 20),
             },
-        ])
+        ]))
     }
 
     #[test]
     fn test_api_key_is_present() {
         let whitelist = get_whitelist_fixture();
-        assert!(api_key_is_valid("test-api-key-0", &Arc::new(whitelist)));
+        let record = authorize("test-api-key-0", &whitelist).unwrap();
+        assert_eq!(record.name, "test-name-0");
+    }
+
+    #[test]
+    fn test_api_key_as_bearer_token() {
+        let whitelist = get_whitelist_fixture();
+        let record = authorize("Bearer test-api-key-2", &whitelist).unwrap();
+        assert_eq!(record.name, "test-name-2");
+        assert_eq!(record.max_sent_data, Some(1024));
     }
 
     #[test]
     fn test_api_key_is_absent() {
         let whitelist = get_whitelist_fixture();
-        assert_eq!(
-            api_key_is_valid("test-api-keY-0", &Arc::new(whitelist)),
-            false
-        );
+        assert!(authorize("test-api-keY-0", &whitelist).is_none());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("admin-api-key", "admin-api-key"));
+        assert!(!constant_time_eq("admin-api-key", "admin-api-kez"));
+        assert!(!constant_time_eq("admin-api-key", "admin-api-key-0"));
     }
 }
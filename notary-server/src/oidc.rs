@@ -0,0 +1,339 @@
+use eyre::{ensure, eyre, Result};
+use hyper::{client::HttpConnector, Client, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::{config::OidcProperties, domain::auth::AuthorizedClient};
+
+/// Asymmetric algorithms accepted for bearer tokens, symmetric ones can't be verified with a JWKS
+const ALLOWED_ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Minimum time between fetches of the JWKS triggered by tokens signed with unknown keys, so that
+/// invalid tokens can't be used to flood the identity provider
+const MIN_JWKS_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Timeout of requests to the identity provider
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("Invalid bearer token: {0}")]
+    InvalidToken(#[from] jsonwebtoken::errors::Error),
+    #[error("Bearer token is signed with unsupported algorithm {0:?}")]
+    UnsupportedAlgorithm(Algorithm),
+    #[error("Bearer token is signed with an unknown key")]
+    UnknownKey,
+    #[error("Failed to fetch JWKS of the identity provider: {0}")]
+    Jwks(String),
+}
+
+/// Claims of the bearer token that are used by the notary, registered claims like `exp`, `iss` and `aud`
+/// are checked during decoding
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+/// Subset of the OpenID provider configuration needed to discover the JWKS
+#[derive(Debug, Deserialize)]
+struct OpenIdConfiguration {
+    jwks_uri: String,
+}
+
+#[derive(Debug)]
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Validator of OAuth2/OIDC bearer tokens, which caches the JWKS of the identity provider
+#[derive(Debug)]
+pub struct OidcValidator {
+    config: OidcProperties,
+    client: Client<HttpsConnector<HttpConnector>>,
+    jwks: RwLock<Option<CachedJwks>>,
+}
+
+impl OidcValidator {
+    /// Create a validator, failing if the issuer or the JWKS url is not an https url, as the keys
+    /// fetched from them are trusted to authorize provers
+    pub fn new(config: OidcProperties) -> Result<Self> {
+        ensure_https("issuer", &config.issuer)?;
+        if let Some(jwks_url) = &config.jwks_url {
+            ensure_https("jwks-url", jwks_url)?;
+        }
+
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_only()
+            .enable_http1()
+            .build();
+        Ok(Self {
+            config,
+            client: Client::builder().build(connector),
+            jwks: RwLock::new(None),
+        })
+    }
+
+    /// Validate the signature, expiry, issuer and audience of a bearer token, returning the client
+    /// identified by its subject
+    pub async fn validate(&self, token: &str) -> Result<AuthorizedClient, OidcError> {
+        let header = decode_header(token)?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(OidcError::UnsupportedAlgorithm(header.alg));
+        }
+
+        let jwk = self.find_key(header.kid.as_deref()).await?;
+        let key = DecodingKey::from_jwk(&jwk)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<Claims>(token, &key, &validation)?.claims;
+        Ok(AuthorizedClient {
+            name: claims.sub,
+            max_sent_data: None,
+            max_recv_data: None,
+            max_monthly_data: None,
+        })
+    }
+
+    /// Find the key a token is signed with, fetching the JWKS again if the cached one is outdated
+    /// or doesn't contain the key
+    async fn find_key(&self, kid: Option<&str>) -> Result<Jwk, OidcError> {
+        let mut stale_key = None;
+        if let Some(cached) = self.jwks.read().await.as_ref() {
+            let age = cached.fetched_at.elapsed();
+            match find_jwk(&cached.keys, kid) {
+                Some(jwk) if age < Duration::from_secs(self.config.jwks_refresh_interval) => {
+                    return Ok(jwk.clone())
+                }
+                Some(jwk) => stale_key = Some(jwk.clone()),
+                None if age < MIN_JWKS_REFETCH_INTERVAL => return Err(OidcError::UnknownKey),
+                None => {}
+            }
+        }
+
+        let keys = match self.fetch_jwks().await {
+            Ok(keys) => keys,
+            Err(err) => {
+                // Keep accepting known keys while the identity provider is unreachable
+                if let Some(jwk) = stale_key {
+                    warn!("Using outdated JWKS as it can't be refreshed: {err}");
+                    return Ok(jwk);
+                }
+                return Err(OidcError::Jwks(err.to_string()));
+            }
+        };
+        let jwk = find_jwk(&keys, kid).cloned();
+        *self.jwks.write().await = Some(CachedJwks {
+            keys,
+            fetched_at: Instant::now(),
+        });
+
+        jwk.ok_or(OidcError::UnknownKey)
+    }
+
+    async fn fetch_jwks(&self) -> Result<JwkSet> {
+        let jwks_url = match &self.config.jwks_url {
+            Some(jwks_url) => jwks_url.clone(),
+            None => {
+                let discovery_url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer.trim_end_matches('/')
+                );
+                self.fetch_json::<OpenIdConfiguration>(&discovery_url)
+                    .await?
+                    .jwks_uri
+            }
+        };
+        debug!("Fetching JWKS from {jwks_url}");
+        self.fetch_json(&jwks_url).await
+    }
+
+    async fn fetch_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|err| eyre!("Invalid url {url}: {err}"))?;
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, self.client.get(uri))
+            .await
+            .map_err(|_| eyre!("Request to {url} timed out"))??;
+        ensure!(
+            response.status().is_success(),
+            "Request to {url} failed with status {}",
+            response.status()
+        );
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+fn ensure_https(name: &str, url: &str) -> Result<()> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|err| eyre!("Invalid OIDC {name} {url}: {err}"))?;
+    ensure!(
+        uri.scheme_str() == Some("https"),
+        "OIDC {name} {url} must be an https url"
+    );
+    Ok(())
+}
+
+/// Find the key with the key id of a token, tokens without key id can only be matched if the issuer
+/// has a single key
+fn find_jwk<'a>(keys: &'a JwkSet, kid: Option<&str>) -> Option<&'a Jwk> {
+    match kid {
+        Some(kid) => keys.find(kid),
+        None => match keys.keys.as_slice() {
+            [jwk] => Some(jwk),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+    use serde_json::json;
+
+    const NOTARY_KEY_PATH: &str = "./fixture/notary/notary.key";
+    const ISSUER: &str = "https://auth.example.com/";
+    const AUDIENCE: &str = "notary-server";
+
+    fn get_validator_fixture() -> OidcValidator {
+        let signing_key = SigningKey::read_pkcs8_pem_file(NOTARY_KEY_PATH).unwrap();
+        let point = signing_key.verifying_key().to_encoded_point(false);
+        let keys: JwkSet = serde_json::from_value(json!({
+            "keys": [{
+                "kty": "EC",
+                "crv": "P-256",
+                "kid": "test-key",
+                "alg": "ES256",
+                "x": URL_SAFE_NO_PAD.encode(point.x().unwrap()),
+                "y": URL_SAFE_NO_PAD.encode(point.y().unwrap()),
+            }]
+        }))
+        .unwrap();
+
+        let validator = OidcValidator::new(OidcProperties {
+            issuer: ISSUER.to_string(),
+            audience: AUDIENCE.to_string(),
+            jwks_url: Some("https://auth.example.com/jwks.json".to_string()),
+            jwks_refresh_interval: 3600,
+        })
+        .unwrap();
+        *validator.jwks.try_write().unwrap() = Some(CachedJwks {
+            keys,
+            fetched_at: Instant::now(),
+        });
+        validator
+    }
+
+    fn sign_token(kid: &str, audience: &str, expires_in: i64) -> String {
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(kid.to_string());
+        let claims = json!({
+            "sub": "test-client",
+            "iss": ISSUER,
+            "aud": audience,
+            "exp": chrono::Utc::now().timestamp() + expires_in,
+        });
+        let key = EncodingKey::from_ec_pem(&std::fs::read(NOTARY_KEY_PATH).unwrap()).unwrap();
+        encode(&header, &claims, &key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_valid_token() {
+        let validator = get_validator_fixture();
+        let client = validator
+            .validate(&sign_token("test-key", AUDIENCE, 300))
+            .await
+            .unwrap();
+        assert_eq!(client.name, "test-client");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_audience() {
+        let validator = get_validator_fixture();
+        let result = validator
+            .validate(&sign_token("test-key", "other-service", 300))
+            .await;
+        assert!(matches!(result, Err(OidcError::InvalidToken(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token() {
+        let validator = get_validator_fixture();
+        let result = validator
+            .validate(&sign_token("test-key", AUDIENCE, -300))
+            .await;
+        assert!(matches!(result, Err(OidcError::InvalidToken(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_key() {
+        // The JWKS was just fetched, so it isn't fetched again for the unknown key
+        let validator = get_validator_fixture();
+        let result = validator
+            .validate(&sign_token("other-key", AUDIENCE, 300))
+            .await;
+        assert!(matches!(result, Err(OidcError::UnknownKey)));
+    }
+
+    #[tokio::test]
+    async fn test_symmetric_token_is_rejected() {
+        let validator = get_validator_fixture();
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("test-key".to_string());
+        let token = encode(
+            &header,
+            &json!({ "sub": "test-client", "iss": ISSUER, "aud": AUDIENCE, "exp": i64::MAX }),
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        let result = validator.validate(&token).await;
+        assert!(matches!(
+            result,
+            Err(OidcError::UnsupportedAlgorithm(Algorithm::HS256))
+        ));
+    }
+
+    #[test]
+    fn test_http_urls_are_rejected() {
+        let config = |issuer: &str, jwks_url: Option<&str>| OidcProperties {
+            issuer: issuer.to_string(),
+            audience: AUDIENCE.to_string(),
+            jwks_url: jwks_url.map(String::from),
+            jwks_refresh_interval: 3600,
+        };
+
+        assert!(OidcValidator::new(config(ISSUER, None)).is_ok());
+        assert!(OidcValidator::new(config("http://auth.example.com/", None)).is_err());
+        assert!(
+            OidcValidator::new(config(ISSUER, Some("http://auth.example.com/jwks.json"))).is_err()
+        );
+    }
+}
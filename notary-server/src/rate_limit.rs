@@ -0,0 +1,311 @@
+use chrono::{DateTime, Datelike, Months, TimeZone, Utc};
+use eyre::Result;
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    config::RateLimitProperties,
+    domain::notary::{SessionStore, UsageCounter},
+};
+
+const SECONDS_PER_MINUTE: i64 = 60;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Clone, Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RateLimitError {
+    #[error("Rate limit of {limit} sessions per minute exceeded")]
+    TooManySessions { limit: u32, retry_after: u64 },
+    #[error("Daily data quota of {limit} bytes exceeded")]
+    QuotaExceeded { limit: usize, retry_after: u64 },
+    #[error("Monthly data quota of {limit} bytes of the client exceeded")]
+    MonthlyQuotaExceeded { limit: usize, retry_after: u64 },
+}
+
+impl RateLimitError {
+    /// Seconds until the client can retry, i.e. until the current window ends
+    pub fn retry_after(&self) -> u64 {
+        match self {
+            Self::TooManySessions { retry_after, .. }
+            | Self::QuotaExceeded { retry_after, .. }
+            | Self::MonthlyQuotaExceeded { retry_after, .. } => *retry_after,
+        }
+    }
+}
+
+/// Limits the sessions started and the transcript data requested by each client, identified by its
+/// API key name or IP address, as well as the monthly transcript data of clients whose API key has a quota.
+/// The usage is counted in the session store, so that the limits hold across the notary instances sharing it
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RwLock<RateLimitProperties>,
+    store: Arc<dyn SessionStore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitProperties, store: Arc<dyn SessionStore>) -> Self {
+        Self {
+            config: RwLock::new(config),
+            store,
+        }
+    }
+
+    /// Replace the limits, keeping the usage of the clients in the current windows
+    pub fn set_config(&self, config: RateLimitProperties) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// Record a new session of the client requesting `data` bytes of transcript, unless it exceeds
+    /// the rate limit, the daily data quota or the `monthly_limit` of the client. Fails if the usage
+    /// can't be counted in the session store
+    pub async fn check(
+        &self,
+        client: &str,
+        data: usize,
+        monthly_limit: Option<usize>,
+    ) -> Result<Result<(), RateLimitError>> {
+        self.check_at(client, data, monthly_limit, Utc::now()).await
+    }
+
+    async fn check_at(
+        &self,
+        client: &str,
+        data: usize,
+        monthly_limit: Option<usize>,
+        now: DateTime<Utc>,
+    ) -> Result<Result<(), RateLimitError>> {
+        let config = self.config.read().unwrap().clone();
+        let timestamp = now.timestamp();
+        let minute = timestamp.div_euclid(SECONDS_PER_MINUTE);
+        let day = timestamp.div_euclid(SECONDS_PER_DAY);
+        let month = now.year() * 12 + now.month0() as i32;
+        let next_month = start_of_next_month(now);
+
+        // Only the usage of the configured limits is counted, along with the error if it exceeds them
+        let mut limits = Vec::new();
+        if let Some(limit) = config.max_sessions_per_minute {
+            let expires_at = Utc
+                .timestamp_opt((minute + 1) * SECONDS_PER_MINUTE, 0)
+                .unwrap();
+            limits.push((
+                UsageCounter {
+                    key: format!("{client}:sessions:{minute}"),
+                    amount: 1,
+                    limit: limit.into(),
+                    expires_at,
+                },
+                RateLimitError::TooManySessions {
+                    limit,
+                    retry_after: (expires_at - now).num_seconds() as u64,
+                },
+            ));
+        }
+        if let Some(limit) = config.max_daily_data {
+            let expires_at = Utc.timestamp_opt((day + 1) * SECONDS_PER_DAY, 0).unwrap();
+            limits.push((
+                UsageCounter {
+                    key: format!("{client}:data:{day}"),
+                    amount: data as u64,
+                    limit: limit as u64,
+                    expires_at,
+                },
+                RateLimitError::QuotaExceeded {
+                    limit,
+                    retry_after: (expires_at - now).num_seconds() as u64,
+                },
+            ));
+        }
+        if let Some(limit) = monthly_limit {
+            limits.push((
+                UsageCounter {
+                    key: format!("{client}:monthly-data:{month}"),
+                    amount: data as u64,
+                    limit: limit as u64,
+                    expires_at: next_month,
+                },
+                RateLimitError::MonthlyQuotaExceeded {
+                    limit,
+                    retry_after: (next_month - now).num_seconds() as u64,
+                },
+            ));
+        }
+        if limits.is_empty() {
+            return Ok(Ok(()));
+        }
+
+        let (counters, errors): (Vec<_>, Vec<_>) = limits.into_iter().unzip();
+        Ok(match self.store.add_usage(&counters, now).await? {
+            Some(index) => Err(errors[index].clone()),
+            None => Ok(()),
+        })
+    }
+}
+
+fn start_of_next_month(now: DateTime<Utc>) -> DateTime<Utc> {
+    let start_of_month = Utc
+        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .unwrap();
+    start_of_month + Months::new(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::notary::MemorySessionStore;
+
+    fn limiter(config: RateLimitProperties) -> RateLimiter {
+        RateLimiter::new(config, Arc::new(MemorySessionStore::default()))
+    }
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, minute, second)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sessions_per_minute() {
+        let limiter = limiter(RateLimitProperties {
+            max_sessions_per_minute: Some(2),
+            max_daily_data: None,
+        });
+
+        assert!(limiter
+            .check_at("client-0", 0, None, at(10, 0, 0))
+            .await
+            .unwrap()
+            .is_ok());
+        assert!(limiter
+            .check_at("client-0", 0, None, at(10, 0, 10))
+            .await
+            .unwrap()
+            .is_ok());
+        assert_eq!(
+            limiter
+                .check_at("client-0", 0, None, at(10, 0, 45))
+                .await
+                .unwrap(),
+            Err(RateLimitError::TooManySessions {
+                limit: 2,
+                retry_after: 15
+            })
+        );
+        // Other clients are limited separately
+        assert!(limiter
+            .check_at("client-1", 0, None, at(10, 0, 45))
+            .await
+            .unwrap()
+            .is_ok());
+        // The limit resets in the next minute
+        assert!(limiter
+            .check_at("client-0", 0, None, at(10, 1, 0))
+            .await
+            .unwrap()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_daily_data_quota() {
+        let limiter = limiter(RateLimitProperties {
+            max_sessions_per_minute: None,
+            max_daily_data: Some(1000),
+        });
+
+        assert!(limiter
+            .check_at("client-0", 600, None, at(10, 0, 0))
+            .await
+            .unwrap()
+            .is_ok());
+        assert_eq!(
+            limiter
+                .check_at("client-0", 600, None, at(23, 0, 0))
+                .await
+                .unwrap(),
+            Err(RateLimitError::QuotaExceeded {
+                limit: 1000,
+                retry_after: 3600
+            })
+        );
+        // Rejected requests don't use up the quota
+        assert!(limiter
+            .check_at("client-0", 400, None, at(23, 0, 0))
+            .await
+            .unwrap()
+            .is_ok());
+        // The quota resets at midnight
+        assert!(limiter
+            .check_at(
+                "client-0",
+                1000,
+                None,
+                at(0, 0, 0) + chrono::Duration::days(1)
+            )
+            .await
+            .unwrap()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_monthly_data_quota() {
+        let limiter = limiter(RateLimitProperties::default());
+        let quota = Some(1000);
+
+        assert!(limiter
+            .check_at("client-0", 600, quota, at(10, 0, 0))
+            .await
+            .unwrap()
+            .is_ok());
+        // The monthly quota is not reset at midnight
+        let next_day = at(10, 0, 0) + chrono::Duration::days(1);
+        assert_eq!(
+            limiter
+                .check_at("client-0", 600, quota, next_day)
+                .await
+                .unwrap(),
+            Err(RateLimitError::MonthlyQuotaExceeded {
+                limit: 1000,
+                retry_after: 29 * 24 * 60 * 60 + 14 * 60 * 60
+            })
+        );
+        // Clients without a quota are not tracked
+        assert!(limiter
+            .check_at("client-1", 600, None, next_day)
+            .await
+            .unwrap()
+            .is_ok());
+        assert!(limiter
+            .check_at("client-1", 600, quota, next_day)
+            .await
+            .unwrap()
+            .is_ok());
+        // The quota resets at the start of the next month
+        let next_month = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        assert!(limiter
+            .check_at("client-0", 1000, quota, next_month)
+            .await
+            .unwrap()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_shared_through_store() {
+        let config = RateLimitProperties {
+            max_sessions_per_minute: Some(1),
+            max_daily_data: None,
+        };
+        let store: Arc<dyn SessionStore> = Arc::new(MemorySessionStore::default());
+        // Two notary instances sharing the same store
+        let limiters = [
+            RateLimiter::new(config.clone(), store.clone()),
+            RateLimiter::new(config, store),
+        ];
+
+        assert!(limiters[0]
+            .check_at("client-0", 0, None, at(10, 0, 0))
+            .await
+            .unwrap()
+            .is_ok());
+        assert!(limiters[1]
+            .check_at("client-0", 0, None, at(10, 0, 30))
+            .await
+            .unwrap()
+            .is_err());
+    }
+}
@@ -6,6 +6,7 @@ use axum::{
     Json, Router,
 };
 use eyre::{ensure, eyre, Result};
+use futures::StreamExt;
 use futures_util::future::poll_fn;
 use hyper::server::{
     accept::Accept,
@@ -16,6 +17,7 @@ use notify::{
 };
 use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
 use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_acme::{caches::DirCache, AcmeAcceptor, AcmeConfig};
 use std::{
     collections::HashMap,
     fs::File as StdFile,
@@ -23,37 +25,163 @@ use std::{
     net::{IpAddr, SocketAddr},
     path::Path,
     pin::Pin,
-    sync::{Arc, Mutex},
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::Duration,
 };
 use tower_http::cors::CorsLayer;
 
+use tlsn_core::{tee::TeePlatform, SignatureAlgorithm};
 use tokio::{fs::File, net::TcpListener};
 use tokio_rustls::TlsAcceptor;
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
 use tower::MakeService;
 use tracing::{debug, error, info};
 
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcServer;
 use crate::{
-    config::{NotaryServerProperties, NotarySigningKeyProperties},
+    admin::{get_status, reload_policies, rotate_key, start_draining, stop_draining},
+    audit::{connect_audit_sink, AuditLog, AuditSink},
+    billing::{get_receipt, Billing, BillingHook, WebhookBillingHook},
+    config::{
+        AcmeProperties, AdditionalSigningKeyProperties, NotaryServerProperties,
+        NotarySigningKeyProperties,
+    },
     domain::{
-        auth::{authorization_whitelist_vec_into_hashmap, AuthorizationWhitelistRecord},
-        notary::NotaryGlobals,
+        attestation::AttestationStore,
+        auth::{
+            authorization_whitelist_vec_into_hashmap, ApiKeyStore, AuthorizationWhitelistRecord,
+        },
+        notary::{MemorySessionStore, NotaryGlobals, SessionStore},
         InfoResponse,
     },
     error::NotaryServerError,
-    middleware::AuthorizationMiddleware,
-    service::{initialize, upgrade_protocol},
+    health::{get_liveness, get_readiness},
+    keys::{parse_public_key, CurrentKey, NotaryKeys},
+    middleware::{AdminAuthorizationMiddleware, AuthorizationMiddleware},
+    oidc::OidcValidator,
+    service::{get_attestation, get_keys, get_metrics, initialize, upgrade_protocol},
+    signer::{connect_signer, load_local_signer, LocalSigner, NotarySigner},
+    storage::{RedisSessionStore, SqliteAttestationStore},
+    tee::generate_tee_quote,
     util::parse_csv_file,
 };
 
+/// Interval at which the ongoing notarizations are counted while shutting down
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Storage backends to use instead of the ones set up from the config, e.g. to keep API keys and
+/// attestations in a Postgres database
+#[derive(Clone, Debug, Default)]
+pub struct Stores {
+    /// Store of the API keys allowed to request notarization, instead of the whitelist csv
+    pub api_key_store: Option<Arc<dyn ApiKeyStore>>,
+    /// Store of the signed attestations, instead of the SQLite database
+    pub attestation_store: Option<Arc<dyn AttestationStore>>,
+    /// Store of the configuration data of sessions, instead of the in-memory or Redis store
+    pub session_store: Option<Arc<dyn SessionStore>>,
+    /// Sink of the audit records, instead of the one set in the config
+    pub audit_sink: Option<Arc<dyn AuditSink>>,
+    /// Hook reporting the usage of completed sessions, instead of the webhook set in the config
+    pub billing_hook: Option<Arc<dyn BillingHook>>,
+}
+
 /// Start a TCP server (with or without TLS) to accept notarization request for both TCP and WebSocket clients
 #[tracing::instrument(skip(config))]
 pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotaryServerError> {
-    // Load the private key for notarized transcript signing
-    let notary_signing_key = load_notary_signing_key(&config.notary_key).await?;
+    run_server_with_stores(config, Stores::default()).await
+}
+
+/// Start the notary server like [run_server], but with custom storage backends
+#[tracing::instrument(skip(config, stores))]
+pub async fn run_server_with_stores(
+    config: &NotaryServerProperties,
+    stores: Stores,
+) -> Result<(), NotaryServerError> {
+    let (authorization_whitelist, _watcher) = match stores.api_key_store {
+        Some(api_key_store) => (Some(api_key_store), None),
+        None => {
+            // Load the authorization whitelist csv if it is turned on
+            let authorization_whitelist = load_authorization_whitelist(config)?
+                .map(|whitelist| Arc::new(Mutex::new(whitelist)));
+            // Enable hot reload if authorization whitelist is available
+            let watcher = watch_and_reload_authorization_whitelist(
+                config.clone(),
+                authorization_whitelist.as_ref().map(Arc::clone),
+            )?;
+            if watcher.is_some() {
+                debug!("Successfully setup watcher for hot reload of authorization whitelist!");
+            }
+            (
+                authorization_whitelist.map(|whitelist| whitelist as Arc<dyn ApiKeyStore>),
+                watcher,
+            )
+        }
+    };
+
+    let attestation_store = match stores.attestation_store {
+        Some(attestation_store) => Some(attestation_store),
+        None => load_attestation_store(config)?,
+    };
+
+    let session_store = match stores.session_store {
+        Some(session_store) => session_store,
+        None => load_session_store(config).await?,
+    };
+
+    let audit_sink = match stores.audit_sink {
+        Some(audit_sink) => Some(audit_sink),
+        None => load_audit_sink(config).await?,
+    };
+
+    let billing_hook = match stores.billing_hook {
+        Some(billing_hook) => Some(billing_hook),
+        None => load_billing_hook(config),
+    };
+
+    serve(
+        config,
+        authorization_whitelist,
+        attestation_store,
+        session_store,
+        audit_sink,
+        billing_hook,
+    )
+    .await
+}
+
+async fn serve(
+    config: &NotaryServerProperties,
+    authorization_whitelist: Option<Arc<dyn ApiKeyStore>>,
+    attestation_store: Option<Arc<dyn AttestationStore>>,
+    session_store: Arc<dyn SessionStore>,
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    billing_hook: Option<Arc<dyn BillingHook>>,
+) -> Result<(), NotaryServerError> {
+    // Load the private key for notarized transcript signing, or connect to the KMS or HSM holding it
+    let notary_key = load_notary_key(
+        &config.notary_key,
+        config.tee.as_ref().map(|tee| tee.platform),
+    )
+    .await?;
+    let additional_keys = load_additional_keys(
+        &config.notary_key.additional_keys,
+        config.tee.as_ref().map(|tee| tee.platform),
+    )?;
+    let notary_keys = Arc::new(
+        NotaryKeys::new(
+            notary_key,
+            config.admin.key_history_path.clone(),
+            config.tee.as_ref().map(|tee| tee.platform),
+        )?
+        .with_additional_keys(additional_keys)?,
+    );
     // Build TLS acceptor if it is turned on
     let tls_acceptor = if !config.tls.enabled {
         debug!("Skipping TLS setup as it is turned off.");
         None
+    } else if let Some(acme) = &config.tls.acme {
+        Some(setup_acme(acme))
     } else {
         let (tls_private_key, tls_certificates) = load_tls_key_and_cert(
             &config.tls.private_key_pem_path,
@@ -70,21 +198,9 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
         // Set the http protocols we support
         server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
         let tls_config = Arc::new(server_config);
-        Some(TlsAcceptor::from(tls_config))
+        Some(TlsTermination::Static(TlsAcceptor::from(tls_config)))
     };
 
-    // Load the authorization whitelist csv if it is turned on
-    let authorization_whitelist =
-        load_authorization_whitelist(config)?.map(|whitelist| Arc::new(Mutex::new(whitelist)));
-    // Enable hot reload if authorization whitelist is available
-    let watcher = watch_and_reload_authorization_whitelist(
-        config.clone(),
-        authorization_whitelist.as_ref().map(Arc::clone),
-    )?;
-    if watcher.is_some() {
-        debug!("Successfully setup watcher for hot reload of authorization whitelist!");
-    }
-
     let notary_address = SocketAddr::new(
         IpAddr::V4(config.server.host.parse().map_err(|err| {
             eyre!("Failed to parse notary host address from server config: {err}")
@@ -100,33 +216,87 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
     info!("Listening for TCP traffic at {}", notary_address);
 
     let protocol = Arc::new(Http::new());
-    let notary_globals = NotaryGlobals::new(
-        notary_signing_key,
-        config.notarization.clone(),
-        authorization_whitelist,
-    );
+    // Set up validation of bearer tokens if OIDC is configured
+    let oidc_validator = config
+        .authorization
+        .oidc
+        .clone()
+        .filter(|_| config.authorization.enabled)
+        .map(|oidc| OidcValidator::new(oidc).map(Arc::new))
+        .transpose()?;
+    if config.authorization.enabled && authorization_whitelist.is_none() && oidc_validator.is_none()
+    {
+        return Err(eyre!("Authorization is turned on without a whitelist or OIDC").into());
+    }
+
+    // Load the API key of the admin API if it is turned on
+    let admin_api_key = if config.admin.enabled {
+        let api_key = std::env::var(&config.admin.api_key_env).map_err(|_| {
+            eyre!(
+                "Admin API is turned on without an API key set in {}",
+                config.admin.api_key_env
+            )
+        })?;
+        Some(api_key)
+    } else {
+        debug!("Skipping admin API as it is turned off.");
+        None
+    };
+
+    // Continue the chain of the audit log if it is enabled
+    let audit_log = match audit_sink {
+        Some(audit_sink) => Some(Arc::new(
+            AuditLog::new(audit_sink, notary_keys.clone()).await?,
+        )),
+        None => None,
+    };
+
+    // Report the usage of completed sessions and sign receipts of it if either is enabled
+    let billing = (billing_hook.is_some() || config.billing.signed_receipts).then(|| {
+        Arc::new(Billing::new(
+            billing_hook,
+            config.billing.signed_receipts,
+            session_store.clone(),
+        ))
+    });
 
     // Parameters needed for the info endpoint
-    let public_key = std::fs::read_to_string(&config.notary_key.public_key_pem_path)
-        .map_err(|err| eyre!("Failed to load notary public signing key for notarization: {err}"))?;
     let version = env!("CARGO_PKG_VERSION").to_string();
     let git_commit_hash = env!("GIT_COMMIT_HASH").to_string();
     let git_commit_timestamp = env!("GIT_COMMIT_TIMESTAMP").to_string();
 
-    // Parameters needed for the root / endpoint
-    let html_string = config.server.html_info.clone();
-    let html_info = Html(
-        html_string
-            .replace("{version}", &version)
-            .replace("{git_commit_hash}", &git_commit_hash)
-            .replace("{git_commit_timestamp}", &git_commit_timestamp)
-            .replace("{public_key}", &public_key),
+    let notary_globals = NotaryGlobals::new(
+        notary_keys.clone(),
+        config.notarization.clone(),
+        authorization_whitelist,
+        oidc_validator,
+        config.rate_limit.clone(),
+        config.concurrency.clone(),
+        attestation_store,
+        config.websocket.clone(),
+        session_store,
+        admin_api_key.clone(),
+        audit_log,
+        billing,
     );
 
+    // Parameters needed for the root / endpoint, the public key is filled in on each request as it can be rotated
+    let html_string = config
+        .server
+        .html_info
+        .replace("{version}", &version)
+        .replace("{git_commit_hash}", &git_commit_hash)
+        .replace("{git_commit_timestamp}", &git_commit_timestamp);
+
+    let info_keys = notary_keys.clone();
     let router = Router::new()
         .route(
             "/",
-            get(|| async move { (StatusCode::OK, html_info).into_response() }),
+            get(|| async move {
+                let html_info =
+                    Html(html_string.replace("{public_key}", &notary_keys.current().public_key));
+                (StatusCode::OK, html_info).into_response()
+            }),
         )
         .route(
             "/healthcheck",
@@ -135,19 +305,25 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
         .route(
             "/info",
             get(|| async move {
+                let key = info_keys.current();
                 (
                     StatusCode::OK,
                     Json(InfoResponse {
                         version,
-                        public_key,
+                        public_key: key.public_key,
                         git_commit_hash,
                         git_commit_timestamp,
+                        tee_quote: key.tee_quote,
                     }),
                 )
                     .into_response()
             }),
         )
+        .route("/keys", get(get_keys))
+        .route("/metrics", get(get_metrics))
         .route("/session", post(initialize))
+        .route("/attestations/:id", get(get_attestation))
+        .route("/receipts/:id", get(get_receipt))
         // Not applying auth middleware to /notarize endpoint for now as we can rely on our
         // short-lived session id generated from /session endpoint, as it is not possible
         // to use header for API key for websocket /notarize endpoint due to browser restriction
@@ -156,15 +332,61 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
         .route_layer(from_extractor_with_state::<
             AuthorizationMiddleware,
             NotaryGlobals,
-        >(notary_globals.clone()))
+        >(notary_globals.clone()));
+    // The admin API is authorized with its own API key instead of the API keys of the provers
+    let router = if admin_api_key.is_some() {
+        router.merge(
+            Router::new()
+                .route("/admin/status", get(get_status))
+                .route("/admin/keys/rotate", post(rotate_key))
+                .route("/admin/policies/reload", post(reload_policies))
+                .route("/admin/drain", post(start_draining).delete(stop_draining))
+                .route_layer(from_extractor_with_state::<
+                    AdminAuthorizationMiddleware,
+                    NotaryGlobals,
+                >(notary_globals.clone())),
+        )
+    } else {
+        router
+    };
+    let router = router
         .route("/notarize", get(upgrade_protocol))
+        // Health checks are not authorized, so that they can be used by orchestrators and load balancers
+        .route("/healthz", get(get_liveness))
+        .route("/readyz", get(get_readiness))
         .layer(CorsLayer::permissive())
-        .with_state(notary_globals);
-    let mut app = router.into_make_service();
+        .with_state(notary_globals.clone());
+    // Make the address of provers available to the handlers, to rate limit them
+    let mut app = router.into_make_service_with_connect_info::<SocketAddr>();
+
+    #[cfg(feature = "grpc")]
+    let grpc_server = match &config.grpc {
+        Some(grpc) => Some(GrpcServer::start(config, grpc, notary_globals.clone()).await?),
+        None => None,
+    };
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc.is_some() {
+        return Err(eyre!(
+            "Notary server is built without support for the gRPC API, enable the grpc feature to use it"
+        )
+        .into());
+    }
+
+    // Stop serving once the ongoing notarizations have finished after a shutdown signal, connections are
+    // still accepted meanwhile so that provers can reconnect to resumable sessions
+    let shutdown = shutdown_when_drained(
+        notary_globals,
+        Duration::from_secs(config.server.shutdown_grace_period),
+    );
+    tokio::pin!(shutdown);
 
     loop {
         // Poll and await for any incoming connection, ensure that all operations inside are infallible to prevent bringing down the server
-        let (_, stream) = match poll_fn(|cx| Pin::new(&mut listener).poll_accept(cx)).await {
+        let connection = tokio::select! {
+            connection = poll_fn(|cx| Pin::new(&mut listener).poll_accept(cx)) => connection,
+            _ = &mut shutdown => break,
+        };
+        let (_, stream) = match connection {
             Some(Ok(connection)) => (connection.remote_addr(), connection),
             Some(Err(err)) => {
                 error!("{}", NotaryServerError::Connection(err.to_string()));
@@ -180,8 +402,38 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
 
         // Spawn a new async task to handle the new connection
         tokio::spawn(async move {
-            // When TLS is enabled
-            if let Some(acceptor) = tls_acceptor {
+            // When TLS is enabled with a certificate provisioned via ACME
+            if let Some(TlsTermination::Acme { acceptor, config }) = tls_acceptor {
+                // The acceptor answers TLS-ALPN-01 challenges of the ACME provider itself
+                let start_handshake = match acceptor.accept(stream.compat()).await {
+                    Ok(Some(start_handshake)) => start_handshake,
+                    Ok(None) => {
+                        debug!("Answered ACME TLS-ALPN-01 challenge");
+                        return;
+                    }
+                    Err(err) => {
+                        error!("{}", NotaryServerError::Connection(err.to_string()));
+                        return;
+                    }
+                };
+                match start_handshake.into_stream(config).await {
+                    Ok(stream) => {
+                        info!("Accepted prover's TLS-secured TCP connection");
+                        // Serve different requests using the same hyper protocol and axum router
+                        let _ = protocol
+                            // Can unwrap because it's infallible
+                            .serve_connection(stream.compat(), service.await.unwrap())
+                            // use with_upgrades to upgrade connection to websocket for websocket clients
+                            // and to extract tcp connection for tcp clients
+                            .with_upgrades()
+                            .await;
+                    }
+                    Err(err) => {
+                        error!("{}", NotaryServerError::Connection(err.to_string()));
+                    }
+                }
+            // When TLS is enabled with a static certificate
+            } else if let Some(TlsTermination::Static(acceptor)) = tls_acceptor {
                 match acceptor.accept(stream).await {
                     Ok(stream) => {
                         info!("Accepted prover's TLS-secured TCP connection");
@@ -212,6 +464,197 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
             }
         });
     }
+
+    // Notarizations over gRPC are drained with the others, so that only idle calls are left
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_server) = grpc_server {
+        grpc_server.stop().await;
+    }
+
+    info!("Notary server shut down");
+    Ok(())
+}
+
+/// Wait for SIGINT or SIGTERM, then refuse new sessions and wait until the ongoing notarizations have
+/// finished or the grace period has passed
+async fn shutdown_when_drained(notary_globals: NotaryGlobals, grace_period: Duration) {
+    shutdown_signal().await;
+    info!(
+        active_sessions = notary_globals.metrics.active_sessions(),
+        "Received shutdown signal, draining sessions for up to {grace_period:?}"
+    );
+    notary_globals.draining.store(true, Ordering::Relaxed);
+
+    let drained = async {
+        while notary_globals.metrics.active_sessions() > 0 {
+            tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+    };
+    if tokio::time::timeout(grace_period, drained).await.is_err() {
+        error!(
+            active_sessions = notary_globals.metrics.active_sessions(),
+            "Grace period ended before all notarizations finished"
+        );
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for SIGINT: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                error!("Failed to listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// How TLS connections from provers are terminated
+#[derive(Clone)]
+enum TlsTermination {
+    /// With the certificate loaded from the PEM files
+    Static(TlsAcceptor),
+    /// With a certificate provisioned and renewed via ACME
+    Acme {
+        acceptor: Arc<AcmeAcceptor>,
+        config: Arc<ServerConfig>,
+    },
+}
+
+/// Set up provisioning and renewal of the TLS certificate via ACME, using the TLS-ALPN-01 challenge
+fn setup_acme(acme: &AcmeProperties) -> TlsTermination {
+    let mut state = AcmeConfig::new(acme.domains.clone())
+        .contact(acme.contacts.iter().map(|email| format!("mailto:{email}")))
+        .cache(DirCache::new(acme.cache_dir.clone()))
+        .directory(&acme.directory_url)
+        .state();
+
+    let mut server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(state.resolver());
+    // Set the http protocols we support
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    let acceptor = state.acceptor();
+
+    // Drive the ACME state machine, which orders the certificate and renews it before it expires
+    tokio::spawn(async move {
+        while let Some(event) = state.next().await {
+            match event {
+                Ok(event) => info!("ACME event: {:?}", event),
+                Err(err) => error!("ACME error: {:?}", err),
+            }
+        }
+    });
+    debug!(domains = ?acme.domains, "Successfully set up ACME certificate provisioning!");
+
+    TlsTermination::Acme {
+        acceptor: Arc::new(acceptor),
+        config: Arc::new(server_config),
+    }
+}
+
+/// Load the notary signing key with its public key, and request a quote binding the public key to this
+/// server if it runs in a TEE
+pub(crate) async fn load_notary_key(
+    config: &NotarySigningKeyProperties,
+    tee_platform: Option<TeePlatform>,
+) -> Result<CurrentKey> {
+    let signer = load_notary_signer(config).await?;
+    let public_key = std::fs::read_to_string(&config.public_key_pem_path)
+        .map_err(|err| eyre!("Failed to load notary public signing key for notarization: {err}"))?;
+    let tee_quote = match tee_platform {
+        Some(platform) => {
+            let tee_quote = generate_tee_quote(platform, SignatureAlgorithm::P256, &public_key)?;
+            info!("Generated {platform:?} quote of the notary public key");
+            Some(tee_quote)
+        }
+        None => None,
+    };
+    Ok(CurrentKey {
+        signer,
+        algorithm: SignatureAlgorithm::P256,
+        public_key,
+        tee_quote,
+    })
+}
+
+/// Load the keys of other signature algorithms that provers can request, with a quote binding each of them
+/// to this server if it runs in a TEE
+fn load_additional_keys(
+    config: &[AdditionalSigningKeyProperties],
+    tee_platform: Option<TeePlatform>,
+) -> Result<HashMap<SignatureAlgorithm, CurrentKey>> {
+    let mut keys = HashMap::new();
+    for key in config {
+        let algorithm = key.algorithm;
+        ensure!(
+            algorithm != SignatureAlgorithm::P256,
+            "Additional notary keys can't be P-256, which is the algorithm of the main notary key"
+        );
+        ensure!(
+            keys.insert(algorithm, load_additional_key(key, tee_platform)?)
+                .is_none(),
+            "More than 1 additional notary key with algorithm {algorithm:?}"
+        );
+        debug!("Loaded additional {algorithm:?} notary signing key");
+    }
+    Ok(keys)
+}
+
+/// Load a key of another signature algorithm than P-256, with a quote binding it to this server if it runs
+/// in a TEE
+pub(crate) fn load_additional_key(
+    config: &AdditionalSigningKeyProperties,
+    tee_platform: Option<TeePlatform>,
+) -> Result<CurrentKey> {
+    let algorithm = config.algorithm;
+    let signer = load_local_signer(algorithm, &config.private_key_pem_path)?;
+    let public_key = std::fs::read_to_string(&config.public_key_pem_path).map_err(|err| {
+        eyre!("Failed to load {algorithm:?} notary public key for notarization: {err}")
+    })?;
+    // Fail early on a public key which can't be published to verifiers
+    parse_public_key(algorithm, &public_key)?;
+    let tee_quote = tee_platform
+        .map(|platform| generate_tee_quote(platform, algorithm, &public_key))
+        .transpose()?;
+    Ok(CurrentKey {
+        signer,
+        algorithm,
+        public_key,
+        tee_quote,
+    })
+}
+
+/// Set up the signer of the session headers, using the configured KMS or HSM if any, otherwise the
+/// signing key from static file
+async fn load_notary_signer(config: &NotarySigningKeyProperties) -> Result<Arc<dyn NotarySigner>> {
+    let Some(backend) = &config.backend else {
+        return Ok(Arc::new(LocalSigner::new(
+            load_notary_signing_key(config).await?,
+        )));
+    };
+    debug!("Connecting to notary signing key backend {backend:?}");
+    let signer = connect_signer(backend).await?;
+    debug!("Successfully connected to notary signing key backend!");
+    Ok(signer)
 }
 
 /// Load notary signing key from static file
@@ -256,6 +699,52 @@ async fn load_tls_key_and_cert(
     Ok((private_key, certificates))
 }
 
+/// Open the SQLite database storing the attestations if it is enabled
+fn load_attestation_store(
+    config: &NotaryServerProperties,
+) -> Result<Option<Arc<dyn AttestationStore>>> {
+    if !config.storage.enabled {
+        debug!("Skipping attestation storage as it is turned off.");
+        return Ok(None);
+    }
+    let store = SqliteAttestationStore::open(&config.storage.sqlite_path)?;
+    debug!("Successfully opened attestation database!");
+    Ok(Some(Arc::new(store)))
+}
+
+/// Connect to the shared session store if it is configured, otherwise keep the sessions in memory
+async fn load_session_store(config: &NotaryServerProperties) -> Result<Arc<dyn SessionStore>> {
+    let Some(redis_url) = &config.session_store.redis_url else {
+        debug!("Keeping session data in memory as no shared session store is configured.");
+        return Ok(Arc::new(MemorySessionStore::default()));
+    };
+    let store = RedisSessionStore::connect(redis_url, config.session_store.session_ttl).await?;
+    debug!("Successfully connected to redis session store!");
+    Ok(Arc::new(store))
+}
+
+/// Set up the sink of the audit log if it is enabled
+async fn load_audit_sink(config: &NotaryServerProperties) -> Result<Option<Arc<dyn AuditSink>>> {
+    let Some(audit) = &config.audit else {
+        debug!("Skipping audit log as it is turned off.");
+        return Ok(None);
+    };
+    let sink = connect_audit_sink(audit).await?;
+    debug!(?sink, "Successfully set up audit log sink!");
+    Ok(Some(sink))
+}
+
+/// Set up the billing webhook if its url is set
+fn load_billing_hook(config: &NotaryServerProperties) -> Option<Arc<dyn BillingHook>> {
+    let Some(webhook_url) = &config.billing.webhook_url else {
+        debug!("Skipping billing webhook as its url is not set.");
+        return None;
+    };
+    let hook = WebhookBillingHook::new(webhook_url, config.billing.webhook_headers.clone());
+    debug!(?hook, "Successfully set up billing webhook!");
+    Some(Arc::new(hook))
+}
+
 /// Load authorization whitelist if it is enabled
 fn load_authorization_whitelist(
     config: &NotaryServerProperties,
@@ -263,6 +752,9 @@ fn load_authorization_whitelist(
     let authorization_whitelist = if !config.authorization.enabled {
         debug!("Skipping authorization as it is turned off.");
         None
+    } else if config.authorization.whitelist_csv_path.is_empty() {
+        debug!("Skipping authorization whitelist as its path is not set.");
+        None
     } else {
         // Load the csv
         let whitelist_csv = parse_csv_file::<AuthorizationWhitelistRecord>(
@@ -357,6 +849,7 @@ mod test {
         let config = NotarySigningKeyProperties {
             private_key_pem_path: "./fixture/notary/notary.key".to_string(),
             public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            backend: None,
         };
         let result: Result<SigningKey> = load_notary_signing_key(&config).await;
         assert!(result.is_ok(), "Could not load notary private key");
@@ -374,6 +867,7 @@ mod test {
             authorization: AuthorizationProperties {
                 enabled: true,
                 whitelist_csv_path,
+                oidc: None,
             },
             ..Default::default()
         };
@@ -396,6 +890,9 @@ mod test {
             name: "unit-test-name".to_string(),
             api_key: "unit-test-api-key".to_string(),
             created_at: "unit-test-created-at".to_string(),
+            max_sent_data: Some(1024),
+            max_recv_data: None,
+            max_monthly_data: None,
         };
         let file = OpenOptions::new()
             .append(true)
@@ -4,30 +4,52 @@ pub mod websocket;
 
 use async_trait::async_trait;
 use axum::{
-    extract::{rejection::JsonRejection, FromRequestParts, Query, State},
+    extract::{rejection::JsonRejection, ConnectInfo, FromRequestParts, Path, Query, State},
     http::{header, request::Parts, StatusCode},
     response::{IntoResponse, Json, Response},
+    Extension,
 };
 use axum_macros::debug_handler;
-use chrono::Utc;
-use p256::ecdsa::{Signature, SigningKey};
-use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use eyre::eyre;
+use futures::{channel::mpsc, SinkExt};
+use p256::ecdsa::signature;
+use rand::RngCore;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{atomic::Ordering, Mutex},
+    time::Instant,
+};
+use tlsn_core::{tee::TeeQuote, SessionHeader, Signature, SignatureAlgorithm};
+use tlsn_verifier::tls::{AsyncSigner, Compression, Socket, Verifier, VerifierConfig};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::compat::TokioAsyncReadCompatExt;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 use uuid::Uuid;
 
 use crate::{
-    domain::notary::{
-        NotarizationRequestQuery, NotarizationSessionRequest, NotarizationSessionResponse,
-        NotaryGlobals, SessionData,
+    audit::{AuditEntry, AuditOutcome},
+    billing::SessionUsage,
+    concurrency::SessionPermit,
+    config::NotarizationProperties,
+    domain::{
+        attestation::AttestationRecord,
+        auth::AuthorizedClient,
+        notary::{
+            NotarizationRequestQuery, NotarizationSessionRequest, NotarizationSessionResponse,
+            NotaryGlobals, SessionData,
+        },
     },
     error::NotaryServerError,
+    keys::CurrentKey,
+    metrics::Metrics,
     service::{
         axum_websocket::{header_eq, WebSocketUpgrade},
         tcp::{tcp_notarize, TcpUpgrade},
-        websocket::websocket_notarize,
+        websocket::{websocket_notarize, websocket_stream},
     },
+    signer::NotarySigner,
 };
 
 /// A wrapper enum to facilitate extracting TCP connection for either WebSocket or TCP clients,
@@ -71,41 +93,193 @@ where
 pub async fn upgrade_protocol(
     protocol_upgrade: ProtocolUpgrade,
     State(notary_globals): State<NotaryGlobals>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
     Query(params): Query<NotarizationRequestQuery>,
 ) -> Response {
     info!("Received upgrade protocol request");
     let session_id = params.session_id;
-    // Fetch the configuration data from the store using the session_id
-    // This also removes the configuration data from the store as each session_id can only be used once
-    let (max_sent_data, max_recv_data) = match notary_globals.store.lock().await.remove(&session_id)
+    let start = match start_session(
+        &notary_globals,
+        &session_id,
+        params.resumption_secret.as_deref(),
+        address.ip(),
+    )
+    .await
     {
-        Some(data) => (data.max_sent_data, data.max_recv_data),
-        None => {
-            let err_msg = format!("Session id {} does not exist", session_id);
-            error!(err_msg);
-            return NotaryServerError::BadProverRequest(err_msg).into_response();
-        }
+        Ok(start) => start,
+        Err(err) => return err.into_response(),
     };
     // This completes the HTTP Upgrade request and returns a successful response to the client, meanwhile initiating the websocket or tcp connection
-    match protocol_upgrade {
-        ProtocolUpgrade::Ws(ws) => ws.on_upgrade(move |socket| {
+    match (start, protocol_upgrade) {
+        (
+            SessionStart::New {
+                session_data,
+                key,
+                resumption,
+                permit,
+            },
+            ProtocolUpgrade::Ws(ws),
+        ) => ws.on_upgrade(move |socket| {
             websocket_notarize(
                 socket,
                 notary_globals,
                 session_id,
-                max_sent_data,
-                max_recv_data,
+                session_data,
+                key,
+                resumption,
+                permit,
             )
         }),
-        ProtocolUpgrade::Tcp(tcp) => tcp.on_upgrade(move |stream| {
+        (
+            SessionStart::New {
+                session_data,
+                key,
+                resumption,
+                permit,
+            },
+            ProtocolUpgrade::Tcp(tcp),
+        ) => tcp.on_upgrade(move |stream| {
             tcp_notarize(
                 stream,
                 notary_globals,
                 session_id,
-                max_sent_data,
-                max_recv_data,
+                session_data,
+                key,
+                resumption,
+                permit,
             )
         }),
+        // Pass the connection to the ongoing session if the prover is reconnecting to it
+        (SessionStart::Resume(sender), ProtocolUpgrade::Ws(ws)) => ws.on_upgrade(move |socket| {
+            resume_session(
+                Box::new(websocket_stream(socket, &notary_globals.websocket_config).compat()),
+                sender,
+                session_id,
+            )
+        }),
+        (SessionStart::Resume(sender), ProtocolUpgrade::Tcp(tcp)) => {
+            tcp.on_upgrade(move |stream| {
+                resume_session(Box::new(stream.compat()), sender, session_id)
+            })
+        }
+    }
+}
+
+/// How a prover connecting with a session id takes part in a notarization
+pub enum SessionStart {
+    /// Start the notarization of a configured session
+    New {
+        session_data: SessionData,
+        /// Key to sign the session header with
+        key: CurrentKey,
+        resumption: Option<mpsc::Receiver<Box<dyn Socket>>>,
+        /// Worker running the MPC protocol of the session, which is freed once it is dropped
+        permit: SessionPermit,
+    },
+    /// Reconnect to an ongoing resumable session
+    Resume(mpsc::Sender<Box<dyn Socket>>),
+}
+
+/// Look up the session a prover is connecting to with its session id, waiting for a free worker to run
+/// new sessions on. Provers reconnecting to an ongoing resumable session must present its resumption secret
+pub async fn start_session(
+    notary_globals: &NotaryGlobals,
+    session_id: &str,
+    resumption_secret: Option<&str>,
+    address: IpAddr,
+) -> Result<SessionStart, NotaryServerError> {
+    let resumption = notary_globals
+        .resumptions
+        .get(session_id, resumption_secret);
+    // While draining, only provers reconnecting to ongoing resumable sessions are accepted. New sessions are
+    // left in the store, so that they can be started on another instance sharing it
+    if notary_globals.draining.load(Ordering::Relaxed) && resumption.is_none() {
+        error!(
+            ?session_id,
+            "Refused to start a notarization session as the server is draining"
+        );
+        return Err(NotaryServerError::Unavailable(
+            "Server is draining sessions".to_string(),
+        ));
+    }
+    if let Some(sender) = resumption {
+        return Ok(SessionStart::Resume(sender));
+    }
+    // Wait for a free worker before taking the session from the store, so that a refused prover can retry with
+    // the same session_id
+    let permit = match notary_globals.session_limiter.acquire(address).await {
+        Ok(permit) => permit,
+        Err(err) => {
+            warn!(
+                ?session_id,
+                ?address,
+                "Refused to start a notarization session as the server is overloaded: {err}"
+            );
+            return Err(err.into());
+        }
+    };
+    // Fetch the configuration data from the store using the session_id
+    // This also removes the configuration data from the store as each session_id can only be used once
+    let session_data = match notary_globals.store.take(session_id).await {
+        Ok(session_data) => session_data,
+        Err(err) => {
+            error!(
+                ?session_id,
+                "Failed to fetch session data from store: {err}"
+            );
+            return Err(err.into());
+        }
+    };
+    match session_data {
+        Some(session_data) => {
+            // Sign with the key current at the start of the session, even if it is rotated meanwhile. The key
+            // is missing if the session was configured on another instance sharing the session store
+            let Some(key) = notary_globals
+                .notary_keys
+                .for_algorithm(session_data.signature_algorithm)
+            else {
+                let err_msg = format!(
+                    "Signature algorithm {:?} of session id {} is not supported by this notary",
+                    session_data.signature_algorithm, session_id
+                );
+                error!(err_msg);
+                return Err(NotaryServerError::BadProverRequest(err_msg));
+            };
+            // Register resumable sessions so that the prover can reconnect with the same session_id and its
+            // resumption secret
+            let resumption = session_data.resumption_secret.clone().map(|secret| {
+                notary_globals
+                    .resumptions
+                    .register(session_id.to_string(), secret)
+            });
+            info!(?session_id, client_name = ?session_data.client_name, "Starting notarization session");
+            Ok(SessionStart::New {
+                session_data,
+                key,
+                resumption,
+                permit,
+            })
+        }
+        None => {
+            let err_msg = format!("Session id {} does not exist", session_id);
+            error!(err_msg);
+            Err(NotaryServerError::BadProverRequest(err_msg))
+        }
+    }
+}
+
+/// Pass the connection of a prover reconnecting to a resumable session to the ongoing notarization
+pub async fn resume_session(
+    socket: Box<dyn Socket>,
+    mut sender: mpsc::Sender<Box<dyn Socket>>,
+    session_id: String,
+) {
+    match sender.send(socket).await {
+        Ok(_) => info!(?session_id, "Prover reconnected to resumable session"),
+        Err(_) => error!(
+            ?session_id,
+            "Resumable session finished before prover reconnected"
+        ),
     }
 }
 
@@ -113,89 +287,538 @@ pub async fn upgrade_protocol(
 #[debug_handler(state = NotaryGlobals)]
 pub async fn initialize(
     State(notary_globals): State<NotaryGlobals>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    client: Option<Extension<AuthorizedClient>>,
     payload: Result<Json<NotarizationSessionRequest>, JsonRejection>,
 ) -> impl IntoResponse {
     info!(
         ?payload,
+        client_name = ?client.as_ref().map(|Extension(client)| &client.name),
         "Received request for initializing a notarization session"
     );
 
     // Parse the body payload
     let payload = match payload {
-        Ok(payload) => payload,
+        Ok(Json(payload)) => payload,
         Err(err) => {
             error!("Malformed payload submitted for initializing notarization: {err}");
             return NotaryServerError::BadProverRequest(err.to_string()).into_response();
         }
     };
 
+    match configure_session(
+        &notary_globals,
+        client.as_ref().map(|Extension(client)| client),
+        address.ip(),
+        payload.max_sent_data,
+        payload.max_recv_data,
+        payload.resumable.unwrap_or_default(),
+        payload.signature_algorithm,
+    )
+    .await
+    {
+        // Return the session id in the response to the client
+        Ok((session_id, resumption_secret)) => (
+            StatusCode::OK,
+            Json(NotarizationSessionResponse {
+                session_id,
+                resumption_secret,
+            }),
+        )
+            .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Configure a notarization session after checking its limits against those of the client and the server,
+/// returning the id of the session and, if it is resumable, its resumption secret
+pub async fn configure_session(
+    notary_globals: &NotaryGlobals,
+    client: Option<&AuthorizedClient>,
+    address: IpAddr,
+    mut max_sent_data: Option<usize>,
+    mut max_recv_data: Option<usize>,
+    resumable: bool,
+    signature_algorithm: Option<SignatureAlgorithm>,
+) -> Result<(String, Option<String>), NotaryServerError> {
+    let client_name = client.map(|client| client.name.clone());
+
+    // Refuse new sessions while the server is draining
+    if notary_globals.draining.load(Ordering::Relaxed) {
+        error!("Refused to initialize a notarization session as the server is draining");
+        return Err(NotaryServerError::Unavailable(
+            "Server is draining sessions".to_string(),
+        ));
+    }
+
+    // Apply the limits configured for the client, which are used if the prover does not request any
+    if let Some(client) = client {
+        for (requested, limit, kind) in [
+            (&mut max_sent_data, client.max_sent_data, "sent"),
+            (&mut max_recv_data, client.max_recv_data, "received"),
+        ] {
+            let Some(limit) = limit else {
+                continue;
+            };
+            match *requested {
+                Some(size) if size > limit => {
+                    error!(
+                        ?client_name,
+                        "Max {kind} data requested {size} exceeds the limit of the client {limit}"
+                    );
+                    return Err(NotaryServerError::BadProverRequest(format!(
+                        "Max {kind} data requested exceeds the limit of the client"
+                    )));
+                }
+                Some(_) => {}
+                None => *requested = Some(limit),
+            }
+        }
+    }
+
     // Ensure that the max_transcript_size submitted is not larger than the global max limit configured in notary server
-    if payload.max_sent_data.is_some() || payload.max_recv_data.is_some() {
+    if max_sent_data.is_some() || max_recv_data.is_some() {
         let requested_transcript_size =
-            payload.max_sent_data.unwrap_or_default() + payload.max_recv_data.unwrap_or_default();
-        if requested_transcript_size > notary_globals.notarization_config.max_transcript_size {
+            max_sent_data.unwrap_or_default() + max_recv_data.unwrap_or_default();
+        let max_transcript_size = notary_globals
+            .notarization_config
+            .read()
+            .unwrap()
+            .max_transcript_size;
+        if requested_transcript_size > max_transcript_size {
             error!(
                 "Max transcript size requested {:?} exceeds the maximum threshold {:?}",
-                requested_transcript_size, notary_globals.notarization_config.max_transcript_size
+                requested_transcript_size, max_transcript_size
             );
-            return NotaryServerError::BadProverRequest(
+            return Err(NotaryServerError::BadProverRequest(
                 "Max transcript size requested exceeds the maximum threshold".to_string(),
-            )
-            .into_response();
+            ));
         }
     }
 
+    // Only algorithms of the keys loaded by the operator can be requested
+    let signature_algorithm = signature_algorithm.unwrap_or_default();
+    if notary_globals
+        .notary_keys
+        .for_algorithm(signature_algorithm)
+        .is_none()
+    {
+        error!(
+            ?client_name,
+            "Signature algorithm {signature_algorithm:?} requested is not supported"
+        );
+        return Err(NotaryServerError::BadProverRequest(format!(
+            "Signature algorithm {signature_algorithm:?} is not supported by this notary"
+        )));
+    }
+
+    // Enforce the rate limit of the client before any resources are spent on the session, clients are
+    // identified by their API key if authorization is enabled, otherwise by their IP address
+    let rate_limit_key = client_name.clone().unwrap_or_else(|| address.to_string());
+    let transcript_size = session_transcript_size(max_sent_data, max_recv_data);
+    let monthly_limit = client.and_then(|client| client.max_monthly_data);
+    let rate_limit = notary_globals
+        .rate_limiter
+        .check(&rate_limit_key, transcript_size, monthly_limit)
+        .await
+        .map_err(|err| {
+            error!("Failed to check rate limit: {err}");
+            err
+        })?;
+    if let Err(err) = rate_limit {
+        error!(?client_name, %address, "Rate limit exceeded: {err}");
+        return Err(err.into());
+    }
+
     let prover_session_id = Uuid::new_v4().to_string();
+    let resumption_secret = resumable.then(resumption_secret);
 
     // Store the configuration data in a temporary store
-    notary_globals.store.lock().await.insert(
-        prover_session_id.clone(),
-        SessionData {
-            max_sent_data: payload.max_sent_data,
-            max_recv_data: payload.max_recv_data,
-            created_at: Utc::now(),
-        },
-    );
+    if let Err(err) = notary_globals
+        .store
+        .insert(
+            &prover_session_id,
+            SessionData {
+                max_sent_data,
+                max_recv_data,
+                resumable,
+                resumption_secret: resumption_secret.clone(),
+                created_at: Utc::now(),
+                client_name,
+                signature_algorithm,
+            },
+        )
+        .await
+    {
+        error!("Failed to store session data: {err}");
+        return Err(err.into());
+    }
 
     trace!("Latest store state: {:?}", notary_globals.store);
 
-    // Return the session id in the response to the client
-    (
-        StatusCode::OK,
-        Json(NotarizationSessionResponse {
-            session_id: prover_session_id,
-        }),
-    )
-        .into_response()
+    Ok((prover_session_id, resumption_secret))
+}
+
+/// Random secret that a prover must present to reconnect to a resumable session
+fn resumption_secret() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    URL_SAFE_NO_PAD.encode(secret)
+}
+
+/// Transcript size that the prover can use in a session, falling back to the default limits of the verifier
+fn session_transcript_size(max_sent_data: Option<usize>, max_recv_data: Option<usize>) -> usize {
+    let defaults = VerifierConfig::builder()
+        .id("")
+        .build()
+        .expect("Default verifier config should be valid");
+    max_sent_data.unwrap_or(defaults.max_sent_data())
+        + max_recv_data.unwrap_or(defaults.max_recv_data())
 }
 
 /// Run the notarization
+#[allow(clippy::too_many_arguments)]
 pub async fn notary_service<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     socket: T,
-    signing_key: &SigningKey,
+    signer: &dyn NotarySigner,
     session_id: &str,
     max_sent_data: Option<usize>,
     max_recv_data: Option<usize>,
-) -> Result<(), NotaryServerError> {
+    resumption: Option<mpsc::Receiver<Box<dyn Socket>>>,
+    metrics: &Metrics,
+    tee_quote: Option<&TeeQuote>,
+    notarization: &NotarizationProperties,
+) -> Result<(SessionHeader, Signature), NotaryServerError> {
     debug!(?session_id, "Starting notarization...");
+    metrics.sessions_started.inc();
+
+    let result = async {
+        let mut config_builder = VerifierConfig::builder();
+
+        config_builder = config_builder.id(session_id);
+
+        if let Some(tee_quote) = tee_quote {
+            config_builder = config_builder.tee_quote(tee_quote.clone());
+        }
+
+        if let Some(max_sent_data) = max_sent_data {
+            config_builder = config_builder.max_sent_data(max_sent_data);
+        }
+
+        if let Some(max_recv_data) = max_recv_data {
+            config_builder = config_builder.max_recv_data(max_recv_data);
+        }
+
+        if let Some(max_threads) = notarization.max_threads {
+            config_builder = config_builder.max_threads(max_threads);
+        }
+
+        if !notarization.compressed_channels.is_empty() {
+            config_builder = config_builder.compression(Compression::new(
+                notarization.compressed_channels.iter().cloned(),
+            ));
+        }
+
+        let config = config_builder.build()?;
+
+        let socket = metrics.metered(socket);
+        let verifier = Verifier::new(config);
+        let start = Instant::now();
+        let verifier = match resumption {
+            Some(resumption) => {
+                verifier
+                    .setup_resumable(socket.compat(), resumption)
+                    .await?
+            }
+            None => verifier.setup(socket.compat()).await?,
+        };
+        metrics.observe_phase("setup", start);
+
+        let start = Instant::now();
+        let notarize = verifier.run().await?.start_notarize();
+        metrics.observe_phase("tls", start);
+
+        let signer = RecordingSigner {
+            signer,
+            signature: Mutex::new(None),
+            metrics,
+        };
+        let start = Instant::now();
+        let session_header = notarize.finalize_async(&signer).await?;
+        metrics.observe_phase("notarize", start);
+
+        let signature = signer
+            .signature
+            .into_inner()
+            .unwrap()
+            .ok_or_else(|| eyre!("Session header was not signed"))?;
+
+        Ok::<_, NotaryServerError>((session_header, signature))
+    }
+    .await;
+
+    match result {
+        Ok(_) => metrics.sessions_completed.inc(),
+        Err(_) => metrics.sessions_failed.inc(),
+    }
+    result
+}
+
+/// Signer which keeps the signature of the session header, to store it with the attestation
+struct RecordingSigner<'a> {
+    signer: &'a dyn NotarySigner,
+    signature: Mutex<Option<Signature>>,
+    metrics: &'a Metrics,
+}
+
+#[async_trait]
+impl AsyncSigner for RecordingSigner<'_> {
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+        let signature = self.signer.sign(msg).await?;
+        self.metrics.signatures.inc();
+        *self.signature.lock().unwrap() = Some(signature.clone());
+        Ok(signature)
+    }
+}
+
+/// Handler to export the metrics of the notary server in the Prometheus text format
+pub async fn get_metrics(
+    State(notary_globals): State<NotaryGlobals>,
+) -> Result<Response, NotaryServerError> {
+    let metrics = notary_globals.metrics.encode()?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics,
+    )
+        .into_response())
+}
+
+/// Handler to publish the current and previous public keys of the notary with their validity windows
+pub async fn get_keys(State(notary_globals): State<NotaryGlobals>) -> impl IntoResponse {
+    (StatusCode::OK, Json(notary_globals.notary_keys.published()))
+}
 
-    let mut config_builder = VerifierConfig::builder();
+/// Persist the attestation of a successful notarization, if attestation storage is enabled, returning
+/// whether it was stored
+pub async fn store_attestation(
+    notary_globals: &NotaryGlobals,
+    session_id: &str,
+    session_data: &SessionData,
+    session_header: SessionHeader,
+    signature: Signature,
+) -> bool {
+    let Some(store) = notary_globals.attestation_store.clone() else {
+        return false;
+    };
+    let record = AttestationRecord {
+        id: session_id.to_string(),
+        client_name: session_data.client_name.clone(),
+        created_at: session_data.created_at,
+        notarized_at: Utc::now(),
+        session_header,
+        signature,
+    };
+    // Storage backends may block, e.g. on disk io
+    match tokio::task::spawn_blocking(move || store.insert(&record)).await {
+        Ok(Ok(())) => {
+            debug!(?session_id, "Stored attestation");
+            true
+        }
+        Ok(Err(err)) => {
+            error!(?session_id, "Failed to store attestation: {err}");
+            false
+        }
+        Err(err) => {
+            error!(?session_id, "Failed to store attestation: {err}");
+            false
+        }
+    }
+}
 
-    config_builder = config_builder.id(session_id);
+/// Finish a notarization, storing its attestation if it succeeded and recording it in the audit log
+pub async fn finish_session(
+    notary_globals: &NotaryGlobals,
+    session_id: &str,
+    session_data: &SessionData,
+    started_at: DateTime<Utc>,
+    result: Result<(SessionHeader, Signature), NotaryServerError>,
+) {
+    let outcome = match result {
+        Ok((session_header, signature)) => {
+            let (sent_len, recv_len) = (session_header.sent_len(), session_header.recv_len());
+            if let Some(billing) = &notary_globals.billing {
+                let finished_at = Utc::now();
+                let usage = SessionUsage {
+                    session_id: session_id.to_string(),
+                    client_name: session_data.client_name.clone(),
+                    sent_len,
+                    recv_len,
+                    started_at,
+                    finished_at,
+                    mpc_duration_ms: (finished_at - started_at).num_milliseconds().max(0) as u64,
+                    signature_algorithm: session_data.signature_algorithm,
+                };
+                billing.record(usage, &notary_globals.notary_keys).await;
+            }
+            let stored = store_attestation(
+                notary_globals,
+                session_id,
+                session_data,
+                session_header,
+                signature,
+            )
+            .await;
+            AuditOutcome::Success {
+                sent_len,
+                recv_len,
+                // Attestations are stored by session id
+                attestation_id: stored.then(|| session_id.to_string()),
+            }
+        }
+        Err(err) => AuditOutcome::Failure {
+            error: err.to_string(),
+        },
+    };
 
-    if let Some(max_sent_data) = max_sent_data {
-        config_builder = config_builder.max_sent_data(max_sent_data);
+    let Some(audit_log) = &notary_globals.audit_log else {
+        return;
+    };
+    let entry = AuditEntry {
+        session_id: session_id.to_string(),
+        client_name: session_data.client_name.clone(),
+        created_at: session_data.created_at,
+        started_at,
+        max_sent_data: session_data.max_sent_data,
+        max_recv_data: session_data.max_recv_data,
+        outcome,
+    };
+    if let Err(err) = audit_log.record(entry).await {
+        error!(?session_id, "Failed to record session in audit log: {err}");
     }
+}
+
+/// Handler to retrieve a stored attestation by its id, i.e. the session id
+pub async fn get_attestation(
+    State(notary_globals): State<NotaryGlobals>,
+    client: Option<Extension<AuthorizedClient>>,
+    Path(id): Path<String>,
+) -> Result<Json<AttestationRecord>, NotaryServerError> {
+    fetch_attestation(
+        &notary_globals,
+        client.as_ref().map(|Extension(client)| client),
+        id,
+    )
+    .await
+    .map(Json)
+}
+
+/// Look up a stored attestation by its id, only returning attestations of sessions of the
+/// authorized client if authorization is turned on
+pub async fn fetch_attestation(
+    notary_globals: &NotaryGlobals,
+    client: Option<&AuthorizedClient>,
+    id: String,
+) -> Result<AttestationRecord, NotaryServerError> {
+    let Some(store) = notary_globals.attestation_store.clone() else {
+        return Err(NotaryServerError::NotFound(
+            "Attestation storage is not enabled".to_string(),
+        ));
+    };
+    let record = tokio::task::spawn_blocking(move || store.get(&id))
+        .await
+        .map_err(|err| eyre!("Failed to retrieve attestation: {err}"))??;
+
+    // Attestations of other clients are reported as missing, so that their ids can't be probed
+    match record {
+        Some(record)
+            if client.map_or(true, |client| {
+                record.client_name.as_ref() == Some(&client.name)
+            }) =>
+        {
+            Ok(record)
+        }
+        _ => Err(NotaryServerError::NotFound(
+            "Attestation does not exist".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        domain::{attestation::AttestationStore, notary::MemorySessionStore},
+        keys::{CurrentKey, NotaryKeys},
+        signer::LocalSigner,
+        storage::SqliteAttestationStore,
+    };
+    use p256::{
+        ecdsa::{signature::Signer, Signature as P256Signature, SigningKey},
+        pkcs8::DecodePrivateKey,
+    };
+    use std::sync::Arc;
+    use tlsn_core::{fixtures, merkle::MerkleRoot};
 
-    if let Some(max_recv_data) = max_recv_data {
-        config_builder = config_builder.max_recv_data(max_recv_data);
+    fn notary_globals(attestation_store: Arc<dyn AttestationStore>) -> NotaryGlobals {
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let key = CurrentKey {
+            signer: Arc::new(LocalSigner::new(signing_key)),
+            algorithm: SignatureAlgorithm::P256,
+            public_key: std::fs::read_to_string("./fixture/notary/notary.pub").unwrap(),
+            tee_quote: None,
+        };
+        NotaryGlobals::new(
+            Arc::new(NotaryKeys::new(key, None, None).unwrap()),
+            Default::default(),
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            Some(attestation_store),
+            Default::default(),
+            Arc::new(MemorySessionStore::default()),
+            None,
+            None,
+            None,
+        )
     }
 
-    let config = config_builder.build()?;
+    fn client(name: &str) -> AuthorizedClient {
+        AuthorizedClient {
+            name: name.to_string(),
+            max_sent_data: None,
+            max_recv_data: None,
+            max_monthly_data: None,
+        }
+    }
 
-    Verifier::new(config)
-        .notarize::<_, Signature>(socket.compat(), signing_key)
-        .await?;
+    #[tokio::test]
+    async fn test_fetch_attestation_of_other_client() {
+        let store = SqliteAttestationStore::open(":memory:").unwrap();
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let signature: P256Signature = signing_key.sign(b"session header");
+        store
+            .insert(&AttestationRecord {
+                id: "test-session-id".to_string(),
+                client_name: Some("test-name-0".to_string()),
+                created_at: Utc::now(),
+                notarized_at: Utc::now(),
+                session_header: fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10),
+                signature: signature.into(),
+            })
+            .unwrap();
+        let notary_globals = notary_globals(Arc::new(store));
 
-    Ok(())
+        let id = || "test-session-id".to_string();
+        assert!(fetch_attestation(&notary_globals, None, id()).await.is_ok());
+        assert!(
+            fetch_attestation(&notary_globals, Some(&client("test-name-0")), id())
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            fetch_attestation(&notary_globals, Some(&client("test-name-1")), id()).await,
+            Err(NotaryServerError::NotFound(_))
+        ));
+    }
 }
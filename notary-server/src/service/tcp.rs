@@ -5,11 +5,20 @@ use axum::{
     http::{header, request::Parts, HeaderValue, StatusCode},
     response::Response,
 };
+use chrono::Utc;
+use futures::channel::mpsc;
 use hyper::upgrade::{OnUpgrade, Upgraded};
 use std::future::Future;
+use tlsn_verifier::tls::Socket;
 use tracing::{debug, error, info};
 
-use crate::{domain::notary::NotaryGlobals, service::notary_service, NotaryServerError};
+use crate::{
+    concurrency::SessionPermit,
+    domain::notary::{NotaryGlobals, SessionData},
+    keys::CurrentKey,
+    service::{finish_session, notary_service},
+    NotaryServerError,
+};
 
 /// Custom extractor used to extract underlying TCP connection for TCP client — using the same upgrade primitives used by
 /// the WebSocket implementation where the underlying TCP connection (wrapped in an Upgraded object) only gets polled as an OnUpgrade future
@@ -80,24 +89,41 @@ pub async fn tcp_notarize(
     stream: Upgraded,
     notary_globals: NotaryGlobals,
     session_id: String,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
+    session_data: SessionData,
+    key: CurrentKey,
+    resumption: Option<mpsc::Receiver<Box<dyn Socket>>>,
+    permit: SessionPermit,
 ) {
     debug!(?session_id, "Upgraded to tcp connection");
-    match notary_service(
+    let started_at = Utc::now();
+    // The policy may be reloaded during the session, which keeps the one it started with
+    let notarization = notary_globals.notarization_config.read().unwrap().clone();
+    let result = notary_service(
         stream,
-        &notary_globals.notary_signing_key,
+        key.signer.as_ref(),
         &session_id,
-        max_sent_data,
-        max_recv_data,
+        session_data.max_sent_data,
+        session_data.max_recv_data,
+        resumption,
+        &notary_globals.metrics,
+        key.tee_quote.as_ref(),
+        &notarization,
     )
-    .await
-    {
-        Ok(_) => {
-            info!(?session_id, "Successful notarization using tcp!");
-        }
-        Err(err) => {
-            error!(?session_id, "Failed notarization using tcp: {err}");
-        }
+    .await;
+    // Free the worker for queued sessions, as the MPC protocol is over
+    drop(permit);
+    match &result {
+        Ok(_) => info!(?session_id, "Successful notarization using tcp!"),
+        Err(err) => error!(?session_id, "Failed notarization using tcp: {err}"),
     }
+    finish_session(
+        &notary_globals,
+        &session_id,
+        &session_data,
+        started_at,
+        result,
+    )
+    .await;
+
+    notary_globals.resumptions.remove(&session_id);
 }
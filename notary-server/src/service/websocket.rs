@@ -1,36 +1,162 @@
-use tracing::{debug, error, info};
-use ws_stream_tungstenite::WsStream;
+use async_tungstenite::tungstenite::Message;
+use chrono::Utc;
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use tlsn_verifier::tls::Socket;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    domain::notary::NotaryGlobals,
-    service::{axum_websocket::WebSocket, notary_service},
+    concurrency::SessionPermit,
+    config::WebSocketProperties,
+    domain::notary::{NotaryGlobals, SessionData},
+    keys::CurrentKey,
+    service::{axum_websocket::WebSocket, finish_session, notary_service},
 };
 
+/// Size of the buffers between the websocket and the notarization, which is also the maximum size
+/// of the binary messages sent to the prover
+const BUFFER_SIZE: usize = 1 << 16;
+
 /// Perform notarization using the established websocket connection
 pub async fn websocket_notarize(
     socket: WebSocket,
     notary_globals: NotaryGlobals,
     session_id: String,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
+    session_data: SessionData,
+    key: CurrentKey,
+    resumption: Option<mpsc::Receiver<Box<dyn Socket>>>,
+    permit: SessionPermit,
 ) {
     debug!(?session_id, "Upgraded to websocket connection");
-    // Wrap the websocket in WsStream so that we have AsyncRead and AsyncWrite implemented
-    let stream = WsStream::new(socket.into_inner());
-    match notary_service(
+    let stream = websocket_stream(socket, &notary_globals.websocket_config);
+    let started_at = Utc::now();
+    // The policy may be reloaded during the session, which keeps the one it started with
+    let notarization = notary_globals.notarization_config.read().unwrap().clone();
+    let result = notary_service(
         stream,
-        &notary_globals.notary_signing_key,
+        key.signer.as_ref(),
+        &session_id,
+        session_data.max_sent_data,
+        session_data.max_recv_data,
+        resumption,
+        &notary_globals.metrics,
+        key.tee_quote.as_ref(),
+        &notarization,
+    )
+    .await;
+    // Free the worker for queued sessions, as the MPC protocol is over
+    drop(permit);
+    match &result {
+        Ok(_) => info!(?session_id, "Successful notarization using websocket!"),
+        Err(err) => error!(?session_id, "Failed notarization using websocket: {err}"),
+    }
+    finish_session(
+        &notary_globals,
         &session_id,
-        max_sent_data,
-        max_recv_data,
+        &session_data,
+        started_at,
+        result,
     )
-    .await
-    {
-        Ok(_) => {
-            info!(?session_id, "Successful notarization using websocket!");
+    .await;
+
+    notary_globals.resumptions.remove(&session_id);
+}
+
+/// Bridge a websocket to a byte stream for the notarization, where the data is carried in binary
+/// messages and the connection is kept alive with pings. The connection is closed if nothing is
+/// received from the prover, not even a pong, within the idle timeout
+pub fn websocket_stream(socket: WebSocket, config: &WebSocketProperties) -> DuplexStream {
+    let (stream, bridge) = tokio::io::duplex(BUFFER_SIZE);
+    tokio::spawn(bridge_websocket(
+        socket,
+        bridge,
+        Duration::from_secs(config.ping_interval),
+        Duration::from_secs(config.idle_timeout),
+    ));
+    stream
+}
+
+async fn bridge_websocket(
+    socket: WebSocket,
+    bridge: DuplexStream,
+    ping_interval: Duration,
+    idle_timeout: Duration,
+) {
+    let (mut ws_sink, mut ws_stream) = socket.into_inner().split();
+    let (mut reader, mut writer) = tokio::io::split(bridge);
+    let start = Instant::now();
+    // Milliseconds since start when the last message was received from the prover
+    let last_received = AtomicU64::new(0);
+
+    // Both directions are polled concurrently, so that neither blocks the other when buffers are full
+    let inbound = async {
+        while let Some(message) = ws_stream.next().await {
+            last_received.store(start.elapsed().as_millis() as u64, Ordering::Relaxed);
+            match message {
+                Ok(Message::Binary(data)) => {
+                    if writer.write_all(&data).await.is_err() {
+                        break;
+                    }
+                }
+                // Pings are answered by tungstenite, pongs only keep the connection alive
+                Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)) => {}
+                Ok(Message::Text(_)) => {
+                    warn!(
+                        "Received text message over websocket, only binary messages are supported"
+                    );
+                    break;
+                }
+                Ok(Message::Close(_)) => break,
+                Err(err) => {
+                    debug!("Websocket connection failed: {err}");
+                    break;
+                }
+            }
         }
-        Err(err) => {
-            error!(?session_id, "Failed notarization using websocket: {err}");
+    };
+
+    let outbound = async {
+        let mut buf = vec![0u8; BUFFER_SIZE];
+        let mut ping = tokio::time::interval(ping_interval);
+        ping.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        ping.tick().await;
+        loop {
+            tokio::select! {
+                read = reader.read(&mut buf) => match read {
+                    Ok(0) | Err(_) => {
+                        let _ = ws_sink.send(Message::Close(None)).await;
+                        break;
+                    }
+                    Ok(n) => {
+                        if ws_sink.send(Message::Binary(buf[..n].to_vec())).await.is_err() {
+                            break;
+                        }
+                    }
+                },
+                _ = ping.tick() => {
+                    let idle = start.elapsed()
+                        - Duration::from_millis(last_received.load(Ordering::Relaxed));
+                    if idle > idle_timeout {
+                        warn!("Closing websocket connection after being idle for {idle:?}");
+                        let _ = ws_sink.send(Message::Close(None)).await;
+                        break;
+                    }
+                    if ws_sink.send(Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                }
+            }
         }
+    };
+
+    // Once either direction ends the bridge is dropped, which ends the notarization stream
+    tokio::select! {
+        _ = inbound => {}
+        _ = outbound => {}
     }
 }
@@ -0,0 +1,405 @@
+use async_trait::async_trait;
+use eyre::Result;
+use p256::ecdsa::{
+    signature::{self, Signer},
+    Signature as P256Signature, SigningKey,
+};
+use std::{fmt::Debug, sync::Arc};
+use tlsn_core::{Signature, SignatureAlgorithm};
+use tlsn_verifier::tls::AsyncSigner;
+
+use crate::config::SignerBackendProperties;
+
+/// Signer of the session headers, which holds the notary signing key or calls the KMS or HSM holding it
+pub trait NotarySigner: AsyncSigner + Debug {}
+
+impl<T: AsyncSigner + Debug> NotarySigner for T {}
+
+/// Signer with the key loaded in memory from the private key PEM file
+#[derive(Debug)]
+pub struct LocalSigner(SigningKey);
+
+impl LocalSigner {
+    pub fn new(signing_key: SigningKey) -> Self {
+        Self(signing_key)
+    }
+}
+
+#[async_trait]
+impl AsyncSigner for LocalSigner {
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+        let signature: P256Signature = self.0.try_sign(msg)?;
+        Ok(signature.into())
+    }
+}
+
+/// Signer with a secp256k1 key loaded in memory from its private key PEM file
+#[derive(Debug)]
+pub struct Secp256k1Signer(k256::ecdsa::SigningKey);
+
+#[async_trait]
+impl AsyncSigner for Secp256k1Signer {
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+        let signature: k256::ecdsa::Signature = self.0.try_sign(msg)?;
+        Ok(signature.into())
+    }
+}
+
+/// Signer with an Ed25519 key loaded in memory from its private key PEM file
+#[derive(Debug)]
+pub struct Ed25519Signer(ed25519_dalek::SigningKey);
+
+#[async_trait]
+impl AsyncSigner for Ed25519Signer {
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+        let signature: ed25519_dalek::Signature = self.0.try_sign(msg)?;
+        Ok(signature.into())
+    }
+}
+
+/// Load a signing key of the given algorithm from its PKCS#8 private key PEM file
+pub fn load_local_signer(
+    algorithm: SignatureAlgorithm,
+    private_key_pem_path: &str,
+) -> Result<Arc<dyn NotarySigner>> {
+    use p256::pkcs8::DecodePrivateKey;
+
+    let pem = std::fs::read_to_string(private_key_pem_path).map_err(|err| {
+        eyre::eyre!("Failed to read {algorithm:?} signing key at {private_key_pem_path}: {err}")
+    })?;
+    let signer: Result<Arc<dyn NotarySigner>, _> = match algorithm {
+        SignatureAlgorithm::P256 => SigningKey::from_pkcs8_pem(&pem)
+            .map(|key| Arc::new(LocalSigner::new(key)) as Arc<dyn NotarySigner>),
+        SignatureAlgorithm::Secp256k1 => k256::ecdsa::SigningKey::from_pkcs8_pem(&pem)
+            .map(|key| Arc::new(Secp256k1Signer(key)) as Arc<dyn NotarySigner>),
+        SignatureAlgorithm::Ed25519 => ed25519_dalek::SigningKey::from_pkcs8_pem(&pem)
+            .map(|key| Arc::new(Ed25519Signer(key)) as Arc<dyn NotarySigner>),
+    };
+    signer.map_err(|err| {
+        eyre::eyre!("Failed to load {algorithm:?} signing key at {private_key_pem_path}: {err}")
+    })
+}
+
+/// Connect to the KMS or HSM holding the notary signing key
+pub async fn connect_signer(config: &SignerBackendProperties) -> Result<Arc<dyn NotarySigner>> {
+    match config {
+        #[cfg(feature = "aws-kms")]
+        SignerBackendProperties::AwsKms { key_id, region } => Ok(Arc::new(
+            aws_kms::AwsKmsSigner::new(key_id.clone(), region.clone()).await,
+        )),
+        #[cfg(feature = "gcp-kms")]
+        SignerBackendProperties::GcpKms { key_version } => Ok(Arc::new(
+            gcp_kms::GcpKmsSigner::new(key_version.clone()).await?,
+        )),
+        #[cfg(feature = "pkcs11")]
+        SignerBackendProperties::Pkcs11 {
+            module_path,
+            token_label,
+            key_label,
+            pin_env,
+        } => Ok(Arc::new(pkcs11::Pkcs11Signer::new(
+            module_path,
+            token_label,
+            key_label,
+            pin_env,
+        )?)),
+        #[allow(unreachable_patterns)]
+        backend => Err(eyre::eyre!(
+            "Notary server is built without support for the signer backend {backend:?}, enable the \
+            corresponding feature to use it"
+        )),
+    }
+}
+
+/// Parse a DER-encoded ECDSA signature returned by a KMS, normalizing it to low S as KMS signatures
+/// are not guaranteed to be
+#[cfg(any(feature = "aws-kms", feature = "gcp-kms", test))]
+fn signature_from_der(der: &[u8]) -> Result<Signature, signature::Error> {
+    let signature = P256Signature::from_der(der)?;
+    Ok(signature.normalize_s().unwrap_or(signature).into())
+}
+
+#[cfg(feature = "aws-kms")]
+mod aws_kms {
+    use aws_sdk_kms::{
+        config::Region,
+        primitives::Blob,
+        types::{MessageType, SigningAlgorithmSpec},
+        Client,
+    };
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    /// Signer with an `ECC_NIST_P256` key held in AWS KMS
+    #[derive(Debug)]
+    pub struct AwsKmsSigner {
+        client: Client,
+        key_id: String,
+    }
+
+    impl AwsKmsSigner {
+        /// Credentials are loaded from the environment, e.g. the instance profile or the AWS_* variables
+        pub async fn new(key_id: String, region: Option<String>) -> Self {
+            let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+            if let Some(region) = region {
+                loader = loader.region(Region::new(region));
+            }
+            Self {
+                client: Client::new(&loader.load().await),
+                key_id,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AsyncSigner for AwsKmsSigner {
+        async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+            let output = self
+                .client
+                .sign()
+                .key_id(&self.key_id)
+                .message(Blob::new(Sha256::digest(msg).to_vec()))
+                .message_type(MessageType::Digest)
+                .signing_algorithm(SigningAlgorithmSpec::EcdsaSha256)
+                .send()
+                .await
+                .map_err(signature::Error::from_source)?;
+            let der = output.signature().ok_or_else(signature::Error::new)?;
+            signature_from_der(der.as_ref())
+        }
+    }
+}
+
+#[cfg(feature = "gcp-kms")]
+mod gcp_kms {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use eyre::{ensure, eyre};
+    use gcp_auth::AuthenticationManager;
+    use hyper::{client::HttpConnector, header, Body, Client, Method, Request};
+    use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+    use serde::Deserialize;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+    use std::time::Duration;
+
+    use super::*;
+
+    const CLOUD_KMS_SCOPE: &str = "https://www.googleapis.com/auth/cloudkms";
+
+    /// Timeout of requests to Cloud KMS
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[derive(Deserialize)]
+    struct AsymmetricSignResponse {
+        signature: String,
+    }
+
+    /// Signer with an `EC_SIGN_P256_SHA256` key version held in GCP Cloud KMS
+    pub struct GcpKmsSigner {
+        key_version: String,
+        auth: AuthenticationManager,
+        client: Client<HttpsConnector<HttpConnector>>,
+    }
+
+    impl GcpKmsSigner {
+        /// Credentials are loaded from the application default credentials, e.g. the service account of the instance
+        pub async fn new(key_version: String) -> Result<Self> {
+            let auth = AuthenticationManager::new()
+                .await
+                .map_err(|err| eyre!("Failed to load GCP credentials: {err}"))?;
+            let connector = HttpsConnectorBuilder::new()
+                .with_webpki_roots()
+                .https_only()
+                .enable_http1()
+                .build();
+            Ok(Self {
+                key_version,
+                auth,
+                client: Client::builder().build(connector),
+            })
+        }
+
+        async fn asymmetric_sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+            let token = self.auth.get_token(&[CLOUD_KMS_SCOPE]).await?;
+            let body = json!({ "digest": { "sha256": STANDARD.encode(Sha256::digest(msg)) } });
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(format!(
+                    "https://cloudkms.googleapis.com/v1/{}:asymmetricSign",
+                    self.key_version
+                ))
+                .header(header::AUTHORIZATION, format!("Bearer {}", token.as_str()))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))?;
+
+            let response = tokio::time::timeout(REQUEST_TIMEOUT, self.client.request(request))
+                .await
+                .map_err(|_| eyre!("Request to Cloud KMS timed out"))??;
+            ensure!(
+                response.status().is_success(),
+                "Request to Cloud KMS failed with status {}",
+                response.status()
+            );
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            let response: AsymmetricSignResponse = serde_json::from_slice(&body)?;
+            Ok(STANDARD.decode(response.signature)?)
+        }
+    }
+
+    #[async_trait]
+    impl AsyncSigner for GcpKmsSigner {
+        async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+            let der = self
+                .asymmetric_sign(msg)
+                .await
+                .map_err(|err| signature::Error::from_source(err.to_string()))?;
+            signature_from_der(&der)
+        }
+    }
+
+    impl Debug for GcpKmsSigner {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("GcpKmsSigner")
+                .field("key_version", &self.key_version)
+                .finish()
+        }
+    }
+}
+
+#[cfg(feature = "pkcs11")]
+mod pkcs11 {
+    use cryptoki::{
+        context::{CInitializeArgs, Pkcs11},
+        mechanism::Mechanism,
+        object::{Attribute, ObjectClass, ObjectHandle},
+        session::{Session, UserType},
+        types::AuthPin,
+    };
+    use eyre::eyre;
+    use sha2::{Digest, Sha256};
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Signer with a P-256 key held in an HSM accessed through its PKCS#11 module, e.g. a YubiHSM 2
+    /// through yubihsm_pkcs11.so
+    #[derive(Debug)]
+    pub struct Pkcs11Signer {
+        session: Arc<Mutex<Session>>,
+        key: ObjectHandle,
+    }
+
+    impl Pkcs11Signer {
+        /// Log in to the token with the given label using the PIN in the `pin_env` environment variable,
+        /// and find the private key with the given label
+        pub fn new(
+            module_path: &str,
+            token_label: &str,
+            key_label: &str,
+            pin_env: &str,
+        ) -> Result<Self> {
+            let pin = std::env::var(pin_env)
+                .map_err(|_| eyre!("PIN of the PKCS#11 token is not set in {pin_env}"))?;
+            let pkcs11 = Pkcs11::new(module_path)
+                .map_err(|err| eyre!("Failed to load PKCS#11 module {module_path}: {err}"))?;
+            pkcs11.initialize(CInitializeArgs::OsThreads)?;
+
+            let slot = pkcs11
+                .get_slots_with_token()?
+                .into_iter()
+                .find(|slot| {
+                    pkcs11
+                        .get_token_info(*slot)
+                        .map(|info| info.label() == token_label)
+                        .unwrap_or(false)
+                })
+                .ok_or_else(|| eyre!("PKCS#11 token {token_label} not found"))?;
+            let session = pkcs11.open_ro_session(slot)?;
+            session.login(UserType::User, Some(&AuthPin::new(pin)))?;
+
+            let key = session
+                .find_objects(&[
+                    Attribute::Class(ObjectClass::PRIVATE_KEY),
+                    Attribute::Label(key_label.as_bytes().to_vec()),
+                ])?
+                .into_iter()
+                .next()
+                .ok_or_else(|| eyre!("Private key {key_label} not found on PKCS#11 token"))?;
+
+            Ok(Self {
+                session: Arc::new(Mutex::new(session)),
+                key,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl AsyncSigner for Pkcs11Signer {
+        async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+            let session = self.session.clone();
+            let key = self.key;
+            let digest = Sha256::digest(msg).to_vec();
+            // PKCS#11 calls block until the HSM responds
+            let raw = tokio::task::spawn_blocking(move || {
+                session
+                    .lock()
+                    .unwrap()
+                    .sign(&Mechanism::Ecdsa, key, &digest)
+            })
+            .await
+            .map_err(signature::Error::from_source)?
+            .map_err(signature::Error::from_source)?;
+            // CKM_ECDSA returns the signature as the concatenation of r and s
+            let signature = P256Signature::from_slice(&raw)?;
+            Ok(signature.normalize_s().unwrap_or(signature).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use p256::{ecdsa::signature::Verifier, pkcs8::DecodePrivateKey};
+
+    #[tokio::test]
+    async fn test_local_signer() {
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let verifying_key = *signing_key.verifying_key();
+        let signer = LocalSigner::new(signing_key);
+
+        let Signature::P256(signature) = signer.sign(b"session header").await.unwrap() else {
+            panic!("Local signer should produce P-256 signatures");
+        };
+        assert!(verifying_key.verify(b"session header", &signature).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_additional_signers() {
+        let secp256k1 =
+            Secp256k1Signer(k256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap());
+        let public_key = k256::PublicKey::from(*secp256k1.0.verifying_key());
+        let signature = secp256k1.sign(b"session header").await.unwrap();
+        assert!(signature.verify(b"session header", public_key).is_ok());
+
+        let ed25519 = Ed25519Signer(ed25519_dalek::SigningKey::from_bytes(&[1u8; 32]));
+        let public_key = ed25519.0.verifying_key();
+        let signature = ed25519.sign(b"session header").await.unwrap();
+        assert!(signature.verify(b"session header", public_key).is_ok());
+    }
+
+    #[test]
+    fn test_signature_from_der_is_normalized() {
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let signature: P256Signature = signing_key.sign(b"session header");
+        // Flip s to its high form, which KMS may return
+        let (r, s) = signature.split_scalars();
+        let high_s = P256Signature::from_scalars(r, -s).unwrap();
+
+        let Signature::P256(parsed) = signature_from_der(high_s.to_der().as_bytes()).unwrap()
+        else {
+            panic!("DER signatures should be parsed as P-256 signatures");
+        };
+        assert_eq!(parsed, signature.normalize_s().unwrap_or(signature));
+    }
+}
@@ -0,0 +1,281 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Result};
+use redis::aio::ConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{fmt, sync::Mutex, time::Duration};
+
+use crate::{
+    billing::UsageReceipt,
+    domain::{
+        attestation::{AttestationRecord, AttestationStore},
+        notary::{SessionData, SessionStore, UsageCounter},
+    },
+};
+
+/// Attestation storage backed by a SQLite database file
+#[derive(Debug)]
+pub struct SqliteAttestationStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteAttestationStore {
+    /// Open the database at the given path, creating it and its schema if needed
+    pub fn open(path: &str) -> Result<Self> {
+        let connection = Connection::open(path)
+            .map_err(|err| eyre!("Failed to open attestation database at {path}: {err}"))?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS attestations (
+                id TEXT PRIMARY KEY,
+                client_name TEXT,
+                created_at TEXT NOT NULL,
+                notarized_at TEXT NOT NULL,
+                session_header TEXT NOT NULL,
+                signature TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl AttestationStore for SqliteAttestationStore {
+    fn insert(&self, record: &AttestationRecord) -> Result<()> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO attestations (id, client_name, created_at, notarized_at, session_header, signature)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                record.id,
+                record.client_name,
+                record.created_at.to_rfc3339(),
+                record.notarized_at.to_rfc3339(),
+                serde_json::to_string(&record.session_header)?,
+                serde_json::to_string(&record.signature)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<AttestationRecord>> {
+        let row = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT client_name, created_at, notarized_at, session_header, signature
+                FROM attestations WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((client_name, created_at, notarized_at, session_header, signature)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(AttestationRecord {
+            id: id.to_string(),
+            client_name,
+            created_at: parse_timestamp(&created_at)?,
+            notarized_at: parse_timestamp(&notarized_at)?,
+            session_header: serde_json::from_str(&session_header)?,
+            signature: serde_json::from_str(&signature)?,
+        }))
+    }
+
+    fn ping(&self) -> Result<()> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row("SELECT 1", [], |_| Ok(()))?;
+        Ok(())
+    }
+}
+
+/// Session storage backed by a Redis server, shared by all notary instances behind a load balancer
+#[derive(Clone)]
+pub struct RedisSessionStore {
+    connection: ConnectionManager,
+    /// Time in seconds after which sessions that haven't been connected to expire
+    session_ttl: u64,
+}
+
+impl RedisSessionStore {
+    /// Connect to the Redis server at the given url, reconnecting automatically if the connection drops
+    pub async fn connect(url: &str, session_ttl: u64) -> Result<Self> {
+        let client =
+            redis::Client::open(url).map_err(|err| eyre!("Invalid redis url {url}: {err}"))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|err| eyre!("Failed to connect to redis at {url}: {err}"))?;
+        Ok(Self {
+            connection,
+            session_ttl,
+        })
+    }
+}
+
+/// Script adding the amounts of the counters in `KEYS` if none exceeds its limit, with the amount, limit and
+/// expiry time of each counter in `ARGV`. Returns the index of the first counter exceeding its limit, or -1
+const ADD_USAGE_SCRIPT: &str = r#"
+for i = 1, #KEYS do
+    local value = tonumber(redis.call('GET', KEYS[i]) or '0')
+    if value + tonumber(ARGV[3 * i - 2]) > tonumber(ARGV[3 * i - 1]) then
+        return i - 1
+    end
+end
+for i = 1, #KEYS do
+    redis.call('INCRBY', KEYS[i], ARGV[3 * i - 2])
+    redis.call('EXPIREAT', KEYS[i], ARGV[3 * i])
+end
+return -1
+"#;
+
+fn session_key(session_id: &str) -> String {
+    format!("notary:session:{session_id}")
+}
+
+fn usage_key(key: &str) -> String {
+    format!("notary:usage:{key}")
+}
+
+fn receipt_key(session_id: &str) -> String {
+    format!("notary:receipt:{session_id}")
+}
+
+#[async_trait]
+impl SessionStore for RedisSessionStore {
+    async fn insert(&self, session_id: &str, data: SessionData) -> Result<()> {
+        redis::cmd("SET")
+            .arg(session_key(session_id))
+            .arg(serde_json::to_string(&data)?)
+            .arg("EX")
+            .arg(self.session_ttl)
+            .query_async::<_, ()>(&mut self.connection.clone())
+            .await?;
+        Ok(())
+    }
+
+    async fn take(&self, session_id: &str) -> Result<Option<SessionData>> {
+        // GETDEL is atomic, so that a session can't be started by multiple instances at once
+        let data: Option<String> = redis::cmd("GETDEL")
+            .arg(session_key(session_id))
+            .query_async(&mut self.connection.clone())
+            .await?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    async fn add_usage(
+        &self,
+        counters: &[UsageCounter],
+        _now: DateTime<Utc>,
+    ) -> Result<Option<usize>> {
+        // The script runs atomically, so that instances can't both pass a limit that only one of them fits in
+        let script = redis::Script::new(ADD_USAGE_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        for counter in counters {
+            invocation
+                .key(usage_key(&counter.key))
+                .arg(counter.amount)
+                .arg(counter.limit)
+                .arg(counter.expires_at.timestamp());
+        }
+        let exceeded: i64 = invocation
+            .invoke_async(&mut self.connection.clone())
+            .await?;
+        Ok(usize::try_from(exceeded).ok())
+    }
+
+    async fn insert_receipt(
+        &self,
+        session_id: &str,
+        receipt: &UsageReceipt,
+        ttl: Duration,
+    ) -> Result<()> {
+        redis::cmd("SET")
+            .arg(receipt_key(session_id))
+            .arg(serde_json::to_string(receipt)?)
+            .arg("EX")
+            .arg(ttl.as_secs())
+            .query_async::<_, ()>(&mut self.connection.clone())
+            .await?;
+        Ok(())
+    }
+
+    async fn receipt(&self, session_id: &str) -> Result<Option<UsageReceipt>> {
+        let receipt: Option<String> = redis::cmd("GET")
+            .arg(receipt_key(session_id))
+            .query_async(&mut self.connection.clone())
+            .await?;
+        Ok(receipt
+            .map(|receipt| serde_json::from_str(&receipt))
+            .transpose()?)
+    }
+
+    async fn ping(&self) -> Result<()> {
+        redis::cmd("PING")
+            .query_async::<_, String>(&mut self.connection.clone())
+            .await?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RedisSessionStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisSessionStore")
+            .field("session_ttl", &self.session_ttl)
+            .finish()
+    }
+}
+
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(timestamp)?.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use p256::{
+        ecdsa::{signature::Signer, Signature, SigningKey},
+        pkcs8::DecodePrivateKey,
+    };
+    use tlsn_core::{fixtures, merkle::MerkleRoot};
+
+    #[test]
+    fn test_insert_and_get_attestation() {
+        let store = SqliteAttestationStore::open(":memory:").unwrap();
+        let session_header = fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10);
+        let signing_key = SigningKey::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let signature: Signature = signing_key.sign(b"session header");
+        let record = AttestationRecord {
+            id: "test-session-id".to_string(),
+            client_name: Some("test-name-0".to_string()),
+            created_at: Utc::now(),
+            notarized_at: Utc::now(),
+            session_header,
+            signature: signature.into(),
+        };
+
+        store.insert(&record).unwrap();
+        let stored = store.get("test-session-id").unwrap().unwrap();
+        assert_eq!(stored.client_name, record.client_name);
+        assert_eq!(stored.created_at, record.created_at);
+        assert_eq!(
+            serde_json::to_value(&stored.session_header).unwrap(),
+            serde_json::to_value(&record.session_header).unwrap()
+        );
+
+        assert!(store.get("unknown-session-id").unwrap().is_none());
+        // Attestations can't be overwritten
+        assert!(store.insert(&record).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use eyre::{eyre, Result};
+use std::fs;
+use tlsn_core::{
+    tee::{notary_key_binding, TeePlatform, TeeQuote},
+    SignatureAlgorithm,
+};
+
+use crate::keys::parse_public_key;
+
+/// Pseudo-file of Gramine which the report data of the next SGX quote is written to
+const GRAMINE_USER_REPORT_DATA_PATH: &str = "/dev/attestation/user_report_data";
+/// Pseudo-file of Gramine which the SGX quote is read from
+const GRAMINE_QUOTE_PATH: &str = "/dev/attestation/quote";
+/// Directory of the configfs-tsm interface of the Linux kernel, which produces SEV-SNP attestation reports
+const TSM_REPORT_DIR: &str = "/sys/kernel/config/tsm/report";
+
+/// Request a hardware attestation quote from the TEE that the notary runs in, binding the notary public key
+/// to the measurement of the notary server
+pub fn generate_tee_quote(
+    platform: TeePlatform,
+    algorithm: SignatureAlgorithm,
+    public_key_pem: &str,
+) -> Result<TeeQuote> {
+    let public_key = parse_public_key(algorithm, public_key_pem)?;
+    let binding = notary_key_binding(&public_key);
+    let mut report_data = [0u8; 64];
+    report_data[..32].copy_from_slice(&binding);
+
+    let quote = match platform {
+        TeePlatform::Sgx => sgx_quote(&report_data),
+        TeePlatform::SevSnp => snp_report(&report_data),
+        TeePlatform::Nitro => nitro_document(&binding),
+        platform => Err(eyre!("Unsupported TEE platform {platform:?}")),
+    }
+    .map_err(|err| eyre!("Failed to generate {platform:?} quote: {err}"))?;
+
+    Ok(TeeQuote::new(platform, quote)?)
+}
+
+/// Request an SGX quote through the attestation interface of Gramine
+fn sgx_quote(report_data: &[u8; 64]) -> Result<Vec<u8>> {
+    fs::write(GRAMINE_USER_REPORT_DATA_PATH, report_data)?;
+    Ok(fs::read(GRAMINE_QUOTE_PATH)?)
+}
+
+/// Request an SEV-SNP attestation report through the configfs-tsm interface of the Linux kernel
+fn snp_report(report_data: &[u8; 64]) -> Result<Vec<u8>> {
+    let report_dir = format!("{TSM_REPORT_DIR}/notary-server-{}", std::process::id());
+    fs::create_dir(&report_dir)?;
+    let report = fs::write(format!("{report_dir}/inblob"), report_data)
+        .and_then(|_| fs::read(format!("{report_dir}/outblob")));
+    fs::remove_dir(&report_dir)?;
+    Ok(report?)
+}
+
+/// Request an attestation document from the Nitro Secure Module
+#[cfg(feature = "nitro")]
+fn nitro_document(user_data: &[u8]) -> Result<Vec<u8>> {
+    use aws_nitro_enclaves_nsm_api::{
+        api::{Request, Response},
+        driver::{nsm_exit, nsm_init, nsm_process_request},
+    };
+    use serde_bytes::ByteBuf;
+
+    let fd = nsm_init();
+    if fd < 0 {
+        return Err(eyre!("Nitro Secure Module is not available"));
+    }
+    let response = nsm_process_request(
+        fd,
+        Request::Attestation {
+            user_data: Some(ByteBuf::from(user_data.to_vec())),
+            nonce: None,
+            public_key: None,
+        },
+    );
+    nsm_exit(fd);
+
+    match response {
+        Response::Attestation { document } => Ok(document),
+        Response::Error(code) => Err(eyre!("Nitro Secure Module returned {code:?}")),
+        _ => Err(eyre!("Unexpected response from Nitro Secure Module")),
+    }
+}
+
+#[cfg(not(feature = "nitro"))]
+fn nitro_document(_user_data: &[u8]) -> Result<Vec<u8>> {
+    Err(eyre!(
+        "Notary server is built without support for Nitro Enclaves, enable the nitro feature to use it"
+    ))
+}
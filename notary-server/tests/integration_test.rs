@@ -27,9 +27,11 @@ use tracing::debug;
 use ws_stream_tungstenite::WsStream;
 
 use notary_server::{
-    read_pem_file, run_server, AuthorizationProperties, LoggingProperties, NotarizationProperties,
-    NotarizationSessionRequest, NotarizationSessionResponse, NotaryServerProperties,
-    NotarySigningKeyProperties, ServerProperties, TLSProperties,
+    read_pem_file, run_server, AdminProperties, AuthorizationProperties, BillingProperties,
+    LoggingProperties, NotarizationProperties, NotarizationSessionRequest,
+    NotarizationSessionResponse, NotaryServerProperties, NotarySigningKeyProperties,
+    RateLimitProperties, ServerProperties, SessionStoreProperties, StorageProperties,
+    TLSProperties, WebSocketProperties,
 };
 
 const NOTARY_CA_CERT_PATH: &str = "./fixture/tls/rootCA.crt";
@@ -44,18 +46,24 @@ fn get_server_config(port: u16, tls_enabled: bool) -> NotaryServerProperties {
             host: "127.0.0.1".to_string(),
             port,
             html_info: "example html response".to_string(),
+            shutdown_grace_period: 30,
         },
         notarization: NotarizationProperties {
             max_transcript_size: 1 << 14,
+            max_threads: None,
+            compressed_channels: vec![],
         },
         tls: TLSProperties {
             enabled: tls_enabled,
             private_key_pem_path: "./fixture/tls/notary.key".to_string(),
             certificate_pem_path: "./fixture/tls/notary.crt".to_string(),
+            acme: None,
         },
         notary_key: NotarySigningKeyProperties {
             private_key_pem_path: "./fixture/notary/notary.key".to_string(),
             public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            backend: None,
+            additional_keys: vec![],
         },
         logging: LoggingProperties {
             level: "DEBUG".to_string(),
@@ -64,7 +72,18 @@ fn get_server_config(port: u16, tls_enabled: bool) -> NotaryServerProperties {
         authorization: AuthorizationProperties {
             enabled: false,
             whitelist_csv_path: "./fixture/auth/whitelist.csv".to_string(),
+            oidc: None,
         },
+        rate_limit: RateLimitProperties::default(),
+        storage: StorageProperties::default(),
+        websocket: WebSocketProperties::default(),
+        session_store: SessionStoreProperties::default(),
+        tee: None,
+        admin: AdminProperties::default(),
+        audit: None,
+        grpc: None,
+        billing: BillingProperties::default(),
+        concurrency: ConcurrencyProperties::default(),
     }
 }
 
@@ -173,6 +192,8 @@ async fn test_tcp_prover<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         client_type: notary_server::ClientType::Tcp,
         max_sent_data: Some(MAX_SENT),
         max_recv_data: Some(MAX_RECV),
+        resumable: None,
+        signature_algorithm: None,
     })
     .unwrap();
     let request = Request::builder()
@@ -348,6 +369,8 @@ async fn test_websocket_prover() {
         client_type: notary_server::ClientType::Websocket,
         max_sent_data: Some(MAX_SENT),
         max_recv_data: Some(MAX_RECV),
+        resumable: None,
+        signature_algorithm: None,
     })
     .unwrap();
 
@@ -489,3 +512,146 @@ async fn test_websocket_prover() {
 
     debug!("Done notarization!");
 }
+
+#[cfg(feature = "grpc")]
+#[tokio::test]
+async fn test_grpc_prover() {
+    use futures::SinkExt;
+    use notary_server::{
+        proto::{
+            notarize_request, notary_client::NotaryClient, ConfigureSessionRequest, NotarizeRequest,
+        },
+        GrpcProperties,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt as _};
+
+    // Notary server configuration setup, serving the gRPC API on the next port
+    let mut notary_config = get_server_config(7051, false);
+    notary_config.grpc = Some(GrpcProperties { port: 7052 });
+    let _ = tracing_subscriber::fmt::try_init();
+    let config = notary_config.clone();
+    tokio::spawn(async move {
+        run_server(&config).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Configure notarization and obtain session id
+    let mut client = NotaryClient::connect(format!("http://{}:7052", notary_config.server.host))
+        .await
+        .unwrap();
+    let session_id = client
+        .configure_session(ConfigureSessionRequest {
+            max_sent_data: Some(MAX_SENT as u64),
+            max_recv_data: Some(MAX_RECV as u64),
+            resumable: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .session_id;
+
+    debug!("Configured gRPC session {session_id}");
+
+    // Bridge the notarization call to a byte stream for the prover, the first message carries the session id
+    let (notary_socket, bridge) = tokio::io::duplex(1 << 16);
+    let (mut bridge_reader, mut bridge_writer) = tokio::io::split(bridge);
+    let (mut request_sender, requests) = futures::channel::mpsc::channel(16);
+    request_sender
+        .send(NotarizeRequest {
+            message: Some(notarize_request::Message::SessionId(session_id.clone())),
+            resumption_secret: None,
+        })
+        .await
+        .unwrap();
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 1 << 16];
+        while let Ok(n @ 1..) = bridge_reader.read(&mut buf).await {
+            let request = NotarizeRequest {
+                message: Some(notarize_request::Message::Data(buf[..n].to_vec())),
+                resumption_secret: None,
+            };
+            if request_sender.send(request).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut responses = client.notarize(requests).await.unwrap().into_inner();
+    tokio::spawn(async move {
+        while let Ok(Some(response)) = responses.message().await {
+            if bridge_writer.write_all(&response.data).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Connect to the Server
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+    let server_task = tokio::spawn(bind_test_server_hyper(server_socket.compat()));
+
+    let mut root_store = tls_core::anchors::RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    // Basic default prover config — use the responded session id from notary server
+    let prover_config = ProverConfig::builder()
+        .id(session_id)
+        .server_dns(SERVER_DOMAIN)
+        .max_sent_data(MAX_SENT)
+        .max_recv_data(MAX_RECV)
+        .root_cert_store(root_store)
+        .build()
+        .unwrap();
+
+    // Bind the Prover to the sockets
+    let prover = Prover::new(prover_config)
+        .setup(notary_socket.compat())
+        .await
+        .unwrap();
+    let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+
+    // Spawn the Prover task to be run concurrently
+    let prover_task = tokio::spawn(prover_fut);
+
+    let (mut request_sender, connection) = hyper::client::conn::handshake(tls_connection.compat())
+        .await
+        .unwrap();
+
+    let connection_task = tokio::spawn(connection.without_shutdown());
+
+    let request = Request::builder()
+        .uri(format!("https://{}/echo", SERVER_DOMAIN))
+        .header("Host", SERVER_DOMAIN)
+        .header("Connection", "close")
+        .method("POST")
+        .body(Body::from("echo"))
+        .unwrap();
+
+    let response = request_sender.send_request(request).await.unwrap();
+
+    assert!(response.status() == StatusCode::OK);
+
+    let mut server_tls_conn = server_task.await.unwrap().unwrap();
+
+    // Make sure the server closes cleanly (sends close notify)
+    server_tls_conn.close().await.unwrap();
+
+    let mut client_socket = connection_task.await.unwrap().unwrap().io.into_inner();
+
+    client_socket.close().await.unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    _ = prover.finalize().await.unwrap();
+
+    debug!("Done notarization!");
+}
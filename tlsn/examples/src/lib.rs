@@ -83,6 +83,8 @@ pub async fn request_notarization(
         client_type: ClientType::Tcp,
         max_sent_data,
         max_recv_data,
+        resumable: None,
+        signature_algorithm: None,
     })
     .unwrap();
 
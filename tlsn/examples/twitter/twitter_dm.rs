@@ -188,6 +188,8 @@ async fn main() {
     let proof = TlsProof {
         session: session_proof,
         substrings: substrings_proof,
+        cosignatures: Vec::new(),
+        tee_quote: None,
     };
 
     // Dump the proof to a file.
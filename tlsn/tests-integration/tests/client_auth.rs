@@ -0,0 +1,93 @@
+use futures::{AsyncReadExt, AsyncWriteExt};
+use tls_core::{
+    anchors::RootCertStore,
+    key::{Certificate, PrivateKey},
+};
+use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_server_fixture::{CA_CERT_DER, CLIENT_CERT_DER, CLIENT_KEY_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::instrument;
+
+#[tokio::test]
+#[ignore]
+async fn client_auth() {
+    tracing_subscriber::fmt::init();
+
+    let (socket_0, socket_1) = tokio::io::duplex(2 << 23);
+
+    tokio::join!(prover(socket_0), notary(socket_1));
+}
+
+#[instrument(skip(notary_socket))]
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(notary_socket: T) {
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind_with_client_auth(
+        server_socket.compat(),
+    ));
+
+    let mut root_store = RootCertStore::empty();
+    root_store.add(&Certificate(CA_CERT_DER.to_vec())).unwrap();
+
+    let config = ProverConfig::builder()
+        .id("test")
+        .server_dns(SERVER_DOMAIN)
+        .root_cert_store(root_store)
+        .client_auth(
+            vec![Certificate(CLIENT_CERT_DER.to_vec())],
+            PrivateKey(CLIENT_KEY_DER.to_vec()),
+        )
+        .build()
+        .unwrap();
+
+    // The private key must not be printed.
+    let debug = format!("{config:?}");
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains("PrivateKey"));
+
+    let prover = Prover::new(config)
+        .setup(notary_socket.compat())
+        .await
+        .unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let request = format!("GET / HTTP/1.1\r\nHost: {SERVER_DOMAIN}\r\nConnection: close\r\n\r\n")
+        .into_bytes();
+    tls_connection.write_all(&request).await.unwrap();
+
+    let mut response = Vec::new();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+    tls_connection.close().await.unwrap();
+
+    // The server rejects the handshake unless the client certificate was verified.
+    server_task.await.unwrap().unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+    assert_eq!(prover.sent_transcript().data(), &request[..]);
+    assert_eq!(prover.recv_transcript().data(), &response[..]);
+
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    prover.finalize().await.unwrap();
+}
+
+#[instrument(skip(socket))]
+async fn notary<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(socket: T) {
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap());
+    let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+    _ = verifier
+        .notarize::<_, p256::ecdsa::Signature>(socket.compat(), &signing_key)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,86 @@
+use tlsn_core::{
+    fixtures,
+    proof::{default_cert_verifier, SessionProofError, TlsProof},
+    revocation::ValidityError,
+    NotaryPublicKey,
+};
+use tlsn_verifier::{
+    policy::{Outcome, Policy, Rule},
+    presentation::{verify_presentation, PresentationError},
+};
+
+const TX: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+const RX: &[u8] = b"HTTP/1.1 200 OK\r\n\r\n";
+
+fn notary_key() -> NotaryPublicKey {
+    p256::PublicKey::from(*fixtures::notary_signing_key().verifying_key()).into()
+}
+
+fn proof_expiring_at(expires_at: u64) -> TlsProof {
+    fixtures::tls_proof(TX, RX, |header| header.with_expiry(expires_at))
+}
+
+fn expiry_outcome(policy: &Policy, proof: TlsProof, now: u64) -> Option<Outcome> {
+    policy
+        .evaluate(proof, &default_cert_verifier(), now)
+        .results
+        .into_iter()
+        .find_map(|(rule, outcome)| (rule == Rule::Expiry).then_some(outcome))
+}
+
+#[test]
+fn policy_rejects_expired_presentation() {
+    let policy = Policy {
+        notary_keys: vec![notary_key()],
+        ..Default::default()
+    };
+    let expires_at = proof_expiring_at(0).session.header.time() + 60;
+
+    assert_eq!(
+        expiry_outcome(&policy, proof_expiring_at(expires_at), expires_at - 1),
+        Some(Outcome::Pass)
+    );
+    assert!(matches!(
+        expiry_outcome(&policy, proof_expiring_at(expires_at), expires_at),
+        Some(Outcome::Fail(_))
+    ));
+
+    let report = policy.evaluate(
+        proof_expiring_at(expires_at),
+        &default_cert_verifier(),
+        expires_at,
+    );
+    assert!(!report.is_accepted());
+
+    let policy = Policy {
+        allow_expired: true,
+        ..policy
+    };
+    assert_eq!(
+        expiry_outcome(&policy, proof_expiring_at(expires_at), expires_at),
+        None
+    );
+}
+
+#[test]
+fn verify_presentation_rejects_expired_presentation() {
+    let expires_at = proof_expiring_at(0).session.header.time() + 60;
+
+    let err = verify_presentation(
+        proof_expiring_at(expires_at),
+        notary_key(),
+        &default_cert_verifier(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        PresentationError::Session(SessionProofError::Invalid(ValidityError::Expired { .. }))
+    ));
+
+    verify_presentation(
+        proof_expiring_at(u64::MAX),
+        notary_key(),
+        &default_cert_verifier(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use tlsn_core::Direction;
+use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_verifier::tls::{SessionLimit, Verifier, VerifierConfig, VerifierError};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+async fn rejects_sessions_over_the_limit() {
+    let (socket, _prover_socket) = tokio::io::duplex(1 << 16);
+
+    let err = Verifier::new(
+        VerifierConfig::builder()
+            .id("test")
+            .session_limit(SessionLimit::new(0))
+            .build()
+            .unwrap(),
+    )
+    .setup(socket.compat())
+    .await
+    .err()
+    .unwrap();
+
+    assert!(matches!(err, VerifierError::TooManySessions));
+}
+
+#[tokio::test]
+async fn rejects_excessive_preprocessing() {
+    let (socket, _prover_socket) = tokio::io::duplex(1 << 16);
+
+    let err = Verifier::new(
+        VerifierConfig::builder()
+            .id("test")
+            .max_preprocessing(1)
+            .build()
+            .unwrap(),
+    )
+    .setup(socket.compat())
+    .await
+    .err()
+    .unwrap();
+
+    assert!(matches!(
+        err,
+        VerifierError::PreprocessingLimitExceeded { limit: 1, .. }
+    ));
+}
+
+#[tokio::test]
+async fn rejects_setup_preprocessing_over_the_limits() {
+    let (prover_socket, verifier_socket) = tokio::io::duplex(1 << 16);
+
+    // The prover's own limits allow the preprocessing, the verifier's do not.
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns("test-server.io")
+            .max_sent_data(1 << 12)
+            .setup_preprocess_sent(1 << 12)
+            .build()
+            .unwrap(),
+    )
+    .setup(prover_socket.compat());
+    let verifier = Verifier::new(
+        VerifierConfig::builder()
+            .id("test")
+            .max_sent_data(1 << 10)
+            .build()
+            .unwrap(),
+    )
+    .setup(verifier_socket.compat());
+
+    let (_, verifier) = tokio::join!(prover, verifier);
+
+    assert!(matches!(
+        verifier.err().unwrap(),
+        VerifierError::SetupPreprocessingExceeded {
+            direction: Direction::Sent,
+            requested: 4096,
+            limit: 1024,
+        }
+    ));
+}
+
+#[tokio::test]
+async fn times_out_stalled_sessions() {
+    // The prover never responds.
+    let (socket, _prover_socket) = tokio::io::duplex(1 << 16);
+    let limit = SessionLimit::new(1);
+
+    let err = Verifier::new(
+        VerifierConfig::builder()
+            .id("test")
+            .max_session_duration(Duration::from_millis(100))
+            .session_limit(limit.clone())
+            .build()
+            .unwrap(),
+    )
+    .setup(socket.compat())
+    .await
+    .err()
+    .unwrap();
+
+    assert!(matches!(err, VerifierError::SessionTimeout));
+    // The session no longer counts towards the limit.
+    assert_eq!(limit.active(), 0);
+}
@@ -0,0 +1,113 @@
+use futures::{AsyncReadExt, AsyncWriteExt};
+use tls_core::{anchors::RootCertStore, verify::WebPkiVerifier};
+use tlsn_core::{Direction, RedactedTranscript};
+use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::instrument;
+
+#[tokio::test]
+#[ignore]
+async fn live_reveal() {
+    tracing_subscriber::fmt::init();
+
+    let (socket_0, socket_1) = tokio::io::duplex(2 << 23);
+
+    let ((request, response), reveals) = tokio::join!(prover(socket_0), verifier(socket_1));
+
+    assert_eq!(reveals.len(), 2);
+    assert_eq!(reveals[0].0.data(), &request[..]);
+    assert!(reveals[0].1.data().is_empty());
+    assert!(reveals[1].0.data().is_empty());
+    assert_eq!(reveals[1].1.data(), &response[..]);
+}
+
+#[instrument(skip(notary_socket))]
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+    notary_socket: T,
+) -> (Vec<u8>, Vec<u8>) {
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let mut root_store = RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .build()
+            .unwrap(),
+    )
+    .setup(notary_socket.compat())
+    .await
+    .unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_ctrl = prover_fut.control();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let request = format!("GET / HTTP/1.1\r\nHost: {SERVER_DOMAIN}\r\nConnection: close\r\n\r\n")
+        .into_bytes();
+    tls_connection.write_all(&request).await.unwrap();
+
+    let mut response = Vec::new();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+
+    // Reveal the request and the response before the connection is closed.
+    prover_ctrl
+        .reveal(Direction::Sent, 0, request.clone())
+        .await
+        .unwrap();
+    prover_ctrl
+        .reveal(Direction::Received, 0, response.clone())
+        .await
+        .unwrap();
+
+    tls_connection.close().await.unwrap();
+
+    server_task.await.unwrap().unwrap();
+
+    let prover = prover_task.await.unwrap().unwrap().start_prove();
+    prover.finalize().await.unwrap();
+
+    (request, response)
+}
+
+#[instrument(skip(socket))]
+async fn verifier<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(
+    socket: T,
+) -> Vec<(RedactedTranscript, RedactedTranscript)> {
+    let mut root_store = RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let verifier_config = VerifierConfig::builder()
+        .id("test")
+        .cert_verifier(WebPkiVerifier::new(root_store, None))
+        .build()
+        .unwrap();
+
+    let mut reveals = Vec::new();
+    let verifier = Verifier::new(verifier_config)
+        .setup(socket.compat())
+        .await
+        .unwrap()
+        .run_with_reveals(|sent, received| {
+            reveals.push((sent, received));
+            true
+        })
+        .await
+        .unwrap();
+
+    verifier.start_verify().finalize().await.unwrap();
+
+    reveals
+}
@@ -1,6 +1,6 @@
 use futures::AsyncWriteExt;
 use hyper::{body::to_bytes, Body, Request, StatusCode};
-use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_prover::tls::{Phase, Prover, ProverConfig};
 use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
 use tlsn_verifier::tls::{Verifier, VerifierConfig};
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -40,6 +40,8 @@ async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(notary_socke
     .await
     .unwrap();
 
+    let metrics = prover.metrics();
+
     let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
 
     let prover_task = tokio::spawn(prover_fut);
@@ -84,6 +86,14 @@ async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(notary_socke
     builder.commit_recv(&(0..recv_tx_len)).unwrap();
 
     prover.finalize().await.unwrap();
+
+    let report = metrics.report();
+    assert!(report.verifier_sent > 0 && report.verifier_received > 0);
+    assert!(report.server_received > 16000);
+    assert!(report.ot_sender_count > 0 && report.ot_receiver_count > 0);
+    for phase in [Phase::Setup, Phase::Connection, Phase::Finalization] {
+        assert!(report.phase(phase).is_some());
+    }
 }
 
 #[instrument(skip(socket))]
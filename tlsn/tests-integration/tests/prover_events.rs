@@ -0,0 +1,130 @@
+use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+use tls_core::anchors::RootCertStore;
+use tlsn_prover::tls::{Prover, ProverConfig, ProverEvent};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::instrument;
+
+#[tokio::test]
+#[ignore]
+async fn prover_events() {
+    tracing_subscriber::fmt::init();
+
+    let (socket_0, socket_1) = tokio::io::duplex(2 << 23);
+
+    let ((request, response, events), _) = tokio::join!(prover(socket_0), notary(socket_1));
+
+    assert_eq!(events.first(), Some(&ProverEvent::SetupComplete));
+    assert_eq!(events.get(1), Some(&ProverEvent::HandshakeComplete));
+    assert_eq!(events.last(), Some(&ProverEvent::Finalized));
+
+    let sent: usize = events
+        .iter()
+        .filter_map(|event| match event {
+            ProverEvent::DataSent { len, .. } => Some(len),
+            _ => None,
+        })
+        .sum();
+    let received: usize = events
+        .iter()
+        .filter_map(|event| match event {
+            ProverEvent::DataReceived { len, .. } => Some(len),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(sent, request.len());
+    assert_eq!(received, response.len());
+
+    assert!(events.contains(&ProverEvent::ConnectionClosed {
+        sent: request.len(),
+        received: response.len(),
+    }));
+
+    let finalization = events
+        .iter()
+        .skip_while(|event| **event != ProverEvent::FinalizationStarted)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        finalization,
+        [
+            &ProverEvent::FinalizationStarted,
+            &ProverEvent::FinalizationProgress {
+                completed: 1,
+                total: 3
+            },
+            &ProverEvent::FinalizationProgress {
+                completed: 2,
+                total: 3
+            },
+            &ProverEvent::FinalizationProgress {
+                completed: 3,
+                total: 3
+            },
+            &ProverEvent::Finalized,
+        ]
+    );
+}
+
+#[instrument(skip(notary_socket))]
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+    notary_socket: T,
+) -> (Vec<u8>, Vec<u8>, Vec<ProverEvent>) {
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let mut root_store = RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let mut prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .build()
+            .unwrap(),
+    );
+    let events = prover.events();
+
+    let prover = prover.setup(notary_socket.compat()).await.unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let request = format!("GET / HTTP/1.1\r\nHost: {SERVER_DOMAIN}\r\nConnection: close\r\n\r\n")
+        .into_bytes();
+    tls_connection.write_all(&request).await.unwrap();
+
+    let mut response = Vec::new();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+    tls_connection.close().await.unwrap();
+
+    server_task.await.unwrap().unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    prover.finalize().await.unwrap();
+
+    (request, response, events.collect().await)
+}
+
+#[instrument(skip(socket))]
+async fn notary<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(socket: T) {
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap());
+    let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+    _ = verifier
+        .notarize::<_, p256::ecdsa::Signature>(socket.compat(), &signing_key)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,143 @@
+use futures::{AsyncReadExt, AsyncWriteExt};
+use tls_core::anchors::RootCertStore;
+use tlsn_prover::tls::{Prover, ProverConfig, Proxy};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, DuplexStream};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::instrument;
+
+#[tokio::test]
+#[ignore]
+async fn socks5_proxy() {
+    tracing_subscriber::fmt::init();
+
+    let (socket_0, socket_1) = tokio::io::duplex(2 << 23);
+
+    tokio::join!(
+        prover(socket_0, Proxy::socks5().credentials("user", "pass")),
+        notary(socket_1)
+    );
+}
+
+#[tokio::test]
+#[ignore]
+async fn http_connect_proxy() {
+    let (socket_0, socket_1) = tokio::io::duplex(2 << 23);
+
+    tokio::join!(prover(socket_0, Proxy::http_connect()), notary(socket_1));
+}
+
+/// A proxy which accepts a single tunnel to the server.
+async fn proxy(mut client: DuplexStream, mut server: DuplexStream) {
+    let mut byte = [0u8; 1];
+    client.read_exact(&mut byte).await.unwrap();
+
+    if byte[0] == 0x05 {
+        let mut methods = [0u8; 2];
+        client.read_exact(&mut methods).await.unwrap();
+        assert_eq!(methods, [0x01, 0x02]);
+        client.write_all(&[0x05, 0x02]).await.unwrap();
+
+        let mut auth = [0u8; 11];
+        client.read_exact(&mut auth).await.unwrap();
+        assert_eq!(&auth, b"\x01\x04user\x04pass");
+        client.write_all(&[0x01, 0x00]).await.unwrap();
+
+        let mut request = vec![0u8; 5 + SERVER_DOMAIN.len() + 2];
+        client.read_exact(&mut request).await.unwrap();
+        assert_eq!(
+            &request[..5],
+            &[0x05, 0x01, 0x00, 0x03, SERVER_DOMAIN.len() as u8]
+        );
+        assert_eq!(
+            &request[5..5 + SERVER_DOMAIN.len()],
+            SERVER_DOMAIN.as_bytes()
+        );
+        assert_eq!(&request[5 + SERVER_DOMAIN.len()..], &443u16.to_be_bytes());
+        client
+            .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0x01, 0xbb])
+            .await
+            .unwrap();
+    } else {
+        let mut request = vec![byte[0]];
+        while !request.ends_with(b"\r\n\r\n") {
+            client.read_exact(&mut byte).await.unwrap();
+            request.push(byte[0]);
+        }
+        assert!(request.starts_with(format!("CONNECT {SERVER_DOMAIN}:443 HTTP/1.1\r\n").as_bytes()));
+        client
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .await
+            .unwrap();
+    }
+
+    _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+}
+
+#[instrument(skip(notary_socket))]
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+    notary_socket: T,
+    proxy_config: Proxy,
+) {
+    let (client_socket, proxy_socket) = tokio::io::duplex(2 << 16);
+    let (upstream_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+    tokio::spawn(proxy(proxy_socket, upstream_socket));
+
+    let mut root_store = RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .proxy(proxy_config)
+            .build()
+            .unwrap(),
+    )
+    .setup(notary_socket.compat())
+    .await
+    .unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let request = format!("GET / HTTP/1.1\r\nHost: {SERVER_DOMAIN}\r\nConnection: close\r\n\r\n")
+        .into_bytes();
+    tls_connection.write_all(&request).await.unwrap();
+
+    let mut response = Vec::new();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+    tls_connection.close().await.unwrap();
+
+    server_task.await.unwrap().unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+    assert_eq!(prover.sent_transcript().data(), &request[..]);
+    assert_eq!(prover.recv_transcript().data(), &response[..]);
+
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    prover.finalize().await.unwrap();
+}
+
+#[instrument(skip(socket))]
+async fn notary<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(socket: T) {
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap());
+    let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+    _ = verifier
+        .notarize::<_, p256::ecdsa::Signature>(socket.compat(), &signing_key)
+        .await
+        .unwrap();
+}
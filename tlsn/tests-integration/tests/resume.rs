@@ -0,0 +1,135 @@
+use futures::{channel::mpsc, AsyncReadExt, AsyncWriteExt, SinkExt};
+use tls_core::anchors::RootCertStore;
+use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Socket, Verifier, VerifierConfig};
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _, DuplexStream};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::instrument;
+
+#[tokio::test]
+#[ignore]
+async fn resume_dropped_connection() {
+    tracing_subscriber::fmt::init();
+
+    let (prover_socket, relay_prover) = tokio::io::duplex(2 << 23);
+    let (relay_notary, notary_socket) = tokio::io::duplex(2 << 23);
+
+    // Drop the first connection part way through the MPC setup.
+    tokio::spawn(relay(relay_prover, relay_notary, 1 << 20));
+
+    let (reconnect_sender, reconnect_receiver) = mpsc::channel(1);
+
+    tokio::join!(
+        prover(prover_socket, reconnect_sender),
+        notary(notary_socket, reconnect_receiver)
+    );
+}
+
+/// Relays data between the sockets, dropping the connection once `limit` bytes were relayed.
+async fn relay(prover: DuplexStream, notary: DuplexStream, limit: usize) {
+    let (mut prover_read, mut prover_write) = tokio::io::split(prover);
+    let (mut notary_read, mut notary_write) = tokio::io::split(notary);
+    let mut prover_buf = vec![0u8; 4096];
+    let mut notary_buf = vec![0u8; 4096];
+
+    let mut relayed = 0;
+    while relayed < limit {
+        tokio::select! {
+            read = prover_read.read(&mut prover_buf) => {
+                let read = read.unwrap();
+                if read == 0 {
+                    return;
+                }
+                notary_write.write_all(&prover_buf[..read]).await.unwrap();
+                relayed += read;
+            }
+            read = notary_read.read(&mut notary_buf) => {
+                let read = read.unwrap();
+                if read == 0 {
+                    return;
+                }
+                prover_write.write_all(&notary_buf[..read]).await.unwrap();
+                relayed += read;
+            }
+        }
+    }
+}
+
+#[instrument(skip(notary_socket, reconnect_sender))]
+async fn prover(notary_socket: DuplexStream, reconnect_sender: mpsc::Sender<Box<dyn Socket>>) {
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let mut root_store = RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    // Reconnects by passing a new connection to the notary.
+    let reconnect = move || {
+        let mut reconnect_sender = reconnect_sender.clone();
+        async move {
+            let (prover_socket, notary_socket) = tokio::io::duplex(2 << 23);
+            reconnect_sender
+                .send(Box::new(notary_socket.compat()))
+                .await
+                .map_err(|_| std::io::Error::from(std::io::ErrorKind::ConnectionRefused))?;
+
+            Ok(prover_socket.compat())
+        }
+    };
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .build()
+            .unwrap(),
+    )
+    .setup_resumable(notary_socket.compat(), reconnect)
+    .await
+    .unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let request = format!("GET / HTTP/1.1\r\nHost: {SERVER_DOMAIN}\r\nConnection: close\r\n\r\n")
+        .into_bytes();
+    tls_connection.write_all(&request).await.unwrap();
+
+    let mut response = Vec::new();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+    tls_connection.close().await.unwrap();
+
+    server_task.await.unwrap().unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    prover.finalize().await.unwrap();
+}
+
+#[instrument(skip(socket, reconnect_receiver))]
+async fn notary(socket: DuplexStream, reconnect_receiver: mpsc::Receiver<Box<dyn Socket>>) {
+    let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+    _ = Verifier::new(VerifierConfig::builder().id("test").build().unwrap())
+        .setup_resumable(socket.compat(), reconnect_receiver)
+        .await
+        .unwrap()
+        .run()
+        .await
+        .unwrap()
+        .start_notarize()
+        .finalize::<p256::ecdsa::Signature>(&signing_key)
+        .await
+        .unwrap();
+}
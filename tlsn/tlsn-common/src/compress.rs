@@ -0,0 +1,546 @@
+//! Compression of the data sent on multiplexed channels.
+//!
+//! Each party chooses which of the channels it sends compressed data on, and announces them to
+//! the other party during setup, see [`PeerCompression`]. Channels opened before that, and
+//! channels neither party compresses, carry the data as is. The data of a compressed channel is
+//! sent in frames of at most [`MAX_FRAME_LEN`] bytes, each compressed with zstd on its own. A
+//! frame which doesn't shrink, e.g. random-looking data such as garbled tables, is sent as is, so
+//! compressing a channel costs at most the frame headers.
+//!
+//! Compressing requires the `compression` feature, which is enabled by default. Without it, a
+//! party can't compress its channels and refuses a peer which announces compressed channels.
+
+#[cfg(feature = "compression")]
+use std::task::ready;
+use std::{
+    io::{Error, ErrorKind, Result},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncWrite};
+use uid_mux::{yamux, UidYamuxControl};
+use utils_aio::mux::{MuxStream, MuxerError};
+
+/// Default zstd compression level, which favors speed over ratio.
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 1;
+/// Maximum number of bytes of data in a frame.
+pub const MAX_FRAME_LEN: usize = 1 << 16;
+
+/// Kind of a frame whose payload is the data as is.
+#[cfg(feature = "compression")]
+const FRAME_RAW: u8 = 0;
+/// Kind of a frame whose payload is the zstd compressed data.
+#[cfg(feature = "compression")]
+const FRAME_ZSTD: u8 = 1;
+/// Length of the frame header: the kind, the length of the data and the length of the payload.
+#[cfg(feature = "compression")]
+const FRAME_HEADER_LEN: usize = 9;
+
+/// Configuration of the channels whose sent data is compressed.
+///
+/// Nothing is compressed by default. Whether compression pays off depends on the channel: the
+/// bytes saved on structured messages have to outweigh the time spent compressing, which is worth
+/// measuring with the `tracing` feature enabled before turning it on for a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compression {
+    /// Prefixes of the ids of the compressed channels.
+    channels: Vec<String>,
+    /// zstd compression level.
+    level: i32,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            level: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl Compression {
+    /// Creates a new configuration compressing the channels whose ids start with one of the given
+    /// prefixes, e.g. `"ot/"` for the oblivious transfer channels.
+    #[cfg(feature = "compression")]
+    pub fn new<I, S>(channels: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            channels: channels.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the zstd compression level.
+    #[cfg(feature = "compression")]
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Returns the prefixes of the ids of the compressed channels.
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// Returns the zstd compression level.
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    /// Returns whether the data sent on the channel with the given id is compressed.
+    pub fn is_compressed(&self, id: &str) -> bool {
+        self.channels.iter().any(|prefix| id.starts_with(prefix))
+    }
+}
+
+/// The channels on which the other party sends compressed data.
+///
+/// Both parties announce the prefixes of the ids of the channels they compress during setup, and
+/// set those of the other party here. Until then, streams carry the data as is in both
+/// directions, and a party only compresses the channels it opens afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct PeerCompression(Arc<Mutex<Option<Vec<String>>>>);
+
+impl PeerCompression {
+    /// Sets the prefixes of the ids of the channels the other party compresses.
+    ///
+    /// Returns an error if the other party compresses any channel but the `compression` feature
+    /// is disabled.
+    pub fn set(&self, channels: Vec<String>) -> Result<()> {
+        if cfg!(not(feature = "compression")) && !channels.is_empty() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "the other party compresses channels {channels:?}, which requires the \
+                     compression feature"
+                ),
+            ));
+        }
+
+        *self.0.lock().unwrap() = Some(channels);
+        Ok(())
+    }
+
+    /// Returns whether the compressed channels of the other party are known, and if so whether it
+    /// compresses the channel with the given id.
+    fn is_compressed(&self, id: &str) -> Option<bool> {
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|channels| channels.iter().any(|prefix| id.starts_with(prefix)))
+    }
+}
+
+/// A muxer control opening streams which compress the data sent on the configured channels.
+#[derive(Debug, Clone)]
+pub struct CompressedMuxControl {
+    control: UidYamuxControl,
+    compression: Compression,
+    peer: PeerCompression,
+}
+
+impl CompressedMuxControl {
+    /// Creates a new control wrapping the provided one.
+    ///
+    /// # Arguments
+    ///
+    /// * `control` - The control of the muxer.
+    /// * `compression` - The channels whose sent data is compressed.
+    /// * `peer` - The channels whose received data is compressed, once known.
+    pub fn new(control: UidYamuxControl, compression: Compression, peer: PeerCompression) -> Self {
+        Self {
+            control,
+            compression,
+            peer,
+        }
+    }
+
+    /// Closes the connection.
+    pub async fn close(&mut self) -> std::result::Result<(), MuxerError> {
+        self.control.close().await
+    }
+}
+
+#[async_trait]
+impl MuxStream for CompressedMuxControl {
+    type Stream = ChannelStream<yamux::Stream>;
+
+    async fn get_stream(&mut self, id: &str) -> std::result::Result<Self::Stream, MuxerError> {
+        let stream = self.control.get_stream(id).await?;
+
+        // Nothing is compressed until both parties know the compressed channels of each other.
+        let Some(peer_compressed) = self.peer.is_compressed(id) else {
+            return Ok(ChannelStream::plain(id, stream));
+        };
+        let level = self
+            .compression
+            .is_compressed(id)
+            .then_some(self.compression.level);
+
+        Ok(ChannelStream::new(id, stream, level, peer_compressed))
+    }
+}
+
+/// A stream of a channel, which decodes the data received if the other party compresses the
+/// channel, and sends its data in compressed frames if the channel is compressed.
+pub struct ChannelStream<S> {
+    id: String,
+    inner: S,
+    #[cfg(feature = "compression")]
+    reader: Option<FrameReader>,
+    #[cfg(feature = "compression")]
+    writer: Option<FrameWriter>,
+}
+
+impl<S> std::fmt::Debug for ChannelStream<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ChannelStream");
+        debug.field("id", &self.id);
+        #[cfg(feature = "compression")]
+        debug
+            .field("compressed_recv", &self.reader.is_some())
+            .field("compressed_sent", &self.writer.is_some());
+        debug.finish_non_exhaustive()
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ChannelStream<S> {
+    /// Wraps the stream of a channel whose data is sent as is in both directions.
+    fn plain(id: &str, inner: S) -> Self {
+        Self {
+            id: id.to_string(),
+            inner,
+            #[cfg(feature = "compression")]
+            reader: None,
+            #[cfg(feature = "compression")]
+            writer: None,
+        }
+    }
+
+    /// Wraps the stream of a channel, compressing the data sent with the given level if any, and
+    /// decoding the data received if `recv_compressed` is set.
+    #[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+    fn new(id: &str, inner: S, level: Option<i32>, recv_compressed: bool) -> Self {
+        let mut stream = Self::plain(id, inner);
+        #[cfg(feature = "compression")]
+        {
+            stream.reader = recv_compressed.then(FrameReader::default);
+            stream.writer = level.map(FrameWriter::new);
+        }
+        stream
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ChannelStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        #[cfg(feature = "compression")]
+        if let Some(reader) = &mut this.reader {
+            return reader.poll_read(&mut this.inner, cx, buf);
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ChannelStream<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        #[cfg(feature = "compression")]
+        if let Some(writer) = &mut this.writer {
+            return writer.poll_write(&mut this.inner, cx, buf);
+        }
+        Pin::new(&mut this.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        #[cfg(feature = "compression")]
+        if let Some(writer) = &mut this.writer {
+            ready!(writer.poll_write_frames(&mut this.inner, cx))?;
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        #[cfg(feature = "compression")]
+        if let Some(writer) = &mut this.writer {
+            ready!(writer.poll_write_frames(&mut this.inner, cx))?;
+        }
+        Pin::new(&mut this.inner).poll_close(cx)
+    }
+}
+
+impl<S> Drop for ChannelStream<S> {
+    fn drop(&mut self) {
+        #[cfg(all(feature = "tracing", feature = "compression"))]
+        if let Some(writer) = &self.writer {
+            tracing::debug!(
+                channel = %self.id,
+                data_bytes = writer.data_bytes,
+                sent_bytes = writer.sent_bytes,
+                "compressed channel closed"
+            );
+        }
+    }
+}
+
+/// Reads the frames of a compressed stream.
+#[cfg(feature = "compression")]
+#[derive(Default)]
+struct FrameReader {
+    header: [u8; FRAME_HEADER_LEN],
+    header_read: usize,
+    /// The payload of the current frame, once its header is read.
+    payload: Option<Vec<u8>>,
+    payload_read: usize,
+    /// The data of the last frame, and the position up to which it has been read.
+    data: Vec<u8>,
+    pos: usize,
+}
+
+#[cfg(feature = "compression")]
+impl FrameReader {
+    fn poll_read<S: AsyncRead + Unpin>(
+        &mut self,
+        inner: &mut S,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        loop {
+            if self.pos < self.data.len() {
+                let n = buf.len().min(self.data.len() - self.pos);
+                buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+                self.pos += n;
+                return Poll::Ready(Ok(n));
+            }
+
+            if self.header_read < FRAME_HEADER_LEN {
+                let n = ready!(
+                    Pin::new(&mut *inner).poll_read(cx, &mut self.header[self.header_read..])
+                )?;
+                if n == 0 {
+                    // The stream may only end between frames.
+                    if self.header_read == 0 {
+                        return Poll::Ready(Ok(0));
+                    }
+                    return Poll::Ready(Err(ErrorKind::UnexpectedEof.into()));
+                }
+                self.header_read += n;
+                continue;
+            }
+
+            let (kind, data_len, payload_len) = self.parse_header()?;
+            let payload = self.payload.get_or_insert_with(|| vec![0u8; payload_len]);
+            while self.payload_read < payload.len() {
+                let n =
+                    ready!(Pin::new(&mut *inner).poll_read(cx, &mut payload[self.payload_read..]))?;
+                if n == 0 {
+                    return Poll::Ready(Err(ErrorKind::UnexpectedEof.into()));
+                }
+                self.payload_read += n;
+            }
+
+            let payload = self.payload.take().expect("payload is set");
+            self.data = match kind {
+                FRAME_RAW => payload,
+                FRAME_ZSTD => zstd::bulk::decompress(&payload, data_len)?,
+                _ => unreachable!("frame kind is checked when parsing the header"),
+            };
+            if self.data.len() != data_len {
+                return Poll::Ready(Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "frame data doesn't match its length",
+                )));
+            }
+            self.pos = 0;
+            self.header_read = 0;
+            self.payload_read = 0;
+        }
+    }
+
+    fn parse_header(&self) -> Result<(u8, usize, usize)> {
+        let kind = self.header[0];
+        let data_len = u32::from_be_bytes(self.header[1..5].try_into().unwrap()) as usize;
+        let payload_len = u32::from_be_bytes(self.header[5..9].try_into().unwrap()) as usize;
+
+        if kind != FRAME_RAW && kind != FRAME_ZSTD {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown frame kind {kind}"),
+            ));
+        }
+        // Bounds the memory of a frame, as the lengths come from the other party.
+        if data_len > MAX_FRAME_LEN || payload_len > zstd::zstd_safe::compress_bound(MAX_FRAME_LEN)
+        {
+            return Err(Error::new(ErrorKind::InvalidData, "frame is too long"));
+        }
+
+        Ok((kind, data_len, payload_len))
+    }
+}
+
+/// Writes the data of a compressed stream in frames.
+#[cfg(feature = "compression")]
+struct FrameWriter {
+    level: i32,
+    /// Data which hasn't been framed yet.
+    pending: Vec<u8>,
+    /// The frame being written, and the position up to which it has been written.
+    frame: Vec<u8>,
+    pos: usize,
+    /// Number of bytes of data written, and of bytes sent on the stream for them.
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    data_bytes: u64,
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    sent_bytes: u64,
+}
+
+#[cfg(feature = "compression")]
+impl FrameWriter {
+    fn new(level: i32) -> Self {
+        Self {
+            level,
+            pending: Vec::new(),
+            frame: Vec::new(),
+            pos: 0,
+            data_bytes: 0,
+            sent_bytes: 0,
+        }
+    }
+
+    fn poll_write<S: AsyncWrite + Unpin>(
+        &mut self,
+        inner: &mut S,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        if self.pending.len() >= MAX_FRAME_LEN {
+            ready!(self.poll_write_frames(inner, cx))?;
+        }
+
+        let n = buf.len().min(MAX_FRAME_LEN - self.pending.len());
+        self.pending.extend_from_slice(&buf[..n]);
+
+        Poll::Ready(Ok(n))
+    }
+
+    /// Writes the pending data in frames, until all of it is written to the stream.
+    fn poll_write_frames<S: AsyncWrite + Unpin>(
+        &mut self,
+        inner: &mut S,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<()>> {
+        loop {
+            while self.pos < self.frame.len() {
+                let n = ready!(Pin::new(&mut *inner).poll_write(cx, &self.frame[self.pos..]))?;
+                if n == 0 {
+                    return Poll::Ready(Err(ErrorKind::WriteZero.into()));
+                }
+                self.pos += n;
+            }
+
+            if self.pending.is_empty() {
+                return Poll::Ready(Ok(()));
+            }
+            self.encode_frame()?;
+        }
+    }
+
+    fn encode_frame(&mut self) -> Result<()> {
+        let compressed = zstd::bulk::compress(&self.pending, self.level)?;
+        let (kind, payload) = if compressed.len() < self.pending.len() {
+            (FRAME_ZSTD, compressed.as_slice())
+        } else {
+            (FRAME_RAW, self.pending.as_slice())
+        };
+
+        self.frame.clear();
+        self.frame.push(kind);
+        self.frame
+            .extend_from_slice(&(self.pending.len() as u32).to_be_bytes());
+        self.frame
+            .extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        self.frame.extend_from_slice(payload);
+        self.pos = 0;
+
+        self.data_bytes += self.pending.len() as u64;
+        self.sent_bytes += self.frame.len() as u64;
+        self.pending.clear();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_compressed_stream() {
+        let (a, b) = tokio::io::duplex(1 << 20);
+        let mut a = ChannelStream::new("test", a.compat(), Some(DEFAULT_COMPRESSION_LEVEL), false);
+        let mut b = ChannelStream::new("test", b.compat(), None, true);
+
+        // Compressible data spanning several frames, followed by data which doesn't compress
+        let mut data = vec![42u8; 3 * MAX_FRAME_LEN + 100];
+        data.extend((0..1000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8));
+
+        a.write_all(&data).await.unwrap();
+        a.close().await.unwrap();
+        let sent_bytes = a.writer.as_ref().unwrap().sent_bytes;
+        assert!(sent_bytes < data.len() as u64);
+
+        let mut received = Vec::new();
+        b.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, data);
+    }
+
+    #[tokio::test]
+    async fn test_plain_stream() {
+        let (a, mut b) = tokio::io::duplex(1 << 20);
+        let mut a = ChannelStream::new("test", a.compat(), None, false);
+
+        a.write_all(b"hello").await.unwrap();
+        a.close().await.unwrap();
+
+        // The data of a channel which isn't compressed is sent as is, without any framing.
+        let mut received = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut b, &mut received)
+            .await
+            .unwrap();
+        assert_eq!(received, b"hello");
+    }
+
+    #[test]
+    fn test_peer_compression() {
+        let peer = PeerCompression::default();
+        assert_eq!(peer.is_compressed("ot/0"), None);
+
+        peer.set(Vec::new()).unwrap();
+        assert_eq!(peer.is_compressed("ot/0"), Some(false));
+
+        let result = peer.set(vec!["ot/".to_string()]);
+        if cfg!(feature = "compression") {
+            result.unwrap();
+            assert_eq!(peer.is_compressed("ot/0"), Some(true));
+            assert_eq!(peer.is_compressed("setup"), Some(false));
+        } else {
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::Unsupported);
+        }
+    }
+}
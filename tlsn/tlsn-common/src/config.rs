@@ -6,6 +6,9 @@ use crate::Role;
 pub const DEFAULT_MAX_SENT_LIMIT: usize = 1 << 12;
 /// Default for the maximum number of bytes that can be received (16Kb).
 pub const DEFAULT_MAX_RECV_LIMIT: usize = 1 << 14;
+/// Default for the maximum number of threads which garble and evaluate the encryption and
+/// decryption circuits in parallel.
+pub const DEFAULT_MAX_THREADS: usize = 8;
 
 // Determined experimentally, will be subject to change if underlying protocols are modified.
 const KE_OTS: usize = 3360;
@@ -18,6 +21,10 @@ const OTS_PER_BYTE_SENT: usize = 8;
 const OTS_PER_BYTE_RECV: usize = 16;
 
 /// Returns an estimate of the number of OTs that will be sent.
+///
+/// Unlike the encryption and decryption circuits, which are preprocessed incrementally, OTs are
+/// set up for the maximum amount of data, as OT extension can only be performed once per
+/// session. Extending OTs is cheap compared to garbling, so this costs little setup time.
 pub fn ot_send_estimate(role: Role, max_sent_data: usize, max_recv_data: usize) -> usize {
     match role {
         Role::Prover => KE_OTS + GHASH_OTS + EXTRA_OTS,
@@ -31,6 +38,8 @@ pub fn ot_send_estimate(role: Role, max_sent_data: usize, max_recv_data: usize)
 }
 
 /// Returns an estimate of the number of OTs that will be received.
+///
+/// See [`ot_send_estimate`] on why this depends on the maximum amount of data.
 pub fn ot_recv_estimate(role: Role, max_sent_data: usize, max_recv_data: usize) -> usize {
     match role {
         Role::Prover => {
@@ -4,8 +4,10 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+pub mod compress;
 pub mod config;
 pub mod mux;
+pub mod resume;
 
 /// The party's role in the TLSN protocol.
 ///
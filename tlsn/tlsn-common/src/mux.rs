@@ -3,30 +3,36 @@
 use utils_aio::codec::BincodeMux;
 
 use futures::{AsyncRead, AsyncWrite};
-use uid_mux::{yamux, UidYamux, UidYamuxControl};
+use uid_mux::{yamux, UidYamux};
 
-use crate::Role;
+use crate::{
+    compress::{CompressedMuxControl, Compression, PeerCompression},
+    Role,
+};
 
 /// Multiplexer supporting unique deterministic stream IDs.
 pub type Mux<T> = UidYamux<T>;
 /// Multiplexer controller providing streams with a codec attached.
-pub type MuxControl = BincodeMux<UidYamuxControl>;
+pub type MuxControl = BincodeMux<CompressedMuxControl>;
 
 const KB: usize = 1024;
 const MB: usize = 1024 * KB;
 
 /// Attaches a multiplexer to the provided socket.
 ///
-/// Returns the multiplexer and a controller for creating streams with a codec attached.
+/// Returns the multiplexer, a controller for creating streams with a codec attached, and the
+/// channels the other party compresses, which must be set once they are announced during setup.
 ///
 /// # Arguments
 ///
 /// * `socket` - The socket to attach the multiplexer to.
 /// * `role` - The role of the party using the multiplexer.
+/// * `compression` - The channels whose sent data is compressed.
 pub fn attach_mux<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     socket: T,
     role: Role,
-) -> (Mux<T>, MuxControl) {
+    compression: Compression,
+) -> (Mux<T>, MuxControl, PeerCompression) {
     let mut mux_config = yamux::Config::default();
     // See PR #418
     mux_config.set_max_num_streams(40);
@@ -39,7 +45,12 @@ pub fn attach_mux<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     };
 
     let mux = UidYamux::new(mux_config, socket, mux_role);
-    let ctrl = BincodeMux::new(mux.control());
-
-    (mux, ctrl)
+    let peer_compression = PeerCompression::default();
+    let ctrl = BincodeMux::new(CompressedMuxControl::new(
+        mux.control(),
+        compression,
+        peer_compression.clone(),
+    ));
+
+    (mux, ctrl, peer_compression)
 }
@@ -0,0 +1,630 @@
+//! Resumable connections between the prover and the verifier.
+//!
+//! The MPC protocols exchange a large amount of data over a single connection, so a dropped
+//! connection would otherwise fail the whole session and waste the work done so far. A
+//! [resumable](resumable) connection buffers the data sent until the peer acknowledges it. When
+//! the connection drops, a new one is obtained with [`Reconnect`], and each party resends the data
+//! the other has not received, so the protocols above continue where they left off.
+//!
+//! Both parties must use a resumable connection, as the data is framed on the wire.
+
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    channel::mpsc, future::BoxFuture, ready, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+    Future, FutureExt, StreamExt,
+};
+
+/// Default for the maximum number of consecutive failed attempts to reconnect.
+pub const DEFAULT_MAX_RECONNECTS: usize = 3;
+
+/// The maximum length of a data frame.
+const MAX_FRAME_LEN: usize = 1 << 16;
+/// The number of bytes received after which receipt is acknowledged.
+const ACK_INTERVAL: u64 = 1 << 18;
+
+const DATA: u8 = 0;
+const ACK: u8 = 1;
+const RESUME: u8 = 2;
+const CLOSE: u8 = 3;
+
+/// A connection between the prover and the verifier.
+pub trait Socket: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Socket for T {}
+
+/// Obtains a new connection to the peer after the previous one dropped.
+///
+/// This is implemented for closures returning a future which opens a new connection, which is
+/// what the prover uses, and for a channel over which new connections are passed, which is what
+/// the verifier uses to receive connections accepted elsewhere.
+pub trait Reconnect: Send {
+    /// Returns a new connection to the peer.
+    fn reconnect(&mut self) -> BoxFuture<'_, io::Result<Box<dyn Socket>>>;
+}
+
+impl<F, Fut, S> Reconnect for F
+where
+    F: FnMut() -> Fut + Send,
+    Fut: Future<Output = io::Result<S>> + Send + 'static,
+    S: Socket + 'static,
+{
+    fn reconnect(&mut self) -> BoxFuture<'_, io::Result<Box<dyn Socket>>> {
+        let fut = self();
+        Box::pin(async move { Ok(Box::new(fut.await?) as Box<dyn Socket>) })
+    }
+}
+
+impl Reconnect for mpsc::Receiver<Box<dyn Socket>> {
+    fn reconnect(&mut self) -> BoxFuture<'_, io::Result<Box<dyn Socket>>> {
+        Box::pin(async move {
+            self.next()
+                .await
+                .ok_or_else(|| io::Error::from(io::ErrorKind::ConnectionAborted))
+        })
+    }
+}
+
+/// Makes a connection resumable.
+///
+/// Returns the resumable connection and a future which must be polled for it to make progress.
+/// The future resolves once both parties have closed the connection, or with an error once
+/// `max_reconnects` consecutive attempts to reconnect have failed.
+///
+/// # Arguments
+///
+/// * `socket` - The connection to the peer.
+/// * `reconnect` - Obtains a new connection when the current one drops.
+/// * `max_reconnects` - The maximum number of consecutive failed attempts to reconnect.
+pub fn resumable<S: Socket + 'static, R: Reconnect + 'static>(
+    socket: S,
+    reconnect: R,
+    max_reconnects: usize,
+) -> (ResumableSocket, BoxFuture<'static, io::Result<()>>) {
+    let (outgoing_sender, outgoing) = mpsc::unbounded();
+    let (incoming, incoming_receiver) = mpsc::unbounded();
+
+    let driver = Driver {
+        reconnect,
+        max_reconnects,
+        failures: 0,
+        incoming: Some(incoming),
+        unacked: VecDeque::new(),
+        sent: 0,
+        acked: 0,
+        received: 0,
+        acked_received: 0,
+        local_closed: false,
+        peer_closed: false,
+    };
+
+    let socket_io = ResumableSocket {
+        outgoing: Some(outgoing_sender),
+        incoming: incoming_receiver,
+        read_buf: Vec::new(),
+        read_pos: 0,
+    };
+
+    (socket_io, Box::pin(driver.run(Box::new(socket), outgoing)))
+}
+
+/// A resumable connection to the peer.
+///
+/// Created with [`resumable`].
+pub struct ResumableSocket {
+    outgoing: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    incoming: mpsc::UnboundedReceiver<Vec<u8>>,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+}
+
+impl AsyncRead for ResumableSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            if self.read_pos < self.read_buf.len() {
+                let len = buf.len().min(self.read_buf.len() - self.read_pos);
+                buf[..len].copy_from_slice(&self.read_buf[self.read_pos..self.read_pos + len]);
+                self.read_pos += len;
+
+                return Poll::Ready(Ok(len));
+            }
+
+            match ready!(self.incoming.poll_next_unpin(cx)) {
+                Some(data) => {
+                    self.read_buf = data;
+                    self.read_pos = 0;
+                }
+                // The peer closed the connection, or the connection could not be resumed.
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+    }
+}
+
+impl AsyncWrite for ResumableSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let sent = self
+            .outgoing
+            .as_mut()
+            .map(|outgoing| outgoing.unbounded_send(buf.to_vec()).is_ok())
+            .unwrap_or(false);
+
+        if sent {
+            Poll::Ready(Ok(buf.len()))
+        } else {
+            Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.outgoing.take();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A frame exchanged between the drivers of the two parties.
+enum Frame {
+    /// Data sent by the peer.
+    Data(Vec<u8>),
+    /// The peer received this many bytes in total.
+    Ack(u64),
+    /// The peer (re)connected, having received this many bytes in total.
+    Resume(u64),
+    /// The peer closed the connection.
+    Close,
+    /// The peer sent a malformed frame.
+    Invalid(&'static str),
+}
+
+impl Frame {
+    async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Self> {
+        let mut kind = [0u8; 1];
+        reader.read_exact(&mut kind).await?;
+
+        let frame = match kind[0] {
+            DATA => {
+                let mut len = [0u8; 4];
+                reader.read_exact(&mut len).await?;
+
+                let len = u32::from_be_bytes(len) as usize;
+                if len == 0 || len > MAX_FRAME_LEN {
+                    return Ok(Frame::Invalid("invalid data frame length"));
+                }
+
+                let mut data = vec![0u8; len];
+                reader.read_exact(&mut data).await?;
+
+                Frame::Data(data)
+            }
+            ACK | RESUME => {
+                let mut received = [0u8; 8];
+                reader.read_exact(&mut received).await?;
+
+                let received = u64::from_be_bytes(received);
+                if kind[0] == ACK {
+                    Frame::Ack(received)
+                } else {
+                    Frame::Resume(received)
+                }
+            }
+            CLOSE => Frame::Close,
+            _ => Frame::Invalid("unknown frame type"),
+        };
+
+        Ok(frame)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Frame::Data(data) => {
+                let mut frame = Vec::with_capacity(5 + data.len());
+                frame.push(DATA);
+                frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+                frame.extend_from_slice(data);
+                frame
+            }
+            Frame::Ack(received) => [&[ACK][..], &received.to_be_bytes()].concat(),
+            Frame::Resume(received) => [&[RESUME][..], &received.to_be_bytes()].concat(),
+            Frame::Close => vec![CLOSE],
+            Frame::Invalid(_) => unreachable!("invalid frames are never sent"),
+        }
+    }
+}
+
+/// How a connection ended.
+enum Disconnect {
+    /// Both parties closed the connection.
+    Closed,
+    /// The connection was lost.
+    Lost(io::Error),
+}
+
+/// Drives a resumable connection, reconnecting when the connection is lost.
+struct Driver<R> {
+    reconnect: R,
+    max_reconnects: usize,
+    /// Consecutive failed attempts to reconnect.
+    failures: usize,
+    /// Data received from the peer, forwarded to the application.
+    incoming: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    /// Data sent which the peer has not acknowledged yet.
+    unacked: VecDeque<Vec<u8>>,
+    /// Total bytes sent.
+    sent: u64,
+    /// Total bytes acknowledged by the peer.
+    acked: u64,
+    /// Total bytes received.
+    received: u64,
+    /// Total bytes received as of the last acknowledgement sent to the peer.
+    acked_received: u64,
+    local_closed: bool,
+    peer_closed: bool,
+}
+
+impl<R: Reconnect> Driver<R> {
+    async fn run(
+        mut self,
+        socket: Box<dyn Socket>,
+        mut outgoing: mpsc::UnboundedReceiver<Vec<u8>>,
+    ) -> io::Result<()> {
+        let mut first = Some(socket);
+        loop {
+            let socket = match first.take() {
+                Some(socket) => socket,
+                None => match self.reconnect.reconnect().await {
+                    Ok(socket) => socket,
+                    Err(err) => {
+                        self.lost(err)?;
+                        continue;
+                    }
+                },
+            };
+
+            match self.run_connection(socket, &mut outgoing).await? {
+                Disconnect::Closed => return Ok(()),
+                Disconnect::Lost(err) => self.lost(err)?,
+            }
+        }
+    }
+
+    /// Records a failed attempt to reconnect, returning an error once too many have failed.
+    fn lost(&mut self, err: io::Error) -> io::Result<()> {
+        self.failures += 1;
+        if self.failures > self.max_reconnects {
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Exchanges data over a connection until both parties have closed it, or the connection is
+    /// lost.
+    ///
+    /// Returns an error if the peer violates the protocol, in which case the connection is not
+    /// resumed.
+    async fn run_connection(
+        &mut self,
+        socket: Box<dyn Socket>,
+        outgoing: &mut mpsc::UnboundedReceiver<Vec<u8>>,
+    ) -> io::Result<Disconnect> {
+        let (reader, mut writer) = socket.split();
+
+        let mut frames = futures::stream::unfold(reader, |mut reader| async move {
+            let frame = Frame::read(&mut reader).await;
+            Some((frame, reader))
+        })
+        .boxed()
+        .fuse();
+
+        let (frame_sender, mut frame_receiver) = mpsc::unbounded::<Vec<u8>>();
+        let mut write_fut = Box::pin(
+            async move {
+                while let Some(frame) = frame_receiver.next().await {
+                    writer.write_all(&frame).await?;
+                    writer.flush().await?;
+                }
+                writer.close().await
+            }
+            .fuse(),
+        );
+
+        let send = |frame: Frame| {
+            // The receiver is only dropped once the writer failed, which is handled below.
+            _ = frame_sender.unbounded_send(frame.encode());
+        };
+
+        // Data is only sent once the peer told us what it has received.
+        let mut resumed = false;
+        send(Frame::Resume(self.received));
+
+        loop {
+            futures::select! {
+                frame = frames.select_next_some() => {
+                    let frame = match frame {
+                        Ok(frame) => frame,
+                        Err(err) => return Ok(Disconnect::Lost(err)),
+                    };
+
+                    match frame {
+                        Frame::Data(data) => {
+                            let Some(incoming) = &self.incoming else {
+                                return Err(protocol_error("received data after the peer closed"));
+                            };
+
+                            self.received += data.len() as u64;
+                            // Ignore if the application dropped the connection.
+                            _ = incoming.unbounded_send(data);
+
+                            if self.received - self.acked_received >= ACK_INTERVAL {
+                                self.acked_received = self.received;
+                                send(Frame::Ack(self.received));
+                            }
+                        }
+                        Frame::Ack(received) => self.acknowledge(received)?,
+                        Frame::Resume(received) => {
+                            if resumed {
+                                return Err(protocol_error("peer resumed the connection twice"));
+                            }
+
+                            self.acknowledge(received)?;
+                            for data in &self.unacked {
+                                send(Frame::Data(data.clone()));
+                            }
+                            if self.local_closed {
+                                send(Frame::Close);
+                            }
+
+                            resumed = true;
+                            self.failures = 0;
+                        }
+                        Frame::Close => {
+                            self.peer_closed = true;
+                            self.incoming = None;
+                            // Acknowledge all data so the peer is able to finish.
+                            self.acked_received = self.received;
+                            send(Frame::Ack(self.received));
+                        }
+                        Frame::Invalid(msg) => return Err(protocol_error(msg)),
+                    }
+                }
+                data = outgoing.next() => match data {
+                    Some(data) => {
+                        for chunk in data.chunks(MAX_FRAME_LEN) {
+                            self.sent += chunk.len() as u64;
+                            self.unacked.push_back(chunk.to_vec());
+                            if resumed {
+                                send(Frame::Data(chunk.to_vec()));
+                            }
+                        }
+                    }
+                    None => {
+                        self.local_closed = true;
+                        if resumed {
+                            send(Frame::Close);
+                        }
+                    }
+                },
+                res = &mut write_fut => {
+                    let err = res.err().unwrap_or_else(|| io::ErrorKind::WriteZero.into());
+                    return Ok(Disconnect::Lost(err));
+                }
+            }
+
+            if self.local_closed && self.peer_closed && self.unacked.is_empty() {
+                // Flush the remaining frames before closing the connection.
+                drop(send);
+                drop(frame_sender);
+                _ = write_fut.await;
+
+                return Ok(Disconnect::Closed);
+            }
+        }
+    }
+
+    /// Drops the data which the peer has received.
+    fn acknowledge(&mut self, received: u64) -> io::Result<()> {
+        if received < self.acked || received > self.sent {
+            return Err(protocol_error("peer acknowledged data which was not sent"));
+        }
+
+        let mut len = (received - self.acked) as usize;
+        while len > 0 {
+            let data = self
+                .unacked
+                .front_mut()
+                .expect("unacknowledged data is buffered");
+            if data.len() <= len {
+                len -= data.len();
+                self.unacked.pop_front();
+            } else {
+                data.drain(..len);
+                len = 0;
+            }
+        }
+        self.acked = received;
+
+        Ok(())
+    }
+}
+
+fn protocol_error(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    /// A connection which fails once `remaining` bytes have been written to it.
+    struct FailAfter<S> {
+        inner: S,
+        remaining: usize,
+    }
+
+    impl<S: AsyncRead + Unpin> AsyncRead for FailAfter<S> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<S: AsyncWrite + Unpin> AsyncWrite for FailAfter<S> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.remaining == 0 {
+                return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+            }
+
+            let len = buf.len().min(self.remaining);
+            let written = ready!(Pin::new(&mut self.inner).poll_write(cx, &buf[..len]))?;
+            self.remaining -= written;
+
+            Poll::Ready(Ok(written))
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_close(cx)
+        }
+    }
+
+    fn driver() -> Driver<mpsc::Receiver<Box<dyn Socket>>> {
+        let (_, reconnect) = mpsc::channel(1);
+        let (incoming, _) = mpsc::unbounded();
+
+        Driver {
+            reconnect,
+            max_reconnects: 0,
+            failures: 0,
+            incoming: Some(incoming),
+            unacked: VecDeque::new(),
+            sent: 0,
+            acked: 0,
+            received: 0,
+            acked_received: 0,
+            local_closed: false,
+            peer_closed: false,
+        }
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        for frame in [
+            Frame::Data(vec![1, 2, 3]),
+            Frame::Ack(42),
+            Frame::Resume(u64::MAX),
+            Frame::Close,
+        ] {
+            let bytes = frame.encode();
+            let decoded = block_on(Frame::read(&mut bytes.as_slice())).unwrap();
+            assert_eq!(decoded.encode(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        let empty = [&[DATA][..], &0u32.to_be_bytes()].concat();
+        let oversized = [&[DATA][..], &(MAX_FRAME_LEN as u32 + 1).to_be_bytes()].concat();
+
+        for bytes in [empty, oversized, vec![0xff]] {
+            assert!(matches!(
+                block_on(Frame::read(&mut bytes.as_slice())).unwrap(),
+                Frame::Invalid(_)
+            ));
+        }
+
+        // A truncated frame is a lost connection rather than a protocol violation.
+        let truncated = [&[DATA][..], &8u32.to_be_bytes(), &[0u8; 4]].concat();
+        assert!(block_on(Frame::read(&mut truncated.as_slice())).is_err());
+    }
+
+    #[test]
+    fn test_acknowledge() {
+        let mut driver = driver();
+        driver.unacked = VecDeque::from([vec![0u8; 10], vec![1u8; 10]]);
+        driver.sent = 20;
+
+        // Acknowledged data is dropped, including part of a chunk.
+        driver.acknowledge(15).unwrap();
+        assert_eq!(driver.unacked, VecDeque::from([vec![1u8; 5]]));
+        assert_eq!(driver.acked, 15);
+
+        // Acknowledging data which was not sent, or less than before, is a protocol violation.
+        assert!(driver.acknowledge(21).is_err());
+        assert!(driver.acknowledge(10).is_err());
+
+        driver.acknowledge(20).unwrap();
+        assert!(driver.unacked.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resend_after_reconnect() {
+        let (a1, b1) = tokio::io::duplex(1 << 16);
+        let (a2, b2) = tokio::io::duplex(1 << 16);
+
+        let (mut a_reconnect, a_receiver) = mpsc::channel::<Box<dyn Socket>>(1);
+        let (mut b_reconnect, b_receiver) = mpsc::channel::<Box<dyn Socket>>(1);
+        a_reconnect.try_send(Box::new(a2.compat())).unwrap();
+        b_reconnect.try_send(Box::new(b2.compat())).unwrap();
+
+        // The first connection fails in the middle of a frame.
+        let a1 = FailAfter {
+            inner: a1.compat(),
+            remaining: 100_000,
+        };
+        let (mut a, a_fut) = resumable(a1, a_receiver, 1);
+        let (mut b, b_fut) = resumable(b1.compat(), b_receiver, 1);
+        let a_fut = tokio::spawn(a_fut);
+        let b_fut = tokio::spawn(b_fut);
+
+        let data = (0..600_000u32).map(|i| i as u8).collect::<Vec<_>>();
+        a.write_all(&data).await.unwrap();
+        a.close().await.unwrap();
+
+        let mut received = Vec::new();
+        b.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, data);
+
+        b.write_all(b"reply").await.unwrap();
+        b.close().await.unwrap();
+
+        let mut reply = Vec::new();
+        a.read_to_end(&mut reply).await.unwrap();
+        assert_eq!(reply, b"reply");
+
+        a_fut.await.unwrap().unwrap();
+        b_fut.await.unwrap().unwrap();
+    }
+}
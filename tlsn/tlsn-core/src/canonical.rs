@@ -0,0 +1,215 @@
+//! Canonical JSON serialization.
+//!
+//! Produces the JSON Canonicalization Scheme (JCS, RFC 8785) encoding of a value, so that
+//! implementations in other languages which sign or verify JSON encoded session headers and
+//! presentations agree on the exact bytes:
+//!
+//! - Object members are sorted by their keys, compared as UTF-16 code units.
+//! - No insignificant whitespace is emitted.
+//! - Strings are escaped minimally, using lowercase hex for control characters.
+//! - Numbers must be integers which are exactly representable as an IEEE 754 double, ie. with a
+//!   magnitude of at most 2^53, since that is how JCS implementations parse them.
+//!
+//! Notaries sign the canonical JSON encoding of the session header, see
+//! [`SessionHeader::signing_message`], so a verifier in another language only needs a JCS
+//! implementation to check the signature of a JSON encoded presentation.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    proof::{SessionProof, TlsProof},
+    SessionHeader,
+};
+
+/// The largest integer magnitude which is exactly representable as an IEEE 754 double.
+const MAX_SAFE_INTEGER: u64 = 1 << 53;
+
+/// An error for canonical JSON serialization.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CanonicalJsonError {
+    /// The value could not be serialized to JSON.
+    #[error("failed to serialize value to JSON: {0}")]
+    Serialize(#[from] serde_json::Error),
+    /// A number can not be represented in canonical JSON.
+    #[error("number {0} can not be represented in canonical JSON")]
+    UnsupportedNumber(String),
+}
+
+/// Serializes a value to canonical JSON.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, CanonicalJsonError> {
+    let value = serde_json::to_value(value)?;
+
+    let mut out = String::new();
+    write_value(&mut out, &value)?;
+
+    Ok(out)
+}
+
+/// Serializes a value to canonical JSON, returning the UTF-8 bytes.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CanonicalJsonError> {
+    to_string(value).map(String::into_bytes)
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<(), CanonicalJsonError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            let in_range = if let Some(n) = n.as_u64() {
+                n <= MAX_SAFE_INTEGER
+            } else if let Some(n) = n.as_i64() {
+                n.unsigned_abs() <= MAX_SAFE_INTEGER
+            } else {
+                false
+            };
+
+            if !in_range {
+                return Err(CanonicalJsonError::UnsupportedNumber(n.to_string()));
+            }
+
+            out.push_str(&n.to_string());
+        }
+        Value::String(s) => write_string(out, s),
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, value)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, value)?;
+            }
+            out.push('}');
+        }
+    }
+
+    Ok(())
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{09}' => out.push_str("\\t"),
+            '\u{0a}' => out.push_str("\\n"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\u{0d}' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl SessionHeader {
+    /// Returns the canonical JSON encoding of this header.
+    pub fn to_canonical_json(&self) -> Result<String, CanonicalJsonError> {
+        to_string(self)
+    }
+
+    /// Returns the message a Notary signs for this header, which is its canonical JSON encoding.
+    pub fn signing_message(&self) -> Result<Vec<u8>, CanonicalJsonError> {
+        to_vec(self)
+    }
+}
+
+impl SessionProof {
+    /// Returns the canonical JSON encoding of this proof.
+    pub fn to_canonical_json(&self) -> Result<String, CanonicalJsonError> {
+        to_string(self)
+    }
+}
+
+impl TlsProof {
+    /// Returns the canonical JSON encoding of this presentation.
+    pub fn to_canonical_json(&self) -> Result<String, CanonicalJsonError> {
+        to_string(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, merkle::MerkleRoot};
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_json_sorts_keys() {
+        let value = json!({ "b": [1, { "z": null, "a": true }], "a": "x", "\u{e9}": 1, "A": 2 });
+
+        assert_eq!(
+            to_string(&value).unwrap(),
+            r#"{"A":2,"a":"x","b":[1,{"a":true,"z":null}],"é":1}"#
+        );
+    }
+
+    #[test]
+    fn test_canonical_json_escapes_strings() {
+        assert_eq!(to_string("\"\\\n\u{1f}é").unwrap(), r#""\"\\\n\u001fé""#);
+    }
+
+    #[test]
+    fn test_canonical_json_rejects_unsafe_numbers() {
+        assert!(to_string(&MAX_SAFE_INTEGER).is_ok());
+        assert!(matches!(
+            to_string(&(MAX_SAFE_INTEGER + 1)),
+            Err(CanonicalJsonError::UnsupportedNumber(_))
+        ));
+        assert!(matches!(
+            to_string(&1.5f64),
+            Err(CanonicalJsonError::UnsupportedNumber(_))
+        ));
+    }
+
+    #[test]
+    fn test_canonical_json_session_header_is_stable() {
+        let header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+        let json = header.to_canonical_json().unwrap();
+
+        // Re-encoding the parsed JSON, whose key order may differ, yields the same bytes.
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(to_string(&value).unwrap(), json);
+        assert!(!json.contains(char::is_whitespace));
+    }
+
+    #[test]
+    fn test_signature_covers_canonical_json() {
+        use mpz_core::serialize::CanonicalSerialize;
+        use p256::ecdsa::Signature as P256Signature;
+        use signature::Signer;
+
+        let header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+        let key = fixtures::notary_signing_key();
+        let public_key = p256::PublicKey::from(*key.verifying_key());
+
+        let signature: P256Signature = key.sign(header.to_canonical_json().unwrap().as_bytes());
+        assert!(header
+            .verify_signature(&signature.into(), public_key)
+            .is_ok());
+
+        // A signature over the binary encoding is not valid.
+        let signature: P256Signature = key.sign(&header.to_bytes());
+        assert!(header
+            .verify_signature(&signature.into(), public_key)
+            .is_err());
+    }
+}
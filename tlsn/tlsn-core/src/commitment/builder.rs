@@ -6,10 +6,11 @@ use utils::range::{RangeSet, ToRangeSet};
 
 use crate::{
     commitment::{
-        blake3::Blake3Commitment, Commitment, CommitmentId, CommitmentInfo, CommitmentKind,
-        TranscriptCommitments,
+        blake3::Blake3Commitment, hash::HashCommitment, Commitment, CommitmentId, CommitmentInfo,
+        CommitmentKind, TranscriptCommitments,
     },
-    merkle::MerkleTree,
+    hash::{HashAlgorithm, HashAlgorithmError},
+    merkle::{MerkleError, MerkleTree},
     transcript::get_value_ids,
     Direction, EncodingProvider,
 };
@@ -37,6 +38,12 @@ pub enum TranscriptCommitmentBuilderError {
     /// No commitments were added
     #[error("no commitments were added")]
     NoCommitments,
+    /// The hash algorithm can not be used
+    #[error(transparent)]
+    HashAlgorithm(#[from] HashAlgorithmError),
+    /// The hash algorithm was changed after commitments were added
+    #[error("can not change the hash algorithm after commitments were added")]
+    HashAlgorithmChanged,
 }
 
 /// A builder for [`TranscriptCommitments`].
@@ -45,6 +52,8 @@ pub struct TranscriptCommitmentBuilder {
     /// Information about the above `commitments`.
     commitment_info: BiMap<CommitmentId, CommitmentInfo>,
     merkle_leaves: Vec<Hash>,
+    /// The hash algorithm used for the commitments.
+    hash_algorithm: HashAlgorithm,
     /// A function that returns the encodings for the provided transcript byte ids.
     encoding_provider: EncodingProvider,
     sent_len: usize,
@@ -65,12 +74,39 @@ impl TranscriptCommitmentBuilder {
             commitments: HashMap::default(),
             commitment_info: BiMap::default(),
             merkle_leaves: Vec::default(),
+            hash_algorithm: HashAlgorithm::default(),
             encoding_provider,
             sent_len,
             recv_len,
         }
     }
 
+    /// Sets the hash algorithm used for the commitments.
+    ///
+    /// Defaults to [`HashAlgorithm::Blake3`]. All commitments use the same algorithm, so this must
+    /// be called before any commitments are added.
+    pub fn set_hash_algorithm(
+        &mut self,
+        alg: HashAlgorithm,
+    ) -> Result<&mut Self, TranscriptCommitmentBuilderError> {
+        if !alg.is_supported() {
+            return Err(HashAlgorithmError::Unsupported(alg).into());
+        }
+
+        if alg != self.hash_algorithm && !self.merkle_leaves.is_empty() {
+            return Err(TranscriptCommitmentBuilderError::HashAlgorithmChanged);
+        }
+
+        self.hash_algorithm = alg;
+
+        Ok(self)
+    }
+
+    /// Returns the hash algorithm used for the commitments.
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
     /// Commits to the provided ranges of the `sent` transcript.
     pub fn commit_sent(
         &mut self,
@@ -143,14 +179,14 @@ impl TranscriptCommitmentBuilder {
         let encodings = (self.encoding_provider)(&id_refs)
             .ok_or(TranscriptCommitmentBuilderError::MissingEncodings)?;
 
-        // We only support BLAKE3 for now
-        let commitment = Blake3Commitment::new(&encodings);
-        let hash = *commitment.hash();
+        let commitment: Commitment = match self.hash_algorithm {
+            HashAlgorithm::Blake3 => Blake3Commitment::new(&encodings).into(),
+            alg => HashCommitment::new(alg, &encodings)?.into(),
+        };
+        let hash = commitment.hash();
 
         let id = CommitmentId::new(self.merkle_leaves.len() as u32);
 
-        let commitment: Commitment = commitment.into();
-
         // Store commitment with its id
         self.commitment_info
             .insert_no_overwrite(
@@ -176,14 +212,21 @@ impl TranscriptCommitmentBuilder {
             commitments,
             commitment_info,
             merkle_leaves,
+            hash_algorithm,
             ..
         } = self;
 
-        let merkle_tree = MerkleTree::from_leaves(&merkle_leaves)
-            .map_err(|_| TranscriptCommitmentBuilderError::NoCommitments)?;
+        let merkle_tree =
+            MerkleTree::from_leaves(hash_algorithm, &merkle_leaves).map_err(|e| match e {
+                MerkleError::UnsupportedHashAlgorithm(alg) => {
+                    HashAlgorithmError::Unsupported(alg).into()
+                }
+                _ => TranscriptCommitmentBuilderError::NoCommitments,
+            })?;
 
         Ok(TranscriptCommitments {
             merkle_tree,
+            hash_algorithm,
             commitments,
             commitment_info,
         })
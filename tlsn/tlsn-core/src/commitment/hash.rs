@@ -0,0 +1,136 @@
+use crate::{
+    commitment::{Commitment, CommitmentOpening},
+    hash::{HashAlgorithm, HashAlgorithmError},
+};
+use mpz_core::{
+    commit::{Decommitment, Nonce},
+    hash::Hash,
+};
+use mpz_garble_core::{encoding_state, encoding_state::Full, EncodedValue};
+use serde::{Deserialize, Serialize};
+
+/// A commitment to the encodings of the substrings of a [`Transcript`](crate::Transcript) using a
+/// configurable [`HashAlgorithm`].
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct HashCommitment {
+    alg: HashAlgorithm,
+    hash: Hash,
+    nonce: Nonce,
+}
+
+opaque_debug::implement!(HashCommitment);
+
+impl HashCommitment {
+    /// Creates a new commitment
+    pub fn new(
+        alg: HashAlgorithm,
+        encodings: &[EncodedValue<encoding_state::Active>],
+    ) -> Result<Self, HashAlgorithmError> {
+        let decommitment = Decommitment::new(encodings.to_vec());
+
+        Ok(Self {
+            alg,
+            hash: hash_decommitment(alg, &decommitment)?,
+            nonce: *decommitment.nonce(),
+        })
+    }
+
+    /// Returns the hash algorithm of this commitment
+    pub fn alg(&self) -> HashAlgorithm {
+        self.alg
+    }
+
+    /// Returns the hash of this commitment
+    pub fn hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    /// Returns the nonce of this commitment
+    pub fn nonce(&self) -> &Nonce {
+        &self.nonce
+    }
+
+    /// Opens this commitment
+    pub fn open(&self, data: Vec<u8>) -> HashOpening {
+        HashOpening::new(self.alg, data, self.nonce)
+    }
+}
+
+impl From<HashCommitment> for Commitment {
+    fn from(value: HashCommitment) -> Self {
+        Self::Hash(value)
+    }
+}
+
+/// A substring opening using a configurable [`HashAlgorithm`]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HashOpening {
+    alg: HashAlgorithm,
+    data: Vec<u8>,
+    nonce: Nonce,
+}
+
+impl HashOpening {
+    pub(crate) fn new(alg: HashAlgorithm, data: Vec<u8>, nonce: Nonce) -> Self {
+        Self { alg, data, nonce }
+    }
+
+    /// Returns the hash algorithm of this opening
+    pub fn alg(&self) -> HashAlgorithm {
+        self.alg
+    }
+
+    /// Recovers the expected commitment from this opening.
+    ///
+    /// # Panics
+    ///
+    /// - If the number of encodings does not match the number of bytes in the opening.
+    /// - If an encoding is not for a u8.
+    pub fn recover(
+        &self,
+        encodings: &[EncodedValue<Full>],
+    ) -> Result<HashCommitment, HashAlgorithmError> {
+        assert_eq!(
+            encodings.len(),
+            self.data.len(),
+            "encodings and data must have the same length"
+        );
+
+        let encodings = encodings
+            .iter()
+            .zip(&self.data)
+            .map(|(encoding, data)| encoding.select(*data).expect("encoding is for a u8"))
+            .collect::<Vec<_>>();
+
+        let decommitment = Decommitment::new_with_nonce(encodings, self.nonce);
+
+        Ok(HashCommitment {
+            alg: self.alg,
+            hash: hash_decommitment(self.alg, &decommitment)?,
+            nonce: self.nonce,
+        })
+    }
+
+    /// Returns the transcript data corresponding to this opening
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the transcript data corresponding to this opening
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl From<HashOpening> for CommitmentOpening {
+    fn from(value: HashOpening) -> Self {
+        Self::Hash(value)
+    }
+}
+
+fn hash_decommitment(
+    alg: HashAlgorithm,
+    decommitment: &Decommitment<Vec<EncodedValue<encoding_state::Active>>>,
+) -> Result<Hash, HashAlgorithmError> {
+    alg.hash(&bincode::serialize(decommitment).expect("decommitment is serializable"))
+}
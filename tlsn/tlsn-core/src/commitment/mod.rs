@@ -3,6 +3,8 @@
 /// BLAKE3 commitments.
 pub mod blake3;
 mod builder;
+/// Commitments using a configurable hash algorithm.
+pub mod hash;
 
 use std::collections::HashMap;
 
@@ -13,6 +15,7 @@ use serde::{Deserialize, Serialize};
 use utils::range::RangeSet;
 
 use crate::{
+    hash::{HashAlgorithm, HashAlgorithmError},
     merkle::{MerkleRoot, MerkleTree},
     Direction,
 };
@@ -75,6 +78,8 @@ impl CommitmentInfo {
 pub enum Commitment {
     /// A BLAKE3 commitment to encodings of the transcript.
     Blake3(blake3::Blake3Commitment),
+    /// A commitment to encodings of the transcript using a configurable hash algorithm.
+    Hash(hash::HashCommitment),
 }
 
 impl Commitment {
@@ -82,6 +87,7 @@ impl Commitment {
     pub fn hash(&self) -> Hash {
         match self {
             Commitment::Blake3(commitment) => *commitment.hash(),
+            Commitment::Hash(commitment) => *commitment.hash(),
         }
     }
 
@@ -89,6 +95,7 @@ impl Commitment {
     pub fn kind(&self) -> CommitmentKind {
         match self {
             Commitment::Blake3(_) => CommitmentKind::Blake3,
+            Commitment::Hash(commitment) => CommitmentKind::Hash(commitment.alg()),
         }
     }
 }
@@ -99,6 +106,28 @@ impl Commitment {
 pub enum CommitmentKind {
     /// A BLAKE3 commitment to encodings of the transcript.
     Blake3,
+    /// A commitment to encodings of the transcript using the given hash algorithm.
+    ///
+    /// Commitments using [`HashAlgorithm::Blake3`] are always of kind [`CommitmentKind::Blake3`].
+    Hash(HashAlgorithm),
+}
+
+impl CommitmentKind {
+    /// Returns the hash algorithm used by commitments of this kind.
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        match self {
+            CommitmentKind::Blake3 => HashAlgorithm::Blake3,
+            CommitmentKind::Hash(alg) => *alg,
+        }
+    }
+
+    /// Returns the kind of commitments using the given hash algorithm.
+    pub fn from_hash_algorithm(alg: HashAlgorithm) -> Self {
+        match alg {
+            HashAlgorithm::Blake3 => CommitmentKind::Blake3,
+            alg => CommitmentKind::Hash(alg),
+        }
+    }
 }
 
 /// An opening to a commitment to the transcript.
@@ -107,6 +136,8 @@ pub enum CommitmentKind {
 pub enum CommitmentOpening {
     /// An opening to a BLAKE3 commitment
     Blake3(blake3::Blake3Opening),
+    /// An opening to a commitment using a configurable hash algorithm
+    Hash(hash::HashOpening),
 }
 
 impl CommitmentOpening {
@@ -114,6 +145,7 @@ impl CommitmentOpening {
     pub fn kind(&self) -> CommitmentKind {
         match self {
             CommitmentOpening::Blake3(_) => CommitmentKind::Blake3,
+            CommitmentOpening::Hash(opening) => CommitmentKind::Hash(opening.alg()),
         }
     }
 
@@ -125,9 +157,13 @@ impl CommitmentOpening {
     ///
     /// - If the number of encodings does not match the number of bytes in the opening.
     /// - If an encoding is not for a u8.
-    pub fn recover(&self, encodings: &[EncodedValue<Full>]) -> Commitment {
+    pub fn recover(
+        &self,
+        encodings: &[EncodedValue<Full>],
+    ) -> Result<Commitment, HashAlgorithmError> {
         match self {
-            CommitmentOpening::Blake3(opening) => opening.recover(encodings).into(),
+            CommitmentOpening::Blake3(opening) => Ok(opening.recover(encodings).into()),
+            CommitmentOpening::Hash(opening) => opening.recover(encodings).map(Into::into),
         }
     }
 
@@ -135,6 +171,7 @@ impl CommitmentOpening {
     pub fn data(&self) -> &[u8] {
         match self {
             CommitmentOpening::Blake3(opening) => opening.data(),
+            CommitmentOpening::Hash(opening) => opening.data(),
         }
     }
 
@@ -142,6 +179,7 @@ impl CommitmentOpening {
     pub fn into_data(self) -> Vec<u8> {
         match self {
             CommitmentOpening::Blake3(opening) => opening.into_data(),
+            CommitmentOpening::Hash(opening) => opening.into_data(),
         }
     }
 }
@@ -151,6 +189,8 @@ impl CommitmentOpening {
 pub struct TranscriptCommitments {
     /// A Merkle tree of commitments. Each commitment's index in the tree matches its `CommitmentId`.
     merkle_tree: MerkleTree,
+    /// The hash algorithm used by all `commitments`.
+    hash_algorithm: HashAlgorithm,
     commitments: HashMap<CommitmentId, Commitment>,
     /// Information about the above `commitments`.
     commitment_info: BiMap<CommitmentId, CommitmentInfo>,
@@ -169,6 +209,11 @@ impl TranscriptCommitments {
         self.merkle_tree.root()
     }
 
+    /// Returns the hash algorithm used by the commitments.
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
     /// Returns a commitment if it exists.
     pub fn get(&self, id: &CommitmentId) -> Option<&Commitment> {
         self.commitments.get(id)
@@ -190,4 +235,19 @@ impl TranscriptCommitments {
     pub fn get_info(&self, id: &CommitmentId) -> Option<&CommitmentInfo> {
         self.commitment_info.get_by_left(id)
     }
+
+    /// Returns a KZG vector commitment to the commitments, as an alternative to the merkle tree.
+    ///
+    /// Each commitment's index in the vector matches its `CommitmentId`.
+    #[cfg(feature = "kzg")]
+    pub fn kzg_vector(
+        &self,
+        params: &crate::kzg::KzgParams,
+    ) -> Result<crate::kzg::KzgVector, crate::kzg::KzgError> {
+        let leaves = (0..self.commitments.len() as u32)
+            .map(|id| self.commitments[&CommitmentId::new(id)].hash())
+            .collect::<Vec<_>>();
+
+        crate::kzg::KzgVector::from_leaves(params, &leaves)
+    }
 }
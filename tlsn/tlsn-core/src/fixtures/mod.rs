@@ -21,12 +21,15 @@ use tls_core::{
     },
 };
 
-use p256::ecdsa::SigningKey;
+use p256::ecdsa::{Signature as P256Signature, SigningKey};
+use signature::Signer;
 
 use crate::{
+    commitment::{CommitmentKind, TranscriptCommitmentBuilder},
     merkle::MerkleRoot,
+    proof::{SessionInfo, SessionProof, SubstringsProofBuilder, TlsProof},
     session::{HandshakeSummary, SessionHeader},
-    EncodingProvider,
+    Direction, EncodingProvider, ServerName, Transcript,
 };
 
 fn value_id(id: &str) -> u64 {
@@ -167,3 +170,87 @@ pub fn server_ephemeral_key() -> PublicKey {
 pub fn notary_signing_key() -> SigningKey {
     SigningKey::from_slice(&[1; 32]).unwrap()
 }
+
+/// Returns a presentation fixture of a session with tlsnotary.org which discloses the given
+/// transcripts in full, see [`server_tls_proof`].
+pub fn tls_proof(
+    transcript_tx: &[u8],
+    transcript_rx: &[u8],
+    header: impl FnOnce(SessionHeader) -> SessionHeader,
+) -> TlsProof {
+    server_tls_proof(&cert::tlsnotary(), transcript_tx, transcript_rx, header)
+}
+
+/// Returns a presentation fixture of a session with the server of the given test data which
+/// discloses the given transcripts in full.
+///
+/// The session header is passed through `header` before it is signed with
+/// [`notary_signing_key`].
+///
+/// # Arguments
+///
+/// * `server` - The test data of the server.
+/// * `transcript_tx` - The sent transcript, must not be empty.
+/// * `transcript_rx` - The received transcript, must not be empty.
+/// * `header` - Modifies the session header before it is signed.
+pub fn server_tls_proof(
+    server: &cert::TestData,
+    transcript_tx: &[u8],
+    transcript_rx: &[u8],
+    header: impl FnOnce(SessionHeader) -> SessionHeader,
+) -> TlsProof {
+    let mut builder = TranscriptCommitmentBuilder::new(
+        encoding_provider(transcript_tx, transcript_rx),
+        transcript_tx.len(),
+        transcript_rx.len(),
+    );
+    builder.commit_sent(&(0..transcript_tx.len())).unwrap();
+    builder.commit_recv(&(0..transcript_rx.len())).unwrap();
+    let commitments = builder.build().unwrap();
+
+    let (handshake_decommitment, handshake_commitment) = HandshakeData::new(
+        ServerCertDetails::new(
+            vec![server.ee.clone(), server.inter.clone(), server.ca.clone()],
+            vec![],
+            None,
+        ),
+        ServerKxDetails::new(server.kx_params(), server.dss()),
+        server.cr,
+        server.sr,
+    )
+    .hash_commit();
+    let header = header(SessionHeader::new(
+        encoder_seed(),
+        commitments.merkle_root(),
+        transcript_tx.len(),
+        transcript_rx.len(),
+        HandshakeSummary::new(server.time, server.pubkey.clone(), handshake_commitment),
+    ));
+    let signature: P256Signature = notary_signing_key().sign(&header.signing_message().unwrap());
+
+    let sent = 0..transcript_tx.len();
+    let recv = 0..transcript_rx.len();
+    let transcript_tx = Transcript::new(transcript_tx.to_vec());
+    let transcript_rx = Transcript::new(transcript_rx.to_vec());
+    let mut substrings = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+    substrings
+        .reveal(&sent, Direction::Sent, CommitmentKind::Blake3)
+        .unwrap()
+        .reveal(&recv, Direction::Received, CommitmentKind::Blake3)
+        .unwrap();
+
+    TlsProof {
+        session: SessionProof {
+            header,
+            signature: Some(signature.into()),
+            session_info: SessionInfo {
+                server_name: ServerName::Dns(server.dns_name.clone()),
+                handshake_decommitment,
+            },
+            timestamp: None,
+        },
+        substrings: substrings.build().unwrap(),
+        cosignatures: vec![],
+        tee_quote: None,
+    }
+}
@@ -0,0 +1,196 @@
+//! Hash algorithms for transcript commitments.
+//!
+//! Different verifiers favor different hash functions: BLAKE3 is fastest natively, SHA-256 and
+//! Keccak-256 are cheap to verify on EVM chains, and Poseidon is cheap to verify in zk circuits.
+//! The Prover chooses the algorithm used for its transcript commitments, and the Notary records it
+//! in the [`SessionHeader`](crate::SessionHeader) so that a verifier only accepts openings made
+//! with that algorithm.
+
+use mpz_core::hash::Hash;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+/// An error for [`HashAlgorithm`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HashAlgorithmError {
+    /// The algorithm identifier is unknown.
+    #[error("unknown hash algorithm id: {0:#04x}")]
+    UnknownId(u8),
+    /// The algorithm is not supported by this build.
+    #[error("hash algorithm {0:?} is not supported by this build")]
+    Unsupported(HashAlgorithm),
+}
+
+/// A hash algorithm used for transcript commitments.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum HashAlgorithm {
+    /// BLAKE3.
+    #[default]
+    Blake3,
+    /// SHA-256.
+    Sha256,
+    /// Keccak-256, as used by Ethereum.
+    Keccak256,
+    /// Poseidon over the BN254 scalar field, as implemented by the `poseidon` module.
+    ///
+    /// Requires the `poseidon` feature.
+    Poseidon,
+}
+
+impl HashAlgorithm {
+    /// Returns the identifier of this algorithm.
+    pub fn id(&self) -> u8 {
+        match self {
+            HashAlgorithm::Blake3 => 0x00,
+            HashAlgorithm::Sha256 => 0x01,
+            HashAlgorithm::Keccak256 => 0x02,
+            HashAlgorithm::Poseidon => 0x03,
+        }
+    }
+
+    /// Returns whether this algorithm is supported by this build.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            HashAlgorithm::Poseidon => cfg!(feature = "poseidon"),
+            _ => true,
+        }
+    }
+
+    /// Hashes the provided data.
+    pub fn hash(&self, data: &[u8]) -> Result<Hash, HashAlgorithmError> {
+        let hash: [u8; 32] = match self {
+            HashAlgorithm::Blake3 => mpz_core::utils::blake3(data),
+            HashAlgorithm::Sha256 => sha2::Sha256::digest(data).into(),
+            HashAlgorithm::Keccak256 => sha3::Keccak256::digest(data).into(),
+            #[cfg(feature = "poseidon")]
+            HashAlgorithm::Poseidon => crate::poseidon::hash_bytes(data),
+            #[cfg(not(feature = "poseidon"))]
+            HashAlgorithm::Poseidon => return Err(HashAlgorithmError::Unsupported(*self)),
+        };
+
+        Ok(Hash::from(hash))
+    }
+}
+
+impl TryFrom<u8> for HashAlgorithm {
+    type Error = HashAlgorithmError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        match id {
+            0x00 => Ok(HashAlgorithm::Blake3),
+            0x01 => Ok(HashAlgorithm::Sha256),
+            0x02 => Ok(HashAlgorithm::Keccak256),
+            0x03 => Ok(HashAlgorithm::Poseidon),
+            id => Err(HashAlgorithmError::UnknownId(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+        fixtures,
+        proof::{SubstringsProofBuilder, SubstringsProofError},
+        Transcript,
+    };
+
+    static TX: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\n\r\n";
+
+    #[test]
+    fn test_hash_algorithm_id_roundtrip() {
+        for alg in [
+            HashAlgorithm::Blake3,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Keccak256,
+            HashAlgorithm::Poseidon,
+        ] {
+            assert_eq!(HashAlgorithm::try_from(alg.id()).unwrap(), alg);
+        }
+
+        assert!(HashAlgorithm::try_from(0xff).is_err());
+    }
+
+    #[test]
+    fn test_hash_algorithm_known_digests() {
+        assert_eq!(
+            hex::encode(HashAlgorithm::Sha256.hash(b"abc").unwrap().as_bytes()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex::encode(HashAlgorithm::Keccak256.hash(b"abc").unwrap().as_bytes()),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn test_hash_algorithm_enforced() {
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        builder.set_hash_algorithm(HashAlgorithm::Sha256).unwrap();
+        builder.commit_sent(&(0..5)).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let prove = || {
+            let mut builder =
+                SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+            builder
+                .reveal_sent(&(0..5), CommitmentKind::Hash(HashAlgorithm::Sha256))
+                .unwrap();
+            builder.build().unwrap()
+        };
+
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len());
+
+        assert!(prove()
+            .verify(&header.clone().with_hash_algorithm(HashAlgorithm::Sha256))
+            .is_ok());
+        assert!(matches!(
+            prove().verify(&header),
+            Err(SubstringsProofError::HashAlgorithmMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_merkle_tree_uses_hash_algorithm() {
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        builder
+            .set_hash_algorithm(HashAlgorithm::Keccak256)
+            .unwrap();
+        builder.commit_sent(&(0..5)).unwrap();
+        builder.commit_recv(&(0..5)).unwrap();
+        let commitments = builder.build().unwrap();
+        assert_eq!(
+            commitments.merkle_tree().algorithm(),
+            HashAlgorithm::Keccak256
+        );
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        builder
+            .reveal_sent(&(0..5), CommitmentKind::Hash(HashAlgorithm::Keccak256))
+            .unwrap();
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len())
+            .with_hash_algorithm(HashAlgorithm::Keccak256);
+        let (sent, _) = proof.verify(&header).unwrap();
+        assert_eq!(sent.data()[..5], TX[..5]);
+    }
+}
@@ -0,0 +1,549 @@
+//! KZG vector commitment types.
+//!
+//! # Usage
+//!
+//! A `KzgVector` is an alternative to a [`MerkleTree`](crate::merkle::MerkleTree) of transcript
+//! commitments. Opening any subset of the leaves of a `KzgVector` requires a single group element,
+//! whereas the size of a `MerkleProof` grows with the number of opened leaves. This matters for
+//! presentations which reveal many scattered ranges of the transcript.
+//!
+//! A Prover configured with `KzgParams` sends the [`KzgCommitment`] to its transcript commitments
+//! to the Notary along with the Merkle root, and the Notary signs both in the
+//! [`SessionHeader`](crate::SessionHeader). The Prover can then open its commitments with either,
+//! see [`SubstringsProofBuilder::build_kzg`](crate::proof::SubstringsProofBuilder::build_kzg) and
+//! [`SubstringsProof::verify_kzg`](crate::proof::SubstringsProof::verify_kzg).
+//!
+//! The commitment and proof types are always available, so that session headers and proofs have
+//! the same encoding regardless of features. Committing and verifying requires the `kzg` feature.
+//!
+//! Each leaf is mapped to a field element of BN254, and the leaves are interpolated into a
+//! polynomial `p` over a power-of-two evaluation domain, such that leaf `i` is `p(ω^i)`. The
+//! commitment is `[p(τ)]₁`. A `KzgProof` for a set of indices `S` is `[q(τ)]₁` where
+//! `q = (p - I) / Z`, `I` interpolates the opened leaves and `Z` vanishes on `{ω^i : i ∈ S}`.
+//!
+//! # Trusted setup
+//!
+//! Committing and verifying requires `KzgParams` containing powers of a secret `τ`. Anybody who
+//! knows `τ` can open a commitment to arbitrary values, so in production the parameters must come
+//! from a trusted setup ceremony, see [`KzgParams::new`]. [`KzgParams::setup`] samples `τ` locally
+//! and is only suitable for testing.
+
+#[cfg(feature = "kzg")]
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+#[cfg(feature = "kzg")]
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, Group, VariableBaseMSM};
+#[cfg(feature = "kzg")]
+use ark_ff::{Field, PrimeField};
+#[cfg(feature = "kzg")]
+use ark_poly::{
+    univariate::{DenseOrSparsePolynomial, DensePolynomial},
+    DenseUVPolynomial, EvaluationDomain, Evaluations, Radix2EvaluationDomain,
+};
+#[cfg(feature = "kzg")]
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(feature = "kzg")]
+use ark_std::{rand::Rng, One, UniformRand, Zero};
+#[cfg(feature = "kzg")]
+use mpz_core::hash::Hash;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "kzg")]
+use utils::iter::DuplicateCheck;
+
+/// Errors that can occur during operations with KZG vector commitments and proofs
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[non_exhaustive]
+pub enum KzgError {
+    /// The proof does not open the commitment to the given leaves.
+    #[error("Failed to verify a KZG proof")]
+    KzgProofVerificationFailed,
+    /// A vector commitment was created without any leaves.
+    #[error("No leaves were provided when constructing a KZG vector commitment")]
+    KzgNoLeavesProvided,
+    /// A leaf index is not within the committed vector.
+    #[error("Leaf index {0} is out of bounds")]
+    KzgIndexOutOfBounds(usize),
+    /// The parameters don't support as many leaves, or as many openings, as requested.
+    #[error("KZG parameters are too small: {0}")]
+    KzgParamsTooSmall(String),
+    /// A commitment or proof is not a valid encoding of a point of the BN254 G1 group.
+    #[error("Invalid encoding of a group element")]
+    KzgInvalidPoint,
+}
+
+/// Public parameters for KZG vector commitments.
+#[cfg(feature = "kzg")]
+#[derive(Clone)]
+pub struct KzgParams {
+    /// `[τ^i]₁` for `i` in `0..powers_g1.len()`.
+    powers_g1: Vec<G1Affine>,
+    /// `[τ^i]₂` for `i` in `0..powers_g2.len()`.
+    powers_g2: Vec<G2Affine>,
+}
+
+#[cfg(feature = "kzg")]
+opaque_debug::implement!(KzgParams);
+
+#[cfg(feature = "kzg")]
+impl KzgParams {
+    /// Creates parameters from powers of a secret `τ`, e.g. the output of a trusted setup ceremony.
+    ///
+    /// # Arguments
+    ///
+    /// * `powers_g1` - `[τ^i]₁` for `i` in `0..n`, where `n` bounds the number of leaves.
+    /// * `powers_g2` - `[τ^i]₂` for `i` in `0..=k`, where `k` bounds the number of leaves opened
+    ///   by a single proof.
+    ///
+    /// # Panics
+    ///
+    /// - If `powers_g1` is empty.
+    /// - If `powers_g2` contains less than 2 elements.
+    pub fn new(powers_g1: Vec<G1Affine>, powers_g2: Vec<G2Affine>) -> Self {
+        assert!(!powers_g1.is_empty(), "powers_g1 must not be empty");
+        assert!(
+            powers_g2.len() >= 2,
+            "powers_g2 must contain at least 2 elements"
+        );
+
+        Self {
+            powers_g1,
+            powers_g2,
+        }
+    }
+
+    /// Generates parameters from local randomness.
+    ///
+    /// # Warning
+    ///
+    /// Whoever knows the randomness can open commitments to arbitrary values. This is only
+    /// suitable for testing.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_leaves` - The maximum number of leaves in a vector.
+    /// * `max_openings` - The maximum number of leaves opened by a single proof.
+    /// * `rng` - The source of randomness.
+    pub fn setup<R: Rng + ?Sized>(max_leaves: usize, max_openings: usize, rng: &mut R) -> Self {
+        let tau = Fr::rand(rng);
+
+        let g1_len = max_leaves.max(1).next_power_of_two();
+        let g2_len = max_openings.max(1) + 1;
+
+        let mut powers = Vec::with_capacity(g1_len.max(g2_len));
+        let mut power = Fr::one();
+        for _ in 0..g1_len.max(g2_len) {
+            powers.push(power);
+            power *= tau;
+        }
+
+        let g1 = G1Projective::generator();
+        let g2 = G2Projective::generator();
+
+        Self::new(
+            G1Projective::normalize_batch(
+                &powers[..g1_len].iter().map(|p| g1 * p).collect::<Vec<_>>(),
+            ),
+            G2Projective::normalize_batch(
+                &powers[..g2_len].iter().map(|p| g2 * p).collect::<Vec<_>>(),
+            ),
+        )
+    }
+
+    /// Returns the maximum number of leaves in a vector.
+    pub fn max_leaves(&self) -> usize {
+        self.powers_g1.len()
+    }
+
+    /// Returns the maximum number of leaves opened by a single proof.
+    pub fn max_openings(&self) -> usize {
+        self.powers_g2.len() - 1
+    }
+
+    fn commit_g1(&self, poly: &DensePolynomial<Fr>) -> Result<G1Projective, KzgError> {
+        if poly.coeffs.len() > self.powers_g1.len() {
+            return Err(KzgError::KzgParamsTooSmall(format!(
+                "polynomial of degree {} exceeds the maximum degree {}",
+                poly.coeffs.len() - 1,
+                self.powers_g1.len() - 1
+            )));
+        }
+
+        Ok(G1Projective::msm_unchecked(
+            &self.powers_g1[..poly.coeffs.len()],
+            &poly.coeffs,
+        ))
+    }
+
+    fn commit_g2(&self, poly: &DensePolynomial<Fr>) -> Result<G2Projective, KzgError> {
+        if poly.coeffs.len() > self.powers_g2.len() {
+            return Err(KzgError::KzgParamsTooSmall(format!(
+                "cannot open more than {} leaves",
+                self.max_openings()
+            )));
+        }
+
+        Ok(G2Projective::msm_unchecked(
+            &self.powers_g2[..poly.coeffs.len()],
+            &poly.coeffs,
+        ))
+    }
+}
+
+/// A KZG vector commitment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KzgCommitment {
+    /// The compressed encoding of `[p(τ)]₁`.
+    point: [u8; 32],
+    /// The size of the evaluation domain, which binds the positions of the leaves.
+    domain_size: u32,
+}
+
+impl KzgCommitment {
+    /// Returns the compressed encoding of the commitment.
+    pub fn to_inner(self) -> [u8; 32] {
+        self.point
+    }
+
+    /// Returns the size of the evaluation domain.
+    pub fn domain_size(&self) -> usize {
+        self.domain_size as usize
+    }
+}
+
+/// A KZG proof that a subset of leaves is contained in a [`KzgCommitment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KzgProof {
+    /// The compressed encoding of `[q(τ)]₁`.
+    point: [u8; 32],
+}
+
+#[cfg(feature = "kzg")]
+impl KzgProof {
+    /// Checks if indices and hashes are valid for the provided commitment
+    ///
+    /// # Panics
+    ///
+    /// - If the length of `leaf_indices` and `leaf_hashes` does not match.
+    /// - If `leaf_indices` contains duplicates.
+    pub fn verify(
+        &self,
+        params: &KzgParams,
+        commitment: &KzgCommitment,
+        leaf_indices: &[usize],
+        leaf_hashes: &[Hash],
+    ) -> Result<(), KzgError> {
+        assert_eq!(
+            leaf_indices.len(),
+            leaf_hashes.len(),
+            "leaf indices length must match leaf hashes length"
+        );
+        assert!(
+            !leaf_indices.iter().contains_dups(),
+            "duplicate indices provided {:?}",
+            leaf_indices
+        );
+
+        let domain = Radix2EvaluationDomain::<Fr>::new(commitment.domain_size())
+            .filter(|domain| domain.size() == commitment.domain_size())
+            .ok_or(KzgError::KzgProofVerificationFailed)?;
+
+        if let Some(&index) = leaf_indices.iter().find(|&&index| index >= domain.size()) {
+            return Err(KzgError::KzgIndexOutOfBounds(index));
+        }
+
+        let points = leaf_indices
+            .iter()
+            .zip(leaf_hashes)
+            .map(|(&index, hash)| (domain.element(index), leaf_to_field(hash)))
+            .collect::<Vec<_>>();
+
+        let interpolation = params.commit_g1(&interpolate(&points))?;
+        let vanishing = params.commit_g2(&vanishing(points.iter().map(|(x, _)| *x)))?;
+
+        let commitment = decode_point(&commitment.point)?;
+        let proof = decode_point(&self.point)?;
+
+        let lhs = Bn254::pairing(commitment.into_group() - interpolation, params.powers_g2[0]);
+        let rhs = Bn254::pairing(proof, vanishing);
+
+        if lhs != rhs {
+            return Err(KzgError::KzgProofVerificationFailed);
+        }
+
+        Ok(())
+    }
+}
+
+/// A KZG vector commitment to a list of leaves, along with the data needed to create proofs.
+#[cfg(feature = "kzg")]
+#[derive(Clone)]
+pub struct KzgVector {
+    len: usize,
+    domain: Radix2EvaluationDomain<Fr>,
+    evaluations: Vec<Fr>,
+    poly: DensePolynomial<Fr>,
+    commitment: KzgCommitment,
+}
+
+#[cfg(feature = "kzg")]
+opaque_debug::implement!(KzgVector);
+
+#[cfg(feature = "kzg")]
+impl KzgVector {
+    /// Commits to the given `leaves`.
+    pub fn from_leaves(params: &KzgParams, leaves: &[Hash]) -> Result<Self, KzgError> {
+        if leaves.is_empty() {
+            return Err(KzgError::KzgNoLeavesProvided);
+        }
+
+        let domain = Radix2EvaluationDomain::<Fr>::new(leaves.len())
+            .filter(|domain| domain.size() <= params.max_leaves())
+            .ok_or_else(|| {
+                KzgError::KzgParamsTooSmall(format!(
+                    "{} leaves exceed the maximum of {}",
+                    leaves.len(),
+                    params.max_leaves()
+                ))
+            })?;
+
+        let mut evaluations = leaves.iter().map(leaf_to_field).collect::<Vec<_>>();
+        evaluations.resize(domain.size(), Fr::zero());
+
+        let poly = Evaluations::from_vec_and_domain(evaluations.clone(), domain).interpolate();
+        let commitment = KzgCommitment {
+            point: encode_point(params.commit_g1(&poly)?),
+            domain_size: u32::try_from(domain.size()).expect("domain size fits in u32"),
+        };
+
+        Ok(Self {
+            len: leaves.len(),
+            domain,
+            evaluations,
+            poly,
+            commitment,
+        })
+    }
+
+    /// Returns the commitment to the leaves.
+    pub fn commitment(&self) -> KzgCommitment {
+        self.commitment
+    }
+
+    /// Creates a proof for the leaves at the given `indices`
+    ///
+    /// # Panics
+    ///
+    /// - if `indices` is not sorted.
+    /// - if `indices` contains duplicates
+    pub fn proof(&self, params: &KzgParams, indices: &[usize]) -> Result<KzgProof, KzgError> {
+        assert!(
+            indices.windows(2).all(|w| w[0] < w[1]),
+            "indices must be sorted"
+        );
+
+        if let Some(&index) = indices.iter().find(|&&index| index >= self.len) {
+            return Err(KzgError::KzgIndexOutOfBounds(index));
+        }
+
+        if indices.len() > params.max_openings() {
+            return Err(KzgError::KzgParamsTooSmall(format!(
+                "cannot open more than {} leaves",
+                params.max_openings()
+            )));
+        }
+
+        let points = indices
+            .iter()
+            .map(|&index| (self.domain.element(index), self.evaluations[index]))
+            .collect::<Vec<_>>();
+
+        let numerator = &self.poly - &interpolate(&points);
+        let denominator = vanishing(points.iter().map(|(x, _)| *x));
+        let (quotient, remainder) = DenseOrSparsePolynomial::from(numerator)
+            .divide_with_q_and_r(&DenseOrSparsePolynomial::from(denominator))
+            .expect("vanishing polynomial is not zero");
+        debug_assert!(remainder.is_zero());
+
+        Ok(KzgProof {
+            point: encode_point(params.commit_g1(&quotient)?),
+        })
+    }
+}
+
+#[cfg(feature = "kzg")]
+fn leaf_to_field(hash: &Hash) -> Fr {
+    Fr::from_be_bytes_mod_order(hash.as_bytes())
+}
+
+#[cfg(feature = "kzg")]
+fn encode_point(point: G1Projective) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    point
+        .into_affine()
+        .serialize_compressed(&mut bytes[..])
+        .expect("compressed G1 point is 32 bytes");
+    bytes
+}
+
+#[cfg(feature = "kzg")]
+fn decode_point(bytes: &[u8; 32]) -> Result<G1Affine, KzgError> {
+    G1Affine::deserialize_compressed(&bytes[..]).map_err(|_| KzgError::KzgInvalidPoint)
+}
+
+/// Multiplies `poly` by `(X - root)`.
+#[cfg(feature = "kzg")]
+fn mul_linear(poly: &DensePolynomial<Fr>, root: Fr) -> DensePolynomial<Fr> {
+    let mut coeffs = vec![Fr::zero(); poly.coeffs.len() + 1];
+    for (i, coeff) in poly.coeffs.iter().enumerate() {
+        coeffs[i + 1] += coeff;
+        coeffs[i] -= *coeff * root;
+    }
+
+    DensePolynomial::from_coefficients_vec(coeffs)
+}
+
+/// Returns the polynomial which vanishes exactly on `roots`.
+#[cfg(feature = "kzg")]
+fn vanishing(roots: impl IntoIterator<Item = Fr>) -> DensePolynomial<Fr> {
+    roots.into_iter().fold(
+        DensePolynomial::from_coefficients_vec(vec![Fr::one()]),
+        |poly, root| mul_linear(&poly, root),
+    )
+}
+
+/// Returns the polynomial of least degree passing through `points`.
+#[cfg(feature = "kzg")]
+fn interpolate(points: &[(Fr, Fr)]) -> DensePolynomial<Fr> {
+    let mut coeffs = vec![Fr::zero(); points.len()];
+    for (j, &(x_j, y_j)) in points.iter().enumerate() {
+        let mut basis = DensePolynomial::from_coefficients_vec(vec![Fr::one()]);
+        let mut denominator = Fr::one();
+        for (m, &(x_m, _)) in points.iter().enumerate() {
+            if m != j {
+                basis = mul_linear(&basis, x_m);
+                denominator *= x_j - x_m;
+            }
+        }
+
+        let scale = y_j * denominator.inverse().expect("points are distinct");
+        for (coeff, basis_coeff) in coeffs.iter_mut().zip(&basis.coeffs) {
+            *coeff += *basis_coeff * scale;
+        }
+    }
+
+    DensePolynomial::from_coefficients_vec(coeffs)
+}
+
+#[cfg(all(test, feature = "kzg"))]
+mod test {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn setup() -> (KzgParams, Vec<Hash>, KzgVector) {
+        let params = KzgParams::setup(8, 4, &mut ChaCha20Rng::seed_from_u64(0));
+        let leaves = (0..5u8).map(|i| Hash::from([i; 32])).collect::<Vec<_>>();
+        let vector = KzgVector::from_leaves(&params, &leaves).unwrap();
+
+        (params, leaves, vector)
+    }
+
+    // Expect KZG proof verification to succeed
+    #[test]
+    fn test_verify_success() {
+        let (params, leaves, vector) = setup();
+        let proof = vector.proof(&params, &[0, 2, 4]).unwrap();
+
+        assert!(proof
+            .verify(
+                &params,
+                &vector.commitment(),
+                &[4, 0, 2],
+                &[leaves[4], leaves[0], leaves[2]]
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_verify_fail_wrong_leaf() {
+        let (params, leaves, vector) = setup();
+        let proof = vector.proof(&params, &[0, 2, 4]).unwrap();
+
+        assert_eq!(
+            proof.verify(
+                &params,
+                &vector.commitment(),
+                &[0, 2, 4],
+                &[leaves[0], leaves[3], leaves[4]]
+            ),
+            Err(KzgError::KzgProofVerificationFailed)
+        );
+    }
+
+    #[test]
+    fn test_substrings_proof_kzg() {
+        use crate::{
+            commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+            fixtures,
+            proof::{SubstringsProofBuilder, SubstringsProofError},
+            Transcript,
+        };
+        use utils::range::RangeSet;
+
+        const TX: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+        const RX: &[u8] = b"HTTP/1.1 200 OK\r\n\r\nsecret";
+
+        let (params, _, _) = setup();
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        builder.commit_sent(&(0..TX.len())).unwrap();
+        builder.commit_recv(&(0..8)).unwrap();
+        builder.commit_recv(&(8..RX.len())).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len());
+        let kzg_header = header
+            .clone()
+            .with_kzg_commitment(commitments.kzg_vector(&params).unwrap().commitment());
+
+        let build = || {
+            let mut builder =
+                SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+            builder
+                .reveal_sent(&(0..TX.len()), CommitmentKind::Blake3)
+                .unwrap()
+                .reveal_recv(&(0..8), CommitmentKind::Blake3)
+                .unwrap();
+            builder.build_kzg(&params).unwrap()
+        };
+
+        let (sent, recv) = build().verify_kzg(&kzg_header, &params).unwrap();
+        assert_eq!(sent.data(), TX);
+        assert_eq!(&recv.data()[..8], &RX[..8]);
+        assert_eq!(recv.redacted(), &RangeSet::from(8..RX.len()));
+
+        assert!(matches!(
+            build().verify(&kzg_header),
+            Err(SubstringsProofError::KzgParamsRequired)
+        ));
+        assert!(matches!(
+            build().verify_kzg(&header, &params),
+            Err(SubstringsProofError::MissingKzgCommitment)
+        ));
+    }
+
+    #[test]
+    fn test_proof_too_many_openings() {
+        let (params, _, vector) = setup();
+
+        assert!(matches!(
+            vector.proof(&params, &[0, 1, 2, 3, 4]),
+            Err(KzgError::KzgParamsTooSmall(_))
+        ));
+    }
+}
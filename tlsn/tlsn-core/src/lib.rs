@@ -6,19 +6,33 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+pub mod canonical;
 pub mod commitment;
 #[cfg(any(test, feature = "fixtures"))]
 pub mod fixtures;
+pub mod hash;
+pub mod kzg;
 pub mod merkle;
 pub mod msg;
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
 pub mod proof;
+pub mod quorum;
+pub mod revocation;
 pub mod session;
 mod signature;
+pub mod tee;
+pub mod time;
+pub mod timestamp;
 pub mod transcript;
+pub mod trust;
 
 pub use session::{HandshakeSummary, NotarizedSession, SessionData, SessionHeader};
-pub use signature::{NotaryPublicKey, Signature};
-pub use transcript::{Direction, RedactedTranscript, Transcript, TranscriptSlice};
+pub use signature::{
+    BlsError, BlsPublicKey, BlsSignature, BlsSigningKey, NotaryPublicKey, Signature,
+    SignatureAggregateError, SignatureAlgorithm,
+};
+pub use transcript::{Direction, LengthPadding, RedactedTranscript, Transcript, TranscriptSlice};
 
 use mpz_garble_core::{encoding_state, EncodedValue};
 use serde::{Deserialize, Serialize};
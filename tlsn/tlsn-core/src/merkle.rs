@@ -9,15 +9,26 @@
 //!
 //! Later, during selective disclosure to a `Verifier`, the `Prover` can open any subset of the commitments in the `MerkleTree`
 //! by providing a `MerkleProof` for the corresponding `MerkleRoot` which was signed by the Notary.
+//!
+//! # Hash algorithm
+//!
+//! The tree is hashed with the [`HashAlgorithm`] of the transcript commitments, which the Notary
+//! records in the [`SessionHeader`](crate::SessionHeader), so that a verifier which can only
+//! compute one hash function can check both the commitments and their inclusion in the tree.
+//! The exception is [`HashAlgorithm::Blake3`], the default, whose tree is hashed with SHA-256 as
+//! in earlier releases, so that their presentations remain verifiable.
 
 use mpz_core::hash::Hash;
 use rs_merkle::{
-    algorithms::Sha256, proof_serializers, MerkleProof as MerkleProof_rs_merkle,
+    algorithms::Sha256, Hasher, MerkleProof as MerkleProof_rs_merkle,
     MerkleTree as MerkleTree_rs_merkle,
 };
 use serde::{ser::Serializer, Deserialize, Deserializer, Serialize};
+use sha2::Digest;
 use utils::iter::DuplicateCheck;
 
+use crate::hash::HashAlgorithm;
+
 /// A Merkle root.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MerkleRoot([u8; 32]);
@@ -43,16 +54,74 @@ pub enum MerkleError {
     MerkleProofVerificationFailed,
     #[error("No leaves were provided when constructing a Merkle tree")]
     MerkleNoLeavesProvided,
+    /// The hash algorithm is not supported by this build.
+    #[error("hash algorithm {0:?} is not supported by this build")]
+    UnsupportedHashAlgorithm(HashAlgorithm),
+}
+
+/// Keccak-256 as a hasher of [`rs_merkle`].
+#[derive(Clone)]
+struct Keccak256;
+
+impl Hasher for Keccak256 {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> [u8; 32] {
+        sha3::Keccak256::digest(data).into()
+    }
+}
+
+/// Poseidon as a hasher of [`rs_merkle`].
+#[cfg(feature = "poseidon")]
+#[derive(Clone)]
+struct Poseidon;
+
+#[cfg(feature = "poseidon")]
+impl Hasher for Poseidon {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> [u8; 32] {
+        crate::poseidon::hash_bytes(data)
+    }
+}
+
+/// Evaluates `$body` with `$hasher` set to the [`rs_merkle`] hasher of the tree of `$alg`, or
+/// returns an error if the algorithm is not supported.
+macro_rules! with_hasher {
+    ($alg:expr, $hasher:ident => $body:expr) => {
+        match $alg {
+            HashAlgorithm::Blake3 | HashAlgorithm::Sha256 => {
+                type $hasher = Sha256;
+                $body
+            }
+            HashAlgorithm::Keccak256 => {
+                type $hasher = Keccak256;
+                $body
+            }
+            #[cfg(feature = "poseidon")]
+            HashAlgorithm::Poseidon => {
+                type $hasher = Poseidon;
+                $body
+            }
+            #[cfg(not(feature = "poseidon"))]
+            alg @ HashAlgorithm::Poseidon => {
+                return Err(MerkleError::UnsupportedHashAlgorithm(alg));
+            }
+        }
+    };
 }
 
 /// A Merkle proof.
-#[derive(Serialize, Deserialize)]
+///
+/// The proof does not record the hash algorithm of the tree, it is verified with the algorithm
+/// of the session header.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MerkleProof {
     #[serde(
         serialize_with = "merkle_proof_serialize",
         deserialize_with = "merkle_proof_deserialize"
     )]
-    proof: MerkleProof_rs_merkle<Sha256>,
+    proof_hashes: Vec<[u8; 32]>,
     total_leaves: usize,
 }
 
@@ -65,6 +134,7 @@ impl MerkleProof {
     /// - If `leaf_indices` contains duplicates.
     pub fn verify(
         &self,
+        algorithm: HashAlgorithm,
         root: &MerkleRoot,
         leaf_indices: &[usize],
         leaf_hashes: &[Hash],
@@ -91,65 +161,119 @@ impl MerkleProof {
         tuples.sort_by(|(a, _), (b, _)| a.cmp(b));
         let (indices, hashes): (Vec<usize>, Vec<[u8; 32]>) = tuples.into_iter().unzip();
 
-        if !self
-            .proof
-            .verify(root.to_inner(), &indices, &hashes, self.total_leaves)
-        {
+        let valid = with_hasher!(algorithm, H => {
+            MerkleProof_rs_merkle::<H>::new(self.proof_hashes.clone()).verify(
+                root.to_inner(),
+                &indices,
+                &hashes,
+                self.total_leaves,
+            )
+        });
+
+        if !valid {
             return Err(MerkleError::MerkleProofVerificationFailed);
         }
         Ok(())
     }
 }
 
-impl Clone for MerkleProof {
-    fn clone(&self) -> Self {
-        let bytes = self.proof.to_bytes();
-        Self {
-            proof: MerkleProof_rs_merkle::<Sha256>::from_bytes(&bytes).unwrap(),
-            total_leaves: self.total_leaves,
-        }
-    }
-}
-
-fn merkle_proof_serialize<S>(
-    proof: &MerkleProof_rs_merkle<Sha256>,
-    serializer: S,
-) -> Result<S::Ok, S::Error>
+/// Serializes the proof hashes in the order of `rs_merkle`'s `DirectHashesOrder`.
+fn merkle_proof_serialize<S>(proof_hashes: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let bytes = proof.serialize::<proof_serializers::DirectHashesOrder>();
-    serializer.serialize_bytes(&bytes)
+    serializer.serialize_bytes(&proof_hashes.concat())
 }
 
-fn merkle_proof_deserialize<'de, D>(
-    deserializer: D,
-) -> Result<MerkleProof_rs_merkle<Sha256>, D::Error>
+fn merkle_proof_deserialize<'de, D>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let bytes = Vec::deserialize(deserializer)?;
-    MerkleProof_rs_merkle::<Sha256>::from_bytes(bytes.as_slice()).map_err(serde::de::Error::custom)
+    let bytes: Vec<u8> = Vec::deserialize(deserializer)?;
+    if bytes.len() % 32 != 0 {
+        return Err(serde::de::Error::custom("proof hashes must be 32 bytes"));
+    }
+
+    Ok(bytes.chunks(32).map(|c| c.try_into().unwrap()).collect())
+}
+
+/// An `rs_merkle` tree over the hasher of a [`HashAlgorithm`].
+#[derive(Clone)]
+enum Tree {
+    Sha256(MerkleTree_rs_merkle<Sha256>),
+    Keccak256(MerkleTree_rs_merkle<Keccak256>),
+    #[cfg(feature = "poseidon")]
+    Poseidon(MerkleTree_rs_merkle<Poseidon>),
+}
+
+/// Evaluates `$body` with `$inner` bound to the `rs_merkle` tree of `$tree`.
+macro_rules! with_tree {
+    ($tree:expr, $inner:ident => $body:expr) => {
+        match $tree {
+            Tree::Sha256($inner) => $body,
+            Tree::Keccak256($inner) => $body,
+            #[cfg(feature = "poseidon")]
+            Tree::Poseidon($inner) => $body,
+        }
+    };
 }
 
 /// A Merkle tree.
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct MerkleTree(
-    #[serde(
-        serialize_with = "merkle_tree_serialize",
-        deserialize_with = "merkle_tree_deserialize"
-    )]
-    pub MerkleTree_rs_merkle<Sha256>,
-);
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "MerkleTreeData", into = "MerkleTreeData")]
+pub struct MerkleTree {
+    algorithm: HashAlgorithm,
+    tree: Tree,
+}
+
+/// The serialized form of a [`MerkleTree`].
+#[derive(Serialize, Deserialize)]
+struct MerkleTreeData {
+    algorithm: HashAlgorithm,
+    leaves: Vec<Hash>,
+}
+
+impl From<MerkleTree> for MerkleTreeData {
+    fn from(tree: MerkleTree) -> Self {
+        let leaves = with_tree!(&tree.tree, inner => inner.leaves())
+            .unwrap_or_default()
+            .into_iter()
+            .map(Hash::from)
+            .collect();
+
+        Self {
+            algorithm: tree.algorithm,
+            leaves,
+        }
+    }
+}
+
+impl TryFrom<MerkleTreeData> for MerkleTree {
+    type Error = MerkleError;
+
+    fn try_from(data: MerkleTreeData) -> Result<Self, Self::Error> {
+        MerkleTree::from_leaves(data.algorithm, &data.leaves)
+    }
+}
 
 impl MerkleTree {
-    /// Create a new Merkle tree from the given `leaves`
-    pub fn from_leaves(leaves: &[Hash]) -> Result<Self, MerkleError> {
+    /// Create a new Merkle tree from the given `leaves`, hashed with the tree hash of `algorithm`.
+    pub fn from_leaves(algorithm: HashAlgorithm, leaves: &[Hash]) -> Result<Self, MerkleError> {
         if leaves.is_empty() {
             return Err(MerkleError::MerkleNoLeavesProvided);
         }
         let leaves: Vec<[u8; 32]> = leaves.iter().map(|h| *h.as_bytes()).collect();
-        Ok(Self(MerkleTree_rs_merkle::<Sha256>::from_leaves(&leaves)))
+
+        let tree = with_hasher!(algorithm, H => {
+            Tree::from(MerkleTree_rs_merkle::<H>::from_leaves(&leaves))
+        });
+
+        Ok(Self { algorithm, tree })
+    }
+
+    /// Returns the hash algorithm of the transcript commitments this tree was created for.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
     }
 
     /// Creates an inclusion proof for the given `indices`
@@ -164,57 +288,37 @@ impl MerkleTree {
             "indices must be sorted"
         );
 
-        let proof = self.0.proof(indices);
-        MerkleProof {
-            proof,
-            total_leaves: self.0.leaves_len(),
-        }
+        with_tree!(&self.tree, inner => MerkleProof {
+            proof_hashes: inner.proof(indices).proof_hashes().to_vec(),
+            total_leaves: inner.leaves_len(),
+        })
     }
 
     /// Returns the Merkle root for this MerkleTree
     pub fn root(&self) -> MerkleRoot {
-        self.0
-            .root()
+        with_tree!(&self.tree, inner => inner.root())
             .expect("Merkle root should be available")
             .into()
     }
 }
 
-/// Serialize the rs_merkle's `MerkleTree` type
-fn merkle_tree_serialize<S>(
-    tree: &MerkleTree_rs_merkle<Sha256>,
-    serializer: S,
-) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    // all leaves are sha256 hashes
-    let hash_size = 32;
-    let mut bytes: Vec<u8> = Vec::with_capacity(tree.leaves_len() * hash_size);
-    if let Some(leaves) = tree.leaves() {
-        for leaf in leaves {
-            bytes.append(&mut leaf.to_vec());
-        }
+impl From<MerkleTree_rs_merkle<Sha256>> for Tree {
+    fn from(tree: MerkleTree_rs_merkle<Sha256>) -> Self {
+        Tree::Sha256(tree)
     }
-
-    serializer.serialize_bytes(&bytes)
 }
 
-fn merkle_tree_deserialize<'de, D>(
-    deserializer: D,
-) -> Result<MerkleTree_rs_merkle<Sha256>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let bytes: Vec<u8> = Vec::deserialize(deserializer)?;
-    if bytes.len() % 32 != 0 {
-        return Err(serde::de::Error::custom("leaves must be 32 bytes"));
+impl From<MerkleTree_rs_merkle<Keccak256>> for Tree {
+    fn from(tree: MerkleTree_rs_merkle<Keccak256>) -> Self {
+        Tree::Keccak256(tree)
     }
-    let leaves: Vec<[u8; 32]> = bytes.chunks(32).map(|c| c.try_into().unwrap()).collect();
+}
 
-    Ok(MerkleTree_rs_merkle::<Sha256>::from_leaves(
-        leaves.as_slice(),
-    ))
+#[cfg(feature = "poseidon")]
+impl From<MerkleTree_rs_merkle<Poseidon>> for Tree {
+    fn from(tree: MerkleTree_rs_merkle<Poseidon>) -> Self {
+        Tree::Poseidon(tree)
+    }
 }
 
 #[cfg(test)]
@@ -229,11 +333,18 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let proof = tree.proof(&[2, 3, 4]);
 
         assert!(proof
-            .verify(&tree.root(), &[2, 3, 4], &[leaf2, leaf3, leaf4])
+            .verify(
+                HashAlgorithm::Sha256,
+                &tree.root(),
+                &[2, 3, 4],
+                &[leaf2, leaf3, leaf4]
+            )
             .is_ok(),);
     }
 
@@ -244,13 +355,20 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let proof = tree.proof(&[2, 3, 4]);
 
         // fail because the leaf is wrong
         assert_eq!(
             proof
-                .verify(&tree.root(), &[2, 3, 4], &[leaf1, leaf3, leaf4])
+                .verify(
+                    HashAlgorithm::Sha256,
+                    &tree.root(),
+                    &[2, 3, 4],
+                    &[leaf1, leaf3, leaf4]
+                )
                 .err()
                 .unwrap(),
             MerkleError::MerkleProofVerificationFailed
@@ -265,7 +383,9 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         _ = tree.proof(&[2, 4, 3]);
     }
 
@@ -277,7 +397,9 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         _ = tree.proof(&[2, 2, 3]);
     }
 
@@ -289,10 +411,17 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let proof = tree.proof(&[2, 3, 4]);
 
-        _ = proof.verify(&tree.root(), &[1, 2, 3, 4], &[leaf2, leaf3, leaf4]);
+        _ = proof.verify(
+            HashAlgorithm::Sha256,
+            &tree.root(),
+            &[1, 2, 3, 4],
+            &[leaf2, leaf3, leaf4],
+        );
     }
 
     #[test]
@@ -303,10 +432,17 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let proof = tree.proof(&[2, 3, 4]);
 
-        _ = proof.verify(&tree.root(), &[2, 2, 3], &[leaf2, leaf2, leaf3]);
+        _ = proof.verify(
+            HashAlgorithm::Sha256,
+            &tree.root(),
+            &[2, 2, 3],
+            &[leaf2, leaf2, leaf3],
+        );
     }
 
     #[test]
@@ -316,14 +452,21 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let mut proof = tree.proof(&[2, 3, 4]);
 
         proof.total_leaves = 6;
 
         // fail because leaf count is wrong
         assert!(proof
-            .verify(&tree.root(), &[2, 3, 4], &[leaf2, leaf3, leaf4])
+            .verify(
+                HashAlgorithm::Sha256,
+                &tree.root(),
+                &[2, 3, 4],
+                &[leaf2, leaf3, leaf4]
+            )
             .is_err());
     }
 
@@ -334,12 +477,19 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let proof = tree.proof(&[2, 3, 4]);
 
         // fail because tree index is wrong
         assert!(proof
-            .verify(&tree.root(), &[1, 3, 4], &[leaf1, leaf3, leaf4])
+            .verify(
+                HashAlgorithm::Sha256,
+                &tree.root(),
+                &[1, 3, 4],
+                &[leaf1, leaf3, leaf4]
+            )
             .is_err());
     }
 
@@ -350,15 +500,64 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let proof = tree.proof(&[2, 3, 4]);
 
         // trying to verify less leaves than what was included in the proof
         assert!(proof
-            .verify(&tree.root(), &[3, 4], &[leaf3, leaf4])
+            .verify(
+                HashAlgorithm::Sha256,
+                &tree.root(),
+                &[3, 4],
+                &[leaf3, leaf4]
+            )
             .is_err());
     }
 
+    #[test]
+    fn test_tree_hash_follows_algorithm() {
+        let leaves = (0..5u8).map(|i| Hash::from([i; 32])).collect::<Vec<_>>();
+        let tree = |alg| MerkleTree::from_leaves(alg, &leaves).unwrap();
+
+        let sha256 = tree(HashAlgorithm::Sha256);
+        let keccak256 = tree(HashAlgorithm::Keccak256);
+        assert_ne!(sha256.root(), keccak256.root());
+        // BLAKE3 commitments keep the SHA-256 tree of earlier releases.
+        assert_eq!(tree(HashAlgorithm::Blake3).root(), sha256.root());
+
+        let proof = keccak256.proof(&[1, 3]);
+        assert!(proof
+            .verify(
+                HashAlgorithm::Keccak256,
+                &keccak256.root(),
+                &[1, 3],
+                &[leaves[1], leaves[3]]
+            )
+            .is_ok());
+        assert_eq!(
+            proof.verify(
+                HashAlgorithm::Sha256,
+                &keccak256.root(),
+                &[1, 3],
+                &[leaves[1], leaves[3]]
+            ),
+            Err(MerkleError::MerkleProofVerificationFailed)
+        );
+    }
+
+    #[test]
+    fn test_serialization_keeps_algorithm() {
+        let leaves = (0..5u8).map(|i| Hash::from([i; 32])).collect::<Vec<_>>();
+        let tree = MerkleTree::from_leaves(HashAlgorithm::Keccak256, &leaves).unwrap();
+
+        let tree2: MerkleTree = bincode::deserialize(&bincode::serialize(&tree).unwrap()).unwrap();
+
+        assert_eq!(tree2.algorithm(), HashAlgorithm::Keccak256);
+        assert_eq!(tree2.root(), tree.root());
+    }
+
     // Expect MerkleProof/MerkleTree custom serialization/deserialization to work
     #[test]
     fn test_serialization() {
@@ -367,7 +566,9 @@ mod test {
         let leaf2 = Hash::from([2u8; 32]);
         let leaf3 = Hash::from([3u8; 32]);
         let leaf4 = Hash::from([4u8; 32]);
-        let tree = MerkleTree::from_leaves(&[leaf0, leaf1, leaf2, leaf3, leaf4]).unwrap();
+        let tree =
+            MerkleTree::from_leaves(HashAlgorithm::Sha256, &[leaf0, leaf1, leaf2, leaf3, leaf4])
+                .unwrap();
         let proof = tree.proof(&[2, 3, 4]);
 
         // serialize
@@ -379,7 +580,12 @@ mod test {
         let proof2: MerkleProof = bincode::deserialize(&proof_bytes).unwrap();
 
         assert!(proof2
-            .verify(&tree2.root(), &[2, 3, 4], &[leaf2, leaf3, leaf4])
+            .verify(
+                HashAlgorithm::Sha256,
+                &tree2.root(),
+                &[2, 3, 4],
+                &[leaf2, leaf3, leaf4]
+            )
             .is_ok());
     }
 }
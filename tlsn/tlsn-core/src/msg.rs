@@ -1,15 +1,28 @@
 //! Protocol message types.
 
 use serde::{Deserialize, Serialize};
-use utils::range::RangeSet;
+use utils::range::{RangeDifference, RangeSet};
 
-use crate::{merkle::MerkleRoot, proof::SessionInfo, signature::Signature, SessionHeader};
+use crate::{
+    hash::HashAlgorithm, kzg::KzgCommitment, merkle::MerkleRoot, proof::SessionInfo,
+    session::Extensions, signature::Signature, tee::TeeQuote, LengthPadding, RedactedTranscript,
+    SessionHeader,
+};
 
 /// Top-level enum for all messages
 #[derive(Debug, Serialize, Deserialize)]
 pub enum TlsnMessage {
     /// A Merkle root for the tree of commitments to the transcript.
     TranscriptCommitmentRoot(MerkleRoot),
+    /// A KZG vector commitment to the transcript commitments, if the prover opens them with KZG
+    /// proofs.
+    KzgCommitment(Option<KzgCommitment>),
+    /// The hash algorithm of the transcript commitments.
+    CommitmentHashAlgorithm(HashAlgorithm),
+    /// Extension claims the prover requests to be included in the session header.
+    Extensions(Extensions),
+    /// Padding to apply to the transcript lengths in the session header.
+    LengthPadding(LengthPadding),
     /// A session header signed by a notary.
     SignedSessionHeader(SignedSessionHeader),
     /// A session header.
@@ -18,6 +31,21 @@ pub enum TlsnMessage {
     SessionInfo(SessionInfo),
     /// Information about the values the prover wants to prove
     ProvingInfo(ProvingInfo),
+    /// Parts of the transcript the verifier asks the prover to reveal
+    RevealRequest(RevealRequest),
+    /// The prover declined a [`RevealRequest`]
+    RevealDeclined,
+    /// Data the prover asks to preprocess during setup
+    SetupPreprocessing(SetupPreprocessing),
+    /// The maximum number of threads a party runs the encryption and decryption circuits on.
+    ///
+    /// Both parties send theirs during setup and use the smaller of the two, as the circuits are
+    /// split across the same number of threads on each side.
+    MaxThreads(usize),
+    /// The prefixes of the ids of the channels a party sends compressed data on.
+    ///
+    /// Both parties send theirs during setup, and only compress the channels opened afterwards.
+    CompressedChannels(Vec<String>),
 }
 
 /// A signed session header.
@@ -27,6 +55,22 @@ pub struct SignedSessionHeader {
     pub header: SessionHeader,
     /// The notary's signature
     pub signature: Signature,
+    /// The hardware attestation quote of the notary, if it runs in a TEE
+    pub tee_quote: Option<TeeQuote>,
+}
+
+/// Data whose encryption and decryption the prover asks to preprocess during setup, before it
+/// connects to the server, so that less work is left for the connection.
+///
+/// Both parties preprocess the same amounts, which must not exceed the limits of sent and received
+/// data of the verifier, or it rejects the session. If not set, the default amounts of the
+/// MPC-TLS configuration are preprocessed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupPreprocessing {
+    /// Bytes of sent data
+    pub sent: Option<usize>,
+    /// Bytes of received data
+    pub recv: Option<usize>,
 }
 
 /// Information about the values the prover wants to prove
@@ -39,3 +83,25 @@ pub struct ProvingInfo {
     /// Purported cleartext values
     pub cleartext: Vec<u8>,
 }
+
+/// Parts of the transcript a verifier asks the prover to reveal.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevealRequest {
+    /// Ranges of the sent transcript
+    pub sent: RangeSet<usize>,
+    /// Ranges of the received transcript
+    pub recv: RangeSet<usize>,
+    /// Names of fields of the transcript, which the prover resolves to ranges, e.g. the names of
+    /// HTTP commitments such as `response[0].headers.content-type`
+    pub fields: Vec<String>,
+}
+
+impl RevealRequest {
+    /// Returns whether the requested ranges are revealed in the given transcripts.
+    ///
+    /// Fields are not checked, as resolving them depends on the format of the transcript.
+    pub fn is_revealed(&self, sent: &RedactedTranscript, recv: &RedactedTranscript) -> bool {
+        self.sent.difference(sent.authed()).is_empty()
+            && self.recv.difference(recv.authed()).is_empty()
+    }
+}
@@ -0,0 +1,118 @@
+//! Poseidon hashing of session headers.
+//!
+//! Hashing the session header with SHA-256 or Keccak inside a zk circuit is prohibitively
+//! expensive. This module provides a Poseidon hash over the BN254 scalar field, using the
+//! circom-compatible parameters, so a circuit can cheaply bind to a session header and its
+//! commitment root.
+//!
+//! # Encoding
+//!
+//! The header is split into field elements as follows:
+//!
+//! - The [signing message](SessionHeader::signing_message) of the header is split into 31 byte
+//!   chunks, each of which is interpreted as a big-endian integer. 31 bytes always fit in a
+//!   field element.
+//! - The elements are absorbed one at a time, `state = poseidon(state, chunk)`, starting with
+//!   the byte length of the message as the initial state.
+//!
+//! # Binding to the Notary signature
+//!
+//! The Notary signs the header, not its Poseidon hash. A digest which a circuit can bind to is
+//! instead included in the signed header: a Notary configured to do so records the Poseidon
+//! hash of the commitment root with [`SessionHeader::with_poseidon_root`], which a Verifier
+//! reads with [`SessionHeader::poseidon_root`] once the header signature was verified.
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use light_poseidon::{Poseidon, PoseidonHasher};
+
+use crate::{canonical::CanonicalJsonError, merkle::MerkleRoot, SessionHeader};
+
+/// The number of bytes packed into each field element.
+pub const CHUNK_SIZE: usize = 31;
+
+/// Splits bytes into the field elements which are absorbed by [`hash_bytes`].
+pub fn field_elements(bytes: &[u8]) -> Vec<Fr> {
+    bytes
+        .chunks(CHUNK_SIZE)
+        .map(Fr::from_be_bytes_mod_order)
+        .collect()
+}
+
+/// Hashes arbitrary bytes with Poseidon, returning the big-endian encoding of the digest.
+pub fn hash_bytes(bytes: &[u8]) -> [u8; 32] {
+    let mut poseidon = Poseidon::<Fr>::new_circom(2).expect("width 3 is supported");
+
+    let digest =
+        field_elements(bytes)
+            .into_iter()
+            .fold(Fr::from(bytes.len() as u64), |state, chunk| {
+                poseidon
+                    .hash(&[state, chunk])
+                    .expect("inputs are field elements")
+            });
+
+    digest
+        .into_bigint()
+        .to_bytes_be()
+        .try_into()
+        .expect("field element is 32 bytes")
+}
+
+impl SessionHeader {
+    /// Returns the Poseidon hash of the signing message of this header.
+    pub fn poseidon_hash(&self) -> Result<[u8; 32], CanonicalJsonError> {
+        Ok(hash_bytes(&self.signing_message()?))
+    }
+}
+
+impl MerkleRoot {
+    /// Returns the Poseidon hash of this root.
+    pub fn poseidon_hash(&self) -> [u8; 32] {
+        hash_bytes(&self.to_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poseidon_hash_bytes() {
+        assert_eq!(hash_bytes(b"header"), hash_bytes(b"header"));
+        assert_ne!(hash_bytes(b"header"), hash_bytes(b"header\0"));
+        // Length is bound, so trailing zeros in the last chunk are not ambiguous.
+        assert_ne!(hash_bytes(&[0u8; 31]), hash_bytes(&[0u8; 30]));
+    }
+
+    #[test]
+    fn test_poseidon_known_answer() {
+        // poseidon([1, 2]) from the circomlib test vectors.
+        let expected: [u8; 32] = [
+            0x11, 0x5c, 0xc0, 0xf5, 0xe7, 0xd6, 0x90, 0x41, 0x3d, 0xf6, 0x4c, 0x6b, 0x96, 0x62,
+            0xe9, 0xcf, 0x2a, 0x36, 0x17, 0xf2, 0x74, 0x32, 0x45, 0x51, 0x9e, 0x19, 0x60, 0x7a,
+            0x44, 0x17, 0x18, 0x9a,
+        ];
+
+        let digest = Poseidon::<Fr>::new_circom(2)
+            .unwrap()
+            .hash(&[Fr::from(1u64), Fr::from(2u64)])
+            .unwrap();
+        assert_eq!(digest.into_bigint().to_bytes_be(), expected);
+
+        // A single byte is absorbed as `poseidon(len, byte)`.
+        assert_eq!(hash_bytes(&[2]), expected);
+    }
+
+    #[test]
+    fn test_poseidon_root_is_signed() {
+        let header = crate::fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10);
+        let root = header.merkle_root().poseidon_hash();
+        let header = header.with_poseidon_root(root);
+
+        assert_eq!(header.poseidon_root(), Some(&root));
+        assert!(String::from_utf8(header.signing_message().unwrap())
+            .unwrap()
+            .contains("poseidon_root"));
+    }
+}
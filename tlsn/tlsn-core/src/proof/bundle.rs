@@ -0,0 +1,488 @@
+//! Bundles of presentations from multiple notarized sessions.
+
+use std::ops::Range;
+
+use mpz_core::{hash::Hash, serialize::CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+use tls_core::verify::ServerCertVerifier;
+
+use crate::{
+    proof::{SessionProofError, SubstringsProofError, TlsProof},
+    signature::{Signature, SignatureVerifyError},
+    timestamp::attestation_digest,
+    Direction, NotaryPublicKey, RedactedTranscript, ServerName,
+};
+
+/// An error for [`PresentationBundle`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum BundleError {
+    /// The bundle does not contain any presentations.
+    #[error("bundle is empty")]
+    Empty,
+    /// The manifest does not match the presentations in the bundle.
+    #[error("manifest does not match presentation {0}")]
+    ManifestMismatch(usize),
+    /// A presentation appears more than once in the bundle.
+    #[error("presentation {0} is a duplicate")]
+    DuplicatePresentation(usize),
+    /// A presentation is missing the notary signature.
+    #[error("presentation {0} is missing the notary signature")]
+    MissingNotarySignature(usize),
+    /// A presentation was not signed by any of the trusted notaries.
+    #[error("presentation {0} was not signed by a trusted notary")]
+    UntrustedNotary(usize),
+    /// A presentation is invalid.
+    #[error("presentation {index} is invalid: {source}")]
+    InvalidSession {
+        /// The index of the presentation.
+        index: usize,
+        /// The error.
+        source: SessionProofError,
+    },
+    /// A presentation contains an invalid substrings proof.
+    #[error("presentation {index} contains an invalid substrings proof: {source}")]
+    InvalidSubstrings {
+        /// The index of the presentation.
+        index: usize,
+        /// The error.
+        source: SubstringsProofError,
+    },
+    /// The presentations are from different servers.
+    #[error("presentation {0} is from a different server")]
+    ServerMismatch(usize),
+    /// The account id is not disclosed, or differs from the account id commitment.
+    #[error("account id in presentation {0} is not consistent with the bundle")]
+    AccountIdMismatch(usize),
+    /// The bundle signature is invalid.
+    #[error(transparent)]
+    InvalidSignature(#[from] SignatureVerifyError),
+    /// The bundle is missing a signature.
+    #[error("bundle is missing a signature")]
+    MissingSignature,
+}
+
+/// The location of an account id in the disclosed transcript of a presentation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountIdLocation {
+    /// The direction of the transcript containing the account id.
+    pub direction: Direction,
+    /// The range of the account id in the transcript.
+    pub range: Range<usize>,
+}
+
+/// A commitment to an account id which must be consistent across all presentations in a bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountIdCommitment {
+    /// BLAKE3 hash of the salt followed by the account id.
+    pub hash: Hash,
+    /// The salt.
+    pub salt: [u8; 16],
+    /// Where the account id is disclosed in each presentation, in bundle order.
+    pub locations: Vec<AccountIdLocation>,
+}
+
+impl AccountIdCommitment {
+    /// Creates a new commitment to the account id.
+    pub fn new(account_id: &[u8], salt: [u8; 16], locations: Vec<AccountIdLocation>) -> Self {
+        Self {
+            hash: Self::compute(account_id, &salt),
+            salt,
+            locations,
+        }
+    }
+
+    fn compute(account_id: &[u8], salt: &[u8; 16]) -> Hash {
+        let mut bytes = salt.to_vec();
+        bytes.extend_from_slice(account_id);
+        Hash::from(mpz_core::utils::blake3(&bytes))
+    }
+}
+
+/// A manifest of the presentations in a bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// The attestation digests of the presentations, in bundle order.
+    pub attestations: Vec<[u8; 32]>,
+    /// An optional commitment to an account id which is disclosed in every presentation.
+    pub account_id: Option<AccountIdCommitment>,
+}
+
+/// A bundle of presentations from multiple notarized sessions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PresentationBundle {
+    /// The manifest of the bundle.
+    pub manifest: BundleManifest,
+    /// The presentations.
+    pub presentations: Vec<TlsProof>,
+    /// A signature over the manifest.
+    pub signature: Option<Signature>,
+}
+
+/// A verified bundle of presentations.
+#[derive(Debug)]
+pub struct VerifiedBundle {
+    /// The server all presentations are from.
+    pub server_name: ServerName,
+    /// The redacted sent and received transcripts of each presentation, in bundle order.
+    pub transcripts: Vec<(RedactedTranscript, RedactedTranscript)>,
+    /// Whether the server closed the connection of each presentation cleanly, in bundle order,
+    /// see [`SessionHeader::closed_cleanly`](crate::SessionHeader::closed_cleanly).
+    pub closed_cleanly: Vec<bool>,
+}
+
+impl PresentationBundle {
+    /// Creates a new bundle.
+    ///
+    /// Returns an error if any presentation is missing the notary signature.
+    pub fn new(
+        presentations: Vec<TlsProof>,
+        account_id: Option<AccountIdCommitment>,
+    ) -> Result<Self, BundleError> {
+        let attestations = presentations
+            .iter()
+            .enumerate()
+            .map(|(index, presentation)| {
+                let session = &presentation.session;
+                session
+                    .signature
+                    .as_ref()
+                    .map(|signature| attestation_digest(&session.header, signature))
+                    .ok_or(BundleError::MissingNotarySignature(index))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            manifest: BundleManifest {
+                attestations,
+                account_id,
+            },
+            presentations,
+            signature: None,
+        })
+    }
+
+    /// Signs the manifest of this bundle.
+    pub fn sign<T>(&mut self, signer: &impl signature::Signer<T>)
+    where
+        T: Into<Signature>,
+    {
+        self.signature = Some(signer.sign(&self.manifest.to_bytes()).into());
+    }
+
+    /// Verifies the bundle and returns the disclosed transcripts.
+    ///
+    /// Checks that:
+    ///
+    /// - Every presentation is listed in the manifest and was signed by one of the trusted notaries.
+    /// - No presentation appears more than once.
+    /// - All presentations are from the same server.
+    /// - If the manifest commits to an account id, every presentation discloses the same account id.
+    /// - If `signer` is provided, the manifest was signed with that key.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_keys` - The public keys of the trusted notaries.
+    /// * `cert_verifier` - The certificate verifier.
+    /// * `signer` - The expected signer of the manifest, if any.
+    pub fn verify(
+        self,
+        notary_public_keys: &[NotaryPublicKey],
+        cert_verifier: &impl ServerCertVerifier,
+        signer: Option<&NotaryPublicKey>,
+    ) -> Result<VerifiedBundle, BundleError> {
+        let Self {
+            manifest,
+            presentations,
+            signature,
+        } = self;
+
+        if let Some(signer) = signer {
+            signature
+                .ok_or(BundleError::MissingSignature)?
+                .verify(&manifest.to_bytes(), signer.clone())?;
+        }
+
+        if presentations.is_empty() {
+            return Err(BundleError::Empty);
+        }
+
+        if manifest.attestations.len() != presentations.len() {
+            return Err(BundleError::ManifestMismatch(
+                manifest.attestations.len().min(presentations.len()),
+            ));
+        }
+
+        if let Some(index) = (1..manifest.attestations.len())
+            .find(|index| manifest.attestations[..*index].contains(&manifest.attestations[*index]))
+        {
+            return Err(BundleError::DuplicatePresentation(index));
+        }
+
+        if let Some(account_id) = &manifest.account_id {
+            if account_id.locations.len() != presentations.len() {
+                return Err(BundleError::AccountIdMismatch(
+                    account_id.locations.len().min(presentations.len()),
+                ));
+            }
+        }
+
+        let mut server_name: Option<ServerName> = None;
+        let mut transcripts = Vec::with_capacity(presentations.len());
+        let mut closed_cleanly = Vec::with_capacity(presentations.len());
+        for (index, presentation) in presentations.into_iter().enumerate() {
+            let TlsProof {
+                session,
+                substrings,
+                ..
+            } = presentation;
+
+            let notary_signature = session
+                .signature
+                .as_ref()
+                .ok_or(BundleError::MissingNotarySignature(index))?;
+
+            if attestation_digest(&session.header, notary_signature) != manifest.attestations[index]
+            {
+                return Err(BundleError::ManifestMismatch(index));
+            }
+
+            let notary_public_key = notary_public_keys
+                .iter()
+                .find(|key| {
+                    session
+                        .header
+                        .verify_signature(notary_signature, (*key).clone())
+                        .is_ok()
+                })
+                .ok_or(BundleError::UntrustedNotary(index))?;
+
+            session
+                .verify(notary_public_key.clone(), cert_verifier)
+                .map_err(|source| BundleError::InvalidSession { index, source })?;
+
+            match &server_name {
+                Some(name) if name != &session.session_info.server_name => {
+                    return Err(BundleError::ServerMismatch(index))
+                }
+                Some(_) => {}
+                None => server_name = Some(session.session_info.server_name.clone()),
+            }
+
+            let (sent, recv) = substrings
+                .verify(&session.header)
+                .map_err(|source| BundleError::InvalidSubstrings { index, source })?;
+
+            if let Some(account_id) = &manifest.account_id {
+                let AccountIdLocation { direction, range } = &account_id.locations[index];
+                let transcript = match direction {
+                    Direction::Sent => &sent,
+                    Direction::Received => &recv,
+                };
+
+                let authed = transcript
+                    .authed()
+                    .iter_ranges()
+                    .any(|authed| authed.start <= range.start && range.end <= authed.end);
+                if !authed
+                    || range.end > transcript.data().len()
+                    || AccountIdCommitment::compute(
+                        &transcript.data()[range.clone()],
+                        &account_id.salt,
+                    ) != account_id.hash
+                {
+                    return Err(BundleError::AccountIdMismatch(index));
+                }
+            }
+
+            transcripts.push((sent, recv));
+            closed_cleanly.push(session.header.closed_cleanly());
+        }
+
+        Ok(VerifiedBundle {
+            server_name: server_name.expect("bundle is not empty"),
+            transcripts,
+            closed_cleanly,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, proof::default_cert_verifier};
+    use p256::ecdsa::Signature as P256Signature;
+
+    #[test]
+    fn test_bundle_manifest_signature() {
+        let key = fixtures::notary_signing_key();
+        let public_key = NotaryPublicKey::from(p256::PublicKey::from(*key.verifying_key()));
+
+        let mut bundle = PresentationBundle::new(vec![], None).unwrap();
+        bundle.sign::<P256Signature>(&key);
+
+        // The signature is checked before the presentations.
+        assert!(matches!(
+            bundle.verify(&[], &default_cert_verifier(), Some(&public_key)),
+            Err(BundleError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_bundle_missing_signature() {
+        let key = fixtures::notary_signing_key();
+        let public_key = NotaryPublicKey::from(p256::PublicKey::from(*key.verifying_key()));
+
+        let bundle = PresentationBundle::new(vec![], None).unwrap();
+
+        assert!(matches!(
+            bundle.verify(&[], &default_cert_verifier(), Some(&public_key)),
+            Err(BundleError::MissingSignature)
+        ));
+    }
+
+    #[test]
+    fn test_bundle_closed_cleanly() {
+        let key = fixtures::notary_signing_key();
+        let public_key = NotaryPublicKey::from(p256::PublicKey::from(*key.verifying_key()));
+
+        let bundle = PresentationBundle::new(
+            vec![
+                fixtures::tls_proof(
+                    b"GET / HTTP/1.1\r\n\r\n",
+                    b"HTTP/1.1 200 OK\r\n\r\n",
+                    |header| header.with_closed_cleanly(true),
+                ),
+                fixtures::tls_proof(b"GET / HTTP/1.1\r\n\r\n", b"HTTP/1.1 200", |header| header),
+            ],
+            None,
+        )
+        .unwrap();
+
+        let verified = bundle
+            .verify(&[public_key], &default_cert_verifier(), None)
+            .unwrap();
+
+        assert_eq!(verified.closed_cleanly, vec![true, false]);
+        assert_eq!(verified.transcripts[1].1.data(), b"HTTP/1.1 200");
+    }
+
+    static TX: &[u8] = b"GET /account HTTP/1.1\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\n\r\nalice";
+
+    fn public_key() -> NotaryPublicKey {
+        NotaryPublicKey::from(p256::PublicKey::from(
+            *fixtures::notary_signing_key().verifying_key(),
+        ))
+    }
+
+    #[test]
+    fn test_bundle_verify() {
+        let account_id = AccountIdCommitment::new(
+            b"alice",
+            [7; 16],
+            vec![
+                AccountIdLocation {
+                    direction: Direction::Received,
+                    range: RX.len() - 5..RX.len(),
+                };
+                2
+            ],
+        );
+        let mut bundle = PresentationBundle::new(
+            vec![
+                fixtures::tls_proof(TX, RX, |header| header),
+                fixtures::tls_proof(TX, RX, |header| header),
+            ],
+            Some(account_id),
+        )
+        .unwrap();
+        bundle.sign::<P256Signature>(&fixtures::notary_signing_key());
+
+        let verified = bundle
+            .verify(
+                &[public_key()],
+                &default_cert_verifier(),
+                Some(&public_key()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            verified.server_name,
+            ServerName::Dns("tlsnotary.org".to_string())
+        );
+        assert_eq!(verified.transcripts.len(), 2);
+        assert!(verified
+            .transcripts
+            .iter()
+            .all(|(sent, recv)| sent.data() == TX && recv.data() == RX));
+    }
+
+    #[test]
+    fn test_bundle_account_id_mismatch() {
+        let account_id = AccountIdCommitment::new(
+            b"bob",
+            [7; 16],
+            vec![AccountIdLocation {
+                direction: Direction::Received,
+                range: RX.len() - 5..RX.len(),
+            }],
+        );
+        let bundle = PresentationBundle::new(
+            vec![fixtures::tls_proof(TX, RX, |header| header)],
+            Some(account_id),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            bundle.verify(&[public_key()], &default_cert_verifier(), None),
+            Err(BundleError::AccountIdMismatch(0))
+        ));
+    }
+
+    #[test]
+    fn test_bundle_server_mismatch() {
+        let bundle = PresentationBundle::new(
+            vec![
+                fixtures::tls_proof(TX, RX, |header| header),
+                fixtures::server_tls_proof(&fixtures::cert::appliedzkp(), TX, RX, |header| header),
+            ],
+            None,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            bundle.verify(&[public_key()], &default_cert_verifier(), None),
+            Err(BundleError::ServerMismatch(1))
+        ));
+    }
+
+    #[test]
+    fn test_bundle_untrusted_notary() {
+        let other = NotaryPublicKey::from(p256::PublicKey::from(
+            *p256::ecdsa::SigningKey::from_slice(&[2; 32])
+                .unwrap()
+                .verifying_key(),
+        ));
+        let bundle =
+            PresentationBundle::new(vec![fixtures::tls_proof(TX, RX, |header| header)], None)
+                .unwrap();
+
+        assert!(matches!(
+            bundle.verify(&[other], &default_cert_verifier(), None),
+            Err(BundleError::UntrustedNotary(0))
+        ));
+    }
+
+    #[test]
+    fn test_bundle_duplicate_presentation() {
+        let proof = fixtures::tls_proof(TX, RX, |header| header);
+        let duplicate = TlsProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        let bundle = PresentationBundle::new(vec![proof, duplicate], None).unwrap();
+
+        assert!(matches!(
+            bundle.verify(&[public_key()], &default_cert_verifier(), None),
+            Err(BundleError::DuplicatePresentation(1))
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+//! Binary presentation format.
+//!
+//! A presentation is encoded as a magic header followed by a format version and a sequence of
+//! sections:
+//!
+//! ```text
+//! magic   : b"TLSN"
+//! version : u16 (big-endian)
+//! section : tag u8 | len u32 (big-endian) | body
+//! ...
+//! ```
+//!
+//! Section bodies are encoded with bincode using variable-length integers. Readers skip sections
+//! with unknown tags, so newer writers can add sections without breaking older readers, and
+//! readers keep decoding every version which was ever released.
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    proof::{SessionProof, SubstringsProof, TlsProof},
+    quorum::Cosignature,
+    tee::TeeQuote,
+};
+
+/// Magic bytes at the start of every encoded presentation.
+pub const MAGIC: [u8; 4] = *b"TLSN";
+
+/// The format version written by this crate.
+pub const CURRENT_VERSION: u16 = 1;
+
+const TAG_SESSION: u8 = 0x01;
+const TAG_SUBSTRINGS: u8 = 0x02;
+const TAG_COSIGNATURES: u8 = 0x03;
+const TAG_TEE_QUOTE: u8 = 0x04;
+
+/// An error that can occur while encoding or decoding a presentation.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum PresentationFormatError {
+    /// The data does not start with the magic bytes.
+    #[error("data is not a TLSNotary presentation")]
+    InvalidMagic,
+    /// The format version is not supported by this reader.
+    #[error("unsupported presentation format version: {0}")]
+    UnsupportedVersion(u16),
+    /// The data ended unexpectedly.
+    #[error("presentation is truncated")]
+    Truncated,
+    /// A required section is missing.
+    #[error("presentation is missing section {0:#04x}")]
+    MissingSection(u8),
+    /// A section appears more than once.
+    #[error("presentation contains duplicate section {0:#04x}")]
+    DuplicateSection(u8),
+    /// A section could not be encoded or decoded.
+    #[error("invalid section {tag:#04x}: {reason}")]
+    InvalidSection {
+        /// The tag of the section.
+        tag: u8,
+        /// The reason the section is invalid.
+        reason: String,
+    },
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_varint_encoding()
+        .with_limit(crate::MAX_TOTAL_COMMITTED_DATA as u64)
+}
+
+fn encode_section(
+    out: &mut Vec<u8>,
+    tag: u8,
+    body: &impl Serialize,
+) -> Result<(), PresentationFormatError> {
+    let body = options()
+        .serialize(body)
+        .map_err(|e| PresentationFormatError::InvalidSection {
+            tag,
+            reason: e.to_string(),
+        })?;
+    let len = u32::try_from(body.len()).map_err(|_| PresentationFormatError::InvalidSection {
+        tag,
+        reason: "section is too large".to_string(),
+    })?;
+
+    out.push(tag);
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(&body);
+
+    Ok(())
+}
+
+fn decode_section<T: DeserializeOwned>(tag: u8, body: &[u8]) -> Result<T, PresentationFormatError> {
+    options()
+        .deserialize(body)
+        .map_err(|e| PresentationFormatError::InvalidSection {
+            tag,
+            reason: e.to_string(),
+        })
+}
+
+/// Splits off `n` bytes from the front of `data`.
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Result<&'a [u8], PresentationFormatError> {
+    if data.len() < n {
+        return Err(PresentationFormatError::Truncated);
+    }
+
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+
+    Ok(head)
+}
+
+/// Iterates over the sections of an encoded presentation.
+fn sections(mut data: &[u8]) -> impl Iterator<Item = Result<(u8, &[u8]), PresentationFormatError>> {
+    std::iter::from_fn(move || {
+        if data.is_empty() {
+            return None;
+        }
+
+        Some((|| {
+            let tag = take(&mut data, 1)?[0];
+            let len = u32::from_be_bytes(take(&mut data, 4)?.try_into().unwrap());
+            let body = take(&mut data, len as usize)?;
+
+            Ok((tag, body))
+        })())
+    })
+}
+
+impl TlsProof {
+    /// Encodes this presentation using the [current format version](CURRENT_VERSION).
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PresentationFormatError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&CURRENT_VERSION.to_be_bytes());
+
+        encode_section(&mut out, TAG_SESSION, &self.session)?;
+        encode_section(&mut out, TAG_SUBSTRINGS, &self.substrings)?;
+        // Only written if present, so that presentations without cosignatures are unchanged.
+        if !self.cosignatures.is_empty() {
+            encode_section(&mut out, TAG_COSIGNATURES, &self.cosignatures)?;
+        }
+        if let Some(tee_quote) = &self.tee_quote {
+            encode_section(&mut out, TAG_TEE_QUOTE, tee_quote)?;
+        }
+
+        Ok(out)
+    }
+
+    /// Decodes a presentation encoded with any supported format version.
+    pub fn from_bytes(mut data: &[u8]) -> Result<Self, PresentationFormatError> {
+        if take(&mut data, MAGIC.len()).map_err(|_| PresentationFormatError::InvalidMagic)? != MAGIC
+        {
+            return Err(PresentationFormatError::InvalidMagic);
+        }
+
+        let version = u16::from_be_bytes(take(&mut data, 2)?.try_into().unwrap());
+        match version {
+            1 => Self::decode_v1(data),
+            version => Err(PresentationFormatError::UnsupportedVersion(version)),
+        }
+    }
+
+    fn decode_v1(data: &[u8]) -> Result<Self, PresentationFormatError> {
+        let mut session: Option<SessionProof> = None;
+        let mut substrings: Option<SubstringsProof> = None;
+        let mut cosignatures: Option<Vec<Cosignature>> = None;
+        let mut tee_quote: Option<TeeQuote> = None;
+
+        for section in sections(data) {
+            let (tag, body) = section?;
+            match tag {
+                TAG_SESSION if session.is_some() => {
+                    return Err(PresentationFormatError::DuplicateSection(tag))
+                }
+                TAG_SESSION => session = Some(decode_section(tag, body)?),
+                TAG_SUBSTRINGS if substrings.is_some() => {
+                    return Err(PresentationFormatError::DuplicateSection(tag))
+                }
+                TAG_SUBSTRINGS => substrings = Some(decode_section(tag, body)?),
+                TAG_COSIGNATURES if cosignatures.is_some() => {
+                    return Err(PresentationFormatError::DuplicateSection(tag))
+                }
+                TAG_COSIGNATURES => cosignatures = Some(decode_section(tag, body)?),
+                TAG_TEE_QUOTE if tee_quote.is_some() => {
+                    return Err(PresentationFormatError::DuplicateSection(tag))
+                }
+                TAG_TEE_QUOTE => tee_quote = Some(decode_section(tag, body)?),
+                // Sections added by newer writers are skipped.
+                _ => {}
+            }
+        }
+
+        Ok(TlsProof {
+            session: session.ok_or(PresentationFormatError::MissingSection(TAG_SESSION))?,
+            substrings: substrings
+                .ok_or(PresentationFormatError::MissingSection(TAG_SUBSTRINGS))?,
+            cosignatures: cosignatures.unwrap_or_default(),
+            tee_quote,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, proof::default_cert_verifier};
+
+    #[test]
+    fn test_presentation_format_rejects_bad_header() {
+        assert!(matches!(
+            TlsProof::from_bytes(b"NOPE\x00\x01"),
+            Err(PresentationFormatError::InvalidMagic)
+        ));
+        assert!(matches!(
+            TlsProof::from_bytes(b"TLSN\x00\x02"),
+            Err(PresentationFormatError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            TlsProof::from_bytes(b"TLSN\x00\x01"),
+            Err(PresentationFormatError::MissingSection(TAG_SESSION))
+        ));
+    }
+
+    #[test]
+    fn test_presentation_format_truncated_section() {
+        assert!(matches!(
+            TlsProof::from_bytes(b"TLSN\x00\x01\x01\x00\x00\x00\x10\x00"),
+            Err(PresentationFormatError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn test_presentation_format_skips_unknown_sections() {
+        // An unknown section, followed by nothing else, still fails on the missing required
+        // sections rather than on the unknown tag.
+        assert!(matches!(
+            TlsProof::from_bytes(b"TLSN\x00\x01\x7f\x00\x00\x00\x01\xff"),
+            Err(PresentationFormatError::MissingSection(TAG_SESSION))
+        ));
+    }
+
+    #[test]
+    fn test_presentation_format_round_trip() {
+        let proof = fixtures::tls_proof(
+            b"GET / HTTP/1.1\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\n\r\n",
+            |header| header,
+        );
+        let mut bytes = proof.to_bytes().unwrap();
+        assert_eq!(bytes[..4], MAGIC);
+
+        // Sections added by newer writers are skipped.
+        bytes.extend_from_slice(&[0x7f, 0x00, 0x00, 0x00, 0x01, 0xff]);
+
+        let decoded = TlsProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes().unwrap(), proof.to_bytes().unwrap());
+
+        let public_key = p256::PublicKey::from(*fixtures::notary_signing_key().verifying_key());
+        decoded
+            .session
+            .verify(public_key, &default_cert_verifier())
+            .unwrap();
+        let (sent, recv) = decoded.substrings.verify(&decoded.session.header).unwrap();
+        assert_eq!(sent.data(), b"GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(recv.data(), b"HTTP/1.1 200 OK\r\n\r\n");
+    }
+}
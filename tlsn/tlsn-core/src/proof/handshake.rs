@@ -0,0 +1,116 @@
+//! Items of the TLS handshake.
+//!
+//! [`SessionProof::verify_handshake_items`](crate::proof::SessionProof::verify_handshake_items)
+//! derives items such as the server name or a fingerprint of the server certificate from the
+//! handshake data of a session proof, once the handshake data was checked against the commitment
+//! signed by the Notary and the server certificate chain was verified. The items can therefore
+//! not be chosen by the Prover, and a Verifier can check them without inspecting the handshake.
+
+use serde::{Deserialize, Serialize};
+use tls_core::handshake::HandshakeData;
+
+use crate::ServerName;
+
+/// The TLS 1.2 protocol version.
+pub const TLS_1_2: u16 = 0x0303;
+
+/// The kind of a [`HandshakeItem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum HandshakeItemKind {
+    /// The name of the server.
+    ServerName,
+    /// The negotiated protocol version.
+    ProtocolVersion,
+    /// The fingerprint of the server's end-entity certificate.
+    CertFingerprint,
+}
+
+impl HandshakeItemKind {
+    /// All kinds.
+    pub const ALL: [HandshakeItemKind; 3] = [
+        HandshakeItemKind::ServerName,
+        HandshakeItemKind::ProtocolVersion,
+        HandshakeItemKind::CertFingerprint,
+    ];
+}
+
+/// An item derived from the TLS handshake.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum HandshakeItem {
+    /// The name of the server.
+    ServerName(ServerName),
+    /// The negotiated protocol version, as encoded on the wire.
+    ProtocolVersion(u16),
+    /// The BLAKE3 hash of the DER encoding of the server's end-entity certificate.
+    CertFingerprint([u8; 32]),
+}
+
+impl HandshakeItem {
+    /// Returns the kind of this item.
+    pub fn kind(&self) -> HandshakeItemKind {
+        match self {
+            HandshakeItem::ServerName(_) => HandshakeItemKind::ServerName,
+            HandshakeItem::ProtocolVersion(_) => HandshakeItemKind::ProtocolVersion,
+            HandshakeItem::CertFingerprint(_) => HandshakeItemKind::CertFingerprint,
+        }
+    }
+
+    /// Derives the item of the given kind from verified handshake data.
+    ///
+    /// Returns `None` if the handshake data does not contain the item.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of the item.
+    /// * `server_name` - The server name the certificate chain was verified for.
+    /// * `handshake_data` - The handshake data, verified against the session header.
+    pub(crate) fn derive(
+        kind: HandshakeItemKind,
+        server_name: &ServerName,
+        handshake_data: &HandshakeData,
+    ) -> Option<Self> {
+        match kind {
+            HandshakeItemKind::ServerName => Some(HandshakeItem::ServerName(server_name.clone())),
+            // Only TLS 1.2 is supported under MPC.
+            HandshakeItemKind::ProtocolVersion => Some(HandshakeItem::ProtocolVersion(TLS_1_2)),
+            HandshakeItemKind::CertFingerprint => handshake_data
+                .server_cert_details()
+                .cert_chain()
+                .first()
+                .map(|cert| HandshakeItem::CertFingerprint(mpz_core::utils::blake3(&cert.0))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_derive_handshake_items() {
+        let server_name = ServerName::Dns("tlsnotary.org".to_string());
+        let data = fixtures::handshake_data();
+
+        let items = HandshakeItemKind::ALL
+            .into_iter()
+            .map(|kind| HandshakeItem::derive(kind, &server_name, &data).unwrap())
+            .collect::<Vec<_>>();
+
+        let end_entity = &data.server_cert_details().cert_chain()[0];
+        assert_eq!(
+            items,
+            vec![
+                HandshakeItem::ServerName(server_name),
+                HandshakeItem::ProtocolVersion(TLS_1_2),
+                HandshakeItem::CertFingerprint(mpz_core::utils::blake3(&end_entity.0)),
+            ]
+        );
+        assert!(items
+            .iter()
+            .zip(HandshakeItemKind::ALL)
+            .all(|(item, kind)| item.kind() == kind));
+    }
+}
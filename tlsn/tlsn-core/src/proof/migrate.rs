@@ -0,0 +1,385 @@
+//! Migration of presentations from older releases.
+//!
+//! Releases before the [binary presentation format](TlsProof::to_bytes) stored a [`TlsProof`] as plain
+//! JSON or bincode, and their [`SessionHeader`] did not contain any of the optional fields which
+//! were added since. Their [`SubstringsProof`] always contained a Merkle inclusion proof. Those
+//! artifacts are referred to as [`LEGACY_VERSION`].
+//!
+//! A legacy presentation can only be upgraded if it does not carry a Notary signature: the Notary
+//! signed the legacy encoding of the header, which no longer matches the encoding of the upgraded
+//! header. Signed legacy presentations remain verifiable with [`LegacyTlsProof::verify`].
+
+use std::collections::HashMap;
+
+use bincode::Options;
+use mpz_core::serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+use tls_core::verify::ServerCertVerifier;
+
+use crate::{
+    commitment::{CommitmentId, CommitmentInfo, CommitmentOpening},
+    merkle::{MerkleProof, MerkleRoot},
+    proof::{
+        substrings::InclusionProof, PresentationFormatError, SessionInfo, SessionProof,
+        SessionProofError, SubstringsProof, SubstringsProofError, TlsProof, CURRENT_VERSION, MAGIC,
+    },
+    signature::Signature,
+    HandshakeSummary, NotaryPublicKey, RedactedTranscript, SessionHeader,
+};
+
+/// The version assigned to presentations from releases before the binary presentation format.
+pub const LEGACY_VERSION: u16 = 0;
+
+/// An error that can occur while migrating a presentation.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum MigrationError {
+    /// The data is not a presentation in any known format.
+    #[error("data is not a presentation in any known format: {0}")]
+    UnknownFormat(String),
+    /// The presentation uses the binary format, but could not be decoded.
+    #[error(transparent)]
+    Format(#[from] PresentationFormatError),
+    /// The legacy presentation is signed by the Notary, and the signature does not cover the
+    /// upgraded session header.
+    #[error(
+        "legacy presentation is signed over the legacy session header encoding and can not be \
+         upgraded, verify it with LegacyTlsProof::verify instead"
+    )]
+    SignatureNotPortable,
+    /// The legacy session proof is invalid.
+    #[error(transparent)]
+    InvalidSession(#[from] SessionProofError),
+    /// The legacy substrings proof is invalid.
+    #[error(transparent)]
+    InvalidSubstrings(#[from] SubstringsProofError),
+}
+
+/// A session header from a release before the binary presentation format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacySessionHeader {
+    /// A PRG seeds used to generate encodings for the plaintext
+    pub encoder_seed: [u8; 32],
+    /// The root of the Merkle tree of all the commitments.
+    pub merkle_root: MerkleRoot,
+    /// Bytelength of all data which was sent to the webserver
+    pub sent_len: usize,
+    /// Bytelength of all data which was received from the webserver
+    pub recv_len: usize,
+    /// Summary of the TLS handshake.
+    pub handshake_summary: HandshakeSummary,
+}
+
+impl From<LegacySessionHeader> for SessionHeader {
+    fn from(header: LegacySessionHeader) -> Self {
+        SessionHeader::new(
+            header.encoder_seed,
+            header.merkle_root,
+            header.sent_len,
+            header.recv_len,
+            header.handshake_summary,
+        )
+    }
+}
+
+/// A session proof from a release before the binary presentation format.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacySessionProof {
+    /// The session header
+    pub header: LegacySessionHeader,
+    /// Signature for the session header, if the notary signed it
+    pub signature: Option<Signature>,
+    /// Information about the server
+    pub session_info: SessionInfo,
+}
+
+/// A substrings proof from a release before the binary presentation format, which always
+/// proved the inclusion of the openings with a Merkle proof.
+#[derive(Serialize, Deserialize)]
+pub struct LegacySubstringsProof {
+    openings: HashMap<CommitmentId, (CommitmentInfo, CommitmentOpening)>,
+    inclusion_proof: MerkleProof,
+}
+
+opaque_debug::implement!(LegacySubstringsProof);
+
+impl From<LegacySubstringsProof> for SubstringsProof {
+    fn from(proof: LegacySubstringsProof) -> Self {
+        SubstringsProof {
+            openings: proof.openings,
+            inclusion_proof: InclusionProof::Merkle(proof.inclusion_proof),
+        }
+    }
+}
+
+/// A presentation from a release before the binary presentation format.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacyTlsProof {
+    /// Proof of the TLS handshake, server identity, and commitments to the transcript.
+    pub session: LegacySessionProof,
+    /// Proof regarding the contents of the transcript.
+    pub substrings: LegacySubstringsProof,
+}
+
+impl LegacyTlsProof {
+    /// Verifies this presentation as the release which created it would have, and returns the
+    /// redacted sent and received transcripts.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify(
+        self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), MigrationError> {
+        let LegacyTlsProof {
+            session,
+            substrings,
+        } = self;
+
+        let signature = session
+            .signature
+            .as_ref()
+            .ok_or(SessionProofError::MissingNotarySignature)?;
+
+        signature
+            .verify(&session.header.to_bytes(), notary_public_key)
+            .map_err(SessionProofError::from)?;
+        session
+            .session_info
+            .verify(&session.header.handshake_summary, cert_verifier)?;
+
+        Ok(SubstringsProof::from(substrings).verify(&session.header.into())?)
+    }
+
+    /// Upgrades this presentation to the current format.
+    ///
+    /// Returns [`MigrationError::SignatureNotPortable`] if the presentation is signed by the
+    /// Notary.
+    pub fn upgrade(self) -> Result<TlsProof, MigrationError> {
+        let LegacyTlsProof {
+            session,
+            substrings,
+        } = self;
+
+        if session.signature.is_some() {
+            return Err(MigrationError::SignatureNotPortable);
+        }
+
+        Ok(TlsProof {
+            session: SessionProof {
+                header: session.header.into(),
+                signature: None,
+                session_info: session.session_info,
+                timestamp: None,
+            },
+            substrings: substrings.into(),
+            cosignatures: Vec::new(),
+            tee_quote: None,
+        })
+    }
+}
+
+/// A presentation decoded from any supported format.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Migrated {
+    /// A presentation in the current format, or one which was upgraded to it.
+    Current {
+        /// The presentation.
+        proof: TlsProof,
+        /// The format version the presentation was decoded from.
+        from_version: u16,
+    },
+    /// A legacy presentation which could not be upgraded.
+    Legacy {
+        /// The presentation.
+        proof: LegacyTlsProof,
+        /// The reason the presentation could not be upgraded.
+        reason: MigrationError,
+    },
+}
+
+/// Decodes a presentation stored by any release and upgrades it to the current format where
+/// possible.
+///
+/// Supports the [binary presentation format](TlsProof::to_bytes) of every version, presentations
+/// encoded as JSON, and legacy presentations encoded as JSON or bincode.
+pub fn migrate(data: &[u8]) -> Result<Migrated, MigrationError> {
+    if data.starts_with(&MAGIC) {
+        let version = data
+            .get(MAGIC.len()..MAGIC.len() + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .ok_or(PresentationFormatError::Truncated)?;
+
+        return Ok(Migrated::Current {
+            proof: TlsProof::from_bytes(data)?,
+            from_version: version,
+        });
+    }
+
+    // Legacy headers lack required fields, so they are never mistaken for current ones.
+    if let Ok(proof) = serde_json::from_slice::<TlsProof>(data) {
+        return Ok(Migrated::Current {
+            proof,
+            from_version: CURRENT_VERSION,
+        });
+    }
+
+    let legacy = match serde_json::from_slice::<LegacyTlsProof>(data) {
+        Ok(legacy) => legacy,
+        Err(json_err) => match bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .reject_trailing_bytes()
+            .deserialize::<LegacyTlsProof>(data)
+        {
+            Ok(legacy) => legacy,
+            Err(bincode_err) => {
+                return Err(MigrationError::UnknownFormat(format!(
+                    "not JSON ({json_err}), not bincode ({bincode_err})"
+                )))
+            }
+        },
+    };
+
+    match legacy.session.signature {
+        Some(_) => Ok(Migrated::Legacy {
+            proof: legacy,
+            reason: MigrationError::SignatureNotPortable,
+        }),
+        None => Ok(Migrated::Current {
+            proof: legacy.upgrade()?,
+            from_version: LEGACY_VERSION,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+        fixtures,
+        proof::SubstringsProofBuilder,
+        Direction, ServerName, Transcript,
+    };
+    use mpz_core::commit::HashCommit;
+    use p256::ecdsa::Signature as P256Signature;
+    use signature::Signer;
+
+    static TX: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\n\r\n";
+
+    fn legacy_proof(signed: bool) -> LegacyTlsProof {
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        builder.commit_sent(&(0..TX.len())).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let mut substrings =
+            SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        substrings
+            .reveal(&(0..TX.len()), Direction::Sent, CommitmentKind::Blake3)
+            .unwrap();
+
+        let (handshake_decommitment, _) = fixtures::handshake_data().hash_commit();
+        let header = LegacySessionHeader {
+            encoder_seed: fixtures::encoder_seed(),
+            merkle_root: commitments.merkle_root(),
+            sent_len: TX.len(),
+            recv_len: RX.len(),
+            handshake_summary: fixtures::handshake_summary(),
+        };
+        let signature = signed.then(|| {
+            let signature: P256Signature = fixtures::notary_signing_key().sign(&header.to_bytes());
+            signature.into()
+        });
+
+        LegacyTlsProof {
+            session: LegacySessionProof {
+                header,
+                signature,
+                session_info: SessionInfo {
+                    server_name: ServerName::Dns("tlsnotary.org".to_string()),
+                    handshake_decommitment,
+                },
+            },
+            substrings: legacy_substrings(substrings.build().unwrap()),
+        }
+    }
+
+    fn legacy_substrings(proof: SubstringsProof) -> LegacySubstringsProof {
+        let SubstringsProof {
+            openings,
+            inclusion_proof: InclusionProof::Merkle(inclusion_proof),
+        } = proof
+        else {
+            panic!("expected a Merkle inclusion proof");
+        };
+
+        LegacySubstringsProof {
+            openings,
+            inclusion_proof,
+        }
+    }
+
+    #[test]
+    fn test_migrate_unsigned_legacy_json() {
+        let data = serde_json::to_vec(&legacy_proof(false)).unwrap();
+
+        let Migrated::Current {
+            proof,
+            from_version,
+        } = migrate(&data).unwrap()
+        else {
+            panic!("expected upgraded presentation");
+        };
+
+        assert_eq!(from_version, LEGACY_VERSION);
+        let (sent, _) = proof.substrings.verify(&proof.session.header).unwrap();
+        assert_eq!(sent.data(), TX);
+    }
+
+    #[test]
+    fn test_migrate_signed_legacy_bincode() {
+        let data = bincode::serialize(&legacy_proof(true)).unwrap();
+
+        assert!(matches!(
+            migrate(&data).unwrap(),
+            Migrated::Legacy {
+                reason: MigrationError::SignatureNotPortable,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_migrate_current_format() {
+        let proof = legacy_proof(false).upgrade().unwrap();
+        let data = proof.to_bytes().unwrap();
+
+        assert!(matches!(
+            migrate(&data).unwrap(),
+            Migrated::Current {
+                from_version: CURRENT_VERSION,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_migrate_unknown_format() {
+        assert!(matches!(
+            migrate(b"not a presentation"),
+            Err(MigrationError::UnknownFormat(_))
+        ));
+    }
+}
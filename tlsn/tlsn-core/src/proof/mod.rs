@@ -1,16 +1,35 @@
 //! Different types of proofs used in the TLSNotary protocol.
 
+mod bundle;
+mod format;
+mod handshake;
+mod migrate;
+mod pinning;
 mod session;
 mod substrings;
 
+pub use bundle::{
+    AccountIdCommitment, AccountIdLocation, BundleError, BundleManifest, PresentationBundle,
+    VerifiedBundle,
+};
+pub use format::{PresentationFormatError, CURRENT_VERSION, MAGIC};
+pub use handshake::{HandshakeItem, HandshakeItemKind, TLS_1_2};
+pub use migrate::{
+    migrate, LegacySessionHeader, LegacySessionProof, LegacySubstringsProof, LegacyTlsProof,
+    Migrated, MigrationError, LEGACY_VERSION,
+};
+pub use pinning::{spki_pin, CertPins, PinnedCertVerifier};
 pub use session::{default_cert_verifier, SessionInfo, SessionProof, SessionProofError};
 pub use substrings::{
-    SubstringsProof, SubstringsProofBuilder, SubstringsProofBuilderError, SubstringsProofError,
+    DisclosedSlices, SubstringsProof, SubstringsProofBuilder, SubstringsProofBuilderError,
+    SubstringsProofError,
 };
 
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
+use crate::{quorum::Cosignature, tee::TeeQuote};
+
 /// Proof that a transcript of communications took place between a Prover and Server.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TlsProof {
@@ -18,4 +37,12 @@ pub struct TlsProof {
     pub session: SessionProof,
     /// Proof regarding the contents of the transcript.
     pub substrings: SubstringsProof,
+    /// Signatures of further notaries over the session header, see
+    /// [`QuorumPolicy`](crate::quorum::QuorumPolicy).
+    #[serde(default)]
+    pub cosignatures: Vec<Cosignature>,
+    /// The hardware attestation quote of the notary, if it runs in a TEE, see
+    /// [`TeeQuote::verify`].
+    #[serde(default)]
+    pub tee_quote: Option<TeeQuote>,
 }
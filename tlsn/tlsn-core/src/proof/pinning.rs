@@ -0,0 +1,249 @@
+//! Certificate pinning for server identity verification.
+//!
+//! Pins restrict which certificates are accepted for a server beyond chaining to a trusted root,
+//! e.g. to the keys of a private CA or of the server itself. A pin is the SHA-256 hash of the
+//! DER encoded `SubjectPublicKeyInfo` of a certificate, as used by HTTP public key pinning, so
+//! pins survive certificate renewals which keep the same key. See [`spki_pin`] to compute one.
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+use tls_core::{
+    dns::ServerName,
+    key::Certificate,
+    msgs::{enums::SignatureScheme, handshake::DigitallySignedStruct},
+    verify::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    Error,
+};
+use web_time::SystemTime;
+
+/// Per-server pins on the public keys of server certificates.
+///
+/// The certificate chain of a pinned server is only accepted if at least one of its
+/// certificates has a pinned key. Servers without pins are not restricted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CertPins(HashMap<String, Vec<[u8; 32]>>);
+
+impl CertPins {
+    /// Creates an empty set of pins.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pins a public key for a server.
+    ///
+    /// Pinning several keys for the same server accepts a chain containing any of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `server_name` - The DNS name of the server.
+    /// * `pin` - The SHA-256 hash of the DER encoded `SubjectPublicKeyInfo`, see [`spki_pin`].
+    pub fn pin(mut self, server_name: impl Into<String>, pin: [u8; 32]) -> Self {
+        let mut server_name = server_name.into();
+        server_name.make_ascii_lowercase();
+        self.0.entry(server_name).or_default().push(pin);
+        self
+    }
+
+    /// Returns the pins of a server, if it is pinned.
+    pub fn get(&self, server_name: &str) -> Option<&[[u8; 32]]> {
+        self.0
+            .get(&server_name.to_ascii_lowercase())
+            .map(Vec::as_slice)
+    }
+
+    /// Returns whether no server is pinned.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks a certificate chain against the pins of a server.
+    fn check(
+        &self,
+        server_name: &str,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+    ) -> Result<(), Error> {
+        let Some(pins) = self.get(server_name) else {
+            return Ok(());
+        };
+
+        let pinned = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(|cert| spki_pin(&cert.0))
+            .any(|pin| pins.contains(&pin));
+
+        if pinned {
+            Ok(())
+        } else {
+            Err(Error::InvalidCertificateData(format!(
+                "certificate chain does not match the pins of {server_name}"
+            )))
+        }
+    }
+}
+
+/// A [`ServerCertVerifier`] which checks certificate pins in addition to the checks of another
+/// verifier, e.g. a [`WebPkiVerifier`](tls_core::verify::WebPkiVerifier) with a custom root
+/// store.
+#[derive(Debug)]
+pub struct PinnedCertVerifier<'a, V> {
+    inner: &'a V,
+    pins: &'a CertPins,
+}
+
+impl<'a, V> PinnedCertVerifier<'a, V> {
+    /// Creates a verifier which checks `pins` after `inner` accepted the certificate chain.
+    pub fn new(inner: &'a V, pins: &'a CertPins) -> Self {
+        Self { inner, pins }
+    }
+}
+
+impl<V: ServerCertVerifier> ServerCertVerifier for PinnedCertVerifier<'_, V> {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut (dyn Iterator<Item = &[u8]> + Send),
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )?;
+
+        if let ServerName::DnsName(dns_name) = server_name {
+            self.pins
+                .check(dns_name.as_ref(), end_entity, intermediates)?;
+        }
+
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+
+    fn request_scts(&self) -> bool {
+        self.inner.request_scts()
+    }
+}
+
+/// Computes the pin of a certificate, the SHA-256 hash of its DER encoded
+/// `SubjectPublicKeyInfo`.
+///
+/// Returns `None` if the certificate is not valid DER.
+pub fn spki_pin(cert_der: &[u8]) -> Option<[u8; 32]> {
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signatureValue }
+    let (cert, _) = der_element(cert_der, 0x30)?;
+    let (tbs, _) = der_element(der_contents(cert)?, 0x30)?;
+
+    // TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber, signature, issuer,
+    //     validity, subject, subjectPublicKeyInfo, ... }
+    let mut fields = der_contents(tbs)?;
+    if fields.first() == Some(&0xa0) {
+        fields = der_element(fields, 0xa0)?.1;
+    }
+    for tag in [0x02, 0x30, 0x30, 0x30, 0x30] {
+        fields = der_element(fields, tag)?.1;
+    }
+    let (spki, _) = der_element(fields, 0x30)?;
+
+    Some(Sha256::digest(spki).into())
+}
+
+/// Splits the DER element with the expected tag off the front of `data`, returning the whole
+/// element and the remaining data.
+fn der_element(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if *data.first()? != tag {
+        return None;
+    }
+
+    let (header_len, len) = der_length(data.get(1..)?)?;
+    let end = (1 + header_len).checked_add(len)?;
+    if end > data.len() {
+        return None;
+    }
+
+    Some(data.split_at(end))
+}
+
+/// Returns the contents of a DER element.
+fn der_contents(element: &[u8]) -> Option<&[u8]> {
+    let (header_len, _) = der_length(element.get(1..)?)?;
+    element.get(1 + header_len..)
+}
+
+/// Decodes a DER length, returning the number of bytes it occupies and its value.
+fn der_length(data: &[u8]) -> Option<(usize, usize)> {
+    let first = *data.first()?;
+    if first < 0x80 {
+        return Some((1, first as usize));
+    }
+
+    let n = (first & 0x7f) as usize;
+    if n == 0 || n > std::mem::size_of::<usize>() {
+        return None;
+    }
+
+    let len = data
+        .get(1..1 + n)?
+        .iter()
+        .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+
+    Some((1 + n, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fixtures::cert::{appliedzkp, TestData};
+
+    #[test]
+    fn test_spki_pin() {
+        let data = appliedzkp();
+
+        let ee = spki_pin(&data.ee.0).unwrap();
+        let inter = spki_pin(&data.inter.0).unwrap();
+        assert_ne!(ee, inter);
+
+        assert!(spki_pin(b"not a certificate").is_none());
+    }
+
+    #[test]
+    fn test_cert_pins() {
+        let TestData { ee, inter, .. } = appliedzkp();
+        let pin = spki_pin(&inter.0).unwrap();
+        let pins = CertPins::new().pin("Example.com", pin);
+
+        assert!(pins.check("example.com", &ee, &[]).is_err());
+        // Servers without pins are not restricted.
+        assert!(pins.check("other.com", &ee, &[]).is_ok());
+        assert!(pins.check("example.com", &ee, &[inter]).is_ok());
+    }
+}
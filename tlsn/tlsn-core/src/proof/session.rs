@@ -2,17 +2,22 @@ use web_time::{Duration, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
-use mpz_core::{commit::Decommitment, serialize::CanonicalSerialize};
+use mpz_core::commit::Decommitment;
 use tls_core::{
     anchors::{OwnedTrustAnchor, RootCertStore},
     dns::ServerName as TlsServerName,
     handshake::HandshakeData,
+    ocsp::{verify_ocsp_response, CertStatus},
     verify::{ServerCertVerifier, WebPkiVerifier},
 };
 
 use crate::{
+    proof::{HandshakeItem, HandshakeItemKind},
+    revocation::{RevocationList, ValidityError, ValidityPolicy},
     session::SessionHeader,
     signature::{Signature, SignatureVerifyError},
+    timestamp::{attestation_digest, TimestampAuthority, TimestampError, TimestampToken},
+    trust::TrustStore,
     HandshakeSummary, NotaryPublicKey, ServerName,
 };
 
@@ -35,6 +40,24 @@ pub enum SessionProofError {
     /// Invalid server certificate
     #[error("server certificate verification failed: {0}")]
     InvalidServerCertificate(String),
+    /// The session is not signed by a key trusted at the time of notarization
+    #[error("session is not signed by a notary key trusted at the time of notarization")]
+    UntrustedNotary,
+    /// Session proof is missing a timestamp token
+    #[error("session proof is missing a timestamp token")]
+    MissingTimestamp,
+    /// Invalid timestamp token
+    #[error(transparent)]
+    InvalidTimestamp(#[from] TimestampError),
+    /// The session has expired or was revoked
+    #[error(transparent)]
+    Invalid(#[from] ValidityError),
+    /// The server did not staple an OCSP response to its certificate
+    #[error("server did not staple an OCSP response")]
+    MissingOcspResponse,
+    /// The stapled OCSP response is invalid
+    #[error("OCSP response verification failed: {0}")]
+    InvalidOcspResponse(String),
 }
 
 /// A session proof which is created from a [crate::session::NotarizedSession]
@@ -48,6 +71,8 @@ pub struct SessionProof {
     pub signature: Option<Signature>,
     /// Information about the server
     pub session_info: SessionInfo,
+    /// An RFC 3161 timestamp token over the signed header, if the prover obtained one
+    pub timestamp: Option<TimestampToken>,
 }
 
 impl SessionProof {
@@ -68,13 +93,249 @@ impl SessionProof {
             .as_ref()
             .ok_or(SessionProofError::MissingNotarySignature)?;
 
-        signature.verify(&self.header.to_bytes(), notary_public_key)?;
+        self.header.verify_signature(signature, notary_public_key)?;
         self.session_info
             .verify(self.header.handshake_summary(), cert_verifier)?;
 
+        // A timestamp token, if present, must be bound to this session
+        if let Some(timestamp) = &self.timestamp {
+            timestamp.verify_imprint(&attestation_digest(&self.header, signature))?;
+        }
+
+        Ok(())
+    }
+
+    /// Verify the session proof against a trust store.
+    ///
+    /// The session must be signed by a key which the trust store trusted at the time in the
+    /// session header. Returns the key which signed the session.
+    ///
+    /// The header time is chosen by the signer, so this does not protect against a retired key
+    /// which was leaked: its holder can date a header within the key's validity window. Use
+    /// [`SessionProof::verify_with_trust_store_timestamped`] to select keys by an independently
+    /// asserted time instead, or revoke the key, see [`crate::revocation`].
+    ///
+    /// # Arguments
+    ///
+    /// * `trust_store` - The trusted notary keys.
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify_with_trust_store(
+        &self,
+        trust_store: &TrustStore,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<NotaryPublicKey, SessionProofError> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or(SessionProofError::MissingNotarySignature)?;
+
+        let key = trust_store
+            .keys_at(self.header.time())
+            .find(|key| {
+                self.header
+                    .verify_signature(signature, (*key).clone())
+                    .is_ok()
+            })
+            .ok_or(SessionProofError::UntrustedNotary)?;
+
+        self.verify(key.clone(), cert_verifier)?;
+
+        Ok(key.clone())
+    }
+
+    /// Verify the session proof against a trust store, selecting the trusted keys by the time
+    /// asserted by the timestamp token of the session rather than by the header time.
+    ///
+    /// Returns the key which signed the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `trust_store` - The trusted notary keys.
+    /// * `authority` - A verifier for the signature of the timestamp authority.
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify_with_trust_store_timestamped(
+        &self,
+        trust_store: &TrustStore,
+        authority: &impl TimestampAuthority,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<NotaryPublicKey, SessionProofError> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or(SessionProofError::MissingNotarySignature)?;
+        let timestamped_at = self.verify_timestamp(authority)?;
+
+        let key = trust_store
+            .keys_at(timestamped_at)
+            .find(|key| {
+                self.header
+                    .verify_signature(signature, (*key).clone())
+                    .is_ok()
+            })
+            .ok_or(SessionProofError::UntrustedNotary)?;
+
+        self.verify(key.clone(), cert_verifier)?;
+
+        Ok(key.clone())
+    }
+
+    /// Verifies the identity of the server.
+    ///
+    /// Validates the server certificate chain, checks that the certificate is valid for the
+    /// claimed server name, and that the server signed the ephemeral key which the notary
+    /// attested to. The certificate chain is part of the handshake data, which must match the
+    /// commitment in the session header.
+    ///
+    /// Returns the verified server name.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify_server_identity(
+        &self,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<&ServerName, SessionProofError> {
+        let handshake_summary = self.header.handshake_summary();
+
+        self.session_info.verify(handshake_summary, cert_verifier)?;
+
+        handshake_summary
+            .verify(&self.session_info.handshake_decommitment)
+            .map_err(|e| SessionProofError::InvalidHandshake(e.to_string()))?;
+
+        Ok(&self.session_info.server_name)
+    }
+
+    /// Verifies the identity of the server and returns the handshake items of the given kinds,
+    /// derived from the handshake data, see [`SessionProof::verify_server_identity`].
+    ///
+    /// # Arguments
+    ///
+    /// * `kinds` - The kinds of the items to return.
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify_handshake_items(
+        &self,
+        kinds: &[HandshakeItemKind],
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<Vec<HandshakeItem>, SessionProofError> {
+        let server_name = self.verify_server_identity(cert_verifier)?;
+        let handshake_data = self.session_info.handshake_decommitment.data();
+
+        kinds
+            .iter()
+            .map(|kind| {
+                HandshakeItem::derive(*kind, server_name, handshake_data).ok_or_else(|| {
+                    SessionProofError::InvalidHandshake(format!(
+                        "handshake does not contain a {kind:?} item"
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Verifies the identity of the server against the provided root store.
+    ///
+    /// See [`SessionProof::verify_server_identity`].
+    pub fn verify_server_identity_with_roots(
+        &self,
+        roots: RootCertStore,
+    ) -> Result<&ServerName, SessionProofError> {
+        self.verify_server_identity(&WebPkiVerifier::new(roots, None))
+    }
+
+    /// Returns the revocation status of the server certificate at the time of the session.
+    ///
+    /// See [`SessionInfo::revocation_status`].
+    pub fn verify_revocation(&self) -> Result<CertStatus, SessionProofError> {
+        self.session_info
+            .revocation_status(self.header.handshake_summary())
+    }
+
+    /// Checks that the session has not expired or been revoked.
+    ///
+    /// This does not verify the notary signature, see [`SessionProof::verify`].
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `now` - The current time, in seconds since the UNIX epoch.
+    /// * `policy` - The checks to enforce.
+    /// * `revocations` - Verified revocation lists to check against.
+    pub fn verify_validity(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+        now: u64,
+        policy: &ValidityPolicy,
+        revocations: &[RevocationList],
+    ) -> Result<(), SessionProofError> {
+        if self.signature.is_none() {
+            return Err(SessionProofError::MissingNotarySignature);
+        }
+
+        policy.check(&self.header, &notary_public_key.into(), now, revocations)?;
+
         Ok(())
     }
 
+    /// Checks the validity of this session proof, using the time asserted by its timestamp token
+    /// to decide whether a revoked notary key was still valid when the session was notarized.
+    ///
+    /// See [`ValidityPolicy::check_timestamped`].
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `now` - The current time, in seconds since the UNIX epoch.
+    /// * `policy` - The checks to enforce.
+    /// * `revocations` - Verified revocation lists to check against.
+    /// * `authority` - A verifier for the signature of the timestamp authority.
+    pub fn verify_validity_timestamped(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+        now: u64,
+        policy: &ValidityPolicy,
+        revocations: &[RevocationList],
+        authority: &impl TimestampAuthority,
+    ) -> Result<(), SessionProofError> {
+        let timestamped_at = self.verify_timestamp(authority)?;
+        if self.signature.is_none() {
+            return Err(SessionProofError::MissingNotarySignature);
+        }
+
+        policy.check_timestamped(
+            &self.header,
+            &notary_public_key.into(),
+            now,
+            timestamped_at,
+            revocations,
+        )?;
+
+        Ok(())
+    }
+
+    /// Verifies the timestamp token of this session proof.
+    ///
+    /// Returns the time asserted by the timestamp authority, in seconds since the UNIX epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - A verifier for the signature of the timestamp authority.
+    pub fn verify_timestamp(
+        &self,
+        authority: &impl TimestampAuthority,
+    ) -> Result<u64, SessionProofError> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or(SessionProofError::MissingNotarySignature)?;
+        let timestamp = self
+            .timestamp
+            .as_ref()
+            .ok_or(SessionProofError::MissingTimestamp)?;
+
+        Ok(timestamp.verify(&attestation_digest(&self.header, signature), authority)?)
+    }
+
     /// Verify the session proof using trust anchors from the `webpki-roots` crate.
     ///
     /// # Arguments
@@ -128,6 +389,43 @@ impl SessionInfo {
         Ok(())
     }
 
+    /// Returns the revocation status of the server certificate at the time of the session.
+    ///
+    /// The status is read from the OCSP response the server stapled during the handshake, which
+    /// is part of the handshake data committed to in the session header. This does not verify
+    /// the certificate chain, see [`SessionInfo::verify`].
+    pub fn revocation_status(
+        &self,
+        handshake_summary: &HandshakeSummary,
+    ) -> Result<CertStatus, SessionProofError> {
+        self.handshake_decommitment
+            .verify(handshake_summary.handshake_commitment())
+            .map_err(|e| SessionProofError::InvalidHandshake(e.to_string()))?;
+
+        let server_cert_details = self.handshake_decommitment.data().server_cert_details();
+        let ocsp_response = server_cert_details.ocsp_response();
+        if ocsp_response.is_empty() {
+            return Err(SessionProofError::MissingOcspResponse);
+        }
+
+        let (end_entity, issuer) = match server_cert_details.cert_chain() {
+            [end_entity, issuer, ..] => (end_entity, issuer),
+            _ => {
+                return Err(SessionProofError::InvalidOcspResponse(
+                    "the issuer certificate is missing from the chain".to_string(),
+                ))
+            }
+        };
+
+        verify_ocsp_response(
+            ocsp_response,
+            end_entity,
+            issuer,
+            UNIX_EPOCH + Duration::from_secs(handshake_summary.time()),
+        )
+        .map_err(|e| SessionProofError::InvalidOcspResponse(e.to_string()))
+    }
+
     /// Verify the session info using trust anchors from the `webpki-roots` crate.
     ///
     /// # Arguments
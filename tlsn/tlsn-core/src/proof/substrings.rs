@@ -5,15 +5,18 @@ use crate::{
         Commitment, CommitmentId, CommitmentInfo, CommitmentKind, CommitmentOpening,
         TranscriptCommitments,
     },
+    hash::{HashAlgorithm, HashAlgorithmError},
+    kzg::{KzgCommitment, KzgError, KzgProof},
     merkle::MerkleProof,
     transcript::get_value_ids,
     Direction, EncodingId, RedactedTranscript, SessionHeader, Transcript, TranscriptSlice,
     MAX_TOTAL_COMMITTED_DATA,
 };
 use mpz_circuits::types::ValueType;
+use mpz_core::hash::Hash;
 use mpz_garble_core::Encoder;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 use utils::range::{RangeDisjoint, RangeSet, RangeUnion, ToRangeSet};
 
 /// An error for [`SubstringsProofBuilder`]
@@ -32,6 +35,9 @@ pub enum SubstringsProofBuilderError {
     /// Attempted to add a commitment with a duplicate id.
     #[error("commitment with id {0:?} already exists")]
     DuplicateCommitmentId(CommitmentId),
+    /// The KZG inclusion proof could not be created.
+    #[error(transparent)]
+    Kzg(#[from] KzgError),
 }
 
 /// A builder for [`SubstringsProof`]
@@ -112,12 +118,6 @@ impl<'a> SubstringsProofBuilder<'a> {
             .get_info(&id)
             .expect("info exists if commitment exists");
 
-        #[allow(irrefutable_let_patterns)]
-        let Commitment::Blake3(commitment) = commitment
-        else {
-            return Err(SubstringsProofBuilderError::InvalidCommitmentType(id));
-        };
-
         let transcript = match info.direction() {
             Direction::Sent => self.transcript_tx,
             Direction::Received => self.transcript_rx,
@@ -125,12 +125,13 @@ impl<'a> SubstringsProofBuilder<'a> {
 
         let data = transcript.get_bytes_in_ranges(info.ranges());
 
+        let opening: CommitmentOpening = match commitment {
+            Commitment::Blake3(commitment) => commitment.open(data).into(),
+            Commitment::Hash(commitment) => commitment.open(data).into(),
+        };
+
         // add commitment to openings and return an error if it is already present
-        if self
-            .openings
-            .insert(id, (info.clone(), commitment.open(data).into()))
-            .is_some()
-        {
+        if self.openings.insert(id, (info.clone(), opening)).is_some() {
             return Err(SubstringsProofBuilderError::DuplicateCommitmentId(id));
         }
 
@@ -139,25 +140,52 @@ impl<'a> SubstringsProofBuilder<'a> {
 
     /// Builds the [`SubstringsProof`]
     pub fn build(self) -> Result<SubstringsProof, SubstringsProofBuilderError> {
-        let Self {
-            commitments,
-            openings,
-            ..
-        } = self;
+        let indices = self.indices();
+        let inclusion_proof = self.commitments.merkle_tree().proof(&indices);
 
-        let mut indices = openings
-            .keys()
-            .map(|id| id.to_inner() as usize)
-            .collect::<Vec<_>>();
-        indices.sort();
+        Ok(SubstringsProof {
+            openings: self.openings,
+            inclusion_proof: InclusionProof::Merkle(inclusion_proof),
+        })
+    }
 
-        let inclusion_proof = commitments.merkle_tree().proof(&indices);
+    /// Builds a [`SubstringsProof`] which proves the inclusion of the opened commitments with a
+    /// KZG proof against [`SessionHeader::kzg_commitment`], instead of a Merkle proof.
+    ///
+    /// The KZG proof has the same size regardless of how many commitments are opened. The
+    /// session header must contain the KZG commitment, which the Prover sends during
+    /// notarization if it is configured with the same `params`.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The KZG parameters the commitment was created with.
+    #[cfg(feature = "kzg")]
+    pub fn build_kzg(
+        self,
+        params: &crate::kzg::KzgParams,
+    ) -> Result<SubstringsProof, SubstringsProofBuilderError> {
+        let indices = self.indices();
+        let inclusion_proof = self
+            .commitments
+            .kzg_vector(params)?
+            .proof(params, &indices)?;
 
         Ok(SubstringsProof {
-            openings,
-            inclusion_proof,
+            openings: self.openings,
+            inclusion_proof: InclusionProof::Kzg(inclusion_proof),
         })
     }
+
+    /// Returns the sorted indices of the opened commitments.
+    fn indices(&self) -> Vec<usize> {
+        let mut indices = self
+            .openings
+            .keys()
+            .map(|id| id.to_inner() as usize)
+            .collect::<Vec<_>>();
+        indices.sort();
+        indices
+    }
 }
 
 /// An error relating to [`SubstringsProof`]
@@ -179,26 +207,71 @@ pub enum SubstringsProofError {
     /// The proof contains an invalid commitment opening.
     #[error("invalid opening for commitment id: {0:?}")]
     InvalidOpening(CommitmentId),
+    /// The opening uses a hash algorithm other than the one in the session header.
+    #[error("opening {id:?} uses hash algorithm {actual:?}, expected {expected:?}")]
+    HashAlgorithmMismatch {
+        /// The commitment id.
+        id: CommitmentId,
+        /// The hash algorithm in the session header.
+        expected: HashAlgorithm,
+        /// The hash algorithm of the opening.
+        actual: HashAlgorithm,
+    },
+    /// The hash algorithm is not supported.
+    #[error(transparent)]
+    HashAlgorithm(#[from] HashAlgorithmError),
     /// The proof contains an invalid inclusion proof.
     #[error("invalid inclusion proof: {0}")]
     InvalidInclusionProof(String),
+    /// The proof contains a KZG inclusion proof, which is verified with
+    /// [`SubstringsProof::verify_kzg`].
+    #[error("KZG parameters are required to verify a KZG inclusion proof")]
+    KzgParamsRequired,
+    /// The proof contains a KZG inclusion proof, but the session header has no KZG commitment.
+    #[error("session header does not contain a KZG commitment")]
+    MissingKzgCommitment,
 }
 
 /// A substring proof using commitments
 ///
 /// This substring proof contains the commitment openings and a proof
-/// that the corresponding commitments are present in the merkle tree.
+/// that the corresponding commitments are present in the merkle tree, or in the KZG vector
+/// commitment of the session header.
 #[derive(Serialize, Deserialize)]
 pub struct SubstringsProof {
-    openings: HashMap<CommitmentId, (CommitmentInfo, CommitmentOpening)>,
-    inclusion_proof: MerkleProof,
+    pub(crate) openings: HashMap<CommitmentId, (CommitmentInfo, CommitmentOpening)>,
+    pub(crate) inclusion_proof: InclusionProof,
+}
+
+/// Checks a KZG inclusion proof of the leaves at the given indices against a commitment.
+type VerifyKzg<'a> =
+    &'a dyn Fn(&KzgProof, &KzgCommitment, &[usize], &[Hash]) -> Result<(), SubstringsProofError>;
+
+/// A proof that the opened commitments were committed to in the session header.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum InclusionProof {
+    /// A proof against [`SessionHeader::merkle_root`].
+    Merkle(MerkleProof),
+    /// A proof against [`SessionHeader::kzg_commitment`].
+    Kzg(KzgProof),
 }
 
 opaque_debug::implement!(SubstringsProof);
 
 impl SubstringsProof {
+    /// Returns the commitments opened by this proof, with the direction and ranges of the data
+    /// each of them discloses.
+    ///
+    /// This information is only trustworthy once the proof has been verified.
+    pub fn openings(&self) -> impl Iterator<Item = (CommitmentId, &CommitmentInfo)> {
+        self.openings.iter().map(|(id, (info, _))| (*id, info))
+    }
+
     /// Verifies this proof and, if successful, returns the redacted sent and received transcripts.
     ///
+    /// Returns [`SubstringsProofError::KzgParamsRequired`] if the proof was built with
+    /// [`SubstringsProofBuilder::build_kzg`].
+    ///
     /// # Arguments
     ///
     /// * `header` - The session header.
@@ -206,6 +279,170 @@ impl SubstringsProof {
         self,
         header: &SessionHeader,
     ) -> Result<(RedactedTranscript, RedactedTranscript), SubstringsProofError> {
+        self.verify_with(header, &|_, _, _, _| {
+            Err(SubstringsProofError::KzgParamsRequired)
+        })
+    }
+
+    /// Verifies this proof, which may contain a KZG inclusion proof, and if successful returns the
+    /// redacted sent and received transcripts.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `params` - The KZG parameters the commitment in the header was created with.
+    #[cfg(feature = "kzg")]
+    pub fn verify_kzg(
+        self,
+        header: &SessionHeader,
+        params: &crate::kzg::KzgParams,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), SubstringsProofError> {
+        self.verify_with(header, &|proof, commitment, indices, hashes| {
+            proof
+                .verify(params, commitment, indices, hashes)
+                .map_err(|e| SubstringsProofError::InvalidInclusionProof(e.to_string()))
+        })
+    }
+
+    /// Verifies this proof, checking KZG inclusion proofs with `verify_kzg`.
+    fn verify_with(
+        self,
+        header: &SessionHeader,
+        verify_kzg: VerifyKzg<'_>,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), SubstringsProofError> {
+        let mut sent = vec![0u8; header.sent_len()];
+        let mut recv = vec![0u8; header.recv_len()];
+        let mut sent_ranges = RangeSet::default();
+        let mut recv_ranges = RangeSet::default();
+        for (direction, ranges, mut data) in self.verify_openings(header, verify_kzg)? {
+            let dest = match direction {
+                Direction::Sent => {
+                    sent_ranges = sent_ranges.union(&ranges);
+                    &mut sent
+                }
+                Direction::Received => {
+                    recv_ranges = recv_ranges.union(&ranges);
+                    &mut recv
+                }
+            };
+
+            // Iterate over the ranges backwards, copying the data from the opening
+            // then truncating it.
+            for range in ranges.iter_ranges().rev() {
+                let start = data.len() - range.len();
+                dest[range].copy_from_slice(&data[start..]);
+                data.truncate(start);
+            }
+        }
+
+        // Iterate over the unioned ranges and create TranscriptSlices for each.
+        // This ensures that the slices are sorted and disjoint.
+        let sent_slices = sent_ranges
+            .iter_ranges()
+            .map(|range| TranscriptSlice::new(range.clone(), sent[range].to_vec()))
+            .collect();
+        let recv_slices = recv_ranges
+            .iter_ranges()
+            .map(|range| TranscriptSlice::new(range.clone(), recv[range].to_vec()))
+            .collect();
+
+        Ok((
+            RedactedTranscript::new(header.sent_len(), sent_slices),
+            RedactedTranscript::new(header.recv_len(), recv_slices),
+        ))
+    }
+
+    /// Verifies this proof and, if successful, returns the disclosed slices of the transcripts.
+    ///
+    /// Unlike [`SubstringsProof::verify`], this does not assemble the full-length sent and
+    /// received transcripts, so the redacted parts of the transcripts take no memory. The
+    /// disclosed data is moved out of the proof and handed out slice by slice, sorted by
+    /// direction and position, and the data of each opening is released once it has been
+    /// consumed.
+    ///
+    /// Memory use is not bounded: the proof, and so all of the disclosed data, is in memory before
+    /// it is verified, and every opening is hashed before the first slice is yielded. This avoids
+    /// a second copy of the disclosed data, but does not allow verifying a proof larger than the
+    /// available memory.
+    ///
+    /// Returns [`SubstringsProofError::KzgParamsRequired`] if the proof was built with
+    /// [`SubstringsProofBuilder::build_kzg`].
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    pub fn verify_streaming(
+        self,
+        header: &SessionHeader,
+    ) -> Result<DisclosedSlices, SubstringsProofError> {
+        self.verify_streaming_with(header, &|_, _, _, _| {
+            Err(SubstringsProofError::KzgParamsRequired)
+        })
+    }
+
+    /// Like [`SubstringsProof::verify_streaming`], for proofs which may contain a KZG inclusion
+    /// proof.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `params` - The KZG parameters the commitment in the header was created with.
+    #[cfg(feature = "kzg")]
+    pub fn verify_streaming_kzg(
+        self,
+        header: &SessionHeader,
+        params: &crate::kzg::KzgParams,
+    ) -> Result<DisclosedSlices, SubstringsProofError> {
+        self.verify_streaming_with(header, &|proof, commitment, indices, hashes| {
+            proof
+                .verify(params, commitment, indices, hashes)
+                .map_err(|e| SubstringsProofError::InvalidInclusionProof(e.to_string()))
+        })
+    }
+
+    /// Verifies this proof for streaming, checking KZG inclusion proofs with `verify_kzg`.
+    fn verify_streaming_with(
+        self,
+        header: &SessionHeader,
+        verify_kzg: VerifyKzg<'_>,
+    ) -> Result<DisclosedSlices, SubstringsProofError> {
+        let openings = self.verify_openings(header, verify_kzg)?;
+
+        let mut slices = Vec::new();
+        let mut data = Vec::with_capacity(openings.len());
+        for (index, (direction, ranges, opening_data)) in openings.into_iter().enumerate() {
+            let mut offset = 0;
+            for range in ranges.iter_ranges() {
+                let len = range.len();
+                slices.push(SliceLocation {
+                    direction,
+                    range,
+                    opening: index,
+                    offset,
+                });
+                offset += len;
+            }
+            data.push((ranges.iter_ranges().count(), opening_data));
+        }
+
+        // Openings are disjoint, so this orders the slices by their position in the transcript.
+        slices.sort_by_key(|slice| (slice.direction == Direction::Received, slice.range.start));
+
+        Ok(DisclosedSlices {
+            slices: slices.into_iter(),
+            data,
+        })
+    }
+
+    /// Checks every opening against the session header and the inclusion proof, and returns the
+    /// direction, ranges and data of each.
+    ///
+    /// KZG inclusion proofs are checked with `verify_kzg`.
+    fn verify_openings(
+        self,
+        header: &SessionHeader,
+        verify_kzg: VerifyKzg<'_>,
+    ) -> Result<Vec<(Direction, RangeSet<usize>, Vec<u8>)>, SubstringsProofError> {
         let Self {
             openings,
             inclusion_proof,
@@ -213,16 +450,25 @@ impl SubstringsProof {
 
         let mut indices = Vec::with_capacity(openings.len());
         let mut expected_hashes = Vec::with_capacity(openings.len());
-        let mut sent = vec![0u8; header.sent_len()];
-        let mut recv = vec![0u8; header.recv_len()];
         let mut sent_ranges = RangeSet::default();
         let mut recv_ranges = RangeSet::default();
         let mut total_opened = 0u128;
+        let mut verified = Vec::with_capacity(openings.len());
         for (id, (info, opening)) in openings {
             let CommitmentInfo {
                 ranges, direction, ..
             } = info;
 
+            // Make sure the opening uses the hash algorithm recorded by the Notary.
+            let alg = opening.kind().hash_algorithm();
+            if alg != header.hash_algorithm() {
+                return Err(SubstringsProofError::HashAlgorithmMismatch {
+                    id,
+                    expected: header.hash_algorithm(),
+                    actual: alg,
+                });
+            }
+
             let opened_len = ranges.len();
 
             // Make sure the amount of data being proved is bounded.
@@ -277,50 +523,89 @@ impl SubstringsProof {
             // Compute the expected hash of the commitment to make sure it is
             // present in the merkle tree.
             indices.push(id.to_inner() as usize);
-            expected_hashes.push(opening.recover(&encodings).hash());
+            expected_hashes.push(opening.recover(&encodings)?.hash());
 
             // Make sure the length of data from the opening matches the commitment.
-            let mut data = opening.into_data();
+            let data = opening.into_data();
             if data.len() != ranges.len() {
                 return Err(SubstringsProofError::InvalidOpening(id));
             }
 
-            let dest = match direction {
-                Direction::Sent => &mut sent,
-                Direction::Received => &mut recv,
-            };
-
-            // Iterate over the ranges backwards, copying the data from the opening
-            // then truncating it.
-            for range in ranges.iter_ranges().rev() {
-                let start = data.len() - range.len();
-                dest[range].copy_from_slice(&data[start..]);
-                data.truncate(start);
-            }
+            verified.push((direction, ranges, data));
         }
 
-        // Verify that the expected hashes are present in the merkle tree.
+        // Verify that the expected hashes are present in the merkle tree, or in the KZG vector.
         //
         // This proves the Prover committed to the purported data prior to the encoder
         // seed being revealed.
-        inclusion_proof
-            .verify(header.merkle_root(), &indices, &expected_hashes)
-            .map_err(|e| SubstringsProofError::InvalidInclusionProof(e.to_string()))?;
+        match inclusion_proof {
+            InclusionProof::Merkle(proof) => proof
+                .verify(
+                    header.hash_algorithm(),
+                    header.merkle_root(),
+                    &indices,
+                    &expected_hashes,
+                )
+                .map_err(|e| SubstringsProofError::InvalidInclusionProof(e.to_string()))?,
+            InclusionProof::Kzg(proof) => {
+                let commitment = header
+                    .kzg_commitment()
+                    .ok_or(SubstringsProofError::MissingKzgCommitment)?;
+                verify_kzg(&proof, commitment, &indices, &expected_hashes)?
+            }
+        }
 
-        // Iterate over the unioned ranges and create TranscriptSlices for each.
-        // This ensures that the slices are sorted and disjoint.
-        let sent_slices = sent_ranges
-            .iter_ranges()
-            .map(|range| TranscriptSlice::new(range.clone(), sent[range].to_vec()))
-            .collect();
-        let recv_slices = recv_ranges
-            .iter_ranges()
-            .map(|range| TranscriptSlice::new(range.clone(), recv[range].to_vec()))
-            .collect();
+        Ok(verified)
+    }
+}
 
-        Ok((
-            RedactedTranscript::new(header.sent_len(), sent_slices),
-            RedactedTranscript::new(header.recv_len(), recv_slices),
-        ))
+/// The location of a disclosed slice within the verified openings.
+struct SliceLocation {
+    direction: Direction,
+    range: Range<usize>,
+    opening: usize,
+    offset: usize,
+}
+
+/// The disclosed slices of a verified [`SubstringsProof`], see
+/// [`SubstringsProof::verify_streaming`].
+///
+/// Yields the slices of the sent transcript followed by those of the received transcript, each
+/// sorted by position. Slices are disjoint, but adjacent slices are not merged. The data of an
+/// opening is released as soon as its last slice has been yielded.
+pub struct DisclosedSlices {
+    slices: std::vec::IntoIter<SliceLocation>,
+    /// The number of slices left to yield and the data of each opening.
+    data: Vec<(usize, Vec<u8>)>,
+}
+
+opaque_debug::implement!(DisclosedSlices);
+
+impl Iterator for DisclosedSlices {
+    type Item = (Direction, TranscriptSlice);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let SliceLocation {
+            direction,
+            range,
+            opening,
+            offset,
+        } = self.slices.next()?;
+
+        let (remaining, data) = &mut self.data[opening];
+        let slice = data[offset..offset + range.len()].to_vec();
+
+        *remaining -= 1;
+        if *remaining == 0 {
+            *data = Vec::new();
+        }
+
+        Some((direction, TranscriptSlice::new(range, slice)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.slices.size_hint()
     }
 }
+
+impl ExactSizeIterator for DisclosedSlices {}
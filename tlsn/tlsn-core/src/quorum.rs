@@ -0,0 +1,391 @@
+//! Threshold verification of sessions attested by multiple notaries.
+//!
+//! Trusting a single Notary means trusting it not to collude with the Prover. A verifier can
+//! instead require that a session header is signed by at least `m` of `n` trusted notaries, see
+//! [`QuorumPolicy`]. Besides the signature of the session proof, a presentation carries the
+//! signatures of further notaries over the same header as [`Cosignature`]s, either one per notary
+//! or [aggregated](crate::Signature::aggregate) from several BLS signatures.
+//!
+//! An aggregated signature is only as strong as the proofs of possession of the keys it is
+//! verified with: otherwise a key chosen as `x·G` minus the keys of honest notaries lets its
+//! owner forge their signatures. The policy therefore requires the proof of possession of every
+//! trusted BLS key, and only accepts aggregated signatures whose signers are all trusted.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    signature::{NotaryPublicKey, Signature, SignatureVerifyError},
+    BlsPublicKey, BlsSignature, SessionHeader,
+};
+
+/// An error that can occur while checking the notaries of a session against a [`QuorumPolicy`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum QuorumError {
+    /// The threshold is zero, or larger than the number of trusted notaries.
+    #[error("threshold {threshold} is invalid for {keys} trusted notaries")]
+    InvalidThreshold {
+        /// The threshold of the policy.
+        threshold: usize,
+        /// The number of trusted notaries.
+        keys: usize,
+    },
+    /// A cosignature is invalid.
+    #[error("cosignature {index} is invalid: {source}")]
+    InvalidCosignature {
+        /// The index of the cosignature in the presentation.
+        index: usize,
+        /// The reason the signature is invalid.
+        source: SignatureVerifyError,
+    },
+    /// A BLS key was trusted without its proof of possession.
+    #[error("BLS keys must be trusted with their proof of possession")]
+    MissingProofOfPossession,
+    /// The proof of possession of a BLS key is invalid.
+    #[error("proof of possession of a BLS key is invalid")]
+    InvalidProofOfPossession,
+    /// An aggregated cosignature has a signer which is not a trusted notary, whose proof of
+    /// possession is therefore unknown.
+    #[error("aggregated cosignature {index} has a signer which is not a trusted notary")]
+    UntrustedAggregateSigner {
+        /// The index of the cosignature in the presentation.
+        index: usize,
+    },
+    /// Fewer trusted notaries signed the session than required.
+    #[error("session is signed by {signers} trusted notaries, {threshold} are required")]
+    NotReached {
+        /// The number of distinct trusted notaries which signed the session.
+        signers: usize,
+        /// The threshold of the policy.
+        threshold: usize,
+    },
+}
+
+/// A signature over a session header by one or more notaries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cosignature {
+    /// The public keys of the notaries which contributed to the signature. A signature with
+    /// more than one signer is a BLS signature aggregated from the signature of each.
+    pub signers: Vec<NotaryPublicKey>,
+    /// The signature over the session header.
+    pub signature: Signature,
+}
+
+impl Cosignature {
+    /// Verifies the signature over the session header.
+    ///
+    /// Aggregated signatures are only sound if the proof of possession of every signer was
+    /// verified, which [`QuorumPolicy::check`] ensures.
+    pub(crate) fn verify(&self, header: &SessionHeader) -> Result<(), SignatureVerifyError> {
+        let msg = header.signing_message()?;
+        match self.signers.as_slice() {
+            [signer] => self.signature.verify(&msg, signer.clone()),
+            signers => self.signature.verify_aggregate(&msg, signers),
+        }
+    }
+}
+
+/// A policy requiring a session to be signed by at least `threshold` of the trusted notaries.
+///
+/// BLS keys are trusted together with their proof of possession, which is verified when they are
+/// added. For example, to accept sessions signed by at least 2 of 3 notaries:
+///
+/// ```
+/// # use tlsn_core::{quorum::{QuorumError, QuorumPolicy}, BlsSigningKey};
+/// # fn example() -> Result<(), QuorumError> {
+/// # let keys = [1u8, 2, 3].map(|seed| BlsSigningKey::from_ikm(&[seed; 32]).unwrap());
+/// let mut policy = QuorumPolicy::new(2);
+/// for key in &keys {
+///     // The notaries publish their public key with its proof of possession.
+///     policy = policy.with_bls_key(key.public_key(), &key.proof_of_possession())?;
+/// }
+/// # Ok(())
+/// # }
+/// # example().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuorumPolicy {
+    /// The public keys of the trusted notaries.
+    notary_keys: Vec<NotaryPublicKey>,
+    /// The minimum number of distinct trusted notaries which must sign a session.
+    threshold: usize,
+}
+
+impl QuorumPolicy {
+    /// Creates a policy requiring `threshold` distinct trusted notaries to sign a session, which
+    /// are added with [`QuorumPolicy::with_key`] and [`QuorumPolicy::with_bls_key`].
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            notary_keys: Vec::new(),
+            threshold,
+        }
+    }
+
+    /// Trusts the key of a notary.
+    ///
+    /// BLS keys must be added with [`QuorumPolicy::with_bls_key`] instead, as they can sign
+    /// aggregated signatures.
+    pub fn with_key(mut self, key: NotaryPublicKey) -> Result<Self, QuorumError> {
+        if matches!(key, NotaryPublicKey::Bls(_)) {
+            return Err(QuorumError::MissingProofOfPossession);
+        }
+        if !self.notary_keys.contains(&key) {
+            self.notary_keys.push(key);
+        }
+        Ok(self)
+    }
+
+    /// Trusts the BLS key of a notary, after verifying its proof of possession.
+    pub fn with_bls_key(
+        mut self,
+        key: BlsPublicKey,
+        proof_of_possession: &BlsSignature,
+    ) -> Result<Self, QuorumError> {
+        key.verify_proof_of_possession(proof_of_possession)
+            .map_err(|_| QuorumError::InvalidProofOfPossession)?;
+        let key = NotaryPublicKey::Bls(key);
+        if !self.notary_keys.contains(&key) {
+            self.notary_keys.push(key);
+        }
+        Ok(self)
+    }
+
+    /// Returns the public keys of the trusted notaries.
+    pub fn notary_keys(&self) -> &[NotaryPublicKey] {
+        &self.notary_keys
+    }
+
+    /// Returns the minimum number of distinct trusted notaries which must sign a session.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Checks that enough trusted notaries signed a session header, and returns their keys.
+    ///
+    /// The signature of the session proof counts for the trusted notary whose key it verifies
+    /// with. Cosignatures count for each of their signers which is trusted, and must be valid
+    /// even if some of their signers are not trusted. Aggregated cosignatures are rejected if
+    /// any of their signers is not trusted, as the proof of possession of its key is unknown.
+    /// Each notary is only counted once.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `signature` - The signature of the session proof, if any.
+    /// * `cosignatures` - The signatures of further notaries over the header.
+    pub fn check(
+        &self,
+        header: &SessionHeader,
+        signature: Option<&Signature>,
+        cosignatures: &[Cosignature],
+    ) -> Result<Vec<NotaryPublicKey>, QuorumError> {
+        if self.threshold == 0 || self.threshold > self.notary_keys.len() {
+            return Err(QuorumError::InvalidThreshold {
+                threshold: self.threshold,
+                keys: self.notary_keys.len(),
+            });
+        }
+
+        let mut signers: Vec<NotaryPublicKey> = Vec::new();
+        let mut count = |key: &NotaryPublicKey| {
+            if self.notary_keys.contains(key) && !signers.contains(key) {
+                signers.push(key.clone());
+            }
+        };
+
+        if let Some(signature) = signature {
+            if let Some(key) = self
+                .notary_keys
+                .iter()
+                .find(|key| header.verify_signature(signature, (*key).clone()).is_ok())
+            {
+                count(key);
+            }
+        }
+
+        for (index, cosignature) in cosignatures.iter().enumerate() {
+            if cosignature.signers.len() > 1
+                && cosignature
+                    .signers
+                    .iter()
+                    .any(|signer| !self.notary_keys.contains(signer))
+            {
+                return Err(QuorumError::UntrustedAggregateSigner { index });
+            }
+            cosignature
+                .verify(header)
+                .map_err(|source| QuorumError::InvalidCosignature { index, source })?;
+            cosignature.signers.iter().for_each(&mut count);
+        }
+
+        if signers.len() < self.threshold {
+            return Err(QuorumError::NotReached {
+                signers: signers.len(),
+                threshold: self.threshold,
+            });
+        }
+
+        Ok(signers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, merkle::MerkleRoot, BlsSigningKey};
+    use signature::Signer;
+
+    fn bls_key(seed: u8) -> BlsSigningKey {
+        BlsSigningKey::from_ikm(&[seed; 32]).unwrap()
+    }
+
+    fn cosign(header: &SessionHeader, keys: &[&BlsSigningKey]) -> Cosignature {
+        let signatures = keys
+            .iter()
+            .map(|key| Signature::from(key.sign(&header.signing_message().unwrap())))
+            .collect::<Vec<_>>();
+
+        Cosignature {
+            signers: keys.iter().map(|key| key.public_key().into()).collect(),
+            signature: Signature::aggregate(&signatures).unwrap(),
+        }
+    }
+
+    fn policy(threshold: usize, keys: &[&BlsSigningKey]) -> QuorumPolicy {
+        keys.iter()
+            .try_fold(QuorumPolicy::new(threshold), |policy, key| {
+                policy.with_bls_key(key.public_key(), &key.proof_of_possession())
+            })
+            .unwrap()
+    }
+
+    /// The order of the BLS12-381 scalar field, big-endian.
+    const SCALAR_ORDER: [u8; 32] = [
+        0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8,
+        0x05, 0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00,
+        0x00, 0x01,
+    ];
+
+    /// Computes `a - b` modulo the scalar field order, for big-endian scalars.
+    fn sub_scalar(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let diff = a[i] as i16 - b[i] as i16 - borrow;
+            borrow = (diff < 0) as i16;
+            out[i] = diff.rem_euclid(256) as u8;
+        }
+        if borrow == 1 {
+            let mut carry = 0u16;
+            for i in (0..32).rev() {
+                let sum = out[i] as u16 + SCALAR_ORDER[i] as u16 + carry;
+                carry = sum >> 8;
+                out[i] = sum as u8;
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_quorum_threshold() {
+        let header = fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10);
+        let (a, b, c) = (bls_key(1), bls_key(2), bls_key(3));
+        let policy = policy(2, &[&a, &b, &c]);
+
+        let primary = Signature::from(a.sign(&header.signing_message().unwrap()));
+        assert!(matches!(
+            policy.check(&header, Some(&primary), &[]),
+            Err(QuorumError::NotReached {
+                signers: 1,
+                threshold: 2
+            })
+        ));
+
+        // The same notary is only counted once.
+        assert!(matches!(
+            policy.check(&header, Some(&primary), &[cosign(&header, &[&a])]),
+            Err(QuorumError::NotReached { signers: 1, .. })
+        ));
+
+        let signers = policy
+            .check(&header, Some(&primary), &[cosign(&header, &[&b, &c])])
+            .unwrap();
+        assert_eq!(signers.len(), 3);
+
+        // Untrusted notaries do not count towards the threshold.
+        assert!(matches!(
+            policy.check(&header, None, &[cosign(&header, &[&bls_key(4)])]),
+            Err(QuorumError::NotReached { signers: 0, .. })
+        ));
+
+        // Aggregated signatures must only have trusted signers.
+        assert!(matches!(
+            policy.check(&header, None, &[cosign(&header, &[&a, &bls_key(4)])]),
+            Err(QuorumError::UntrustedAggregateSigner { index: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_quorum_rejects_rogue_key() {
+        let header = fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10);
+        let (a, b) = (bls_key(1), bls_key(2));
+        let policy = policy(2, &[&a, &b]);
+
+        // The attacker knows `x` and publishes `x·G - A - B`, so that the aggregate of the
+        // signer keys is `x·G` and a signature by `x` alone verifies for A and B.
+        let x = bls_key(4);
+        let rogue = BlsSigningKey::from_bytes(&sub_scalar(
+            sub_scalar(x.to_bytes(), a.to_bytes()),
+            b.to_bytes(),
+        ))
+        .unwrap();
+        let forged = Cosignature {
+            signers: vec![
+                a.public_key().into(),
+                b.public_key().into(),
+                rogue.public_key().into(),
+            ],
+            signature: Signature::from(x.sign(&header.signing_message().unwrap())),
+        };
+        assert!(forged.verify(&header).is_ok());
+
+        assert!(matches!(
+            policy.check(&header, None, &[forged]),
+            Err(QuorumError::UntrustedAggregateSigner { index: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_quorum_requires_proof_of_possession() {
+        let (a, b) = (bls_key(1), bls_key(2));
+
+        assert!(matches!(
+            QuorumPolicy::new(1).with_key(a.public_key().into()),
+            Err(QuorumError::MissingProofOfPossession)
+        ));
+        assert!(matches!(
+            QuorumPolicy::new(1).with_bls_key(a.public_key(), &b.proof_of_possession()),
+            Err(QuorumError::InvalidProofOfPossession)
+        ));
+    }
+
+    #[test]
+    fn test_quorum_rejects_invalid_cosignature() {
+        let header = fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10);
+        let other = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 10);
+        let (a, b) = (bls_key(1), bls_key(2));
+        assert!(matches!(
+            policy(2, &[&a, &b]).check(&header, None, &[cosign(&other, &[&a, &b])]),
+            Err(QuorumError::InvalidCosignature { index: 0, .. })
+        ));
+
+        let policy = policy(3, &[&a, &b]);
+        assert!(matches!(
+            policy.check(&header, None, &[cosign(&header, &[&a, &b])]),
+            Err(QuorumError::InvalidThreshold {
+                threshold: 3,
+                keys: 2
+            })
+        ));
+    }
+}
@@ -0,0 +1,548 @@
+//! Expiry and revocation of notarized sessions.
+//!
+//! A Notary can bound the lifetime of a session header by setting an expiry time before signing
+//! it, see [`SessionHeader::with_expiry`]. In addition, a Notary (or whoever operates it) can
+//! publish a signed [`RevocationList`] which revokes:
+//!
+//! - Notary keys, invalidating every session signed by the key.
+//! - Individual sessions, identified by the [digest of their header](session_digest).
+//!
+//! Which of these checks are enforced is configured with a [`ValidityPolicy`], which also bounds
+//! the age of a session, the tolerated clock skew between the verifier and the Notary, and the
+//! precision of the session time.
+//!
+//! # Revoked keys and backdating
+//!
+//! The time in a session header is chosen by whoever signs it, so a holder of a leaked key can
+//! sign a header dated before the key was revoked. The time a key was revoked is therefore only
+//! honoured for sessions whose time was asserted independently of the Notary, by a
+//! [timestamp token](crate::timestamp) verified with [`ValidityPolicy::check_timestamped`]. A
+//! session without such a token is rejected if its key was revoked, regardless of its header
+//! time.
+
+use mpz_core::serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    signature::{Signature, SignatureVerifyError},
+    time::{TimeError, TimeSource},
+    NotaryPublicKey, SessionHeader,
+};
+
+/// An error that can occur while checking the validity of a notarized session.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ValidityError {
+    /// The session has expired.
+    #[error("session expired at {expired_at}, current time is {now}")]
+    Expired {
+        /// The time the session expired, in seconds since the UNIX epoch.
+        expired_at: u64,
+        /// The current time, in seconds since the UNIX epoch.
+        now: u64,
+    },
+    /// The session is older than allowed by the policy.
+    #[error("session notarized at {notarized_at} is older than {max_age} seconds, current time is {now}")]
+    TooOld {
+        /// The time the session was notarized, in seconds since the UNIX epoch.
+        notarized_at: u64,
+        /// The maximum age allowed by the policy, in seconds.
+        max_age: u64,
+        /// The current time, in seconds since the UNIX epoch.
+        now: u64,
+    },
+    /// The session was notarized further in the future than the tolerated clock skew.
+    #[error("session notarized at {notarized_at} is in the future, current time is {now}")]
+    NotarizedInFuture {
+        /// The time the session was notarized, in seconds since the UNIX epoch.
+        notarized_at: u64,
+        /// The current time, in seconds since the UNIX epoch.
+        now: u64,
+    },
+    /// The session time is less precise than required by the policy.
+    #[error("session time has a precision of {precision} seconds, at most {required} is required")]
+    InsufficientTimePrecision {
+        /// The precision of the session time, in seconds.
+        precision: u64,
+        /// The precision required by the policy, in seconds.
+        required: u64,
+    },
+    /// The session does not have an expiry time but the policy requires one.
+    #[error("session does not have an expiry time")]
+    MissingExpiry,
+    /// The notary key was revoked.
+    #[error("notary key was revoked at {0}")]
+    KeyRevoked(u64),
+    /// The session was revoked.
+    #[error("session was revoked")]
+    SessionRevoked,
+    /// The revocation list is older than allowed by the policy.
+    #[error("revocation list issued at {0} is stale")]
+    StaleRevocationList(u64),
+    /// The revocation list signature is invalid.
+    #[error(transparent)]
+    InvalidRevocationList(#[from] SignatureVerifyError),
+    /// The current time could not be determined.
+    #[error(transparent)]
+    Time(#[from] TimeError),
+}
+
+/// A revoked notary key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokedKey {
+    /// The revoked key.
+    pub key: NotaryPublicKey,
+    /// The time the key was revoked, in seconds since the UNIX epoch.
+    ///
+    /// Sessions timestamped before this time remain valid, see the
+    /// [module level documentation](self).
+    pub revoked_at: u64,
+}
+
+/// A list of revoked notary keys and sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RevocationList {
+    /// Time the list was issued, in seconds since the UNIX epoch.
+    pub issued_at: u64,
+    /// Revoked notary keys.
+    pub keys: Vec<RevokedKey>,
+    /// Digests of the headers of revoked sessions, see [`session_digest`].
+    pub sessions: Vec<[u8; 32]>,
+}
+
+impl RevocationList {
+    /// Creates a new, empty, revocation list.
+    pub fn new(issued_at: u64) -> Self {
+        Self {
+            issued_at,
+            ..Default::default()
+        }
+    }
+
+    /// Revokes a notary key as of the given time.
+    pub fn revoke_key(&mut self, key: impl Into<NotaryPublicKey>, revoked_at: u64) -> &mut Self {
+        self.keys.push(RevokedKey {
+            key: key.into(),
+            revoked_at,
+        });
+        self
+    }
+
+    /// Revokes a single session.
+    pub fn revoke_session(&mut self, header: &SessionHeader) -> &mut Self {
+        self.sessions.push(session_digest(header));
+        self
+    }
+
+    /// Checks whether a session signed with the given key has been revoked.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `timestamped_at` - The time asserted by a verified timestamp token of the session, if
+    ///   any. Sessions signed with a revoked key are only valid if this is before the key was
+    ///   revoked.
+    pub fn check(
+        &self,
+        header: &SessionHeader,
+        notary_public_key: &NotaryPublicKey,
+        timestamped_at: Option<u64>,
+    ) -> Result<(), ValidityError> {
+        if let Some(revoked) = self
+            .keys
+            .iter()
+            .filter(|revoked| &revoked.key == notary_public_key)
+            .find(|revoked| timestamped_at.map_or(true, |time| time >= revoked.revoked_at))
+        {
+            return Err(ValidityError::KeyRevoked(revoked.revoked_at));
+        }
+
+        if self.sessions.contains(&session_digest(header)) {
+            return Err(ValidityError::SessionRevoked);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the digest which identifies a session in a [`RevocationList`].
+///
+/// The digest is the SHA-256 hash of the header bytes. Unlike the
+/// [attestation digest](crate::timestamp::attestation_digest) it does not cover the signature,
+/// as ECDSA signatures are malleable: anyone can derive a second valid signature over the same
+/// header, which would not match a revoked attestation digest.
+pub fn session_digest(header: &SessionHeader) -> [u8; 32] {
+    Sha256::digest(header.to_bytes()).into()
+}
+
+/// A [`RevocationList`] signed by its issuer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRevocationList {
+    /// The revocation list.
+    pub list: RevocationList,
+    /// The issuer's signature over the list.
+    pub signature: Signature,
+}
+
+impl SignedRevocationList {
+    /// Signs a revocation list.
+    pub fn sign<T>(list: RevocationList, signer: &impl signature::Signer<T>) -> Self
+    where
+        T: Into<Signature>,
+    {
+        let signature = signer.sign(&list.to_bytes()).into();
+
+        Self { list, signature }
+    }
+
+    /// Verifies the issuer's signature and returns the revocation list.
+    pub fn verify(
+        self,
+        issuer: impl Into<NotaryPublicKey>,
+    ) -> Result<RevocationList, ValidityError> {
+        self.signature.verify(&self.list.to_bytes(), issuer)?;
+
+        Ok(self.list)
+    }
+}
+
+/// Policy for checking the validity of a notarized session.
+///
+/// For example, to reject sessions older than 30 days while tolerating 5 minutes of clock skew:
+///
+/// ```
+/// # use tlsn_core::revocation::ValidityPolicy;
+/// let policy = ValidityPolicy {
+///     max_age: Some(30 * 24 * 60 * 60),
+///     max_clock_skew: Some(5 * 60),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct ValidityPolicy {
+    /// Reject sessions whose expiry time has passed.
+    pub enforce_expiry: bool,
+    /// Reject sessions which do not have an expiry time.
+    pub require_expiry: bool,
+    /// Reject sessions older than this many seconds, regardless of their expiry time.
+    pub max_age: Option<u64>,
+    /// Tolerated difference between the clocks of the verifier and the Notary, in seconds.
+    ///
+    /// If set, sessions notarized further in the future than this are rejected, and expiry times
+    /// and the maximum age are extended by it.
+    pub max_clock_skew: Option<u64>,
+    /// Reject sessions whose time was rounded to a coarser granularity than this many seconds,
+    /// see [`SessionHeader::with_time_precision`].
+    pub required_time_precision: Option<u64>,
+    /// Reject revocation lists older than this many seconds.
+    pub max_revocation_list_age: Option<u64>,
+}
+
+impl Default for ValidityPolicy {
+    fn default() -> Self {
+        Self {
+            enforce_expiry: true,
+            require_expiry: false,
+            max_age: None,
+            max_clock_skew: None,
+            required_time_precision: None,
+            max_revocation_list_age: None,
+        }
+    }
+}
+
+impl ValidityPolicy {
+    /// Checks the validity of a signed session header.
+    ///
+    /// Sessions signed with a revoked notary key are rejected regardless of their time, see the
+    /// [module level documentation](self).
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `now` - The current time, in seconds since the UNIX epoch.
+    /// * `revocations` - Verified revocation lists to check against.
+    pub fn check(
+        &self,
+        header: &SessionHeader,
+        notary_public_key: &NotaryPublicKey,
+        now: u64,
+        revocations: &[RevocationList],
+    ) -> Result<(), ValidityError> {
+        self.check_at(header, notary_public_key, now, None, revocations)
+    }
+
+    /// Checks the validity of a signed session header which was timestamped independently of the
+    /// Notary.
+    ///
+    /// Sessions signed with a revoked notary key are accepted if they were timestamped before the
+    /// key was revoked.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `now` - The current time, in seconds since the UNIX epoch.
+    /// * `timestamped_at` - The time asserted by the verified timestamp token of the session, see
+    ///   [`SessionProof::verify_timestamp`](crate::proof::SessionProof::verify_timestamp).
+    /// * `revocations` - Verified revocation lists to check against.
+    pub fn check_timestamped(
+        &self,
+        header: &SessionHeader,
+        notary_public_key: &NotaryPublicKey,
+        now: u64,
+        timestamped_at: u64,
+        revocations: &[RevocationList],
+    ) -> Result<(), ValidityError> {
+        self.check_at(
+            header,
+            notary_public_key,
+            now,
+            Some(timestamped_at),
+            revocations,
+        )
+    }
+
+    fn check_at(
+        &self,
+        header: &SessionHeader,
+        notary_public_key: &NotaryPublicKey,
+        now: u64,
+        timestamped_at: Option<u64>,
+        revocations: &[RevocationList],
+    ) -> Result<(), ValidityError> {
+        let skew = self.max_clock_skew.unwrap_or(0);
+        let notarized_at = header.time();
+
+        if let Some(required) = self.required_time_precision {
+            let precision = header.time_precision().unwrap_or(1);
+            if precision > required {
+                return Err(ValidityError::InsufficientTimePrecision {
+                    precision,
+                    required,
+                });
+            }
+        }
+
+        if self.max_clock_skew.is_some() && notarized_at > now.saturating_add(skew) {
+            return Err(ValidityError::NotarizedInFuture { notarized_at, now });
+        }
+
+        match header.expires_at() {
+            Some(expired_at) if self.enforce_expiry && now >= expired_at.saturating_add(skew) => {
+                return Err(ValidityError::Expired { expired_at, now })
+            }
+            None if self.require_expiry => return Err(ValidityError::MissingExpiry),
+            _ => {}
+        }
+
+        if let Some(max_age) = self.max_age {
+            if now >= notarized_at.saturating_add(max_age).saturating_add(skew) {
+                return Err(ValidityError::TooOld {
+                    notarized_at,
+                    max_age,
+                    now,
+                });
+            }
+        }
+
+        for list in revocations {
+            if let Some(max_age) = self.max_revocation_list_age {
+                if now.saturating_sub(list.issued_at) > max_age {
+                    return Err(ValidityError::StaleRevocationList(list.issued_at));
+                }
+            }
+
+            list.check(header, notary_public_key, timestamped_at)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the validity of a signed session header, taking the current time from `time`.
+    ///
+    /// See [`ValidityPolicy::check`].
+    pub fn check_with_time_source(
+        &self,
+        header: &SessionHeader,
+        notary_public_key: &NotaryPublicKey,
+        time: &dyn TimeSource,
+        revocations: &[RevocationList],
+    ) -> Result<(), ValidityError> {
+        self.check(header, notary_public_key, time.now()?, revocations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, merkle::MerkleRoot, proof::SessionProofError, time::FixedTime};
+    use p256::ecdsa::Signature as P256Signature;
+
+    fn test_header(expires_at: Option<u64>) -> (SessionHeader, NotaryPublicKey) {
+        let mut header = fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10);
+        if let Some(expires_at) = expires_at {
+            header = header.with_expiry(expires_at);
+        }
+        let key = fixtures::notary_signing_key();
+
+        (header, p256::PublicKey::from(*key.verifying_key()).into())
+    }
+
+    #[test]
+    fn test_validity_expiry() {
+        let (header, key) = test_header(Some(100));
+        let policy = ValidityPolicy::default();
+
+        policy.check(&header, &key, 99, &[]).unwrap();
+        assert!(matches!(
+            policy.check(&header, &key, 100, &[]),
+            Err(ValidityError::Expired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validity_time_source() {
+        let (header, key) = test_header(Some(100));
+        let policy = ValidityPolicy::default();
+
+        policy
+            .check_with_time_source(&header, &key, &FixedTime(99), &[])
+            .unwrap();
+        assert!(matches!(
+            policy.check_with_time_source(&header, &key, &FixedTime(100), &[]),
+            Err(ValidityError::Expired { .. })
+        ));
+
+        let unavailable = || -> Result<u64, TimeError> { Err(TimeError::new("unreachable")) };
+        assert!(matches!(
+            policy.check_with_time_source(&header, &key, &unavailable, &[]),
+            Err(ValidityError::Time(_))
+        ));
+    }
+
+    #[test]
+    fn test_validity_max_age_and_clock_skew() {
+        let (header, key) = test_header(None);
+        let policy = ValidityPolicy {
+            max_age: Some(30 * 24 * 60 * 60),
+            max_clock_skew: Some(60),
+            ..Default::default()
+        };
+        let expired_at = header.time() + 30 * 24 * 60 * 60 + 60;
+
+        policy.check(&header, &key, expired_at - 1, &[]).unwrap();
+        assert!(matches!(
+            policy.check(&header, &key, expired_at, &[]),
+            Err(ValidityError::TooOld { .. })
+        ));
+
+        policy
+            .check(&header, &key, header.time() - 60, &[])
+            .unwrap();
+        assert!(matches!(
+            policy.check(&header, &key, header.time() - 61, &[]),
+            Err(ValidityError::NotarizedInFuture { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validity_time_precision() {
+        let key = fixtures::notary_signing_key();
+        let header =
+            fixtures::session_header(MerkleRoot::from([0u8; 32]), 10, 10).with_time_precision(3600);
+        let key: NotaryPublicKey = p256::PublicKey::from(*key.verifying_key()).into();
+
+        let policy = |required| ValidityPolicy {
+            required_time_precision: Some(required),
+            ..Default::default()
+        };
+
+        policy(3600)
+            .check(&header, &key, header.time(), &[])
+            .unwrap();
+        assert!(matches!(
+            policy(60).check(&header, &key, header.time(), &[]),
+            Err(ValidityError::InsufficientTimePrecision {
+                precision: 3600,
+                required: 60
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validity_revoked_key() {
+        let (header, key) = test_header(None);
+        let policy = ValidityPolicy::default();
+
+        let mut list = RevocationList::new(0);
+        list.revoke_key(key.clone(), header.time() + 1);
+
+        // A header dated before the revocation may have been backdated with the revoked key.
+        assert!(matches!(
+            policy.check(&header, &key, header.time(), &[list.clone()]),
+            Err(ValidityError::KeyRevoked(_))
+        ));
+
+        // Unless the session was timestamped before the key was revoked.
+        policy
+            .check_timestamped(&header, &key, header.time(), header.time(), &[list.clone()])
+            .unwrap();
+        assert!(matches!(
+            policy.check_timestamped(&header, &key, header.time() + 1, header.time() + 1, &[list]),
+            Err(ValidityError::KeyRevoked(_))
+        ));
+    }
+
+    #[test]
+    fn test_validity_revoked_session() {
+        let (header, key) = test_header(None);
+
+        let mut list = RevocationList::new(0);
+        list.revoke_session(&header);
+
+        assert!(matches!(
+            ValidityPolicy::default().check(&header, &key, header.time(), &[list]),
+            Err(ValidityError::SessionRevoked)
+        ));
+    }
+
+    #[test]
+    fn test_validity_revoked_session_malleated_signature() {
+        let mut proof = fixtures::tls_proof(
+            b"GET / HTTP/1.1\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\n\r\n",
+            |header| header,
+        );
+        let key = NotaryPublicKey::from(p256::PublicKey::from(
+            *fixtures::notary_signing_key().verifying_key(),
+        ));
+
+        let mut list = RevocationList::new(0);
+        list.revoke_session(&proof.session.header);
+
+        // (r, n - s) is a valid signature over the same header.
+        let Some(Signature::P256(signature)) = proof.session.signature else {
+            panic!("expected a P-256 signature");
+        };
+        let (r, s) = signature.split_scalars();
+        let malleated = P256Signature::from_scalars(r, -s).unwrap();
+        assert_ne!(malleated, signature);
+        proof.session.signature = Some(malleated.into());
+        proof
+            .session
+            .verify(key.clone(), &crate::proof::default_cert_verifier())
+            .unwrap();
+
+        let now = proof.session.header.time();
+        assert!(matches!(
+            proof
+                .session
+                .verify_validity(key, now, &ValidityPolicy::default(), &[list]),
+            Err(SessionProofError::Invalid(ValidityError::SessionRevoked))
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// An error for [`Extensions`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ExtensionError {
+    /// The value of an extension could not be decoded.
+    #[error("failed to decode extension {id:?}: {reason}")]
+    Decode {
+        /// The id of the extension.
+        id: ExtensionId,
+        /// The reason the value could not be decoded.
+        reason: String,
+    },
+}
+
+/// The registered identifier of an [`Extension`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ExtensionId(pub u16);
+
+impl ExtensionId {
+    /// [`NotaryPolicyId`]
+    pub const NOTARY_POLICY_ID: Self = Self(0x0001);
+    /// [`ClientAppId`]
+    pub const CLIENT_APP_ID: Self = Self(0x0002);
+    /// [`GeolocationClass`]
+    pub const GEOLOCATION_CLASS: Self = Self(0x0003);
+    /// The first id reserved for application specific extensions. Ids below this value are
+    /// registered by this crate.
+    pub const PRIVATE_USE_START: Self = Self(0x8000);
+}
+
+/// A typed claim which can be included in a [`SessionHeader`](crate::SessionHeader).
+pub trait Extension: Serialize + DeserializeOwned {
+    /// The registered identifier of this extension.
+    const ID: ExtensionId;
+}
+
+/// The identifier of the policy under which the Notary notarized the session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotaryPolicyId(pub String);
+
+impl Extension for NotaryPolicyId {
+    const ID: ExtensionId = ExtensionId::NOTARY_POLICY_ID;
+}
+
+/// The identifier of the application the Prover used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientAppId(pub String);
+
+impl Extension for ClientAppId {
+    const ID: ExtensionId = ExtensionId::CLIENT_APP_ID;
+}
+
+/// A coarse location of the Prover as determined by the Notary, e.g. an ISO 3166-1 country code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeolocationClass(pub String);
+
+impl Extension for GeolocationClass {
+    const ID: ExtensionId = ExtensionId::GEOLOCATION_CLASS;
+}
+
+/// A set of extension claims, keyed by their [`ExtensionId`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Extensions(BTreeMap<ExtensionId, Vec<u8>>);
+
+impl Extensions {
+    /// Creates an empty set of extensions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts an extension, replacing any existing extension with the same id.
+    pub fn insert<T: Extension>(&mut self, value: &T) -> &mut Self {
+        self.insert_raw(
+            T::ID,
+            bincode::serialize(value).expect("extension is serializable"),
+        )
+    }
+
+    /// Inserts an extension, replacing any existing extension with the same id.
+    pub fn with<T: Extension>(mut self, value: &T) -> Self {
+        self.insert(value);
+        self
+    }
+
+    /// Inserts the encoded value of an extension, replacing any existing extension with the same
+    /// id.
+    pub fn insert_raw(&mut self, id: ExtensionId, value: Vec<u8>) -> &mut Self {
+        self.0.insert(id, value);
+        self
+    }
+
+    /// Returns an extension, if it is present.
+    pub fn get<T: Extension>(&self) -> Result<Option<T>, ExtensionError> {
+        self.get_raw(T::ID)
+            .map(|value| {
+                bincode::deserialize(value).map_err(|e| ExtensionError::Decode {
+                    id: T::ID,
+                    reason: e.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    /// Returns the encoded value of an extension, if it is present.
+    pub fn get_raw(&self, id: ExtensionId) -> Option<&[u8]> {
+        self.0.get(&id).map(Vec::as_slice)
+    }
+
+    /// Returns the ids of the extensions, in ascending order.
+    pub fn ids(&self) -> impl Iterator<Item = ExtensionId> + '_ {
+        self.0.keys().copied()
+    }
+
+    /// Returns `true` if there are no extensions.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Inserts all extensions from `other`, replacing existing extensions with the same ids.
+    pub fn extend(&mut self, other: Extensions) {
+        self.0.extend(other.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extensions_typed_roundtrip() {
+        let mut extensions = Extensions::new()
+            .with(&NotaryPolicyId("policy-1".to_string()))
+            .with(&ClientAppId("app".to_string()));
+        extensions.extend(Extensions::new().with(&ClientAppId("other".to_string())));
+
+        assert_eq!(
+            extensions.get::<NotaryPolicyId>().unwrap(),
+            Some(NotaryPolicyId("policy-1".to_string()))
+        );
+        assert_eq!(
+            extensions.get::<ClientAppId>().unwrap(),
+            Some(ClientAppId("other".to_string()))
+        );
+        assert_eq!(extensions.get::<GeolocationClass>().unwrap(), None);
+        assert_eq!(
+            extensions.ids().collect::<Vec<_>>(),
+            vec![ExtensionId::NOTARY_POLICY_ID, ExtensionId::CLIENT_APP_ID]
+        );
+    }
+
+    #[test]
+    fn test_extensions_invalid_value() {
+        let mut extensions = Extensions::new();
+        extensions.insert_raw(ExtensionId::CLIENT_APP_ID, vec![0xff]);
+
+        assert!(extensions.get::<ClientAppId>().is_err());
+    }
+}
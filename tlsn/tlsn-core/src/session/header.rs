@@ -4,7 +4,14 @@ use serde::{Deserialize, Serialize};
 use mpz_garble_core::ChaChaEncoder;
 use tls_core::{handshake::HandshakeData, key::PublicKey};
 
-use crate::{merkle::MerkleRoot, HandshakeSummary};
+use crate::{
+    hash::HashAlgorithm,
+    kzg::KzgCommitment,
+    merkle::MerkleRoot,
+    session::Extensions,
+    signature::{Signature, SignatureVerifyError},
+    HandshakeSummary, NotaryPublicKey,
+};
 
 /// An error that can occur while verifying a session header
 #[derive(Debug, thiserror::Error)]
@@ -35,6 +42,29 @@ pub struct SessionHeader {
     recv_len: usize,
 
     handshake_summary: HandshakeSummary,
+
+    /// Time after which the header is no longer valid, in seconds since the UNIX epoch
+    expires_at: Option<u64>,
+
+    /// Granularity of the session time in seconds, if the Notary rounded it down
+    time_precision: Option<u64>,
+
+    /// Hash algorithm of the Prover's transcript commitments
+    hash_algorithm: HashAlgorithm,
+
+    /// Typed extension claims
+    extensions: Extensions,
+
+    /// Whether the server closed the connection with a close_notify alert
+    closed_cleanly: bool,
+
+    /// A KZG vector commitment to the same commitments as `merkle_root`, if the Prover sent one.
+    /// Like the root, it was made known to the Notary before the encoder seed was revealed.
+    kzg_commitment: Option<KzgCommitment>,
+
+    /// The Poseidon hash of `merkle_root`, if the Notary recorded it, so that a zk circuit can
+    /// bind to the commitments signed by the Notary.
+    poseidon_root: Option<[u8; 32]>,
 }
 
 impl SessionHeader {
@@ -52,9 +82,61 @@ impl SessionHeader {
             sent_len,
             recv_len,
             handshake_summary,
+            expires_at: None,
+            time_precision: None,
+            hash_algorithm: HashAlgorithm::default(),
+            extensions: Extensions::default(),
+            closed_cleanly: false,
+            kzg_commitment: None,
+            poseidon_root: None,
         }
     }
 
+    /// Sets the time after which the header is no longer valid, in seconds since the UNIX epoch.
+    pub fn with_expiry(mut self, expires_at: u64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Sets the granularity of the session time, in seconds.
+    ///
+    /// The Notary sets this if it rounded the session time down to a multiple of `precision`, so
+    /// that the time reveals less about the Prover.
+    pub fn with_time_precision(mut self, precision: u64) -> Self {
+        self.time_precision = Some(precision);
+        self
+    }
+
+    /// Sets the hash algorithm of the Prover's transcript commitments.
+    pub fn with_hash_algorithm(mut self, alg: HashAlgorithm) -> Self {
+        self.hash_algorithm = alg;
+        self
+    }
+
+    /// Sets the extension claims.
+    pub fn with_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Sets whether the server closed the connection with a close_notify alert.
+    pub fn with_closed_cleanly(mut self, closed_cleanly: bool) -> Self {
+        self.closed_cleanly = closed_cleanly;
+        self
+    }
+
+    /// Sets the KZG vector commitment to the Prover's transcript commitments.
+    pub fn with_kzg_commitment(mut self, commitment: KzgCommitment) -> Self {
+        self.kzg_commitment = Some(commitment);
+        self
+    }
+
+    /// Sets the Poseidon hash of the commitment root.
+    pub fn with_poseidon_root(mut self, digest: [u8; 32]) -> Self {
+        self.poseidon_root = Some(digest);
+        self
+    }
+
     /// Verify the data in the header is consistent with the Prover's view
     pub fn verify(
         &self,
@@ -64,21 +146,47 @@ impl SessionHeader {
         encoder_seed: &[u8; 32],
         handshake_data_decommitment: &Decommitment<HandshakeData>,
     ) -> Result<(), SessionHeaderVerifyError> {
-        let ok_time = self.handshake_summary.time().abs_diff(time) <= 300;
+        // The Notary may have rounded the time down
+        let ok_time =
+            self.handshake_summary.time().abs_diff(time) <= 300 + self.time_precision.unwrap_or(0);
         let ok_root = &self.merkle_root == root;
         let ok_encoder_seed = &self.encoder_seed == encoder_seed;
         let ok_handshake_data = handshake_data_decommitment
             .verify(self.handshake_summary.handshake_commitment())
             .is_ok();
         let ok_server_public_key = self.handshake_summary.server_public_key() == server_public_key;
+        // The Poseidon root can only be checked if Poseidon hashing is available
+        #[cfg(feature = "poseidon")]
+        let ok_poseidon_root = self
+            .poseidon_root
+            .map_or(true, |digest| digest == root.poseidon_hash());
+        #[cfg(not(feature = "poseidon"))]
+        let ok_poseidon_root = true;
 
-        if !(ok_time && ok_root && ok_encoder_seed && ok_handshake_data && ok_server_public_key) {
+        if !(ok_time
+            && ok_root
+            && ok_encoder_seed
+            && ok_handshake_data
+            && ok_server_public_key
+            && ok_poseidon_root)
+        {
             return Err(SessionHeaderVerifyError::InconsistentHeader);
         }
 
         Ok(())
     }
 
+    /// Verifies a Notary signature over this header.
+    ///
+    /// The signature covers the [signing message](SessionHeader::signing_message) of the header.
+    pub fn verify_signature(
+        &self,
+        signature: &Signature,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), SignatureVerifyError> {
+        signature.verify(&self.signing_message()?, notary_public_key)
+    }
+
     /// Create a new [ChaChaEncoder] from encoder_seed
     pub fn encoder(&self) -> ChaChaEncoder {
         ChaChaEncoder::new(self.encoder_seed)
@@ -108,6 +216,45 @@ impl SessionHeader {
         self.handshake_summary.time()
     }
 
+    /// Returns the time after which the header is no longer valid, in seconds since the UNIX epoch
+    pub fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    /// Returns the granularity of the session time in seconds, if the Notary rounded it down
+    pub fn time_precision(&self) -> Option<u64> {
+        self.time_precision
+    }
+
+    /// Returns the hash algorithm of the Prover's transcript commitments
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// Returns the extension claims
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns whether the server closed the connection with a close_notify alert.
+    ///
+    /// The alert is authenticated by the server and can only be decrypted after every record the
+    /// server sent before it, so if this is `true` the received data was not truncated. If it is
+    /// `false`, the Prover may have stopped the session before the server finished responding.
+    pub fn closed_cleanly(&self) -> bool {
+        self.closed_cleanly
+    }
+
+    /// Returns the KZG vector commitment to the Prover's transcript commitments, if any
+    pub fn kzg_commitment(&self) -> Option<&KzgCommitment> {
+        self.kzg_commitment.as_ref()
+    }
+
+    /// Returns the Poseidon hash of the commitment root, if the Notary recorded it
+    pub fn poseidon_root(&self) -> Option<&[u8; 32]> {
+        self.poseidon_root.as_ref()
+    }
+
     /// Returns the number of bytes sent to the server
     pub fn sent_len(&self) -> usize {
         self.sent_len
@@ -118,3 +265,32 @@ impl SessionHeader {
         self.recv_len
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use p256::ecdsa::Signature as P256Signature;
+    use signature::Signer;
+
+    #[test]
+    fn test_closed_cleanly_is_signed() {
+        let key = fixtures::notary_signing_key();
+        let public_key = p256::PublicKey::from(*key.verifying_key());
+
+        let truncated = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+        assert!(!truncated.closed_cleanly());
+
+        let closed = truncated.clone().with_closed_cleanly(true);
+        assert!(closed.closed_cleanly());
+
+        // A signature over a truncated session does not attest to a clean close.
+        let signature: P256Signature = key.sign(&truncated.signing_message().unwrap());
+        assert!(truncated
+            .verify_signature(&signature.into(), public_key)
+            .is_ok());
+        assert!(closed
+            .verify_signature(&signature.into(), public_key)
+            .is_err());
+    }
+}
@@ -1,18 +1,25 @@
 //! TLS session types.
 
 mod data;
+mod extension;
 mod handshake;
 mod header;
 
 use serde::{Deserialize, Serialize};
 
 pub use data::SessionData;
+pub use extension::{
+    ClientAppId, Extension, ExtensionError, ExtensionId, Extensions, GeolocationClass,
+    NotaryPolicyId,
+};
 pub use handshake::{HandshakeSummary, HandshakeVerifyError};
 pub use header::{SessionHeader, SessionHeaderVerifyError};
 
 use crate::{
     proof::{SessionInfo, SessionProof},
     signature::Signature,
+    tee::TeeQuote,
+    timestamp::{attestation_digest, TimestampError, TimestampToken},
 };
 
 /// A validated notarized session stored by the Prover
@@ -21,6 +28,8 @@ pub struct NotarizedSession {
     header: SessionHeader,
     signature: Option<Signature>,
     data: SessionData,
+    timestamp: Option<TimestampToken>,
+    tee_quote: Option<TeeQuote>,
 }
 
 opaque_debug::implement!(NotarizedSession);
@@ -32,6 +41,8 @@ impl NotarizedSession {
             header,
             signature,
             data,
+            timestamp: None,
+            tee_quote: None,
         }
     }
 
@@ -46,6 +57,7 @@ impl NotarizedSession {
             header: self.header.clone(),
             signature: self.signature.clone(),
             session_info,
+            timestamp: self.timestamp.clone(),
         }
     }
 
@@ -63,4 +75,40 @@ impl NotarizedSession {
     pub fn data(&self) -> &SessionData {
         &self.data
     }
+
+    /// Returns the digest which should be timestamped by a TSA, if the notary signed the header.
+    pub fn attestation_digest(&self) -> Option<[u8; 32]> {
+        self.signature
+            .as_ref()
+            .map(|signature| attestation_digest(&self.header, signature))
+    }
+
+    /// Returns the timestamp token for this session, if one was attached.
+    pub fn timestamp(&self) -> Option<&TimestampToken> {
+        self.timestamp.as_ref()
+    }
+
+    /// Attaches an RFC 3161 timestamp token over the [attestation digest](Self::attestation_digest).
+    ///
+    /// Returns an error if the session is not signed, or the token is not over this session.
+    pub fn set_timestamp(&mut self, token: TimestampToken) -> Result<(), TimestampError> {
+        let digest = self
+            .attestation_digest()
+            .ok_or(TimestampError::MissingSignature)?;
+        token.verify_imprint(&digest)?;
+
+        self.timestamp = Some(token);
+
+        Ok(())
+    }
+
+    /// Returns the hardware attestation quote of the notary, if it runs in a TEE.
+    pub fn tee_quote(&self) -> Option<&TeeQuote> {
+        self.tee_quote.as_ref()
+    }
+
+    /// Attaches the hardware attestation quote the notary sent with its signature.
+    pub fn set_tee_quote(&mut self, quote: TeeQuote) {
+        self.tee_quote = Some(quote);
+    }
 }
@@ -0,0 +1,308 @@
+//! BLS12-381 notary signatures.
+//!
+//! Signatures use the "minimal-pubkey-size" variant (public keys in G1, signatures in G2) with
+//! the proof-of-possession scheme from the IETF BLS signature draft. Signatures from several
+//! notaries over the same session header can be aggregated into a single [`BlsSignature`] and
+//! verified with one pairing check.
+//!
+//! # Rogue key attacks
+//!
+//! Fast aggregate verification is only sound if every public key involved has been checked
+//! against a proof of possession, see [`BlsSigningKey::proof_of_possession`] and
+//! [`BlsPublicKey::verify_proof_of_possession`]. Verifiers must do this once when they first
+//! accept a notary key.
+
+use blst::{
+    blst_scalar,
+    min_pk::{AggregateSignature, PublicKey, SecretKey, Signature},
+    BLST_ERROR,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{ser::Serializer, Deserialize, Deserializer, Serialize};
+
+/// Domain separation tag for signatures, as specified for the proof-of-possession scheme.
+const SIG_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// Domain separation tag for proofs of possession.
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// An error that can occur while working with BLS keys and signatures.
+#[derive(Debug, thiserror::Error)]
+#[error("bls error: {0:?}")]
+pub struct BlsError(BLST_ERROR);
+
+fn check(err: BLST_ERROR) -> Result<(), BlsError> {
+    match err {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        err => Err(BlsError(err)),
+    }
+}
+
+/// A BLS12-381 signing key for a Notary.
+pub struct BlsSigningKey(SecretKey);
+
+opaque_debug::implement!(BlsSigningKey);
+
+impl BlsSigningKey {
+    /// Derives a signing key from the provided input key material.
+    ///
+    /// # Arguments
+    ///
+    /// * `ikm` - Secret input key material, must be at least 32 bytes.
+    pub fn from_ikm(ikm: &[u8]) -> Result<Self, BlsError> {
+        SecretKey::key_gen(ikm, &[]).map(Self).map_err(BlsError)
+    }
+
+    /// Creates a signing key from its 32 byte big-endian encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BlsError> {
+        SecretKey::from_bytes(bytes).map(Self).map_err(BlsError)
+    }
+
+    /// Returns the 32 byte big-endian encoding of this key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// Returns the public key corresponding to this signing key.
+    pub fn public_key(&self) -> BlsPublicKey {
+        BlsPublicKey(self.0.sk_to_pk())
+    }
+
+    /// Returns a proof that the holder of this key possesses the secret key.
+    pub fn proof_of_possession(&self) -> BlsSignature {
+        let pk = self.0.sk_to_pk().compress();
+        BlsSignature(self.0.sign(&pk, POP_DST, &[]))
+    }
+}
+
+impl signature::Signer<BlsSignature> for BlsSigningKey {
+    fn try_sign(&self, msg: &[u8]) -> Result<BlsSignature, signature::Error> {
+        Ok(BlsSignature(self.0.sign(msg, SIG_DST, &[])))
+    }
+}
+
+/// A BLS12-381 public key of a Notary.
+#[derive(Debug, Clone, Copy)]
+pub struct BlsPublicKey(PublicKey);
+
+impl BlsPublicKey {
+    /// Parses a public key from its 48 byte compressed encoding.
+    ///
+    /// The key is checked to be a valid, non-identity point in the prime order subgroup.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BlsError> {
+        let key = PublicKey::uncompress(bytes).map_err(BlsError)?;
+        key.validate().map_err(BlsError)?;
+
+        Ok(Self(key))
+    }
+
+    /// Returns the 48 byte compressed encoding of this key.
+    pub fn to_bytes(&self) -> [u8; 48] {
+        self.0.compress()
+    }
+
+    /// Verifies a proof of possession for this key.
+    pub fn verify_proof_of_possession(&self, proof: &BlsSignature) -> Result<(), BlsError> {
+        check(
+            proof
+                .0
+                .verify(true, &self.0.compress(), POP_DST, &[], &self.0, true),
+        )
+    }
+}
+
+impl PartialEq for BlsPublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for BlsPublicKey {}
+
+impl Serialize for BlsPublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsPublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A BLS12-381 signature, possibly aggregated from several notaries.
+#[derive(Debug, Clone, Copy)]
+pub struct BlsSignature(Signature);
+
+impl BlsSignature {
+    /// Parses a signature from its 96 byte compressed encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BlsError> {
+        Signature::uncompress(bytes).map(Self).map_err(BlsError)
+    }
+
+    /// Returns the 96 byte compressed encoding of this signature.
+    pub fn to_bytes(&self) -> [u8; 96] {
+        self.0.compress()
+    }
+
+    /// Aggregates signatures into a single signature.
+    ///
+    /// Returns an error if `signatures` is empty or if any of the signatures is invalid.
+    pub fn aggregate<'a>(
+        signatures: impl IntoIterator<Item = &'a BlsSignature>,
+    ) -> Result<Self, BlsError> {
+        let signatures = signatures.into_iter().map(|sig| &sig.0).collect::<Vec<_>>();
+
+        AggregateSignature::aggregate(&signatures, true)
+            .map(|agg| Self(agg.to_signature()))
+            .map_err(BlsError)
+    }
+
+    /// Verifies this signature over `msg` against a single public key.
+    pub fn verify(&self, msg: &[u8], key: &BlsPublicKey) -> Result<(), BlsError> {
+        check(self.0.verify(true, msg, SIG_DST, &[], &key.0, true))
+    }
+
+    /// Verifies this aggregate signature over `msg` against all of the provided public keys.
+    ///
+    /// # Warning
+    ///
+    /// Each key must have had its proof of possession verified beforehand, see the
+    /// [module level documentation](self).
+    pub fn verify_aggregate(&self, msg: &[u8], keys: &[BlsPublicKey]) -> Result<(), BlsError> {
+        if keys.is_empty() {
+            return Err(BlsError(BLST_ERROR::BLST_AGGR_TYPE_MISMATCH));
+        }
+
+        let keys = keys.iter().map(|key| &key.0).collect::<Vec<_>>();
+
+        check(self.0.fast_aggregate_verify(true, msg, SIG_DST, &keys))
+    }
+
+    /// Verifies signatures over distinct messages, each against a single public key, with one
+    /// multi-pairing check.
+    ///
+    /// Each signature is weighted with a random 64-bit scalar, so that invalid signatures can
+    /// not cancel each other out. If the check fails, at least one signature is invalid and
+    /// they must be verified individually to find out which.
+    pub fn verify_batch(items: &[(&[u8], &BlsPublicKey, &BlsSignature)]) -> Result<(), BlsError> {
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let msgs = items.iter().map(|(msg, _, _)| *msg).collect::<Vec<_>>();
+        let keys = items.iter().map(|(_, key, _)| &key.0).collect::<Vec<_>>();
+        let sigs = items.iter().map(|(_, _, sig)| &sig.0).collect::<Vec<_>>();
+        let rands = items
+            .iter()
+            .map(|_| {
+                let mut b = [0u8; 32];
+                b[..8].copy_from_slice(&OsRng.next_u64().max(1).to_le_bytes());
+                blst_scalar { b }
+            })
+            .collect::<Vec<_>>();
+
+        check(Signature::verify_multiple_aggregate_signatures(
+            &msgs, SIG_DST, &keys, true, &sigs, true, &rands, 64,
+        ))
+    }
+}
+
+impl Serialize for BlsSignature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsSignature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signature::Signer;
+
+    const MSG: &[u8] = b"session header";
+
+    fn keys(n: u8) -> Vec<BlsSigningKey> {
+        (0..n)
+            .map(|i| BlsSigningKey::from_ikm(&[i; 32]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_bls_sign_verify() {
+        let key = BlsSigningKey::from_ikm(&[0u8; 32]).unwrap();
+        let sig = key.sign(MSG);
+
+        sig.verify(MSG, &key.public_key()).unwrap();
+        assert!(sig.verify(b"other", &key.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_bls_aggregate_verify() {
+        let keys = keys(3);
+        let sigs = keys.iter().map(|key| key.sign(MSG)).collect::<Vec<_>>();
+        let pks = keys.iter().map(|key| key.public_key()).collect::<Vec<_>>();
+
+        let agg = BlsSignature::aggregate(&sigs).unwrap();
+
+        agg.verify_aggregate(MSG, &pks).unwrap();
+        // Missing a signer
+        assert!(agg.verify_aggregate(MSG, &pks[..2]).is_err());
+        assert!(agg.verify_aggregate(MSG, &[]).is_err());
+    }
+
+    #[test]
+    fn test_bls_batch_verify() {
+        let keys = keys(3);
+        let msgs: [&[u8]; 3] = [b"header 0", b"header 1", b"header 2"];
+        let sigs = keys
+            .iter()
+            .zip(msgs)
+            .map(|(key, msg)| key.sign(msg))
+            .collect::<Vec<_>>();
+        let pks = keys.iter().map(|key| key.public_key()).collect::<Vec<_>>();
+
+        let items = (0..3)
+            .map(|i| (msgs[i], &pks[i], &sigs[i]))
+            .collect::<Vec<_>>();
+        BlsSignature::verify_batch(&items).unwrap();
+
+        // A signature over another message
+        let mut items = items;
+        items[1].2 = &sigs[2];
+        assert!(BlsSignature::verify_batch(&items).is_err());
+    }
+
+    #[test]
+    fn test_bls_proof_of_possession() {
+        let keys = keys(2);
+
+        keys[0]
+            .public_key()
+            .verify_proof_of_possession(&keys[0].proof_of_possession())
+            .unwrap();
+        assert!(keys[0]
+            .public_key()
+            .verify_proof_of_possession(&keys[1].proof_of_possession())
+            .is_err());
+    }
+
+    #[test]
+    fn test_bls_serde_roundtrip() {
+        let key = BlsSigningKey::from_ikm(&[7u8; 32]).unwrap();
+        let sig = key.sign(MSG);
+
+        let sig: BlsSignature = bincode::deserialize(&bincode::serialize(&sig).unwrap()).unwrap();
+        let pk: BlsPublicKey =
+            bincode::deserialize(&bincode::serialize(&key.public_key()).unwrap()).unwrap();
+
+        sig.verify(MSG, &pk).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+mod bls;
+
+use serde::{Deserialize, Serialize};
+
+use p256::ecdsa::{signature::Verifier, VerifyingKey};
+
+pub use bls::{BlsError, BlsPublicKey, BlsSignature, BlsSigningKey};
+
+/// A Notary public key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[non_exhaustive]
+pub enum NotaryPublicKey {
+    /// A NIST P-256 public key.
+    P256(p256::PublicKey),
+    /// A BLS12-381 public key.
+    Bls(BlsPublicKey),
+    /// A secp256k1 public key.
+    Secp256k1(k256::PublicKey),
+    /// An Ed25519 public key.
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+impl From<p256::PublicKey> for NotaryPublicKey {
+    fn from(key: p256::PublicKey) -> Self {
+        Self::P256(key)
+    }
+}
+
+impl From<BlsPublicKey> for NotaryPublicKey {
+    fn from(key: BlsPublicKey) -> Self {
+        Self::Bls(key)
+    }
+}
+
+impl From<k256::PublicKey> for NotaryPublicKey {
+    fn from(key: k256::PublicKey) -> Self {
+        Self::Secp256k1(key)
+    }
+}
+
+impl From<ed25519_dalek::VerifyingKey> for NotaryPublicKey {
+    fn from(key: ed25519_dalek::VerifyingKey) -> Self {
+        Self::Ed25519(key)
+    }
+}
+
+impl NotaryPublicKey {
+    /// Returns the algorithm of signatures made with this key, if it can be requested from a Notary.
+    pub fn algorithm(&self) -> Option<SignatureAlgorithm> {
+        match self {
+            Self::P256(_) => Some(SignatureAlgorithm::P256),
+            Self::Secp256k1(_) => Some(SignatureAlgorithm::Secp256k1),
+            Self::Ed25519(_) => Some(SignatureAlgorithm::Ed25519),
+            Self::Bls(_) => None,
+        }
+    }
+}
+
+/// A signature algorithm which a Prover can request the Notary to sign its session header with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureAlgorithm {
+    /// ECDSA over NIST P-256 with SHA-256.
+    #[default]
+    P256,
+    /// ECDSA over secp256k1 with SHA-256, e.g. for verification on EVM chains.
+    Secp256k1,
+    /// Ed25519, e.g. for verification in mobile wallets.
+    Ed25519,
+}
+
+/// An error occurred while verifying a signature.
+#[derive(Debug, thiserror::Error)]
+#[error("signature verification failed: {0}")]
+pub struct SignatureVerifyError(String);
+
+impl From<crate::canonical::CanonicalJsonError> for SignatureVerifyError {
+    fn from(e: crate::canonical::CanonicalJsonError) -> Self {
+        Self(e.to_string())
+    }
+}
+
+/// An error occurred while aggregating signatures.
+#[derive(Debug, thiserror::Error)]
+#[error("signature aggregation failed: {0}")]
+pub struct SignatureAggregateError(String);
+
+/// A Notary signature.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[non_exhaustive]
+pub enum Signature {
+    /// A secp256r1 signature.
+    P256(p256::ecdsa::Signature),
+    /// A BLS12-381 signature, possibly aggregated from multiple notaries.
+    Bls(BlsSignature),
+    /// A secp256k1 ECDSA signature.
+    Secp256k1(k256::ecdsa::Signature),
+    /// An Ed25519 signature.
+    Ed25519(ed25519_dalek::Signature),
+}
+
+impl From<p256::ecdsa::Signature> for Signature {
+    fn from(sig: p256::ecdsa::Signature) -> Self {
+        Self::P256(sig)
+    }
+}
+
+impl From<BlsSignature> for Signature {
+    fn from(sig: BlsSignature) -> Self {
+        Self::Bls(sig)
+    }
+}
+
+impl From<k256::ecdsa::Signature> for Signature {
+    fn from(sig: k256::ecdsa::Signature) -> Self {
+        Self::Secp256k1(sig)
+    }
+}
+
+impl From<ed25519_dalek::Signature> for Signature {
+    fn from(sig: ed25519_dalek::Signature) -> Self {
+        Self::Ed25519(sig)
+    }
+}
+
+impl Signature {
+    /// Returns the bytes of this signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::P256(sig) => sig.to_vec(),
+            Self::Bls(sig) => sig.to_bytes().to_vec(),
+            Self::Secp256k1(sig) => sig.to_vec(),
+            Self::Ed25519(sig) => sig.to_vec(),
+        }
+    }
+
+    /// Returns the algorithm of this signature, if it can be requested from a Notary.
+    pub fn algorithm(&self) -> Option<SignatureAlgorithm> {
+        match self {
+            Self::P256(_) => Some(SignatureAlgorithm::P256),
+            Self::Secp256k1(_) => Some(SignatureAlgorithm::Secp256k1),
+            Self::Ed25519(_) => Some(SignatureAlgorithm::Ed25519),
+            Self::Bls(_) => None,
+        }
+    }
+
+    /// Verifies the signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to verify.
+    /// * `notary_public_key` - The public key of the notary.
+    pub fn verify(
+        &self,
+        msg: &[u8],
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), SignatureVerifyError> {
+        match (self, notary_public_key.into()) {
+            (Self::P256(sig), NotaryPublicKey::P256(key)) => VerifyingKey::from(key)
+                .verify(msg, sig)
+                .map_err(|e| SignatureVerifyError(e.to_string())),
+            (Self::Bls(sig), NotaryPublicKey::Bls(key)) => sig
+                .verify(msg, &key)
+                .map_err(|e| SignatureVerifyError(e.to_string())),
+            (Self::Secp256k1(sig), NotaryPublicKey::Secp256k1(key)) => {
+                k256::ecdsa::VerifyingKey::from(key)
+                    .verify(msg, sig)
+                    .map_err(|e| SignatureVerifyError(e.to_string()))
+            }
+            (Self::Ed25519(sig), NotaryPublicKey::Ed25519(key)) => key
+                .verify_strict(msg, sig)
+                .map_err(|e| SignatureVerifyError(e.to_string())),
+            _ => Err(SignatureVerifyError(
+                "signature and public key types do not match".to_string(),
+            )),
+        }
+    }
+
+    /// Aggregates signatures from multiple notaries over the same message into one signature.
+    ///
+    /// Only BLS signatures can be aggregated.
+    pub fn aggregate<'a>(
+        signatures: impl IntoIterator<Item = &'a Signature>,
+    ) -> Result<Self, SignatureAggregateError> {
+        let signatures = signatures
+            .into_iter()
+            .map(|sig| match sig {
+                Self::Bls(sig) => Ok(sig),
+                _ => Err(SignatureAggregateError(
+                    "only BLS signatures can be aggregated".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        BlsSignature::aggregate(signatures)
+            .map(Self::Bls)
+            .map_err(|e| SignatureAggregateError(e.to_string()))
+    }
+
+    /// Verifies an aggregated signature against the public keys of all the notaries which
+    /// contributed to it.
+    ///
+    /// # Warning
+    ///
+    /// The proof of possession of every BLS key must have been verified beforehand, see
+    /// [`BlsPublicKey::verify_proof_of_possession`].
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to verify.
+    /// * `notary_public_keys` - The public keys of the notaries.
+    pub fn verify_aggregate(
+        &self,
+        msg: &[u8],
+        notary_public_keys: &[NotaryPublicKey],
+    ) -> Result<(), SignatureVerifyError> {
+        let Self::Bls(sig) = self else {
+            return Err(SignatureVerifyError(
+                "only BLS signatures can be aggregated".to_string(),
+            ));
+        };
+
+        let keys = notary_public_keys
+            .iter()
+            .map(|key| match key {
+                NotaryPublicKey::Bls(key) => Ok(*key),
+                _ => Err(SignatureVerifyError(
+                    "aggregate verification requires BLS public keys".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        sig.verify_aggregate(msg, &keys)
+            .map_err(|e| SignatureVerifyError(e.to_string()))
+    }
+
+    /// Verifies many signatures, each over its own message, and returns the result of each.
+    ///
+    /// BLS signatures are checked together with a single multi-pairing, and only verified one
+    /// by one if that fails. Other signatures are verified individually.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The messages, signatures and the public keys of the notaries which signed them.
+    pub fn verify_batch(
+        items: &[(&[u8], &Signature, &NotaryPublicKey)],
+    ) -> Vec<Result<(), SignatureVerifyError>> {
+        let bls = items
+            .iter()
+            .filter_map(|(msg, sig, key)| match (sig, key) {
+                (Self::Bls(sig), NotaryPublicKey::Bls(key)) => Some((*msg, key, sig)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let bls_valid = bls.len() > 1 && BlsSignature::verify_batch(&bls).is_ok();
+
+        items
+            .iter()
+            .map(|(msg, sig, key)| match (sig, key) {
+                (Self::Bls(_), NotaryPublicKey::Bls(_)) if bls_valid => Ok(()),
+                _ => sig.verify(msg, (*key).clone()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+
+    const MSG: &[u8] = b"session header";
+
+    #[test]
+    fn test_verify_secp256k1() {
+        let key = k256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+        let sig: k256::ecdsa::Signature = key.sign(MSG);
+        let sig = Signature::from(sig);
+        let public_key = NotaryPublicKey::from(k256::PublicKey::from(*key.verifying_key()));
+
+        assert_eq!(sig.algorithm(), Some(SignatureAlgorithm::Secp256k1));
+        assert!(sig.verify(MSG, public_key.clone()).is_ok());
+        assert!(sig.verify(b"other header", public_key).is_err());
+    }
+
+    #[test]
+    fn test_verify_ed25519() {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[1u8; 32]);
+        let sig = Signature::from(key.sign(MSG));
+        let public_key = NotaryPublicKey::from(key.verifying_key());
+
+        assert_eq!(sig.algorithm(), Some(SignatureAlgorithm::Ed25519));
+        assert!(sig.verify(MSG, public_key.clone()).is_ok());
+        assert!(sig.verify(b"other header", public_key).is_err());
+    }
+
+    #[test]
+    fn test_verify_algorithm_mismatch() {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[1u8; 32]);
+        let sig = Signature::from(key.sign(MSG));
+        let other = k256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+        let public_key = NotaryPublicKey::from(k256::PublicKey::from(*other.verifying_key()));
+
+        assert!(sig.verify(MSG, public_key).is_err());
+    }
+}
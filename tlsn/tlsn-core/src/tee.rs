@@ -0,0 +1,756 @@
+//! Hardware attestation quotes of Notaries running in a trusted execution environment (TEE).
+//!
+//! A Notary running inside an Intel SGX enclave, an AMD SEV-SNP confidential VM or an AWS Nitro
+//! Enclave can attach a quote to its notarizations, which binds the measurement of the code it runs
+//! to its signing key. The [key binding](notary_key_binding) is placed in the report data of the
+//! quote, i.e. the first 32 bytes of the 64 byte report data for SGX and SEV-SNP, or the user data
+//! of the attestation document for Nitro.
+//!
+//! A quote is checked to be bound to a Notary key with [`TeeQuote::verify_binding`], and its
+//! measurement is extracted. The signature of the hardware vendor over the quote is verified by a
+//! [`TeeVerifier`] provided by the Verifier. [`NitroVerifier`] verifies AWS Nitro attestation
+//! documents against the pinned AWS Nitro Enclaves root certificate. SGX and SEV-SNP quotes need
+//! collateral fetched from the vendor and a policy on the acceptable TCB versions, so their
+//! verifiers are left to the Verifier.
+
+use std::time::Duration;
+
+use ciborium::Value;
+use cms::cert::x509::Certificate;
+use der::{asn1::ObjectIdentifier, Decode, Encode};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p384::ecdsa::{signature::Verifier, Signature as P384Signature, VerifyingKey as P384Key};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::NotaryPublicKey;
+
+/// Domain separator of the key binding.
+const KEY_BINDING_DOMAIN: &[u8] = b"tlsn-notary-key";
+
+/// Size of the header of an SGX ECDSA quote, which precedes the report body.
+const SGX_QUOTE_HEADER_LEN: usize = 48;
+/// Size of an SGX report body.
+const SGX_REPORT_BODY_LEN: usize = 384;
+/// Offset of `MRENCLAVE` in an SGX report body.
+const SGX_MRENCLAVE_OFFSET: usize = 64;
+/// Offset of the report data in an SGX report body.
+const SGX_REPORT_DATA_OFFSET: usize = 320;
+/// TEE type of SGX in the header of an SGX ECDSA quote.
+const SGX_TEE_TYPE: u32 = 0;
+
+/// Size of an SEV-SNP attestation report.
+const SNP_REPORT_LEN: usize = 0x4a0;
+/// Offset of the report data in an SEV-SNP attestation report.
+const SNP_REPORT_DATA_OFFSET: usize = 0x50;
+/// Offset of the launch measurement in an SEV-SNP attestation report.
+const SNP_MEASUREMENT_OFFSET: usize = 0x90;
+/// Size of the launch measurement in an SEV-SNP attestation report.
+const SNP_MEASUREMENT_LEN: usize = 48;
+
+/// CBOR tag of a `COSE_Sign1` structure.
+const COSE_SIGN1_TAG: u64 = 18;
+/// Label of the algorithm in a COSE header.
+const COSE_HEADER_ALG: i64 = 1;
+/// COSE identifier of ECDSA with SHA-384, which signs Nitro attestation documents.
+const COSE_ALG_ES384: i64 = -35;
+/// Object identifier of ECDSA with SHA-384, which signs the certificates of the Nitro chain.
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+
+/// An error that can occur while parsing or verifying a TEE quote.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TeeError {
+    /// The quote is not well-formed for its platform.
+    #[error("malformed {platform:?} quote: {reason}")]
+    Malformed {
+        /// The platform of the quote.
+        platform: TeePlatform,
+        /// The reason the quote is malformed.
+        reason: String,
+    },
+    /// The report data of the quote does not bind the Notary key.
+    #[error("quote is not bound to the notary key")]
+    KeyMismatch,
+    /// The quote was rejected by the verifier of the hardware vendor's signature.
+    #[error("quote verification failed: {0}")]
+    Rejected(String),
+    /// A trusted certificate of the verifier is invalid.
+    #[error("invalid certificate: {0}")]
+    InvalidCertificate(String),
+}
+
+/// A verifier for the signature of the hardware vendor over a [`TeeQuote`].
+pub trait TeeVerifier {
+    /// Verifies that `quote` was signed by the hardware vendor, e.g. with the DCAP collateral of
+    /// Intel for SGX, the VCEK certificate chain of AMD for SEV-SNP, or the certificate chain to the
+    /// AWS Nitro root for Nitro, and that the TCB of the platform is acceptable.
+    fn verify(&self, quote: &TeeQuote) -> Result<(), TeeError>;
+}
+
+/// A [`TeeVerifier`] of AWS Nitro Enclaves attestation documents.
+///
+/// The document must be signed with ES384 by the key of its certificate, which must chain up
+/// through the `cabundle` of the document to the pinned root certificate, with every certificate
+/// valid at the time of the document. Quotes of other platforms are rejected.
+#[derive(Debug, Clone)]
+pub struct NitroVerifier {
+    root: Vec<u8>,
+}
+
+impl NitroVerifier {
+    /// Creates a verifier trusting the DER encoded root certificate, i.e. the AWS Nitro Enclaves
+    /// root certificate published at
+    /// <https://aws-nitro-enclaves.amazonaws.com/AWS_NitroEnclaves_Root-G1.zip>.
+    pub fn new(root: &[u8]) -> Result<Self, TeeError> {
+        Certificate::from_der(root).map_err(|e| TeeError::InvalidCertificate(e.to_string()))?;
+
+        Ok(Self {
+            root: root.to_vec(),
+        })
+    }
+}
+
+impl TeeVerifier for NitroVerifier {
+    fn verify(&self, quote: &TeeQuote) -> Result<(), TeeError> {
+        if quote.platform() != TeePlatform::Nitro {
+            return Err(TeeError::Rejected(format!(
+                "{:?} quotes are not supported",
+                quote.platform()
+            )));
+        }
+        let cose = parse_cose_sign1(quote.as_bytes()).map_err(TeeError::Rejected)?;
+
+        let protected: Value = ciborium::de::from_reader(cose.protected.as_slice())
+            .map_err(|e| TeeError::Rejected(e.to_string()))?;
+        let alg = protected.as_map().and_then(|header| {
+            header
+                .iter()
+                .find(|(label, _)| label.as_integer() == Some(COSE_HEADER_ALG.into()))
+                .and_then(|(_, alg)| alg.as_integer())
+        });
+        if alg != Some(COSE_ALG_ES384.into()) {
+            return Err(TeeError::Rejected(
+                "attestation document is not signed with ES384".to_string(),
+            ));
+        }
+
+        let fields = parse_nitro_payload(&cose.payload).map_err(TeeError::Rejected)?;
+        let missing =
+            |name: &str| TeeError::Rejected(format!("attestation document is missing {name}"));
+        let certificate = nitro_field(&fields, "certificate")
+            .and_then(Value::as_bytes)
+            .ok_or_else(|| missing("certificate"))?;
+        let cabundle = nitro_field(&fields, "cabundle")
+            .and_then(Value::as_array)
+            .ok_or_else(|| missing("cabundle"))?
+            .iter()
+            .map(|cert| cert.as_bytes().ok_or_else(|| missing("cabundle")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let timestamp = nitro_field(&fields, "timestamp")
+            .and_then(Value::as_integer)
+            .and_then(|timestamp| u64::try_from(timestamp).ok())
+            .map(Duration::from_millis)
+            .ok_or_else(|| missing("timestamp"))?;
+
+        // The bundle starts with the root and ends with the issuer of the document certificate.
+        if cabundle.first().map(|root| root.as_slice()) != Some(self.root.as_slice()) {
+            return Err(TeeError::Rejected(
+                "certificate chain does not start with the trusted root".to_string(),
+            ));
+        }
+        let chain = cabundle
+            .into_iter()
+            .chain([certificate])
+            .map(|cert| Certificate::from_der(cert).map_err(|e| TeeError::Rejected(e.to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+        for cert in &chain {
+            let validity = &cert.tbs_certificate.validity;
+            if timestamp < validity.not_before.to_unix_duration()
+                || timestamp > validity.not_after.to_unix_duration()
+            {
+                return Err(TeeError::Rejected(
+                    "certificate is not valid at the time of the attestation document".to_string(),
+                ));
+            }
+        }
+        for pair in chain.windows(2) {
+            verify_issued(&pair[0], &pair[1])?;
+        }
+
+        // The signature is over the `Sig_structure` of RFC 8152, with empty external data.
+        let sig_structure = Value::Array(vec![
+            Value::Text("Signature1".to_string()),
+            Value::Bytes(cose.protected),
+            Value::Bytes(Vec::new()),
+            Value::Bytes(cose.payload),
+        ]);
+        let mut message = Vec::new();
+        ciborium::ser::into_writer(&sig_structure, &mut message)
+            .map_err(|e| TeeError::Rejected(e.to_string()))?;
+        let signature = P384Signature::from_slice(&cose.signature)
+            .map_err(|e| TeeError::Rejected(e.to_string()))?;
+        certificate_key(
+            chain
+                .last()
+                .expect("chain contains the document certificate"),
+        )?
+        .verify(&message, &signature)
+        .map_err(|e| TeeError::Rejected(e.to_string()))
+    }
+}
+
+/// A trusted execution environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum TeePlatform {
+    /// An Intel SGX enclave, attested with an ECDSA (DCAP) quote.
+    Sgx,
+    /// An AMD SEV-SNP confidential VM, attested with an attestation report.
+    SevSnp,
+    /// An AWS Nitro Enclave, attested with an attestation document.
+    Nitro,
+}
+
+/// A hardware attestation quote of a Notary.
+#[derive(Clone)]
+pub struct TeeQuote {
+    platform: TeePlatform,
+    quote: Vec<u8>,
+    measurement: Vec<u8>,
+    report_data: Vec<u8>,
+}
+
+opaque_debug::implement!(TeeQuote);
+
+impl TeeQuote {
+    /// Parses a quote as produced by the platform, i.e. an SGX ECDSA quote, an SEV-SNP attestation
+    /// report or a Nitro attestation document.
+    pub fn new(platform: TeePlatform, quote: Vec<u8>) -> Result<Self, TeeError> {
+        let (measurement, report_data) = match platform {
+            TeePlatform::Sgx => parse_sgx(&quote),
+            TeePlatform::SevSnp => parse_snp(&quote),
+            TeePlatform::Nitro => parse_nitro(&quote),
+        }
+        .map_err(|reason| TeeError::Malformed { platform, reason })?;
+
+        Ok(Self {
+            platform,
+            quote,
+            measurement,
+            report_data,
+        })
+    }
+
+    /// Returns the platform of this quote.
+    pub fn platform(&self) -> TeePlatform {
+        self.platform
+    }
+
+    /// Returns the raw quote.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.quote
+    }
+
+    /// Returns the measurement of the code running in the TEE, i.e. `MRENCLAVE` for SGX, the
+    /// launch measurement for SEV-SNP, or `PCR0` for Nitro.
+    pub fn measurement(&self) -> &[u8] {
+        &self.measurement
+    }
+
+    /// Returns the report data of the quote, i.e. the user data for Nitro.
+    pub fn report_data(&self) -> &[u8] {
+        &self.report_data
+    }
+
+    /// Verifies that this quote binds the provided Notary key.
+    ///
+    /// This does not verify the signature of the hardware vendor, see [`TeeQuote::verify`].
+    pub fn verify_binding(&self, notary_key: &NotaryPublicKey) -> Result<(), TeeError> {
+        if !self
+            .report_data
+            .starts_with(&notary_key_binding(notary_key))
+        {
+            return Err(TeeError::KeyMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that this quote binds the provided Notary key, and that it was signed by the
+    /// hardware vendor.
+    ///
+    /// Returns the measurement of the code running in the TEE, which the caller should compare
+    /// against the measurements of the Notary builds it trusts.
+    pub fn verify(
+        &self,
+        notary_key: &NotaryPublicKey,
+        verifier: &impl TeeVerifier,
+    ) -> Result<&[u8], TeeError> {
+        self.verify_binding(notary_key)?;
+        verifier.verify(self)?;
+
+        Ok(self.measurement())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawTeeQuote {
+    platform: TeePlatform,
+    quote: Vec<u8>,
+}
+
+impl Serialize for TeeQuote {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RawTeeQuote {
+            platform: self.platform,
+            quote: self.quote.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TeeQuote {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawTeeQuote { platform, quote } = RawTeeQuote::deserialize(deserializer)?;
+        Self::new(platform, quote).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the digest of a Notary key which a TEE quote must contain in its report data.
+///
+/// The digest is the SHA-256 hash of `b"tlsn-notary-key"` followed by the compressed SEC1
+/// encoding of a P-256 or secp256k1 key, or the compressed encoding of a BLS or Ed25519 key.
+pub fn notary_key_binding(notary_key: &NotaryPublicKey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_BINDING_DOMAIN);
+    match notary_key {
+        NotaryPublicKey::P256(key) => hasher.update(key.to_encoded_point(true).as_bytes()),
+        NotaryPublicKey::Bls(key) => hasher.update(key.to_bytes()),
+        NotaryPublicKey::Secp256k1(key) => hasher.update(key.to_encoded_point(true).as_bytes()),
+        NotaryPublicKey::Ed25519(key) => hasher.update(key.as_bytes()),
+    }
+    hasher.finalize().into()
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], String> {
+    data.get(offset..offset + len).ok_or_else(|| {
+        format!(
+            "expected at least {} bytes, got {}",
+            offset + len,
+            data.len()
+        )
+    })
+}
+
+fn parse_sgx(quote: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let header = slice(quote, 0, SGX_QUOTE_HEADER_LEN)?;
+    let version = u16::from_le_bytes([header[0], header[1]]);
+    if !matches!(version, 3 | 4) {
+        return Err(format!("unsupported quote version {version}"));
+    }
+    let tee_type = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if tee_type != SGX_TEE_TYPE {
+        return Err(format!("unsupported TEE type {tee_type:#x}"));
+    }
+
+    let body = slice(quote, SGX_QUOTE_HEADER_LEN, SGX_REPORT_BODY_LEN)?;
+    Ok((
+        body[SGX_MRENCLAVE_OFFSET..SGX_MRENCLAVE_OFFSET + 32].to_vec(),
+        body[SGX_REPORT_DATA_OFFSET..SGX_REPORT_DATA_OFFSET + 64].to_vec(),
+    ))
+}
+
+fn parse_snp(report: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    slice(report, 0, SNP_REPORT_LEN)?;
+    let version = u32::from_le_bytes(report[0..4].try_into().unwrap());
+    if version < 2 {
+        return Err(format!("unsupported report version {version}"));
+    }
+
+    Ok((
+        report[SNP_MEASUREMENT_OFFSET..SNP_MEASUREMENT_OFFSET + SNP_MEASUREMENT_LEN].to_vec(),
+        report[SNP_REPORT_DATA_OFFSET..SNP_REPORT_DATA_OFFSET + 64].to_vec(),
+    ))
+}
+
+/// The signed parts and the signature of a `COSE_Sign1` structure.
+struct CoseSign1 {
+    protected: Vec<u8>,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+fn parse_cose_sign1(document: &[u8]) -> Result<CoseSign1, String> {
+    let cose: Value = ciborium::de::from_reader(document).map_err(|e| e.to_string())?;
+    let cose = match cose {
+        Value::Tag(COSE_SIGN1_TAG, inner) => *inner,
+        cose => cose,
+    };
+    let Value::Array(items) = cose else {
+        return Err("attestation document is not a COSE_Sign1 structure".to_string());
+    };
+    match <[Value; 4]>::try_from(items) {
+        Ok([Value::Bytes(protected), _, Value::Bytes(payload), Value::Bytes(signature)]) => {
+            Ok(CoseSign1 {
+                protected,
+                payload,
+                signature,
+            })
+        }
+        Ok(_) => Err("COSE_Sign1 fields are not byte strings".to_string()),
+        Err(_) => Err("attestation document is not a COSE_Sign1 structure".to_string()),
+    }
+}
+
+fn parse_nitro_payload(payload: &[u8]) -> Result<Vec<(Value, Value)>, String> {
+    let payload: Value = ciborium::de::from_reader(payload).map_err(|e| e.to_string())?;
+    let Value::Map(fields) = payload else {
+        return Err("attestation document payload is not a map".to_string());
+    };
+    Ok(fields)
+}
+
+fn nitro_field<'a>(fields: &'a [(Value, Value)], name: &str) -> Option<&'a Value> {
+    fields
+        .iter()
+        .find(|(key, _)| key.as_text() == Some(name))
+        .map(|(_, value)| value)
+}
+
+/// Verifies that `cert` was issued and signed by `issuer`.
+fn verify_issued(issuer: &Certificate, cert: &Certificate) -> Result<(), TeeError> {
+    if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        return Err(TeeError::Rejected(
+            "certificate chain is not in order".to_string(),
+        ));
+    }
+    if cert.signature_algorithm.oid != ECDSA_WITH_SHA384 {
+        return Err(TeeError::Rejected(format!(
+            "unsupported certificate signature algorithm: {}",
+            cert.signature_algorithm.oid
+        )));
+    }
+
+    let tbs = cert
+        .tbs_certificate
+        .to_der()
+        .map_err(|e| TeeError::Rejected(e.to_string()))?;
+    let signature = cert
+        .signature
+        .as_bytes()
+        .ok_or_else(|| TeeError::Rejected("certificate signature is not octet aligned".to_string()))
+        .and_then(|der| {
+            P384Signature::from_der(der).map_err(|e| TeeError::Rejected(e.to_string()))
+        })?;
+    certificate_key(issuer)?
+        .verify(&tbs, &signature)
+        .map_err(|e| TeeError::Rejected(e.to_string()))
+}
+
+fn certificate_key(cert: &Certificate) -> Result<P384Key, TeeError> {
+    P384Key::from_sec1_bytes(
+        cert.tbs_certificate
+            .subject_public_key_info
+            .subject_public_key
+            .raw_bytes(),
+    )
+    .map_err(|e| TeeError::Rejected(e.to_string()))
+}
+
+fn parse_nitro(document: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let cose = parse_cose_sign1(document)?;
+    let fields = parse_nitro_payload(&cose.payload)?;
+    let field = |name: &str| nitro_field(&fields, name);
+
+    let pcr0 = match field("pcrs") {
+        Some(Value::Map(pcrs)) => pcrs.iter().find_map(|(index, value)| match (index, value) {
+            (Value::Integer(index), Value::Bytes(pcr)) if i128::from(*index) == 0 => {
+                Some(pcr.clone())
+            }
+            _ => None,
+        }),
+        _ => None,
+    }
+    .ok_or_else(|| "attestation document is missing PCR0".to_string())?;
+    let user_data = match field("user_data") {
+        Some(Value::Bytes(user_data)) => user_data.clone(),
+        _ => Vec::new(),
+    };
+
+    Ok((pcr0, user_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::notary_signing_key;
+
+    struct AcceptAll;
+
+    impl TeeVerifier for AcceptAll {
+        fn verify(&self, _quote: &TeeQuote) -> Result<(), TeeError> {
+            Ok(())
+        }
+    }
+
+    fn notary_key() -> NotaryPublicKey {
+        p256::PublicKey::from(*notary_signing_key().verifying_key()).into()
+    }
+
+    fn report_data(key: &NotaryPublicKey) -> [u8; 64] {
+        let mut report_data = [0u8; 64];
+        report_data[..32].copy_from_slice(&notary_key_binding(key));
+        report_data
+    }
+
+    fn sgx_quote(report_data: &[u8; 64]) -> Vec<u8> {
+        let mut quote = vec![0u8; SGX_QUOTE_HEADER_LEN + SGX_REPORT_BODY_LEN + 64];
+        quote[0] = 3;
+        let body = &mut quote[SGX_QUOTE_HEADER_LEN..];
+        body[SGX_MRENCLAVE_OFFSET..SGX_MRENCLAVE_OFFSET + 32].fill(0xaa);
+        body[SGX_REPORT_DATA_OFFSET..SGX_REPORT_DATA_OFFSET + 64].copy_from_slice(report_data);
+        quote
+    }
+
+    #[test]
+    fn test_sgx_quote() {
+        let key = notary_key();
+        let quote = TeeQuote::new(TeePlatform::Sgx, sgx_quote(&report_data(&key))).unwrap();
+
+        assert_eq!(quote.verify(&key, &AcceptAll).unwrap(), &[0xaa; 32]);
+    }
+
+    #[test]
+    fn test_snp_report() {
+        let key = notary_key();
+        let mut report = vec![0u8; SNP_REPORT_LEN];
+        report[0] = 2;
+        report[SNP_REPORT_DATA_OFFSET..SNP_REPORT_DATA_OFFSET + 64]
+            .copy_from_slice(&report_data(&key));
+        report[SNP_MEASUREMENT_OFFSET..SNP_MEASUREMENT_OFFSET + SNP_MEASUREMENT_LEN].fill(0xbb);
+        let quote = TeeQuote::new(TeePlatform::SevSnp, report).unwrap();
+
+        assert_eq!(quote.verify(&key, &AcceptAll).unwrap(), &[0xbb; 48]);
+    }
+
+    #[test]
+    fn test_nitro_document() {
+        let key = notary_key();
+        let payload = Value::Map(vec![
+            (Value::Text("module_id".into()), Value::Text("i-0".into())),
+            (
+                Value::Text("pcrs".into()),
+                Value::Map(vec![(0.into(), Value::Bytes(vec![0xcc; 48]))]),
+            ),
+            (
+                Value::Text("user_data".into()),
+                Value::Bytes(notary_key_binding(&key).to_vec()),
+            ),
+        ]);
+        let mut payload_bytes = Vec::new();
+        ciborium::ser::into_writer(&payload, &mut payload_bytes).unwrap();
+        let cose = Value::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(Vec::new()),
+                Value::Map(Vec::new()),
+                Value::Bytes(payload_bytes),
+                Value::Bytes(vec![0; 96]),
+            ])),
+        );
+        let mut document = Vec::new();
+        ciborium::ser::into_writer(&cose, &mut document).unwrap();
+        let quote = TeeQuote::new(TeePlatform::Nitro, document).unwrap();
+
+        assert_eq!(quote.verify(&key, &AcceptAll).unwrap(), &[0xcc; 48]);
+    }
+
+    const NITRO_ROOT: &[u8] = include_bytes!("fixtures/testdata/nitro/root.der");
+    const NITRO_INTER: &[u8] = include_bytes!("fixtures/testdata/nitro/inter.der");
+    const NITRO_LEAF: &[u8] = include_bytes!("fixtures/testdata/nitro/leaf.der");
+    const NITRO_OTHER: &[u8] = include_bytes!("fixtures/testdata/nitro/other.der");
+    /// Time of the signed attestation documents, at which the fixture certificates are valid.
+    const NITRO_TIMESTAMP: u64 = 1_710_000_000_000;
+
+    /// Returns an attestation document bound to `key` signed by the fixture certificate, and
+    /// changes its payload after signing if `tamper` is set.
+    fn signed_nitro_document(key: &NotaryPublicKey, timestamp: u64, tamper: bool) -> Vec<u8> {
+        let payload = |pcr0: u8| {
+            let payload = Value::Map(vec![
+                (Value::Text("module_id".into()), Value::Text("i-0".into())),
+                (Value::Text("timestamp".into()), timestamp.into()),
+                (
+                    Value::Text("pcrs".into()),
+                    Value::Map(vec![(0.into(), Value::Bytes(vec![pcr0; 48]))]),
+                ),
+                (
+                    Value::Text("certificate".into()),
+                    Value::Bytes(NITRO_LEAF.to_vec()),
+                ),
+                (
+                    Value::Text("cabundle".into()),
+                    Value::Array(vec![
+                        Value::Bytes(NITRO_ROOT.to_vec()),
+                        Value::Bytes(NITRO_INTER.to_vec()),
+                    ]),
+                ),
+                (
+                    Value::Text("user_data".into()),
+                    Value::Bytes(notary_key_binding(key).to_vec()),
+                ),
+            ]);
+            let mut bytes = Vec::new();
+            ciborium::ser::into_writer(&payload, &mut bytes).unwrap();
+            bytes
+        };
+        let mut protected = Vec::new();
+        ciborium::ser::into_writer(
+            &Value::Map(vec![(COSE_HEADER_ALG.into(), COSE_ALG_ES384.into())]),
+            &mut protected,
+        )
+        .unwrap();
+
+        let mut message = Vec::new();
+        ciborium::ser::into_writer(
+            &Value::Array(vec![
+                Value::Text("Signature1".into()),
+                Value::Bytes(protected.clone()),
+                Value::Bytes(Vec::new()),
+                Value::Bytes(payload(0xcc)),
+            ]),
+            &mut message,
+        )
+        .unwrap();
+        let signing_key = p384::ecdsa::SigningKey::from_slice(&[2; 48]).unwrap();
+        let signature: P384Signature = p384::ecdsa::signature::Signer::sign(&signing_key, &message);
+
+        let cose = Value::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(protected),
+                Value::Map(Vec::new()),
+                Value::Bytes(payload(if tamper { 0xdd } else { 0xcc })),
+                Value::Bytes(signature.to_bytes().to_vec()),
+            ])),
+        );
+        let mut document = Vec::new();
+        ciborium::ser::into_writer(&cose, &mut document).unwrap();
+        document
+    }
+
+    #[test]
+    fn test_nitro_verifier() {
+        let key = notary_key();
+        let verifier = NitroVerifier::new(NITRO_ROOT).unwrap();
+        let quote = TeeQuote::new(
+            TeePlatform::Nitro,
+            signed_nitro_document(&key, NITRO_TIMESTAMP, false),
+        )
+        .unwrap();
+
+        assert_eq!(quote.verify(&key, &verifier).unwrap(), &[0xcc; 48]);
+    }
+
+    #[test]
+    fn test_nitro_verifier_rejects_untrusted_root() {
+        let key = notary_key();
+        let verifier = NitroVerifier::new(NITRO_OTHER).unwrap();
+        let quote = TeeQuote::new(
+            TeePlatform::Nitro,
+            signed_nitro_document(&key, NITRO_TIMESTAMP, false),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            quote.verify(&key, &verifier),
+            Err(TeeError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_nitro_verifier_rejects_tampered_document() {
+        let key = notary_key();
+        let verifier = NitroVerifier::new(NITRO_ROOT).unwrap();
+        let quote = TeeQuote::new(
+            TeePlatform::Nitro,
+            signed_nitro_document(&key, NITRO_TIMESTAMP, true),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            quote.verify(&key, &verifier),
+            Err(TeeError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_nitro_verifier_rejects_expired_certificates() {
+        let key = notary_key();
+        let verifier = NitroVerifier::new(NITRO_ROOT).unwrap();
+        // The fixture certificates are only valid from 2024.
+        let quote = TeeQuote::new(
+            TeePlatform::Nitro,
+            signed_nitro_document(&key, 1_600_000_000_000, false),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            quote.verify(&key, &verifier),
+            Err(TeeError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_nitro_verifier_rejects_other_platforms() {
+        let key = notary_key();
+        let verifier = NitroVerifier::new(NITRO_ROOT).unwrap();
+        let quote = TeeQuote::new(TeePlatform::Sgx, sgx_quote(&report_data(&key))).unwrap();
+
+        assert!(matches!(
+            quote.verify(&key, &verifier),
+            Err(TeeError::Rejected(_))
+        ));
+        assert!(matches!(
+            NitroVerifier::new(&[0x30, 0x00]),
+            Err(TeeError::InvalidCertificate(_))
+        ));
+    }
+
+    #[test]
+    fn test_quote_bound_to_other_key() {
+        let other_key: NotaryPublicKey = crate::BlsSigningKey::from_ikm(&[1; 32])
+            .unwrap()
+            .public_key()
+            .into();
+        let quote = TeeQuote::new(TeePlatform::Sgx, sgx_quote(&report_data(&other_key))).unwrap();
+
+        assert!(matches!(
+            quote.verify_binding(&notary_key()),
+            Err(TeeError::KeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_malformed_quote() {
+        assert!(matches!(
+            TeeQuote::new(TeePlatform::Sgx, vec![3, 0, 2, 0]),
+            Err(TeeError::Malformed { .. })
+        ));
+        assert!(matches!(
+            TeeQuote::new(TeePlatform::Nitro, vec![0xff]),
+            Err(TeeError::Malformed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_quote_serde_roundtrip() {
+        let key = notary_key();
+        let quote = TeeQuote::new(TeePlatform::Sgx, sgx_quote(&report_data(&key))).unwrap();
+        let quote: TeeQuote = bincode::deserialize(&bincode::serialize(&quote).unwrap()).unwrap();
+
+        assert_eq!(quote.platform(), TeePlatform::Sgx);
+        assert!(quote.verify_binding(&key).is_ok());
+    }
+}
@@ -0,0 +1,74 @@
+//! Sources of the current time.
+//!
+//! Checks which depend on the current time, such as the expiry and the maximum age of a session
+//! (see [`ValidityPolicy`](crate::revocation::ValidityPolicy)), take their notion of "now" from a
+//! [`TimeSource`]. The local clock is used by default, see [`SystemClock`], but deployments which
+//! do not trust the local clock can provide a time obtained with an authenticated protocol such
+//! as Roughtime or NTS, and tests can use a [`FixedTime`] to be deterministic.
+
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// An error returned by a [`TimeSource`] which can not provide the current time.
+#[derive(Debug, thiserror::Error)]
+#[error("current time is unavailable: {0}")]
+pub struct TimeError(String);
+
+impl TimeError {
+    /// Creates a new error with the given reason.
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self(reason.into())
+    }
+}
+
+/// A source of the current time.
+pub trait TimeSource: Send + Sync {
+    /// Returns the current time, in seconds since the UNIX epoch.
+    fn now(&self) -> Result<u64, TimeError>;
+}
+
+/// The clock of the local system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> Result<u64, TimeError> {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .map_err(|_| TimeError::new("system clock is set before the UNIX epoch"))
+    }
+}
+
+/// A time which never changes, in seconds since the UNIX epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedTime(pub u64);
+
+impl TimeSource for FixedTime {
+    fn now(&self) -> Result<u64, TimeError> {
+        Ok(self.0)
+    }
+}
+
+impl<F> TimeSource for F
+where
+    F: Fn() -> Result<u64, TimeError> + Send + Sync,
+{
+    fn now(&self) -> Result<u64, TimeError> {
+        self()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_sources() {
+        assert_eq!(FixedTime(1_700_000_000).now().unwrap(), 1_700_000_000);
+        assert!(SystemClock.now().unwrap() > 1_700_000_000);
+
+        let unavailable =
+            || -> Result<u64, TimeError> { Err(TimeError::new("no response from server")) };
+        assert!(unavailable.now().is_err());
+    }
+}
@@ -0,0 +1,387 @@
+//! RFC 3161 timestamps over notarized sessions.
+//!
+//! A Prover can obtain a timestamp token from a Time-Stamp Authority (TSA) over the
+//! [attestation digest](attestation_digest) of a notarized session. The token provides a time
+//! anchor which is independent of the Notary's clock.
+//!
+//! A token is checked to be bound to a session with [`TimestampToken::verify_imprint`]. The TSA's
+//! signature over the token is verified by a [`TimestampAuthority`] provided by the Verifier, as
+//! the set of trusted authorities is a policy decision. [`PinnedTimestampAuthority`] trusts the
+//! authorities with the given certificates.
+
+use cms::{
+    cert::x509::{attr::Attribute, Certificate},
+    content_info::ContentInfo,
+    signed_data::{SignedData, SignerIdentifier},
+};
+use der::{
+    asn1::{Any, ObjectIdentifier, OctetString},
+    Decode, Encode,
+};
+use mpz_core::serialize::CanonicalSerialize;
+use p256::ecdsa::{signature::Verifier, Signature as EcdsaSignature, VerifyingKey};
+use serde::{ser::Serializer, Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use x509_tsp::TstInfo;
+
+use crate::{signature::Signature, SessionHeader};
+
+/// Object identifier of SHA-256, the only supported message imprint hash algorithm.
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+/// Object identifier of ECDSA with SHA-256, the only supported signature algorithm of
+/// [`PinnedTimestampAuthority`].
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+/// Object identifier of the `TSTInfo` content type.
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+/// Object identifier of the content type signed attribute.
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+/// Object identifier of the message digest signed attribute.
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+
+/// An error that can occur while parsing or verifying a timestamp token.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TimestampError {
+    /// The token is not a well-formed RFC 3161 timestamp token.
+    #[error("malformed timestamp token: {0}")]
+    Malformed(String),
+    /// The message imprint uses an unsupported hash algorithm.
+    #[error("unsupported message imprint hash algorithm: {0}")]
+    UnsupportedHashAlgorithm(ObjectIdentifier),
+    /// The message imprint does not match the attestation.
+    #[error("timestamp token is not over the provided attestation")]
+    ImprintMismatch,
+    /// The session is not signed, so there is no attestation to timestamp.
+    #[error("session is not signed by the notary")]
+    MissingSignature,
+    /// A certificate of a timestamp authority is malformed.
+    #[error("malformed timestamp authority certificate: {0}")]
+    InvalidCertificate(String),
+    /// The token is not signed by a trusted authority, or the certificate of the authority was
+    /// not valid at the time asserted by the token.
+    #[error("timestamp token is not signed by a trusted authority")]
+    UntrustedSigner,
+    /// The signature of the authority over the token is invalid.
+    #[error("invalid timestamp authority signature: {0}")]
+    InvalidSignature(String),
+    /// The timestamp authority rejected the token.
+    #[error("timestamp authority verification failed: {0}")]
+    Authority(String),
+}
+
+/// A verifier for the signature of a Time-Stamp Authority over a [`TimestampToken`].
+pub trait TimestampAuthority {
+    /// Verifies that `token` was signed by a trusted authority.
+    fn verify(&self, token: &TimestampToken) -> Result<(), TimestampError>;
+}
+
+/// A [`TimestampAuthority`] which trusts the authorities with the given certificates.
+///
+/// A token is accepted if it is signed with ECDSA P-256 and SHA-256 by the key of one of the
+/// certificates, and the certificate was valid at the time asserted by the token. The certificates
+/// are trusted as they are, without checking a chain to a root.
+#[derive(Debug, Clone)]
+pub struct PinnedTimestampAuthority {
+    certificates: Vec<Certificate>,
+}
+
+impl PinnedTimestampAuthority {
+    /// Creates a new authority trusting the given DER encoded certificates.
+    pub fn new(
+        certificates: impl IntoIterator<Item = impl AsRef<[u8]>>,
+    ) -> Result<Self, TimestampError> {
+        let certificates = certificates
+            .into_iter()
+            .map(|der| Certificate::from_der(der.as_ref()))
+            .collect::<Result<_, _>>()
+            .map_err(|e| TimestampError::InvalidCertificate(e.to_string()))?;
+
+        Ok(Self { certificates })
+    }
+}
+
+impl TimestampAuthority for PinnedTimestampAuthority {
+    fn verify(&self, token: &TimestampToken) -> Result<(), TimestampError> {
+        let signed_data = parse_signed_data(&token.der)?;
+        if signed_data.encap_content_info.econtent_type != ID_CT_TST_INFO {
+            return Err(TimestampError::Malformed(
+                "content is not a TSTInfo".to_string(),
+            ));
+        }
+        let content = encapsulated_content(&signed_data)?;
+
+        let [signer_info] = signed_data.signer_infos.0.as_slice() else {
+            return Err(TimestampError::Malformed(
+                "expected a single signer".to_string(),
+            ));
+        };
+
+        let SignerIdentifier::IssuerAndSerialNumber(sid) = &signer_info.sid else {
+            return Err(TimestampError::UntrustedSigner);
+        };
+        let certificate = self
+            .certificates
+            .iter()
+            .find(|certificate| {
+                certificate.tbs_certificate.issuer == sid.issuer
+                    && certificate.tbs_certificate.serial_number == sid.serial_number
+            })
+            .ok_or(TimestampError::UntrustedSigner)?;
+
+        let validity = &certificate.tbs_certificate.validity;
+        let time = token.info.gen_time.to_unix_duration();
+        if time < validity.not_before.to_unix_duration()
+            || time > validity.not_after.to_unix_duration()
+        {
+            return Err(TimestampError::UntrustedSigner);
+        }
+
+        if signer_info.digest_alg.oid != ID_SHA256 {
+            return Err(TimestampError::UnsupportedHashAlgorithm(
+                signer_info.digest_alg.oid,
+            ));
+        }
+
+        // The signature is over the signed attributes, which bind the content by its digest.
+        let signed_attrs = signer_info
+            .signed_attrs
+            .as_ref()
+            .ok_or_else(|| TimestampError::InvalidSignature("missing signed attributes".into()))?;
+        let invalid = |e: der::Error| TimestampError::InvalidSignature(e.to_string());
+
+        let content_type = signed_attribute(signed_attrs.as_slice(), ID_CONTENT_TYPE)?
+            .decode_as::<ObjectIdentifier>()
+            .map_err(invalid)?;
+        if content_type != ID_CT_TST_INFO {
+            return Err(TimestampError::InvalidSignature(
+                "signed content type is not TSTInfo".to_string(),
+            ));
+        }
+
+        let message_digest = signed_attribute(signed_attrs.as_slice(), ID_MESSAGE_DIGEST)?
+            .decode_as::<OctetString>()
+            .map_err(invalid)?;
+        if message_digest.as_bytes() != Sha256::digest(content.as_bytes()).as_slice() {
+            return Err(TimestampError::InvalidSignature(
+                "signed digest does not match the content".to_string(),
+            ));
+        }
+
+        if signer_info.signature_algorithm.oid != ECDSA_WITH_SHA256 {
+            return Err(TimestampError::InvalidSignature(format!(
+                "unsupported signature algorithm: {}",
+                signer_info.signature_algorithm.oid
+            )));
+        }
+
+        let key = VerifyingKey::from_sec1_bytes(
+            certificate
+                .tbs_certificate
+                .subject_public_key_info
+                .subject_public_key
+                .raw_bytes(),
+        )
+        .map_err(|e| TimestampError::InvalidCertificate(e.to_string()))?;
+        let signature = EcdsaSignature::from_der(signer_info.signature.as_bytes())
+            .map_err(|e| TimestampError::InvalidSignature(e.to_string()))?;
+
+        key.verify(&signed_attrs.to_der().map_err(invalid)?, &signature)
+            .map_err(|e| TimestampError::InvalidSignature(e.to_string()))
+    }
+}
+
+/// An RFC 3161 timestamp token.
+#[derive(Clone)]
+pub struct TimestampToken {
+    der: Vec<u8>,
+    info: TstInfo,
+}
+
+opaque_debug::implement!(TimestampToken);
+
+impl TimestampToken {
+    /// Parses a DER encoded timestamp token, as returned by a TSA in a `TimeStampResp`.
+    pub fn from_der(der: Vec<u8>) -> Result<Self, TimestampError> {
+        let info = parse_tst_info(&der)?;
+
+        Ok(Self { der, info })
+    }
+
+    /// Returns the DER encoding of this token.
+    pub fn as_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Returns the time asserted by the authority, in seconds since the UNIX epoch.
+    pub fn time(&self) -> u64 {
+        self.info.gen_time.to_unix_duration().as_secs()
+    }
+
+    /// Returns the hashed message which was timestamped.
+    pub fn hashed_message(&self) -> &[u8] {
+        self.info.message_imprint.hashed_message.as_bytes()
+    }
+
+    /// Verifies that this token is over the provided attestation digest.
+    ///
+    /// This does not verify the signature of the authority, see [`TimestampToken::verify`].
+    pub fn verify_imprint(&self, digest: &[u8; 32]) -> Result<(), TimestampError> {
+        let algorithm = self.info.message_imprint.hash_algorithm.oid;
+        if algorithm != ID_SHA256 {
+            return Err(TimestampError::UnsupportedHashAlgorithm(algorithm));
+        }
+
+        if self.hashed_message() != digest {
+            return Err(TimestampError::ImprintMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Verifies this token over the provided attestation digest, and that it was signed by the
+    /// authority.
+    ///
+    /// Returns the time asserted by the authority, in seconds since the UNIX epoch.
+    pub fn verify(
+        &self,
+        digest: &[u8; 32],
+        authority: &impl TimestampAuthority,
+    ) -> Result<u64, TimestampError> {
+        self.verify_imprint(digest)?;
+        authority.verify(self)?;
+
+        Ok(self.time())
+    }
+}
+
+impl Serialize for TimestampToken {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.der)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimestampToken {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let der = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_der(der).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the digest of a signed session header which a TSA should timestamp.
+///
+/// The digest is the SHA-256 hash of the header bytes followed by the signature bytes, so the
+/// token anchors the Notary's signature as well as the header.
+pub fn attestation_digest(header: &SessionHeader, signature: &Signature) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(header.to_bytes());
+    hasher.update(signature.to_bytes());
+    hasher.finalize().into()
+}
+
+/// Extracts the `TSTInfo` structure from the signed content of a timestamp token.
+fn parse_tst_info(der: &[u8]) -> Result<TstInfo, TimestampError> {
+    let content = encapsulated_content(&parse_signed_data(der)?)?;
+
+    TstInfo::from_der(content.as_bytes()).map_err(|e| TimestampError::Malformed(e.to_string()))
+}
+
+/// Parses the signed data of a timestamp token.
+fn parse_signed_data(der: &[u8]) -> Result<SignedData, TimestampError> {
+    let malformed = |e: der::Error| TimestampError::Malformed(e.to_string());
+
+    ContentInfo::from_der(der)
+        .map_err(malformed)?
+        .content
+        .decode_as::<SignedData>()
+        .map_err(malformed)
+}
+
+/// Returns the encapsulated content of signed data.
+fn encapsulated_content(signed_data: &SignedData) -> Result<OctetString, TimestampError> {
+    signed_data
+        .encap_content_info
+        .econtent
+        .as_ref()
+        .ok_or_else(|| TimestampError::Malformed("missing encapsulated content".to_string()))?
+        .decode_as::<OctetString>()
+        .map_err(|e| TimestampError::Malformed(e.to_string()))
+}
+
+/// Returns the single value of the signed attribute with the given identifier.
+fn signed_attribute(attrs: &[Attribute], oid: ObjectIdentifier) -> Result<&Any, TimestampError> {
+    let attr = attrs.iter().find(|attr| attr.oid == oid).ok_or_else(|| {
+        TimestampError::InvalidSignature(format!("missing signed attribute {oid}"))
+    })?;
+
+    match attr.values.as_slice() {
+        [value] => Ok(value),
+        _ => Err(TimestampError::InvalidSignature(format!(
+            "expected a single value of signed attribute {oid}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_token_rejects_malformed() {
+        assert!(matches!(
+            TimestampToken::from_der(vec![0x30, 0x03, 0x02, 0x01, 0x01]),
+            Err(TimestampError::Malformed(_))
+        ));
+    }
+
+    const TOKEN: &[u8] = include_bytes!("fixtures/testdata/timestamp/token.der");
+    const TSA: &[u8] = include_bytes!("fixtures/testdata/timestamp/tsa.der");
+    const OTHER: &[u8] = include_bytes!("fixtures/testdata/timestamp/other.der");
+
+    fn digest() -> [u8; 32] {
+        Sha256::digest(b"tlsn").into()
+    }
+
+    #[test]
+    fn test_timestamp_token_verifies() {
+        let token = TimestampToken::from_der(TOKEN.to_vec()).unwrap();
+        let authority = PinnedTimestampAuthority::new([TSA]).unwrap();
+
+        assert_eq!(token.verify(&digest(), &authority).unwrap(), token.time());
+    }
+
+    #[test]
+    fn test_timestamp_token_rejects_other_digest() {
+        let token = TimestampToken::from_der(TOKEN.to_vec()).unwrap();
+        let authority = PinnedTimestampAuthority::new([TSA]).unwrap();
+
+        assert!(matches!(
+            token.verify(&[0u8; 32], &authority),
+            Err(TimestampError::ImprintMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_token_rejects_untrusted_authority() {
+        let token = TimestampToken::from_der(TOKEN.to_vec()).unwrap();
+        let authority = PinnedTimestampAuthority::new([OTHER]).unwrap();
+
+        assert!(matches!(
+            token.verify(&digest(), &authority),
+            Err(TimestampError::UntrustedSigner)
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_token_rejects_tampered_signature() {
+        // The signature is the last field of the token.
+        let mut der = TOKEN.to_vec();
+        *der.last_mut().unwrap() ^= 1;
+        let token = TimestampToken::from_der(der).unwrap();
+        let authority = PinnedTimestampAuthority::new([TSA]).unwrap();
+
+        assert!(matches!(
+            token.verify(&digest(), &authority),
+            Err(TimestampError::InvalidSignature(_))
+        ));
+    }
+}
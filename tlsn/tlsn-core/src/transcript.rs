@@ -44,7 +44,7 @@ impl Transcript {
 }
 
 /// A transcript which may have some data redacted.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RedactedTranscript {
     data: Vec<u8>,
     /// Ranges of `data` which have been authenticated
@@ -169,6 +169,44 @@ pub enum Direction {
     Received,
 }
 
+/// Padding applied to the transcript lengths in the session header, to avoid leaking the exact
+/// length of the sent and received data in presentations.
+///
+/// The Notary signs the padded lengths, so a Verifier can only learn which bucket the lengths
+/// fall into. Bytes beyond the actual length of a transcript can never be opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum LengthPadding {
+    /// Lengths are not padded.
+    #[default]
+    None,
+    /// Lengths are rounded up to the next multiple of the bucket size.
+    Bucket(usize),
+    /// Lengths are rounded up to the next power of two, but at least the provided minimum.
+    PowerOfTwo {
+        /// The minimum padded length.
+        min: usize,
+    },
+}
+
+impl LengthPadding {
+    /// Returns the padded length.
+    pub fn pad(&self, len: usize) -> usize {
+        match *self {
+            LengthPadding::None | LengthPadding::Bucket(0) => len,
+            LengthPadding::Bucket(size) => len.checked_next_multiple_of(size).unwrap_or(len),
+            LengthPadding::PowerOfTwo { min } => {
+                len.max(min).checked_next_power_of_two().unwrap_or(len)
+            }
+        }
+    }
+
+    /// Returns the padded length, capped at `max` but never less than `len`.
+    pub fn pad_bounded(&self, len: usize, max: usize) -> usize {
+        self.pad(len).min(max).max(len)
+    }
+}
+
 /// Returns the value ID for each byte in the provided range set
 pub fn get_value_ids(
     ranges: &RangeSet<usize>,
@@ -195,6 +233,21 @@ mod tests {
         (Transcript::new(sent), Transcript::new(recv))
     }
 
+    #[rstest]
+    #[case::none(LengthPadding::None, 13, 13)]
+    #[case::bucket(LengthPadding::Bucket(16), 13, 16)]
+    #[case::bucket_exact(LengthPadding::Bucket(16), 32, 32)]
+    #[case::power_of_two(LengthPadding::PowerOfTwo { min: 64 }, 13, 64)]
+    #[case::power_of_two_large(LengthPadding::PowerOfTwo { min: 64 }, 65, 128)]
+    fn test_length_padding(
+        #[case] padding: LengthPadding,
+        #[case] len: usize,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(padding.pad(len), expected);
+        assert_eq!(padding.pad_bounded(len, len), len);
+    }
+
     #[rstest]
     fn test_get_bytes_in_ranges(transcripts: (Transcript, Transcript)) {
         let (sent, recv) = transcripts;
@@ -0,0 +1,371 @@
+//! Trust stores of Notary public keys.
+//!
+//! A [`TrustStore`] holds the Notary keys a verifier trusts, along with the period during which
+//! each key was in use, so that sessions notarized before a key rotation remain verifiable while
+//! the retired key can not be used to sign new sessions.
+//!
+//! Validity windows are checked against the time of a session. The time in a session header is
+//! chosen by whoever signs it, so windows keyed on the header time only retire keys which remain
+//! secret: the holder of a leaked key can date a header within the key's window. Either select
+//! keys by the time of a verified timestamp token, see
+//! [`SessionProof::verify_with_trust_store_timestamped`](crate::proof::SessionProof::verify_with_trust_store_timestamped),
+//! or revoke leaked keys, which invalidates all of their sessions, see [`crate::revocation`].
+//!
+//! Trust stores can be loaded from:
+//!
+//! - A key document, which is either a static file or served from the
+//!   [well-known endpoint](well_known_url) of a Notary.
+//! - A DID document, e.g. resolved from a `did:web` identifier, see [`did_web_url`].
+//!
+//! Fetching documents is left to the application, so that it can use its own HTTP client and
+//! caching policy.
+//!
+//! # Key document
+//!
+//! ```json
+//! {
+//!   "keys": [
+//!     { "p256_pem": "-----BEGIN PUBLIC KEY-----\n...", "valid_from": 0, "valid_until": 1700000000 },
+//!     { "p256_pem": "-----BEGIN PUBLIC KEY-----\n...", "valid_from": 1700000000, "label": "2024" },
+//!     { "bls_hex": "a1b2..." }
+//!   ]
+//! }
+//! ```
+
+use p256::pkcs8::DecodePublicKey;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{BlsPublicKey, NotaryPublicKey};
+
+/// The path of the well-known endpoint serving a Notary's key document.
+pub const WELL_KNOWN_PATH: &str = "/.well-known/tlsn-notary-keys.json";
+
+/// An error for [`TrustStore`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TrustStoreError {
+    /// The document is not valid JSON, or does not have the expected structure.
+    #[error("invalid document: {0}")]
+    InvalidDocument(String),
+    /// A key in the document could not be parsed.
+    #[error("invalid key {index}: {reason}")]
+    InvalidKey {
+        /// The index of the key in the document.
+        index: usize,
+        /// The reason the key could not be parsed.
+        reason: String,
+    },
+}
+
+/// A Notary public key trusted during a period of time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedNotaryKey {
+    /// The public key.
+    pub key: NotaryPublicKey,
+    /// The time from which the key is trusted, in seconds since the UNIX epoch.
+    pub valid_from: u64,
+    /// The time from which the key is no longer trusted, in seconds since the UNIX epoch.
+    pub valid_until: Option<u64>,
+    /// A human readable label.
+    pub label: Option<String>,
+}
+
+impl TrustedNotaryKey {
+    /// Returns whether the key is trusted for sessions notarized at the given time.
+    pub fn is_valid_at(&self, time: u64) -> bool {
+        self.valid_from <= time && self.valid_until.map_or(true, |until| time < until)
+    }
+}
+
+/// A set of trusted Notary public keys, including their rotation history.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustStore {
+    keys: Vec<TrustedNotaryKey>,
+}
+
+impl TrustStore {
+    /// Creates an empty trust store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key.
+    pub fn add(&mut self, key: TrustedNotaryKey) -> &mut Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Pins a key, trusting it for sessions notarized at any time.
+    pub fn pin(&mut self, key: impl Into<NotaryPublicKey>) -> &mut Self {
+        self.add(TrustedNotaryKey {
+            key: key.into(),
+            valid_from: 0,
+            valid_until: None,
+            label: None,
+        })
+    }
+
+    /// Rotates to a new key at the given time.
+    ///
+    /// Keys which are trusted indefinitely stop being trusted for sessions notarized at or after
+    /// `at`, and the new key is trusted from `at` onwards.
+    pub fn rotate(&mut self, key: impl Into<NotaryPublicKey>, at: u64) -> &mut Self {
+        for trusted in self.keys.iter_mut() {
+            if trusted.valid_until.is_none() {
+                trusted.valid_until = Some(at.max(trusted.valid_from));
+            }
+        }
+
+        self.add(TrustedNotaryKey {
+            key: key.into(),
+            valid_from: at,
+            valid_until: None,
+            label: None,
+        })
+    }
+
+    /// Adds all keys from `other`.
+    pub fn extend(&mut self, other: TrustStore) {
+        self.keys.extend(other.keys);
+    }
+
+    /// Returns all keys, including retired ones.
+    pub fn keys(&self) -> &[TrustedNotaryKey] {
+        &self.keys
+    }
+
+    /// Returns the keys trusted for sessions notarized at the given time.
+    pub fn keys_at(&self, time: u64) -> impl Iterator<Item = &NotaryPublicKey> {
+        self.keys
+            .iter()
+            .filter(move |trusted| trusted.is_valid_at(time))
+            .map(|trusted| &trusted.key)
+    }
+
+    /// Returns whether the key is trusted for sessions notarized at the given time.
+    pub fn is_trusted(&self, key: &NotaryPublicKey, time: u64) -> bool {
+        self.keys_at(time).any(|trusted| trusted == key)
+    }
+
+    /// Loads a trust store from a key document, see the [module level documentation](self).
+    pub fn from_key_document(document: &str) -> Result<Self, TrustStoreError> {
+        #[derive(Deserialize)]
+        struct KeyDocument {
+            keys: Vec<KeyEntry>,
+        }
+
+        #[derive(Deserialize)]
+        struct KeyEntry {
+            #[serde(default)]
+            p256_pem: Option<String>,
+            #[serde(default)]
+            bls_hex: Option<String>,
+            #[serde(default)]
+            valid_from: u64,
+            #[serde(default)]
+            valid_until: Option<u64>,
+            #[serde(default)]
+            label: Option<String>,
+        }
+
+        let document: KeyDocument = serde_json::from_str(document)
+            .map_err(|e| TrustStoreError::InvalidDocument(e.to_string()))?;
+
+        let mut store = Self::new();
+        for (index, entry) in document.keys.into_iter().enumerate() {
+            let invalid = |reason: String| TrustStoreError::InvalidKey { index, reason };
+
+            let key = match (entry.p256_pem, entry.bls_hex) {
+                (Some(pem), None) => p256::PublicKey::from_public_key_pem(&pem)
+                    .map(NotaryPublicKey::from)
+                    .map_err(|e| invalid(e.to_string()))?,
+                (None, Some(bls)) => hex::decode(bls)
+                    .map_err(|e| invalid(e.to_string()))
+                    .and_then(|bytes| {
+                        BlsPublicKey::from_bytes(&bytes).map_err(|e| invalid(e.to_string()))
+                    })?
+                    .into(),
+                _ => {
+                    return Err(invalid(
+                        "exactly one of p256_pem and bls_hex must be set".to_string(),
+                    ))
+                }
+            };
+
+            if entry
+                .valid_until
+                .is_some_and(|until| until < entry.valid_from)
+            {
+                return Err(invalid("valid_until is before valid_from".to_string()));
+            }
+
+            store.add(TrustedNotaryKey {
+                key,
+                valid_from: entry.valid_from,
+                valid_until: entry.valid_until,
+                label: entry.label,
+            });
+        }
+
+        Ok(store)
+    }
+
+    /// Loads a trust store from a DID document.
+    ///
+    /// Trusts the P-256 keys of the verification methods which are listed as assertion methods,
+    /// or all verification methods if the document does not list any assertion methods. Keys are
+    /// encoded as `publicKeyJwk`.
+    pub fn from_did_document(document: &str) -> Result<Self, TrustStoreError> {
+        let document: Value = serde_json::from_str(document)
+            .map_err(|e| TrustStoreError::InvalidDocument(e.to_string()))?;
+
+        let methods = document
+            .get("verificationMethod")
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                TrustStoreError::InvalidDocument("missing verificationMethod".to_string())
+            })?;
+
+        // Assertion methods are either references to verification methods, or embedded methods.
+        let assertion_methods = document.get("assertionMethod").and_then(Value::as_array);
+        let is_assertion_method = |method: &Value| {
+            let Some(assertion_methods) = assertion_methods else {
+                return true;
+            };
+            let id = method.get("id").and_then(Value::as_str);
+            assertion_methods
+                .iter()
+                .any(|assertion| assertion.as_str().is_some() && assertion.as_str() == id)
+        };
+        let embedded = assertion_methods
+            .into_iter()
+            .flatten()
+            .filter(|assertion| assertion.is_object());
+
+        let mut store = Self::new();
+        for (index, method) in methods
+            .iter()
+            .filter(|method| is_assertion_method(method))
+            .chain(embedded)
+            .enumerate()
+        {
+            let invalid = |reason: String| TrustStoreError::InvalidKey { index, reason };
+
+            let jwk = method
+                .get("publicKeyJwk")
+                .ok_or_else(|| invalid("missing publicKeyJwk".to_string()))?;
+            let jwk: p256::elliptic_curve::JwkEcKey =
+                serde_json::from_value(jwk.clone()).map_err(|e| invalid(e.to_string()))?;
+            let key = p256::PublicKey::from_jwk(&jwk).map_err(|e| invalid(e.to_string()))?;
+
+            store.add(TrustedNotaryKey {
+                key: key.into(),
+                valid_from: 0,
+                valid_until: None,
+                label: method.get("id").and_then(Value::as_str).map(String::from),
+            });
+        }
+
+        Ok(store)
+    }
+}
+
+/// Returns the URL of the well-known endpoint serving the key document of a Notary.
+pub fn well_known_url(host: &str) -> String {
+    format!("https://{host}{WELL_KNOWN_PATH}")
+}
+
+/// Returns the URL of the DID document of a `did:web` identifier, or `None` if the identifier is
+/// not a `did:web` identifier.
+pub fn did_web_url(did: &str) -> Option<String> {
+    let id = did.strip_prefix("did:web:")?;
+    if id.is_empty() {
+        return None;
+    }
+
+    let mut parts = id.split(':');
+    let host = parts.next()?.replace("%3A", ":");
+    let path = parts.collect::<Vec<_>>();
+
+    Some(if path.is_empty() {
+        format!("https://{host}/.well-known/did.json")
+    } else {
+        format!("https://{host}/{}/did.json", path.join("/"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use p256::pkcs8::{EncodePublicKey, LineEnding};
+
+    fn key() -> p256::PublicKey {
+        p256::PublicKey::from(*fixtures::notary_signing_key().verifying_key())
+    }
+
+    #[test]
+    fn test_trust_store_rotation() {
+        let old = key();
+        let new = p256::PublicKey::from(
+            *p256::ecdsa::SigningKey::from_slice(&[2u8; 32])
+                .unwrap()
+                .verifying_key(),
+        );
+
+        let mut store = TrustStore::new();
+        store.pin(old).rotate(new, 100);
+
+        assert!(store.is_trusted(&old.into(), 99));
+        assert!(!store.is_trusted(&old.into(), 100));
+        assert!(!store.is_trusted(&new.into(), 99));
+        assert!(store.is_trusted(&new.into(), 100));
+    }
+
+    #[test]
+    fn test_trust_store_from_key_document() {
+        let pem = key().to_public_key_pem(LineEnding::LF).unwrap();
+        let document = serde_json::json!({
+            "keys": [{ "p256_pem": pem, "valid_from": 10, "valid_until": 20 }]
+        })
+        .to_string();
+
+        let store = TrustStore::from_key_document(&document).unwrap();
+
+        assert!(store.is_trusted(&key().into(), 10));
+        assert!(!store.is_trusted(&key().into(), 20));
+    }
+
+    #[test]
+    fn test_trust_store_from_did_document() {
+        let jwk: Value = serde_json::from_str(&key().to_jwk_string()).unwrap();
+        let document = serde_json::json!({
+            "id": "did:web:notary.example",
+            "verificationMethod": [
+                { "id": "did:web:notary.example#key-1", "type": "JsonWebKey2020", "publicKeyJwk": jwk },
+                { "id": "did:web:notary.example#key-2", "type": "JsonWebKey2020", "publicKeyJwk": {} }
+            ],
+            "assertionMethod": ["did:web:notary.example#key-1"]
+        })
+        .to_string();
+
+        let store = TrustStore::from_did_document(&document).unwrap();
+
+        assert_eq!(store.keys().len(), 1);
+        assert!(store.is_trusted(&key().into(), 0));
+    }
+
+    #[test]
+    fn test_did_web_url() {
+        assert_eq!(
+            did_web_url("did:web:notary.example").unwrap(),
+            "https://notary.example/.well-known/did.json"
+        );
+        assert_eq!(
+            did_web_url("did:web:notary.example%3A8443:notaries:eu").unwrap(),
+            "https://notary.example:8443/notaries/eu/did.json"
+        );
+        assert!(did_web_url("did:key:z6Mk").is_none());
+    }
+}
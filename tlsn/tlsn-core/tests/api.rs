@@ -17,15 +17,18 @@ use tls_core::{
     msgs::{enums::SignatureScheme, handshake::DigitallySignedStruct},
 };
 
-use mpz_core::{commit::HashCommit, serialize::CanonicalSerialize};
+use mpz_core::commit::HashCommit;
 
 use tlsn_core::{
     commitment::TranscriptCommitmentBuilder,
     fixtures,
     msg::SignedSessionHeader,
-    proof::{SessionProof, SubstringsProof},
-    HandshakeSummary, NotarizedSession, ServerName, SessionData, SessionHeader, Signature,
-    Transcript,
+    proof::{
+        default_cert_verifier, HandshakeItem, HandshakeItemKind, SessionProof, SubstringsProof,
+        TLS_1_2,
+    },
+    Direction, HandshakeSummary, NotarizedSession, ServerName, SessionData, SessionHeader,
+    Signature, Transcript,
 };
 
 #[test]
@@ -113,23 +116,26 @@ fn test_api() {
         HandshakeSummary::new(time + 60, ephem_key.clone(), hs_commitment),
     );
 
-    let signature: P256Signature = signer.sign(&header.to_bytes());
+    let signature: P256Signature = signer.sign(&header.signing_message().unwrap());
     // Notary creates a msg and sends it to Prover
     let msg = SignedSessionHeader {
         header,
         signature: signature.into(),
+        tee_quote: None,
     };
 
     //---------------------------------------
     let msg_bytes = bincode::serialize(&msg).unwrap();
-    let SignedSessionHeader { header, signature } = bincode::deserialize(&msg_bytes).unwrap();
+    let SignedSessionHeader {
+        header, signature, ..
+    } = bincode::deserialize(&msg_bytes).unwrap();
     //---------------------------------------
 
     // Prover verifies the signature
     #[allow(irrefutable_let_patterns)]
     if let Signature::P256(signature) = signature {
         notary_verifing_key
-            .verify(&header.to_bytes(), &signature)
+            .verify(&header.signing_message().unwrap(), &signature)
             .unwrap();
     } else {
         panic!("Notary signature is not P256");
@@ -173,6 +179,24 @@ fn test_api() {
         .verify_with_default_cert_verifier(notary_pubkey)
         .unwrap();
 
+    let server_name = session_proof
+        .verify_server_identity(&default_cert_verifier())
+        .unwrap();
+    assert_eq!(server_name.as_str(), testdata.dns_name.as_str());
+
+    // Handshake items are derived from the verified handshake data.
+    let items = session_proof
+        .verify_handshake_items(&HandshakeItemKind::ALL, &default_cert_verifier())
+        .unwrap();
+    assert_eq!(
+        items,
+        vec![
+            HandshakeItem::ServerName(ServerName::Dns(testdata.dns_name.clone())),
+            HandshakeItem::ProtocolVersion(TLS_1_2),
+            HandshakeItem::CertFingerprint(mpz_core::utils::blake3(&testdata.ee.0)),
+        ]
+    );
+
     let SessionProof {
         header,
         session_info,
@@ -187,6 +211,22 @@ fn test_api() {
 
     let (sent, recv) = substrings_proof.verify(&header).unwrap();
 
-    assert_eq!(&sent.data()[range1], b"se".as_slice());
-    assert_eq!(&recv.data()[range2], b"ec".as_slice());
+    assert_eq!(&sent.data()[range1.clone()], b"se".as_slice());
+    assert_eq!(&recv.data()[range2.clone()], b"ec".as_slice());
+
+    // Streaming verification yields the same disclosed data.
+    let substrings_proof: SubstringsProof = bincode::deserialize(&substrings_proof_bytes).unwrap();
+    let slices = substrings_proof
+        .verify_streaming(&header)
+        .unwrap()
+        .map(|(direction, slice)| (direction, slice.range(), slice.into_bytes()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        slices,
+        vec![
+            (Direction::Sent, range1, b"se".to_vec()),
+            (Direction::Received, range2, b"ec".to_vec()),
+        ]
+    );
 }
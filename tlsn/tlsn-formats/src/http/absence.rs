@@ -0,0 +1,252 @@
+use spansy::Spanned;
+use tlsn_core::{
+    commitment::CommitmentKind,
+    proof::{SubstringsProofBuilder, SubstringsProofBuilderError},
+    Direction, RedactedTranscript,
+};
+
+use crate::http::{Header, Request, Response};
+
+/// An error for header absence verification.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HeaderAbsenceError {
+    /// The header is present.
+    #[error("header \"{0}\" is present")]
+    Present(String),
+    /// A byte which is needed to determine the structure of the header section is not authenticated.
+    #[error("byte at offset {0} in the header section is not authenticated")]
+    Unauthenticated(usize),
+    /// The header section is malformed.
+    #[error("header section is malformed at offset {0}")]
+    Malformed(usize),
+    /// The header section ends before the empty line which terminates it.
+    #[error("header section is truncated")]
+    Truncated,
+}
+
+/// Reveals the structure of the header section of a request, so that a verifier can check
+/// which headers are absent.
+///
+/// This reveals the request line, and every header excluding its value unless `reveal_values`
+/// is set. It relies on the commitments created by the default [`HttpCommit`](crate::http::HttpCommit)
+/// implementation.
+pub fn reveal_request_header_section(
+    builder: &mut SubstringsProofBuilder,
+    request: &Request,
+    reveal_values: bool,
+) -> Result<(), SubstringsProofBuilderError> {
+    if !request.headers.is_empty() || request.body.is_some() {
+        builder.reveal_sent(&request.without_data(), CommitmentKind::Blake3)?;
+        builder.reveal_sent(&request.request.target, CommitmentKind::Blake3)?;
+    } else {
+        builder.reveal_sent(request, CommitmentKind::Blake3)?;
+        return Ok(());
+    }
+
+    reveal_headers(builder, Direction::Sent, &request.headers, reveal_values)
+}
+
+/// Reveals the structure of the header section of a response, so that a verifier can check
+/// which headers are absent.
+///
+/// See [`reveal_request_header_section`].
+pub fn reveal_response_header_section(
+    builder: &mut SubstringsProofBuilder,
+    response: &Response,
+    reveal_values: bool,
+) -> Result<(), SubstringsProofBuilderError> {
+    if !response.headers.is_empty() || response.body.is_some() {
+        builder.reveal_recv(&response.without_data(), CommitmentKind::Blake3)?;
+    } else {
+        builder.reveal_recv(response, CommitmentKind::Blake3)?;
+        return Ok(());
+    }
+
+    reveal_headers(
+        builder,
+        Direction::Received,
+        &response.headers,
+        reveal_values,
+    )
+}
+
+fn reveal_headers(
+    builder: &mut SubstringsProofBuilder,
+    direction: Direction,
+    headers: &[Header],
+    reveal_values: bool,
+) -> Result<(), SubstringsProofBuilderError> {
+    for header in headers {
+        if reveal_values || header.value.span().is_empty() {
+            builder.reveal(header, direction, CommitmentKind::Blake3)?;
+        } else {
+            builder.reveal(&header.without_value(), direction, CommitmentKind::Blake3)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies that a header is absent from the HTTP message starting at `start` in a redacted
+/// transcript.
+///
+/// The start line and the name of every header, along with all line terminators, must be
+/// authenticated so that the complete header section can be enumerated.
+///
+/// # Warning
+///
+/// If `allow_redacted_values` is set, header values (and the request target) may be redacted.
+/// A malicious prover can then hide a line terminator followed by the absent header inside a
+/// redacted value, see the [crate level documentation](crate). Only set this if that is
+/// acceptable for the application.
+///
+/// # Arguments
+///
+/// * `transcript` - The redacted transcript containing the message.
+/// * `start` - The offset of the first byte of the message in the transcript.
+/// * `name` - The name of the header, compared case-insensitively.
+/// * `allow_redacted_values` - Whether header values may be redacted.
+pub fn verify_header_absence(
+    transcript: &RedactedTranscript,
+    start: usize,
+    name: &str,
+    allow_redacted_values: bool,
+) -> Result<(), HeaderAbsenceError> {
+    let data = transcript.data();
+    let mut authed = vec![false; data.len()];
+    for range in transcript.authed().iter_ranges() {
+        authed[range].fill(true);
+    }
+
+    let is_crlf = |pos: usize| {
+        pos + 1 < data.len() && authed[pos] && authed[pos + 1] && &data[pos..pos + 2] == b"\r\n"
+    };
+
+    // Finds the authenticated line terminator ending the field which starts at `pos`.
+    let end_of_line = |mut pos: usize| -> Result<usize, HeaderAbsenceError> {
+        loop {
+            if pos + 1 >= data.len() {
+                return Err(HeaderAbsenceError::Truncated);
+            } else if is_crlf(pos) {
+                return Ok(pos);
+            } else if !authed[pos] {
+                if !allow_redacted_values {
+                    return Err(HeaderAbsenceError::Unauthenticated(pos));
+                }
+            } else if matches!(data[pos], b'\r' | b'\n') {
+                return Err(HeaderAbsenceError::Malformed(pos));
+            }
+            pos += 1;
+        }
+    };
+
+    // Start line
+    let mut pos = end_of_line(start)? + 2;
+
+    loop {
+        if pos + 1 >= data.len() {
+            return Err(HeaderAbsenceError::Truncated);
+        } else if is_crlf(pos) {
+            return Ok(());
+        }
+
+        // Header name, which must be fully authenticated.
+        let name_start = pos;
+        loop {
+            if pos >= data.len() {
+                return Err(HeaderAbsenceError::Truncated);
+            } else if !authed[pos] {
+                return Err(HeaderAbsenceError::Unauthenticated(pos));
+            }
+
+            match data[pos] {
+                b':' if pos > name_start => break,
+                b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^'
+                | b'_' | b'`' | b'|' | b'~' => {}
+                c if c.is_ascii_alphanumeric() => {}
+                _ => return Err(HeaderAbsenceError::Malformed(pos)),
+            }
+            pos += 1;
+        }
+
+        let header_name = &data[name_start..pos];
+        if header_name.eq_ignore_ascii_case(name.as_bytes()) {
+            return Err(HeaderAbsenceError::Present(name.to_string()));
+        }
+
+        pos = end_of_line(pos + 1)? + 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{
+        commitment::TranscriptCommitmentBuilder, fixtures, proof::SubstringsProofBuilder,
+        Transcript,
+    };
+
+    use crate::http::{DefaultHttpCommitter, HttpCommit, HttpTranscript};
+
+    static TX: &[u8] = b"GET /info HTTP/1.1\r\nHost: localhost\r\nCookie: secret\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+    fn verify(reveal_values: bool, name: &str) -> Result<(), HeaderAbsenceError> {
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+        let transcript = HttpTranscript::parse(&transcript_tx, &transcript_rx).unwrap();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        DefaultHttpCommitter::default()
+            .commit_transcript(&mut builder, &transcript)
+            .unwrap();
+        let commitments = builder.build().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        reveal_request_header_section(&mut builder, &transcript.requests[0], reveal_values)
+            .unwrap();
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len());
+        let (sent, _) = proof.verify(&header).unwrap();
+
+        verify_header_absence(&sent, 0, name, !reveal_values)
+    }
+
+    #[test]
+    fn test_header_absence() {
+        verify(true, "x-forwarded-for").unwrap();
+        verify(false, "x-forwarded-for").unwrap();
+    }
+
+    #[test]
+    fn test_header_absence_present() {
+        assert!(matches!(
+            verify(false, "COOKIE"),
+            Err(HeaderAbsenceError::Present(_))
+        ));
+    }
+
+    #[test]
+    fn test_header_absence_redacted_values_not_allowed() {
+        let transcript_tx = Transcript::new(TX);
+        let transcript = RedactedTranscript::new(
+            TX.len(),
+            vec![tlsn_core::TranscriptSlice::new(
+                0..TX.len() - 8,
+                transcript_tx.data()[..TX.len() - 8].to_vec(),
+            )],
+        );
+
+        assert!(matches!(
+            verify_header_absence(&transcript, 0, "x-forwarded-for", false),
+            Err(HeaderAbsenceError::Unauthenticated(_))
+        ));
+    }
+}
@@ -0,0 +1,633 @@
+//! Decoding of HTTP message bodies.
+//!
+//! Servers often send bodies with the chunked transfer coding, a compressed content coding, or
+//! both, in which case the bytes of the transcript are not the human meaningful body.
+//!
+//! The chunked transfer coding only adds framing around the body data, so the decoded body is a
+//! set of ranges of the transcript. The Prover commits to the raw body, to the decoded body, and
+//! to the framing separately, see [`DecodedMessage::commit`], so that it can disclose the decoded
+//! body, or parts of it, like any other data.
+//!
+//! Content codings (gzip, deflate, brotli) can not be expressed as ranges of the transcript, so
+//! compressed bodies are always committed to as raw bytes. A verifier can decode a compressed
+//! body only if it is disclosed in full, see [`DecodedMessage::decode_disclosed`]. Redacting data
+//! inside a compressed body is not supported.
+//!
+//! Messages are parsed with the same parser as [`HttpTranscript`](crate::http::HttpTranscript),
+//! see [`DecodedMessage::from_response`]; this module only decodes their bodies.
+
+use std::{io::Read, ops::Range};
+
+use bytes::Bytes;
+use tlsn_core::{
+    commitment::{TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    Direction, RedactedTranscript,
+};
+use utils::range::{RangeDifference, RangeSet, RangeUnion, ToRangeSet};
+
+use crate::{
+    http::{exchange::bounds, Body, Header, MessageKind, Request, Requests, Response, Responses},
+    ParseError,
+};
+
+/// The maximum length of a decoded body, which bounds the memory used to decompress a body.
+pub const MAX_DECODED_LEN: usize = 1 << 24;
+
+/// An error for decoding HTTP messages.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The message could not be parsed.
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    /// The framing of the body, or a header which determines it, is malformed.
+    #[error("message is malformed at offset {0}")]
+    Malformed(usize),
+    /// The message ends before its body is complete.
+    #[error("message is truncated")]
+    Truncated,
+    /// The message uses a transfer coding other than chunked.
+    #[error("unsupported transfer coding: {0}")]
+    UnsupportedTransferCoding(String),
+    /// The message uses an unknown content coding.
+    #[error("unsupported content coding: {0}")]
+    UnsupportedContentCoding(String),
+    /// The body could not be decompressed.
+    #[error("failed to decompress body: {0}")]
+    Decompress(#[from] std::io::Error),
+    /// The decoded body is longer than [`MAX_DECODED_LEN`].
+    #[error("decoded body is longer than {MAX_DECODED_LEN} bytes")]
+    TooLarge,
+    /// A byte which is needed to decode the body is not authenticated.
+    #[error("byte at offset {0} is needed to decode the body but is not authenticated")]
+    Unauthenticated(usize),
+}
+
+/// A content coding of an HTTP body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ContentCoding {
+    /// No coding.
+    Identity,
+    /// gzip.
+    Gzip,
+    /// deflate.
+    Deflate,
+    /// Brotli.
+    Brotli,
+}
+
+impl ContentCoding {
+    /// Parses a content coding from its name, as used in the `Content-Encoding` header.
+    pub fn parse(name: &str) -> Result<Self, DecodeError> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "identity" => Ok(Self::Identity),
+            "gzip" | "x-gzip" => Ok(Self::Gzip),
+            "deflate" => Ok(Self::Deflate),
+            "br" => Ok(Self::Brotli),
+            name => Err(DecodeError::UnsupportedContentCoding(name.to_string())),
+        }
+    }
+
+    /// Decodes data encoded with this coding.
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        match self {
+            Self::Identity => Ok(data.to_vec()),
+            Self::Gzip => read_bounded(flate2::read::MultiGzDecoder::new(data)),
+            // "deflate" is specified to use the zlib format, but some servers send raw deflate data.
+            Self::Deflate => match read_bounded(flate2::read::ZlibDecoder::new(data)) {
+                Err(DecodeError::Decompress(_)) => {
+                    read_bounded(flate2::read::DeflateDecoder::new(data))
+                }
+                res => res,
+            },
+            Self::Brotli => read_bounded(brotli::Decompressor::new(data, 4096)),
+        }
+    }
+}
+
+fn read_bounded(reader: impl Read) -> Result<Vec<u8>, DecodeError> {
+    let mut out = Vec::new();
+    reader
+        .take(MAX_DECODED_LEN as u64 + 1)
+        .read_to_end(&mut out)?;
+
+    if out.len() > MAX_DECODED_LEN {
+        return Err(DecodeError::TooLarge);
+    }
+
+    Ok(out)
+}
+
+/// The body of an HTTP message, with the location of its data in the transcript.
+#[derive(Debug, Clone)]
+pub struct DecodedBody {
+    raw: Range<usize>,
+    data: RangeSet<usize>,
+    chunked: bool,
+    content_codings: Vec<ContentCoding>,
+}
+
+impl DecodedBody {
+    /// Returns the range of the raw body in the transcript, including any chunk framing.
+    pub fn raw(&self) -> &Range<usize> {
+        &self.raw
+    }
+
+    /// Returns the ranges of the transcript which contain the body data, ie. the body with the
+    /// chunked transfer coding removed.
+    pub fn data(&self) -> &RangeSet<usize> {
+        &self.data
+    }
+
+    /// Returns the ranges of the transcript which contain the chunk framing.
+    pub fn framing(&self) -> RangeSet<usize> {
+        RangeSet::from(self.raw.clone()).difference(&self.data)
+    }
+
+    /// Returns `true` if the body uses the chunked transfer coding.
+    pub fn is_chunked(&self) -> bool {
+        self.chunked
+    }
+
+    /// Returns the content codings of the body, in the order they were applied.
+    pub fn content_codings(&self) -> &[ContentCoding] {
+        &self.content_codings
+    }
+
+    /// Returns `true` if the body is compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.content_codings
+            .iter()
+            .any(|coding| *coding != ContentCoding::Identity)
+    }
+
+    /// Commits to the raw body and, if it is chunked, to the body data and the chunk framing
+    /// separately.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        if self.raw.is_empty() {
+            return Ok(());
+        }
+
+        builder.commit(&self.raw, direction)?;
+
+        if self.chunked {
+            if self.data.iter_ranges().any(|range| !range.is_empty()) {
+                builder.commit(&self.data, direction)?;
+            }
+            builder.commit(&self.framing(), direction)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the body from the transcript data.
+    pub fn decode(&self, transcript: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let mut body = Vec::new();
+        for range in self.data.iter_ranges() {
+            body.extend_from_slice(transcript.get(range).ok_or(DecodeError::Truncated)?);
+        }
+
+        for coding in self.content_codings.iter().rev() {
+            body = coding.decode(&body)?;
+        }
+
+        Ok(body)
+    }
+}
+
+/// An HTTP message parsed for decoding its body.
+#[derive(Debug, Clone)]
+pub struct DecodedMessage {
+    kind: MessageKind,
+    head: Range<usize>,
+    header_values: RangeSet<usize>,
+    framing_headers: RangeSet<usize>,
+    body: Option<DecodedBody>,
+}
+
+impl DecodedMessage {
+    /// Prepares a request parsed with [`parse_request`](crate::http::parse_request) for decoding
+    /// its body.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request.
+    /// * `data` - The transcript data the request was parsed from.
+    pub fn from_request(request: &Request, data: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            MessageKind::Request,
+            request,
+            &request.headers,
+            request.body.as_ref(),
+            data,
+        )
+    }
+
+    /// Prepares a response parsed with [`parse_response`](crate::http::parse_response) for
+    /// decoding its body.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The response.
+    /// * `data` - The transcript data the response was parsed from.
+    pub fn from_response(response: &Response, data: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            MessageKind::Response,
+            response,
+            &response.headers,
+            response.body.as_ref(),
+            data,
+        )
+    }
+
+    /// Returns the kind of the message.
+    pub fn kind(&self) -> MessageKind {
+        self.kind
+    }
+
+    /// Returns the range of the start line and header section in the transcript.
+    pub fn head(&self) -> &Range<usize> {
+        &self.head
+    }
+
+    /// Returns the body, if the message has one.
+    pub fn body(&self) -> Option<&DecodedBody> {
+        self.body.as_ref()
+    }
+
+    /// Returns the range of the message in the transcript.
+    pub fn span(&self) -> Range<usize> {
+        let end = self
+            .body
+            .as_ref()
+            .map(|body| body.raw.end)
+            .unwrap_or(self.head.end);
+
+        self.head.start..end
+    }
+
+    /// Returns the ranges a verifier needs to decode the body: the head excluding header values,
+    /// the headers which determine how the body is encoded, and the chunk framing.
+    pub fn structure(&self) -> RangeSet<usize> {
+        let mut structure = RangeSet::from(self.head.clone())
+            .difference(&self.header_values)
+            .union(&self.framing_headers);
+
+        if let Some(body) = &self.body {
+            structure = structure.union(&body.framing());
+        }
+
+        structure
+    }
+
+    /// Commits to the [structure](Self::structure) of the message and to its body, see
+    /// [`DecodedBody::commit`].
+    ///
+    /// This is meant to be used in addition to an [`HttpCommit`](crate::http::HttpCommit)
+    /// implementation, which commits to the head of the message.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        builder.commit(&self.structure(), direction)?;
+
+        if let Some(body) = &self.body {
+            body.commit(builder, direction)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the body from a redacted transcript.
+    ///
+    /// The [structure](Self::structure) of the message must be authenticated, as well as the
+    /// entire body data if the body is compressed. Redacted parts of an uncompressed body are
+    /// returned as zeros.
+    pub fn decode_disclosed(
+        &self,
+        transcript: &RedactedTranscript,
+    ) -> Result<Option<Vec<u8>>, DecodeError> {
+        let mut required = self.structure();
+        if let Some(body) = self.body.as_ref().filter(|body| body.is_compressed()) {
+            required = required.union(&body.data);
+        }
+
+        let mut authed = vec![false; transcript.data().len()];
+        for range in transcript.authed().iter_ranges() {
+            authed[range].fill(true);
+        }
+
+        if let Some(pos) = required
+            .iter_ranges()
+            .flatten()
+            .find(|pos| !authed.get(*pos).copied().unwrap_or(false))
+        {
+            return Err(DecodeError::Unauthenticated(pos));
+        }
+
+        self.body
+            .as_ref()
+            .map(|body| body.decode(transcript.data()))
+            .transpose()
+    }
+}
+
+/// Parses the requests in the transcript data for decoding their bodies.
+pub fn decode_requests(data: &[u8]) -> Result<Vec<DecodedMessage>, DecodeError> {
+    Requests::new(Bytes::copy_from_slice(data))
+        .map(|request| DecodedMessage::from_request(&request?, data))
+        .collect()
+}
+
+/// Parses the responses in the transcript data for decoding their bodies.
+pub fn decode_responses(data: &[u8]) -> Result<Vec<DecodedMessage>, DecodeError> {
+    Responses::new(Bytes::copy_from_slice(data))
+        .map(|response| DecodedMessage::from_response(&response?, data))
+        .collect()
+}
+
+/// Locates the body data of a message parsed by spansy, using the headers which determine how
+/// the body is encoded.
+fn decode_message(
+    kind: MessageKind,
+    message: &dyn ToRangeSet<usize>,
+    headers: &[Header],
+    body: Option<&Body>,
+    data: &[u8],
+) -> Result<DecodedMessage, DecodeError> {
+    let span = bounds(message);
+    let raw_body = body.map(|body| bounds(body));
+    let head = span.start..raw_body.as_ref().map_or(span.end, |body| body.start);
+
+    let mut header_values = RangeSet::default();
+    let mut framing_headers = RangeSet::default();
+    let mut chunked = false;
+    let mut content_codings = Vec::new();
+    for header in headers {
+        let value = header
+            .to_range_set()
+            .difference(&header.without_value().to_range_set());
+        header_values = header_values.union(&value);
+
+        let name = header.name.as_str().to_ascii_lowercase();
+        if !matches!(
+            name.as_str(),
+            "content-length" | "transfer-encoding" | "content-encoding"
+        ) {
+            continue;
+        }
+        framing_headers = framing_headers.union(&header.to_range_set());
+
+        let offset = value
+            .iter_ranges()
+            .next()
+            .map_or(head.start, |range| range.start);
+        let value = value
+            .iter_ranges()
+            .flat_map(|range| data[range].iter().copied())
+            .collect::<Vec<_>>();
+        let value = std::str::from_utf8(&value).map_err(|_| DecodeError::Malformed(offset))?;
+        match name.as_str() {
+            "transfer-encoding" => {
+                for coding in value.split(',').map(str::trim) {
+                    match coding.to_ascii_lowercase().as_str() {
+                        "chunked" => chunked = true,
+                        "identity" | "" => {}
+                        coding => {
+                            return Err(DecodeError::UnsupportedTransferCoding(coding.to_string()))
+                        }
+                    }
+                }
+            }
+            "content-encoding" => {
+                for coding in value.split(',').filter(|coding| !coding.trim().is_empty()) {
+                    content_codings.push(ContentCoding::parse(coding)?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let body = if chunked && has_body(kind, &data[head.clone()]) {
+        Some(decode_chunked(data, head.end, content_codings)?)
+    } else {
+        raw_body.map(|raw| DecodedBody {
+            data: RangeSet::from(raw.clone()),
+            raw,
+            chunked: false,
+            content_codings,
+        })
+    };
+
+    Ok(DecodedMessage {
+        kind,
+        head,
+        header_values,
+        framing_headers,
+        body,
+    })
+}
+
+/// Returns whether a message with the given head has a body, which responses with a 1xx, 204 or
+/// 304 status never have.
+fn has_body(kind: MessageKind, head: &[u8]) -> bool {
+    match kind {
+        MessageKind::Request => true,
+        MessageKind::Response => !matches!(
+            head.split(|b| *b == b' ').nth(1),
+            Some([b'1', _, _]) | Some(b"204") | Some(b"304")
+        ),
+    }
+}
+
+fn decode_chunked(
+    data: &[u8],
+    start: usize,
+    content_codings: Vec<ContentCoding>,
+) -> Result<DecodedBody, DecodeError> {
+    let mut pos = start;
+    let mut chunks = RangeSet::default();
+    loop {
+        let line = read_line(data, pos)?;
+        let size = &data[line.clone()];
+        // Chunk extensions are ignored
+        let size = &size[..size.iter().position(|b| *b == b';').unwrap_or(size.len())];
+        let size = std::str::from_utf8(size)
+            .ok()
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+            .ok_or(DecodeError::Malformed(line.start))?;
+        pos = line.end + 2;
+
+        if size == 0 {
+            // Skip the trailer section
+            loop {
+                let line = read_line(data, pos)?;
+                pos = line.end + 2;
+                if line.is_empty() {
+                    break;
+                }
+            }
+            break;
+        }
+
+        let end = pos
+            .checked_add(size)
+            .filter(|end| end + 2 <= data.len())
+            .ok_or(DecodeError::Truncated)?;
+        if &data[end..end + 2] != b"\r\n" {
+            return Err(DecodeError::Malformed(end));
+        }
+
+        chunks = chunks.union(&(pos..end));
+        pos = end + 2;
+    }
+
+    Ok(DecodedBody {
+        raw: start..pos,
+        data: chunks,
+        chunked: true,
+        content_codings,
+    })
+}
+
+/// Returns the range of the line starting at `pos`, excluding the line terminator.
+fn read_line(data: &[u8], pos: usize) -> Result<Range<usize>, DecodeError> {
+    data.get(pos..)
+        .and_then(|rest| rest.windows(2).position(|w| w == b"\r\n"))
+        .map(|len| pos..pos + len)
+        .ok_or(DecodeError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tlsn_core::{
+        commitment::CommitmentKind, fixtures, proof::SubstringsProofBuilder, Transcript,
+    };
+
+    static CHUNKED: &[u8] =
+        b"HTTP/1.1 200 OK\r\nSet-Cookie: secret\r\nTransfer-Encoding: chunked\r\n\r\n\
+    7\r\n{\"foo\":\r\n6;ext=1\r\n \"bar\"\r\n1\r\n}\r\n0\r\n\r\n";
+
+    fn gzip_response(body: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body).unwrap();
+        let body = encoder.finish().unwrap();
+
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(&body);
+        response
+    }
+
+    fn prove(tx: &[u8], rx: &[u8], reveal: &[RangeSet<usize>]) -> RedactedTranscript {
+        let transcript_tx = Transcript::new(tx.to_vec());
+        let transcript_rx = Transcript::new(rx.to_vec());
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(tx, rx),
+            tx.len(),
+            rx.len(),
+        );
+        for message in decode_responses(rx).unwrap() {
+            message.commit(&mut builder, Direction::Received).unwrap();
+        }
+        let commitments = builder.build().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        for ranges in reveal {
+            builder.reveal_recv(ranges, CommitmentKind::Blake3).unwrap();
+        }
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), tx.len(), rx.len());
+        proof.verify(&header).unwrap().1
+    }
+
+    #[test]
+    fn test_decode_chunked_body() {
+        let messages = decode_responses(CHUNKED).unwrap();
+        assert_eq!(messages.len(), 1);
+
+        let body = messages[0].body().unwrap();
+        assert!(body.is_chunked());
+        assert_eq!(body.decode(CHUNKED).unwrap(), b"{\"foo\": \"bar\"}");
+    }
+
+    #[test]
+    fn test_decode_disclosed_chunked_body() {
+        let message = decode_responses(CHUNKED).unwrap().remove(0);
+        let body = message.body().unwrap();
+
+        // The cookie value stays redacted.
+        let recv = prove(
+            b"GET / HTTP/1.1\r\n\r\n",
+            CHUNKED,
+            &[message.structure(), body.data().clone()],
+        );
+
+        let disclosed = decode_responses(recv.data()).unwrap().remove(0);
+        assert_eq!(
+            disclosed.decode_disclosed(&recv).unwrap().unwrap(),
+            b"{\"foo\": \"bar\"}"
+        );
+    }
+
+    #[test]
+    fn test_decode_disclosed_compressed_body() {
+        let rx = gzip_response(b"{\"foo\": \"bar\"}");
+        let message = decode_responses(&rx).unwrap().remove(0);
+        let body = message.body().unwrap();
+        assert_eq!(body.content_codings(), &[ContentCoding::Gzip]);
+
+        let recv = prove(
+            b"GET / HTTP/1.1\r\n\r\n",
+            &rx,
+            &[message.structure(), RangeSet::from(body.raw().clone())],
+        );
+        assert_eq!(
+            message.decode_disclosed(&recv).unwrap().unwrap(),
+            b"{\"foo\": \"bar\"}"
+        );
+
+        // A compressed body can not be decoded if it is partially redacted.
+        let recv = prove(b"GET / HTTP/1.1\r\n\r\n", &rx, &[message.structure()]);
+        assert!(matches!(
+            message.decode_disclosed(&recv),
+            Err(DecodeError::Unauthenticated(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_unsupported_transfer_coding() {
+        assert!(matches!(
+            decode_responses(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n"),
+            Err(DecodeError::UnsupportedTransferCoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_parsed_response() {
+        let rx = gzip_response(b"{\"foo\": \"bar\"}");
+        let response = crate::http::parse_response(&rx).unwrap();
+
+        let message = DecodedMessage::from_response(&response, &rx).unwrap();
+        let body = message.body().unwrap();
+        assert_eq!(
+            RangeSet::from(body.raw().clone()),
+            response.body.as_ref().unwrap().to_range_set()
+        );
+        assert_eq!(message.span(), 0..rx.len());
+        assert_eq!(body.decode(&rx).unwrap(), b"{\"foo\": \"bar\"}");
+    }
+}
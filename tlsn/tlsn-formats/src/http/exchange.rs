@@ -0,0 +1,215 @@
+use std::ops::Range;
+
+use tlsn_core::{
+    commitment::{CommitmentKind, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    proof::{SubstringsProofBuilder, SubstringsProofBuilderError},
+    Direction,
+};
+use utils::range::{RangeSet, ToRangeSet};
+
+use crate::http::{Request, Response};
+
+/// A request and the response to it.
+///
+/// HTTP/1.1 connections are persistent, so several requests can be sent over the same notarized
+/// connection, e.g. a login request followed by a data request. The server responds to them in
+/// order, and each exchange records where one request and its response are in the transcript, so
+/// that it can be committed to and disclosed independently of the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    request: usize,
+    response: Option<usize>,
+    sent: Range<usize>,
+    received: Option<Range<usize>>,
+}
+
+impl Exchange {
+    /// Returns the index of the request in the transcript.
+    pub fn request(&self) -> usize {
+        self.request
+    }
+
+    /// Returns the index of the final response in the transcript, if it was received.
+    pub fn response(&self) -> Option<usize> {
+        self.response
+    }
+
+    /// Returns the range of the sent transcript containing the request.
+    pub fn sent(&self) -> &Range<usize> {
+        &self.sent
+    }
+
+    /// Returns the range of the received transcript containing the response, including any
+    /// interim (1xx) responses preceding it.
+    pub fn received(&self) -> Option<&Range<usize>> {
+        self.received.as_ref()
+    }
+
+    /// Commits to the request and the response as a whole.
+    ///
+    /// Commitments which already exist are skipped, so this can be used alongside an
+    /// [`HttpCommit`](crate::http::HttpCommit) implementation.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        for (range, direction) in self.ranges() {
+            match builder.commit(&RangeSet::from(range), direction) {
+                Ok(_) | Err(TranscriptCommitmentBuilderError::Duplicate(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reveals the request and the response, which must have been committed to with
+    /// [`Exchange::commit`].
+    pub fn reveal(
+        &self,
+        builder: &mut SubstringsProofBuilder,
+        kind: CommitmentKind,
+    ) -> Result<(), SubstringsProofBuilderError> {
+        for (range, direction) in self.ranges() {
+            builder.reveal(&RangeSet::from(range), direction, kind)?;
+        }
+
+        Ok(())
+    }
+
+    fn ranges(&self) -> impl Iterator<Item = (Range<usize>, Direction)> {
+        std::iter::once((self.sent.clone(), Direction::Sent)).chain(
+            self.received
+                .clone()
+                .map(|range| (range, Direction::Received)),
+        )
+    }
+}
+
+/// Pairs requests with their responses.
+///
+/// Interim responses are attributed to the exchange of the request they precede the final
+/// response of.
+pub(crate) fn pair(requests: &[Request], responses: &[Response], rx: &[u8]) -> Vec<Exchange> {
+    let mut responses = responses.iter().enumerate();
+
+    requests
+        .iter()
+        .enumerate()
+        .map(|(request, message)| {
+            let mut exchange = Exchange {
+                request,
+                response: None,
+                sent: bounds(message),
+                received: None,
+            };
+
+            for (index, message) in responses.by_ref() {
+                let range = bounds(message);
+                let interim = is_interim(&rx[range.clone()]);
+
+                exchange.received = Some(match exchange.received {
+                    Some(received) => received.start..range.end,
+                    None => range,
+                });
+
+                if !interim {
+                    exchange.response = Some(index);
+                    break;
+                }
+            }
+
+            exchange
+        })
+        .collect()
+}
+
+/// Returns the range from the start to the end of a message, or a part of it.
+pub(crate) fn bounds(message: &dyn ToRangeSet<usize>) -> Range<usize> {
+    let ranges = message.to_range_set();
+    let mut ranges = ranges.iter_ranges();
+    let first = ranges.next().unwrap_or_default();
+    let end = ranges.last().map_or(first.end, |range| range.end);
+
+    first.start..end
+}
+
+/// Returns whether a response is informational, except for `101 Switching Protocols` which is
+/// the final response to an upgrade request.
+fn is_interim(response: &[u8]) -> bool {
+    match response.split(|b| *b == b' ').nth(1) {
+        Some(status) => status.len() == 3 && status[0] == b'1' && status != b"101",
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpTranscript;
+    use tlsn_core::{fixtures, Transcript};
+
+    static TX: &[u8] = b"POST /login HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\n\
+    Content-Length: 5\r\n\r\nalice\
+    GET /balance HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n\
+    HTTP/1.1 200 OK\r\nSet-Cookie: session=abc\r\nContent-Length: 2\r\n\r\nok\
+    HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\n100";
+
+    #[test]
+    fn test_exchanges() {
+        let transcript = HttpTranscript::parse(&Transcript::new(TX), &Transcript::new(RX)).unwrap();
+        let exchanges = transcript.exchanges();
+
+        assert_eq!(exchanges.len(), 2);
+
+        let login = &exchanges[0];
+        assert_eq!(login.request(), 0);
+        assert_eq!(login.response(), Some(1));
+        assert!(TX[login.sent().clone()].starts_with(b"POST /login"));
+        assert!(TX[login.sent().clone()].ends_with(b"alice"));
+        assert!(RX[login.received().unwrap().clone()].starts_with(b"HTTP/1.1 100"));
+        assert!(RX[login.received().unwrap().clone()].ends_with(b"ok"));
+
+        let balance = &exchanges[1];
+        assert_eq!(balance.response(), Some(2));
+        assert_eq!(balance.sent().end, TX.len());
+        assert_eq!(balance.received().unwrap().end, RX.len());
+    }
+
+    #[test]
+    fn test_exchange_reveal() {
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+        let transcript = HttpTranscript::parse(&transcript_tx, &transcript_rx).unwrap();
+        let balance = &transcript.exchanges()[1];
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        for exchange in transcript.exchanges() {
+            exchange.commit(&mut builder).unwrap();
+        }
+        let commitments = builder.build().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        balance
+            .reveal(&mut builder, CommitmentKind::Blake3)
+            .unwrap();
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len());
+        let (sent, recv) = proof.verify(&header).unwrap();
+
+        assert!(sent.data()[..balance.sent().start].iter().all(|b| *b == 0));
+        assert_eq!(
+            &sent.data()[balance.sent().clone()],
+            &TX[balance.sent().clone()]
+        );
+        let received = balance.received().unwrap().clone();
+        assert!(recv.data()[..received.start].iter().all(|b| *b == 0));
+        assert_eq!(&recv.data()[received.clone()], &RX[received]);
+    }
+}
@@ -1,9 +1,27 @@
 //! Tooling for working with HTTP data.
 
+mod absence;
 mod commit;
+mod decode;
+mod exchange;
+mod named;
+mod preset;
+mod secret;
 mod session;
 
+pub use absence::{
+    reveal_request_header_section, reveal_response_header_section, verify_header_absence,
+    HeaderAbsenceError,
+};
 pub use commit::{DefaultHttpCommitter, HttpCommit, HttpCommitError};
+pub use decode::{
+    decode_requests, decode_responses, ContentCoding, DecodeError, DecodedBody, DecodedMessage,
+    MAX_DECODED_LEN,
+};
+pub use exchange::Exchange;
+pub use named::{NamedCommitment, NamedCommitmentError, NamedCommitments};
+pub use preset::{RedactionPreset, Redactions};
+pub use secret::{RequestTemplate, SecretError, SecretRequest, Secrets};
 pub use session::NotarizedHttpSession;
 
 #[doc(hidden)]
@@ -31,6 +49,7 @@ pub struct HttpTranscript {
     pub requests: Vec<Request>,
     /// The responses received from the server.
     pub responses: Vec<Response>,
+    exchanges: Vec<Exchange>,
 }
 
 impl HttpTranscript {
@@ -38,12 +57,19 @@ impl HttpTranscript {
     pub fn parse(tx: &Transcript, rx: &Transcript) -> Result<Self, spansy::ParseError> {
         let requests = Requests::new(tx.data().clone()).collect::<Result<Vec<_>, _>>()?;
         let responses = Responses::new(rx.data().clone()).collect::<Result<Vec<_>, _>>()?;
+        let exchanges = exchange::pair(&requests, &responses, rx.data());
 
         Ok(Self {
             requests,
             responses,
+            exchanges,
         })
     }
+
+    /// Returns the requests paired with their responses, in the order they were sent.
+    pub fn exchanges(&self) -> &[Exchange] {
+        &self.exchanges
+    }
 }
 
 #[cfg(test)]
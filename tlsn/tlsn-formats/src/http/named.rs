@@ -0,0 +1,313 @@
+//! Commitments named after the structure of an HTTP transcript.
+//!
+//! [`NamedCommitments`] derives a commitment for each element of the parsed requests and
+//! responses, so that they can be revealed by name instead of by their ranges in the transcript.
+
+use std::collections::HashMap;
+
+use tlsn_core::{
+    commitment::{CommitmentKind, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    proof::{SubstringsProofBuilder, SubstringsProofBuilderError},
+    Direction, Transcript,
+};
+use utils::range::{RangeDifference, RangeSet, ToRangeSet};
+
+use crate::http::{Body, Header, HttpTranscript};
+
+/// Named commitment error.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum NamedCommitmentError {
+    /// There is no commitment with the given name.
+    #[error("no commitment named {0:?}")]
+    UnknownName(String),
+    /// An error occurred while revealing the commitment.
+    #[error(transparent)]
+    Proof(#[from] SubstringsProofBuilderError),
+}
+
+/// A commitment to an element of an HTTP transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedCommitment {
+    name: String,
+    direction: Direction,
+    ranges: RangeSet<usize>,
+}
+
+impl NamedCommitment {
+    /// Returns the name of the commitment.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the direction of the transcript the commitment is to.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the ranges of the transcript the commitment is to.
+    pub fn ranges(&self) -> &RangeSet<usize> {
+        &self.ranges
+    }
+}
+
+/// Commitments to the elements of an HTTP transcript, retrievable by name.
+///
+/// The commitments are named as follows:
+///
+/// - `request[0]`, `response[0]`: the entire message.
+/// - `request[0].request_line`, `response[0].status_line`: the first line, excluding the line
+///   break.
+/// - `request[0].target`: the request target.
+/// - `request[0].headers.host`: the entire header. Header names are lowercased, and repeated
+///   headers are indexed from their second occurrence, e.g. `response[0].headers.set-cookie[1]`.
+/// - `request[0].headers.host.value`: the value of the header.
+/// - `request[0].body`, `response[0].body`: the body content.
+#[derive(Debug, Clone, Default)]
+pub struct NamedCommitments {
+    commitments: Vec<NamedCommitment>,
+}
+
+impl NamedCommitments {
+    /// Derives the named commitments of an HTTP transcript.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The HTTP transcript parsed from `tx` and `rx`.
+    /// * `tx` - The sent transcript.
+    /// * `rx` - The received transcript.
+    pub fn new(transcript: &HttpTranscript, tx: &Transcript, rx: &Transcript) -> Self {
+        let mut named = Self::default();
+
+        for (idx, request) in transcript.requests.iter().enumerate() {
+            let prefix = format!("request[{idx}]");
+            named.add_message(&prefix, Direction::Sent, tx.data(), request, "request_line");
+            named.add(
+                format!("{prefix}.target"),
+                Direction::Sent,
+                request.request.target.to_range_set(),
+            );
+            named.add_headers(&prefix, Direction::Sent, &request.headers);
+            named.add_body(&prefix, Direction::Sent, request.body.as_ref());
+        }
+
+        for (idx, response) in transcript.responses.iter().enumerate() {
+            let prefix = format!("response[{idx}]");
+            named.add_message(
+                &prefix,
+                Direction::Received,
+                rx.data(),
+                response,
+                "status_line",
+            );
+            named.add_headers(&prefix, Direction::Received, &response.headers);
+            named.add_body(&prefix, Direction::Received, response.body.as_ref());
+        }
+
+        named
+    }
+
+    /// Returns the commitment with the given name.
+    pub fn get(&self, name: &str) -> Option<&NamedCommitment> {
+        self.commitments
+            .iter()
+            .find(|commitment| commitment.name == name)
+    }
+
+    /// Returns an iterator over the commitments, in transcript order.
+    pub fn iter(&self) -> impl Iterator<Item = &NamedCommitment> {
+        self.commitments.iter()
+    }
+
+    /// Returns an iterator over the names of the commitments.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commitments.iter().map(|commitment| commitment.name())
+    }
+
+    /// Commits to every named element.
+    ///
+    /// Commitments which already exist are skipped, so this can be used alongside an
+    /// [`HttpCommit`](crate::http::HttpCommit) implementation.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        for commitment in &self.commitments {
+            match builder.commit(&commitment.ranges, commitment.direction) {
+                Ok(_) | Err(TranscriptCommitmentBuilderError::Duplicate(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reveals the element with the given name, which must have been committed to with
+    /// [`NamedCommitments::commit`].
+    pub fn reveal(
+        &self,
+        builder: &mut SubstringsProofBuilder,
+        name: &str,
+        kind: CommitmentKind,
+    ) -> Result<(), NamedCommitmentError> {
+        let commitment = self
+            .get(name)
+            .ok_or_else(|| NamedCommitmentError::UnknownName(name.to_string()))?;
+
+        builder.reveal(&commitment.ranges, commitment.direction, kind)?;
+
+        Ok(())
+    }
+
+    fn add(&mut self, name: String, direction: Direction, ranges: RangeSet<usize>) {
+        // Empty elements, e.g. a header without a value, can not be committed to.
+        if !ranges.is_empty() {
+            self.commitments.push(NamedCommitment {
+                name,
+                direction,
+                ranges,
+            });
+        }
+    }
+
+    fn add_message(
+        &mut self,
+        prefix: &str,
+        direction: Direction,
+        data: &[u8],
+        message: &dyn ToRangeSet<usize>,
+        line: &str,
+    ) {
+        let ranges = message.to_range_set();
+        let start = ranges.iter_ranges().next().map_or(0, |range| range.start);
+        let len = data[start..]
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .unwrap_or_default();
+
+        self.add(prefix.to_string(), direction, ranges);
+        self.add(
+            format!("{prefix}.{line}"),
+            direction,
+            RangeSet::from(start..start + len),
+        );
+    }
+
+    fn add_headers(&mut self, prefix: &str, direction: Direction, headers: &[Header]) {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for header in headers {
+            let name = header.name.as_str().to_ascii_lowercase();
+            let count = seen.entry(name.clone()).or_default();
+            let name = match *count {
+                0 => format!("{prefix}.headers.{name}"),
+                n => format!("{prefix}.headers.{name}[{n}]"),
+            };
+            *count += 1;
+
+            let value = header
+                .to_range_set()
+                .difference(&header.without_value().to_range_set());
+
+            self.add(name.clone(), direction, header.to_range_set());
+            self.add(format!("{name}.value"), direction, value);
+        }
+    }
+
+    fn add_body(&mut self, prefix: &str, direction: Direction, body: Option<&Body>) {
+        if let Some(body) = body {
+            self.add(
+                format!("{prefix}.body"),
+                direction,
+                body.content.to_range_set(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tlsn_core::fixtures;
+
+    static TX: &[u8] = b"POST /login HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nalice";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\n\
+    Content-Length: 2\r\n\r\nok";
+
+    fn data<'a>(named: &NamedCommitments, name: &str, data: &'a [u8]) -> Vec<&'a [u8]> {
+        named
+            .get(name)
+            .unwrap()
+            .ranges()
+            .iter_ranges()
+            .map(|range| &data[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_named_commitments() {
+        let (tx, rx) = (Transcript::new(TX), Transcript::new(RX));
+        let transcript = HttpTranscript::parse(&tx, &rx).unwrap();
+        let named = NamedCommitments::new(&transcript, &tx, &rx);
+
+        assert_eq!(data(&named, "request[0]", TX), [TX]);
+        assert_eq!(
+            data(&named, "request[0].request_line", TX),
+            [b"POST /login HTTP/1.1"]
+        );
+        assert_eq!(data(&named, "request[0].target", TX), [b"/login"]);
+        assert_eq!(
+            data(&named, "request[0].headers.host.value", TX),
+            [b"localhost"]
+        );
+        assert_eq!(data(&named, "request[0].body", TX), [b"alice"]);
+        assert_eq!(
+            data(&named, "response[0].status_line", RX),
+            [b"HTTP/1.1 200 OK"]
+        );
+        assert_eq!(
+            data(&named, "response[0].headers.set-cookie.value", RX),
+            [b"a=1"]
+        );
+        assert_eq!(
+            data(&named, "response[0].headers.set-cookie[1].value", RX),
+            [b"b=2"]
+        );
+        assert_eq!(data(&named, "response[0].body", RX), [b"ok"]);
+        assert!(named.get("response[1]").is_none());
+    }
+
+    #[test]
+    fn test_named_commitments_reveal() {
+        let (tx, rx) = (Transcript::new(TX), Transcript::new(RX));
+        let transcript = HttpTranscript::parse(&tx, &rx).unwrap();
+        let named = NamedCommitments::new(&transcript, &tx, &rx);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        named.commit(&mut builder).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &tx, &rx);
+        named
+            .reveal(
+                &mut builder,
+                "response[0].status_line",
+                CommitmentKind::Blake3,
+            )
+            .unwrap();
+        assert!(matches!(
+            named.reveal(&mut builder, "response[0].trailer", CommitmentKind::Blake3),
+            Err(NamedCommitmentError::UnknownName(_))
+        ));
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len());
+        let (_, recv) = proof.verify(&header).unwrap();
+
+        assert_eq!(&recv.data()[..15], b"HTTP/1.1 200 OK");
+        assert!(recv.data()[15..].iter().all(|b| *b == 0));
+    }
+}
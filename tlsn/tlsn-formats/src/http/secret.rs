@@ -0,0 +1,377 @@
+//! Requests with secret parts.
+//!
+//! Requests often carry credentials, e.g. an `Authorization` header or an API key in the query,
+//! which must never be disclosed. A [`RequestTemplate`] marks these parts as secret when the
+//! request is built, and the resulting [`SecretRequest`] records where they are. The secret
+//! ranges are collected in [`Secrets`], which commits to and discloses the rest of the transcript
+//! without them, so that revealing "everything" can not accidentally reveal a credential.
+
+use tlsn_core::{
+    commitment::{CommitmentKind, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    proof::{SubstringsProofBuilder, SubstringsProofBuilderError},
+    Direction,
+};
+use utils::range::{RangeDifference, RangeSet, RangeUnion};
+
+/// An error for [`Secrets`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SecretError {
+    /// The ranges contain secret data.
+    #[error("{direction:?} data at offset {offset} is secret")]
+    Secret {
+        /// The direction of the transcript.
+        direction: Direction,
+        /// The offset of the first secret byte.
+        offset: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct TemplateHeader {
+    name: String,
+    value: Vec<u8>,
+    secret: bool,
+}
+
+/// A builder for HTTP/1.1 requests in which sensitive parts are marked as secret.
+///
+/// Only the values of secret headers, query parameters and bodies are secret, their names and the
+/// rest of the request remain public.
+#[derive(Debug, Clone)]
+pub struct RequestTemplate {
+    method: String,
+    path: String,
+    query: Vec<(String, String, bool)>,
+    headers: Vec<TemplateHeader>,
+    body: Option<(Vec<u8>, bool)>,
+}
+
+impl RequestTemplate {
+    /// Creates a new template for a request with the given method and path.
+    pub fn new(method: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            method: method.into(),
+            path: path.into(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// Adds a query parameter.
+    pub fn query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into(), false));
+        self
+    }
+
+    /// Adds a query parameter with a secret value, e.g. an API key.
+    pub fn secret_query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into(), true));
+        self
+    }
+
+    /// Adds a header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        self.headers.push(TemplateHeader {
+            name: name.into(),
+            value: value.into(),
+            secret: false,
+        });
+        self
+    }
+
+    /// Adds a header with a secret value, e.g. `Authorization` or `Cookie`.
+    pub fn secret_header(mut self, name: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        self.headers.push(TemplateHeader {
+            name: name.into(),
+            value: value.into(),
+            secret: true,
+        });
+        self
+    }
+
+    /// Sets the body. A `Content-Length` header is added when the request is built.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some((body.into(), false));
+        self
+    }
+
+    /// Sets a secret body. A `Content-Length` header is added when the request is built.
+    pub fn secret_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some((body.into(), true));
+        self
+    }
+
+    /// Builds the request.
+    pub fn build(self) -> SecretRequest {
+        let mut request = SecretRequest {
+            bytes: Vec::new(),
+            secrets: RangeSet::default(),
+        };
+
+        request.push(self.method.as_bytes(), false);
+        request.push(b" ", false);
+        request.push(self.path.as_bytes(), false);
+        for (i, (name, value, secret)) in self.query.iter().enumerate() {
+            request.push(if i == 0 { b"?" } else { b"&" }, false);
+            request.push(name.as_bytes(), false);
+            request.push(b"=", false);
+            request.push(value.as_bytes(), *secret);
+        }
+        request.push(b" HTTP/1.1\r\n", false);
+
+        for header in &self.headers {
+            request.push(header.name.as_bytes(), false);
+            request.push(b": ", false);
+            request.push(&header.value, header.secret);
+            request.push(b"\r\n", false);
+        }
+
+        if let Some((body, _)) = &self.body {
+            request.push(
+                format!("Content-Length: {}\r\n", body.len()).as_bytes(),
+                false,
+            );
+        }
+        request.push(b"\r\n", false);
+
+        if let Some((body, secret)) = &self.body {
+            request.push(body, *secret);
+        }
+
+        request
+    }
+}
+
+/// A request built from a [`RequestTemplate`].
+#[derive(Debug, Clone)]
+pub struct SecretRequest {
+    bytes: Vec<u8>,
+    secrets: RangeSet<usize>,
+}
+
+impl SecretRequest {
+    fn push(&mut self, bytes: &[u8], secret: bool) {
+        let start = self.bytes.len();
+        self.bytes.extend_from_slice(bytes);
+        if secret && !bytes.is_empty() {
+            self.secrets = self.secrets.union(&(start..self.bytes.len()));
+        }
+    }
+
+    /// Returns the bytes of the request, which are to be sent to the server.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the ranges of the request which are secret, relative to the start of the request.
+    pub fn secrets(&self) -> &RangeSet<usize> {
+        &self.secrets
+    }
+}
+
+/// Secret ranges of a transcript which are excluded from disclosure by default.
+#[derive(Debug, Clone, Default)]
+pub struct Secrets {
+    sent: RangeSet<usize>,
+    recv: RangeSet<usize>,
+}
+
+impl Secrets {
+    /// Creates an empty set of secrets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the given ranges of the transcript as secret.
+    pub fn add(&mut self, direction: Direction, ranges: &RangeSet<usize>) -> &mut Self {
+        match direction {
+            Direction::Sent => self.sent = self.sent.union(ranges),
+            Direction::Received => self.recv = self.recv.union(ranges),
+        }
+        self
+    }
+
+    /// Marks the secret parts of a request as secret.
+    ///
+    /// `offset` is the position of the request in the sent transcript, ie. the number of bytes
+    /// sent before it.
+    pub fn add_request(&mut self, offset: usize, request: &SecretRequest) -> &mut Self {
+        let ranges = request
+            .secrets()
+            .iter_ranges()
+            .fold(RangeSet::default(), |ranges, range| {
+                ranges.union(&(range.start + offset..range.end + offset))
+            });
+        self.add(Direction::Sent, &ranges)
+    }
+
+    /// Returns the secret ranges of the transcript.
+    pub fn get(&self, direction: Direction) -> &RangeSet<usize> {
+        match direction {
+            Direction::Sent => &self.sent,
+            Direction::Received => &self.recv,
+        }
+    }
+
+    /// Returns the ranges of a transcript of length `len` which are not secret.
+    pub fn public(&self, direction: Direction, len: usize) -> RangeSet<usize> {
+        if len == 0 {
+            return RangeSet::default();
+        }
+
+        RangeSet::from(0..len).difference(self.get(direction))
+    }
+
+    /// Returns an error if the ranges contain secret data.
+    pub fn check(&self, direction: Direction, ranges: &RangeSet<usize>) -> Result<(), SecretError> {
+        let secret = ranges.difference(&ranges.difference(self.get(direction)));
+        match secret.iter_ranges().find(|range| !range.is_empty()) {
+            Some(range) => Err(SecretError::Secret {
+                direction,
+                offset: range.start,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Commits to the public ranges of each direction as a whole, and to each secret range
+    /// separately so that it can still be revealed explicitly.
+    ///
+    /// Commitments which already exist are skipped, so this can be used alongside an
+    /// [`HttpCommit`](crate::http::HttpCommit) implementation.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        sent_len: usize,
+        recv_len: usize,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        for (direction, len) in [(Direction::Sent, sent_len), (Direction::Received, recv_len)] {
+            let ranges = std::iter::once(self.public(direction, len))
+                .chain(self.get(direction).iter_ranges().map(RangeSet::from));
+
+            for ranges in ranges.filter(|ranges| !is_empty(ranges)) {
+                match builder.commit(&ranges, direction) {
+                    Ok(_) | Err(TranscriptCommitmentBuilderError::Duplicate(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reveals everything except the secrets, which must have been committed to with
+    /// [`Secrets::commit`].
+    pub fn reveal_public(
+        &self,
+        builder: &mut SubstringsProofBuilder,
+        sent_len: usize,
+        recv_len: usize,
+        kind: CommitmentKind,
+    ) -> Result<(), SubstringsProofBuilderError> {
+        for (direction, len) in [(Direction::Sent, sent_len), (Direction::Received, recv_len)] {
+            let public = self.public(direction, len);
+            if !is_empty(&public) {
+                builder.reveal(&public, direction, kind)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_empty(ranges: &RangeSet<usize>) -> bool {
+    ranges.iter_ranges().all(|range| range.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tlsn_core::{fixtures, Transcript};
+
+    fn request() -> SecretRequest {
+        RequestTemplate::new("POST", "/api/transfer")
+            .query("currency", "EUR")
+            .secret_query("api_key", "k-123")
+            .header("Host", "bank.example")
+            .secret_header("Authorization", "Bearer secret-token")
+            .body("{\"amount\":10}")
+            .build()
+    }
+
+    #[test]
+    fn test_secret_request() {
+        let request = request();
+
+        assert_eq!(
+            request.as_bytes(),
+            b"POST /api/transfer?currency=EUR&api_key=k-123 HTTP/1.1\r\n\
+            Host: bank.example\r\nAuthorization: Bearer secret-token\r\n\
+            Content-Length: 13\r\n\r\n{\"amount\":10}"
+        );
+
+        let secrets = request
+            .secrets()
+            .iter_ranges()
+            .map(|range| request.as_bytes()[range].to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            secrets,
+            [b"k-123".to_vec(), b"Bearer secret-token".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_secrets_check() {
+        let request = request();
+        let mut secrets = Secrets::new();
+        secrets.add_request(10, &request);
+
+        let key = request.secrets().iter_ranges().next().unwrap();
+        assert!(secrets
+            .check(Direction::Sent, &RangeSet::from(0..10 + key.start))
+            .is_ok());
+
+        let err = secrets
+            .check(Direction::Sent, &RangeSet::from(0..10 + key.end))
+            .unwrap_err();
+        assert!(matches!(err, SecretError::Secret { offset, .. } if offset == 10 + key.start));
+    }
+
+    #[test]
+    fn test_secrets_reveal_public() {
+        let request = request();
+        let tx = request.as_bytes().to_vec();
+        let rx = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_vec();
+        let transcript_tx = Transcript::new(tx.clone());
+        let transcript_rx = Transcript::new(rx.clone());
+
+        let mut secrets = Secrets::new();
+        secrets.add_request(0, &request);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(&tx, &rx),
+            tx.len(),
+            rx.len(),
+        );
+        secrets.commit(&mut builder, tx.len(), rx.len()).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        secrets
+            .reveal_public(&mut builder, tx.len(), rx.len(), CommitmentKind::Blake3)
+            .unwrap();
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), tx.len(), rx.len());
+        let (sent, recv) = proof.verify(&header).unwrap();
+
+        assert_eq!(recv.data(), &rx[..]);
+        for range in request.secrets().iter_ranges() {
+            assert!(sent.data()[range].iter().all(|b| *b == 0));
+        }
+        assert_eq!(&sent.data()[..5], b"POST ");
+    }
+}
@@ -1,21 +1,27 @@
 use tlsn_core::{proof::SessionProof, NotarizedSession};
 
-use crate::http::HttpTranscript;
+use crate::http::{HttpTranscript, NamedCommitments};
 
 /// A notarized HTTP session.
 #[derive(Debug)]
 pub struct NotarizedHttpSession {
     session: NotarizedSession,
     transcript: HttpTranscript,
+    named: NamedCommitments,
 }
 
 impl NotarizedHttpSession {
     /// Creates a new notarized HTTP session.
     #[doc(hidden)]
-    pub fn new(session: NotarizedSession, transcript: HttpTranscript) -> Self {
+    pub fn new(
+        session: NotarizedSession,
+        transcript: HttpTranscript,
+        named: NamedCommitments,
+    ) -> Self {
         Self {
             session,
             transcript,
+            named,
         }
     }
 
@@ -29,6 +35,13 @@ impl NotarizedHttpSession {
         &self.transcript
     }
 
+    /// Returns the named commitments to the HTTP transcript.
+    ///
+    /// This is empty unless the prover committed to them with `commit_named`.
+    pub fn named_commitments(&self) -> &NamedCommitments {
+        &self.named
+    }
+
     /// Returns a proof for the TLS session.
     pub fn session_proof(&self) -> SessionProof {
         self.session.session_proof()
@@ -0,0 +1,137 @@
+use std::ops::Range;
+
+use crate::http2::Http2Error;
+
+/// The connection preface sent by the client.
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const FRAME_HEADER_LEN: usize = 9;
+
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const FLAG_PRIORITY: u8 = 0x20;
+
+/// The type of an HTTP/2 frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    /// DATA
+    Data,
+    /// HEADERS
+    Headers,
+    /// PRIORITY
+    Priority,
+    /// RST_STREAM
+    RstStream,
+    /// SETTINGS
+    Settings,
+    /// PUSH_PROMISE
+    PushPromise,
+    /// PING
+    Ping,
+    /// GOAWAY
+    GoAway,
+    /// WINDOW_UPDATE
+    WindowUpdate,
+    /// CONTINUATION
+    Continuation,
+    /// An extension frame type.
+    Unknown(u8),
+}
+
+impl From<u8> for FrameType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x0 => Self::Data,
+            0x1 => Self::Headers,
+            0x2 => Self::Priority,
+            0x3 => Self::RstStream,
+            0x4 => Self::Settings,
+            0x5 => Self::PushPromise,
+            0x6 => Self::Ping,
+            0x7 => Self::GoAway,
+            0x8 => Self::WindowUpdate,
+            0x9 => Self::Continuation,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+/// An HTTP/2 frame.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// The type of the frame.
+    pub kind: FrameType,
+    /// The flags of the frame.
+    pub flags: u8,
+    /// The stream the frame belongs to.
+    pub stream_id: u32,
+    /// The range of the frame in the transcript, including the frame header.
+    pub span: Range<usize>,
+    /// The range of the frame content in the transcript, excluding padding and the priority
+    /// fields of HEADERS frames.
+    pub content: Range<usize>,
+}
+
+impl Frame {
+    /// Returns `true` if the frame ends its stream.
+    pub fn is_end_stream(&self) -> bool {
+        matches!(self.kind, FrameType::Data | FrameType::Headers)
+            && self.flags & FLAG_END_STREAM != 0
+    }
+
+    /// Returns `true` if the frame ends a header block.
+    pub fn is_end_headers(&self) -> bool {
+        matches!(self.kind, FrameType::Headers | FrameType::Continuation)
+            && self.flags & FLAG_END_HEADERS != 0
+    }
+}
+
+/// Parses the frames in the transcript data, starting at `start`.
+pub(crate) fn parse_frames(data: &[u8], start: usize) -> Result<Vec<Frame>, Http2Error> {
+    let mut frames = Vec::new();
+    let mut pos = start;
+    while pos < data.len() {
+        let header = data
+            .get(pos..pos + FRAME_HEADER_LEN)
+            .ok_or(Http2Error::Truncated)?;
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let kind = FrameType::from(header[3]);
+        let flags = header[4];
+        let stream_id =
+            u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+
+        let span = pos..pos + FRAME_HEADER_LEN + len;
+        if span.end > data.len() {
+            return Err(Http2Error::Truncated);
+        }
+
+        let mut content = pos + FRAME_HEADER_LEN..span.end;
+        if matches!(kind, FrameType::Data | FrameType::Headers) && flags & FLAG_PADDED != 0 {
+            let pad_len = *data.get(content.start).ok_or(Http2Error::Malformed(pos))? as usize;
+            content.start += 1;
+            content.end = content
+                .end
+                .checked_sub(pad_len)
+                .filter(|end| *end >= content.start)
+                .ok_or(Http2Error::Malformed(pos))?;
+        }
+        if kind == FrameType::Headers && flags & FLAG_PRIORITY != 0 {
+            content.start += 5;
+            if content.start > content.end {
+                return Err(Http2Error::Malformed(pos));
+            }
+        }
+
+        pos = span.end;
+        frames.push(Frame {
+            kind,
+            flags,
+            stream_id,
+            span,
+            content,
+        });
+    }
+
+    Ok(frames)
+}
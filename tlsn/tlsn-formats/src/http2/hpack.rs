@@ -0,0 +1,179 @@
+use std::ops::Range;
+
+use utils::range::{RangeSet, RangeUnion};
+
+use crate::http2::Http2Error;
+
+/// A header field decoded from an HPACK header block.
+#[derive(Debug, Clone)]
+pub struct Http2Header {
+    /// The name of the header, or pseudo-header, e.g. `:status`.
+    pub name: String,
+    /// The value of the header.
+    pub value: Vec<u8>,
+    /// The ranges of the transcript containing the name, if it is a literal which is not Huffman
+    /// coded.
+    pub name_range: Option<RangeSet<usize>>,
+    /// The ranges of the transcript containing the value, if it is a literal which is not Huffman
+    /// coded.
+    pub value_range: Option<RangeSet<usize>>,
+    /// Whether the sender marked the header as sensitive ("never indexed").
+    pub sensitive: bool,
+}
+
+/// The location of the literals of a field representation in a header block.
+struct FieldLiterals {
+    name: Option<Range<usize>>,
+    value: Option<Range<usize>>,
+    sensitive: bool,
+}
+
+/// An HPACK decoder which also locates the literals of each header field in the transcript.
+pub(crate) struct HpackDecoder {
+    decoder: ::hpack::Decoder<'static>,
+}
+
+impl HpackDecoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            decoder: ::hpack::Decoder::new(),
+        }
+    }
+
+    /// Decodes a header block made of the given fragments of the transcript.
+    ///
+    /// Header blocks must be decoded in the order they were sent, as they modify the dynamic
+    /// table of the decoder.
+    pub(crate) fn decode(
+        &mut self,
+        data: &[u8],
+        fragments: &[Range<usize>],
+    ) -> Result<Vec<Http2Header>, Http2Error> {
+        let block = fragments
+            .iter()
+            .flat_map(|fragment| data[fragment.clone()].iter().copied())
+            .collect::<Vec<_>>();
+
+        let fields = self
+            .decoder
+            .decode(&block)
+            .map_err(|e| Http2Error::Hpack(format!("{e:?}")))?;
+        let literals = locate_literals(&block)?;
+
+        if fields.len() != literals.len() {
+            return Err(Http2Error::Hpack(
+                "header block contains an unexpected number of fields".to_string(),
+            ));
+        }
+
+        fields
+            .into_iter()
+            .zip(literals)
+            .map(|((name, value), literals)| {
+                Ok(Http2Header {
+                    name: String::from_utf8(name)
+                        .map_err(|_| Http2Error::Hpack("header name is not UTF-8".to_string()))?,
+                    value,
+                    name_range: literals.name.map(|range| to_transcript(fragments, range)),
+                    value_range: literals.value.map(|range| to_transcript(fragments, range)),
+                    sensitive: literals.sensitive,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Maps a range of a header block to the ranges of the transcript it was assembled from.
+fn to_transcript(fragments: &[Range<usize>], range: Range<usize>) -> RangeSet<usize> {
+    let mut ranges = RangeSet::default();
+    let mut offset = 0;
+    for fragment in fragments {
+        let start = range.start.max(offset);
+        let end = range.end.min(offset + fragment.len());
+        if start < end {
+            ranges =
+                ranges.union(&(fragment.start + start - offset..fragment.start + end - offset));
+        }
+        offset += fragment.len();
+    }
+
+    ranges
+}
+
+/// Walks the field representations of a header block, see RFC 7541 section 6.
+fn locate_literals(block: &[u8]) -> Result<Vec<FieldLiterals>, Http2Error> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < block.len() {
+        let byte = block[pos];
+        if byte & 0x80 != 0 {
+            // Indexed header field
+            read_int(block, &mut pos, 7)?;
+            fields.push(FieldLiterals {
+                name: None,
+                value: None,
+                sensitive: false,
+            });
+        } else if byte & 0xe0 == 0x20 {
+            // Dynamic table size update
+            read_int(block, &mut pos, 5)?;
+        } else {
+            let (prefix, sensitive) = match byte {
+                byte if byte & 0xc0 == 0x40 => (6, false),
+                byte if byte & 0xf0 == 0x10 => (4, true),
+                _ => (4, false),
+            };
+
+            let name = match read_int(block, &mut pos, prefix)? {
+                0 => read_string(block, &mut pos)?,
+                _ => None,
+            };
+            let value = read_string(block, &mut pos)?;
+
+            fields.push(FieldLiterals {
+                name,
+                value,
+                sensitive,
+            });
+        }
+    }
+
+    Ok(fields)
+}
+
+fn read_int(block: &[u8], pos: &mut usize, prefix: u8) -> Result<usize, Http2Error> {
+    let max = (1usize << prefix) - 1;
+    let mut value = (*block.get(*pos).ok_or(Http2Error::Truncated)? as usize) & max;
+    *pos += 1;
+    if value < max {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+    loop {
+        let byte = *block.get(*pos).ok_or(Http2Error::Truncated)?;
+        *pos += 1;
+        value = ((byte & 0x7f) as usize)
+            .checked_shl(shift)
+            .and_then(|add| value.checked_add(add))
+            .filter(|_| shift < 28)
+            .ok_or_else(|| Http2Error::Hpack("integer overflow".to_string()))?;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Reads a string literal, returning its range if it is not Huffman coded.
+fn read_string(block: &[u8], pos: &mut usize) -> Result<Option<Range<usize>>, Http2Error> {
+    let huffman = *block.get(*pos).ok_or(Http2Error::Truncated)? & 0x80 != 0;
+    let len = read_int(block, pos, 7)?;
+    let range = *pos..pos.checked_add(len).ok_or(Http2Error::Truncated)?;
+    if range.end > block.len() {
+        return Err(Http2Error::Truncated);
+    }
+    *pos = range.end;
+
+    Ok((!huffman).then_some(range))
+}
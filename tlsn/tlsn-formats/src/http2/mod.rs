@@ -0,0 +1,396 @@
+//! Tooling for working with HTTP/2 data.
+//!
+//! HTTP/2 connections are negotiated with ALPN, see `ProverConfigBuilder::alpn_protocols` in
+//! `tlsn-prover`. The transcript of such a connection is a sequence of binary frames, and header
+//! fields are compressed with HPACK, so the transcript is parsed into [`Http2Message`]s before
+//! committing.
+//!
+//! HPACK encodes a header field either as a reference to a table of previously seen fields, or as
+//! string literals which are optionally Huffman coded. Only literals which are not Huffman coded
+//! appear verbatim in the transcript, so only those can be committed to and disclosed
+//! individually, see [`Http2Header::name_range`] and [`Http2Header::value_range`]. Other fields
+//! can only be disclosed by disclosing the entire header block and the header blocks sent before
+//! it on the same connection, from which a verifier can decode them with [`Http2Transcript::parse`].
+
+mod frame;
+mod hpack;
+
+use std::collections::HashMap;
+
+use tlsn_core::{
+    commitment::{TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    Direction, Transcript,
+};
+use utils::range::{RangeSet, RangeUnion};
+
+pub use self::hpack::Http2Header;
+pub use frame::{Frame, FrameType, PREFACE};
+
+/// The ALPN protocol identifier of HTTP/2.
+pub const ALPN_H2: &[u8] = b"h2";
+
+/// An error for parsing HTTP/2 transcripts.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Http2Error {
+    /// The sent transcript does not start with the connection preface.
+    #[error("sent transcript does not start with the HTTP/2 connection preface")]
+    MissingPreface,
+    /// The transcript ends in the middle of a frame or header block.
+    #[error("transcript is truncated")]
+    Truncated,
+    /// A frame is malformed.
+    #[error("frame at offset {0} is malformed")]
+    Malformed(usize),
+    /// A header block could not be decoded.
+    #[error("failed to decode header block: {0}")]
+    Hpack(String),
+    /// The transcript uses a feature which is not supported.
+    #[error("unsupported: {0}")]
+    Unsupported(&'static str),
+}
+
+/// An HTTP/2 request or response, ie. the frames of one direction of a stream.
+#[derive(Debug, Clone)]
+pub struct Http2Message {
+    /// The stream the message was sent on.
+    pub stream_id: u32,
+    /// The header fields, including pseudo-headers.
+    pub headers: Vec<Http2Header>,
+    /// The trailer fields.
+    pub trailers: Vec<Http2Header>,
+    /// The ranges of the transcript containing the header block.
+    pub header_block: RangeSet<usize>,
+    /// The ranges of the transcript containing the body.
+    pub body: RangeSet<usize>,
+    /// Whether the stream was ended by the sender.
+    pub complete: bool,
+}
+
+impl Http2Message {
+    /// Returns the headers with the given name, which is compared case-insensitively.
+    pub fn headers_with_name<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Http2Header> + 'a {
+        self.headers
+            .iter()
+            .filter(move |header| header.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the value of the given pseudo-header, e.g. `:path` or `:status`.
+    pub fn pseudo_header(&self, name: &str) -> Option<&[u8]> {
+        self.headers
+            .iter()
+            .find(|header| header.name == name)
+            .map(|header| header.value.as_slice())
+    }
+
+    /// Commits to the message.
+    ///
+    /// Commits to the header block, to each header literal which appears verbatim in the
+    /// transcript, and to the body.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        if !is_empty(&self.header_block) {
+            builder.commit(&self.header_block, direction)?;
+        }
+
+        for header in self.headers.iter().chain(&self.trailers) {
+            for range in [&header.name_range, &header.value_range]
+                .into_iter()
+                .flatten()
+            {
+                if !is_empty(range) {
+                    builder.commit(range, direction)?;
+                }
+            }
+        }
+
+        if !is_empty(&self.body) {
+            builder.commit(&self.body, direction)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_empty(ranges: &RangeSet<usize>) -> bool {
+    ranges.iter_ranges().all(|range| range.is_empty())
+}
+
+/// An HTTP/2 transcript.
+#[derive(Debug)]
+pub struct Http2Transcript {
+    /// The requests sent to the server, in the order their streams were opened.
+    pub requests: Vec<Http2Message>,
+    /// The responses received from the server, in the order their streams were opened.
+    pub responses: Vec<Http2Message>,
+    /// The frames sent to the server.
+    pub sent_frames: Vec<Frame>,
+    /// The frames received from the server.
+    pub recv_frames: Vec<Frame>,
+}
+
+impl Http2Transcript {
+    /// Parses the HTTP/2 transcript from the provided transcripts.
+    pub fn parse(tx: &Transcript, rx: &Transcript) -> Result<Self, Http2Error> {
+        let tx = tx.data();
+        let rx = rx.data();
+
+        if !tx.starts_with(PREFACE) {
+            return Err(Http2Error::MissingPreface);
+        }
+
+        let sent_frames = frame::parse_frames(tx, PREFACE.len())?;
+        let recv_frames = frame::parse_frames(rx, 0)?;
+
+        Ok(Self {
+            requests: parse_messages(tx, &sent_frames)?,
+            responses: parse_messages(rx, &recv_frames)?,
+            sent_frames,
+            recv_frames,
+        })
+    }
+
+    /// Returns the response on the same stream as the given request.
+    pub fn response_to(&self, request: &Http2Message) -> Option<&Http2Message> {
+        self.responses
+            .iter()
+            .find(|response| response.stream_id == request.stream_id)
+    }
+
+    /// Commits to the transcript.
+    ///
+    /// Commits to the connection preface and the frame headers of each direction, which reveal
+    /// the structure of the connection, and to each message, see [`Http2Message::commit`].
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        let sent_structure = self
+            .sent_frames
+            .iter()
+            .fold(RangeSet::from(0..PREFACE.len()), |ranges, frame| {
+                ranges.union(&(frame.span.start..frame.span.start + 9))
+            });
+        builder.commit(&sent_structure, Direction::Sent)?;
+
+        if !self.recv_frames.is_empty() {
+            let recv_structure = self
+                .recv_frames
+                .iter()
+                .fold(RangeSet::default(), |ranges, frame| {
+                    ranges.union(&(frame.span.start..frame.span.start + 9))
+                });
+            builder.commit(&recv_structure, Direction::Received)?;
+        }
+
+        for request in &self.requests {
+            request.commit(builder, Direction::Sent)?;
+        }
+
+        for response in &self.responses {
+            response.commit(builder, Direction::Received)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_messages(data: &[u8], frames: &[Frame]) -> Result<Vec<Http2Message>, Http2Error> {
+    let mut decoder = self::hpack::HpackDecoder::new();
+    let mut messages: Vec<Http2Message> = Vec::new();
+    let mut streams: HashMap<u32, usize> = HashMap::new();
+
+    let mut frames = frames.iter();
+    while let Some(frame) = frames.next() {
+        match frame.kind {
+            FrameType::Headers => {
+                // A header block is continued by CONTINUATION frames, which must immediately
+                // follow on the same stream.
+                let mut fragments = vec![frame.content.clone()];
+                let mut last = frame;
+                while !last.is_end_headers() {
+                    last = frames.next().ok_or(Http2Error::Truncated)?;
+                    if last.kind != FrameType::Continuation || last.stream_id != frame.stream_id {
+                        return Err(Http2Error::Malformed(last.span.start));
+                    }
+                    fragments.push(last.content.clone());
+                }
+
+                let headers = decoder.decode(data, &fragments)?;
+                let block = fragments
+                    .iter()
+                    .fold(RangeSet::default(), |ranges, fragment| {
+                        ranges.union(fragment)
+                    });
+
+                let index = *streams.entry(frame.stream_id).or_insert_with(|| {
+                    messages.push(Http2Message {
+                        stream_id: frame.stream_id,
+                        headers: Vec::new(),
+                        trailers: Vec::new(),
+                        header_block: RangeSet::default(),
+                        body: RangeSet::default(),
+                        complete: false,
+                    });
+                    messages.len() - 1
+                });
+
+                let message = &mut messages[index];
+                if message.headers.is_empty() {
+                    message.headers = headers;
+                } else {
+                    message.trailers.extend(headers);
+                }
+                message.header_block = message.header_block.union(&block);
+                message.complete |= frame.is_end_stream();
+            }
+            FrameType::Data => {
+                let index = *streams
+                    .get(&frame.stream_id)
+                    .ok_or(Http2Error::Malformed(frame.span.start))?;
+
+                let message = &mut messages[index];
+                message.body = message.body.union(&frame.content);
+                message.complete |= frame.is_end_stream();
+            }
+            FrameType::PushPromise => return Err(Http2Error::Unsupported("server push")),
+            FrameType::Continuation => return Err(Http2Error::Malformed(frame.span.start)),
+            _ => {}
+        }
+    }
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tlsn_core::{
+        commitment::CommitmentKind, fixtures, proof::SubstringsProofBuilder, Transcript,
+    };
+
+    fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut frame = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+        frame.push(kind);
+        frame.push(flags);
+        frame.extend_from_slice(&stream_id.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    /// Encodes a literal header field without indexing, with a literal name, and without
+    /// Huffman coding.
+    fn literal(name: &str, value: &str) -> Vec<u8> {
+        let mut field = vec![0x00, name.len() as u8];
+        field.extend_from_slice(name.as_bytes());
+        field.push(value.len() as u8);
+        field.extend_from_slice(value.as_bytes());
+        field
+    }
+
+    fn transcripts() -> (Vec<u8>, Vec<u8>) {
+        let mut tx = PREFACE.to_vec();
+        tx.extend(frame(0x4, 0, 0, &[]));
+        // :method GET, :scheme https, :path /, :authority (indexed name, literal value), and a
+        // literal header split across a CONTINUATION frame.
+        let mut block = vec![0x82, 0x87, 0x84, 0x01, 9];
+        block.extend_from_slice(b"localhost");
+        block.extend(literal("authorization", "secret-token"));
+        tx.extend(frame(0x1, 0x1, 1, &block[..20]));
+        tx.extend(frame(0x9, 0x4, 1, &block[20..]));
+
+        let mut rx = frame(0x4, 0, 0, &[]);
+        // :status 200 (indexed) and a literal header.
+        let mut block = vec![0x88];
+        block.extend(literal("content-type", "text/plain"));
+        rx.extend(frame(0x1, 0x4, 1, &block));
+        rx.extend(frame(
+            0x0,
+            0x8,
+            1,
+            &[3, b'h', b'e', b'l', b'l', b'o', 0, 0, 0],
+        ));
+        rx.extend(frame(0x0, 0x1, 1, b" world"));
+
+        (tx, rx)
+    }
+
+    #[test]
+    fn test_http2_parse() {
+        let (tx, rx) = transcripts();
+        let transcript =
+            Http2Transcript::parse(&Transcript::new(tx), &Transcript::new(rx.clone())).unwrap();
+
+        let request = &transcript.requests[0];
+        assert_eq!(request.pseudo_header(":method"), Some(&b"GET"[..]));
+        assert_eq!(request.pseudo_header(":authority"), Some(&b"localhost"[..]));
+        assert_eq!(
+            request
+                .headers_with_name("Authorization")
+                .next()
+                .unwrap()
+                .value,
+            b"secret-token"
+        );
+        assert!(request.complete);
+
+        let response = transcript.response_to(request).unwrap();
+        assert_eq!(response.pseudo_header(":status"), Some(&b"200"[..]));
+        assert!(response.headers[0].value_range.is_none());
+        assert!(response.complete);
+
+        let body = response
+            .body
+            .iter_ranges()
+            .flat_map(|range| rx[range].to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(body, b"hello world");
+    }
+
+    #[test]
+    fn test_http2_prove() {
+        let (tx, rx) = transcripts();
+        let transcript_tx = Transcript::new(tx.clone());
+        let transcript_rx = Transcript::new(rx.clone());
+        let transcript = Http2Transcript::parse(&transcript_tx, &transcript_rx).unwrap();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(&tx, &rx),
+            tx.len(),
+            rx.len(),
+        );
+        transcript.commit(&mut builder).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let request = &transcript.requests[0];
+        let response = transcript.response_to(request).unwrap();
+        let content_type = response.headers_with_name("content-type").next().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        builder
+            .reveal_recv(
+                content_type.value_range.as_ref().unwrap(),
+                CommitmentKind::Blake3,
+            )
+            .unwrap()
+            .reveal_recv(&response.body, CommitmentKind::Blake3)
+            .unwrap();
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), tx.len(), rx.len());
+        let (_, recv) = proof.verify(&header).unwrap();
+
+        let value_range = content_type.value_range.as_ref().unwrap();
+        let value = value_range
+            .iter_ranges()
+            .flat_map(|range| recv.data()[range].to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(value, b"text/plain");
+    }
+}
@@ -12,7 +12,9 @@
 #![forbid(unsafe_code)]
 
 pub mod http;
+pub mod http2;
 pub mod json;
+pub mod websocket;
 
 #[doc(hidden)]
 pub use spansy;
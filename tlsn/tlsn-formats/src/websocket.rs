@@ -0,0 +1,470 @@
+//! Tooling for working with WebSocket data.
+//!
+//! A WebSocket connection starts with an HTTP/1.1 upgrade handshake, after which each direction of
+//! the transcript is a sequence of frames, see [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455).
+//! The transcript is parsed into [`WsMessage`]s so that each message can be committed to, and
+//! disclosed or withheld, individually.
+//!
+//! Frames sent by the client are masked, ie. their payload is XORed with a key carried in the
+//! frame header, so the sent transcript does not contain the plaintext of the client's messages.
+//! A verifier can unmask a disclosed message with [`WsMessage::decode`] as long as the
+//! [structure](WsMessage::structure) of the message, which includes the masking keys, is
+//! disclosed as well.
+//!
+//! Compressed messages (`permessage-deflate`) are not supported.
+
+use std::ops::Range;
+
+use tlsn_core::{
+    commitment::{TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    Direction, Transcript,
+};
+use utils::range::{RangeSet, RangeUnion};
+
+/// An error for parsing WebSocket transcripts.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum WebSocketError {
+    /// The transcript does not start with an opening handshake.
+    #[error("transcript does not start with a WebSocket opening handshake")]
+    MissingHandshake,
+    /// The server did not accept the opening handshake.
+    #[error("server responded to the opening handshake with status {0}")]
+    HandshakeRejected(String),
+    /// The transcript ends in the middle of a frame.
+    #[error("transcript is truncated")]
+    Truncated,
+    /// A frame is malformed.
+    #[error("frame at offset {0} is malformed")]
+    Malformed(usize),
+    /// The transcript uses a feature which is not supported.
+    #[error("unsupported: {0}")]
+    Unsupported(&'static str),
+}
+
+/// The opcode of a WebSocket frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    /// A continuation of a fragmented message.
+    Continuation,
+    /// A text message.
+    Text,
+    /// A binary message.
+    Binary,
+    /// A connection close.
+    Close,
+    /// A ping.
+    Ping,
+    /// A pong.
+    Pong,
+}
+
+impl Opcode {
+    fn parse(value: u8) -> Option<Self> {
+        match value {
+            0x0 => Some(Self::Continuation),
+            0x1 => Some(Self::Text),
+            0x2 => Some(Self::Binary),
+            0x8 => Some(Self::Close),
+            0x9 => Some(Self::Ping),
+            0xa => Some(Self::Pong),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the opcode is a control opcode.
+    pub fn is_control(&self) -> bool {
+        matches!(self, Self::Close | Self::Ping | Self::Pong)
+    }
+}
+
+/// A WebSocket frame.
+#[derive(Debug, Clone)]
+pub struct WsFrame {
+    /// Whether this is the final frame of a message.
+    pub fin: bool,
+    /// The opcode of the frame.
+    pub opcode: Opcode,
+    /// The masking key, which is present in frames sent by the client.
+    pub mask: Option<[u8; 4]>,
+    /// The range of the frame header in the transcript, including the masking key.
+    pub header: Range<usize>,
+    /// The range of the payload in the transcript.
+    pub payload: Range<usize>,
+}
+
+/// A WebSocket message, ie. a control frame or the frames of a possibly fragmented data message.
+#[derive(Debug, Clone)]
+pub struct WsMessage {
+    /// The opcode of the message.
+    pub opcode: Opcode,
+    /// The frames of the message.
+    pub frames: Vec<WsFrame>,
+    /// Whether the final frame of the message is in the transcript.
+    pub complete: bool,
+}
+
+impl WsMessage {
+    /// Returns the ranges of the transcript containing the payload.
+    pub fn payload(&self) -> RangeSet<usize> {
+        self.frames
+            .iter()
+            .fold(RangeSet::default(), |ranges, frame| {
+                ranges.union(&frame.payload)
+            })
+    }
+
+    /// Returns the ranges a verifier needs to decode the payload: the frame headers, including
+    /// the masking keys.
+    pub fn structure(&self) -> RangeSet<usize> {
+        self.frames
+            .iter()
+            .fold(RangeSet::default(), |ranges, frame| {
+                ranges.union(&frame.header)
+            })
+    }
+
+    /// Returns the payload, unmasking it if it was sent by the client.
+    ///
+    /// `data` may be a redacted transcript, in which case redacted bytes decode to garbage.
+    pub fn decode(&self, data: &[u8]) -> Vec<u8> {
+        self.frames
+            .iter()
+            .flat_map(|frame| {
+                data[frame.payload.clone()]
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, byte)| match frame.mask {
+                        Some(mask) => byte ^ mask[i % 4],
+                        None => *byte,
+                    })
+            })
+            .collect()
+    }
+
+    /// Commits to the [structure](Self::structure) of the message and to its payload.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        builder.commit(&self.structure(), direction)?;
+
+        let payload = self.payload();
+        if !payload.iter_ranges().all(|range| range.is_empty()) {
+            builder.commit(&payload, direction)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A WebSocket transcript.
+#[derive(Debug)]
+pub struct WebSocketTranscript {
+    /// The range of the opening handshake request in the sent transcript.
+    pub handshake_request: Range<usize>,
+    /// The range of the opening handshake response in the received transcript.
+    pub handshake_response: Range<usize>,
+    /// The messages sent to the server, in the order they were started.
+    pub sent_messages: Vec<WsMessage>,
+    /// The messages received from the server, in the order they were started.
+    pub recv_messages: Vec<WsMessage>,
+}
+
+impl WebSocketTranscript {
+    /// Parses the WebSocket transcript from the provided transcripts.
+    pub fn parse(tx: &Transcript, rx: &Transcript) -> Result<Self, WebSocketError> {
+        let tx = tx.data();
+        let rx = rx.data();
+
+        if !tx.starts_with(b"GET ") {
+            return Err(WebSocketError::MissingHandshake);
+        }
+        let handshake_request = head(tx)?;
+        let handshake_response = head(rx)?;
+
+        let status = rx[handshake_response.clone()]
+            .split(|b| *b == b' ')
+            .nth(1)
+            .ok_or(WebSocketError::MissingHandshake)?;
+        if status != b"101" {
+            return Err(WebSocketError::HandshakeRejected(
+                String::from_utf8_lossy(status).into_owned(),
+            ));
+        }
+
+        let sent_frames = parse_frames(tx, handshake_request.end, true)?;
+        let recv_frames = parse_frames(rx, handshake_response.end, false)?;
+
+        Ok(Self {
+            handshake_request,
+            handshake_response,
+            sent_messages: parse_messages(sent_frames)?,
+            recv_messages: parse_messages(recv_frames)?,
+        })
+    }
+
+    /// Commits to the transcript.
+    ///
+    /// Commits to the opening handshake of each direction as a whole, and to each message, see
+    /// [`WsMessage::commit`]. The handshake request often contains credentials, to disclose parts
+    /// of it parse it with [`parse_request`](crate::http::parse_request) and commit to it with an
+    /// [`HttpCommit`](crate::http::HttpCommit) implementation instead.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        builder.commit(&self.handshake_request, Direction::Sent)?;
+        builder.commit(&self.handshake_response, Direction::Received)?;
+
+        for message in &self.sent_messages {
+            message.commit(builder, Direction::Sent)?;
+        }
+
+        for message in &self.recv_messages {
+            message.commit(builder, Direction::Received)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the range of the start line and header section of the HTTP message at the start of
+/// the data.
+fn head(data: &[u8]) -> Result<Range<usize>, WebSocketError> {
+    data.windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|pos| 0..pos + 4)
+        .ok_or(WebSocketError::MissingHandshake)
+}
+
+fn parse_frames(data: &[u8], start: usize, masked: bool) -> Result<Vec<WsFrame>, WebSocketError> {
+    let mut frames = Vec::new();
+    let mut pos = start;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 2).ok_or(WebSocketError::Truncated)?;
+        if header[0] & 0x70 != 0 {
+            return Err(WebSocketError::Unsupported(
+                "frames with reserved bits set, e.g. permessage-deflate",
+            ));
+        }
+        let fin = header[0] & 0x80 != 0;
+        let opcode = Opcode::parse(header[0] & 0x0f).ok_or(WebSocketError::Malformed(pos))?;
+        // Clients must mask all frames, and servers must not mask any.
+        if (header[1] & 0x80 != 0) != masked {
+            return Err(WebSocketError::Malformed(pos));
+        }
+
+        let mut cursor = pos + 2;
+        let mut read = |len: usize| {
+            let bytes = data
+                .get(cursor..cursor + len)
+                .ok_or(WebSocketError::Truncated)?;
+            cursor += len;
+            Ok::<_, WebSocketError>(bytes)
+        };
+
+        let len = match header[1] & 0x7f {
+            126 => u16::from_be_bytes(read(2)?.try_into().unwrap()) as u64,
+            127 => u64::from_be_bytes(read(8)?.try_into().unwrap()),
+            len => len as u64,
+        };
+        let mask: Option<[u8; 4]> = if masked {
+            Some(read(4)?.try_into().unwrap())
+        } else {
+            None
+        };
+
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| cursor.checked_add(len))
+            .filter(|end| *end <= data.len())
+            .ok_or(WebSocketError::Truncated)?;
+
+        // Control frames must not be fragmented, and their payload is at most 125 bytes.
+        if opcode.is_control() && (!fin || end - cursor > 125) {
+            return Err(WebSocketError::Malformed(pos));
+        }
+
+        frames.push(WsFrame {
+            fin,
+            opcode,
+            mask,
+            header: pos..cursor,
+            payload: cursor..end,
+        });
+        pos = end;
+    }
+
+    Ok(frames)
+}
+
+fn parse_messages(frames: Vec<WsFrame>) -> Result<Vec<WsMessage>, WebSocketError> {
+    let mut messages: Vec<WsMessage> = Vec::new();
+    // The index of the fragmented message which is in progress, control frames may be
+    // interleaved with its fragments.
+    let mut fragmented = None;
+
+    for frame in frames {
+        match frame.opcode {
+            Opcode::Continuation => {
+                let index = fragmented.ok_or(WebSocketError::Malformed(frame.header.start))?;
+                let message: &mut WsMessage = &mut messages[index];
+                message.complete = frame.fin;
+                message.frames.push(frame);
+                if message.complete {
+                    fragmented = None;
+                }
+            }
+            opcode if opcode.is_control() => messages.push(WsMessage {
+                opcode,
+                frames: vec![frame],
+                complete: true,
+            }),
+            opcode => {
+                if fragmented.is_some() {
+                    return Err(WebSocketError::Malformed(frame.header.start));
+                }
+                if !frame.fin {
+                    fragmented = Some(messages.len());
+                }
+                messages.push(WsMessage {
+                    opcode,
+                    complete: frame.fin,
+                    frames: vec![frame],
+                });
+            }
+        }
+    }
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tlsn_core::{commitment::CommitmentKind, fixtures, proof::SubstringsProofBuilder};
+
+    fn frame(fin: bool, opcode: u8, mask: Option<[u8; 4]>, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![((fin as u8) << 7) | opcode];
+        let mask_bit = (mask.is_some() as u8) << 7;
+        match payload.len() {
+            len @ 0..=125 => frame.push(mask_bit | len as u8),
+            len => {
+                frame.push(mask_bit | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        match mask {
+            Some(mask) => {
+                frame.extend_from_slice(&mask);
+                frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+            }
+            None => frame.extend_from_slice(payload),
+        }
+        frame
+    }
+
+    fn transcripts() -> (Vec<u8>, Vec<u8>) {
+        let mut tx = b"GET /feed HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+        Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+        Sec-WebSocket-Version: 13\r\n\r\n"
+            .to_vec();
+        // A fragmented text message with a ping between its fragments.
+        tx.extend(frame(false, 0x1, Some([1, 2, 3, 4]), b"{\"subscribe\":"));
+        tx.extend(frame(true, 0x9, Some([5, 6, 7, 8]), b"ping"));
+        tx.extend(frame(true, 0x0, Some([9, 10, 11, 12]), b"\"prices\"}"));
+
+        let mut rx = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+        Connection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n"
+            .to_vec();
+        rx.extend(frame(true, 0xa, None, b"ping"));
+        rx.extend(frame(true, 0x1, None, b"{\"btc\":42000}"));
+        rx.extend(frame(true, 0x2, None, &[7u8; 300]));
+        rx.extend(frame(true, 0x8, None, &[0x03, 0xe8]));
+
+        (tx, rx)
+    }
+
+    #[test]
+    fn test_websocket_parse() {
+        let (tx, rx) = transcripts();
+        let transcript =
+            WebSocketTranscript::parse(&Transcript::new(tx.clone()), &Transcript::new(rx.clone()))
+                .unwrap();
+
+        let opcodes = |messages: &[WsMessage]| {
+            messages
+                .iter()
+                .map(|message| message.opcode)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            opcodes(&transcript.sent_messages),
+            [Opcode::Text, Opcode::Ping]
+        );
+        assert_eq!(
+            opcodes(&transcript.recv_messages),
+            [Opcode::Pong, Opcode::Text, Opcode::Binary, Opcode::Close]
+        );
+
+        let subscribe = &transcript.sent_messages[0];
+        assert!(subscribe.complete);
+        assert_eq!(subscribe.frames.len(), 2);
+        assert_eq!(subscribe.decode(&tx), b"{\"subscribe\":\"prices\"}");
+
+        assert_eq!(transcript.recv_messages[1].decode(&rx), b"{\"btc\":42000}");
+        assert_eq!(transcript.recv_messages[2].decode(&rx), [7u8; 300]);
+    }
+
+    #[test]
+    fn test_websocket_rejected_handshake() {
+        let (tx, _) = transcripts();
+        let rx = b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n".to_vec();
+
+        let err =
+            WebSocketTranscript::parse(&Transcript::new(tx), &Transcript::new(rx)).unwrap_err();
+
+        assert!(matches!(err, WebSocketError::HandshakeRejected(status) if status == "403"));
+    }
+
+    #[test]
+    fn test_websocket_prove() {
+        let (tx, rx) = transcripts();
+        let transcript_tx = Transcript::new(tx.clone());
+        let transcript_rx = Transcript::new(rx.clone());
+        let transcript = WebSocketTranscript::parse(&transcript_tx, &transcript_rx).unwrap();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(&tx, &rx),
+            tx.len(),
+            rx.len(),
+        );
+        transcript.commit(&mut builder).unwrap();
+        let commitments = builder.build().unwrap();
+
+        // Disclose the subscription and the price, but withhold everything else.
+        let subscribe = &transcript.sent_messages[0];
+        let price = &transcript.recv_messages[1];
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        builder
+            .reveal_sent(&subscribe.structure(), CommitmentKind::Blake3)
+            .unwrap()
+            .reveal_sent(&subscribe.payload(), CommitmentKind::Blake3)
+            .unwrap()
+            .reveal_recv(&price.payload(), CommitmentKind::Blake3)
+            .unwrap();
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), tx.len(), rx.len());
+        let (sent, recv) = proof.verify(&header).unwrap();
+
+        assert_eq!(subscribe.decode(sent.data()), b"{\"subscribe\":\"prices\"}");
+        assert_eq!(price.decode(recv.data()), b"{\"btc\":42000}");
+        assert!(recv.data()[transcript.handshake_response.clone()]
+            .iter()
+            .all(|b| *b == 0));
+    }
+}
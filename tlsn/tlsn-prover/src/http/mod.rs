@@ -8,7 +8,10 @@
 pub mod state;
 
 use tlsn_formats::{
-    http::{DefaultHttpCommitter, HttpCommit, HttpCommitError, HttpTranscript},
+    http::{
+        DefaultHttpCommitter, HttpCommit, HttpCommitError, HttpTranscript, MessageKind,
+        NamedCommitments,
+    },
     ParseError,
 };
 
@@ -42,6 +45,11 @@ impl HttpProver<state::Closed> {
         })
     }
 
+    /// Returns the HTTP transcript.
+    pub fn transcript(&self) -> &HttpTranscript {
+        &self.state.transcript
+    }
+
     /// Starts notarization of the HTTP session.
     ///
     /// If the verifier is a Notary, this function will transition the prover to the next state
@@ -51,12 +59,18 @@ impl HttpProver<state::Closed> {
             state: state::Notarize {
                 prover: self.state.prover.start_notarize(),
                 transcript: self.state.transcript,
+                named: NamedCommitments::default(),
             },
         }
     }
 }
 
 impl HttpProver<state::Notarize> {
+    /// Returns the HTTP transcript.
+    pub fn transcript(&self) -> &HttpTranscript {
+        &self.state.transcript
+    }
+
     /// Generates commitments to the HTTP session using the provided committer.
     pub fn commit_with<C: HttpCommit>(&mut self, committer: &mut C) -> Result<(), HttpCommitError> {
         committer.commit_transcript(
@@ -73,11 +87,61 @@ impl HttpProver<state::Notarize> {
         )
     }
 
+    /// Commits to each request and its response as a whole, so that the
+    /// [exchanges](HttpTranscript::exchanges) of a persistent connection can be disclosed
+    /// separately.
+    pub fn commit_exchanges(&mut self) -> Result<(), HttpCommitError> {
+        for exchange in self.state.transcript.exchanges() {
+            exchange
+                .commit(self.state.prover.commitment_builder())
+                .map_err(|e| {
+                    let mut err = HttpCommitError::new_with_source(
+                        MessageKind::Request,
+                        "failed to commit to exchange",
+                        e,
+                    );
+                    err.set_index(exchange.request());
+                    err
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Commits to the status line, request line and target, each header and the body of every
+    /// message, under names derived from the HTTP structure.
+    ///
+    /// The commitments can be revealed by name using the
+    /// [named commitments](NotarizedHttpSession::named_commitments) of the notarized session,
+    /// e.g. `response[0].headers.content-type`.
+    pub fn commit_named(&mut self) -> Result<(), HttpCommitError> {
+        let named = NamedCommitments::new(
+            &self.state.transcript,
+            self.state.prover.sent_transcript(),
+            self.state.prover.recv_transcript(),
+        );
+
+        named
+            .commit(self.state.prover.commitment_builder())
+            .map_err(|e| {
+                HttpCommitError::new_with_source(
+                    MessageKind::Request,
+                    "failed to commit to named elements",
+                    e,
+                )
+            })?;
+
+        self.state.named = named;
+
+        Ok(())
+    }
+
     /// Finalizes the HTTP session.
     pub async fn finalize(self) -> Result<NotarizedHttpSession, HttpProverError> {
         Ok(NotarizedHttpSession::new(
             self.state.prover.finalize().await?,
             self.state.transcript,
+            self.state.named,
         ))
     }
 }
@@ -1,6 +1,6 @@
 //! HTTP prover state.
 
-use tlsn_formats::http::HttpTranscript;
+use tlsn_formats::http::{HttpTranscript, NamedCommitments};
 
 use crate::tls::{state as prover_state, Prover};
 
@@ -17,6 +17,7 @@ pub struct Closed {
 pub struct Notarize {
     pub(super) prover: Prover<prover_state::Notarize>,
     pub(super) transcript: HttpTranscript,
+    pub(super) named: NamedCommitments,
 }
 
 impl State for Closed {}
@@ -3,6 +3,9 @@
 //! This library contains TLSNotary prover implementations:
 //!   * [`tls`] for the low-level API for working with the underlying byte streams of a TLS connection.
 //!   * [`http`] for a higher-level API which provides abstractions for working with HTTP connections.
+//!
+//! On `wasm32`, the `wasm` module provides the WebSocket transport and task spawning needed to run the
+//! prover in the browser.
 
 #![deny(missing_docs, unreachable_pub, unused_must_use)]
 #![deny(clippy::all)]
@@ -11,3 +14,5 @@
 #[cfg(feature = "formats")]
 pub mod http;
 pub mod tls;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
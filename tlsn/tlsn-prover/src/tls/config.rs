@@ -1,14 +1,24 @@
 use mpz_ot::{chou_orlandi, kos};
 use mpz_share_conversion::{ReceiverConfig, SenderConfig};
-use tls_client::RootCertStore;
-use tls_mpc::{MpcTlsCommonConfig, MpcTlsLeaderConfig, TranscriptConfig};
+use tls_client::{Certificate, PrivateKey, RootCertStore, SupportedCipherSuite, ALL_CIPHER_SUITES};
+use tls_core::msgs::enums::CipherSuite;
+use tls_mpc::{MpcTlsCommonConfig, MpcTlsLeaderConfig, TranscriptConfig, SUPPORTED_CIPHER_SUITES};
 use tlsn_common::{
-    config::{ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
+    compress::Compression,
+    config::{
+        ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT,
+        DEFAULT_MAX_THREADS,
+    },
+    resume::DEFAULT_MAX_RECONNECTS,
     Role,
 };
+use tlsn_core::{msg::SetupPreprocessing, session::Extensions, LengthPadding};
+
+use crate::tls::Proxy;
 
 /// Configuration for the prover
-#[derive(Debug, Clone, derive_builder::Builder)]
+#[derive(Clone, derive_builder::Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
 pub struct ProverConfig {
     /// Id of the notarization session.
     #[builder(setter(into))]
@@ -25,6 +35,173 @@ pub struct ProverConfig {
     /// Maximum number of bytes that can be received.
     #[builder(default = "DEFAULT_MAX_RECV_LIMIT")]
     max_recv_data: usize,
+    /// Padding the Notary applies to the transcript lengths in the session header.
+    #[builder(default)]
+    length_padding: LengthPadding,
+    /// Extension claims to request from the Notary.
+    #[builder(default)]
+    extensions: Extensions,
+    /// Cipher suites to offer to the server, in order of preference.
+    ///
+    /// Only suites which are supported under MPC, listed in [`SUPPORTED_CIPHER_SUITES`], are
+    /// offered. All of them are offered if this is empty.
+    #[builder(default)]
+    cipher_suites: Vec<CipherSuite>,
+    /// Application protocols to offer to the server with ALPN, in order of preference, e.g.
+    /// `b"h2"` and `b"http/1.1"`. No protocol is negotiated if this is empty.
+    #[builder(default)]
+    alpn_protocols: Vec<Vec<u8>>,
+    /// The name to send in the SNI extension instead of the server DNS name, which is still used
+    /// to verify the server certificate.
+    #[builder(setter(strip_option, into), default)]
+    sni_override: Option<String>,
+    /// Additional TLS extensions to include in the client hello, as pairs of extension type and
+    /// encoded extension data.
+    ///
+    /// Only extensions which do not change the negotiated session are allowed, see
+    /// [`is_allowed_tls_extension`].
+    #[builder(default)]
+    tls_extensions: Vec<(u16, Vec<u8>)>,
+    /// Whether to defer decryption of data from the server until the connection is closed.
+    #[builder(default)]
+    defer_decryption: bool,
+    /// Number of bytes of sent data whose encryption is preprocessed during setup.
+    #[builder(setter(strip_option), default)]
+    setup_preprocess_sent: Option<usize>,
+    /// Number of bytes of received data whose decryption is preprocessed during setup.
+    #[builder(setter(strip_option), default)]
+    setup_preprocess_recv: Option<usize>,
+    /// Maximum number of threads to garble and evaluate the encryption and decryption circuits
+    /// on. The session uses the smaller of this and the maximum of the verifier.
+    #[builder(default = "DEFAULT_MAX_THREADS")]
+    max_threads: usize,
+    /// Certificate chain and private key to authenticate to the server with.
+    #[builder(setter(custom), default)]
+    client_auth: Option<(Vec<Certificate>, PrivateKey)>,
+    /// Proxy to connect to the server through.
+    #[builder(setter(strip_option), default)]
+    proxy: Option<Proxy>,
+    /// Maximum number of consecutive failed attempts to reconnect to the verifier, when the
+    /// prover is set up with [`Prover::setup_resumable`](crate::tls::Prover::setup_resumable).
+    #[builder(default = "DEFAULT_MAX_RECONNECTS")]
+    max_reconnects: usize,
+    /// Channels whose data sent to the verifier is compressed.
+    #[builder(default)]
+    compression: Compression,
+    /// Parameters to commit to the transcript commitments with a KZG vector commitment, in
+    /// addition to the Merkle tree.
+    #[cfg(feature = "kzg")]
+    #[builder(setter(strip_option), default)]
+    kzg_params: Option<std::sync::Arc<tlsn_core::kzg::KzgParams>>,
+}
+
+/// Returns whether a TLS extension of the given type may be added to the client hello with
+/// [`ProverConfigBuilder::tls_extensions`].
+///
+/// These are the padding extension and the GREASE values of RFC 8701, which servers must ignore.
+/// Other extensions could change the negotiated session in ways the Notary does not check, or
+/// which the MPC record layer does not support.
+pub fn is_allowed_tls_extension(typ: u16) -> bool {
+    const PADDING: u16 = 0x0015;
+
+    let is_grease = typ & 0x0f0f == 0x0a0a && typ >> 8 == typ & 0xff;
+    typ == PADDING || is_grease
+}
+
+impl std::fmt::Debug for ProverConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The private key of the client certificate must not end up in logs.
+        let client_auth = self
+            .client_auth
+            .as_ref()
+            .map(|(cert_chain, _)| (cert_chain, "<redacted>"));
+
+        let mut debug = f.debug_struct("ProverConfig");
+        debug
+            .field("id", &self.id)
+            .field("server_dns", &self.server_dns)
+            .field("root_cert_store", &self.root_cert_store)
+            .field("max_sent_data", &self.max_sent_data)
+            .field("max_recv_data", &self.max_recv_data)
+            .field("length_padding", &self.length_padding)
+            .field("extensions", &self.extensions)
+            .field("cipher_suites", &self.cipher_suites)
+            .field("alpn_protocols", &self.alpn_protocols)
+            .field("sni_override", &self.sni_override)
+            .field("tls_extensions", &self.tls_extensions)
+            .field("defer_decryption", &self.defer_decryption)
+            .field("setup_preprocess_sent", &self.setup_preprocess_sent)
+            .field("setup_preprocess_recv", &self.setup_preprocess_recv)
+            .field("max_threads", &self.max_threads)
+            .field("client_auth", &client_auth)
+            .field("proxy", &self.proxy)
+            .field("max_reconnects", &self.max_reconnects)
+            .field("compression", &self.compression);
+        #[cfg(feature = "kzg")]
+        debug.field("kzg_params", &self.kzg_params);
+        debug.finish()
+    }
+}
+
+impl ProverConfigBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(cipher_suites) = &self.cipher_suites {
+            if !cipher_suites.is_empty()
+                && !cipher_suites
+                    .iter()
+                    .any(|suite| SUPPORTED_CIPHER_SUITES.contains(suite))
+            {
+                return Err(format!(
+                    "none of the cipher suites {cipher_suites:?} are supported under MPC, \
+                     supported suites are {SUPPORTED_CIPHER_SUITES:?}"
+                ));
+            }
+        }
+
+        let max_sent_data = self.max_sent_data.unwrap_or(DEFAULT_MAX_SENT_LIMIT);
+        if let Some(Some(sent)) = self.setup_preprocess_sent {
+            if sent > max_sent_data {
+                return Err(format!(
+                    "setup preprocessing of {sent} sent bytes exceeds the maximum of {max_sent_data}"
+                ));
+            }
+        }
+        let max_recv_data = self.max_recv_data.unwrap_or(DEFAULT_MAX_RECV_LIMIT);
+        if let Some(Some(recv)) = self.setup_preprocess_recv {
+            if recv > max_recv_data {
+                return Err(format!(
+                    "setup preprocessing of {recv} received bytes exceeds the maximum of {max_recv_data}"
+                ));
+            }
+        }
+
+        if let Some(tls_extensions) = &self.tls_extensions {
+            if let Some((typ, _)) = tls_extensions
+                .iter()
+                .find(|(typ, _)| !is_allowed_tls_extension(*typ))
+            {
+                return Err(format!("TLS extension {typ:#06x} is not allowed"));
+            }
+        }
+
+        if self.max_threads == Some(0) {
+            return Err("max_threads must be at least 1".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Sets a certificate chain and matching private key to authenticate to servers which request
+    /// a client certificate (mTLS).
+    ///
+    /// `cert_chain` is a vector of DER-encoded certificates, and `key` is a DER-encoded RSA,
+    /// ECDSA, or Ed25519 private key. The key never leaves the Prover: the `CertificateVerify`
+    /// message is signed locally over the handshake transcript, and the handshake is otherwise
+    /// run under MPC as usual.
+    pub fn client_auth(&mut self, cert_chain: Vec<Certificate>, key: PrivateKey) -> &mut Self {
+        self.client_auth = Some(Some((cert_chain, key)));
+        self
+    }
 }
 
 impl ProverConfig {
@@ -43,25 +220,156 @@ impl ProverConfig {
         self.max_recv_data
     }
 
+    /// Returns the padding the Notary applies to the transcript lengths in the session header.
+    pub fn length_padding(&self) -> LengthPadding {
+        self.length_padding
+    }
+
+    /// Returns the extension claims to request from the Notary.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns the cipher suites to offer to the server, as configured.
+    pub fn cipher_suites(&self) -> &[CipherSuite] {
+        &self.cipher_suites
+    }
+
+    /// Returns the cipher suites offered to the server, which are the configured suites
+    /// supported under MPC.
+    pub fn offered_cipher_suites(&self) -> Vec<SupportedCipherSuite> {
+        let supported = |suite: &SupportedCipherSuite| {
+            SUPPORTED_CIPHER_SUITES.contains(&suite.suite())
+                && (self.cipher_suites.is_empty() || self.cipher_suites.contains(&suite.suite()))
+        };
+
+        let mut suites: Vec<_> = ALL_CIPHER_SUITES
+            .iter()
+            .copied()
+            .filter(supported)
+            .collect();
+        // Keep the order of preference of the configured suites.
+        suites.sort_by_key(|suite| {
+            self.cipher_suites
+                .iter()
+                .position(|configured| *configured == suite.suite())
+        });
+
+        suites
+    }
+
+    /// Returns the application protocols to offer to the server with ALPN.
+    pub fn alpn_protocols(&self) -> &[Vec<u8>] {
+        &self.alpn_protocols
+    }
+
+    /// Returns whether decryption of data from the server is deferred until the connection is
+    /// closed.
+    ///
+    /// In this mode records received from the server are only committed to while the connection
+    /// is open, and are decrypted once it has closed, which removes the round trips to the Notary
+    /// from the interactive part of the session. The application data from the server is not
+    /// readable until then, so this mode only suits request/response exchanges where the prover
+    /// closes the connection, or the server closes it after responding. See
+    /// [`ProverControl::defer_decryption`](crate::tls::ProverControl::defer_decryption) for
+    /// enabling it part way through a session instead.
+    pub fn defer_decryption(&self) -> bool {
+        self.defer_decryption
+    }
+
+    /// Returns the data whose encryption and decryption is preprocessed during setup.
+    ///
+    /// Setup is the offline phase of the session: it only depends on the configuration, so it can
+    /// run before the user initiates the connection to the server, e.g. while an app starts up.
+    /// By default, encryption of the first 1KB of sent data and decryption of the handshake are
+    /// preprocessed, and the rest is computed while the connection is open. Preprocessing up to the
+    /// expected sizes of the request and response moves that work out of the latency-critical
+    /// part of the session, at the cost of a longer setup.
+    pub fn setup_preprocessing(&self) -> SetupPreprocessing {
+        SetupPreprocessing {
+            sent: self.setup_preprocess_sent,
+            recv: self.setup_preprocess_recv,
+        }
+    }
+
+    /// Returns the maximum number of threads to garble and evaluate the encryption and decryption
+    /// circuits on.
+    ///
+    /// The blocks of each record are split across the threads, and both parties must split them
+    /// the same way, so the session uses the smaller of the maximums of the prover and the
+    /// verifier. A prover on a low-end device can lower it, which also lowers the parallelism of
+    /// the verifier.
+    pub fn max_threads(&self) -> usize {
+        self.max_threads
+    }
+
+    /// Returns the name to send in the SNI extension instead of the server DNS name, if any.
+    pub fn sni_override(&self) -> Option<&str> {
+        self.sni_override.as_deref()
+    }
+
+    /// Returns the additional TLS extensions to include in the client hello.
+    pub fn tls_extensions(&self) -> &[(u16, Vec<u8>)] {
+        &self.tls_extensions
+    }
+
+    /// Returns the certificate chain and private key to authenticate to the server with, if any.
+    pub fn client_auth(&self) -> Option<(&[Certificate], &PrivateKey)> {
+        self.client_auth
+            .as_ref()
+            .map(|(cert_chain, key)| (cert_chain.as_slice(), key))
+    }
+
+    /// Returns the proxy to connect to the server through, if any.
+    pub fn proxy(&self) -> Option<&Proxy> {
+        self.proxy.as_ref()
+    }
+
+    /// Returns the maximum number of consecutive failed attempts to reconnect to the verifier.
+    pub fn max_reconnects(&self) -> usize {
+        self.max_reconnects
+    }
+
+    /// Returns the channels whose data sent to the verifier is compressed.
+    ///
+    /// Each party compresses the data it sends independently, so this doesn't affect the data
+    /// received from the verifier.
+    pub fn compression(&self) -> &Compression {
+        &self.compression
+    }
+
+    /// Returns the parameters of the KZG vector commitment to the transcript commitments, if any.
+    ///
+    /// The Notary signs the commitment in the session header, and substrings proofs built with
+    /// [`SubstringsProofBuilder::build_kzg`](tlsn_core::proof::SubstringsProofBuilder::build_kzg)
+    /// and the same parameters open it.
+    #[cfg(feature = "kzg")]
+    pub fn kzg_params(&self) -> Option<&tlsn_core::kzg::KzgParams> {
+        self.kzg_params.as_deref()
+    }
+
     /// Returns the server DNS name.
     pub fn server_dns(&self) -> &str {
         &self.server_dns
     }
 
-    pub(crate) fn build_mpc_tls_config(&self) -> MpcTlsLeaderConfig {
+    pub(crate) fn build_mpc_tls_config(&self, num_threads: usize) -> MpcTlsLeaderConfig {
         MpcTlsLeaderConfig::builder()
             .common(
                 MpcTlsCommonConfig::builder()
                     .id(format!("{}/mpc_tls", &self.id))
+                    .num_threads(num_threads)
                     .tx_config(
                         TranscriptConfig::default_tx()
                             .max_size(self.max_sent_data)
+                            .setup_preprocess_size(self.setup_preprocess_sent)
                             .build()
                             .unwrap(),
                     )
                     .rx_config(
                         TranscriptConfig::default_rx()
                             .max_size(self.max_recv_data)
+                            .setup_preprocess_size(self.setup_preprocess_recv)
                             .build()
                             .unwrap(),
                     )
@@ -69,6 +377,7 @@ impl ProverConfig {
                     .build()
                     .unwrap(),
             )
+            .defer_decryption(self.defer_decryption)
             .build()
             .unwrap()
     }
@@ -1,4 +1,5 @@
 use std::error::Error;
+use tls_core::msgs::enums::CipherSuite;
 use tls_mpc::MpcTlsError;
 use tlsn_core::commitment::TranscriptCommitmentBuilderError;
 
@@ -9,7 +10,7 @@ pub enum ProverError {
     #[error(transparent)]
     TlsClientError(#[from] tls_client::Error),
     #[error(transparent)]
-    AsyncClientError(#[from] tls_client_async::ConnectionError),
+    AsyncClientError(tls_client_async::ConnectionError),
     #[error(transparent)]
     IOError(#[from] std::io::Error),
     #[error(transparent)]
@@ -28,6 +29,37 @@ pub enum ProverError {
     CommitmentError(#[from] CommitmentError),
     #[error("Range exceeds transcript length")]
     InvalidRange,
+    #[error("unknown transcript field: {0}")]
+    UnknownField(String),
+    #[error("no reveal request to decline")]
+    NoPendingRequest,
+    #[error("proxy error: {0}")]
+    ProxyError(String),
+    #[error(
+        "server supports none of the offered cipher suites {offered:?}{}",
+        fmt_chosen_suite(.chosen)
+    )]
+    NoCommonCipherSuite {
+        offered: Vec<CipherSuite>,
+        chosen: Option<CipherSuite>,
+    },
+}
+
+fn fmt_chosen_suite(chosen: &Option<CipherSuite>) -> String {
+    chosen
+        .map(|suite| format!(", it chose {suite:?}"))
+        .unwrap_or_default()
+}
+
+impl From<tls_client_async::ConnectionError> for ProverError {
+    fn from(e: tls_client_async::ConnectionError) -> Self {
+        match e {
+            tls_client_async::ConnectionError::TlsError(
+                tls_client::Error::NoCommonCipherSuite { offered, chosen },
+            ) => Self::NoCommonCipherSuite { offered, chosen },
+            e => Self::AsyncClientError(e),
+        }
+    }
 }
 
 impl From<MpcTlsError> for ProverError {
@@ -0,0 +1,240 @@
+//! Events emitted by the prover as the session progresses.
+
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use tls_client::{Backend, BackendError, BackendNotify, DecryptMode, EncryptMode};
+use tls_core::{
+    cert::ServerCertDetails,
+    ke::ServerKxDetails,
+    key::PublicKey,
+    msgs::{
+        enums::{ContentType, ProtocolVersion},
+        handshake::Random,
+        message::{OpaqueMessage, PlainMessage},
+    },
+    suites::SupportedCipherSuite,
+};
+
+/// An event emitted by the prover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProverEvent {
+    /// The MPC setup with the verifier has completed.
+    SetupComplete,
+    /// The server's Finished message was received, completing the TLS handshake.
+    HandshakeComplete,
+    /// A record of application data was sent to the server.
+    DataSent {
+        /// The number of bytes in the record.
+        len: usize,
+        /// The total number of bytes sent so far.
+        total: usize,
+    },
+    /// A record of application data from the server was decrypted.
+    ///
+    /// If decryption is deferred, this is emitted for each record once the connection has closed.
+    DataReceived {
+        /// The number of bytes in the record.
+        len: usize,
+        /// The total number of bytes received so far.
+        total: usize,
+    },
+    /// The connection to the server was closed.
+    ConnectionClosed {
+        /// The number of bytes sent to the server.
+        sent: usize,
+        /// The number of bytes received from the server.
+        received: usize,
+    },
+    /// Parts of the transcript were proven to the verifier.
+    Proved {
+        /// The number of sent bytes which were proven.
+        sent: usize,
+        /// The number of received bytes which were proven.
+        received: usize,
+    },
+    /// Finalization of the session started.
+    FinalizationStarted,
+    /// A step of the finalization completed.
+    FinalizationProgress {
+        /// The number of completed steps.
+        completed: usize,
+        /// The total number of steps.
+        total: usize,
+    },
+    /// The session was finalized.
+    Finalized,
+}
+
+/// Sends events to the application, if it subscribed to them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EventSender(Option<mpsc::UnboundedSender<ProverEvent>>);
+
+impl EventSender {
+    /// Creates a sender and the receiver for its events.
+    pub(crate) fn new() -> (Self, mpsc::UnboundedReceiver<ProverEvent>) {
+        let (sender, receiver) = mpsc::unbounded();
+        (Self(Some(sender)), receiver)
+    }
+
+    /// Emits an event, which is dropped if the receiver is gone.
+    pub(crate) fn emit(&self, event: ProverEvent) {
+        if let Some(sender) = &self.0 {
+            _ = sender.unbounded_send(event);
+        }
+    }
+}
+
+/// A TLS backend which emits events for the handshake and application data.
+pub(crate) struct EventBackend<B> {
+    inner: B,
+    events: EventSender,
+    sent: usize,
+    received: usize,
+}
+
+impl<B> EventBackend<B> {
+    pub(crate) fn new(inner: B, events: EventSender) -> Self {
+        Self {
+            inner,
+            events,
+            sent: 0,
+            received: 0,
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Backend for EventBackend<B> {
+    async fn set_protocol_version(&mut self, version: ProtocolVersion) -> Result<(), BackendError> {
+        self.inner.set_protocol_version(version).await
+    }
+
+    async fn set_cipher_suite(&mut self, suite: SupportedCipherSuite) -> Result<(), BackendError> {
+        self.inner.set_cipher_suite(suite).await
+    }
+
+    async fn get_suite(&mut self) -> Result<SupportedCipherSuite, BackendError> {
+        self.inner.get_suite().await
+    }
+
+    async fn set_encrypt(&mut self, mode: EncryptMode) -> Result<(), BackendError> {
+        self.inner.set_encrypt(mode).await
+    }
+
+    async fn set_decrypt(&mut self, mode: DecryptMode) -> Result<(), BackendError> {
+        self.inner.set_decrypt(mode).await
+    }
+
+    async fn get_client_random(&mut self) -> Result<Random, BackendError> {
+        self.inner.get_client_random().await
+    }
+
+    async fn get_client_key_share(&mut self) -> Result<PublicKey, BackendError> {
+        self.inner.get_client_key_share().await
+    }
+
+    async fn set_server_random(&mut self, random: Random) -> Result<(), BackendError> {
+        self.inner.set_server_random(random).await
+    }
+
+    async fn set_server_key_share(&mut self, key: PublicKey) -> Result<(), BackendError> {
+        self.inner.set_server_key_share(key).await
+    }
+
+    async fn set_server_cert_details(
+        &mut self,
+        cert_details: ServerCertDetails,
+    ) -> Result<(), BackendError> {
+        self.inner.set_server_cert_details(cert_details).await
+    }
+
+    async fn set_server_kx_details(
+        &mut self,
+        kx_details: ServerKxDetails,
+    ) -> Result<(), BackendError> {
+        self.inner.set_server_kx_details(kx_details).await
+    }
+
+    async fn set_hs_hash_client_key_exchange(&mut self, hash: Vec<u8>) -> Result<(), BackendError> {
+        self.inner.set_hs_hash_client_key_exchange(hash).await
+    }
+
+    async fn set_hs_hash_server_hello(&mut self, hash: Vec<u8>) -> Result<(), BackendError> {
+        self.inner.set_hs_hash_server_hello(hash).await
+    }
+
+    async fn get_server_finished_vd(&mut self, hash: Vec<u8>) -> Result<Vec<u8>, BackendError> {
+        self.inner.get_server_finished_vd(hash).await
+    }
+
+    async fn get_client_finished_vd(&mut self, hash: Vec<u8>) -> Result<Vec<u8>, BackendError> {
+        self.inner.get_client_finished_vd(hash).await
+    }
+
+    async fn prepare_encryption(&mut self) -> Result<(), BackendError> {
+        self.inner.prepare_encryption().await
+    }
+
+    async fn encrypt(
+        &mut self,
+        msg: PlainMessage,
+        seq: u64,
+    ) -> Result<OpaqueMessage, BackendError> {
+        let (typ, len) = (msg.typ, msg.payload.0.len());
+        let msg = self.inner.encrypt(msg, seq).await?;
+
+        if typ == ContentType::ApplicationData {
+            self.sent += len;
+            self.events.emit(ProverEvent::DataSent {
+                len,
+                total: self.sent,
+            });
+        }
+
+        Ok(msg)
+    }
+
+    async fn decrypt(
+        &mut self,
+        msg: OpaqueMessage,
+        seq: u64,
+    ) -> Result<PlainMessage, BackendError> {
+        let msg = self.inner.decrypt(msg, seq).await?;
+
+        match msg.typ {
+            ContentType::Handshake => self.events.emit(ProverEvent::HandshakeComplete),
+            ContentType::ApplicationData => {
+                let len = msg.payload.0.len();
+                self.received += len;
+                self.events.emit(ProverEvent::DataReceived {
+                    len,
+                    total: self.received,
+                });
+            }
+            _ => {}
+        }
+
+        Ok(msg)
+    }
+
+    async fn buffer_incoming(&mut self, msg: OpaqueMessage) -> Result<(), BackendError> {
+        self.inner.buffer_incoming(msg).await
+    }
+
+    async fn next_incoming(&mut self) -> Result<Option<OpaqueMessage>, BackendError> {
+        self.inner.next_incoming().await
+    }
+
+    async fn get_notify(&mut self) -> Result<BackendNotify, BackendError> {
+        self.inner.get_notify().await
+    }
+
+    async fn buffer_len(&mut self) -> Result<usize, BackendError> {
+        self.inner.buffer_len().await
+    }
+
+    async fn server_closed(&mut self) -> Result<(), BackendError> {
+        self.inner.server_closed().await
+    }
+}
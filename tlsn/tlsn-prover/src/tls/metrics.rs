@@ -0,0 +1,199 @@
+//! Bandwidth and timing metrics of a prover session.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{AsyncRead, AsyncWrite};
+use web_time::Instant;
+
+/// A protocol phase of the prover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// MPC setup with the verifier, prior to connecting to the server.
+    Setup,
+    /// The TLS connection to the server, from connecting until it is closed.
+    Connection,
+    /// Finalization of the session with the verifier.
+    Finalization,
+}
+
+/// A report of the bandwidth used and the time spent by a prover session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MetricsReport {
+    /// Bytes sent to the verifier.
+    pub verifier_sent: usize,
+    /// Bytes received from the verifier.
+    pub verifier_received: usize,
+    /// Bytes sent to the server, including TLS framing.
+    pub server_sent: usize,
+    /// Bytes received from the server, including TLS framing.
+    pub server_received: usize,
+    /// Number of oblivious transfers set up with the prover as the sender.
+    ///
+    /// Together with garbled circuits, oblivious transfers make up most of the traffic with the
+    /// verifier.
+    pub ot_sender_count: usize,
+    /// Number of oblivious transfers set up with the prover as the receiver.
+    pub ot_receiver_count: usize,
+    /// Time spent in each phase which has completed, in the order they completed.
+    pub phases: Vec<(Phase, Duration)>,
+}
+
+impl MetricsReport {
+    /// Returns the time spent in the given phase, if it has completed.
+    pub fn phase(&self, phase: Phase) -> Option<Duration> {
+        self.phases
+            .iter()
+            .find(|(p, _)| *p == phase)
+            .map(|(_, duration)| *duration)
+    }
+}
+
+/// A handle to the metrics of a prover session.
+///
+/// The metrics are updated while the session progresses, and are complete once the prover has
+/// been finalized.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    verifier: Counters,
+    server: Counters,
+    ot_sender_count: AtomicUsize,
+    ot_receiver_count: AtomicUsize,
+    phases: Mutex<Vec<(Phase, Duration)>>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicUsize,
+    received: AtomicUsize,
+}
+
+impl Metrics {
+    /// Returns a report of the metrics collected so far.
+    pub fn report(&self) -> MetricsReport {
+        MetricsReport {
+            verifier_sent: self.0.verifier.sent.load(Ordering::Relaxed),
+            verifier_received: self.0.verifier.received.load(Ordering::Relaxed),
+            server_sent: self.0.server.sent.load(Ordering::Relaxed),
+            server_received: self.0.server.received.load(Ordering::Relaxed),
+            ot_sender_count: self.0.ot_sender_count.load(Ordering::Relaxed),
+            ot_receiver_count: self.0.ot_receiver_count.load(Ordering::Relaxed),
+            phases: self.0.phases.lock().unwrap().clone(),
+        }
+    }
+
+    /// Wraps the socket to the verifier to count the bytes exchanged over it.
+    pub(crate) fn verifier_io<S>(&self, io: S) -> CountingIo<S> {
+        CountingIo {
+            io,
+            metrics: self.0.clone(),
+            server: false,
+        }
+    }
+
+    /// Wraps the socket to the server to count the bytes exchanged over it.
+    pub(crate) fn server_io<S>(&self, io: S) -> CountingIo<S> {
+        CountingIo {
+            io,
+            metrics: self.0.clone(),
+            server: true,
+        }
+    }
+
+    pub(crate) fn set_ot_counts(&self, sender: usize, receiver: usize) {
+        self.0.ot_sender_count.store(sender, Ordering::Relaxed);
+        self.0.ot_receiver_count.store(receiver, Ordering::Relaxed);
+    }
+
+    /// Starts timing a phase, which is recorded when [`PhaseTimer::finish`] is called.
+    pub(crate) fn start(&self, phase: Phase) -> PhaseTimer {
+        PhaseTimer {
+            metrics: self.0.clone(),
+            phase,
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Times a protocol phase.
+pub(crate) struct PhaseTimer {
+    metrics: Arc<Inner>,
+    phase: Phase,
+    start: Instant,
+}
+
+impl PhaseTimer {
+    /// Records the time elapsed since the phase started.
+    pub(crate) fn finish(self) {
+        self.metrics
+            .phases
+            .lock()
+            .unwrap()
+            .push((self.phase, self.start.elapsed()));
+    }
+}
+
+/// A socket which counts the bytes read from and written to it.
+pub(crate) struct CountingIo<S> {
+    io: S,
+    metrics: Arc<Inner>,
+    server: bool,
+}
+
+impl<S> CountingIo<S> {
+    fn counters(&self) -> &Counters {
+        if self.server {
+            &self.metrics.server
+        } else {
+            &self.metrics.verifier
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingIo<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.io).poll_read(cx, buf);
+        if let Poll::Ready(Ok(read)) = poll {
+            self.counters().received.fetch_add(read, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingIo<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.io).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.counters().sent.fetch_add(written, Ordering::Relaxed);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_close(cx)
+    }
+}
@@ -7,22 +7,42 @@
 
 mod config;
 mod error;
+mod event;
 mod future;
+mod metrics;
 mod notarize;
 mod prove;
+mod proxy;
 pub mod state;
 
-pub use config::{ProverConfig, ProverConfigBuilder, ProverConfigBuilderError};
+pub use config::{
+    is_allowed_tls_extension, ProverConfig, ProverConfigBuilder, ProverConfigBuilderError,
+};
 pub use error::ProverError;
+pub use event::ProverEvent;
 pub use future::ProverFuture;
+pub use metrics::{Metrics, MetricsReport, Phase};
+pub use proxy::{Proxy, ProxyKind};
+pub use tls_mpc::SUPPORTED_CIPHER_SUITES;
+pub use tlsn_common::{
+    compress::Compression,
+    resume::{Reconnect, Socket},
+};
 use tlsn_common::{
+    compress::PeerCompression,
     mux::{attach_mux, MuxControl},
+    resume::resumable,
     Role,
 };
 
 use error::OTShutdownError;
+use event::{EventBackend, EventSender};
 use future::{MuxFuture, OTFuture};
-use futures::{AsyncRead, AsyncWrite, FutureExt, StreamExt, TryFutureExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    future::BoxFuture,
+    AsyncRead, AsyncWrite, FutureExt, SinkExt, StreamExt, TryFutureExt,
+};
 use mpz_garble::{config::Role as DEAPRole, protocol::deap::DEAPVm};
 use mpz_ot::{
     actor::kos::{ReceiverActor, SenderActor, SharedReceiver, SharedSender},
@@ -35,8 +55,13 @@ use std::sync::Arc;
 use tls_client::{ClientConnection, ServerName as TlsServerName};
 use tls_client_async::{bind_client, ClosedConnection, TlsConnection};
 use tls_mpc::{setup_components, LeaderCtrl, MpcTlsLeader, TlsRole};
-use tlsn_core::transcript::Transcript;
-use utils_aio::mux::MuxChannel;
+use tlsn_core::{
+    msg::{ProvingInfo, TlsnMessage},
+    transcript::Transcript,
+    Direction,
+};
+use utils::range::RangeSet;
+use utils_aio::{duplex::Duplex, expect_msg_or_err, mux::MuxChannel};
 
 #[cfg(feature = "formats")]
 use crate::http::{state as http_state, HttpProver, HttpProverError};
@@ -48,9 +73,21 @@ use tracing::{debug, debug_span, instrument, Instrument};
 #[derive(Debug)]
 pub struct Prover<T: state::ProverState> {
     config: ProverConfig,
+    events: EventSender,
+    metrics: Metrics,
     state: T,
 }
 
+impl<T: state::ProverState> Prover<T> {
+    /// Returns a handle to the bandwidth and timing metrics of the session.
+    ///
+    /// The handle remains valid after the prover is finalized, at which point its
+    /// [report](Metrics::report) covers the whole session.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
+}
+
 impl Prover<state::Initialized> {
     /// Creates a new prover.
     ///
@@ -60,14 +97,29 @@ impl Prover<state::Initialized> {
     pub fn new(config: ProverConfig) -> Self {
         Self {
             config,
+            events: EventSender::default(),
+            metrics: Metrics::default(),
             state: state::Initialized,
         }
     }
 
+    /// Returns a stream of events which report the progress of the session, e.g. to drive a
+    /// progress indicator or to find out where a session stalls.
+    ///
+    /// Events are only emitted to the stream returned by the latest call, and are dropped if it
+    /// is not polled.
+    pub fn events(&mut self) -> mpsc::UnboundedReceiver<ProverEvent> {
+        let (events, receiver) = EventSender::new();
+        self.events = events;
+        receiver
+    }
+
     /// Set up the prover.
     ///
     /// This performs all MPC setup prior to establishing the connection to the
-    /// application server.
+    /// application server. None of it depends on the data of the session, so it
+    /// can run ahead of time to keep it out of the latency of the connection, see
+    /// [`ProverConfig::setup_preprocessing`].
     ///
     /// # Arguments
     ///
@@ -76,20 +128,81 @@ impl Prover<state::Initialized> {
         self,
         socket: S,
     ) -> Result<Prover<state::Setup>, ProverError> {
-        let (mut mux, mux_ctrl) = attach_mux(socket, Role::Prover);
+        self.setup_inner(socket, None).await
+    }
+
+    /// Set up the prover over a connection which is resumed if it drops.
+    ///
+    /// Same as [`setup`](Self::setup), except that when the connection to the notary drops, a new
+    /// one is obtained with `reconnect` and the session continues where it left off, instead of
+    /// failing. The session fails once [`max_reconnects`](ProverConfig::max_reconnects)
+    /// consecutive attempts to reconnect have failed.
+    ///
+    /// The notary must also set up the session as resumable.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The socket to the notary.
+    /// * `reconnect` - Opens a new socket to the notary, e.g. a closure returning a future which
+    ///   connects to it.
+    pub async fn setup_resumable<
+        S: AsyncWrite + AsyncRead + Send + Unpin + 'static,
+        R: Reconnect + 'static,
+    >(
+        self,
+        socket: S,
+        reconnect: R,
+    ) -> Result<Prover<state::Setup>, ProverError> {
+        let (socket, resume_fut) = resumable(socket, reconnect, self.config.max_reconnects());
+        self.setup_inner(socket, Some(resume_fut)).await
+    }
+
+    async fn setup_inner<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+        self,
+        socket: S,
+        resume_fut: Option<BoxFuture<'static, std::io::Result<()>>>,
+    ) -> Result<Prover<state::Setup>, ProverError> {
+        let timer = self.metrics.start(Phase::Setup);
+        self.metrics.set_ot_counts(
+            self.config.ot_sender_setup_count(),
+            self.config.ot_receiver_setup_count(),
+        );
+
+        let (mut mux, mux_ctrl, peer_compression) = attach_mux(
+            self.metrics.verifier_io(socket),
+            Role::Prover,
+            self.config.compression().clone(),
+        );
 
         let mut mux_fut = MuxFuture {
-            fut: Box::pin(async move { mux.run().await.map_err(ProverError::from) }.fuse()),
+            fut: Box::pin(
+                async move {
+                    let run_fut = mux.run().map_err(ProverError::from);
+                    match resume_fut {
+                        Some(resume_fut) => {
+                            futures::try_join!(run_fut, resume_fut.map_err(ProverError::from))
+                                .map(|_| ())
+                        }
+                        None => run_fut.await,
+                    }
+                }
+                .fuse(),
+            ),
         };
 
-        let mpc_setup_fut = setup_mpc_backend(&self.config, mux_ctrl.clone());
+        let mpc_setup_fut = setup_mpc_backend(&self.config, mux_ctrl.clone(), peer_compression);
         let (mpc_tls, vm, _, gf2, ot_fut) = futures::select! {
             res = mpc_setup_fut.fuse() => res?,
             _ = (&mut mux_fut).fuse() => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
         };
 
+        timer.finish();
+        self.events.emit(ProverEvent::SetupComplete);
+
         Ok(Prover {
             config: self.config,
+            events: self.events,
+            metrics: self.metrics,
             state: state::Setup {
                 mux_ctrl,
                 mux_fut,
@@ -110,7 +223,7 @@ impl Prover<state::Setup> {
     ///
     /// # Arguments
     ///
-    /// * `socket` - The socket to the server.
+    /// * `socket` - The socket to the server, or to the proxy if one is configured.
     #[cfg_attr(
         feature = "tracing",
         instrument(level = "debug", skip(self, socket), err)
@@ -120,23 +233,44 @@ impl Prover<state::Setup> {
         socket: S,
     ) -> Result<(TlsConnection, ProverFuture), ProverError> {
         let state::Setup {
-            mux_ctrl,
+            mut mux_ctrl,
             mut mux_fut,
             mpc_tls,
-            vm,
+            mut vm,
             mut ot_fut,
             gf2,
         } = self.state;
 
         let (mpc_ctrl, mpc_fut) = mpc_tls.run();
+        let (reveal_sender, mut reveal_receiver) = mpsc::channel::<RevealRequest>(0);
 
         let server_name = TlsServerName::try_from(self.config.server_dns())?;
-        let config = tls_client::ClientConfig::builder()
-            .with_safe_defaults()
-            .with_root_certificates(self.config.root_cert_store.clone())
-            .with_no_client_auth();
-        let client =
-            ClientConnection::new(Arc::new(config), Box::new(mpc_ctrl.clone()), server_name)?;
+        let builder = tls_client::ClientConfig::builder()
+            .with_cipher_suites(&self.config.offered_cipher_suites())
+            .with_safe_default_kx_groups()
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(self.config.root_cert_store.clone());
+        let mut config = match self.config.client_auth() {
+            Some((cert_chain, key)) => {
+                builder.with_single_cert(cert_chain.to_vec(), key.clone())?
+            }
+            None => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = self.config.alpn_protocols().to_vec();
+        config.sni_override = self
+            .config
+            .sni_override()
+            .map(TlsServerName::try_from)
+            .transpose()?;
+        config.custom_extensions = self.config.tls_extensions().to_vec();
+        let backend = EventBackend::new(mpc_ctrl.clone(), self.events.clone());
+        let client = ClientConnection::new(Arc::new(config), Box::new(backend), server_name)?;
+
+        let timer = self.metrics.start(Phase::Connection);
+        let mut socket = self.metrics.server_io(socket);
+        if let Some(proxy) = self.config.proxy() {
+            proxy.connect(&mut socket, self.config.server_dns()).await?;
+        }
 
         let (conn, conn_fut) = bind_client(socket, client);
 
@@ -147,22 +281,52 @@ impl Prover<state::Setup> {
             #[allow(clippy::let_and_return)]
             let fut = async move {
                 let conn_fut = async {
-                    let ClosedConnection { sent, recv, .. } = futures::select! {
-                        res = conn_fut.fuse() => res?,
-                        _ = ot_fut => return Err(OTShutdownError)?,
-                        _ = mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+                    let conn_fut = conn_fut.fuse();
+                    futures::pin_mut!(conn_fut);
+
+                    let mut live_reveal = None;
+                    let ClosedConnection { sent, recv, .. } = loop {
+                        futures::select! {
+                            res = conn_fut => break res?,
+                            (proving_info, result) = reveal_receiver.select_next_some() => {
+                                let (sent, received) =
+                                    (proving_info.sent_ids.len(), proving_info.recv_ids.len());
+                                let res = prove::reveal_live(
+                                    &mut mux_ctrl,
+                                    &mut vm,
+                                    &mut live_reveal,
+                                    proving_info,
+                                )
+                                .await;
+                                if res.is_ok() {
+                                    self.events.emit(ProverEvent::Proved { sent, received });
+                                }
+                                _ = result.send(res);
+                            }
+                            _ = ot_fut => return Err(OTShutdownError)?,
+                            _ = mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+                        }
                     };
 
                     mpc_ctrl.close_connection().await?;
 
+                    self.events.emit(ProverEvent::ConnectionClosed {
+                        sent: sent.len(),
+                        received: recv.len(),
+                    });
+
                     Ok::<_, ProverError>((sent, recv))
                 };
 
                 let ((sent, recv), mpc_tls_data) =
                     futures::try_join!(conn_fut, mpc_fut.map_err(ProverError::from))?;
 
+                timer.finish();
+
                 Ok(Prover {
                     config: self.config,
+                    events: self.events,
+                    metrics: self.metrics,
                     state: state::Closed {
                         mux_ctrl,
                         mux_fut,
@@ -188,7 +352,10 @@ impl Prover<state::Setup> {
             conn,
             ProverFuture {
                 fut,
-                ctrl: ProverControl { mpc_ctrl },
+                ctrl: ProverControl {
+                    mpc_ctrl,
+                    reveal: reveal_sender,
+                },
             },
         ))
     }
@@ -218,6 +385,8 @@ impl Prover<state::Closed> {
     pub fn start_notarize(self) -> Prover<Notarize> {
         Prover {
             config: self.config,
+            events: self.events,
+            metrics: self.metrics,
             state: self.state.into(),
         }
     }
@@ -229,6 +398,8 @@ impl Prover<state::Closed> {
     pub fn start_prove(self) -> Prover<Prove> {
         Prover {
             config: self.config,
+            events: self.events,
+            metrics: self.metrics,
             state: self.state.into(),
         }
     }
@@ -240,6 +411,7 @@ impl Prover<state::Closed> {
 async fn setup_mpc_backend(
     config: &ProverConfig,
     mut mux: MuxControl,
+    peer_compression: PeerCompression,
 ) -> Result<
     (
         MpcTlsLeader,
@@ -250,6 +422,29 @@ async fn setup_mpc_backend(
     ),
     ProverError,
 > {
+    let mut setup_channel: Box<dyn Duplex<TlsnMessage>> = mux.get_channel("setup").await?;
+    setup_channel
+        .send(TlsnMessage::SetupPreprocessing(
+            config.setup_preprocessing(),
+        ))
+        .await?;
+
+    // Both parties split the circuits across the smaller of their maximum numbers of threads
+    setup_channel
+        .send(TlsnMessage::MaxThreads(config.max_threads()))
+        .await?;
+    let verifier_max_threads = expect_msg_or_err!(setup_channel, TlsnMessage::MaxThreads)?;
+    let num_threads = config.max_threads().min(verifier_max_threads).max(1);
+
+    // Channels opened from here on are compressed as announced by each party
+    setup_channel
+        .send(TlsnMessage::CompressedChannels(
+            config.compression().channels().to_vec(),
+        ))
+        .await?;
+    let verifier_compressed = expect_msg_or_err!(setup_channel, TlsnMessage::CompressedChannels)?;
+    peer_compression.set(verifier_compressed)?;
+
     let (ot_send_sink, ot_send_stream) = mux.get_channel("ot/0").await?.split();
     let (ot_recv_sink, ot_recv_stream) = mux.get_channel("ot/1").await?.split();
 
@@ -327,7 +522,7 @@ async fn setup_mpc_backend(
     let channel = mux.get_channel(gf2_config.id()).await?;
     let gf2 = ff::ConverterSender::<ff::Gf2_128, _>::new(gf2_config, ot_send.clone(), channel);
 
-    let mpc_tls_config = config.build_mpc_tls_config();
+    let mpc_tls_config = config.build_mpc_tls_config(num_threads);
 
     let (ke, prf, encrypter, decrypter) = setup_components(
         mpc_tls_config.common(),
@@ -353,10 +548,14 @@ async fn setup_mpc_backend(
     Ok((mpc_tls, vm, ot_recv, gf2, ot_fut))
 }
 
+/// A request to reveal parts of the transcript while the connection is open.
+type RevealRequest = (ProvingInfo, oneshot::Sender<Result<(), ProverError>>);
+
 /// A controller for the prover.
 #[derive(Clone)]
 pub struct ProverControl {
     mpc_ctrl: LeaderCtrl,
+    reveal: mpsc::Sender<RevealRequest>,
 }
 
 impl ProverControl {
@@ -375,4 +574,46 @@ impl ProverControl {
             .await
             .map_err(ProverError::from)
     }
+
+    /// Reveals `data`, which is located at `offset` in the transcript of the given direction, to
+    /// the verifier while the connection is still open, e.g. after each response.
+    ///
+    /// Returns once the verifier has verified the data, which it receives with
+    /// `Verifier::run_with_reveals`, so subsequent steps can be gated on the verifier having seen
+    /// it. This is only supported in the direct prover-verifier mode, and the data must already
+    /// have been sent, or received and decrypted, so it is not supported for received data while
+    /// decryption is deferred.
+    ///
+    /// # Notes
+    ///
+    /// * The connection does not make progress while the data is being revealed.
+    pub async fn reveal(
+        &self,
+        direction: Direction,
+        offset: usize,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(), ProverError> {
+        let data = data.into();
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let ranges = RangeSet::from(offset..offset + data.len());
+        let mut proving_info = ProvingInfo::default();
+        match direction {
+            Direction::Sent => proving_info.sent_ids = ranges,
+            Direction::Received => proving_info.recv_ids = ranges,
+        }
+        proving_info.cleartext = data;
+
+        let closed = || std::io::Error::from(std::io::ErrorKind::NotConnected);
+        let (sender, receiver) = oneshot::channel();
+        self.reveal
+            .clone()
+            .send((proving_info, sender))
+            .await
+            .map_err(|_| closed())?;
+
+        receiver.await.map_err(|_| closed())?
+    }
 }
@@ -4,7 +4,7 @@
 
 use crate::tls::error::OTShutdownError;
 
-use super::{ff::ShareConversionReveal, state::Notarize, Prover, ProverError};
+use super::{ff::ShareConversionReveal, state::Notarize, Phase, Prover, ProverError, ProverEvent};
 use futures::{FutureExt, SinkExt, StreamExt};
 use tlsn_core::{
     commitment::TranscriptCommitmentBuilder,
@@ -49,6 +49,9 @@ impl Prover<Notarize> {
             builder,
         } = self.state;
 
+        let timer = self.metrics.start(Phase::Finalization);
+        self.events.emit(ProverEvent::FinalizationStarted);
+
         let commitments = builder.build()?;
 
         let session_data = SessionData::new(
@@ -60,14 +63,50 @@ impl Prover<Notarize> {
         );
 
         let merkle_root = session_data.commitments().merkle_root();
-
+        #[cfg(feature = "kzg")]
+        let kzg_commitment = self
+            .config
+            .kzg_params()
+            .map(|params| session_data.commitments().kzg_vector(params))
+            .transpose()
+            .map_err(|e| ProverError::NotarizationError(e.to_string()))?
+            .map(|vector| vector.commitment());
+        #[cfg(not(feature = "kzg"))]
+        let kzg_commitment = None;
+        let hash_algorithm = session_data.commitments().hash_algorithm();
+
+        let length_padding = self.config.length_padding();
+        let extensions = self.config.extensions().clone();
+        let events = self.events.clone();
+        let progress = move |completed| {
+            events.emit(ProverEvent::FinalizationProgress {
+                completed,
+                total: 3,
+            })
+        };
         let mut notarize_fut = Box::pin(async move {
             let mut channel = mux_ctrl.get_channel("notarize").await?;
 
+            channel
+                .send(TlsnMessage::LengthPadding(length_padding))
+                .await?;
+
+            channel
+                .send(TlsnMessage::CommitmentHashAlgorithm(hash_algorithm))
+                .await?;
+
+            channel.send(TlsnMessage::Extensions(extensions)).await?;
+
             channel
                 .send(TlsnMessage::TranscriptCommitmentRoot(merkle_root))
                 .await?;
 
+            channel
+                .send(TlsnMessage::KzgCommitment(kzg_commitment))
+                .await?;
+
+            progress(1);
+
             let notary_encoder_seed = vm
                 .finalize()
                 .await
@@ -81,13 +120,24 @@ impl Prover<Notarize> {
                 .await
                 .map_err(|e| ProverError::MpcError(Box::new(e)))?;
 
+            progress(2);
+
             let signed_header = expect_msg_or_err!(channel, TlsnMessage::SignedSessionHeader)?;
 
+            progress(3);
+
             Ok::<_, ProverError>((notary_encoder_seed, signed_header))
         })
         .fuse();
 
-        let (notary_encoder_seed, SignedSessionHeader { header, signature }) = futures::select_biased! {
+        let (
+            notary_encoder_seed,
+            SignedSessionHeader {
+                header,
+                signature,
+                tee_quote,
+            },
+        ) = futures::select_biased! {
             res = notarize_fut => res?,
             _ = ot_fut => return Err(OTShutdownError)?,
             _ = &mut mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
@@ -110,6 +160,45 @@ impl Prover<Notarize> {
                 )
             })?;
 
-        Ok(NotarizedSession::new(header, Some(signature), session_data))
+        if header.sent_len() < session_data.sent_transcript().data().len()
+            || header.recv_len() < session_data.recv_transcript().data().len()
+        {
+            return Err(ProverError::NotarizationError(
+                "notary signed transcript lengths shorter than the transcript".to_string(),
+            ));
+        }
+
+        if header.kzg_commitment() != kzg_commitment.as_ref() {
+            return Err(ProverError::NotarizationError(
+                "notary signed an incorrect KZG commitment".to_string(),
+            ));
+        }
+
+        if header.hash_algorithm() != hash_algorithm {
+            return Err(ProverError::NotarizationError(
+                "notary signed an incorrect commitment hash algorithm".to_string(),
+            ));
+        }
+
+        if let Some(id) =
+            self.config.extensions().ids().find(|id| {
+                header.extensions().get_raw(*id) != self.config.extensions().get_raw(*id)
+            })
+        {
+            return Err(ProverError::NotarizationError(format!(
+                "notary signed an altered extension: {:?}",
+                id
+            )));
+        }
+
+        timer.finish();
+        self.events.emit(ProverEvent::Finalized);
+
+        let mut session = NotarizedSession::new(header, Some(signature), session_data);
+        if let Some(tee_quote) = tee_quote {
+            session.set_tee_quote(tee_quote);
+        }
+
+        Ok(session)
     }
 }
@@ -3,17 +3,26 @@
 //! Here the prover deals with a verifier directly, so there is no notary involved. Instead
 //! the verifier directly verifies parts of the transcript.
 
-use super::{state::Prove as ProveState, Prover, ProverError};
+use super::{state::Prove as ProveState, Phase, Prover, ProverError, ProverEvent};
 use crate::tls::error::OTShutdownError;
-use futures::{FutureExt, SinkExt};
-use mpz_garble::{Memory, Prove, Vm};
+use futures::{FutureExt, SinkExt, StreamExt};
+use mpz_garble::{
+    protocol::deap::{DEAPThread, DEAPVm},
+    Memory, Prove, Vm,
+};
+use mpz_ot::actor::kos::{SharedReceiver, SharedSender};
 use mpz_share_conversion::ShareConversionReveal;
+use tlsn_common::mux::MuxControl;
 use tlsn_core::{
-    msg::TlsnMessage, proof::SessionInfo, transcript::get_value_ids, Direction, ServerName,
-    Transcript,
+    msg::{ProvingInfo, RevealRequest, TlsnMessage},
+    proof::SessionInfo,
+    transcript::get_value_ids,
+    Direction, ServerName, Transcript,
 };
+#[cfg(feature = "formats")]
+use tlsn_formats::http::{HttpTranscript, NamedCommitments};
 use utils::range::{RangeSet, RangeUnion};
-use utils_aio::mux::MuxChannel;
+use utils_aio::{duplex::Duplex, expect_msg_or_err, mux::MuxChannel};
 
 #[cfg(feature = "tracing")]
 use tracing::info;
@@ -65,9 +74,82 @@ impl Prover<ProveState> {
         Ok(())
     }
 
+    /// Reveals the parts of the transcripts requested by the verifier, see
+    /// [`Prover::receive_request_after_close`].
+    ///
+    /// Named fields are resolved by parsing the transcripts as HTTP, see
+    /// [`NamedCommitments`](tlsn_formats::http::NamedCommitments), which requires the `formats`
+    /// feature.
+    ///
+    /// # Arguments
+    /// * `request` - The request of the verifier
+    pub fn reveal_request(&mut self, request: &RevealRequest) -> Result<(), ProverError> {
+        self.reveal(request.sent.clone(), Direction::Sent)?;
+        self.reveal(request.recv.clone(), Direction::Received)?;
+
+        for (ranges, direction) in resolve_fields(
+            &self.state.transcript_tx,
+            &self.state.transcript_rx,
+            &request.fields,
+        )? {
+            self.reveal(ranges, direction)?;
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the verifier to request parts of the transcripts.
+    ///
+    /// The verifier can only make requests once the TLS connection is closed, with
+    /// `Verifier::request_after_close`. While the connection is open, the prover reveals data of
+    /// its own choosing with [`ProverControl::reveal`](crate::tls::ProverControl::reveal).
+    ///
+    /// To consent, reveal the requested parts, e.g. with [`Prover::reveal_request`], and call
+    /// [`Prover::prove`]. Otherwise call [`Prover::decline`]. The verifier learns nothing about
+    /// the transcripts from a declined request.
+    pub async fn receive_request_after_close(&mut self) -> Result<RevealRequest, ProverError> {
+        let mut request_fut = Box::pin(async {
+            // Create a new channel if not already present
+            let channel = if let Some(ref mut channel) = self.state.channel {
+                channel
+            } else {
+                self.state.channel = Some(self.state.mux_ctrl.get_channel("prove-verify").await?);
+                self.state.channel.as_mut().unwrap()
+            };
+
+            let request = expect_msg_or_err!(channel, TlsnMessage::RevealRequest)?;
+
+            Ok::<_, ProverError>(request)
+        })
+        .fuse();
+
+        let request = futures::select_biased! {
+            res = request_fut => res?,
+            _ = &mut self.state.ot_fut => return Err(OTShutdownError)?,
+            _ = &mut self.state.mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+        };
+
+        Ok(request)
+    }
+
+    /// Declines the last request of the verifier, see
+    /// [`Prover::receive_request_after_close`].
+    pub async fn decline(&mut self) -> Result<(), ProverError> {
+        let channel = self
+            .state
+            .channel
+            .as_mut()
+            .ok_or(ProverError::NoPendingRequest)?;
+
+        channel.send(TlsnMessage::RevealDeclined).await?;
+
+        Ok(())
+    }
+
     /// Prove transcript values
     pub async fn prove(&mut self) -> Result<(), ProverError> {
         let mut proving_info = std::mem::take(&mut self.state.proving_info);
+        let (sent, received) = (proving_info.sent_ids.len(), proving_info.recv_ids.len());
 
         let mut prove_fut = Box::pin(async {
             // Create a new channel and vm thread if not already present
@@ -85,34 +167,6 @@ impl Prover<ProveState> {
                 self.state.prove_thread.as_mut().unwrap()
             };
 
-            // Now prove the transcript parts which have been marked for reveal
-            let sent_value_ids = proving_info
-                .sent_ids
-                .iter_ranges()
-                .map(|r| get_value_ids(&r.into(), Direction::Sent).collect::<Vec<String>>());
-            let recv_value_ids = proving_info
-                .recv_ids
-                .iter_ranges()
-                .map(|r| get_value_ids(&r.into(), Direction::Received).collect::<Vec<String>>());
-
-            let value_refs = sent_value_ids
-                .chain(recv_value_ids)
-                .map(|ids| {
-                    let inner_refs = ids
-                        .iter()
-                        .map(|id| {
-                            prove_thread
-                                .get_value(id.as_str())
-                                .expect("Byte should be in VM memory")
-                        })
-                        .collect::<Vec<_>>();
-
-                    prove_thread
-                        .array_from_values(inner_refs.as_slice())
-                        .expect("Byte should be in VM Memory")
-                })
-                .collect::<Vec<_>>();
-
             // Extract cleartext we want to reveal from transcripts
             let mut cleartext =
                 Vec::with_capacity(proving_info.sent_ids.len() + proving_info.recv_ids.len());
@@ -126,17 +180,7 @@ impl Prover<ProveState> {
                 .for_each(|r| cleartext.extend_from_slice(&self.state.transcript_rx.data()[r]));
             proving_info.cleartext = cleartext;
 
-            // Send the proving info to the verifier
-            channel.send(TlsnMessage::ProvingInfo(proving_info)).await?;
-
-            #[cfg(feature = "tracing")]
-            info!("Sent proving info to verifier");
-
-            // Prove the revealed transcript parts
-            prove_thread.prove(value_refs.as_slice()).await?;
-
-            #[cfg(feature = "tracing")]
-            info!("Successfully proved cleartext");
+            prove_cleartext(channel, prove_thread, proving_info).await?;
 
             Ok::<_, ProverError>(())
         })
@@ -148,6 +192,8 @@ impl Prover<ProveState> {
             _ = &mut self.state.mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
         };
 
+        self.events.emit(ProverEvent::Proved { sent, received });
+
         Ok(())
     }
 
@@ -163,12 +209,22 @@ impl Prover<ProveState> {
             ..
         } = self.state;
 
+        let timer = self.metrics.start(Phase::Finalization);
+        self.events.emit(ProverEvent::FinalizationStarted);
+
         // Create session data and session_info
         let session_info = SessionInfo {
             server_name: ServerName::Dns(self.config.server_dns().to_string()),
             handshake_decommitment,
         };
 
+        let events = self.events.clone();
+        let progress = move |completed| {
+            events.emit(ProverEvent::FinalizationProgress {
+                completed,
+                total: 2,
+            })
+        };
         let mut finalize_fut = Box::pin(async move {
             let mut channel = mux_ctrl.get_channel("finalize").await?;
 
@@ -185,9 +241,13 @@ impl Prover<ProveState> {
                 .await
                 .map_err(|e| ProverError::MpcError(Box::new(e)))?;
 
+            progress(1);
+
             // Send session_info to the verifier
             channel.send(TlsnMessage::SessionInfo(session_info)).await?;
 
+            progress(2);
+
             Ok::<_, ProverError>(())
         })
         .fuse();
@@ -201,6 +261,118 @@ impl Prover<ProveState> {
         // We need to wait for the verifier to correctly close the connection. Otherwise the prover
         // would rush ahead and close the connection before the verifier has finished.
         mux_fut.await?;
+
+        timer.finish();
+        self.events.emit(ProverEvent::Finalized);
+
         Ok(())
     }
 }
+
+/// Resolves named fields of the transcripts to the ranges to reveal.
+#[cfg(feature = "formats")]
+fn resolve_fields(
+    tx: &Transcript,
+    rx: &Transcript,
+    fields: &[String],
+) -> Result<Vec<(RangeSet<usize>, Direction)>, ProverError> {
+    let Some(first) = fields.first() else {
+        return Ok(Vec::new());
+    };
+
+    let http =
+        HttpTranscript::parse(tx, rx).map_err(|_| ProverError::UnknownField(first.clone()))?;
+    let named = NamedCommitments::new(&http, tx, rx);
+
+    fields
+        .iter()
+        .map(|field| {
+            named
+                .get(field)
+                .map(|field| (field.ranges().clone(), field.direction()))
+                .ok_or_else(|| ProverError::UnknownField(field.clone()))
+        })
+        .collect()
+}
+
+/// Resolves named fields of the transcripts to the ranges to reveal, which requires the
+/// `formats` feature.
+#[cfg(not(feature = "formats"))]
+fn resolve_fields(
+    _tx: &Transcript,
+    _rx: &Transcript,
+    fields: &[String],
+) -> Result<Vec<(RangeSet<usize>, Direction)>, ProverError> {
+    match fields.first() {
+        Some(field) => Err(ProverError::UnknownField(field.clone())),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The channel and VM thread used to reveal data while the connection is open.
+pub(crate) type LiveReveal = (
+    Box<dyn Duplex<TlsnMessage>>,
+    DEAPThread<SharedSender, SharedReceiver>,
+);
+
+/// Reveals parts of the transcript to the verifier while the connection is open.
+pub(crate) async fn reveal_live(
+    mux_ctrl: &mut MuxControl,
+    vm: &mut DEAPVm<SharedSender, SharedReceiver>,
+    live: &mut Option<LiveReveal>,
+    proving_info: ProvingInfo,
+) -> Result<(), ProverError> {
+    if live.is_none() {
+        let channel = mux_ctrl.get_channel("reveal").await?;
+        let thread = vm.new_thread("reveal").await?;
+        *live = Some((channel, thread));
+    }
+
+    let (channel, thread) = live.as_mut().expect("reveal channel is set");
+
+    prove_cleartext(channel, thread, proving_info).await
+}
+
+/// Sends the purported cleartext of the transcript ranges to the verifier and proves it.
+async fn prove_cleartext(
+    channel: &mut Box<dyn Duplex<TlsnMessage>>,
+    thread: &mut DEAPThread<SharedSender, SharedReceiver>,
+    proving_info: ProvingInfo,
+) -> Result<(), ProverError> {
+    let sent_value_ids = proving_info
+        .sent_ids
+        .iter_ranges()
+        .map(|r| get_value_ids(&r.into(), Direction::Sent).collect::<Vec<String>>());
+    let recv_value_ids = proving_info
+        .recv_ids
+        .iter_ranges()
+        .map(|r| get_value_ids(&r.into(), Direction::Received).collect::<Vec<String>>());
+
+    // Values which are not in memory have not been sent or decrypted (yet).
+    let value_refs = sent_value_ids
+        .chain(recv_value_ids)
+        .map(|ids| {
+            let inner_refs = ids
+                .iter()
+                .map(|id| thread.get_value(id.as_str()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(ProverError::InvalidRange)?;
+
+            Ok(thread.array_from_values(inner_refs.as_slice())?)
+        })
+        .collect::<Result<Vec<_>, ProverError>>()?;
+
+    // Send the proving info to the verifier
+    channel.send(TlsnMessage::ProvingInfo(proving_info)).await?;
+
+    #[cfg(feature = "tracing")]
+    info!("Sent proving info to verifier");
+
+    // Prove the revealed transcript parts
+    thread.prove(value_refs.as_slice()).await?;
+
+    #[cfg(feature = "tracing")]
+    info!("Successfully proved cleartext");
+
+    Ok(())
+}
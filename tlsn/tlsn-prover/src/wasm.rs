@@ -0,0 +1,50 @@
+//! Running the prover in the browser.
+//!
+//! Browsers do not give access to TCP sockets, so both the connection to the notary and the
+//! connection to the server go over WebSockets, opened with [`connect_ws`]. The connection to the
+//! server goes through a websockify-style proxy, which relays the binary messages of the WebSocket
+//! over a TCP connection to the server, e.g. `wss://proxy.example.com/?token=example.com`. The TLS
+//! connection is still end-to-end between the prover and the server.
+//!
+//! Futures run on the event loop of the thread which spawned them, and the MPC protocols are CPU
+//! intensive, so the prover should run in a web worker rather than on the main thread of the page.
+//! Within the worker, [`spawn`] runs the [`ProverFuture`](crate::tls::ProverFuture) as a separate
+//! task while the application drives the TLS connection.
+
+use futures::{channel::oneshot, AsyncRead, AsyncWrite, Future};
+use ws_stream_wasm::WsMeta;
+
+use crate::tls::ProverError;
+
+/// Opens a WebSocket connection, returning it as a byte stream.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the notary, or of the websockify-style proxy to the server.
+pub async fn connect_ws(
+    url: &str,
+) -> Result<impl AsyncRead + AsyncWrite + Send + Unpin + 'static, ProverError> {
+    let (_, stream) = WsMeta::connect(url, None).await.map_err(|err| {
+        std::io::Error::new(std::io::ErrorKind::ConnectionRefused, err.to_string())
+    })?;
+
+    Ok(stream.into_io())
+}
+
+/// Runs a future as a separate task on the event loop of the current thread.
+///
+/// Returns a future which resolves to the output of the task, or to an error if the task was
+/// dropped before completing.
+pub fn spawn<F>(fut: F) -> impl Future<Output = Result<F::Output, oneshot::Canceled>>
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    wasm_bindgen_futures::spawn_local(async move {
+        // Ignore if the output is not awaited.
+        _ = sender.send(fut.await);
+    });
+
+    receiver
+}
@@ -12,7 +12,9 @@ use axum::{
 };
 use futures::{channel::oneshot, AsyncRead, AsyncWrite};
 use hyper::{body::Bytes, server::conn::Http, StatusCode};
-use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls::{
+    server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore, ServerConfig,
+};
 
 use tokio_util::compat::FuturesAsyncReadCompatExt;
 
@@ -24,6 +26,12 @@ pub static SERVER_CERT_DER: &[u8] = include_bytes!("tls/domain.der");
 pub static SERVER_KEY_DER: &[u8] = include_bytes!("tls/domain_key.der");
 /// The domain name bound to the server certificate.
 pub static SERVER_DOMAIN: &str = "test-server.io";
+/// A certificate authority certificate fixture for client certificates.
+pub static CLIENT_CA_CERT_DER: &[u8] = include_bytes!("tls/clientCA.der");
+/// A client certificate fixture, issued by [`CLIENT_CA_CERT_DER`].
+pub static CLIENT_CERT_DER: &[u8] = include_bytes!("tls/client.der");
+/// A client private key fixture.
+pub static CLIENT_KEY_DER: &[u8] = include_bytes!("tls/client_key.der");
 
 struct AppState {
     shutdown: Option<oneshot::Sender<()>>,
@@ -51,6 +59,33 @@ pub async fn bind<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
         .with_single_cert(vec![cert], key)
         .unwrap();
 
+    serve(socket, config).await
+}
+
+/// Bind the server to the given socket, requiring clients to authenticate with a certificate
+/// issued by [`CLIENT_CA_CERT_DER`].
+pub async fn bind_with_client_auth<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
+    socket: T,
+) -> anyhow::Result<()> {
+    let key = PrivateKey(SERVER_KEY_DER.to_vec());
+    let cert = Certificate(SERVER_CERT_DER.to_vec());
+
+    let mut client_roots = RootCertStore::empty();
+    client_roots.add(&Certificate(CLIENT_CA_CERT_DER.to_vec()))?;
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(client_roots).boxed())
+        .with_single_cert(vec![cert], key)
+        .unwrap();
+
+    serve(socket, config).await
+}
+
+async fn serve<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
+    socket: T,
+    config: ServerConfig,
+) -> anyhow::Result<()> {
     let acceptor = TlsAcceptor::from(Arc::new(config));
 
     let conn = acceptor.accept(socket).await?;
@@ -0,0 +1,216 @@
+//! Caching of verified presentations.
+//!
+//! Verifying a presentation checks signatures, the server certificate chain and every
+//! commitment of the transcript proof, which is wasteful when the same presentation is submitted
+//! repeatedly, e.g. to a gateway which verifies it on every request. A [`PresentationCache`]
+//! remembers the presentations it verified, keyed by the hash of their encoding, so that
+//! verifying one again is a single lookup.
+//!
+//! Only successful verifications are cached. A cache must only be used with a single
+//! certificate verifier, as the cached results do not record which one accepted them.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use mpz_core::utils::blake3;
+use tls_core::verify::ServerCertVerifier;
+use tlsn_core::{
+    time::{SystemClock, TimeSource},
+    NotaryPublicKey,
+};
+
+use crate::presentation::{verify_presentation_bytes, PresentationError, VerifiedPresentation};
+
+/// A cached verification.
+#[derive(Debug)]
+struct Entry {
+    notary_public_key: NotaryPublicKey,
+    presentation: Arc<VerifiedPresentation>,
+    /// The time the presentation was verified, in seconds since the UNIX epoch.
+    verified_at: u64,
+    /// The value of the use counter when the entry was last used.
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<[u8; 32], Entry>,
+    uses: u64,
+}
+
+/// A bounded cache of verified presentations.
+///
+/// When full, the least recently used presentation is evicted. Presentations can also be
+/// evicted a fixed time after they were verified, see [`PresentationCache::with_ttl`], so that
+/// changes to the trusted keys or certificates take effect. Presentations are always evicted once
+/// their expiry time has passed. The cache is safe to share between threads.
+pub struct PresentationCache {
+    capacity: usize,
+    ttl: Option<u64>,
+    time_source: Arc<dyn TimeSource>,
+    entries: Mutex<Entries>,
+}
+
+impl std::fmt::Debug for PresentationCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PresentationCache")
+            .field("capacity", &self.capacity)
+            .field("ttl", &self.ttl)
+            .field("time_source", &"_")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl PresentationCache {
+    /// Creates a cache holding at most `capacity` presentations.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ttl: None,
+            time_source: Arc::new(SystemClock),
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Evicts presentations `ttl` after they were verified.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl.as_secs());
+        self
+    }
+
+    /// Sets the source of the current time used to evict presentations, see
+    /// [`PresentationCache::with_ttl`]. The system clock is used by default.
+    pub fn with_time_source(mut self, time_source: Arc<dyn TimeSource>) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// Returns the maximum number of cached presentations.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of cached presentations.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns whether no presentation is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached presentations.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Decodes a presentation and verifies it, see
+    /// [`verify_presentation_bytes`](crate::presentation::verify_presentation_bytes), unless it
+    /// was already verified with the same notary key.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The encoded presentation.
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify(
+        &self,
+        data: &[u8],
+        notary_public_key: impl Into<NotaryPublicKey>,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<Arc<VerifiedPresentation>, PresentationError> {
+        let notary_public_key = notary_public_key.into();
+        let key = blake3(data);
+        // Without the current time, expired entries can not be told apart, so the cache is
+        // bypassed.
+        let now = match self.ttl {
+            Some(_) => self.time_source.now().ok(),
+            None => Some(0),
+        };
+
+        if let Some(now) = now {
+            let mut guard = self.lock();
+            let Entries { entries, uses } = &mut *guard;
+            *uses += 1;
+
+            match entries.get_mut(&key) {
+                Some(entry) if self.is_expired(entry, now) => {
+                    entries.remove(&key);
+                }
+                Some(entry) if entry.notary_public_key == notary_public_key => {
+                    entry.last_used = *uses;
+                    return Ok(entry.presentation.clone());
+                }
+                _ => {}
+            }
+        }
+
+        let presentation = Arc::new(verify_presentation_bytes(
+            data,
+            notary_public_key.clone(),
+            cert_verifier,
+        )?);
+
+        if let (Some(now), true) = (now, self.capacity > 0) {
+            let mut guard = self.lock();
+            if guard.entries.len() >= self.capacity && !guard.entries.contains_key(&key) {
+                self.evict(&mut guard, now);
+            }
+
+            let Entries { entries, uses } = &mut *guard;
+            *uses += 1;
+            entries.insert(
+                key,
+                Entry {
+                    notary_public_key,
+                    presentation: presentation.clone(),
+                    verified_at: now,
+                    last_used: *uses,
+                },
+            );
+        }
+
+        Ok(presentation)
+    }
+
+    fn is_expired(&self, entry: &Entry, now: u64) -> bool {
+        self.ttl
+            .is_some_and(|ttl| now >= entry.verified_at.saturating_add(ttl))
+            || entry.presentation.expires_at.is_some_and(|expires_at| {
+                // Checked against the clock which verified the presentation.
+                SystemClock.now().map_or(true, |now| now >= expires_at)
+            })
+    }
+
+    /// Removes the expired entries, or the least recently used entry if none has expired.
+    fn evict(&self, entries: &mut Entries, now: u64) {
+        let len = entries.entries.len();
+        entries
+            .entries
+            .retain(|_, entry| !self.is_expired(entry, now));
+        if entries.entries.len() < len {
+            return;
+        }
+
+        if let Some(key) = entries
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| *key)
+        {
+            entries.entries.remove(&key);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        // The entries are consistent even if a thread panicked while holding the lock.
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
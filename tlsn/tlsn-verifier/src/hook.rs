@@ -0,0 +1,123 @@
+//! Hooks for validating application data during verification.
+//!
+//! Integrators often accept a session only if the disclosed data satisfies domain specific
+//! checks, e.g. that a balance is above a threshold. A [`DataHook`] is invoked with the verified
+//! data, and rejecting it fails the verification, so these checks are part of verification
+//! rather than a separate step which can be forgotten.
+//!
+//! Hooks are run by [`verify_presentation_with_hooks`](crate::presentation::verify_presentation_with_hooks)
+//! and, for interactive sessions, by [`Verifier::finalize`](crate::tls::Verifier::finalize) when
+//! configured with [`VerifierConfigBuilder::data_hooks`](crate::tls::VerifierConfigBuilder::data_hooks).
+
+use std::error::Error;
+
+use tlsn_core::{Direction, RedactedTranscript, ServerName, Transcript};
+use tlsn_formats::http::{HttpTranscript, NamedCommitments};
+
+/// The byte which redacted data is replaced with before parsing it as HTTP.
+const REDACTED_BYTE: u8 = b'X';
+
+/// An error returned by a [`DataHook`] which rejects the data.
+pub type HookError = Box<dyn Error + Send + Sync + 'static>;
+
+/// Verified application data passed to a [`DataHook`].
+#[derive(Debug)]
+pub struct ApplicationData<'a> {
+    /// The server the prover communicated with, if it is known at this point.
+    pub server_name: Option<&'a ServerName>,
+    /// The redacted transcript of the data sent to the server.
+    pub sent: &'a RedactedTranscript,
+    /// The redacted transcript of the data received from the server.
+    pub recv: &'a RedactedTranscript,
+    /// The transcripts parsed as HTTP, if they are valid HTTP/1.1.
+    ///
+    /// Redacted bytes are replaced with `X` before parsing, so hooks must check that the parts
+    /// of a message they rely on are [authenticated](RedactedTranscript::authed).
+    pub http: Option<&'a HttpTranscript>,
+}
+
+/// A check on verified application data.
+///
+/// Implemented for closures taking [`ApplicationData`].
+pub trait DataHook: Send + Sync {
+    /// Validates the data, returning an error to reject it.
+    fn validate(&self, data: &ApplicationData<'_>) -> Result<(), HookError>;
+}
+
+impl<F> DataHook for F
+where
+    F: Fn(&ApplicationData<'_>) -> Result<(), HookError> + Send + Sync,
+{
+    fn validate(&self, data: &ApplicationData<'_>) -> Result<(), HookError> {
+        self(data)
+    }
+}
+
+/// Runs the hooks on the data, returning the error of the first hook which rejects it.
+pub(crate) fn run_hooks<'a>(
+    hooks: impl IntoIterator<Item = &'a dyn DataHook>,
+    server_name: Option<&ServerName>,
+    sent: &RedactedTranscript,
+    recv: &RedactedTranscript,
+) -> Result<(), HookError> {
+    let mut hooks = hooks.into_iter().peekable();
+    if hooks.peek().is_none() {
+        return Ok(());
+    }
+
+    let http = parse_http(sent, recv);
+    let data = ApplicationData {
+        server_name,
+        sent,
+        recv,
+        http: http.as_ref(),
+    };
+
+    hooks.try_for_each(|hook| hook.validate(&data))
+}
+
+/// Returns whether the named HTTP fields, see [`NamedCommitments`], are revealed in full.
+pub(crate) fn fields_revealed(
+    fields: &[String],
+    sent: &RedactedTranscript,
+    recv: &RedactedTranscript,
+) -> bool {
+    if fields.is_empty() {
+        return true;
+    }
+
+    let [tx, rx] = fill_redacted(sent, recv);
+    let Ok(http) = HttpTranscript::parse(&tx, &rx) else {
+        return false;
+    };
+    let named = NamedCommitments::new(&http, &tx, &rx);
+
+    fields.iter().all(|field| {
+        named.get(field).is_some_and(|field| {
+            let authed = match field.direction() {
+                Direction::Sent => sent.authed(),
+                Direction::Received => recv.authed(),
+            };
+            field.ranges().iter_ranges().all(|range| {
+                authed
+                    .iter_ranges()
+                    .any(|authed| authed.start <= range.start && range.end <= authed.end)
+            })
+        })
+    })
+}
+
+fn parse_http(sent: &RedactedTranscript, recv: &RedactedTranscript) -> Option<HttpTranscript> {
+    let [sent, recv] = fill_redacted(sent, recv);
+
+    HttpTranscript::parse(&sent, &recv).ok()
+}
+
+/// Replaces the redacted bytes of the transcripts, so that they can be parsed.
+fn fill_redacted(sent: &RedactedTranscript, recv: &RedactedTranscript) -> [Transcript; 2] {
+    [sent, recv].map(|transcript| {
+        let mut transcript = transcript.clone();
+        transcript.set_redacted(REDACTED_BYTE);
+        Transcript::new(transcript.data().to_vec())
+    })
+}
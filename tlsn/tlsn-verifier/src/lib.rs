@@ -1,7 +1,20 @@
 //! TLSNotary verifier library.
+//!
+//! On `wasm32`, the `wasm` module exports presentation verification to JavaScript.
 
 #![deny(missing_docs, unreachable_pub, unused_must_use)]
 #![deny(clippy::all)]
-#![forbid(unsafe_code)]
+// `wasm_bindgen` generates unsafe code for the JavaScript bindings.
+#![cfg_attr(not(target_arch = "wasm32"), forbid(unsafe_code))]
+#![cfg_attr(target_arch = "wasm32", deny(unsafe_code))]
 
+pub mod cache;
+pub mod hook;
+pub mod policy;
+pub mod presentation;
+pub mod redact;
+pub mod report;
 pub mod tls;
+#[cfg(target_arch = "wasm32")]
+#[allow(unsafe_code)]
+pub mod wasm;
@@ -0,0 +1,434 @@
+//! Declarative acceptance rules for presentations.
+//!
+//! A [`Policy`] describes what a presentation must prove to be accepted: which notaries signed
+//! it, which servers it comes from, how old it may be and which parts of the transcript must be
+//! revealed. [`Policy::evaluate`] checks a presentation against every rule and returns a
+//! [`PolicyReport`] with the outcome of each, so that rejections can be explained, and which
+//! converts to a serializable [`VerificationReport`](crate::report::VerificationReport).
+//!
+//! For example, to accept presentations of `example.com` signed by a notary within the last day,
+//! which reveal the request line and the `Content-Type` header of the response:
+//!
+//! ```
+//! use p256::pkcs8::DecodePublicKey;
+//! use tlsn_verifier::policy::Policy;
+//!
+//! let notary_key = p256::PublicKey::from_public_key_pem(
+//!     "-----BEGIN PUBLIC KEY-----
+//! MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEBv36FI4ZFszJa0DQFJ3wWCXvVLFr
+//! cRzMG5kaTeHGoSzDu6cFqx3uEWYpFGo6C0EOUgf+mEgbktLrXocv5yHzKg==
+//! -----END PUBLIC KEY-----",
+//! )
+//! .unwrap();
+//!
+//! let policy = Policy {
+//!     notary_keys: vec![notary_key.into()],
+//!     server_names: vec!["example.com".to_string()],
+//!     max_age: Some(24 * 60 * 60),
+//!     revealed_sent: vec![0..16],
+//!     response_headers: vec!["content-type".to_string()],
+//!     ..Default::default()
+//! };
+//! ```
+
+use std::{fmt, ops::Range};
+
+use serde::Serialize;
+use tls_core::{ocsp::CertStatus, verify::ServerCertVerifier};
+use tlsn_core::{
+    proof::{default_cert_verifier, TlsProof},
+    quorum::QuorumPolicy,
+    time::{TimeError, TimeSource},
+    Direction, NotaryPublicKey, RedactedTranscript, ServerName,
+};
+
+use crate::report::Disclosure;
+
+/// Rules a presentation must satisfy to be accepted.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    /// Notary keys which are trusted to sign presentations. Presentations are always rejected if
+    /// this is empty and no `quorum` is set.
+    pub notary_keys: Vec<NotaryPublicKey>,
+    /// Require the presentation to be signed by a quorum of notaries, counting its
+    /// cosignatures, instead of by any one of `notary_keys`.
+    pub quorum: Option<QuorumPolicy>,
+    /// Accepted server names. Presentations of any server are accepted if this is empty.
+    pub server_names: Vec<String>,
+    /// Reject presentations notarized more than this many seconds ago.
+    pub max_age: Option<u64>,
+    /// Accept presentations whose expiry time, set by the notary, has passed. See
+    /// [`SessionHeader::expires_at`](tlsn_core::SessionHeader::expires_at).
+    pub allow_expired: bool,
+    /// Require the OCSP response stapled by the server to show that its certificate was not
+    /// revoked at the time of the session.
+    pub require_unrevoked: bool,
+    /// Ranges of the data sent to the server which must be revealed.
+    pub revealed_sent: Vec<Range<usize>>,
+    /// Ranges of the data received from the server which must be revealed.
+    pub revealed_recv: Vec<Range<usize>>,
+    /// Names of HTTP request headers which must be revealed in full, including their values.
+    ///
+    /// Names are matched case-insensitively.
+    pub request_headers: Vec<String>,
+    /// Names of HTTP response headers which must be revealed in full, including their values.
+    ///
+    /// Names are matched case-insensitively.
+    pub response_headers: Vec<String>,
+}
+
+/// A rule of a [`Policy`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Rule {
+    /// The presentation is signed by a trusted notary key.
+    NotaryKey,
+    /// The presentation is signed by at least this many trusted notaries.
+    NotaryQuorum(usize),
+    /// The server identity is valid and its name is accepted.
+    ServerName,
+    /// The presentation is recent enough.
+    MaxAge,
+    /// The expiry time set by the notary has not passed.
+    Expiry,
+    /// The server certificate was not revoked at the time of the session.
+    Unrevoked,
+    /// The transcript proof is valid.
+    Transcript,
+    /// A range of the sent data is revealed.
+    RevealedSent(Range<usize>),
+    /// A range of the received data is revealed.
+    RevealedRecv(Range<usize>),
+    /// An HTTP request header is revealed.
+    RequestHeader(String),
+    /// An HTTP response header is revealed.
+    ResponseHeader(String),
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::NotaryKey => write!(f, "notary key"),
+            Rule::NotaryQuorum(threshold) => write!(f, "quorum of {threshold} notaries"),
+            Rule::ServerName => write!(f, "server name"),
+            Rule::MaxAge => write!(f, "maximum age"),
+            Rule::Expiry => write!(f, "expiry"),
+            Rule::Unrevoked => write!(f, "unrevoked certificate"),
+            Rule::Transcript => write!(f, "transcript"),
+            Rule::RevealedSent(range) => write!(f, "revealed sent data {range:?}"),
+            Rule::RevealedRecv(range) => write!(f, "revealed received data {range:?}"),
+            Rule::RequestHeader(name) => write!(f, "revealed request header {name}"),
+            Rule::ResponseHeader(name) => write!(f, "revealed response header {name}"),
+        }
+    }
+}
+
+/// The outcome of evaluating a [`Rule`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum Outcome {
+    /// The presentation satisfies the rule.
+    Pass,
+    /// The presentation does not satisfy the rule, for the given reason.
+    Fail(String),
+}
+
+/// The result of evaluating a presentation against a [`Policy`].
+#[derive(Debug)]
+pub struct PolicyReport {
+    /// The outcome of each rule of the policy, in the order they were evaluated.
+    pub results: Vec<(Rule, Outcome)>,
+    /// The verified server name, if the server identity is valid.
+    pub server_name: Option<ServerName>,
+    /// The redacted sent and received transcripts, if the transcript proof is valid.
+    pub transcripts: Option<(RedactedTranscript, RedactedTranscript)>,
+    /// The time of the session, in seconds since the UNIX epoch.
+    pub session_time: u64,
+    /// The disclosed data and the commitments authenticating it, if the transcript proof is
+    /// valid.
+    pub disclosures: Vec<Disclosure>,
+}
+
+impl PolicyReport {
+    /// Returns whether the presentation satisfies every rule.
+    pub fn is_accepted(&self) -> bool {
+        self.results
+            .iter()
+            .all(|(_, outcome)| *outcome == Outcome::Pass)
+    }
+
+    /// Returns the rules which the presentation does not satisfy, with the reasons.
+    pub fn failures(&self) -> impl Iterator<Item = (&Rule, &str)> {
+        self.results
+            .iter()
+            .filter_map(|(rule, outcome)| match outcome {
+                Outcome::Pass => None,
+                Outcome::Fail(reason) => Some((rule, reason.as_str())),
+            })
+    }
+}
+
+impl Policy {
+    /// Evaluates a presentation against this policy.
+    ///
+    /// Every rule is evaluated, even after one failed, so that the report lists all the reasons
+    /// a presentation is rejected. Rules on the transcript are only satisfied if the transcript
+    /// proof is valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The presentation.
+    /// * `cert_verifier` - The verifier of the server certificate.
+    /// * `now` - The current time, in seconds since the UNIX epoch.
+    pub fn evaluate(
+        &self,
+        proof: TlsProof,
+        cert_verifier: &impl ServerCertVerifier,
+        now: u64,
+    ) -> PolicyReport {
+        self.evaluate_at(proof, cert_verifier, Ok(now))
+    }
+
+    /// Evaluates a presentation against this policy, taking the current time from `time`.
+    ///
+    /// The maximum age and expiry rules fail if the time source can not provide the current time. See
+    /// [`Policy::evaluate`].
+    pub fn evaluate_with_time_source(
+        &self,
+        proof: TlsProof,
+        cert_verifier: &impl ServerCertVerifier,
+        time: &dyn TimeSource,
+    ) -> PolicyReport {
+        self.evaluate_at(proof, cert_verifier, time.now())
+    }
+
+    fn evaluate_at(
+        &self,
+        proof: TlsProof,
+        cert_verifier: &impl ServerCertVerifier,
+        now: Result<u64, TimeError>,
+    ) -> PolicyReport {
+        let TlsProof {
+            session,
+            substrings,
+            cosignatures,
+            ..
+        } = proof;
+        let header = &session.header;
+
+        let mut results = Vec::new();
+
+        if let Some(quorum) = &self.quorum {
+            let outcome = match quorum.check(header, session.signature.as_ref(), &cosignatures) {
+                Ok(_) => Outcome::Pass,
+                Err(err) => Outcome::Fail(err.to_string()),
+            };
+            results.push((Rule::NotaryQuorum(quorum.threshold()), outcome));
+        } else {
+            let notary_key = match &session.signature {
+                None => Outcome::Fail("presentation is not signed".to_string()),
+                Some(signature) => {
+                    if self
+                        .notary_keys
+                        .iter()
+                        .any(|key| header.verify_signature(signature, key.clone()).is_ok())
+                    {
+                        Outcome::Pass
+                    } else {
+                        Outcome::Fail(
+                            "presentation is not signed by a trusted notary key".to_string(),
+                        )
+                    }
+                }
+            };
+            results.push((Rule::NotaryKey, notary_key));
+        }
+
+        let server_name = session.verify_server_identity(cert_verifier).cloned();
+        let outcome = match &server_name {
+            Err(err) => Outcome::Fail(err.to_string()),
+            Ok(name)
+                if !self.server_names.is_empty()
+                    && !self
+                        .server_names
+                        .iter()
+                        .any(|accepted| accepted.eq_ignore_ascii_case(name.as_str())) =>
+            {
+                Outcome::Fail(format!("server name {} is not accepted", name.as_str()))
+            }
+            Ok(_) => Outcome::Pass,
+        };
+        results.push((Rule::ServerName, outcome));
+
+        if let Some(max_age) = self.max_age {
+            let outcome = match &now {
+                Err(err) => Outcome::Fail(err.to_string()),
+                Ok(now) => {
+                    let age = now.saturating_sub(header.time());
+                    if age > max_age {
+                        Outcome::Fail(format!(
+                            "presentation is {age} seconds old, the maximum is {max_age}"
+                        ))
+                    } else {
+                        Outcome::Pass
+                    }
+                }
+            };
+            results.push((Rule::MaxAge, outcome));
+        }
+
+        if !self.allow_expired {
+            let outcome = match (header.expires_at(), &now) {
+                (None, _) => Outcome::Pass,
+                (Some(_), Err(err)) => Outcome::Fail(err.to_string()),
+                (Some(expires_at), Ok(now)) if *now >= expires_at => {
+                    Outcome::Fail(format!("presentation expired at {expires_at}"))
+                }
+                (Some(_), Ok(_)) => Outcome::Pass,
+            };
+            results.push((Rule::Expiry, outcome));
+        }
+
+        if self.require_unrevoked {
+            let outcome = match session.verify_revocation() {
+                Ok(CertStatus::Good) => Outcome::Pass,
+                Ok(status) => Outcome::Fail(format!("server certificate status is {status:?}")),
+                Err(err) => Outcome::Fail(err.to_string()),
+            };
+            results.push((Rule::Unrevoked, outcome));
+        }
+
+        let mut disclosures = substrings
+            .openings()
+            .map(|(id, info)| Disclosure {
+                direction: *info.direction(),
+                ranges: info.ranges().iter_ranges().collect(),
+                commitment_id: id,
+                commitment_kind: info.kind(),
+            })
+            .collect::<Vec<_>>();
+        disclosures.sort_by_key(|disclosure| {
+            (
+                disclosure.direction == Direction::Received,
+                disclosure.ranges.first().map(|range| range.start),
+            )
+        });
+
+        let transcripts = substrings.verify(header);
+        let outcome = match &transcripts {
+            Ok(_) => Outcome::Pass,
+            Err(err) => Outcome::Fail(err.to_string()),
+        };
+        results.push((Rule::Transcript, outcome));
+        let transcripts = transcripts.ok();
+        if transcripts.is_none() {
+            disclosures.clear();
+        }
+
+        let rules = self
+            .revealed_sent
+            .iter()
+            .map(|range| (Rule::RevealedSent(range.clone()), Direction::Sent))
+            .chain(
+                self.revealed_recv
+                    .iter()
+                    .map(|range| (Rule::RevealedRecv(range.clone()), Direction::Received)),
+            )
+            .chain(
+                self.request_headers
+                    .iter()
+                    .map(|name| (Rule::RequestHeader(name.clone()), Direction::Sent)),
+            )
+            .chain(
+                self.response_headers
+                    .iter()
+                    .map(|name| (Rule::ResponseHeader(name.clone()), Direction::Received)),
+            );
+        for (rule, direction) in rules {
+            let outcome = match &transcripts {
+                None => Outcome::Fail("transcript proof is invalid".to_string()),
+                Some((sent, recv)) => {
+                    let transcript = match direction {
+                        Direction::Sent => sent,
+                        Direction::Received => recv,
+                    };
+                    evaluate_transcript_rule(&rule, transcript)
+                }
+            };
+            results.push((rule, outcome));
+        }
+
+        PolicyReport {
+            results,
+            server_name: server_name.ok(),
+            transcripts,
+            session_time: header.time(),
+            disclosures,
+        }
+    }
+
+    /// Evaluates a presentation against this policy, verifying the server certificate using
+    /// trust anchors from the `webpki-roots` crate.
+    ///
+    /// See [`Policy::evaluate`].
+    pub fn evaluate_with_default_cert_verifier(&self, proof: TlsProof, now: u64) -> PolicyReport {
+        self.evaluate(proof, &default_cert_verifier(), now)
+    }
+}
+
+fn evaluate_transcript_rule(rule: &Rule, transcript: &RedactedTranscript) -> Outcome {
+    match rule {
+        Rule::RevealedSent(range) | Rule::RevealedRecv(range) => {
+            if is_revealed(transcript, range) {
+                Outcome::Pass
+            } else {
+                Outcome::Fail(format!("range {range:?} is not revealed"))
+            }
+        }
+        Rule::RequestHeader(name) | Rule::ResponseHeader(name) => {
+            if header_lines(transcript.data()).any(|line| {
+                is_header(&transcript.data()[line.clone()], name) && is_revealed(transcript, &line)
+            }) {
+                Outcome::Pass
+            } else {
+                Outcome::Fail(format!("header {name} is not revealed"))
+            }
+        }
+        _ => unreachable!("rule does not apply to the transcript"),
+    }
+}
+
+/// Returns whether a range of the transcript is revealed.
+fn is_revealed(transcript: &RedactedTranscript, range: &Range<usize>) -> bool {
+    range.is_empty()
+        || transcript
+            .authed()
+            .iter_ranges()
+            .any(|authed| authed.start <= range.start && range.end <= authed.end)
+}
+
+/// Returns the ranges of the lines of the data, each including the preceding line feed, if any,
+/// and the terminating CRLF, so that a revealed line can not be part of a longer line.
+fn header_lines(data: &[u8]) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        let line_start = start;
+        let end = data
+            .get(line_start..)?
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .map(|pos| line_start + pos + 2)?;
+        start = end;
+
+        Some(line_start.saturating_sub(1)..end)
+    })
+}
+
+/// Returns whether a line is a header with the given name.
+fn is_header(line: &[u8], name: &str) -> bool {
+    let line = line.strip_prefix(b"\n").unwrap_or(line);
+    line.len() > name.len()
+        && line[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+        && line[name.len()] == b':'
+}
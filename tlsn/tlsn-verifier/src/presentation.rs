@@ -0,0 +1,274 @@
+//! Offline verification of serialized presentations.
+//!
+//! Verifying a presentation does not require a connection to the prover or the notary. The
+//! functions in this module decode a presentation in the
+//! [binary presentation format](tlsn_core::proof::MAGIC) and check everything it claims: the
+//! notary signature, its expiry, the server identity, the timestamp binding and the transcript
+//! commitments.
+
+use std::path::Path;
+
+use tls_core::verify::ServerCertVerifier;
+use tlsn_core::{
+    proof::{
+        default_cert_verifier, PresentationFormatError, SessionProofError, SubstringsProofError,
+        TlsProof,
+    },
+    quorum::{QuorumError, QuorumPolicy},
+    revocation::ValidityError,
+    time::{SystemClock, TimeError, TimeSource},
+    timestamp::attestation_digest,
+    NotaryPublicKey, RedactedTranscript, ServerName, Signature,
+};
+
+use crate::hook::{run_hooks, DataHook, HookError};
+
+/// An error that can occur while verifying a serialized presentation.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum PresentationError {
+    /// The presentation file could not be read.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The presentation could not be decoded.
+    #[error(transparent)]
+    Format(#[from] PresentationFormatError),
+    /// The notary signature or the server identity is invalid.
+    #[error(transparent)]
+    Session(#[from] SessionProofError),
+    /// The transcript proof is invalid.
+    #[error(transparent)]
+    Substrings(#[from] SubstringsProofError),
+    /// Not enough trusted notaries signed the presentation.
+    #[error(transparent)]
+    Quorum(#[from] QuorumError),
+    /// The presentation has an expiry time, but the current time is unavailable.
+    #[error(transparent)]
+    Time(#[from] TimeError),
+    /// A [`DataHook`] rejected the disclosed data.
+    #[error("disclosed data was rejected: {0}")]
+    Rejected(HookError),
+}
+
+/// A verified presentation.
+#[derive(Debug)]
+pub struct VerifiedPresentation {
+    /// The server the prover communicated with.
+    pub server_name: ServerName,
+    /// The time of the session, in seconds since the UNIX epoch.
+    pub time: u64,
+    /// Whether the server closed the connection cleanly, see
+    /// [`SessionHeader::closed_cleanly`](tlsn_core::SessionHeader::closed_cleanly).
+    pub closed_cleanly: bool,
+    /// The time after which the presentation is no longer valid, in seconds since the UNIX
+    /// epoch, if the notary set one.
+    pub expires_at: Option<u64>,
+    /// The redacted transcript of the data sent to the server.
+    pub sent: RedactedTranscript,
+    /// The redacted transcript of the data received from the server.
+    pub recv: RedactedTranscript,
+}
+
+/// Verifies a presentation and returns the disclosed transcripts.
+///
+/// Presentations whose expiry time has passed according to the local clock are rejected.
+///
+/// # Arguments
+///
+/// * `proof` - The presentation.
+/// * `notary_public_key` - The public key of the notary.
+/// * `cert_verifier` - The certificate verifier.
+pub fn verify_presentation(
+    proof: TlsProof,
+    notary_public_key: impl Into<NotaryPublicKey>,
+    cert_verifier: &impl ServerCertVerifier,
+) -> Result<VerifiedPresentation, PresentationError> {
+    verify_presentation_with_hooks(proof, notary_public_key, cert_verifier, &[])
+}
+
+/// Verifies a presentation, then validates the disclosed data with `hooks`.
+///
+/// The presentation is rejected if any of the hooks rejects the data, see [`DataHook`].
+///
+/// # Arguments
+///
+/// * `proof` - The presentation.
+/// * `notary_public_key` - The public key of the notary.
+/// * `cert_verifier` - The certificate verifier.
+/// * `hooks` - The checks to run on the disclosed data.
+pub fn verify_presentation_with_hooks(
+    proof: TlsProof,
+    notary_public_key: impl Into<NotaryPublicKey>,
+    cert_verifier: &impl ServerCertVerifier,
+    hooks: &[&dyn DataHook],
+) -> Result<VerifiedPresentation, PresentationError> {
+    let signature = proof
+        .session
+        .signature
+        .as_ref()
+        .ok_or(SessionProofError::MissingNotarySignature)?;
+    proof
+        .session
+        .header
+        .verify_signature(signature, notary_public_key)
+        .map_err(SessionProofError::from)?;
+
+    verify_signed(proof, cert_verifier, hooks)
+}
+
+/// Verifies a presentation which must be signed by a quorum of notaries, see [`QuorumPolicy`],
+/// then validates the disclosed data with `hooks`.
+///
+/// # Arguments
+///
+/// * `proof` - The presentation.
+/// * `quorum` - The trusted notaries and how many of them must sign the presentation.
+/// * `cert_verifier` - The certificate verifier.
+/// * `hooks` - The checks to run on the disclosed data.
+pub fn verify_presentation_with_quorum(
+    proof: TlsProof,
+    quorum: &QuorumPolicy,
+    cert_verifier: &impl ServerCertVerifier,
+    hooks: &[&dyn DataHook],
+) -> Result<VerifiedPresentation, PresentationError> {
+    quorum.check(
+        &proof.session.header,
+        proof.session.signature.as_ref(),
+        &proof.cosignatures,
+    )?;
+
+    verify_signed(proof, cert_verifier, hooks)
+}
+
+/// Verifies many presentations signed by the same notary, and returns the result of each, in
+/// the same order.
+///
+/// Notary signatures are verified together where the signature scheme allows it, see
+/// [`Signature::verify_batch`], which is considerably faster than verifying the presentations
+/// one by one. A presentation which fails verification does not affect the others.
+///
+/// # Arguments
+///
+/// * `proofs` - The presentations.
+/// * `notary_public_key` - The public key of the notary.
+/// * `cert_verifier` - The certificate verifier.
+/// * `hooks` - The checks to run on the disclosed data of each presentation.
+pub fn verify_presentations(
+    proofs: Vec<TlsProof>,
+    notary_public_key: &NotaryPublicKey,
+    cert_verifier: &impl ServerCertVerifier,
+    hooks: &[&dyn DataHook],
+) -> Vec<Result<VerifiedPresentation, PresentationError>> {
+    // A header without a canonical encoding was never signed, and its empty message fails
+    // verification.
+    let headers = proofs
+        .iter()
+        .map(|proof| proof.session.header.signing_message().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let signed = proofs
+        .iter()
+        .zip(&headers)
+        .filter_map(|(proof, header)| {
+            let signature = proof.session.signature.as_ref()?;
+            Some((header.as_slice(), signature, notary_public_key))
+        })
+        .collect::<Vec<_>>();
+    let mut signature_results = Signature::verify_batch(&signed).into_iter();
+
+    proofs
+        .into_iter()
+        .map(|proof| {
+            if proof.session.signature.is_none() {
+                return Err(SessionProofError::MissingNotarySignature.into());
+            }
+
+            signature_results
+                .next()
+                .expect("every signed presentation has a result")
+                .map_err(SessionProofError::from)?;
+
+            verify_signed(proof, cert_verifier, hooks)
+        })
+        .collect()
+}
+
+/// Verifies everything but the notary signatures of a presentation, which must have been
+/// verified by the caller.
+fn verify_signed(
+    proof: TlsProof,
+    cert_verifier: &impl ServerCertVerifier,
+    hooks: &[&dyn DataHook],
+) -> Result<VerifiedPresentation, PresentationError> {
+    let TlsProof {
+        session,
+        substrings,
+        ..
+    } = proof;
+
+    if let Some(expired_at) = session.header.expires_at() {
+        let now = SystemClock.now()?;
+        if now >= expired_at {
+            return Err(SessionProofError::from(ValidityError::Expired { expired_at, now }).into());
+        }
+    }
+
+    // A timestamp token, if present, must be bound to this session.
+    if let (Some(timestamp), Some(signature)) = (&session.timestamp, &session.signature) {
+        timestamp
+            .verify_imprint(&attestation_digest(&session.header, signature))
+            .map_err(SessionProofError::from)?;
+    }
+
+    let server_name = session.verify_server_identity(cert_verifier)?.clone();
+    let (sent, recv) = substrings.verify(&session.header)?;
+
+    run_hooks(hooks.iter().copied(), Some(&server_name), &sent, &recv)
+        .map_err(PresentationError::Rejected)?;
+
+    Ok(VerifiedPresentation {
+        server_name,
+        time: session.header.time(),
+        closed_cleanly: session.header.closed_cleanly(),
+        expires_at: session.header.expires_at(),
+        sent,
+        recv,
+    })
+}
+
+/// Decodes a presentation and verifies it, see [`verify_presentation`].
+///
+/// # Arguments
+///
+/// * `data` - The encoded presentation.
+/// * `notary_public_key` - The public key of the notary.
+/// * `cert_verifier` - The certificate verifier.
+pub fn verify_presentation_bytes(
+    data: &[u8],
+    notary_public_key: impl Into<NotaryPublicKey>,
+    cert_verifier: &impl ServerCertVerifier,
+) -> Result<VerifiedPresentation, PresentationError> {
+    verify_presentation(
+        TlsProof::from_bytes(data)?,
+        notary_public_key,
+        cert_verifier,
+    )
+}
+
+/// Reads a presentation from a file and verifies it, see [`verify_presentation`].
+///
+/// The server certificate is verified using trust anchors from the `webpki-roots` crate.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file containing the encoded presentation.
+/// * `notary_public_key` - The public key of the notary.
+pub fn verify_presentation_file(
+    path: impl AsRef<Path>,
+    notary_public_key: impl Into<NotaryPublicKey>,
+) -> Result<VerifiedPresentation, PresentationError> {
+    verify_presentation_bytes(
+        &std::fs::read(path)?,
+        notary_public_key,
+        &default_cert_verifier(),
+    )
+}
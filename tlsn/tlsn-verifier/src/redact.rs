@@ -0,0 +1,132 @@
+//! Redaction of sensitive data in logs.
+//!
+//! Data disclosed by the Prover often still contains secrets, such as session cookies or bearer
+//! tokens, which must not end up in logs even when debug logging is enabled. All logging of
+//! transcript data by the verifier goes through a [`LogRedactor`], which:
+//!
+//! - never shows bytes which were not disclosed, and
+//! - masks every match of its patterns, by default HTTP credentials, bearer tokens, JWTs and
+//!   `key=value` style secrets.
+//!
+//! The redactor of a verifier is configured with
+//! [`VerifierConfigBuilder::log_redactor`](crate::tls::VerifierConfigBuilder::log_redactor).
+//!
+//! ```
+//! # use tlsn_verifier::redact::LogRedactor;
+//! let redactor = LogRedactor::default().with_pattern(r"acct-(?P<secret>\d+)").unwrap();
+//!
+//! assert_eq!(
+//!     redactor.redact_bytes(b"GET /acct-1234 HTTP/1.1\r\nAuthorization: Bearer abc.def\r\n"),
+//!     r"GET /acct-[REDACTED] HTTP/1.1\r\nAuthorization: [REDACTED]\r\n"
+//! );
+//! ```
+
+use std::borrow::Cow;
+
+use regex::bytes::Regex;
+use tlsn_core::RedactedTranscript;
+
+/// The text which replaces masked data.
+const MASK: &[u8] = b"[REDACTED]";
+
+/// The byte which undisclosed data is replaced with.
+const UNDISCLOSED_BYTE: u8 = b'X';
+
+/// Patterns masked by [`LogRedactor::default`].
+const DEFAULT_PATTERNS: &[&str] = &[
+    // Credentials in HTTP headers.
+    r"(?im)^(?:authorization|proxy-authorization|cookie|set-cookie|x-api-key|x-auth-token)[ \t]*:[ \t]*(?P<secret>[^\r\n]+)",
+    // Bearer tokens outside of headers.
+    r"(?i)bearer[ \t]+(?P<secret>[A-Za-z0-9._~+/=-]+)",
+    // JSON web tokens.
+    r"eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+    // Secrets in query strings, forms and JSON.
+    r#"(?i)(?:password|passwd|secret|token|api_key|apikey|access_token|refresh_token)["']?[ \t]*[=:][ \t]*["']?(?P<secret>[^&"'\s,;}]+)"#,
+];
+
+/// Masks sensitive data before it is logged.
+///
+/// A pattern with a capture group named `secret` masks only that group, e.g. the value of a
+/// header but not its name. Otherwise the whole match is masked.
+#[derive(Debug, Clone)]
+pub struct LogRedactor {
+    patterns: Vec<Regex>,
+}
+
+impl Default for LogRedactor {
+    fn default() -> Self {
+        Self {
+            patterns: DEFAULT_PATTERNS
+                .iter()
+                .map(|pattern| Regex::new(pattern).expect("default pattern is valid"))
+                .collect(),
+        }
+    }
+}
+
+impl LogRedactor {
+    /// Creates a redactor without any patterns, which only hides undisclosed data.
+    pub fn without_patterns() -> Self {
+        Self {
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Adds a pattern to mask, in the syntax of the `regex` crate.
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Returns a printable copy of the disclosed data of a transcript, with undisclosed bytes
+    /// replaced with `X` and the matches of the patterns masked.
+    pub fn redact(&self, transcript: &RedactedTranscript) -> String {
+        let mut transcript = transcript.clone();
+        transcript.set_redacted(UNDISCLOSED_BYTE);
+
+        self.redact_bytes(transcript.data())
+    }
+
+    /// Returns a printable copy of `data`, with the matches of the patterns masked.
+    ///
+    /// Control characters and invalid UTF-8 are escaped, so that the result is a single line.
+    pub fn redact_bytes(&self, data: &[u8]) -> String {
+        let mut data = Cow::Borrowed(data);
+        for pattern in &self.patterns {
+            if let Cow::Owned(masked) = mask(pattern, &data) {
+                data = Cow::Owned(masked);
+            }
+        }
+
+        String::from_utf8_lossy(&data).escape_debug().to_string()
+    }
+}
+
+/// Masks the matches of `pattern` in `data`.
+fn mask<'a>(pattern: &Regex, data: &'a [u8]) -> Cow<'a, [u8]> {
+    let secret = pattern.capture_names().any(|name| name == Some("secret"));
+
+    let mut masked = Vec::new();
+    let mut last = 0;
+    for captures in pattern.captures_iter(data) {
+        let span = if secret {
+            captures.name("secret")
+        } else {
+            captures.get(0)
+        };
+        let Some(span) = span.filter(|span| !span.is_empty()) else {
+            continue;
+        };
+
+        masked.extend_from_slice(&data[last..span.start()]);
+        masked.extend_from_slice(MASK);
+        last = span.end();
+    }
+
+    if last == 0 {
+        return Cow::Borrowed(data);
+    }
+
+    masked.extend_from_slice(&data[last..]);
+    Cow::Owned(masked)
+}
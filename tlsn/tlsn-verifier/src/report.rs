@@ -0,0 +1,84 @@
+//! Machine-readable verification reports.
+//!
+//! A [`VerificationReport`] records every check performed while evaluating a presentation
+//! against a [`Policy`](crate::policy::Policy), its outcome, and which data the presentation
+//! discloses together with the commitment authenticating it. It serializes to JSON, or any other
+//! serde format, so it can be archived as evidence of what exactly was verified.
+//!
+//! Reports are created with [`PolicyReport::report`].
+
+use std::ops::Range;
+
+use serde::Serialize;
+use tlsn_core::{
+    commitment::{CommitmentId, CommitmentKind},
+    Direction,
+};
+
+use crate::policy::{Outcome, PolicyReport, Rule};
+
+/// A structured record of the verification of a presentation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerificationReport {
+    /// Whether the presentation satisfies every check.
+    pub accepted: bool,
+    /// The verified server name, if the server identity is valid.
+    pub server_name: Option<String>,
+    /// The time of the session, in seconds since the UNIX epoch, as signed by the notary.
+    pub session_time: u64,
+    /// The checks performed, in the order they were evaluated.
+    pub checks: Vec<Check>,
+    /// The disclosed data and its provenance, sorted by direction and position. Empty if the
+    /// transcript proof is invalid.
+    pub disclosures: Vec<Disclosure>,
+}
+
+/// A check performed during verification, and its outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    /// The rule which was checked.
+    pub rule: Rule,
+    /// A human readable description of the rule.
+    pub description: String,
+    /// The outcome of the check.
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+/// Data disclosed by a presentation, authenticated by a single commitment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Disclosure {
+    /// The direction of the disclosed data.
+    pub direction: Direction,
+    /// The disclosed ranges of the transcript.
+    pub ranges: Vec<Range<usize>>,
+    /// The id of the commitment which authenticates the data, in the commitment tree whose root
+    /// is signed in the session header.
+    pub commitment_id: CommitmentId,
+    /// The kind of the commitment.
+    pub commitment_kind: CommitmentKind,
+}
+
+impl PolicyReport {
+    /// Returns a machine-readable report of this evaluation.
+    pub fn report(&self) -> VerificationReport {
+        VerificationReport {
+            accepted: self.is_accepted(),
+            server_name: self
+                .server_name
+                .as_ref()
+                .map(|name| name.as_str().to_string()),
+            session_time: self.session_time,
+            checks: self
+                .results
+                .iter()
+                .map(|(rule, outcome)| Check {
+                    rule: rule.clone(),
+                    description: rule.to_string(),
+                    outcome: outcome.clone(),
+                })
+                .collect(),
+            disclosures: self.disclosures.clone(),
+        }
+    }
+}
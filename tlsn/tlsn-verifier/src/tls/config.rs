@@ -1,13 +1,29 @@
+use crate::{hook::DataHook, redact::LogRedactor, tls::SessionLimit};
 use mpz_ot::{chou_orlandi, kos};
 use mpz_share_conversion::{ReceiverConfig, SenderConfig};
-use std::fmt::{Debug, Formatter, Result};
+use std::{
+    fmt::{Debug, Formatter, Result},
+    sync::Arc,
+    time::Duration,
+};
 use tls_core::verify::{ServerCertVerifier, WebPkiVerifier};
 use tls_mpc::{MpcTlsCommonConfig, MpcTlsFollowerConfig, TranscriptConfig};
 use tlsn_common::{
-    config::{ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
+    compress::Compression,
+    config::{
+        ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT,
+        DEFAULT_MAX_THREADS,
+    },
+    resume::DEFAULT_MAX_RECONNECTS,
     Role,
 };
-use tlsn_core::proof::default_cert_verifier;
+use tlsn_core::{
+    msg::SetupPreprocessing,
+    proof::{default_cert_verifier, CertPins, PinnedCertVerifier},
+    session::{ExtensionId, Extensions},
+    tee::TeeQuote,
+    time::{SystemClock, TimeSource},
+};
 
 /// Configuration for the [`Verifier`](crate::tls::Verifier)
 #[allow(missing_docs)]
@@ -28,16 +44,106 @@ pub struct VerifierConfig {
         default = "Some(default_cert_verifier())"
     )]
     cert_verifier: Option<WebPkiVerifier>,
+    /// Pins on the public keys of server certificates, checked in addition to the certificate
+    /// chain. Deployments using a private CA set the roots of `cert_verifier` with
+    /// [`WebPkiVerifier::new`] and may pin its key here.
+    #[builder(default)]
+    cert_pins: CertPins,
+    /// Require the OCSP response stapled by the server to show that its certificate is not
+    /// revoked, when verifying a session with [`Verifier::verify`](crate::tls::Verifier::verify).
+    #[builder(default)]
+    require_unrevoked: bool,
+    /// How long a signed session header remains valid. Headers do not expire if this is not set.
+    #[builder(setter(strip_option), default)]
+    attestation_validity: Option<Duration>,
+    /// Granularity to which the session time is rounded down, so that it reveals less about the
+    /// Prover. The time is recorded to the second if this is not set.
+    #[builder(setter(strip_option), default)]
+    time_precision: Option<Duration>,
+    /// Extension claims the Notary adds to every session header, replacing claims requested by
+    /// the Prover with the same ids.
+    #[builder(default)]
+    extensions: Extensions,
+    /// Ids of the extension claims the Prover is allowed to request.
+    #[builder(default)]
+    accepted_extensions: Vec<ExtensionId>,
+    /// Maximum number of consecutive failed attempts to reconnect to the Prover, when the
+    /// verifier is set up with [`Verifier::setup_resumable`](crate::tls::Verifier::setup_resumable).
+    #[builder(default = "DEFAULT_MAX_RECONNECTS")]
+    max_reconnects: usize,
+    /// Maximum number of oblivious transfers set up during MPC preprocessing. The number
+    /// required grows with `max_sent_data` and `max_recv_data`, so this bounds the work done
+    /// before the session starts when those limits are chosen by the Prover. Not limited if
+    /// this is not set.
+    #[builder(setter(strip_option), default)]
+    max_preprocessing: Option<usize>,
+    /// Maximum number of threads to garble and evaluate the encryption and decryption circuits
+    /// on. The session uses the smaller of this and the maximum of the Prover.
+    #[builder(default = "DEFAULT_MAX_THREADS")]
+    max_threads: usize,
+    /// Maximum wall-clock duration of a session, from the start of setup until finalization.
+    /// Sessions are not limited in time if this is not set.
+    #[builder(setter(strip_option), default)]
+    max_session_duration: Option<Duration>,
+    /// Limit on the number of concurrent sessions, shared with other verifiers.
+    #[builder(setter(strip_option), default)]
+    session_limit: Option<SessionLimit>,
+    /// Checks run on the data received from the Prover with
+    /// [`Verifier::receive`](crate::tls::Verifier::receive), once the session is finalized.
+    #[builder(default)]
+    data_hooks: Vec<Arc<dyn DataHook>>,
+    /// The source of the current time, which determines the session time signed by the Notary,
+    /// and therefore the time at which the server certificate is validated and the expiry time
+    /// of the session header.
+    #[builder(default = "Arc::new(SystemClock)")]
+    time_source: Arc<dyn TimeSource>,
+    /// Masks sensitive data in the transcript data which is logged.
+    #[builder(default)]
+    log_redactor: LogRedactor,
+    /// Hardware attestation quote binding the Notary key to the TEE the Notary runs in, which is
+    /// sent to the Prover with the signed session header.
+    #[builder(setter(strip_option), default)]
+    tee_quote: Option<TeeQuote>,
+    /// Channels whose data sent to the Prover is compressed.
+    #[builder(default)]
+    compression: Compression,
+    /// Record the Poseidon hash of the commitment root in every session header, so that a zk
+    /// circuit can bind to the commitments signed by the Notary.
+    #[cfg(feature = "poseidon")]
+    #[builder(default)]
+    poseidon_root: bool,
 }
 
 impl Debug for VerifierConfig {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        f.debug_struct("VerifierConfig")
+        let mut debug = f.debug_struct("VerifierConfig");
+        debug
             .field("id", &self.id)
             .field("max_sent_data", &self.max_sent_data)
             .field("max_recv_data", &self.max_recv_data)
             .field("cert_verifier", &"_")
-            .finish()
+            .field("cert_pins", &self.cert_pins)
+            .field("require_unrevoked", &self.require_unrevoked)
+            .field("attestation_validity", &self.attestation_validity)
+            .field("time_precision", &self.time_precision)
+            .field("extensions", &self.extensions)
+            .field("accepted_extensions", &self.accepted_extensions)
+            .field("max_reconnects", &self.max_reconnects)
+            .field("max_preprocessing", &self.max_preprocessing)
+            .field("max_threads", &self.max_threads)
+            .field("max_session_duration", &self.max_session_duration)
+            .field("session_limit", &self.session_limit)
+            .field("data_hooks", &self.data_hooks.len())
+            .field("time_source", &"_")
+            .field("log_redactor", &self.log_redactor)
+            .field(
+                "tee_quote",
+                &self.tee_quote.as_ref().map(|quote| quote.platform()),
+            )
+            .field("compression", &self.compression);
+        #[cfg(feature = "poseidon")]
+        debug.field("poseidon_root", &self.poseidon_root);
+        debug.finish()
     }
 }
 
@@ -62,11 +168,107 @@ impl VerifierConfig {
         self.max_recv_data
     }
 
-    /// Get the certificate verifier.
-    pub fn cert_verifier(&self) -> &impl ServerCertVerifier {
-        self.cert_verifier
-            .as_ref()
-            .expect("Certificate verifier should be set")
+    /// Returns how long a signed session header remains valid.
+    pub fn attestation_validity(&self) -> Option<Duration> {
+        self.attestation_validity
+    }
+
+    /// Returns the granularity to which the session time is rounded down.
+    pub fn time_precision(&self) -> Option<Duration> {
+        self.time_precision
+    }
+
+    /// Returns the extension claims the Notary adds to every session header.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns the ids of the extension claims the Prover is allowed to request.
+    pub fn accepted_extensions(&self) -> &[ExtensionId] {
+        &self.accepted_extensions
+    }
+
+    /// Returns the maximum number of consecutive failed attempts to reconnect to the Prover.
+    pub fn max_reconnects(&self) -> usize {
+        self.max_reconnects
+    }
+
+    /// Returns the channels whose data sent to the Prover is compressed.
+    ///
+    /// Each party compresses the data it sends independently, so this doesn't affect the data
+    /// received from the Prover.
+    pub fn compression(&self) -> &Compression {
+        &self.compression
+    }
+
+    /// Returns whether the Poseidon hash of the commitment root is recorded in session headers.
+    #[cfg(feature = "poseidon")]
+    pub fn poseidon_root(&self) -> bool {
+        self.poseidon_root
+    }
+
+    /// Returns the maximum number of threads to garble and evaluate the encryption and decryption
+    /// circuits on.
+    ///
+    /// A Notary serving many Provers at once can set it to the number of cores of its host
+    /// divided by the expected number of concurrent sessions.
+    pub fn max_threads(&self) -> usize {
+        self.max_threads
+    }
+
+    /// Returns the maximum number of oblivious transfers set up during MPC preprocessing.
+    pub fn max_preprocessing(&self) -> Option<usize> {
+        self.max_preprocessing
+    }
+
+    /// Returns the maximum wall-clock duration of a session.
+    pub fn max_session_duration(&self) -> Option<Duration> {
+        self.max_session_duration
+    }
+
+    /// Returns the limit on the number of concurrent sessions.
+    pub fn session_limit(&self) -> Option<&SessionLimit> {
+        self.session_limit.as_ref()
+    }
+
+    /// Returns the checks run on the data received from the Prover.
+    pub fn data_hooks(&self) -> &[Arc<dyn DataHook>] {
+        &self.data_hooks
+    }
+
+    /// Returns the source of the current time.
+    pub fn time_source(&self) -> &dyn TimeSource {
+        self.time_source.as_ref()
+    }
+
+    /// Returns the redactor of the transcript data which is logged.
+    pub fn log_redactor(&self) -> &LogRedactor {
+        &self.log_redactor
+    }
+
+    /// Returns the hardware attestation quote of the Notary, if it runs in a TEE.
+    pub fn tee_quote(&self) -> Option<&TeeQuote> {
+        self.tee_quote.as_ref()
+    }
+
+    /// Returns the pins on the public keys of server certificates.
+    pub fn cert_pins(&self) -> &CertPins {
+        &self.cert_pins
+    }
+
+    /// Returns whether the server certificate must not be revoked.
+    pub fn require_unrevoked(&self) -> bool {
+        self.require_unrevoked
+    }
+
+    /// Get the certificate verifier, which also checks the certificate pins.
+    pub fn cert_verifier(&self) -> impl ServerCertVerifier + '_ {
+        PinnedCertVerifier::new(
+            self.cert_verifier
+                .as_ref()
+                .expect("Certificate verifier should be set"),
+            &self.cert_pins,
+        )
     }
 
     pub(crate) fn build_base_ot_sender_config(&self) -> chou_orlandi::SenderConfig {
@@ -91,20 +293,27 @@ impl VerifierConfig {
         kos::ReceiverConfig::default()
     }
 
-    pub(crate) fn build_mpc_tls_config(&self) -> MpcTlsFollowerConfig {
+    pub(crate) fn build_mpc_tls_config(
+        &self,
+        preprocessing: SetupPreprocessing,
+        num_threads: usize,
+    ) -> MpcTlsFollowerConfig {
         MpcTlsFollowerConfig::builder()
             .common(
                 MpcTlsCommonConfig::builder()
                     .id(format!("{}/mpc_tls", &self.id))
+                    .num_threads(num_threads)
                     .tx_config(
                         TranscriptConfig::default_tx()
                             .max_size(self.max_sent_data)
+                            .setup_preprocess_size(preprocessing.sent)
                             .build()
                             .unwrap(),
                     )
                     .rx_config(
                         TranscriptConfig::default_rx()
                             .max_size(self.max_recv_data)
+                            .setup_preprocess_size(preprocessing.recv)
                             .build()
                             .unwrap(),
                     )
@@ -124,6 +333,11 @@ impl VerifierConfig {
         ot_recv_estimate(Role::Verifier, self.max_sent_data, self.max_recv_data)
     }
 
+    /// Returns the total number of oblivious transfers set up during MPC preprocessing.
+    pub(crate) fn preprocessing_count(&self) -> usize {
+        self.ot_sender_setup_count() + self.ot_receiver_setup_count()
+    }
+
     pub(crate) fn build_p256_sender_config(&self) -> SenderConfig {
         SenderConfig::builder().id("p256/1").build().unwrap()
     }
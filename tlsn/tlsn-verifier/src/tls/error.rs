@@ -13,6 +13,40 @@ pub enum VerifierError {
     MpcError(Box<dyn Error + Send + Sync + 'static>),
     #[error("Range exceeds transcript length")]
     InvalidRange,
+    #[error("unsupported commitment hash algorithm: {0:?}")]
+    UnsupportedHashAlgorithm(tlsn_core::hash::HashAlgorithm),
+    #[error("prover requested an extension which is not accepted: {0:?}")]
+    UnacceptedExtension(tlsn_core::session::ExtensionId),
+    #[error("data revealed by the prover during the session was rejected")]
+    RevealRejected,
+    #[error("preprocessing requires {required} oblivious transfers, the limit is {limit}")]
+    PreprocessingLimitExceeded { required: usize, limit: usize },
+    #[error(
+        "prover asked to preprocess {requested} bytes of {direction:?} data, the limit is {limit}"
+    )]
+    SetupPreprocessingExceeded {
+        direction: tlsn_core::Direction,
+        requested: usize,
+        limit: usize,
+    },
+    #[error("session exceeded the maximum duration")]
+    SessionTimeout,
+    #[error("maximum number of concurrent sessions reached")]
+    TooManySessions,
+    #[error("data received from the prover was rejected: {0}")]
+    DataRejected(crate::hook::HookError),
+    #[error("server certificate revocation check failed: {0}")]
+    Revocation(String),
+    #[error("prover declined to reveal the requested data")]
+    RevealDeclined,
+    #[error("prover revealed less than the requested data")]
+    RequestNotSatisfied,
+    #[error(transparent)]
+    Time(#[from] tlsn_core::time::TimeError),
+    #[error("failed to sign session header: {0}")]
+    Signing(signature::Error),
+    #[error(transparent)]
+    CanonicalJson(#[from] tlsn_core::canonical::CanonicalJsonError),
 }
 
 impl From<MpcTlsError> for VerifierError {
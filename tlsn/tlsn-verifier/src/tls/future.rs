@@ -48,3 +48,10 @@ impl FusedFuture for OTFuture {
         self.fut.is_terminated()
     }
 }
+
+/// Returns the error which ended the [`MuxFuture`], which is expected to run for the whole
+/// session.
+pub(crate) fn mux_error(res: Result<(), VerifierError>) -> VerifierError {
+    res.err()
+        .unwrap_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into())
+}
@@ -0,0 +1,56 @@
+//! Limits on the resources a verifier commits to sessions.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// A limit on the number of concurrent sessions, shared by the verifiers it is configured for.
+///
+/// Clones share the same count, so a service running many verifiers passes a clone of one
+/// limit to each [`VerifierConfig`](crate::tls::VerifierConfig). A session counts towards the
+/// limit from the start of its setup until the verifier is finalized or dropped.
+#[derive(Debug, Clone)]
+pub struct SessionLimit {
+    max: usize,
+    active: Arc<AtomicUsize>,
+}
+
+impl SessionLimit {
+    /// Creates a limit of `max` concurrent sessions.
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns the maximum number of concurrent sessions.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns the number of sessions currently running.
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Reserves a slot for a session, if the limit has not been reached.
+    pub(crate) fn try_acquire(&self) -> Option<SessionPermit> {
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < self.max).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| SessionPermit(self.active.clone()))
+    }
+}
+
+/// A slot reserved by a session, which is released when dropped.
+pub(crate) struct SessionPermit(Arc<AtomicUsize>);
+
+impl Drop for SessionPermit {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
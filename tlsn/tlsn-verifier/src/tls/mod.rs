@@ -3,22 +3,30 @@
 pub(crate) mod config;
 mod error;
 mod future;
+mod limit;
 mod notarize;
+mod signer;
 pub mod state;
 mod verify;
 
 pub use config::{VerifierConfig, VerifierConfigBuilder, VerifierConfigBuilderError};
 pub use error::VerifierError;
-
-use std::time::{SystemTime, UNIX_EPOCH};
+pub use limit::SessionLimit;
+pub use signer::AsyncSigner;
+pub use tlsn_common::{
+    compress::Compression,
+    resume::{Reconnect, Socket},
+};
 
 use crate::tls::future::OTFuture;
-use future::MuxFuture;
+use future::{mux_error, MuxFuture};
 use futures::{
+    future::BoxFuture,
     stream::{SplitSink, SplitStream},
-    AsyncRead, AsyncWrite, FutureExt, StreamExt, TryFutureExt,
+    AsyncRead, AsyncWrite, FutureExt, SinkExt, StreamExt, TryFutureExt,
 };
-use mpz_garble::{config::Role as GarbleRole, protocol::deap::DEAPVm};
+use futures_timer::Delay;
+use mpz_garble::{config::Role as GarbleRole, protocol::deap::DEAPVm, Vm};
 use mpz_ot::{
     actor::kos::{
         msgs::Message as ActorMessage, ReceiverActor, SenderActor, SharedReceiver, SharedSender,
@@ -31,11 +39,16 @@ use signature::Signer;
 use state::{Notarize, Verify};
 use tls_mpc::{setup_components, MpcTlsFollower, MpcTlsFollowerData, TlsRole};
 use tlsn_common::{
+    compress::PeerCompression,
     mux::{attach_mux, MuxControl},
+    resume::resumable,
     Role,
 };
-use tlsn_core::{proof::SessionInfo, RedactedTranscript, SessionHeader, Signature};
-use utils_aio::{duplex::Duplex, mux::MuxChannel};
+use tlsn_core::{
+    msg::TlsnMessage, proof::SessionInfo, Direction, RedactedTranscript, SessionHeader, Signature,
+};
+use utils_aio::{duplex::Duplex, expect_msg_or_err, mux::MuxChannel};
+use verify::verify_cleartext;
 
 #[cfg(feature = "tracing")]
 use tracing::{debug, info, instrument};
@@ -75,17 +88,94 @@ impl Verifier<state::Initialized> {
         self,
         socket: S,
     ) -> Result<Verifier<state::Setup>, VerifierError> {
-        let (mut mux, mux_ctrl) = attach_mux(socket, Role::Verifier);
+        self.setup_inner(socket, None).await
+    }
+
+    /// Set up the verifier over a connection which is resumed if it drops.
+    ///
+    /// Same as [`setup`](Self::setup), except that when the connection to the prover drops, the
+    /// session continues where it left off over a new connection obtained with `reconnect`,
+    /// instead of failing. The session fails once
+    /// [`max_reconnects`](VerifierConfig::max_reconnects) consecutive attempts to reconnect have
+    /// failed.
+    ///
+    /// The prover must also set up the session as resumable.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The socket to the prover.
+    /// * `reconnect` - Provides a new socket to the prover, e.g. the receiver of a channel over
+    ///   which connections accepted from the prover are passed.
+    pub async fn setup_resumable<
+        S: AsyncWrite + AsyncRead + Send + Unpin + 'static,
+        R: Reconnect + 'static,
+    >(
+        self,
+        socket: S,
+        reconnect: R,
+    ) -> Result<Verifier<state::Setup>, VerifierError> {
+        let (socket, resume_fut) = resumable(socket, reconnect, self.config.max_reconnects());
+        self.setup_inner(socket, Some(resume_fut)).await
+    }
+
+    async fn setup_inner<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+        self,
+        socket: S,
+        resume_fut: Option<BoxFuture<'static, std::io::Result<()>>>,
+    ) -> Result<Verifier<state::Setup>, VerifierError> {
+        if let Some(limit) = self.config.max_preprocessing() {
+            let required = self.config.preprocessing_count();
+            if required > limit {
+                return Err(VerifierError::PreprocessingLimitExceeded { required, limit });
+            }
+        }
+
+        // The permit is held by the muxer future, which runs until the session ends.
+        let permit = match self.config.session_limit() {
+            Some(limit) => Some(limit.try_acquire().ok_or(VerifierError::TooManySessions)?),
+            None => None,
+        };
+        let deadline = self.config.max_session_duration().map(Delay::new);
+
+        let (mut mux, mux_ctrl, peer_compression) =
+            attach_mux(socket, Role::Verifier, self.config.compression().clone());
 
         let mut mux_fut = MuxFuture {
-            fut: Box::pin(async move { mux.run().await.map_err(VerifierError::from) }.fuse()),
+            fut: Box::pin(
+                async move {
+                    let _permit = permit;
+                    let run_fut = async {
+                        let run_fut = mux.run().map_err(VerifierError::from);
+                        match resume_fut {
+                            Some(resume_fut) => {
+                                futures::try_join!(run_fut, resume_fut.map_err(VerifierError::from))
+                                    .map(|_| ())
+                            }
+                            None => run_fut.await,
+                        }
+                    };
+                    match deadline {
+                        Some(deadline) => futures::select! {
+                            res = run_fut.fuse() => res,
+                            _ = deadline.fuse() => Err(VerifierError::SessionTimeout),
+                        },
+                        None => run_fut.await,
+                    }
+                }
+                .fuse(),
+            ),
         };
 
         let encoder_seed: [u8; 32] = rand::rngs::OsRng.gen();
-        let mpc_setup_fut = setup_mpc_backend(&self.config, mux_ctrl.clone(), encoder_seed);
+        let mpc_setup_fut = setup_mpc_backend(
+            &self.config,
+            mux_ctrl.clone(),
+            peer_compression,
+            encoder_seed,
+        );
         let (mpc_tls, vm, ot_send, ot_recv, gf2, ot_fut) = futures::select! {
             res = mpc_setup_fut.fuse() => res?,
-            _ = &mut mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+            res = &mut mux_fut => return Err(mux_error(res)),
         };
 
         Ok(Verifier {
@@ -141,12 +231,35 @@ impl Verifier<state::Initialized> {
 
 impl Verifier<state::Setup> {
     /// Runs the verifier until the TLS connection is closed.
+    ///
+    /// Data the prover reveals while the connection is open is verified and discarded, see
+    /// [`Verifier::run_with_reveals`].
     pub async fn run(self) -> Result<Verifier<state::Closed>, VerifierError> {
+        self.run_with_reveals(|_, _| true).await
+    }
+
+    /// Runs the verifier until the TLS connection is closed, passing the **purported** data the
+    /// prover reveals while the connection is open to `on_reveal`.
+    ///
+    /// The data is passed as it is revealed, e.g. after each response from the server, so that
+    /// the verifier can gate subsequent steps on what it has already seen. If `on_reveal` returns
+    /// `false` the session is aborted.
+    ///
+    /// # Warning
+    ///
+    /// The content of the revealed data can not be considered authentic until after finalization.
+    pub async fn run_with_reveals<F>(
+        self,
+        mut on_reveal: F,
+    ) -> Result<Verifier<state::Closed>, VerifierError>
+    where
+        F: FnMut(RedactedTranscript, RedactedTranscript) -> bool,
+    {
         let state::Setup {
             mux_ctrl,
             mut mux_fut,
             mpc_tls,
-            vm,
+            mut vm,
             ot_send,
             ot_recv,
             mut ot_fut,
@@ -154,22 +267,57 @@ impl Verifier<state::Setup> {
             encoder_seed,
         } = self.state;
 
-        let start_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let start_time = self.config.time_source().now()?;
 
         let (_, mpc_fut) = mpc_tls.run();
 
+        let mut reveal_mux = mux_ctrl.clone();
+        #[cfg(feature = "tracing")]
+        let log_redactor = self.config.log_redactor().clone();
         let MpcTlsFollowerData {
             handshake_commitment,
             server_key: server_ephemeral_key,
             bytes_sent: sent_len,
             bytes_recv: recv_len,
-        } = futures::select! {
-            res = mpc_fut.fuse() => res?,
-            _ = &mut mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
-            res = ot_fut => return Err(res.map(|_| ()).expect_err("future will not return Ok here"))
+            close_notify,
+        } = {
+            // Verifies data revealed while the connection is open. The prover opens the channel
+            // with its first reveal, if any.
+            let reveal_fut = async {
+                let mut channel: Box<dyn Duplex<TlsnMessage>> =
+                    reveal_mux.get_channel("reveal").await?;
+                let mut verify_thread = vm.new_thread("reveal").await?;
+
+                loop {
+                    let proving_info = expect_msg_or_err!(channel, TlsnMessage::ProvingInfo)?;
+                    let [sent_len, recv_len] = [&proving_info.sent_ids, &proving_info.recv_ids]
+                        .map(|ids| ids.iter_ranges().last().map_or(0, |range| range.end));
+
+                    let (sent, recv) =
+                        verify_cleartext(&mut verify_thread, proving_info, sent_len, recv_len)
+                            .await?;
+
+                    #[cfg(feature = "tracing")]
+                    debug!(
+                        sent = %log_redactor.redact(&sent),
+                        recv = %log_redactor.redact(&recv),
+                        "Verified data revealed during the session"
+                    );
+
+                    if !on_reveal(sent, recv) {
+                        return Err::<(), VerifierError>(VerifierError::RevealRejected);
+                    }
+                }
+            }
+            .fuse();
+            futures::pin_mut!(reveal_fut);
+
+            futures::select! {
+                res = mpc_fut.fuse() => res?,
+                res = reveal_fut => return Err(res.expect_err("future will not return Ok here")),
+                res = &mut mux_fut => return Err(mux_error(res)),
+                res = ot_fut => return Err(res.map(|_| ()).expect_err("future will not return Ok here"))
+            }
         };
 
         #[cfg(feature = "tracing")]
@@ -194,6 +342,7 @@ impl Verifier<state::Setup> {
                 handshake_commitment,
                 sent_len,
                 recv_len,
+                close_notify,
             },
         })
     }
@@ -229,6 +378,7 @@ impl Verifier<state::Closed> {
 async fn setup_mpc_backend(
     config: &VerifierConfig,
     mut mux_ctrl: MuxControl,
+    peer_compression: PeerCompression,
     encoder_seed: [u8; 32],
 ) -> Result<
     (
@@ -241,6 +391,42 @@ async fn setup_mpc_backend(
     ),
     VerifierError,
 > {
+    // Preprocess as much as the prover asks for during setup, up to the limits of the session
+    let mut setup_channel: Box<dyn Duplex<TlsnMessage>> = mux_ctrl.get_channel("setup").await?;
+    let preprocessing = expect_msg_or_err!(setup_channel, TlsnMessage::SetupPreprocessing)?;
+    for (direction, requested, limit) in [
+        (Direction::Sent, preprocessing.sent, config.max_sent_data()),
+        (
+            Direction::Received,
+            preprocessing.recv,
+            config.max_recv_data(),
+        ),
+    ] {
+        if let Some(requested) = requested.filter(|requested| *requested > limit) {
+            return Err(VerifierError::SetupPreprocessingExceeded {
+                direction,
+                requested,
+                limit,
+            });
+        }
+    }
+
+    // Both parties split the circuits across the smaller of their maximum numbers of threads
+    setup_channel
+        .send(TlsnMessage::MaxThreads(config.max_threads()))
+        .await?;
+    let prover_max_threads = expect_msg_or_err!(setup_channel, TlsnMessage::MaxThreads)?;
+    let num_threads = config.max_threads().min(prover_max_threads).max(1);
+
+    // Channels opened from here on are compressed as announced by each party
+    setup_channel
+        .send(TlsnMessage::CompressedChannels(
+            config.compression().channels().to_vec(),
+        ))
+        .await?;
+    let prover_compressed = expect_msg_or_err!(setup_channel, TlsnMessage::CompressedChannels)?;
+    peer_compression.set(prover_compressed)?;
+
     let (ot_send_sink, ot_send_stream) = mux_ctrl.get_channel("ot/1").await?.split();
     let (ot_recv_sink, ot_recv_stream) = mux_ctrl.get_channel("ot/0").await?.split();
 
@@ -318,7 +504,7 @@ async fn setup_mpc_backend(
     let channel = mux_ctrl.get_channel(gf2_config.id()).await?;
     let gf2 = ff::ConverterReceiver::<ff::Gf2_128, _>::new(gf2_config, ot_recv.clone(), channel);
 
-    let mpc_tls_config = config.build_mpc_tls_config();
+    let mpc_tls_config = config.build_mpc_tls_config(preprocessing, num_threads);
 
     let (ke, prf, encrypter, decrypter) = setup_components(
         mpc_tls_config.common(),
@@ -2,9 +2,8 @@
 //!
 //! The TLS verifier is only a notary.
 
-use super::{state::Notarize, Verifier, VerifierError};
-use futures::{FutureExt, SinkExt, StreamExt, TryFutureExt};
-use mpz_core::serialize::CanonicalSerialize;
+use super::{future::mux_error, state::Notarize, AsyncSigner, Verifier, VerifierError};
+use futures::{future::ready, Future, FutureExt, SinkExt, StreamExt, TryFutureExt};
 use mpz_share_conversion::ShareConversionVerify;
 use signature::Signer;
 use tlsn_core::{
@@ -21,6 +20,27 @@ impl Verifier<Notarize> {
     pub async fn finalize<T>(self, signer: &impl Signer<T>) -> Result<SessionHeader, VerifierError>
     where
         T: Into<Signature>,
+    {
+        self.finalize_with(|msg| ready(Ok(signer.sign(&msg).into())))
+            .await
+    }
+
+    /// Notarizes the TLS session, signing the session header with an asynchronous signer, e.g. a
+    /// remote KMS or an HSM.
+    pub async fn finalize_async(
+        self,
+        signer: &(impl AsyncSigner + ?Sized),
+    ) -> Result<SessionHeader, VerifierError> {
+        self.finalize_with(
+            |msg| async move { signer.sign(&msg).await.map_err(VerifierError::Signing) },
+        )
+        .await
+    }
+
+    async fn finalize_with<F, Fut>(self, sign: F) -> Result<SessionHeader, VerifierError>
+    where
+        F: FnOnce(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<Signature, VerifierError>>,
     {
         let Notarize {
             mut mux_ctrl,
@@ -36,13 +56,29 @@ impl Verifier<Notarize> {
             handshake_commitment,
             sent_len,
             recv_len,
+            close_notify,
         } = self.state;
 
         let notarize_fut = async {
             let mut notarize_channel = mux_ctrl.get_channel("notarize").await?;
 
+            let length_padding = expect_msg_or_err!(notarize_channel, TlsnMessage::LengthPadding)?;
+            let hash_algorithm =
+                expect_msg_or_err!(notarize_channel, TlsnMessage::CommitmentHashAlgorithm)?;
+            if !hash_algorithm.is_supported() {
+                return Err(VerifierError::UnsupportedHashAlgorithm(hash_algorithm));
+            }
+            let mut extensions = expect_msg_or_err!(notarize_channel, TlsnMessage::Extensions)?;
+            if let Some(id) = extensions
+                .ids()
+                .find(|id| !self.config.accepted_extensions().contains(id))
+            {
+                return Err(VerifierError::UnacceptedExtension(id));
+            }
+            extensions.extend(self.config.extensions().clone());
             let merkle_root =
                 expect_msg_or_err!(notarize_channel, TlsnMessage::TranscriptCommitmentRoot)?;
+            let kzg_commitment = expect_msg_or_err!(notarize_channel, TlsnMessage::KzgCommitment)?;
 
             // Finalize all MPC before signing the session header
             let (mut ot_sender_actor, _, _) = futures::try_join!(
@@ -64,18 +100,55 @@ impl Verifier<Notarize> {
             #[cfg(feature = "tracing")]
             info!("Finalized all MPC");
 
+            let precision = self
+                .config
+                .time_precision()
+                .map(|precision| precision.as_secs())
+                .filter(|precision| *precision > 1);
+            let start_time = match precision {
+                Some(precision) => start_time - start_time % precision,
+                None => start_time,
+            };
+
             let handshake_summary =
                 HandshakeSummary::new(start_time, server_ephemeral_key, handshake_commitment);
 
-            let session_header = SessionHeader::new(
+            // Padding is bounded by the configured limits, so the prover can not make
+            // verifiers allocate arbitrarily large transcripts.
+            let sent_len = length_padding.pad_bounded(sent_len, self.config.max_sent_data());
+            let recv_len = length_padding.pad_bounded(recv_len, self.config.max_recv_data());
+
+            let mut session_header = SessionHeader::new(
                 encoder_seed,
                 merkle_root,
                 sent_len,
                 recv_len,
                 handshake_summary,
-            );
+            )
+            .with_hash_algorithm(hash_algorithm)
+            .with_extensions(extensions)
+            .with_closed_cleanly(close_notify);
+
+            if let Some(validity) = self.config.attestation_validity() {
+                session_header =
+                    session_header.with_expiry(start_time.saturating_add(validity.as_secs()));
+            }
+
+            if let Some(precision) = precision {
+                session_header = session_header.with_time_precision(precision);
+            }
+
+            if let Some(kzg_commitment) = kzg_commitment {
+                session_header = session_header.with_kzg_commitment(kzg_commitment);
+            }
+
+            #[cfg(feature = "poseidon")]
+            if self.config.poseidon_root() {
+                let digest = session_header.merkle_root().poseidon_hash();
+                session_header = session_header.with_poseidon_root(digest);
+            }
 
-            let signature = signer.sign(&session_header.to_bytes());
+            let signature = sign(session_header.signing_message()?).await?;
 
             #[cfg(feature = "tracing")]
             info!("Signed session header");
@@ -83,7 +156,8 @@ impl Verifier<Notarize> {
             notarize_channel
                 .send(TlsnMessage::SignedSessionHeader(SignedSessionHeader {
                     header: session_header.clone(),
-                    signature: signature.into(),
+                    signature,
+                    tee_quote: self.config.tee_quote().cloned(),
                 }))
                 .await?;
 
@@ -95,7 +169,7 @@ impl Verifier<Notarize> {
 
         let session_header = futures::select! {
             res = notarize_fut.fuse() => res?,
-            _ = &mut mux_fut => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+            res = &mut mux_fut => Err(mux_error(res))?,
         };
 
         let mut mux_ctrl = mux_ctrl.into_inner();
@@ -0,0 +1,10 @@
+use async_trait::async_trait;
+use tlsn_core::Signature;
+
+/// A signer of session headers which signs asynchronously, e.g. with a key held in a remote KMS
+/// or an HSM.
+#[async_trait]
+pub trait AsyncSigner: Send + Sync {
+    /// Signs the message, returning the signature of the notary.
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, signature::Error>;
+}
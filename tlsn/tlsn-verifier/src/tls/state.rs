@@ -7,7 +7,7 @@ use mpz_share_conversion::{ConverterReceiver, Gf2_128};
 use tls_core::key::PublicKey;
 use tls_mpc::MpcTlsFollower;
 use tlsn_common::mux::MuxControl;
-use tlsn_core::msg::TlsnMessage;
+use tlsn_core::{msg::TlsnMessage, RedactedTranscript};
 use utils_aio::duplex::Duplex;
 
 use crate::tls::future::{MuxFuture, OTFuture};
@@ -52,6 +52,7 @@ pub struct Closed {
     pub(crate) handshake_commitment: Hash,
     pub(crate) sent_len: usize,
     pub(crate) recv_len: usize,
+    pub(crate) close_notify: bool,
 }
 
 opaque_debug::implement!(Closed);
@@ -73,6 +74,7 @@ pub struct Notarize {
     pub(crate) handshake_commitment: Hash,
     pub(crate) sent_len: usize,
     pub(crate) recv_len: usize,
+    pub(crate) close_notify: bool,
 }
 
 opaque_debug::implement!(Notarize);
@@ -93,6 +95,7 @@ impl From<Closed> for Notarize {
             handshake_commitment: value.handshake_commitment,
             sent_len: value.sent_len,
             recv_len: value.recv_len,
+            close_notify: value.close_notify,
         }
     }
 }
@@ -113,9 +116,12 @@ pub struct Verify {
     pub(crate) handshake_commitment: Hash,
     pub(crate) sent_len: usize,
     pub(crate) recv_len: usize,
+    pub(crate) close_notify: bool,
 
     pub(crate) channel: Option<Box<dyn Duplex<TlsnMessage>>>,
     pub(crate) verify_thread: Option<DEAPThread<SharedSender, SharedReceiver>>,
+    /// Data received from the prover, kept to run the data hooks on once it is authentic.
+    pub(crate) received: Vec<(RedactedTranscript, RedactedTranscript)>,
 }
 
 opaque_debug::implement!(Verify);
@@ -135,8 +141,10 @@ impl From<Closed> for Verify {
             handshake_commitment: value.handshake_commitment,
             sent_len: value.sent_len,
             recv_len: value.recv_len,
+            close_notify: value.close_notify,
             channel: None,
             verify_thread: None,
+            received: Vec::new(),
         }
     }
 }
@@ -2,21 +2,33 @@
 //!
 //! The TLS verifier is an application-specific verifier.
 
-use super::{state::Verify as VerifyState, Verifier, VerifierError};
-use futures::{FutureExt, StreamExt, TryFutureExt};
+use super::{future::mux_error, state::Verify as VerifyState, Verifier, VerifierError};
+use crate::hook::{fields_revealed, run_hooks, DataHook};
+use futures::{FutureExt, SinkExt, StreamExt, TryFutureExt};
 use mpz_circuits::types::Value;
-use mpz_garble::{Memory, Verify, Vm};
+use mpz_garble::{protocol::deap::DEAPThread, Memory, Verify, Vm};
+use mpz_ot::actor::kos::{SharedReceiver, SharedSender};
 use mpz_share_conversion::ShareConversionVerify;
+use tls_core::ocsp::CertStatus;
 use tlsn_core::{
-    msg::TlsnMessage, proof::SessionInfo, transcript::get_value_ids, Direction, HandshakeSummary,
-    RedactedTranscript, TranscriptSlice,
+    msg::{ProvingInfo, RevealRequest, TlsnMessage},
+    proof::SessionInfo,
+    transcript::get_value_ids,
+    Direction, HandshakeSummary, RedactedTranscript, TranscriptSlice,
 };
 use utils_aio::{expect_msg_or_err, mux::MuxChannel};
 
 #[cfg(feature = "tracing")]
-use tracing::info;
+use tracing::{debug, info};
 
 impl Verifier<VerifyState> {
+    /// Returns whether the server closed the connection with a CloseNotify alert.
+    ///
+    /// If not, the received data may have been truncated.
+    pub fn closed_cleanly(&self) -> bool {
+        self.state.close_notify
+    }
+
     /// Receives the **purported** transcript from the Prover.
     ///
     /// # Warning
@@ -24,6 +36,45 @@ impl Verifier<VerifyState> {
     /// The content of the received transcripts can not be considered authentic until after finalization.
     pub async fn receive(
         &mut self,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), VerifierError> {
+        self.exchange(None).await
+    }
+
+    /// Requests the Prover to reveal parts of the transcript, and receives the **purported**
+    /// transcript it reveals in response.
+    ///
+    /// Requests can only be made once the TLS connection is closed, before finalization. While the
+    /// connection is open, the Verifier receives only the data the Prover chooses to reveal, see
+    /// [`Verifier::run_with_reveals`](crate::tls::Verifier::run_with_reveals).
+    ///
+    /// The Prover may decline the request, in which case [`VerifierError::RevealDeclined`] is
+    /// returned and further requests can be made. The request fails with
+    /// [`VerifierError::RequestNotSatisfied`] if the Prover reveals less than requested.
+    ///
+    /// # Warning
+    ///
+    /// The content of the received transcripts can not be considered authentic until after finalization.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The parts of the transcript to reveal.
+    pub async fn request_after_close(
+        &mut self,
+        request: RevealRequest,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), VerifierError> {
+        let (sent, recv) = self.exchange(Some(request.clone())).await?;
+
+        if !request.is_revealed(&sent, &recv) || !fields_revealed(&request.fields, &sent, &recv) {
+            return Err(VerifierError::RequestNotSatisfied);
+        }
+
+        Ok((sent, recv))
+    }
+
+    /// Sends the request, if any, then receives and verifies the data the Prover reveals.
+    async fn exchange(
+        &mut self,
+        request: Option<RevealRequest>,
     ) -> Result<(RedactedTranscript, RedactedTranscript), VerifierError> {
         let verify_fut = async {
             // Create a new channel and vm thread if not already present
@@ -41,93 +92,60 @@ impl Verifier<VerifyState> {
                 self.state.verify_thread.as_mut().unwrap()
             };
 
-            // Receive the proving info from the prover
-            let mut proving_info = expect_msg_or_err!(channel, TlsnMessage::ProvingInfo)?;
-            let mut cleartext = proving_info.cleartext.clone();
+            if let Some(request) = request {
+                channel.send(TlsnMessage::RevealRequest(request)).await?;
 
-            #[cfg(feature = "tracing")]
-            info!("Received proving info from prover");
-
-            // Check ranges
-            if proving_info.sent_ids.max().unwrap_or_default() > self.state.sent_len
-                || proving_info.recv_ids.max().unwrap_or_default() > self.state.recv_len
-            {
-                return Err(VerifierError::InvalidRange);
+                #[cfg(feature = "tracing")]
+                info!("Sent reveal request to prover");
             }
 
-            // Now verify the transcript parts which the prover wants to reveal
-            let sent_value_ids = proving_info
-                .sent_ids
-                .iter_ranges()
-                .map(|r| get_value_ids(&r.into(), Direction::Sent).collect::<Vec<String>>());
-            let recv_value_ids = proving_info
-                .recv_ids
-                .iter_ranges()
-                .map(|r| get_value_ids(&r.into(), Direction::Received).collect::<Vec<String>>());
-
-            let value_refs = sent_value_ids
-                .chain(recv_value_ids)
-                .map(|ids| {
-                    let inner_refs = ids
-                        .iter()
-                        .map(|id| {
-                            verify_thread
-                                .get_value(id.as_str())
-                                .expect("Byte should be in VM memory")
-                        })
-                        .collect::<Vec<_>>();
-
-                    verify_thread
-                        .array_from_values(inner_refs.as_slice())
-                        .expect("Byte should be in VM Memory")
-                })
-                .collect::<Vec<_>>();
-
-            let values = proving_info
-                .sent_ids
-                .iter_ranges()
-                .chain(proving_info.recv_ids.iter_ranges())
-                .map(|range| {
-                    Value::Array(cleartext.drain(..range.len()).map(|b| (b).into()).collect())
-                })
-                .collect::<Vec<_>>();
-
-            // Check that purported values are correct
-            verify_thread.verify(&value_refs, &values).await?;
-
-            #[cfg(feature = "tracing")]
-            info!("Successfully verified purported cleartext");
-
-            // Create redacted transcripts
-            let mut transcripts = proving_info
-                .sent_ids
-                .iter_ranges()
-                .chain(proving_info.recv_ids.iter_ranges())
-                .map(|range| {
-                    TranscriptSlice::new(
-                        range.clone(),
-                        proving_info.cleartext.drain(..range.len()).collect(),
+            // Receive the proving info from the prover
+            let proving_info = match channel.next().await {
+                Some(Ok(TlsnMessage::ProvingInfo(proving_info))) => proving_info,
+                Some(Ok(TlsnMessage::RevealDeclined)) => return Err(VerifierError::RevealDeclined),
+                // The message is not included in the error, as it may contain transcript data.
+                Some(Ok(_)) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "unexpected message, expected proving info",
                     )
-                })
-                .collect::<Vec<_>>();
-
-            let recv_transcripts =
-                transcripts.split_off(proving_info.sent_ids.iter_ranges().count());
-            let (sent_redacted, recv_redacted) = (
-                RedactedTranscript::new(self.state.sent_len, transcripts),
-                RedactedTranscript::new(self.state.recv_len, recv_transcripts),
-            );
+                    .into())
+                }
+                Some(Err(e)) => return Err(e.into()),
+                None => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+            };
 
             #[cfg(feature = "tracing")]
-            info!("Successfully created redacted transcripts");
+            info!("Received proving info from prover");
+
+            let (sent_redacted, recv_redacted) = verify_cleartext(
+                verify_thread,
+                proving_info,
+                self.state.sent_len,
+                self.state.recv_len,
+            )
+            .await?;
 
             Ok::<_, VerifierError>((sent_redacted, recv_redacted))
         };
 
-        futures::select! {
-            res = verify_fut.fuse() => res,
-            _ = &mut self.state.mux_fut => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+        let (sent, recv) = futures::select! {
+            res = verify_fut.fuse() => res?,
+            res = &mut self.state.mux_fut => Err(mux_error(res))?,
+        };
+
+        #[cfg(feature = "tracing")]
+        debug!(
+            sent = %self.config.log_redactor().redact(&sent),
+            recv = %self.config.log_redactor().redact(&recv),
+            "Received data revealed by the prover"
+        );
+
+        if !self.config.data_hooks().is_empty() {
+            self.state.received.push((sent.clone(), recv.clone()));
         }
+
+        Ok((sent, recv))
     }
 
     /// Verify the TLS session.
@@ -143,6 +161,7 @@ impl Verifier<VerifyState> {
             start_time,
             server_ephemeral_key,
             handshake_commitment,
+            received,
             ..
         } = self.state;
 
@@ -176,14 +195,40 @@ impl Verifier<VerifyState> {
 
         let session_info = futures::select! {
             res = finalize_fut.fuse() => res?,
-            _ = &mut mux_fut => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+            res = &mut mux_fut => Err(mux_error(res))?,
         };
 
         let handshake_summary =
             HandshakeSummary::new(start_time, server_ephemeral_key, handshake_commitment);
 
         // Verify the TLS session
-        session_info.verify(&handshake_summary, self.config.cert_verifier())?;
+        session_info.verify(&handshake_summary, &self.config.cert_verifier())?;
+
+        if self.config.require_unrevoked() {
+            match session_info.revocation_status(&handshake_summary) {
+                Ok(CertStatus::Good) => {}
+                Ok(status) => {
+                    return Err(VerifierError::Revocation(format!(
+                        "server certificate status is {status:?}"
+                    )))
+                }
+                Err(e) => return Err(VerifierError::Revocation(e.to_string())),
+            }
+        }
+
+        // The received data is authentic now that the MPC is finalized.
+        for (sent, recv) in &received {
+            run_hooks(
+                self.config
+                    .data_hooks()
+                    .iter()
+                    .map(|hook| hook.as_ref() as &dyn DataHook),
+                Some(&session_info.server_name),
+                sent,
+                recv,
+            )
+            .map_err(VerifierError::DataRejected)?;
+        }
 
         #[cfg(feature = "tracing")]
         info!("Successfully verified session");
@@ -195,3 +240,81 @@ impl Verifier<VerifyState> {
         Ok(session_info)
     }
 }
+
+/// Verifies the purported cleartext of the transcript ranges in `proving_info`.
+pub(crate) async fn verify_cleartext(
+    verify_thread: &mut DEAPThread<SharedSender, SharedReceiver>,
+    mut proving_info: ProvingInfo,
+    sent_len: usize,
+    recv_len: usize,
+) -> Result<(RedactedTranscript, RedactedTranscript), VerifierError> {
+    let mut cleartext = proving_info.cleartext.clone();
+
+    // Check ranges
+    if proving_info.sent_ids.max().unwrap_or_default() > sent_len
+        || proving_info.recv_ids.max().unwrap_or_default() > recv_len
+    {
+        return Err(VerifierError::InvalidRange);
+    }
+
+    // Now verify the transcript parts which the prover wants to reveal
+    let sent_value_ids = proving_info
+        .sent_ids
+        .iter_ranges()
+        .map(|r| get_value_ids(&r.into(), Direction::Sent).collect::<Vec<String>>());
+    let recv_value_ids = proving_info
+        .recv_ids
+        .iter_ranges()
+        .map(|r| get_value_ids(&r.into(), Direction::Received).collect::<Vec<String>>());
+
+    // Values which are not in memory have not been sent or decrypted (yet).
+    let value_refs = sent_value_ids
+        .chain(recv_value_ids)
+        .map(|ids| {
+            let inner_refs = ids
+                .iter()
+                .map(|id| verify_thread.get_value(id.as_str()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(VerifierError::InvalidRange)?;
+
+            Ok(verify_thread.array_from_values(inner_refs.as_slice())?)
+        })
+        .collect::<Result<Vec<_>, VerifierError>>()?;
+
+    let values = proving_info
+        .sent_ids
+        .iter_ranges()
+        .chain(proving_info.recv_ids.iter_ranges())
+        .map(|range| Value::Array(cleartext.drain(..range.len()).map(|b| (b).into()).collect()))
+        .collect::<Vec<_>>();
+
+    // Check that purported values are correct
+    verify_thread.verify(&value_refs, &values).await?;
+
+    #[cfg(feature = "tracing")]
+    info!("Successfully verified purported cleartext");
+
+    // Create redacted transcripts
+    let mut transcripts = proving_info
+        .sent_ids
+        .iter_ranges()
+        .chain(proving_info.recv_ids.iter_ranges())
+        .map(|range| {
+            TranscriptSlice::new(
+                range.clone(),
+                proving_info.cleartext.drain(..range.len()).collect(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let recv_transcripts = transcripts.split_off(proving_info.sent_ids.iter_ranges().count());
+    let (sent_redacted, recv_redacted) = (
+        RedactedTranscript::new(sent_len, transcripts),
+        RedactedTranscript::new(recv_len, recv_transcripts),
+    );
+
+    #[cfg(feature = "tracing")]
+    info!("Successfully created redacted transcripts");
+
+    Ok((sent_redacted, recv_redacted))
+}
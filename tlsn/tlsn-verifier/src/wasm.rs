@@ -0,0 +1,100 @@
+//! Verifying presentations in the browser.
+//!
+//! Relying parties can verify presentations entirely client-side, so that the disclosed data
+//! never leaves the page. [`verify_presentation`] is exported to JavaScript as
+//! `verifyPresentation` by `wasm-bindgen`, e.g. when this crate is linked into a `cdylib`:
+//!
+//! ```js
+//! const keys = await (await fetch("https://notary.example.com/.well-known/tlsn-notary-keys.json")).text();
+//! const verified = verifyPresentation(new Uint8Array(await file.arrayBuffer()), keys);
+//! console.log(verified.serverName, new TextDecoder().decode(verified.recv));
+//! ```
+
+use tlsn_core::{
+    proof::{default_cert_verifier, TlsProof},
+    trust::TrustStore,
+    RedactedTranscript,
+};
+use wasm_bindgen::prelude::*;
+
+use crate::presentation::{self, VerifiedPresentation};
+
+/// The byte which redacted data is replaced with.
+const REDACTED_BYTE: u8 = b'X';
+
+/// A verified presentation, as returned to JavaScript.
+#[wasm_bindgen(js_name = VerifiedPresentation)]
+#[derive(Debug)]
+pub struct JsVerifiedPresentation(VerifiedPresentation);
+
+#[wasm_bindgen(js_class = VerifiedPresentation)]
+impl JsVerifiedPresentation {
+    /// The server the prover communicated with.
+    #[wasm_bindgen(getter = serverName)]
+    pub fn server_name(&self) -> String {
+        self.0.server_name.as_str().to_string()
+    }
+
+    /// The time of the session, in seconds since the UNIX epoch.
+    #[wasm_bindgen(getter)]
+    pub fn time(&self) -> u64 {
+        self.0.time
+    }
+
+    /// Whether the server closed the connection cleanly.
+    #[wasm_bindgen(getter = closedCleanly)]
+    pub fn closed_cleanly(&self) -> bool {
+        self.0.closed_cleanly
+    }
+
+    /// The data sent to the server, with redacted bytes replaced with `X`.
+    #[wasm_bindgen(getter)]
+    pub fn sent(&self) -> Vec<u8> {
+        fill_redacted(&self.0.sent)
+    }
+
+    /// The data received from the server, with redacted bytes replaced with `X`.
+    #[wasm_bindgen(getter)]
+    pub fn recv(&self) -> Vec<u8> {
+        fill_redacted(&self.0.recv)
+    }
+}
+
+/// Verifies an encoded presentation, see [`presentation::verify_presentation`].
+///
+/// The server certificate is verified using trust anchors from the `webpki-roots` crate.
+///
+/// # Arguments
+///
+/// * `presentation` - The presentation, in the [binary presentation format](tlsn_core::proof::MAGIC).
+/// * `notary_keys` - A [key document](tlsn_core::trust) with the trusted notary keys.
+#[wasm_bindgen(js_name = verifyPresentation)]
+pub fn verify_presentation(
+    presentation: &[u8],
+    notary_keys: &str,
+) -> Result<JsVerifiedPresentation, JsError> {
+    let trust_store = TrustStore::from_key_document(notary_keys)?;
+    let proof = TlsProof::from_bytes(presentation)?;
+
+    let header = &proof.session.header;
+    let signature = proof
+        .session
+        .signature
+        .as_ref()
+        .ok_or_else(|| JsError::new("presentation is not signed"))?;
+    let notary_public_key = trust_store
+        .keys_at(header.time())
+        .find(|key| header.verify_signature(signature, (*key).clone()).is_ok())
+        .ok_or_else(|| JsError::new("presentation is not signed by a trusted notary key"))?
+        .clone();
+
+    presentation::verify_presentation(proof, notary_public_key, &default_cert_verifier())
+        .map(JsVerifiedPresentation)
+        .map_err(JsError::from)
+}
+
+fn fill_redacted(transcript: &RedactedTranscript) -> Vec<u8> {
+    let mut transcript = transcript.clone();
+    transcript.set_redacted(REDACTED_BYTE);
+    transcript.data().to_vec()
+}